@@ -0,0 +1,22 @@
+#!/bin/sh
+# Regenerates the compiled fixtures used by the tests. Requires a JDK (17+) and python3 on PATH.
+set -e
+cd "$(dirname "$0")"
+rm -rf classes classes11 jars
+javac --release 8 -g -d classes src/*.java src/demo/*.java src/app/*.java
+javac --release 11 -g -cp classes -d classes11 src11/app/*.java
+# String concatenation with invokedynamic.
+javac --release 11 -g -d classes src11/*.java
+# Lambdas, records and streams, run against a JDK's java.base with --java-home.
+javac --release 17 -g -d classes src17/*.java
+# Constant pool entries javac never emits for ldc (CONSTANT_Dynamic, MethodType, MethodHandle).
+python3 gen_condy.py classes/Condy.class
+# A JDK home for --java-home whose module image holds the bundled runtime as java.base.
+python3 gen_jimage.py jdk/lib/modules java.base=../rt/classes
+mkdir jars
+jar --create --date=2020-01-01T00:00:00Z --file jars/classes.jar -C classes .
+jar --create --date=2020-01-01T00:00:00Z --no-compress --file jars/classes-stored.jar -C classes .
+# An executable multi-release jar whose dependency comes from the manifest Class-Path.
+jar --create --date=2020-01-01T00:00:00Z --file jars/lib.jar -C classes demo
+jar --create --date=2020-01-01T00:00:00Z --file jars/app.jar --manifest src/app/MANIFEST.MF -C classes app --release 11 -C classes11 .
+rm -rf classes11
@@ -0,0 +1,105 @@
+#!/usr/bin/env python3
+"""Writes Condy.class, which javac cannot produce: it loads CONSTANT_Dynamic, MethodType and
+MethodHandle constants with ldc. Usage: gen_condy.py <output file>"""
+import struct
+import sys
+
+
+class ConstantPool:
+    def __init__(self):
+        self.entries = []
+        self.indexes = {}
+
+    def add(self, key, data):
+        if key not in self.indexes:
+            self.entries.append(data)
+            self.indexes[key] = len(self.entries)
+        return self.indexes[key]
+
+    def utf8(self, value):
+        encoded = value.encode()
+        return self.add(('Utf8', value), struct.pack('>BH', 1, len(encoded)) + encoded)
+
+    def integer(self, value):
+        return self.add(('Integer', value), struct.pack('>Bi', 3, value))
+
+    def string(self, value):
+        return self.add(('String', value), struct.pack('>BH', 8, self.utf8(value)))
+
+    def class_ref(self, name):
+        return self.add(('Class', name), struct.pack('>BH', 7, self.utf8(name)))
+
+    def name_and_type(self, name, descriptor):
+        return self.add(('NameAndType', name, descriptor), struct.pack('>BHH', 12, self.utf8(name), self.utf8(descriptor)))
+
+    def member(self, tag, owner, name, descriptor):
+        return self.add((tag, owner, name, descriptor),
+                        struct.pack('>BHH', tag, self.class_ref(owner), self.name_and_type(name, descriptor)))
+
+    def method_handle(self, kind, reference):
+        return self.add(('MethodHandle', kind, reference), struct.pack('>BBH', 15, kind, reference))
+
+    def method_type(self, descriptor):
+        return self.add(('MethodType', descriptor), struct.pack('>BH', 16, self.utf8(descriptor)))
+
+    def dynamic(self, bootstrap, name, descriptor):
+        return self.add(('Dynamic', bootstrap, name, descriptor),
+                        struct.pack('>BHH', 17, bootstrap, self.name_and_type(name, descriptor)))
+
+    def bytes(self):
+        return struct.pack('>H', len(self.entries) + 1) + b''.join(self.entries)
+
+
+def main(output):
+    pool = ConstantPool()
+    lookup = 'Ljava/lang/invoke/MethodHandles$Lookup;Ljava/lang/String;Ljava/lang/Class;'
+    bootstraps = [
+        ('bsm', '(' + lookup + ')I', []),
+        ('bsmArgs', '(' + lookup + 'ILjava/lang/String;)I', [pool.integer(7), pool.string('x')]),
+        ('bsmName', '(' + lookup + ')Ljava/lang/Object;', []),
+    ]
+    bootstrap_entries = []
+    for name, descriptor, arguments in bootstraps:
+        handle = pool.method_handle(6, pool.member(10, 'Condy', name, descriptor))
+        bootstrap_entries.append((handle, arguments))
+
+    ldc_w = lambda index: bytes([0x13]) + struct.pack('>H', index)
+    methods = [
+        # name, descriptor, max_stack, max_locals, code
+        ('bsm', bootstraps[0][1], 1, 3, bytes([0x10, 42, 0xac])),
+        ('bsmArgs', bootstraps[1][1], 1, 5, bytes([0x1d, 0xac])),
+        ('bsmName', bootstraps[2][1], 1, 3, bytes([0x2b, 0xb0])),
+        ('answer', '()I', 1, 0, ldc_w(pool.dynamic(0, 'answer', 'I')) + bytes([0xac])),
+        ('withArgs', '()I', 1, 0, ldc_w(pool.dynamic(1, 'withArgs', 'I')) + bytes([0xac])),
+        ('name', '()Ljava/lang/Object;', 1, 0, ldc_w(pool.dynamic(2, 'constantName', 'Ljava/lang/Object;')) + bytes([0xb0])),
+        ('methodType', '()Ljava/lang/Object;', 1, 0, ldc_w(pool.method_type('(IJ)Ljava/lang/String;')) + bytes([0xb0])),
+        ('handle', '()Ljava/lang/Object;', 1, 0, ldc_w(bootstrap_entries[0][0]) + bytes([0xb0])),
+        ('getter', '()Ljava/lang/Object;', 1, 0,
+         ldc_w(pool.method_handle(1, pool.member(9, 'Condy', 'value', 'I'))) + bytes([0xb0])),
+    ]
+
+    code_name = pool.utf8('Code')
+    bootstrap_name = pool.utf8('BootstrapMethods')
+    this_class = pool.class_ref('Condy')
+    super_class = pool.class_ref('java/lang/Object')
+    field = struct.pack('>HHHH', 0x0000, pool.utf8('value'), pool.utf8('I'), 0)
+    method_bytes = b''
+    for name, descriptor, max_stack, max_locals, code in methods:
+        code_attribute = struct.pack('>HHI', max_stack, max_locals, len(code)) + code + struct.pack('>HH', 0, 0)
+        method_bytes += struct.pack('>HHHH', 0x0009, pool.utf8(name), pool.utf8(descriptor), 1)
+        method_bytes += struct.pack('>HI', code_name, len(code_attribute)) + code_attribute
+    bootstrap_bytes = struct.pack('>H', len(bootstrap_entries))
+    for handle, arguments in bootstrap_entries:
+        bootstrap_bytes += struct.pack('>HH', handle, len(arguments)) + b''.join(struct.pack('>H', a) for a in arguments)
+
+    data = struct.pack('>IHH', 0xCAFEBABE, 0, 55) + pool.bytes()
+    data += struct.pack('>HHHH', 0x0021, this_class, super_class, 0)
+    data += struct.pack('>H', 1) + field
+    data += struct.pack('>H', len(methods)) + method_bytes
+    data += struct.pack('>HHI', 1, bootstrap_name, len(bootstrap_bytes)) + bootstrap_bytes
+    with open(output, 'wb') as f:
+        f.write(data)
+
+
+if __name__ == '__main__':
+    main(sys.argv[1])
@@ -0,0 +1,196 @@
+#!/usr/bin/env python3
+"""Writes a small jimage (the lib/modules format of a JDK) holding the class files of the given
+directories, one module per directory, with their /packages entries. Resources are stored in
+turn uncompressed, zip compressed, with shared strings (compact-cp), and with both, the way jlink
+--compress writes them. Usage: gen_jimage.py <output file> <module>=<classes dir>..."""
+import os
+import re
+import struct
+import sys
+import zlib
+
+HASH_MULTIPLIER = 0x01000193
+ENDIAN = '<'
+
+ATTRIBUTE_MODULE, ATTRIBUTE_PARENT, ATTRIBUTE_BASE, ATTRIBUTE_EXTENSION = 1, 2, 3, 4
+ATTRIBUTE_OFFSET, ATTRIBUTE_COMPRESSED, ATTRIBUTE_UNCOMPRESSED = 5, 6, 7
+
+# Bytes following the tag of every constant pool entry but CONSTANT_Utf8.
+CONSTANT_SIZES = {3: 4, 4: 4, 5: 8, 6: 8, 7: 2, 8: 2, 9: 4, 10: 4, 11: 4, 12: 4, 15: 3, 16: 2, 17: 4, 18: 4, 19: 2, 20: 2}
+FIELD_TYPE = rb'\[*(?:[BCDFIJSZ]|L[^;\[()]+;)'
+DESCRIPTOR = re.compile(rb'\((?:' + FIELD_TYPE + rb')*\)(?:V|' + FIELD_TYPE + rb')|' + FIELD_TYPE)
+CLASS_TYPE = re.compile(rb'L([^;]+);')
+
+
+def hash_code(name, seed=HASH_MULTIPLIER):
+    for byte in name:
+        seed = ((seed * HASH_MULTIPLIER) ^ byte) & 0xffffffff
+    return seed & 0x7fffffff
+
+
+class Strings:
+    def __init__(self):
+        self.data = bytearray(b'\0')
+        self.offsets = {b'': 0}
+
+    def add(self, value):
+        if isinstance(value, str):
+            value = value.encode()
+        if value not in self.offsets:
+            self.offsets[value] = len(self.data)
+            self.data += value + b'\0'
+        return self.offsets[value]
+
+
+def compress_int(value):
+    """jlink's CompressIndexes: one to three bytes with the length in bits 5-6, else a plain int."""
+    if value < 1 << 5:
+        return bytes([0x80 | 1 << 5 | value])
+    if value < 1 << 13:
+        return bytes([0x80 | 2 << 5 | value >> 8, value & 0xff])
+    if value < 1 << 21:
+        return bytes([0x80 | 3 << 5 | value >> 16, value >> 8 & 0xff, value & 0xff])
+    return struct.pack('>I', value)
+
+
+def share_strings(data, strings):
+    """Moves the CONSTANT_Utf8 entries of a class file to the strings table; descriptors are split
+    into the descriptor without class names and the (package, class) indexes of each class."""
+    out = bytearray(data[:10])
+    count = struct.unpack('>H', data[8:10])[0]
+    position, index = 10, 1
+    while index < count:
+        tag = data[position]
+        if tag == 1:
+            length = struct.unpack('>H', data[position + 1:position + 3])[0]
+            value = data[position + 3:position + 3 + length]
+            position += 3 + length
+            if any(byte >= 0x80 for byte in value):
+                # non-ASCII strings stay inline
+                out += data[position - 3 - length:position]
+            elif DESCRIPTOR.fullmatch(value) and CLASS_TYPE.search(value):
+                indexes = bytearray()
+                for class_name in CLASS_TYPE.findall(value):
+                    package, _, simple_name = class_name.rpartition(b'/')
+                    indexes += compress_int(strings.add(package)) + compress_int(strings.add(simple_name))
+                out += bytes([25]) + compress_int(strings.add(CLASS_TYPE.sub(b'L;', value)))
+                out += compress_int(len(indexes)) + indexes
+            else:
+                out += bytes([23]) + compress_int(strings.add(value))
+        else:
+            size = CONSTANT_SIZES[tag]
+            out += data[position:position + 1 + size]
+            position += 1 + size
+            if tag in (5, 6):
+                index += 1
+        index += 1
+    return bytes(out + data[position:])
+
+
+def compress(data, decompressor, payload, strings, terminal):
+    # -1 for the offset of the decompressor configuration: there is none
+    header = struct.pack(ENDIAN + 'IQQIiB', 0xCAFEFAFA, len(payload), len(data), strings.add(decompressor), -1, terminal)
+    return header + payload
+
+
+def split_name(name):
+    """The module, parent, base and extension of a full resource name, as jlink splits them."""
+    for directory in ('modules', 'packages'):
+        if name.startswith('/%s/' % directory):
+            return directory, '', name[len(directory) + 2:], ''
+    module, _, rest = name[1:].partition('/')
+    parent, _, rest = rest.rpartition('/')
+    base, dot, extension = rest.rpartition('.')
+    return (module, parent, base, extension) if dot else (module, parent, rest, '')
+
+
+def location(name, offset, compressed_size, size, strings):
+    attributes = zip((ATTRIBUTE_MODULE, ATTRIBUTE_PARENT, ATTRIBUTE_BASE, ATTRIBUTE_EXTENSION),
+                     (strings.add(part) for part in split_name(name)))
+    data = bytearray()
+    for kind, value in list(attributes) + [(ATTRIBUTE_OFFSET, offset), (ATTRIBUTE_COMPRESSED, compressed_size),
+                                           (ATTRIBUTE_UNCOMPRESSED, size)]:
+        if value:
+            length = max(1, (value.bit_length() + 7) // 8)
+            data += bytes([kind << 3 | length - 1]) + value.to_bytes(length, 'big')
+    return bytes(data + b'\0')
+
+
+def perfect_hash(names):
+    """The redirect table and the slot of every name: buckets of colliding names get a seed that
+    spreads them over free slots, single names are stored in the redirect table as -1 - slot."""
+    length = len(names)
+    buckets = {}
+    for name in names:
+        buckets.setdefault(hash_code(name) % length, []).append(name)
+    redirect = [0] * length
+    slots = {}
+    free = set(range(length))
+    for bucket, members in sorted(buckets.items(), key=lambda item: -len(item[1])):
+        if len(members) == 1:
+            slot = min(free)
+            redirect[bucket] = -1 - slot
+            slots[members[0]] = slot
+            free.remove(slot)
+            continue
+        seed = 1
+        while True:
+            candidates = [hash_code(name, seed) % length for name in members]
+            if len(set(candidates)) == len(candidates) and all(slot in free for slot in candidates):
+                break
+            seed += 1
+        redirect[bucket] = seed
+        for name, slot in zip(members, candidates):
+            slots[name] = slot
+            free.remove(slot)
+    return redirect, slots
+
+
+def main(output, modules):
+    strings = Strings()
+    resources = {}
+    packages = {}
+    for module, root in modules:
+        for directory, _, files in os.walk(root):
+            for file in files:
+                path = os.path.relpath(os.path.join(directory, file), root).replace(os.sep, '/')
+                with open(os.path.join(directory, file), 'rb') as f:
+                    resources['/%s/%s' % (module, path)] = f.read()
+                if '/' in path:
+                    packages.setdefault(path.rpartition('/')[0].replace('/', '.'), module)
+    for package, module in packages.items():
+        resources['/packages/' + package] = struct.pack(ENDIAN + 'II', 0, strings.add(module))
+
+    names = sorted(resources)
+    content = bytearray()
+    locations = bytearray(b'\0')
+    location_offsets = {}
+    for i, name in enumerate(names):
+        data = stored = resources[name]
+        if name.endswith('.class') and i % 4 != 0:
+            if i % 4 != 1:
+                stored = compress(data, 'compact-cp', share_strings(data, strings), strings, 1)
+            if i % 4 != 2:
+                stored = compress(stored, 'zip', zlib.compress(stored, 9), strings, int(stored is data))
+        location_offsets[name] = len(locations)
+        locations += location(name, len(content), len(stored) if stored is not data else 0, len(data), strings)
+        content += stored
+
+    encoded = {name.encode(): name for name in names}
+    redirect, slots = perfect_hash(list(encoded))
+    offsets = [0] * len(names)
+    for name, slot in slots.items():
+        offsets[slot] = location_offsets[encoded[name]]
+    header = struct.pack(ENDIAN + 'IIIIIII', 0xCAFEDADA, 1 << 16, 0, len(names), len(names), len(locations), len(strings.data))
+    os.makedirs(os.path.dirname(output) or '.', exist_ok=True)
+    with open(output, 'wb') as f:
+        f.write(header)
+        f.write(struct.pack(ENDIAN + '%di' % len(redirect), *redirect))
+        f.write(struct.pack(ENDIAN + '%dI' % len(offsets), *offsets))
+        f.write(locations)
+        f.write(strings.data)
+        f.write(content)
+
+
+if __name__ == '__main__':
+    main(sys.argv[1], [tuple(argument.split('=', 1)) for argument in sys.argv[2:]])
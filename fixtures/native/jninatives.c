@@ -0,0 +1,123 @@
+/* The native methods of JniNatives, built into libjninatives.so by test_jni_libraries. */
+#include <jni.h>
+#include <stdio.h>
+#include <string.h>
+
+static jboolean registered(JNIEnv *env, jclass cls) {
+    return JNI_TRUE;
+}
+
+JNIEXPORT jint JNICALL JNI_OnLoad(JavaVM *vm, void *reserved) {
+    JNIEnv *env;
+    if ((*vm)->GetEnv(vm, (void **) &env, JNI_VERSION_1_8) != JNI_OK) {
+        return JNI_ERR;
+    }
+    jclass cls = (*env)->FindClass(env, "JniNatives");
+    if (cls == NULL) {
+        return JNI_ERR;
+    }
+    JNINativeMethod methods[] = {{"registered", "()Z", (void *) registered}};
+    if ((*env)->RegisterNatives(env, cls, methods, 1) != JNI_OK) {
+        return JNI_ERR;
+    }
+    return JNI_VERSION_1_8;
+}
+
+JNIEXPORT jint JNICALL Java_JniNatives_add(JNIEnv *env, jclass cls, jint a, jint b) {
+    return a + b;
+}
+
+JNIEXPORT jstring JNICALL Java_JniNatives_greet(JNIEnv *env, jobject self, jstring greeting) {
+    jclass cls = (*env)->GetObjectClass(env, self);
+    jmethodID name = (*env)->GetMethodID(env, cls, "name", "()Ljava/lang/String;");
+    jstring value = (jstring) (*env)->CallObjectMethod(env, self, name);
+    const char *chars = (*env)->GetStringUTFChars(env, value, NULL);
+    const char *prefix = (*env)->GetStringUTFChars(env, greeting, NULL);
+    char buffer[256];
+    snprintf(buffer, sizeof buffer, "%s, %s!", prefix, chars);
+    (*env)->ReleaseStringUTFChars(env, greeting, prefix);
+    (*env)->ReleaseStringUTFChars(env, value, chars);
+    return (*env)->NewStringUTF(env, buffer);
+}
+
+JNIEXPORT jlong JNICALL Java_JniNatives_weighTwice(JNIEnv *env, jobject self) {
+    jclass cls = (*env)->GetObjectClass(env, self);
+    jmethodID weigh = (*env)->GetMethodID(env, cls, "weigh", "(IJDF)J");
+    jlong variadic = (*env)->CallLongMethod(env, self, weigh, 1, (jlong) 20, 300.5, 4000.5f);
+    jvalue args[4];
+    args[0].i = 50000;
+    args[1].j = 600000;
+    args[2].d = 7000000.0;
+    args[3].f = 80000000.0f;
+    return variadic + (*env)->CallLongMethodA(env, self, weigh, args);
+}
+
+JNIEXPORT jobject JNICALL Java_JniNatives_create(JNIEnv *env, jclass unused, jstring name) {
+    jclass cls = (*env)->FindClass(env, "JniNatives");
+    jmethodID init = (*env)->GetMethodID(env, cls, "<init>", "(Ljava/lang/String;)V");
+    return (*env)->NewObject(env, cls, init, name);
+}
+
+JNIEXPORT jint JNICALL Java_JniNatives_version(JNIEnv *env, jclass cls) {
+    return (*env)->GetVersion(env);
+}
+
+JNIEXPORT jstring JNICALL Java_JniNatives_describe__I(JNIEnv *env, jclass cls, jint value) {
+    char buffer[32];
+    snprintf(buffer, sizeof buffer, "int %d", value);
+    return (*env)->NewStringUTF(env, buffer);
+}
+
+JNIEXPORT jstring JNICALL Java_JniNatives_describe__Ljava_lang_String_2(JNIEnv *env, jclass cls, jstring value) {
+    jclass string = (*env)->FindClass(env, "java/lang/String");
+    if (!(*env)->IsInstanceOf(env, value, string)) {
+        return NULL;
+    }
+    char buffer[64];
+    snprintf(buffer, sizeof buffer, "string of %d", (*env)->GetStringLength(env, value));
+    return (*env)->NewStringUTF(env, buffer);
+}
+
+JNIEXPORT jdouble JNICALL Java_JniNatives_sum(JNIEnv *env, jclass cls, jint a, jdouble b, jlong c, jfloat d, jbyte e,
+                                              jchar f, jshort g, jboolean h, jdouble i, jint j, jdouble k, jlong l,
+                                              jdouble m, jdouble n, jdouble o, jdouble p, jdouble q, jint r, jlong s,
+                                              jdouble t) {
+    return a + b + c + d + e + f + g + h + i + j + k + l + m + n + o + p + q + r + s + t;
+}
+
+JNIEXPORT jstring JNICALL Java_JniNatives_rethrow(JNIEnv *env, jclass cls, jstring message) {
+    jmethodID fail = (*env)->GetStaticMethodID(env, cls, "fail", "(Ljava/lang/String;)Ljava/lang/String;");
+    (*env)->CallStaticObjectMethod(env, cls, fail, message);
+    if (!(*env)->ExceptionCheck(env)) {
+        return NULL;
+    }
+    jthrowable thrown = (*env)->ExceptionOccurred(env);
+    (*env)->ExceptionClear(env);
+    jclass throwable = (*env)->FindClass(env, "java/lang/Throwable");
+    jmethodID get_message = (*env)->GetMethodID(env, throwable, "getMessage", "()Ljava/lang/String;");
+    return (jstring) (*env)->CallObjectMethod(env, thrown, get_message);
+}
+
+JNIEXPORT void JNICALL Java_JniNatives_propagate(JNIEnv *env, jclass cls, jstring message) {
+    jmethodID fail = (*env)->GetStaticMethodID(env, cls, "fail", "(Ljava/lang/String;)Ljava/lang/String;");
+    (*env)->CallStaticObjectMethod(env, cls, fail, message);
+}
+
+JNIEXPORT void JNICALL Java_JniNatives_throwNew(JNIEnv *env, jclass cls, jstring message) {
+    const char *chars = (*env)->GetStringUTFChars(env, message, NULL);
+    (*env)->ThrowNew(env, (*env)->FindClass(env, "java/lang/IllegalStateException"), chars);
+    (*env)->ReleaseStringUTFChars(env, message, chars);
+}
+
+/* Holds a string through a local and a global reference while Java code collects garbage. */
+JNIEXPORT jstring JNICALL Java_JniNatives_keep(JNIEnv *env, jclass cls, jstring value, jint garbage) {
+    jobject global = (*env)->NewGlobalRef(env, value);
+    jmethodID churn = (*env)->GetStaticMethodID(env, cls, "churn", "(I)I");
+    (*env)->CallStaticIntMethod(env, cls, churn, garbage);
+    if (!(*env)->IsSameObject(env, global, value) || (*env)->GetObjectRefType(env, global) != JNIGlobalRefType) {
+        return NULL;
+    }
+    jobject local = (*env)->NewLocalRef(env, global);
+    (*env)->DeleteGlobalRef(env, global);
+    return (jstring) local;
+}
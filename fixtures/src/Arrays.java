@@ -0,0 +1,86 @@
+public class Arrays {
+    static int sum(int[] values) {
+        int total = 0;
+        for (int i = 0; i < values.length; i++) {
+            total += values[i];
+        }
+        return total;
+    }
+
+    static int ints() {
+        int[] values = new int[5];
+        for (int i = 0; i < values.length; i++) {
+            values[i] = i;
+        }
+        return sum(values);
+    }
+
+    static long longs(long value) {
+        long[] values = new long[3];
+        values[2] = value;
+        return values[2];
+    }
+
+    static double doubles(double value) {
+        double[] values = new double[2];
+        values[1] = value;
+        return values[1];
+    }
+
+    static int bytes(byte value) {
+        byte[] values = new byte[1];
+        values[0] = value;
+        return values[0];
+    }
+
+    static int chars(char value) {
+        char[] values = new char[1];
+        values[0] = value;
+        return values[0];
+    }
+
+    static int shorts(short value) {
+        short[] values = new short[1];
+        values[0] = value;
+        return values[0];
+    }
+
+    static boolean booleans() {
+        boolean[] values = new boolean[2];
+        values[1] = true;
+        return values[1] && !values[0];
+    }
+
+    static int matrix() {
+        int[][][] cube = new int[2][3][4];
+        cube[1][2][3] = 1;
+        return cube.length + cube[1].length + cube[1][2].length + cube[1][2][3];
+    }
+
+    static int jagged() {
+        int[][] rows = new int[3][];
+        return rows[2] == null ? 1 : 0;
+    }
+
+    static int store(Object[] values, Object value) {
+        values[0] = value;
+        return values.length;
+    }
+
+    static Object[] runnables() {
+        return new Runnable[1];
+    }
+
+    static int outOfBounds(int index) {
+        int[] values = new int[3];
+        return values[index];
+    }
+
+    static int negative(int length) {
+        return new int[length].length;
+    }
+
+    static int negativeMatrix(int length) {
+        return new int[1][length].length;
+    }
+}
@@ -0,0 +1,50 @@
+import java.io.Serializable;
+
+public class Casts {
+    interface Animal {
+    }
+
+    static class Dog implements Animal, Cloneable {
+    }
+
+    static class Puppy extends Dog {
+    }
+
+    static boolean isAnimal(Object o) {
+        return o instanceof Animal;
+    }
+
+    static boolean isDog(Object o) {
+        return o instanceof Dog;
+    }
+
+    static int checks() {
+        int bits = 0;
+        Object puppy = new Puppy();
+        Object strings = new String[1];
+        Object ints = new int[1];
+        Object matrix = new Dog[1][1];
+        if (puppy instanceof Animal) bits |= 1;
+        if (puppy instanceof Cloneable) bits |= 2;
+        if (strings instanceof Object[]) bits |= 4;
+        if (ints instanceof Cloneable && ints instanceof Serializable) bits |= 8;
+        if (!(ints instanceof Object[])) bits |= 16;
+        if (matrix instanceof Animal[][]) bits |= 32;
+        if (matrix instanceof Object[]) bits |= 64;
+        if (!(matrix instanceof Puppy[][])) bits |= 128;
+        if (!(null instanceof Object)) bits |= 256;
+        return bits;
+    }
+
+    static Dog cast(Object o) {
+        return (Dog) o;
+    }
+
+    static Object[] castArray(Object o) {
+        return (Object[]) o;
+    }
+
+    static boolean equalStrings() {
+        return "abc".equals("abc") && !"abc".equals(new Object());
+    }
+}
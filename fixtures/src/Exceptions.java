@@ -0,0 +1,100 @@
+public class Exceptions {
+    static class Failure extends RuntimeException {
+        final int code;
+
+        Failure(String message, int code) {
+            super(message);
+            this.code = code;
+        }
+    }
+
+    static int counter;
+
+    static int divide(int a, int b) {
+        try {
+            return a / b;
+        } catch (ArithmeticException e) {
+            return -1;
+        }
+    }
+
+    static int finallyRuns(boolean fail) {
+        counter = 0;
+        try {
+            if (fail) {
+                throw new IllegalStateException("failed");
+            }
+            counter = 1;
+        } catch (RuntimeException e) {
+            counter = 2;
+        } finally {
+            counter += 10;
+        }
+        return counter;
+    }
+
+    static void thrower(int depth) {
+        if (depth == 0) {
+            throw new Failure("deep", 7);
+        }
+        thrower(depth - 1);
+    }
+
+    static int unwind() {
+        try {
+            thrower(3);
+            return 0;
+        } catch (Failure e) {
+            return e.code;
+        }
+    }
+
+    static boolean nullPointer() {
+        int[] array = null;
+        try {
+            return array.length == 0;
+        } catch (NullPointerException e) {
+            return e.getMessage() == null;
+        }
+    }
+
+    static String message() {
+        try {
+            int[] array = new int[2];
+            array[2] = 1;
+            return null;
+        } catch (IndexOutOfBoundsException e) {
+            return e.getMessage();
+        }
+    }
+
+    static int uncaughtFinally() {
+        try {
+            thrower(0);
+        } finally {
+            counter = 99;
+        }
+        return 0;
+    }
+
+    static void fail() {
+        try {
+            divide(1, 0);
+            thrower(1);
+        } catch (Failure e) {
+            throw new IllegalStateException("wrapped", e);
+        }
+    }
+
+    static Throwable captured() {
+        return new Failure("here", 1);
+    }
+
+    static String topMethod() {
+        return captured().getStackTrace()[0].getMethodName();
+    }
+
+    public static void main(String[] args) {
+        fail();
+    }
+}
@@ -0,0 +1,20 @@
+import java.util.List;
+
+public class Fields {
+    public static final int ANSWER = 42;
+    public static final long BIG = 1234567890123L;
+    public static final float RATIO = 1.5f;
+    public static final double PI = 3.14159;
+    public static final String GREETING = "hello\n\"world\"";
+    private static int counter;
+    protected volatile boolean flag;
+    transient char letter;
+    short small;
+    byte tiny;
+    List<String> names;
+
+    @Deprecated
+    public synchronized long next() throws java.io.IOException {
+        return BIG + counter++;
+    }
+}
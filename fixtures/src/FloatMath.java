@@ -0,0 +1,114 @@
+/** One method per floating point instruction, driven by a conformance table in the tests. */
+public class FloatMath {
+    static float fadd(float a, float b) {
+        return a + b;
+    }
+
+    static float fsub(float a, float b) {
+        return a - b;
+    }
+
+    static float fmul(float a, float b) {
+        return a * b;
+    }
+
+    static float fdiv(float a, float b) {
+        return a / b;
+    }
+
+    static float frem(float a, float b) {
+        return a % b;
+    }
+
+    static double dadd(double a, double b) {
+        return a + b;
+    }
+
+    static double dsub(double a, double b) {
+        return a - b;
+    }
+
+    static double dmul(double a, double b) {
+        return a * b;
+    }
+
+    static double ddiv(double a, double b) {
+        return a / b;
+    }
+
+    static double drem(double a, double b) {
+        return a % b;
+    }
+
+    static float fneg(float a) {
+        return -a;
+    }
+
+    static boolean fless(float a, float b) {
+        return a < b;
+    }
+
+    static boolean fgreater(float a, float b) {
+        return a > b;
+    }
+
+    static boolean fequal(float a, float b) {
+        return a == b;
+    }
+
+    static double dneg(double a) {
+        return -a;
+    }
+
+    static boolean dless(double a, double b) {
+        return a < b;
+    }
+
+    static boolean dgreater(double a, double b) {
+        return a > b;
+    }
+
+    static boolean dequal(double a, double b) {
+        return a == b;
+    }
+
+    static float i2f(int a) {
+        return (float) a;
+    }
+
+    static double i2d(int a) {
+        return (double) a;
+    }
+
+    static float l2f(long a) {
+        return (float) a;
+    }
+
+    static double l2d(long a) {
+        return (double) a;
+    }
+
+    static int f2i(float a) {
+        return (int) a;
+    }
+
+    static long f2l(float a) {
+        return (long) a;
+    }
+
+    static double f2d(float a) {
+        return (double) a;
+    }
+
+    static int d2i(double a) {
+        return (int) a;
+    }
+
+    static long d2l(double a) {
+        return (long) a;
+    }
+
+    static float d2f(double a) {
+        return (float) a;
+    }
+}
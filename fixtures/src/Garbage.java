@@ -0,0 +1,70 @@
+public class Garbage {
+    static class Node {
+        Node next;
+        int[] payload = new int[16];
+    }
+
+    static Object kept;
+
+    static int churn(int count) {
+        int total = 0;
+        for (int i = 0; i < count; i++) {
+            int[] block = new int[256];
+            block[0] = i;
+            total += block.length;
+        }
+        return total;
+    }
+
+    static int chain(int length) {
+        Node head = null;
+        for (int i = 0; i < length; i++) {
+            Node node = new Node();
+            node.next = head;
+            head = node;
+        }
+        kept = head;
+        int count = 0;
+        for (Node node = head; node != null; node = node.next) {
+            count++;
+        }
+        return count;
+    }
+
+    static int retain(int count) {
+        Object[] blocks = new Object[count];
+        for (int i = 0; i < count; i++) {
+            blocks[i] = new int[256];
+        }
+        return blocks.length;
+    }
+
+    static boolean recovers(int count) {
+        try {
+            retain(count);
+            return false;
+        } catch (OutOfMemoryError e) {
+            return e.getMessage() != null;
+        }
+    }
+
+    static int remembered(int count) {
+        Node node = new Node();
+        Object[] slots = new Object[4];
+        churn(1024);
+        int total = 0;
+        for (int i = 0; i < count; i++) {
+            node.next = new Node();
+            node.next.payload[0] = i;
+            slots[i % 4] = new int[] { i };
+            kept = new int[] { i };
+            churn(64);
+            total += node.next.payload[0] + ((int[]) slots[i % 4])[0] + ((int[]) kept)[0];
+        }
+        return total;
+    }
+
+    public static void main(String[] args) {
+        retain(1 << 20);
+    }
+}
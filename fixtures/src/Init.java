@@ -0,0 +1,58 @@
+public class Init {
+    static int counter;
+
+    static int next() {
+        counter = counter + 1;
+        return counter;
+    }
+
+    static class Base {
+        static int order = next();
+        static int value = 3;
+    }
+
+    static class Derived extends Base {
+        static int order = next();
+        static int sum = value + 2;
+
+        static int get() {
+            return sum;
+        }
+    }
+
+    static class Constants {
+        static final long BIG = 1234567890123L;
+        static int order = next();
+    }
+
+    interface WithDefault {
+        int ORDER = next();
+
+        default int m() {
+            return 1;
+        }
+    }
+
+    interface WithoutDefault {
+        int ORDER = next();
+
+        void n();
+    }
+
+    static class Impl implements WithDefault, WithoutDefault {
+        static int order = next();
+
+        public void n() {
+        }
+    }
+
+    static class Failing {
+        static int value = broken();
+
+        static native int broken();
+
+        static int get() {
+            return value;
+        }
+    }
+}
@@ -0,0 +1,129 @@
+/** One method per integer arithmetic instruction, driven by a conformance table in the tests. */
+public class IntegerMath {
+    static int iadd(int a, int b) {
+        return a + b;
+    }
+
+    static int isub(int a, int b) {
+        return a - b;
+    }
+
+    static int imul(int a, int b) {
+        return a * b;
+    }
+
+    static int idiv(int a, int b) {
+        return a / b;
+    }
+
+    static int irem(int a, int b) {
+        return a % b;
+    }
+
+    static int iand(int a, int b) {
+        return a & b;
+    }
+
+    static int ior(int a, int b) {
+        return a | b;
+    }
+
+    static int ixor(int a, int b) {
+        return a ^ b;
+    }
+
+    static int ishl(int a, int b) {
+        return a << b;
+    }
+
+    static int ishr(int a, int b) {
+        return a >> b;
+    }
+
+    static int iushr(int a, int b) {
+        return a >>> b;
+    }
+
+    static long ladd(long a, long b) {
+        return a + b;
+    }
+
+    static long lsub(long a, long b) {
+        return a - b;
+    }
+
+    static long lmul(long a, long b) {
+        return a * b;
+    }
+
+    static long ldiv(long a, long b) {
+        return a / b;
+    }
+
+    static long lrem(long a, long b) {
+        return a % b;
+    }
+
+    static long land(long a, long b) {
+        return a & b;
+    }
+
+    static long lor(long a, long b) {
+        return a | b;
+    }
+
+    static long lxor(long a, long b) {
+        return a ^ b;
+    }
+
+    static long lshl(long a, int b) {
+        return a << b;
+    }
+
+    static long lshr(long a, int b) {
+        return a >> b;
+    }
+
+    static long lushr(long a, int b) {
+        return a >>> b;
+    }
+
+    static int ineg(int a) {
+        return -a;
+    }
+
+    static long lneg(long a) {
+        return -a;
+    }
+
+    static int lcmp(long a, long b) {
+        return a < b ? -1 : a > b ? 1 : 0;
+    }
+
+    static long i2l(int a) {
+        return a;
+    }
+
+    static int l2i(long a) {
+        return (int) a;
+    }
+
+    static int i2b(int a) {
+        return (byte) a;
+    }
+
+    static int i2c(int a) {
+        return (char) a;
+    }
+
+    static int i2s(int a) {
+        return (short) a;
+    }
+
+    static int compound(int a) {
+        a += 1;
+        a *= 3;
+        a <<= 2;
+        return a;
+    }
+}
@@ -0,0 +1,87 @@
+/** Native methods implemented in C by fixtures/native/jninatives.c. */
+public class JniNatives {
+    private final String name;
+
+    JniNatives(String name) {
+        this.name = name;
+    }
+
+    String name() {
+        return name;
+    }
+
+    long weigh(int a, long b, double c, float d) {
+        return a + b + (long) c + (long) d;
+    }
+
+    static String fail(String message) {
+        throw new IllegalArgumentException(message);
+    }
+
+    static int churn(int count) {
+        int total = 0;
+        for (int i = 0; i < count; i++) {
+            total += new int[256].length;
+        }
+        return total;
+    }
+
+    // Exported under their short names.
+    static native int add(int a, int b);
+
+    native String greet(String greeting);
+
+    native long weighTwice();
+
+    static native JniNatives create(String name);
+
+    static native int version();
+
+    // Overloads, exported under their long names.
+    static native String describe(int value);
+
+    static native String describe(String value);
+
+    /** More arguments than there are registers for. */
+    static native double sum(int a, double b, long c, float d, byte e, char f, short g, boolean h, double i, int j,
+                             double k, long l, double m, double n, double o, double p, double q, int r, long s, double t);
+
+    static native String rethrow(String message);
+
+    static native void propagate(String message);
+
+    static native void throwNew(String message);
+
+    static native String keep(String value, int garbage);
+
+    /** Bound with RegisterNatives by JNI_OnLoad. */
+    static native boolean registered();
+
+    static native int missing();
+
+    static String propagated(String message) {
+        try {
+            propagate(message);
+            return null;
+        } catch (IllegalArgumentException e) {
+            return e.getMessage();
+        }
+    }
+
+    static String thrown(String message) {
+        try {
+            throwNew(message);
+            return null;
+        } catch (IllegalStateException e) {
+            return e.getMessage();
+        }
+    }
+
+    static void load(String filename) {
+        System.load(filename);
+    }
+
+    static void loadLibrary(String libname) {
+        System.loadLibrary(libname);
+    }
+}
@@ -0,0 +1,163 @@
+import java.util.Objects;
+
+/** Exercises the bundled class library; prints the same as on a JDK. */
+public class Library {
+    static class Point implements Cloneable {
+        int x;
+        int[] coordinates = { 1, 2 };
+
+        Point(int x) {
+            this.x = x;
+        }
+
+        Point copy() throws CloneNotSupportedException {
+            return (Point) clone();
+        }
+
+        public String toString() {
+            return "Point(" + x + ")";
+        }
+    }
+
+    public static void main(String[] args) throws Exception {
+        System.out.println("args: " + args.length + (args.length > 0 ? " " + args[0] : ""));
+        strings();
+        numbers();
+        math();
+        arrays();
+        exceptions();
+        System.out.print("no newline, ");
+        System.out.print('c');
+        System.out.print(1L);
+        System.out.println();
+        System.err.println("to stderr");
+        if (args.length > 1) {
+            System.exit(Integer.parseInt(args[1]));
+        }
+        System.out.println("not reached with a status");
+    }
+
+    static void strings() {
+        String s = "Hello, World";
+        System.out.println(s.length() + " " + s.substring(7) + " " + s.indexOf('o') + " " + s.lastIndexOf("o") + " " + s.indexOf("xyz"));
+        System.out.println(s.toUpperCase() + " " + s.toLowerCase() + " " + "  trim me ".trim() + "|");
+        System.out.println(s.startsWith("Hell") + " " + s.endsWith("World") + " " + s.contains(", ") + " " + s.replace('l', 'L') + " " + s.replace("World", "there"));
+        System.out.println("abc".compareTo("abd") + " " + "b".compareTo("a") + " " + "ab".equalsIgnoreCase("AB") + " " + "a,b".indexOf(",", 2) + " " + "".isEmpty());
+        System.out.println("caf\u00e9 \u65e5\u672c \ud83d\ude00".getBytes().length + " " + "x".replace("", "-") + " " + String.valueOf((Object) null));
+        StringBuilder builder = new StringBuilder("abc");
+        builder.append(1).append('d').append(2.5).append(true).append((Object) null).append(3L).append(1.5f);
+        builder.insert(0, "[").append(']').reverse();
+        System.out.println(builder + " " + builder.length() + " " + builder.charAt(1));
+        StringBuilder surrogates = new StringBuilder("ab\ud83d\ude00").reverse();
+        System.out.println(Integer.toHexString(surrogates.charAt(0)) + " " + Integer.toHexString(surrogates.charAt(1)) + " " + surrogates.charAt(3));
+        builder.setLength(3);
+        builder.deleteCharAt(0);
+        System.out.println(builder.toString() + Objects.toString(null, "!") + Objects.equals("a", "a") + Objects.hashCode(null));
+    }
+
+    static void numbers() {
+        System.out.println(Integer.parseInt("-123") + Integer.parseInt("+7") + " " + Integer.parseInt("ff", 16) + " " + Integer.MIN_VALUE + " " + Long.MAX_VALUE);
+        System.out.println(Integer.toHexString(-1) + " " + Integer.toBinaryString(10) + " " + Integer.toOctalString(8) + " " + Long.toHexString(255L) + " " + Integer.toString(-255, 16));
+        System.out.println(Integer.valueOf(127) == Integer.valueOf(127));
+        Integer boxed = 1000;
+        Long big = 5L;
+        Character c = 'x';
+        Boolean b = true;
+        Short s = 12;
+        Byte y = -3;
+        System.out.println(boxed + big + c + " " + b + s + y + " " + boxed.equals(1000) + " " + boxed.hashCode() + " " + big.hashCode() + " " + Boolean.hashCode(false));
+        double[] doubles = { 0.0, -0.0, 1.0, 0.1, 100.0, 1e7, 1.0E-3, 9.999e-4, 123456.789, 1e21, Math.PI, Double.MAX_VALUE, Double.MIN_VALUE, 1.0 / 0, -1.0 / 0, 0.0 / 0, 2.0 / 3 };
+        for (double d : doubles) {
+            System.out.print(d + " ");
+        }
+        System.out.println();
+        float[] floats = { 0.1f, 1.0f / 3, 1e10f, 3.4028235e38f, 1.4e-45f, 100f };
+        for (float f : floats) {
+            System.out.print(f + " ");
+        }
+        System.out.println();
+        System.out.println(Double.parseDouble(" 1.5e3 ") + " " + Double.parseDouble("-0") + " " + Float.parseFloat("3.25f") + " " + Double.valueOf("NaN") + " " + Double.parseDouble("-Infinity"));
+        System.out.println(Double.compare(0.0, -0.0) + " " + Double.compare(Double.NaN, 1.0) + " " + Double.doubleToLongBits(1.0) + " " + Float.floatToIntBits(1.0f) + " " + Double.isNaN(0.0 / 0));
+        System.out.println(Character.isDigit('7') + " " + Character.isLetter('\u00e9') + " " + Character.isWhitespace('\t') + " " + (int) Character.toUpperCase('\u00e9') + " " + Character.digit('f', 16) + " " + Character.forDigit(11, 16) + " " + Character.isUpperCase('A'));
+        for (String input : new String[] { "12a", "", "99999999999", "-", null }) {
+            try {
+                Integer.parseInt(input);
+            } catch (NumberFormatException e) {
+                System.out.println(e);
+            }
+        }
+        try {
+            Double.parseDouble("1.2.3");
+        } catch (NumberFormatException e) {
+            System.out.println(e.getMessage());
+        }
+    }
+
+    static void math() {
+        System.out.println(Math.abs(-5) + " " + Math.max(3, 9) + " " + Math.min(-2L, 4L) + " " + Math.max(-0.0, 0.0) + " " + Math.min(-0.0, 0.0) + " " + Math.max(Double.NaN, 1.0));
+        System.out.println(Math.sqrt(2) + " " + Math.pow(2, 10) + " " + Math.pow(1, Double.NaN) + " " + Math.floor(-1.5) + " " + Math.ceil(-1.5) + " " + Math.rint(2.5));
+        System.out.println(Math.round(2.5) + " " + Math.round(-2.5) + " " + Math.round(0.49999999999999994) + " " + Math.round(1.5f) + " " + Math.round(Double.NaN) + " " + Math.round(1e30));
+        System.out.println(Math.floorDiv(-7, 2) + " " + Math.floorMod(-7, 2) + " " + Math.sin(0) + " " + Math.cos(0) + " " + Math.log(Math.E) + " " + Math.atan2(1, 1) + " " + Math.hypot(3, 4));
+        try {
+            Math.addExact(Integer.MAX_VALUE, 1);
+        } catch (ArithmeticException e) {
+            System.out.println(e);
+        }
+    }
+
+    static void arrays() throws CloneNotSupportedException {
+        int[] numbers = { 1, 2, 3, 4, 5 };
+        System.arraycopy(numbers, 0, numbers, 1, 4);
+        int[] copy = numbers.clone();
+        copy[0] = 9;
+        System.out.println(numbers[0] + " " + numbers[1] + " " + numbers[4] + " " + copy[0] + " " + copy.length);
+        Point point = new Point(3);
+        Point other = point.copy();
+        other.x = 4;
+        System.out.println(point + " " + other + " " + (point.coordinates == other.coordinates));
+        Object[] objects = { "a", 1 };
+        String[] strings = new String[2];
+        try {
+            System.arraycopy(objects, 0, strings, 0, 2);
+        } catch (ArrayStoreException e) {
+            System.out.println(e.getMessage() + " " + strings[0]);
+        }
+        try {
+            System.arraycopy(numbers, 3, copy, 0, 5);
+        } catch (ArrayIndexOutOfBoundsException e) {
+            System.out.println(e.getMessage());
+        }
+        try {
+            System.arraycopy(numbers, 0, new long[5], 0, 1);
+        } catch (ArrayStoreException e) {
+            System.out.println(e.getMessage());
+        }
+        try {
+            new Object() {
+                Object copy() throws CloneNotSupportedException {
+                    return clone();
+                }
+            }.copy();
+        } catch (CloneNotSupportedException e) {
+            System.out.println(e);
+        }
+    }
+
+    static void exceptions() {
+        try {
+            Objects.requireNonNull(null, "value");
+        } catch (NullPointerException e) {
+            System.out.println(e);
+        }
+        try {
+            "abc".substring(2, 1);
+        } catch (StringIndexOutOfBoundsException e) {
+            System.out.println(e);
+        }
+        try {
+            throw new UnsupportedOperationException("nope");
+        } catch (RuntimeException e) {
+            System.out.println(e + " " + (e instanceof UnsupportedOperationException));
+        }
+    }
+}
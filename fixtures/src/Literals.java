@@ -0,0 +1,59 @@
+public class Literals {
+    static final String GREETING = "hello";
+
+    static String greeting() {
+        return "hello";
+    }
+
+    static String unicode() {
+        return "h\u00e9\u20ac";
+    }
+
+    static int small() {
+        return -100;
+    }
+
+    static int medium() {
+        return 30000;
+    }
+
+    static int big() {
+        return 1234567;
+    }
+
+    static long one() {
+        return 1L;
+    }
+
+    static long wide() {
+        return 1234567890123L;
+    }
+
+    static float two() {
+        return 2f;
+    }
+
+    static float half() {
+        return 2.5f;
+    }
+
+    static double unit() {
+        return 1.0;
+    }
+
+    static double quarter() {
+        return 3.25;
+    }
+
+    static Class<?> self() {
+        return Literals.class;
+    }
+
+    static Class<?> ints() {
+        return int[].class;
+    }
+
+    static Class<?> matrix() {
+        return Object[][].class;
+    }
+}
@@ -0,0 +1,39 @@
+public class More implements Runnable {
+    private int counter;
+
+    public int classify(int value) {
+        switch (value) {
+            case 0: return 1;
+            case 1: return 2;
+            case 2: return 3;
+            default: return -1;
+        }
+    }
+
+    public int sparse(int value) {
+        switch (value) {
+            case 0: return 1;
+            case 1000: return 2;
+            default: return -1;
+        }
+    }
+
+    public void run() {
+        try {
+            counter = counter / 0;
+        } catch (ArithmeticException e) {
+            counter = 1;
+        } finally {
+            counter++;
+        }
+        for (int i = 0; i < 10; i++) {
+            counter += i;
+        }
+        String[] names = new String[3];
+        int[][] matrix = new int[2][3];
+        Object o = names;
+        if (o instanceof String[]) {
+            counter += matrix.length;
+        }
+    }
+}
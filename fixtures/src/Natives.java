@@ -0,0 +1,38 @@
+/** Native methods bound to Rust functions by the tests. */
+public class Natives {
+    private final int base;
+
+    Natives(int base) {
+        this.base = base;
+    }
+
+    static native int add(int a, int b);
+
+    native long offset(long value);
+
+    static native String greeting(String name);
+
+    static native void fail(String message);
+
+    static native int unbound(double value);
+
+    static int sum() {
+        return add(40, 2) + (int) new Natives(100).offset(-58L);
+    }
+
+    static String failure() {
+        try {
+            fail("from native code");
+            return null;
+        } catch (IllegalStateException e) {
+            return e.getMessage();
+        }
+    }
+
+    static boolean objects() {
+        Object object = new Object();
+        Natives natives = new Natives(0);
+        return object.hashCode() == object.hashCode() && object.getClass() == Object.class
+                && natives.getClass() == Natives.class && natives.getClass() != object.getClass();
+    }
+}
@@ -0,0 +1,102 @@
+public class Objects {
+    static class Point {
+        int x;
+        int y;
+
+        Point(int x, int y) {
+            this.x = x;
+            this.y = y;
+        }
+
+        int sum() {
+            return x + y;
+        }
+    }
+
+    static class Point3 extends Point {
+        int z;
+        long stamp;
+        double weight;
+        byte tag;
+        char letter;
+        short small;
+        boolean flag;
+        Point3 next;
+
+        Point3(int x, int y, int z) {
+            super(x, y);
+            this.z = z;
+        }
+
+        int sum() {
+            return super.sum() + z;
+        }
+    }
+
+    static class Shadow extends Point {
+        int x;
+
+        Shadow() {
+            super(1, 2);
+            x = 5;
+        }
+
+        int both() {
+            return x + super.x;
+        }
+    }
+
+    interface Shape {
+        int corners();
+
+        default int sides() {
+            return corners();
+        }
+    }
+
+    static class Square implements Shape {
+        public int corners() {
+            return 4;
+        }
+    }
+
+    static int dispatch() {
+        Point p = new Point3(1, 2, 3);
+        return p.sum();
+    }
+
+    static int shadowed() {
+        return new Shadow().both();
+    }
+
+    static int interfaces() {
+        Shape s = new Square();
+        return s.sides() + s.corners();
+    }
+
+    static long wide(long stamp, double weight) {
+        Point3 p = new Point3(0, 0, 0);
+        p.stamp = stamp;
+        p.weight = weight;
+        p.next = p;
+        return p.next.stamp;
+    }
+
+    static int same() {
+        Point a = new Point(1, 2);
+        Point b = a;
+        Point c = new Point(1, 2);
+        return (a == b ? 1 : 0) + (a == c ? 2 : 0);
+    }
+
+    static int locked(Point p) {
+        synchronized (p) {
+            return p.x;
+        }
+    }
+
+    static int nullField() {
+        Point p = null;
+        return p.x;
+    }
+}
@@ -0,0 +1,83 @@
+import java.lang.ref.PhantomReference;
+import java.lang.ref.Reference;
+import java.lang.ref.ReferenceQueue;
+import java.lang.ref.SoftReference;
+import java.lang.ref.WeakReference;
+
+public class References {
+    static class Finalizable {
+        int[] payload = new int[16];
+
+        @Override
+        protected void finalize() {
+            finalized++;
+            resurrected = this;
+        }
+    }
+
+    static final int WEAK = 1;
+    static final int SOFT = 2;
+    static final int PHANTOM = 4;
+
+    static ReferenceQueue<Object> queue = new ReferenceQueue<Object>();
+    static Object strong;
+    static WeakReference<Object> weak;
+    static WeakReference<Object> weakToStrong;
+    static SoftReference<Object> soft;
+    static PhantomReference<Object> phantom;
+    static Finalizable resurrected;
+    static int finalized;
+
+    static void setUp() {
+        strong = new Object();
+        weak = new WeakReference<Object>(new int[16], queue);
+        weakToStrong = new WeakReference<Object>(strong, queue);
+        soft = new SoftReference<Object>(new int[16], queue);
+        phantom = new PhantomReference<Object>(new Finalizable(), queue);
+    }
+
+    /** Which references are cleared, as a mask of WEAK, SOFT and PHANTOM. */
+    static int cleared() {
+        int mask = 0;
+        if (weak.get() == null) {
+            mask |= WEAK;
+        }
+        if (soft.get() == null) {
+            mask |= SOFT;
+        }
+        if (weakToStrong.get() != strong) {
+            mask = -1;
+        }
+        return mask;
+    }
+
+    /** Takes the references queued so far, as a mask of WEAK, SOFT and PHANTOM. */
+    static int queued() {
+        int mask = 0;
+        for (Reference<?> r = queue.poll(); r != null; r = queue.poll()) {
+            if (r.get() != null && r != phantom) {
+                return -1;
+            }
+            mask |= r == weak ? WEAK : r == soft ? SOFT : r == phantom ? PHANTOM : 0;
+        }
+        return mask;
+    }
+
+    static int finalized() {
+        return finalized;
+    }
+
+    static void release() {
+        resurrected = null;
+    }
+
+    /** Fills the heap until the collector has to clear soft references and then runs out. */
+    static boolean exhaust(int count) {
+        try {
+            Garbage.retain(count);
+            return false;
+        } catch (OutOfMemoryError e) {
+            return soft.get() == null;
+        }
+    }
+}
@@ -0,0 +1,39 @@
+public class Strings {
+    static final String LATIN1 = "caf\u00e9";
+    static final String UTF16 = "\u65e5\u672c\u8a9e";
+    static final String SUPPLEMENTARY = "a\ud83d\ude00\u0000";
+    static final String UNPAIRED = "x\ud800";
+
+    static int length(String s) {
+        return s.length();
+    }
+
+    static int charAt(String s, int index) {
+        return s.charAt(index);
+    }
+
+    static int hash(String s) {
+        return s.hashCode();
+    }
+
+    /** Builds the string from its characters, which gives an equal string that is not the literal. */
+    static String copy(String s) {
+        char[] chars = new char[s.length()];
+        for (int i = 0; i < chars.length; i++) {
+            chars[i] = s.charAt(i);
+        }
+        return new String(chars);
+    }
+
+    static boolean interned(String s) {
+        String copy = copy(s);
+        return copy != s && copy.equals(s) && copy.intern() == s;
+    }
+
+    /** The first string interned with a value becomes the canonical one. */
+    static boolean internedFirst() {
+        char[] chars = { 'f', 'r', 'e', 's', 'h' };
+        String fresh = new String(chars);
+        return fresh.intern() == fresh && new String(chars).intern() == fresh;
+    }
+}
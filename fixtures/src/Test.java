@@ -0,0 +1,7 @@
+public class Test {
+    public static void main(String[] args) {
+        int a = 8;
+        int b = 2;
+        System.out.println(a + b + 8);
+    }
+}
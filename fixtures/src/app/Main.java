@@ -0,0 +1,9 @@
+package app;
+
+import demo.Greeter;
+
+public class Main {
+    public static void main(String[] args) {
+        System.out.println(Version.release() + Greeter.greet(args.length));
+    }
+}
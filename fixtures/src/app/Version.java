@@ -0,0 +1,7 @@
+package app;
+
+public class Version {
+    public static int release() {
+        return 8;
+    }
+}
@@ -0,0 +1,11 @@
+package demo;
+
+public class Greeter {
+    public static int greet(int times) {
+        int count = 0;
+        for (int i = 0; i < times; i++) {
+            count += 2;
+        }
+        return count;
+    }
+}
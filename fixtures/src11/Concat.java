@@ -0,0 +1,48 @@
+/** Compiled with --release 11, so javac concatenates strings with invokedynamic and StringConcatFactory. */
+public class Concat {
+    static final String CONSTANT = "constant";
+
+    static class Point {
+        final int x;
+        final int y;
+
+        Point(int x, int y) {
+            this.x = x;
+            this.y = y;
+        }
+
+        public String toString() {
+            return "(" + x + ", " + y + ")";
+        }
+    }
+
+    static String primitives(boolean z, char c, byte b, short s, int i, long j, float f, double d) {
+        return "z=" + z + " c=" + c + " b=" + b + " s=" + s + " i=" + i + " j=" + j + " f=" + f + " d=" + d;
+    }
+
+    static String objects(String s, Object o) {
+        return s + o + new Point(1, -2) + '!';
+    }
+
+    /** The characters that are tags in a recipe are passed as constants when they occur in literals. */
+    static String tags(int i) {
+        return "\u0001" + i + "\u0002" + CONSTANT.length();
+    }
+
+    static String unicode(String s) {
+        return s + "\u65e5\u672c" + s;
+    }
+
+    static String format() {
+        return String.format("%s|%5d|%-5d|%05d|%x|%X|%o|%,d|%+.2f|%.3e|%c|%b|%%|%2$s|%<s|%08.3f|%(d|%n",
+                "str", 42, 42, -42, 255, 255, 8, 1234567, 2.675, 12345.678, 'z', null, 3.14159, -7);
+    }
+
+    static String formatFloats() {
+        return String.format("%.0f %.1f %f %.2f %e %.0e %f %f %.3f", 2.5, 0.05, 1e-7, 9.999, 0.0, 9.5, Double.NaN, -1.0 / 0, 1.1f);
+    }
+
+    static String missingArgument() {
+        return String.format("%s %s", "one");
+    }
+}
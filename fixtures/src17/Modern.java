@@ -0,0 +1,62 @@
+import java.util.Comparator;
+import java.util.List;
+import java.util.Map;
+import java.util.Optional;
+import java.util.TreeMap;
+import java.util.function.BiFunction;
+import java.util.function.Supplier;
+import java.util.stream.Collectors;
+import java.util.stream.IntStream;
+import java.util.stream.Stream;
+
+/** Compiled with --release 17: lambdas, records and streams, which run on a JDK's java.base. */
+public class Modern {
+    record Point(int x, double y, String label) {}
+
+    enum Color { RED, GREEN }
+
+    interface Shape {
+        double area();
+
+        default String describe() {
+            return getClass().getSimpleName() + " " + area();
+        }
+    }
+
+    static class Square implements Shape {
+        public double area() {
+            return 4.0;
+        }
+    }
+
+    public static void main(String[] args) throws Exception {
+        System.out.println(IntStream.rangeClosed(1, 10).filter(i -> i % 2 == 0).mapToObj(Integer::toString).collect(Collectors.joining("+")));
+        System.out.println(Stream.of("b", "a", "c").sorted(Comparator.reverseOrder()).collect(Collectors.toList()));
+        Point point = new Point(1, 2.5, "p");
+        System.out.println(point + " " + point.equals(new Point(1, 2.5, "p")) + " " + point.equals(new Point(1, 2.5, "q")) + " " + point.hashCode());
+        System.out.println(Color.valueOf("GREEN").ordinal() + " " + new Square().describe());
+        Map<String, List<Integer>> groups = IntStream.range(0, 7).boxed().collect(Collectors.groupingBy(i -> i % 3 == 0 ? "three" : "other"));
+        System.out.println(new TreeMap<>(groups));
+        Supplier<StringBuilder> supplier = StringBuilder::new;
+        BiFunction<Integer, Integer, Integer> add = (a, b) -> a + b;
+        int base = 10;
+        System.out.println(supplier.get().append("built") + " " + add.apply(2, 3) + " " + Optional.of(4).map(v -> v * base).orElse(0));
+        System.out.println(String.format("%5d|%-4s|%.3f|%x|%,d", 42, "ab", Math.PI, 255, 1234567));
+        System.out.println(Modern.class.getDeclaredMethod("twice", int.class).invoke(null, 21));
+        try {
+            Modern.class.getDeclaredMethod("fail").invoke(null);
+        } catch (java.lang.reflect.InvocationTargetException e) {
+            System.out.println("wrapped " + e.getCause());
+        }
+        new IllegalStateException("boom").printStackTrace(System.out);
+        System.out.println(System.getProperty("java.home").equals(System.getenv("JAVA_HOME")) + " " + Thread.currentThread().getName());
+    }
+
+    static int twice(int value) {
+        return value * 2;
+    }
+
+    static void fail() {
+        throw new UnsupportedOperationException("reflected");
+    }
+}
@@ -0,0 +1,7 @@
+#!/bin/sh
+# Regenerates the class files of the bundled runtime embedded by src/rt.rs. Requires a JDK (17+) on PATH.
+# String concatenation is compiled to StringBuilder calls so the runtime does not depend on invokedynamic.
+set -e
+cd "$(dirname "$0")"
+rm -rf classes
+javac --patch-module java.base=src -source 11 -target 11 -Xlint:-options -XDstringConcat=inline -g -d classes $(find src -name '*.java' | sort)
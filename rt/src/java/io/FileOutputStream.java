@@ -0,0 +1,30 @@
+package java.io;
+
+/** Writes to a file descriptor; the VM routes the standard streams to its own output. */
+public class FileOutputStream extends OutputStream {
+    private final FileDescriptor fd;
+
+    public FileOutputStream(FileDescriptor fdObj) {
+        if (fdObj == null) {
+            throw new NullPointerException();
+        }
+        this.fd = fdObj;
+    }
+
+    public final FileDescriptor getFD() {
+        return fd;
+    }
+
+    private static native void writeBytes(int fd, byte[] b, int off, int len) throws IOException;
+
+    public void write(int b) throws IOException {
+        writeBytes(fd.fd, new byte[] { (byte) b }, 0, 1);
+    }
+
+    public void write(byte[] b, int off, int len) throws IOException {
+        if (off < 0 || len < 0 || off > b.length - len) {
+            throw new IndexOutOfBoundsException("Range [" + off + ", " + off + " + " + len + ") out of bounds for length " + b.length);
+        }
+        writeBytes(fd.fd, b, off, len);
+    }
+}
@@ -0,0 +1,152 @@
+package java.io;
+
+/**
+ * Prints text as UTF-8, ending lines with {@code '\n'}. Like the JDK's, a print stream never
+ * throws {@link IOException}: failures are remembered for {@link #checkError}.
+ */
+public class PrintStream extends OutputStream {
+    private final OutputStream out;
+    private final boolean autoFlush;
+    private boolean trouble;
+
+    public PrintStream(OutputStream out) {
+        this(out, false);
+    }
+
+    public PrintStream(OutputStream out, boolean autoFlush) {
+        if (out == null) {
+            throw new NullPointerException("Null output stream");
+        }
+        this.out = out;
+        this.autoFlush = autoFlush;
+    }
+
+    public void write(int b) {
+        try {
+            out.write(b);
+            if (b == '\n' && autoFlush) {
+                out.flush();
+            }
+        } catch (IOException e) {
+            trouble = true;
+        }
+    }
+
+    public void write(byte[] buf, int off, int len) {
+        try {
+            out.write(buf, off, len);
+            if (autoFlush) {
+                out.flush();
+            }
+        } catch (IOException e) {
+            trouble = true;
+        }
+    }
+
+    public void flush() {
+        try {
+            out.flush();
+        } catch (IOException e) {
+            trouble = true;
+        }
+    }
+
+    public void close() {
+        try {
+            out.close();
+        } catch (IOException e) {
+            trouble = true;
+        }
+    }
+
+    public boolean checkError() {
+        flush();
+        return trouble;
+    }
+
+    public void print(String s) {
+        byte[] bytes = String.valueOf(s).getBytes();
+        write(bytes, 0, bytes.length);
+    }
+
+    public void print(Object obj) {
+        print(String.valueOf(obj));
+    }
+
+    public void print(char[] s) {
+        print(new String(s));
+    }
+
+    public void print(boolean b) {
+        print(String.valueOf(b));
+    }
+
+    public void print(char c) {
+        print(String.valueOf(c));
+    }
+
+    public void print(int i) {
+        print(String.valueOf(i));
+    }
+
+    public void print(long l) {
+        print(String.valueOf(l));
+    }
+
+    public void print(float f) {
+        print(String.valueOf(f));
+    }
+
+    public void print(double d) {
+        print(String.valueOf(d));
+    }
+
+    public void println() {
+        print("\n");
+    }
+
+    public void println(String x) {
+        print(String.valueOf(x).concat("\n"));
+    }
+
+    public void println(Object x) {
+        println(String.valueOf(x));
+    }
+
+    public void println(char[] x) {
+        println(new String(x));
+    }
+
+    public void println(boolean x) {
+        println(String.valueOf(x));
+    }
+
+    public void println(char x) {
+        println(String.valueOf(x));
+    }
+
+    public void println(int x) {
+        println(String.valueOf(x));
+    }
+
+    public void println(long x) {
+        println(String.valueOf(x));
+    }
+
+    public void println(float x) {
+        println(String.valueOf(x));
+    }
+
+    public void println(double x) {
+        println(String.valueOf(x));
+    }
+
+    public PrintStream printf(String format, Object... args) {
+        return format(format, args);
+    }
+
+    public PrintStream format(String format, Object... args) {
+        print(String.format(format, args));
+        return this;
+    }
+}
@@ -0,0 +1,57 @@
+package java.lang;
+
+public final class Boolean implements java.io.Serializable, Comparable<Boolean> {
+    public static final Boolean TRUE = new Boolean(true);
+    public static final Boolean FALSE = new Boolean(false);
+
+    private final boolean value;
+
+    public Boolean(boolean value) {
+        this.value = value;
+    }
+
+    public static Boolean valueOf(boolean b) {
+        return b ? TRUE : FALSE;
+    }
+
+    public static Boolean valueOf(String s) {
+        return parseBoolean(s) ? TRUE : FALSE;
+    }
+
+    /** True for "true" in any case, false for anything else, null included. */
+    public static boolean parseBoolean(String s) {
+        return "true".equalsIgnoreCase(s);
+    }
+
+    public static String toString(boolean b) {
+        return b ? "true" : "false";
+    }
+
+    public static int compare(boolean x, boolean y) {
+        return (x == y) ? 0 : (x ? 1 : -1);
+    }
+
+    public static int hashCode(boolean value) {
+        return value ? 1231 : 1237;
+    }
+
+    public boolean booleanValue() {
+        return value;
+    }
+
+    public int compareTo(Boolean other) {
+        return compare(value, other.value);
+    }
+
+    public int hashCode() {
+        return hashCode(value);
+    }
+
+    public boolean equals(Object obj) {
+        return obj instanceof Boolean && value == ((Boolean) obj).value;
+    }
+
+    public String toString() {
+        return toString(value);
+    }
+}
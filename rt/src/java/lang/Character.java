@@ -0,0 +1,184 @@
+package java.lang;
+
+/**
+ * Characters and their classification. The Unicode properties of characters outside ASCII come
+ * from the VM's natives, which follow Rust's {@code char} predicates: letters are the Alphabetic
+ * characters and digits the Numeric ones, a superset of Java's decimal digits.
+ */
+public final class Character implements java.io.Serializable, Comparable<Character> {
+    public static final int MIN_RADIX = 2;
+    public static final int MAX_RADIX = 36;
+    public static final char MIN_VALUE = '\u0000';
+    public static final char MAX_VALUE = '\uffff';
+    public static final char MIN_HIGH_SURROGATE = '\ud800';
+    public static final char MAX_HIGH_SURROGATE = '\udbff';
+    public static final char MIN_LOW_SURROGATE = '\udc00';
+    public static final char MAX_LOW_SURROGATE = '\udfff';
+    public static final int MIN_SUPPLEMENTARY_CODE_POINT = 0x010000;
+    public static final int MAX_CODE_POINT = 0x10ffff;
+
+    private static final Character[] CACHE = new Character[128];
+
+    private final char value;
+
+    public Character(char value) {
+        this.value = value;
+    }
+
+    public static Character valueOf(char c) {
+        if (c < CACHE.length) {
+            Character cached = CACHE[c];
+            if (cached == null) {
+                cached = CACHE[c] = new Character(c);
+            }
+            return cached;
+        }
+        return new Character(c);
+    }
+
+    public char charValue() {
+        return value;
+    }
+
+    public int hashCode() {
+        return value;
+    }
+
+    public static int hashCode(char value) {
+        return value;
+    }
+
+    public boolean equals(Object obj) {
+        return obj instanceof Character && value == ((Character) obj).value;
+    }
+
+    public int compareTo(Character other) {
+        return compare(value, other.value);
+    }
+
+    public static int compare(char x, char y) {
+        return x - y;
+    }
+
+    public String toString() {
+        return String.valueOf(value);
+    }
+
+    public static String toString(char c) {
+        return String.valueOf(c);
+    }
+
+    public static boolean isHighSurrogate(char ch) {
+        return ch >= MIN_HIGH_SURROGATE && ch <= MAX_HIGH_SURROGATE;
+    }
+
+    public static boolean isLowSurrogate(char ch) {
+        return ch >= MIN_LOW_SURROGATE && ch <= MAX_LOW_SURROGATE;
+    }
+
+    public static boolean isSurrogate(char ch) {
+        return ch >= MIN_HIGH_SURROGATE && ch <= MAX_LOW_SURROGATE;
+    }
+
+    public static int toCodePoint(char high, char low) {
+        return ((high - MIN_HIGH_SURROGATE) << 10) + (low - MIN_LOW_SURROGATE) + MIN_SUPPLEMENTARY_CODE_POINT;
+    }
+
+    public static boolean isDigit(char ch) {
+        return isDigit((int) ch);
+    }
+
+    public static boolean isDigit(int codePoint) {
+        return codePoint < 0x80 ? codePoint >= '0' && codePoint <= '9' : isNumeric(codePoint);
+    }
+
+    public static boolean isLetter(char ch) {
+        return isLetter((int) ch);
+    }
+
+    public static boolean isLetter(int codePoint) {
+        return codePoint < 0x80 ? (codePoint | 0x20) >= 'a' && (codePoint | 0x20) <= 'z' : isAlphabetic(codePoint);
+    }
+
+    public static boolean isLetterOrDigit(char ch) {
+        return isLetter(ch) || isDigit(ch);
+    }
+
+    public static boolean isLetterOrDigit(int codePoint) {
+        return isLetter(codePoint) || isDigit(codePoint);
+    }
+
+    public static native boolean isAlphabetic(int codePoint);
+
+    private static native boolean isNumeric(int codePoint);
+
+    public static boolean isUpperCase(char ch) {
+        return isUpperCase((int) ch);
+    }
+
+    public static native boolean isUpperCase(int codePoint);
+
+    public static boolean isLowerCase(char ch) {
+        return isLowerCase((int) ch);
+    }
+
+    public static native boolean isLowerCase(int codePoint);
+
+    public static boolean isWhitespace(char ch) {
+        return isWhitespace((int) ch);
+    }
+
+    /** Space separators other than no-break spaces, and the ASCII control characters that separate. */
+    public static boolean isWhitespace(int codePoint) {
+        switch (codePoint) {
+            case ' ': case '\t': case '\n': case 0x0b: case '\f': case '\r':
+            case 0x1c: case 0x1d: case 0x1e: case 0x1f:
+            case 0x1680: case 0x2028: case 0x2029: case 0x205f: case 0x3000:
+                return true;
+            default:
+                return codePoint >= 0x2000 && codePoint <= 0x200a && codePoint != 0x2007;
+        }
+    }
+
+    /** The upper case mapping of a character, or the character if it has none of a single char. */
+    public static char toUpperCase(char ch) {
+        return (char) toUpperCase((int) ch);
+    }
+
+    public static native int toUpperCase(int codePoint);
+
+    public static char toLowerCase(char ch) {
+        return (char) toLowerCase((int) ch);
+    }
+
+    public static native int toLowerCase(int codePoint);
+
+    /** The value of a digit in a radix, the ASCII letters standing for 10 to 35; -1 if there is none. */
+    public static int digit(char ch, int radix) {
+        return digit((int) ch, radix);
+    }
+
+    public static int digit(int codePoint, int radix) {
+        if (radix < MIN_RADIX || radix > MAX_RADIX) {
+            return -1;
+        }
+        int value;
+        if (codePoint >= '0' && codePoint <= '9') {
+            value = codePoint - '0';
+        } else if ((codePoint | 0x20) >= 'a' && (codePoint | 0x20) <= 'z') {
+            value = (codePoint | 0x20) - 'a' + 10;
+        } else if (codePoint >= 0xff10 && codePoint <= 0xff19) {
+            value = codePoint - 0xff10;
+        } else {
+            return -1;
+        }
+        return value < radix ? value : -1;
+    }
+
+    public static char forDigit(int digit, int radix) {
+        if (digit < 0 || digit >= radix || radix < MIN_RADIX || radix > MAX_RADIX) {
+            return '\u0000';
+        }
+        return (char) (digit < 10 ? '0' + digit : 'a' - 10 + digit);
+    }
+}
@@ -0,0 +1,15 @@
+package java.lang;
+
+/** The mirror of a class, array class or primitive type, created by the VM. */
+public final class Class<T> {
+    // Identifies the class in the VM's class registry.
+    private transient int classId;
+    private transient String name;
+
+    private Class() {
+    }
+
+    public String getName() {
+        return name;
+    }
+}
@@ -0,0 +1,78 @@
+package java.lang;
+
+/**
+ * The runtime's class loader base. Loading is parent-first: a loader only looks for a class itself,
+ * through {@link #findClass}, once its parent (or the bootstrap loader) could not find it.
+ */
+public abstract class ClassLoader {
+    private final ClassLoader parent;
+
+    protected ClassLoader() {
+        this(getSystemClassLoader());
+    }
+
+    protected ClassLoader(ClassLoader parent) {
+        this.parent = parent;
+        register(parent);
+    }
+
+    public final ClassLoader getParent() {
+        return parent;
+    }
+
+    public Class<?> loadClass(String name) throws ClassNotFoundException {
+        return loadClass(name, false);
+    }
+
+    protected Class<?> loadClass(String name, boolean resolve) throws ClassNotFoundException {
+        Class<?> c = findLoadedClass(name);
+        if (c == null) {
+            try {
+                if (parent != null) {
+                    c = parent.loadClass(name, false);
+                } else {
+                    c = findBootstrapClass(name);
+                }
+            } catch (ClassNotFoundException e) {
+                // not found by the parents, look it up below
+            }
+            if (c == null) {
+                c = findClass(name);
+            }
+        }
+        return c;
+    }
+
+    protected Class<?> findClass(String name) throws ClassNotFoundException {
+        throw new ClassNotFoundException(name);
+    }
+
+    protected final Class<?> defineClass(String name, byte[] b, int off, int len) throws ClassFormatError {
+        if (b == null) {
+            throw new NullPointerException();
+        }
+        if (off < 0 || len < 0 || off + len > b.length) {
+            throw new IndexOutOfBoundsException();
+        }
+        return defineClass0(name, b, off, len);
+    }
+
+    protected final Class<?> defineClass(byte[] b, int off, int len) throws ClassFormatError {
+        return defineClass(null, b, off, len);
+    }
+
+    protected final native Class<?> findLoadedClass(String name);
+
+    public static native ClassLoader getSystemClassLoader();
+
+    public static ClassLoader getPlatformClassLoader() {
+        return getSystemClassLoader().getParent();
+    }
+
+    /** Gives this loader a loader id in the VM's class registry. */
+    private native void register(ClassLoader parent);
+
+    private native Class<?> defineClass0(String name, byte[] b, int off, int len);
+
+    private static native Class<?> findBootstrapClass(String name) throws ClassNotFoundException;
+}
@@ -0,0 +1,122 @@
+package java.lang;
+
+public final class Double extends Number implements Comparable<Double> {
+    public static final double POSITIVE_INFINITY = 1.0 / 0.0;
+    public static final double NEGATIVE_INFINITY = -1.0 / 0.0;
+    public static final double NaN = 0.0d / 0.0;
+    public static final double MAX_VALUE = 0x1.fffffffffffffP+1023;
+    public static final double MIN_NORMAL = 0x1.0p-1022;
+    public static final double MIN_VALUE = 0x0.0000000000001P-1022;
+    public static final int SIZE = 64;
+    public static final int BYTES = 8;
+
+    private final double value;
+
+    public Double(double value) {
+        this.value = value;
+    }
+
+    public static Double valueOf(double d) {
+        return new Double(d);
+    }
+
+    public static Double valueOf(String s) {
+        return new Double(parseDouble(s));
+    }
+
+    /** Parses a decimal number as Java source spells it, with an optional {@code d} or {@code f} suffix. */
+    public static native double parseDouble(String s);
+
+    /** The shortest decimal that rounds to {@code d}, in scientific notation outside 10^-3 to 10^7. */
+    public static native String toString(double d);
+
+    public static native long doubleToRawLongBits(double value);
+
+    public static native double longBitsToDouble(long bits);
+
+    /** The bits of a double with every NaN collapsed to the canonical one. */
+    public static long doubleToLongBits(double value) {
+        return isNaN(value) ? 0x7ff8000000000000L : doubleToRawLongBits(value);
+    }
+
+    public static boolean isNaN(double v) {
+        return v != v;
+    }
+
+    public static boolean isInfinite(double v) {
+        return v == POSITIVE_INFINITY || v == NEGATIVE_INFINITY;
+    }
+
+    public static boolean isFinite(double d) {
+        return Math.abs(d) <= MAX_VALUE;
+    }
+
+    /** Orders -0.0 before 0.0 and NaN after everything else, unlike the comparison operators. */
+    public static int compare(double d1, double d2) {
+        if (d1 < d2) {
+            return -1;
+        }
+        if (d1 > d2) {
+            return 1;
+        }
+        long bits1 = doubleToLongBits(d1);
+        long bits2 = doubleToLongBits(d2);
+        return bits1 == bits2 ? 0 : (bits1 < bits2 ? -1 : 1);
+    }
+
+    public static double sum(double a, double b) {
+        return a + b;
+    }
+
+    public static double max(double a, double b) {
+        return Math.max(a, b);
+    }
+
+    public static double min(double a, double b) {
+        return Math.min(a, b);
+    }
+
+    public static int hashCode(double value) {
+        return Long.hashCode(doubleToLongBits(value));
+    }
+
+    public boolean isNaN() {
+        return isNaN(value);
+    }
+
+    public boolean isInfinite() {
+        return isInfinite(value);
+    }
+
+    public int intValue() {
+        return (int) value;
+    }
+
+    public long longValue() {
+        return (long) value;
+    }
+
+    public float floatValue() {
+        return (float) value;
+    }
+
+    public double doubleValue() {
+        return value;
+    }
+
+    public int compareTo(Double other) {
+        return compare(value, other.value);
+    }
+
+    public int hashCode() {
+        return hashCode(value);
+    }
+
+    public boolean equals(Object obj) {
+        return obj instanceof Double && doubleToLongBits(value) == doubleToLongBits(((Double) obj).value);
+    }
+
+    public String toString() {
+        return toString(value);
+    }
+}
@@ -0,0 +1,120 @@
+package java.lang;
+
+public final class Float extends Number implements Comparable<Float> {
+    public static final float POSITIVE_INFINITY = 1.0f / 0.0f;
+    public static final float NEGATIVE_INFINITY = -1.0f / 0.0f;
+    public static final float NaN = 0.0f / 0.0f;
+    public static final float MAX_VALUE = 0x1.fffffeP+127f;
+    public static final float MIN_NORMAL = 0x1.0p-126f;
+    public static final float MIN_VALUE = 0x0.000002P-126f;
+    public static final int SIZE = 32;
+    public static final int BYTES = 4;
+
+    private final float value;
+
+    public Float(float value) {
+        this.value = value;
+    }
+
+    public static Float valueOf(float f) {
+        return new Float(f);
+    }
+
+    public static Float valueOf(String s) {
+        return new Float(parseFloat(s));
+    }
+
+    /** Parses like {@link Double#parseDouble}, rounding once to the nearest float. */
+    public static native float parseFloat(String s);
+
+    /** The shortest decimal that rounds to {@code f} as a float, laid out like {@link Double#toString(double)}. */
+    public static native String toString(float f);
+
+    public static native int floatToRawIntBits(float value);
+
+    public static native float intBitsToFloat(int bits);
+
+    public static int floatToIntBits(float value) {
+        return isNaN(value) ? 0x7fc00000 : floatToRawIntBits(value);
+    }
+
+    public static boolean isNaN(float v) {
+        return v != v;
+    }
+
+    public static boolean isInfinite(float v) {
+        return v == POSITIVE_INFINITY || v == NEGATIVE_INFINITY;
+    }
+
+    public static boolean isFinite(float f) {
+        return Math.abs(f) <= MAX_VALUE;
+    }
+
+    public static int compare(float f1, float f2) {
+        if (f1 < f2) {
+            return -1;
+        }
+        if (f1 > f2) {
+            return 1;
+        }
+        int bits1 = floatToIntBits(f1);
+        int bits2 = floatToIntBits(f2);
+        return bits1 == bits2 ? 0 : (bits1 < bits2 ? -1 : 1);
+    }
+
+    public static float sum(float a, float b) {
+        return a + b;
+    }
+
+    public static float max(float a, float b) {
+        return Math.max(a, b);
+    }
+
+    public static float min(float a, float b) {
+        return Math.min(a, b);
+    }
+
+    public static int hashCode(float value) {
+        return floatToIntBits(value);
+    }
+
+    public boolean isNaN() {
+        return isNaN(value);
+    }
+
+    public boolean isInfinite() {
+        return isInfinite(value);
+    }
+
+    public int intValue() {
+        return (int) value;
+    }
+
+    public long longValue() {
+        return (long) value;
+    }
+
+    public float floatValue() {
+        return value;
+    }
+
+    public double doubleValue() {
+        return value;
+    }
+
+    public int compareTo(Float other) {
+        return compare(value, other.value);
+    }
+
+    public int hashCode() {
+        return hashCode(value);
+    }
+
+    public boolean equals(Object obj) {
+        return obj instanceof Float && floatToIntBits(value) == floatToIntBits(((Float) obj).value);
+    }
+
+    public String toString() {
+        return toString(value);
+    }
+}
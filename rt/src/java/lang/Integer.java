@@ -0,0 +1,134 @@
+package java.lang;
+
+public final class Integer extends Number implements Comparable<Integer> {
+    public static final int MIN_VALUE = 0x80000000;
+    public static final int MAX_VALUE = 0x7fffffff;
+    public static final int SIZE = 32;
+    public static final int BYTES = 4;
+
+    /** The instances {@link #valueOf(int)} returns for -128 to 127, as JLS 5.1.7 requires. */
+    private static final Integer[] CACHE = new Integer[256];
+
+    private final int value;
+
+    public Integer(int value) {
+        this.value = value;
+    }
+
+    public static Integer valueOf(int i) {
+        if (i >= -128 && i <= 127) {
+            Integer cached = CACHE[i + 128];
+            if (cached == null) {
+                cached = CACHE[i + 128] = new Integer(i);
+            }
+            return cached;
+        }
+        return new Integer(i);
+    }
+
+    public static Integer valueOf(String s) {
+        return valueOf(parseInt(s, 10));
+    }
+
+    public static Integer valueOf(String s, int radix) {
+        return valueOf(parseInt(s, radix));
+    }
+
+    public static int parseInt(String s) {
+        return parseInt(s, 10);
+    }
+
+    /** Parses an optionally signed number; unlike {@link Long#parseLong} the result has to fit an int. */
+    public static int parseInt(String s, int radix) {
+        long value = Long.parseLong(s, radix);
+        if (value < MIN_VALUE || value > MAX_VALUE) {
+            throw NumberFormatException.forInputString(s, radix);
+        }
+        return (int) value;
+    }
+
+    public static String toString(int i) {
+        return toString(i, 10);
+    }
+
+    public static String toString(int i, int radix) {
+        return Long.toString(i, radix);
+    }
+
+    public static String toHexString(int i) {
+        return Long.toUnsignedString(i & 0xffffffffL, 4);
+    }
+
+    public static String toOctalString(int i) {
+        return Long.toUnsignedString(i & 0xffffffffL, 3);
+    }
+
+    public static String toBinaryString(int i) {
+        return Long.toUnsignedString(i & 0xffffffffL, 1);
+    }
+
+    public static int compare(int x, int y) {
+        return x < y ? -1 : (x == y ? 0 : 1);
+    }
+
+    public static int signum(int i) {
+        return (i >> 31) | (-i >>> 31);
+    }
+
+    public static int sum(int a, int b) {
+        return a + b;
+    }
+
+    public static int max(int a, int b) {
+        return Math.max(a, b);
+    }
+
+    public static int min(int a, int b) {
+        return Math.min(a, b);
+    }
+
+    public static int bitCount(int i) {
+        int count = 0;
+        while (i != 0) {
+            i &= i - 1;
+            count++;
+        }
+        return count;
+    }
+
+    public static int hashCode(int value) {
+        return value;
+    }
+
+    public int intValue() {
+        return value;
+    }
+
+    public long longValue() {
+        return value;
+    }
+
+    public float floatValue() {
+        return value;
+    }
+
+    public double doubleValue() {
+        return value;
+    }
+
+    public int compareTo(Integer other) {
+        return compare(value, other.value);
+    }
+
+    public int hashCode() {
+        return value;
+    }
+
+    public boolean equals(Object obj) {
+        return obj instanceof Integer && value == ((Integer) obj).value;
+    }
+
+    public String toString() {
+        return toString(value);
+    }
+}
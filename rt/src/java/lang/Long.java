@@ -0,0 +1,178 @@
+package java.lang;
+
+public final class Long extends Number implements Comparable<Long> {
+    public static final long MIN_VALUE = 0x8000000000000000L;
+    public static final long MAX_VALUE = 0x7fffffffffffffffL;
+    public static final int SIZE = 64;
+    public static final int BYTES = 8;
+
+    private static final Long[] CACHE = new Long[256];
+
+    private final long value;
+
+    public Long(long value) {
+        this.value = value;
+    }
+
+    public static Long valueOf(long l) {
+        if (l >= -128 && l <= 127) {
+            Long cached = CACHE[(int) l + 128];
+            if (cached == null) {
+                cached = CACHE[(int) l + 128] = new Long(l);
+            }
+            return cached;
+        }
+        return new Long(l);
+    }
+
+    public static Long valueOf(String s) {
+        return valueOf(parseLong(s, 10));
+    }
+
+    public static long parseLong(String s) {
+        return parseLong(s, 10);
+    }
+
+    /**
+     * Parses an optionally signed number in a radix. The digits accumulate negatively, like in the
+     * JDK, so that {@link #MIN_VALUE} can be parsed without overflowing.
+     */
+    public static long parseLong(String s, int radix) {
+        if (s == null) {
+            throw new NumberFormatException("Cannot parse null string");
+        }
+        if (radix < Character.MIN_RADIX || radix > Character.MAX_RADIX) {
+            throw new NumberFormatException("radix " + radix + " out of range");
+        }
+        int length = s.length();
+        int i = 0;
+        boolean negative = false;
+        if (length > 0 && (s.charAt(0) == '-' || s.charAt(0) == '+')) {
+            negative = s.charAt(0) == '-';
+            i++;
+        }
+        if (i == length) {
+            throw NumberFormatException.forInputString(s, radix);
+        }
+        long limit = negative ? MIN_VALUE : -MAX_VALUE;
+        long multiplyLimit = limit / radix;
+        long result = 0;
+        for (; i < length; i++) {
+            int digit = Character.digit(s.charAt(i), radix);
+            if (digit < 0 || result < multiplyLimit) {
+                throw NumberFormatException.forInputString(s, radix);
+            }
+            result *= radix;
+            if (result < limit + digit) {
+                throw NumberFormatException.forInputString(s, radix);
+            }
+            result -= digit;
+        }
+        return negative ? result : -result;
+    }
+
+    public static String toString(long l) {
+        return toString(l, 10);
+    }
+
+    public static String toString(long l, int radix) {
+        if (radix < Character.MIN_RADIX || radix > Character.MAX_RADIX) {
+            radix = 10;
+        }
+        char[] buffer = new char[65];
+        int position = buffer.length;
+        boolean negative = l < 0;
+        // Works on the negative value, which has room for MIN_VALUE.
+        if (!negative) {
+            l = -l;
+        }
+        do {
+            buffer[--position] = Character.forDigit((int) -(l % radix), radix);
+            l /= radix;
+        } while (l != 0);
+        if (negative) {
+            buffer[--position] = '-';
+        }
+        return new String(buffer, position, buffer.length - position);
+    }
+
+    /** Formats the bits of an unsigned value in groups of {@code shift} bits, for radix 2, 8 or 16. */
+    static String toUnsignedString(long l, int shift) {
+        char[] buffer = new char[64];
+        int position = buffer.length;
+        int radix = 1 << shift;
+        do {
+            buffer[--position] = Character.forDigit((int) (l & (radix - 1)), radix);
+            l >>>= shift;
+        } while (l != 0);
+        return new String(buffer, position, buffer.length - position);
+    }
+
+    public static String toHexString(long l) {
+        return toUnsignedString(l, 4);
+    }
+
+    public static String toOctalString(long l) {
+        return toUnsignedString(l, 3);
+    }
+
+    public static String toBinaryString(long l) {
+        return toUnsignedString(l, 1);
+    }
+
+    public static int compare(long x, long y) {
+        return x < y ? -1 : (x == y ? 0 : 1);
+    }
+
+    public static int signum(long l) {
+        return (int) ((l >> 63) | (-l >>> 63));
+    }
+
+    public static long sum(long a, long b) {
+        return a + b;
+    }
+
+    public static long max(long a, long b) {
+        return Math.max(a, b);
+    }
+
+    public static long min(long a, long b) {
+        return Math.min(a, b);
+    }
+
+    public static int hashCode(long value) {
+        return (int) (value ^ (value >>> 32));
+    }
+
+    public int intValue() {
+        return (int) value;
+    }
+
+    public long longValue() {
+        return value;
+    }
+
+    public float floatValue() {
+        return value;
+    }
+
+    public double doubleValue() {
+        return value;
+    }
+
+    public int compareTo(Long other) {
+        return compare(value, other.value);
+    }
+
+    public int hashCode() {
+        return hashCode(value);
+    }
+
+    public boolean equals(Object obj) {
+        return obj instanceof Long && value == ((Long) obj).value;
+    }
+
+    public String toString() {
+        return toString(value);
+    }
+}
@@ -0,0 +1,242 @@
+package java.lang;
+
+/** Basic numeric operations. The transcendental functions are natives backed by Rust's {@code f64}. */
+public final class Math {
+    public static final double E = 2.718281828459045;
+    public static final double PI = 3.141592653589793;
+
+    private Math() {
+    }
+
+    public static native double sin(double a);
+
+    public static native double cos(double a);
+
+    public static native double tan(double a);
+
+    public static native double asin(double a);
+
+    public static native double acos(double a);
+
+    public static native double atan(double a);
+
+    public static native double atan2(double y, double x);
+
+    public static native double sinh(double x);
+
+    public static native double cosh(double x);
+
+    public static native double tanh(double x);
+
+    public static native double exp(double a);
+
+    public static native double log(double a);
+
+    public static native double log10(double a);
+
+    public static native double sqrt(double a);
+
+    public static native double cbrt(double a);
+
+    public static native double hypot(double x, double y);
+
+    public static native double pow(double a, double b);
+
+    public static native double floor(double a);
+
+    public static native double ceil(double a);
+
+    /** Rounds to the nearest integer value, ties to even. */
+    public static native double rint(double a);
+
+    public static double toRadians(double angdeg) {
+        return angdeg / 180.0 * PI;
+    }
+
+    public static double toDegrees(double angrad) {
+        return angrad * 180.0 / PI;
+    }
+
+    /** Rounds half up; NaN is 0 and values out of range saturate like a cast. */
+    public static long round(double a) {
+        double floor = floor(a);
+        long result = (long) floor;
+        return a - floor >= 0.5 ? result + 1 : result;
+    }
+
+    public static int round(float a) {
+        double floor = floor(a);
+        int result = (int) floor;
+        return a - floor >= 0.5 ? result + 1 : result;
+    }
+
+    public static int abs(int a) {
+        return a < 0 ? -a : a;
+    }
+
+    public static long abs(long a) {
+        return a < 0 ? -a : a;
+    }
+
+    public static float abs(float a) {
+        return a <= 0.0f ? 0.0f - a : a;
+    }
+
+    public static double abs(double a) {
+        return a <= 0.0d ? 0.0d - a : a;
+    }
+
+    public static int max(int a, int b) {
+        return a >= b ? a : b;
+    }
+
+    public static long max(long a, long b) {
+        return a >= b ? a : b;
+    }
+
+    /** NaN if either value is, and 0.0 is greater than -0.0. */
+    public static float max(float a, float b) {
+        if (a != a) {
+            return a;
+        }
+        if (a == 0.0f && b == 0.0f && Float.floatToRawIntBits(a) == Float.floatToRawIntBits(-0.0f)) {
+            return b;
+        }
+        return a >= b ? a : b;
+    }
+
+    public static double max(double a, double b) {
+        if (a != a) {
+            return a;
+        }
+        if (a == 0.0d && b == 0.0d && Double.doubleToRawLongBits(a) == Double.doubleToRawLongBits(-0.0d)) {
+            return b;
+        }
+        return a >= b ? a : b;
+    }
+
+    public static int min(int a, int b) {
+        return a <= b ? a : b;
+    }
+
+    public static long min(long a, long b) {
+        return a <= b ? a : b;
+    }
+
+    public static float min(float a, float b) {
+        if (a != a) {
+            return a;
+        }
+        if (a == 0.0f && b == 0.0f && Float.floatToRawIntBits(b) == Float.floatToRawIntBits(-0.0f)) {
+            return b;
+        }
+        return a <= b ? a : b;
+    }
+
+    public static double min(double a, double b) {
+        if (a != a) {
+            return a;
+        }
+        if (a == 0.0d && b == 0.0d && Double.doubleToRawLongBits(b) == Double.doubleToRawLongBits(-0.0d)) {
+            return b;
+        }
+        return a <= b ? a : b;
+    }
+
+    public static double signum(double d) {
+        return (d == 0.0 || d != d) ? d : (d > 0.0 ? 1.0 : -1.0);
+    }
+
+    public static float signum(float f) {
+        return (f == 0.0f || f != f) ? f : (f > 0.0f ? 1.0f : -1.0f);
+    }
+
+    public static int floorDiv(int x, int y) {
+        int q = x / y;
+        return (x % y != 0 && (x ^ y) < 0) ? q - 1 : q;
+    }
+
+    public static long floorDiv(long x, long y) {
+        long q = x / y;
+        return (x % y != 0 && (x ^ y) < 0) ? q - 1 : q;
+    }
+
+    public static int floorMod(int x, int y) {
+        int r = x % y;
+        return (r != 0 && (r ^ y) < 0) ? r + y : r;
+    }
+
+    public static long floorMod(long x, long y) {
+        long r = x % y;
+        return (r != 0 && (r ^ y) < 0) ? r + y : r;
+    }
+
+    public static int addExact(int x, int y) {
+        int r = x + y;
+        if (((x ^ r) & (y ^ r)) < 0) {
+            throw new ArithmeticException("integer overflow");
+        }
+        return r;
+    }
+
+    public static long addExact(long x, long y) {
+        long r = x + y;
+        if (((x ^ r) & (y ^ r)) < 0) {
+            throw new ArithmeticException("long overflow");
+        }
+        return r;
+    }
+
+    public static int subtractExact(int x, int y) {
+        int r = x - y;
+        if (((x ^ y) & (x ^ r)) < 0) {
+            throw new ArithmeticException("integer overflow");
+        }
+        return r;
+    }
+
+    public static long subtractExact(long x, long y) {
+        long r = x - y;
+        if (((x ^ y) & (x ^ r)) < 0) {
+            throw new ArithmeticException("long overflow");
+        }
+        return r;
+    }
+
+    public static int multiplyExact(int x, int y) {
+        long r = (long) x * (long) y;
+        if ((int) r != r) {
+            throw new ArithmeticException("integer overflow");
+        }
+        return (int) r;
+    }
+
+    public static long multiplyExact(long x, long y) {
+        long r = x * y;
+        if ((x != 0 && (r / x != y || (x == -1 && y == Long.MIN_VALUE))) || (y == -1 && x == Long.MIN_VALUE)) {
+            throw new ArithmeticException("long overflow");
+        }
+        return r;
+    }
+
+    public static int negateExact(int a) {
+        if (a == Integer.MIN_VALUE) {
+            throw new ArithmeticException("integer overflow");
+        }
+        return -a;
+    }
+
+    public static long negateExact(long a) {
+        if (a == Long.MIN_VALUE) {
+            throw new ArithmeticException("long overflow");
+        }
+        return -a;
+    }
+
+    public static int toIntExact(long value) {
+        if ((int) value != value) {
+            throw new ArithmeticException("integer overflow");
+        }
+        return (int) value;
+    }
+}
@@ -0,0 +1,91 @@
+package java.lang;
+
+public final class Short extends Number implements Comparable<Short> {
+    public static final short MIN_VALUE = -32768;
+    public static final short MAX_VALUE = 32767;
+    public static final int SIZE = 16;
+    public static final int BYTES = 2;
+
+    private static final Short[] CACHE = new Short[256];
+
+    private final short value;
+
+    public Short(short value) {
+        this.value = value;
+    }
+
+    public static Short valueOf(short value) {
+        if (value >= -128 && value <= 127) {
+            Short cached = CACHE[value + 128];
+            if (cached == null) {
+                cached = CACHE[value + 128] = new Short(value);
+            }
+            return cached;
+        }
+        return new Short(value);
+    }
+
+    public static Short valueOf(String s) {
+        return valueOf(parseShort(s, 10));
+    }
+
+    public static short parseShort(String s) {
+        return parseShort(s, 10);
+    }
+
+    public static short parseShort(String s, int radix) {
+        int value = Integer.parseInt(s, radix);
+        if (value < MIN_VALUE || value > MAX_VALUE) {
+            throw new NumberFormatException("Value out of range. Value:\"" + s + "\" Radix:" + radix);
+        }
+        return (short) value;
+    }
+
+    public static String toString(short value) {
+        return Integer.toString(value);
+    }
+
+    public static int compare(short x, short y) {
+        return x - y;
+    }
+
+    public static int hashCode(short value) {
+        return value;
+    }
+
+    public short shortValue() {
+        return value;
+    }
+
+    public int intValue() {
+        return value;
+    }
+
+    public long longValue() {
+        return value;
+    }
+
+    public float floatValue() {
+        return value;
+    }
+
+    public double doubleValue() {
+        return value;
+    }
+
+    public int compareTo(Short other) {
+        return compare(value, other.value);
+    }
+
+    public int hashCode() {
+        return value;
+    }
+
+    public boolean equals(Object obj) {
+        return obj instanceof Short && value == ((Short) obj).value;
+    }
+
+    public String toString() {
+        return Integer.toString(value);
+    }
+}
@@ -0,0 +1,51 @@
+package java.lang;
+
+public final class StackTraceElement {
+    private final String declaringClass;
+    private final String methodName;
+    private final String fileName;
+    private final int lineNumber;
+
+    public StackTraceElement(String declaringClass, String methodName, String fileName, int lineNumber) {
+        this.declaringClass = declaringClass;
+        this.methodName = methodName;
+        this.fileName = fileName;
+        this.lineNumber = lineNumber;
+    }
+
+    public String getClassName() {
+        return declaringClass;
+    }
+
+    public String getMethodName() {
+        return methodName;
+    }
+
+    public String getFileName() {
+        return fileName;
+    }
+
+    public int getLineNumber() {
+        return lineNumber;
+    }
+
+    public boolean isNativeMethod() {
+        return lineNumber == -2;
+    }
+
+    /** {@code Foo.bar(Foo.java:12)}, the way stack traces print frames. */
+    public String toString() {
+        StringBuilder builder = new StringBuilder(declaringClass).append('.').append(methodName).append('(');
+        if (isNativeMethod()) {
+            builder.append("Native Method");
+        } else if (fileName == null) {
+            builder.append("Unknown Source");
+        } else {
+            builder.append(fileName);
+            if (lineNumber >= 0) {
+                builder.append(':').append(lineNumber);
+            }
+        }
+        return builder.append(')').toString();
+    }
+}
@@ -0,0 +1,430 @@
+package java.lang;
+
+/**
+ * Strings have the compact layout of JDK 9 and later: {@code value} holds one byte per character
+ * when every character is Latin-1 ({@link #LATIN1}) and two bytes per UTF-16 code unit, low byte
+ * first, otherwise ({@link #UTF16}). Strings are only stored as UTF-16 when they have to be. The VM
+ * creates instances for literals and keeps the interned ones in its string table.
+ */
+public final class String implements CharSequence, Comparable<String> {
+    static final boolean COMPACT_STRINGS = true;
+    static final byte LATIN1 = 0;
+    static final byte UTF16 = 1;
+
+    private final byte[] value;
+    private final byte coder;
+    private int hash;
+    private boolean hashIsZero;
+
+    public String() {
+        value = new byte[0];
+        coder = LATIN1;
+    }
+
+    public String(char[] value) {
+        this(value, 0, value.length);
+    }
+
+    public String(char[] value, int offset, int count) {
+        if (offset < 0 || count < 0 || offset > value.length - count) {
+            throw new StringIndexOutOfBoundsException(offset < 0 ? offset : count < 0 ? count : offset + count);
+        }
+        byte[] latin1 = new byte[count];
+        int i = 0;
+        while (i < count && value[offset + i] <= 0xff) {
+            latin1[i] = (byte) value[offset + i];
+            i++;
+        }
+        if (i == count) {
+            this.value = latin1;
+            this.coder = LATIN1;
+        } else {
+            byte[] utf16 = new byte[count << 1];
+            for (i = 0; i < count; i++) {
+                putChar(utf16, i, value[offset + i]);
+            }
+            this.value = utf16;
+            this.coder = UTF16;
+        }
+    }
+
+    /** Takes {@code value} over, which has to be compact if it can be. */
+    String(byte[] value, byte coder) {
+        this.value = value;
+        this.coder = coder;
+    }
+
+    static char getChar(byte[] value, int index) {
+        index <<= 1;
+        return (char) ((value[index] & 0xff) | ((value[index + 1] & 0xff) << 8));
+    }
+
+    static void putChar(byte[] value, int index, int c) {
+        index <<= 1;
+        value[index] = (byte) c;
+        value[index + 1] = (byte) (c >> 8);
+    }
+
+    byte coder() {
+        return coder;
+    }
+
+    byte[] value() {
+        return value;
+    }
+
+    boolean isLatin1() {
+        return coder == LATIN1;
+    }
+
+    public int length() {
+        return value.length >> coder;
+    }
+
+    public boolean isEmpty() {
+        return value.length == 0;
+    }
+
+    public char charAt(int index) {
+        if (index < 0 || index >= length()) {
+            throw new StringIndexOutOfBoundsException(index);
+        }
+        return isLatin1() ? (char) (value[index] & 0xff) : getChar(value, index);
+    }
+
+    public void getChars(int srcBegin, int srcEnd, char[] dst, int dstBegin) {
+        checkBounds(srcBegin, srcEnd, length());
+        for (int i = srcBegin; i < srcEnd; i++) {
+            dst[dstBegin++] = charAt(i);
+        }
+    }
+
+    public char[] toCharArray() {
+        char[] chars = new char[length()];
+        getChars(0, chars.length, chars, 0);
+        return chars;
+    }
+
+    /** The UTF-8 encoding, the platform's default charset; unpaired surrogates become {@code '?'}. */
+    public byte[] getBytes() {
+        int length = length();
+        byte[] buffer = new byte[length * 3];
+        int count = 0;
+        for (int i = 0; i < length; i++) {
+            int c = charAt(i);
+            if (Character.isHighSurrogate((char) c) && i + 1 < length && Character.isLowSurrogate(charAt(i + 1))) {
+                c = Character.toCodePoint((char) c, charAt(++i));
+            } else if (Character.isSurrogate((char) c)) {
+                c = '?';
+            }
+            if (c < 0x80) {
+                buffer[count++] = (byte) c;
+            } else if (c < 0x800) {
+                buffer[count++] = (byte) (0xc0 | (c >> 6));
+                buffer[count++] = (byte) (0x80 | (c & 0x3f));
+            } else if (c < 0x10000) {
+                buffer[count++] = (byte) (0xe0 | (c >> 12));
+                buffer[count++] = (byte) (0x80 | ((c >> 6) & 0x3f));
+                buffer[count++] = (byte) (0x80 | (c & 0x3f));
+            } else {
+                buffer[count++] = (byte) (0xf0 | (c >> 18));
+                buffer[count++] = (byte) (0x80 | ((c >> 12) & 0x3f));
+                buffer[count++] = (byte) (0x80 | ((c >> 6) & 0x3f));
+                buffer[count++] = (byte) (0x80 | (c & 0x3f));
+            }
+        }
+        byte[] bytes = new byte[count];
+        System.arraycopy(buffer, 0, bytes, 0, count);
+        return bytes;
+    }
+
+    static void checkBounds(int begin, int end, int length) {
+        if (begin < 0 || begin > end || end > length) {
+            throw new StringIndexOutOfBoundsException("begin " + begin + ", end " + end + ", length " + length);
+        }
+    }
+
+    public String substring(int beginIndex) {
+        return substring(beginIndex, length());
+    }
+
+    public String substring(int beginIndex, int endIndex) {
+        int length = length();
+        checkBounds(beginIndex, endIndex, length);
+        if (beginIndex == 0 && endIndex == length) {
+            return this;
+        }
+        char[] chars = new char[endIndex - beginIndex];
+        getChars(beginIndex, endIndex, chars, 0);
+        return new String(chars);
+    }
+
+    public CharSequence subSequence(int beginIndex, int endIndex) {
+        return substring(beginIndex, endIndex);
+    }
+
+    public String concat(String str) {
+        if (str.isEmpty()) {
+            return this;
+        }
+        char[] chars = new char[length() + str.length()];
+        getChars(0, length(), chars, 0);
+        str.getChars(0, str.length(), chars, length());
+        return new String(chars);
+    }
+
+    public int indexOf(int ch) {
+        return indexOf(ch, 0);
+    }
+
+    public int indexOf(int ch, int fromIndex) {
+        int length = length();
+        for (int i = Math.max(fromIndex, 0); i < length; i++) {
+            if (charAt(i) == ch) {
+                return i;
+            }
+        }
+        return -1;
+    }
+
+    public int lastIndexOf(int ch) {
+        for (int i = length() - 1; i >= 0; i--) {
+            if (charAt(i) == ch) {
+                return i;
+            }
+        }
+        return -1;
+    }
+
+    public int indexOf(String str) {
+        return indexOf(str, 0);
+    }
+
+    public int indexOf(String str, int fromIndex) {
+        int last = length() - str.length();
+        for (int i = Math.max(fromIndex, 0); i <= last; i++) {
+            if (startsWith(str, i)) {
+                return i;
+            }
+        }
+        return -1;
+    }
+
+    public int lastIndexOf(String str) {
+        for (int i = length() - str.length(); i >= 0; i--) {
+            if (startsWith(str, i)) {
+                return i;
+            }
+        }
+        return -1;
+    }
+
+    public boolean contains(CharSequence s) {
+        return indexOf(s.toString()) >= 0;
+    }
+
+    public boolean startsWith(String prefix) {
+        return startsWith(prefix, 0);
+    }
+
+    public boolean startsWith(String prefix, int toffset) {
+        if (toffset < 0 || toffset > length() - prefix.length()) {
+            return false;
+        }
+        for (int i = 0; i < prefix.length(); i++) {
+            if (charAt(toffset + i) != prefix.charAt(i)) {
+                return false;
+            }
+        }
+        return true;
+    }
+
+    public boolean endsWith(String suffix) {
+        return startsWith(suffix, length() - suffix.length());
+    }
+
+    public String replace(char oldChar, char newChar) {
+        if (indexOf(oldChar) < 0) {
+            return this;
+        }
+        char[] chars = toCharArray();
+        for (int i = 0; i < chars.length; i++) {
+            if (chars[i] == oldChar) {
+                chars[i] = newChar;
+            }
+        }
+        return new String(chars);
+    }
+
+    public String replace(CharSequence target, CharSequence replacement) {
+        String from = target.toString();
+        String to = replacement.toString();
+        StringBuilder builder = new StringBuilder();
+        int start = 0;
+        int index;
+        while ((index = indexOf(from, start)) >= 0) {
+            builder.append(this, start, index).append(to);
+            start = index + Math.max(from.length(), 1);
+            if (from.isEmpty()) {
+                if (index >= length()) {
+                    return builder.toString();
+                }
+                builder.append(charAt(index));
+            }
+        }
+        return builder.append(this, start, length()).toString();
+    }
+
+    public String trim() {
+        int begin = 0;
+        int end = length();
+        while (begin < end && charAt(begin) <= ' ') {
+            begin++;
+        }
+        while (end > begin && charAt(end - 1) <= ' ') {
+            end--;
+        }
+        return substring(begin, end);
+    }
+
+    public boolean isBlank() {
+        for (int i = 0; i < length(); i++) {
+            if (!Character.isWhitespace(charAt(i))) {
+                return false;
+            }
+        }
+        return true;
+    }
+
+    public String repeat(int count) {
+        if (count < 0) {
+            throw new IllegalArgumentException("count is negative: " + count);
+        }
+        StringBuilder builder = new StringBuilder(length() * count);
+        for (int i = 0; i < count; i++) {
+            builder.append(this);
+        }
+        return builder.toString();
+    }
+
+    public String toLowerCase() {
+        char[] chars = toCharArray();
+        for (int i = 0; i < chars.length; i++) {
+            chars[i] = Character.toLowerCase(chars[i]);
+        }
+        return new String(chars);
+    }
+
+    public String toUpperCase() {
+        char[] chars = toCharArray();
+        for (int i = 0; i < chars.length; i++) {
+            chars[i] = Character.toUpperCase(chars[i]);
+        }
+        return new String(chars);
+    }
+
+    public int compareTo(String other) {
+        int length = Math.min(length(), other.length());
+        for (int i = 0; i < length; i++) {
+            char a = charAt(i);
+            char b = other.charAt(i);
+            if (a != b) {
+                return a - b;
+            }
+        }
+        return length() - other.length();
+    }
+
+    public boolean equalsIgnoreCase(String other) {
+        if (other == null || other.length() != length()) {
+            return false;
+        }
+        for (int i = 0; i < length(); i++) {
+            char a = charAt(i);
+            char b = other.charAt(i);
+            if (a != b && Character.toUpperCase(a) != Character.toUpperCase(b)
+                    && Character.toLowerCase(a) != Character.toLowerCase(b)) {
+                return false;
+            }
+        }
+        return true;
+    }
+
+    public static String format(String format, Object... args) {
+        return new java.util.Formatter().format(format, args).toString();
+    }
+
+    public static String valueOf(Object obj) {
+        return obj == null ? "null" : obj.toString();
+    }
+
+    public static String valueOf(char[] data) {
+        return new String(data);
+    }
+
+    public static String valueOf(boolean b) {
+        return b ? "true" : "false";
+    }
+
+    public static String valueOf(char c) {
+        return new String(new char[] { c });
+    }
+
+    public static String valueOf(int i) {
+        return Integer.toString(i);
+    }
+
+    public static String valueOf(long l) {
+        return Long.toString(l);
+    }
+
+    public static String valueOf(float f) {
+        return Float.toString(f);
+    }
+
+    public static String valueOf(double d) {
+        return Double.toString(d);
+    }
+
+    public boolean equals(Object other) {
+        if (this == other) {
+            return true;
+        }
+        if (!(other instanceof String)) {
+            return false;
+        }
+        String string = (String) other;
+        byte[] otherValue = string.value;
+        if (string.coder != coder || otherValue.length != value.length) {
+            return false;
+        }
+        for (int i = 0; i < value.length; i++) {
+            if (value[i] != otherValue[i]) {
+                return false;
+            }
+        }
+        return true;
+    }
+
+    public int hashCode() {
+        int h = hash;
+        if (h == 0 && !hashIsZero) {
+            int length = length();
+            for (int i = 0; i < length; i++) {
+                h = 31 * h + (isLatin1() ? value[i] & 0xff : getChar(value, i));
+            }
+            if (h == 0) {
+                hashIsZero = true;
+            } else {
+                hash = h;
+            }
+        }
+        return h;
+    }
+
+    public native String intern();
+
+    public String toString() {
+        return this;
+    }
+}
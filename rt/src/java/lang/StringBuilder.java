@@ -0,0 +1,228 @@
+package java.lang;
+
+/**
+ * A mutable sequence of characters. The characters are kept as UTF-16 code units in a growing
+ * {@code char[]} and compacted by {@link #toString}, which is what string concatenation ends with.
+ */
+public final class StringBuilder implements CharSequence {
+    private char[] value;
+    private int count;
+
+    public StringBuilder() {
+        this(16);
+    }
+
+    public StringBuilder(int capacity) {
+        value = new char[capacity];
+    }
+
+    public StringBuilder(String str) {
+        this(str.length() + 16);
+        append(str);
+    }
+
+    public StringBuilder(CharSequence seq) {
+        this(seq.length() + 16);
+        append(seq);
+    }
+
+    private void ensureCapacity(int minimumCapacity) {
+        if (minimumCapacity > value.length) {
+            char[] grown = new char[Math.max(minimumCapacity, (value.length << 1) + 2)];
+            System.arraycopy(value, 0, grown, 0, count);
+            value = grown;
+        }
+    }
+
+    public int length() {
+        return count;
+    }
+
+    public int capacity() {
+        return value.length;
+    }
+
+    public char charAt(int index) {
+        if (index < 0 || index >= count) {
+            throw new StringIndexOutOfBoundsException("index " + index + ",length " + count);
+        }
+        return value[index];
+    }
+
+    public void setCharAt(int index, char ch) {
+        charAt(index);
+        value[index] = ch;
+    }
+
+    public void setLength(int newLength) {
+        if (newLength < 0) {
+            throw new StringIndexOutOfBoundsException(newLength);
+        }
+        ensureCapacity(newLength);
+        for (int i = count; i < newLength; i++) {
+            value[i] = 0;
+        }
+        count = newLength;
+    }
+
+    public StringBuilder append(Object obj) {
+        return append(String.valueOf(obj));
+    }
+
+    public StringBuilder append(String str) {
+        if (str == null) {
+            return appendNull();
+        }
+        int length = str.length();
+        ensureCapacity(count + length);
+        str.getChars(0, length, value, count);
+        count += length;
+        return this;
+    }
+
+    public StringBuilder append(StringBuilder sb) {
+        if (sb == null) {
+            return appendNull();
+        }
+        ensureCapacity(count + sb.count);
+        System.arraycopy(sb.value, 0, value, count, sb.count);
+        count += sb.count;
+        return this;
+    }
+
+    public StringBuilder append(CharSequence s) {
+        if (s == null) {
+            return appendNull();
+        }
+        return append(s, 0, s.length());
+    }
+
+    public StringBuilder append(CharSequence s, int start, int end) {
+        if (s == null) {
+            s = "null";
+        }
+        String.checkBounds(start, end, s.length());
+        ensureCapacity(count + end - start);
+        for (int i = start; i < end; i++) {
+            value[count++] = s.charAt(i);
+        }
+        return this;
+    }
+
+    public StringBuilder append(char[] str) {
+        ensureCapacity(count + str.length);
+        System.arraycopy(str, 0, value, count, str.length);
+        count += str.length;
+        return this;
+    }
+
+    private StringBuilder appendNull() {
+        return append("null");
+    }
+
+    public StringBuilder append(boolean b) {
+        return append(b ? "true" : "false");
+    }
+
+    public StringBuilder append(char c) {
+        ensureCapacity(count + 1);
+        value[count++] = c;
+        return this;
+    }
+
+    public StringBuilder append(int i) {
+        return append(Integer.toString(i));
+    }
+
+    public StringBuilder append(long l) {
+        return append(Long.toString(l));
+    }
+
+    public StringBuilder append(float f) {
+        return append(Float.toString(f));
+    }
+
+    public StringBuilder append(double d) {
+        return append(Double.toString(d));
+    }
+
+    public StringBuilder insert(int offset, String str) {
+        if (offset < 0 || offset > count) {
+            throw new StringIndexOutOfBoundsException("offset " + offset + ", length " + count);
+        }
+        if (str == null) {
+            str = "null";
+        }
+        int length = str.length();
+        ensureCapacity(count + length);
+        System.arraycopy(value, offset, value, offset + length, count - offset);
+        str.getChars(0, length, value, offset);
+        count += length;
+        return this;
+    }
+
+    public StringBuilder insert(int offset, char c) {
+        return insert(offset, String.valueOf(c));
+    }
+
+    public StringBuilder insert(int offset, Object obj) {
+        return insert(offset, String.valueOf(obj));
+    }
+
+    public StringBuilder delete(int start, int end) {
+        if (end > count) {
+            end = count;
+        }
+        String.checkBounds(start, end, count);
+        System.arraycopy(value, end, value, start, count - end);
+        count -= end - start;
+        return this;
+    }
+
+    public StringBuilder deleteCharAt(int index) {
+        charAt(index);
+        return delete(index, index + 1);
+    }
+
+    /** Reverses the characters, keeping surrogate pairs in order. */
+    public StringBuilder reverse() {
+        for (int i = 0, j = count - 1; i < j; i++, j--) {
+            char c = value[i];
+            value[i] = value[j];
+            value[j] = c;
+        }
+        for (int i = 0; i < count - 1; i++) {
+            if (Character.isLowSurrogate(value[i]) && Character.isHighSurrogate(value[i + 1])) {
+                char c = value[i];
+                value[i] = value[i + 1];
+                value[++i] = c;
+            }
+        }
+        return this;
+    }
+
+    public int indexOf(String str) {
+        return toString().indexOf(str);
+    }
+
+    public int lastIndexOf(String str) {
+        return toString().lastIndexOf(str);
+    }
+
+    public String substring(int start) {
+        return substring(start, count);
+    }
+
+    public String substring(int start, int end) {
+        String.checkBounds(start, end, count);
+        return new String(value, start, end - start);
+    }
+
+    public CharSequence subSequence(int start, int end) {
+        return substring(start, end);
+    }
+
+    public String toString() {
+        return new String(value, 0, count);
+    }
+}
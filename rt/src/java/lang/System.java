@@ -0,0 +1,52 @@
+package java.lang;
+
+import java.io.FileDescriptor;
+import java.io.FileOutputStream;
+import java.io.PrintStream;
+
+/** Access to the VM the program runs in. */
+public final class System {
+    /** Standard output, which the VM may redirect, flushed at every line. */
+    public static final PrintStream out = new PrintStream(new FileOutputStream(FileDescriptor.out), true);
+
+    /** Standard error, where uncaught exceptions are reported. */
+    public static final PrintStream err = new PrintStream(new FileOutputStream(FileDescriptor.err), true);
+
+    private System() {
+    }
+
+    public static native long currentTimeMillis();
+
+    /** A monotonic time in nanoseconds, only meaningful as the difference of two calls. */
+    public static native long nanoTime();
+
+    /**
+     * Copies array elements as if through a temporary array, so the ranges may overlap. Reference
+     * elements are checked one by one, those before a mismatch stay copied.
+     */
+    public static native void arraycopy(Object src, int srcPos, Object dest, int destPos, int length);
+
+    public static native int identityHashCode(Object x);
+
+    /** Runs a full collection. */
+    public static native void gc();
+
+    /** Stops the program with a status, without returning or unwinding. */
+    public static native void exit(int status);
+
+    public static String lineSeparator() {
+        return "\n";
+    }
+
+    /**
+     * Loads the JNI library at an absolute path for the class loader of the calling class. Native
+     * methods of classes defined by that loader are then looked up in the library.
+     */
+    public static native void load(String filename);
+
+    /** Loads a JNI library by name from the directories of {@code java.library.path}. */
+    public static native void loadLibrary(String libname);
+
+    /** The platform's file name of a library, {@code libfoo.so} for {@code foo} on Linux. */
+    public static native String mapLibraryName(String libname);
+}
@@ -0,0 +1,121 @@
+package java.lang;
+
+import java.io.PrintStream;
+
+public class Throwable {
+    private String detailMessage;
+    private Throwable cause = this;
+    private StackTraceElement[] stackTrace;
+
+    public Throwable() {
+        fillInStackTrace();
+    }
+
+    public Throwable(String message) {
+        fillInStackTrace();
+        detailMessage = message;
+    }
+
+    public Throwable(String message, Throwable cause) {
+        fillInStackTrace();
+        detailMessage = message;
+        this.cause = cause;
+    }
+
+    public Throwable(Throwable cause) {
+        fillInStackTrace();
+        detailMessage = (cause == null ? null : cause.toString());
+        this.cause = cause;
+    }
+
+    public String getMessage() {
+        return detailMessage;
+    }
+
+    public String getLocalizedMessage() {
+        return getMessage();
+    }
+
+    public synchronized Throwable getCause() {
+        return (cause == this ? null : cause);
+    }
+
+    public synchronized Throwable initCause(Throwable cause) {
+        if (this.cause != this) {
+            throw new IllegalStateException("Can't overwrite cause with " + cause, this);
+        }
+        if (cause == this) {
+            throw new IllegalArgumentException("Self-causation not permitted", this);
+        }
+        this.cause = cause;
+        return this;
+    }
+
+    public synchronized Throwable fillInStackTrace() {
+        return fillInStackTrace(0);
+    }
+
+    private native Throwable fillInStackTrace(int dummy);
+
+    public StackTraceElement[] getStackTrace() {
+        if (stackTrace == null) {
+            return new StackTraceElement[0];
+        }
+        StackTraceElement[] copy = new StackTraceElement[stackTrace.length];
+        for (int i = 0; i < copy.length; i++) {
+            copy[i] = stackTrace[i];
+        }
+        return copy;
+    }
+
+    public void setStackTrace(StackTraceElement[] stackTrace) {
+        StackTraceElement[] copy = new StackTraceElement[stackTrace.length];
+        for (int i = 0; i < copy.length; i++) {
+            if (stackTrace[i] == null) {
+                throw new NullPointerException("stackTrace[" + i + "]");
+            }
+            copy[i] = stackTrace[i];
+        }
+        this.stackTrace = copy;
+    }
+
+    /** Prints this throwable and its stack trace to {@link System#err}. */
+    public void printStackTrace() {
+        printStackTrace(System.err);
+    }
+
+    /** Prints the stack trace and those of the causes, leaving out frames shared with the enclosing trace. */
+    public void printStackTrace(PrintStream s) {
+        s.println(this);
+        StackTraceElement[] trace = getStackTrace();
+        for (int i = 0; i < trace.length; i++) {
+            s.println("\tat " + trace[i]);
+        }
+        Throwable enclosing = this;
+        StackTraceElement[] enclosingTrace = trace;
+        for (Throwable cause = getCause(); cause != null && cause != enclosing; cause = cause.getCause()) {
+            StackTraceElement[] causeTrace = cause.getStackTrace();
+            int m = causeTrace.length - 1;
+            int n = enclosingTrace.length - 1;
+            while (m >= 0 && n >= 0 && causeTrace[m].toString().equals(enclosingTrace[n].toString())) {
+                m--;
+                n--;
+            }
+            s.println("Caused by: " + cause);
+            for (int i = 0; i <= m; i++) {
+                s.println("\tat " + causeTrace[i]);
+            }
+            if (m < causeTrace.length - 1) {
+                s.println("\t... " + (causeTrace.length - 1 - m) + " more");
+            }
+            enclosing = cause;
+            enclosingTrace = causeTrace;
+        }
+    }
+
+    public String toString() {
+        String s = getClass().getName();
+        String message = getLocalizedMessage();
+        return (message != null) ? (s + ": " + message) : s;
+    }
+}
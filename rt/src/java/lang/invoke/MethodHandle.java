@@ -0,0 +1,21 @@
+package java.lang.invoke;
+
+/** A direct handle to a field or method, as resolved from a {@code CONSTANT_MethodHandle} by the VM. */
+public final class MethodHandle {
+    private final MethodType type;
+    // One of the REF_ kinds of the class file format.
+    private final int referenceKind;
+    private final Class<?> declaringClass;
+    private final String name;
+
+    private MethodHandle(MethodType type, int referenceKind, Class<?> declaringClass, String name) {
+        this.type = type;
+        this.referenceKind = referenceKind;
+        this.declaringClass = declaringClass;
+        this.name = name;
+    }
+
+    public MethodType type() {
+        return type;
+    }
+}
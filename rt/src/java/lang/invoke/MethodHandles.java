@@ -0,0 +1,19 @@
+package java.lang.invoke;
+
+public class MethodHandles {
+    private MethodHandles() {
+    }
+
+    /** The lookup context passed to bootstrap methods. */
+    public static final class Lookup {
+        private final Class<?> lookupClass;
+
+        private Lookup(Class<?> lookupClass) {
+            this.lookupClass = lookupClass;
+        }
+
+        public Class<?> lookupClass() {
+            return lookupClass;
+        }
+    }
+}
@@ -0,0 +1,24 @@
+package java.lang.invoke;
+
+/** A method signature as resolved from a {@code CONSTANT_MethodType}, created by the VM. */
+public final class MethodType {
+    private final Class<?> rtype;
+    private final Class<?>[] ptypes;
+
+    private MethodType(Class<?> rtype, Class<?>[] ptypes) {
+        this.rtype = rtype;
+        this.ptypes = ptypes;
+    }
+
+    public Class<?> returnType() {
+        return rtype;
+    }
+
+    public int parameterCount() {
+        return ptypes.length;
+    }
+
+    public Class<?> parameterType(int index) {
+        return ptypes[index];
+    }
+}
@@ -0,0 +1,8 @@
+package java.lang.ref;
+
+/** The kind of reference the collector keeps the referent of alive until it has been finalized. */
+class FinalReference<T> extends Reference<T> {
+    FinalReference(T referent, ReferenceQueue<? super T> q) {
+        super(referent, q);
+    }
+}
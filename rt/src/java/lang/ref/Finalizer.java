@@ -0,0 +1,51 @@
+package java.lang.ref;
+
+/**
+ * The final references of objects whose class overrides {@code finalize()}. The VM registers
+ * every such object as it is created and, once the collector has queued its reference, takes it
+ * back through {@link #next} to call {@code finalize()} on it.
+ */
+final class Finalizer extends FinalReference<Object> {
+    private static final ReferenceQueue<Object> queue = new ReferenceQueue<>();
+
+    /** Keeps the references of objects not finalized yet reachable. */
+    private static Finalizer unfinalized = null;
+
+    private Finalizer nextUnfinalized;
+    private Finalizer prevUnfinalized;
+
+    private Finalizer(Object finalizee) {
+        super(finalizee, queue);
+        if (unfinalized != null) {
+            this.nextUnfinalized = unfinalized;
+            unfinalized.prevUnfinalized = this;
+        }
+        unfinalized = this;
+    }
+
+    /** Called by the VM when an object of a class that overrides {@code finalize()} is created. */
+    static void register(Object finalizee) {
+        new Finalizer(finalizee);
+    }
+
+    /** Called by the VM to take the next object to finalize, or {@code null} when there is none. */
+    static Object next() {
+        Finalizer f = (Finalizer) queue.poll();
+        if (f == null) {
+            return null;
+        }
+        if (unfinalized == f) {
+            unfinalized = f.nextUnfinalized;
+        }
+        if (f.nextUnfinalized != null) {
+            f.nextUnfinalized.prevUnfinalized = f.prevUnfinalized;
+        }
+        if (f.prevUnfinalized != null) {
+            f.prevUnfinalized.nextUnfinalized = f.nextUnfinalized;
+        }
+        f.nextUnfinalized = f.prevUnfinalized = null;
+        Object finalizee = f.get();
+        f.clear();
+        return finalizee;
+    }
+}
@@ -0,0 +1,50 @@
+package java.lang.ref;
+
+/**
+ * The base of the reference objects the collector treats specially. It clears {@code referent}
+ * when its kind of reachability is lost and then hands the reference to
+ * {@link #processPendingReference} at the next safepoint.
+ */
+public abstract class Reference<T> {
+    private T referent;
+    volatile ReferenceQueue<? super T> queue;
+    /** The next reference in {@link #queue} while it is enqueued. */
+    volatile Reference next;
+
+    Reference(T referent) {
+        this(referent, null);
+    }
+
+    Reference(T referent, ReferenceQueue<? super T> queue) {
+        this.referent = referent;
+        this.queue = (queue == null) ? ReferenceQueue.NULL : queue;
+    }
+
+    public T get() {
+        return referent;
+    }
+
+    public final boolean refersTo(T obj) {
+        return referent == obj;
+    }
+
+    public void clear() {
+        referent = null;
+    }
+
+    public boolean isEnqueued() {
+        return queue == ReferenceQueue.ENQUEUED;
+    }
+
+    public boolean enqueue() {
+        referent = null;
+        return queue.enqueue(this);
+    }
+
+    /** Called by the VM for each reference the collector cleared or found ready to finalize. */
+    @SuppressWarnings({"rawtypes", "unchecked"})
+    private static void processPendingReference(Reference r) {
+        ReferenceQueue q = r.queue;
+        q.enqueue(r);
+    }
+}
@@ -0,0 +1,62 @@
+package java.lang.ref;
+
+/**
+ * The queue the collector appends cleared references to. The VM runs a single Java thread, so
+ * {@link #remove} never blocks and returns what {@link #poll} would.
+ */
+public class ReferenceQueue<T> {
+    private static class Null extends ReferenceQueue<Object> {
+        @Override
+        boolean enqueue(Reference<?> r) {
+            return false;
+        }
+    }
+
+    static final ReferenceQueue<Object> NULL = new Null();
+    static final ReferenceQueue<Object> ENQUEUED = new Null();
+
+    /** The most recently enqueued reference; the last one's {@code next} is itself. */
+    private Reference<? extends T> head;
+    private long queueLength;
+
+    public ReferenceQueue() {
+    }
+
+    @SuppressWarnings("unchecked")
+    boolean enqueue(Reference<? extends T> r) {
+        ReferenceQueue<?> queue = r.queue;
+        if (queue == NULL || queue == ENQUEUED) {
+            return false;
+        }
+        r.next = (head == null) ? r : head;
+        head = r;
+        r.queue = ENQUEUED;
+        queueLength++;
+        return true;
+    }
+
+    @SuppressWarnings("unchecked")
+    public Reference<? extends T> poll() {
+        Reference<? extends T> r = head;
+        if (r == null) {
+            return null;
+        }
+        Reference<? extends T> next = r.next;
+        head = (next == r) ? null : next;
+        r.queue = NULL;
+        r.next = r;
+        queueLength--;
+        return r;
+    }
+
+    public Reference<? extends T> remove(long timeout) throws InterruptedException {
+        if (timeout < 0) {
+            throw new IllegalArgumentException("Negative timeout value");
+        }
+        return poll();
+    }
+
+    public Reference<? extends T> remove() throws InterruptedException {
+        return remove(0);
+    }
+}
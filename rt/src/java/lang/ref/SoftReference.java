@@ -0,0 +1,12 @@
+package java.lang.ref;
+
+/** Cleared only when the heap is exhausted otherwise, before an {@link OutOfMemoryError}. */
+public class SoftReference<T> extends Reference<T> {
+    public SoftReference(T referent) {
+        super(referent);
+    }
+
+    public SoftReference(T referent, ReferenceQueue<? super T> q) {
+        super(referent, q);
+    }
+}
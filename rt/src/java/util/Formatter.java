@@ -0,0 +1,266 @@
+package java.util;
+
+/**
+ * The format strings of {@link String#format}: {@code %[index$][flags][width][.precision]conversion}
+ * with the conversions {@code b h s c d o x e f n %}, and {@code B H S C X E} for upper case. The
+ * flags are {@code -}, {@code #}, {@code +}, space, {@code 0}, {@code ,} and {@code (}; an index of
+ * {@code <} reuses the previous argument. Locales and {@code Formattable} are not supported.
+ */
+public final class Formatter {
+    private static final String FLAGS = "-#+ 0,(";
+
+    private final StringBuilder out = new StringBuilder();
+
+    public Formatter() {
+    }
+
+    public Formatter format(String format, Object... args) {
+        int length = format.length();
+        int ordinary = 0;
+        int previous = -1;
+        int i = 0;
+        while (i < length) {
+            char c = format.charAt(i);
+            if (c != '%') {
+                out.append(c);
+                i++;
+                continue;
+            }
+            int start = i++;
+            int index = -1;
+            boolean relative = false;
+            if (i < length && format.charAt(i) == '<') {
+                relative = true;
+                i++;
+            } else {
+                int end = skipDigits(format, i);
+                if (end > i && end < length && format.charAt(end) == '$') {
+                    index = Integer.parseInt(format.substring(i, end)) - 1;
+                    i = end + 1;
+                }
+            }
+            int flagsStart = i;
+            while (i < length && FLAGS.indexOf(format.charAt(i)) >= 0) {
+                i++;
+            }
+            String flags = format.substring(flagsStart, i);
+            int width = -1;
+            int end = skipDigits(format, i);
+            if (end > i) {
+                width = Integer.parseInt(format.substring(i, end));
+                i = end;
+            }
+            int precision = -1;
+            if (i < length && format.charAt(i) == '.') {
+                end = skipDigits(format, i + 1);
+                if (end == i + 1) {
+                    throw new UnknownFormatConversionException(".");
+                }
+                precision = Integer.parseInt(format.substring(i + 1, end));
+                i = end;
+            }
+            if (i >= length) {
+                throw new UnknownFormatConversionException("%");
+            }
+            char conversion = format.charAt(i++);
+            if (conversion == 'n') {
+                out.append('\n');
+                continue;
+            }
+            if (conversion == '%') {
+                pad("%", flags, width, false);
+                continue;
+            }
+            if (relative) {
+                index = previous;
+            } else if (index < 0) {
+                index = ordinary++;
+            }
+            if (index < 0 || args == null || index >= args.length) {
+                throw new MissingFormatArgumentException(format.substring(start, i));
+            }
+            previous = index;
+            String text = convert(Character.toLowerCase(conversion), args[index], flags, precision);
+            boolean numeric = args[index] != null && "doxef".indexOf(Character.toLowerCase(conversion)) >= 0
+                    && !text.equals("NaN") && !text.endsWith("Infinity");
+            pad(Character.isUpperCase(conversion) ? text.toUpperCase() : text, flags, width, numeric);
+        }
+        return this;
+    }
+
+    private static int skipDigits(String s, int i) {
+        while (i < s.length() && Character.isDigit(s.charAt(i))) {
+            i++;
+        }
+        return i;
+    }
+
+    private void pad(String text, String flags, int width, boolean numeric) {
+        boolean left = flags.indexOf('-') >= 0;
+        if (numeric && flags.indexOf('0') >= 0 && !left) {
+            // The zeros go after the sign and radix prefix.
+            int prefix = text.length() > 0 && "-+ (".indexOf(text.charAt(0)) >= 0 ? 1 : 0;
+            if (text.startsWith("0x", prefix) || text.startsWith("0X", prefix)) {
+                prefix += 2;
+            }
+            out.append(text, 0, prefix);
+            for (int i = text.length(); i < width; i++) {
+                out.append('0');
+            }
+            out.append(text, prefix, text.length());
+            return;
+        }
+        if (left) {
+            out.append(text);
+        }
+        for (int i = text.length(); i < width; i++) {
+            out.append(' ');
+        }
+        if (!left) {
+            out.append(text);
+        }
+    }
+
+    private static String convert(char conversion, Object arg, String flags, int precision) {
+        String text;
+        switch (conversion) {
+            case 'b':
+                text = String.valueOf(arg instanceof Boolean ? arg : Boolean.valueOf(arg != null));
+                break;
+            case 'h':
+                text = arg == null ? "null" : Integer.toHexString(arg.hashCode());
+                break;
+            case 's':
+                text = String.valueOf(arg);
+                break;
+            case 'c':
+                return arg == null ? "null" : character(arg);
+            case 'd':
+            case 'o':
+            case 'x':
+                return arg == null ? "null" : integral(conversion, arg, flags);
+            case 'e':
+            case 'f':
+                return arg == null ? "null" : floating(conversion, arg, flags, precision);
+            default:
+                throw new UnknownFormatConversionException(String.valueOf(conversion));
+        }
+        return precision >= 0 && precision < text.length() ? text.substring(0, precision) : text;
+    }
+
+    private static String character(Object arg) {
+        if (arg instanceof Character) {
+            return arg.toString();
+        }
+        int codePoint;
+        if (arg instanceof Byte || arg instanceof Short || arg instanceof Integer) {
+            codePoint = ((Number) arg).intValue();
+        } else {
+            throw new IllegalFormatConversionException('c', arg.getClass());
+        }
+        if (codePoint >= 0x10000) {
+            return new String(new char[] { (char) ((codePoint >>> 10) + 0xd7c0), (char) ((codePoint & 0x3ff) + 0xdc00) });
+        }
+        return String.valueOf((char) codePoint);
+    }
+
+    private static String integral(char conversion, Object arg, String flags) {
+        long value;
+        int bits;
+        if (arg instanceof Integer) {
+            value = (Integer) arg;
+            bits = 32;
+        } else if (arg instanceof Long) {
+            value = (Long) arg;
+            bits = 64;
+        } else if (arg instanceof Short) {
+            value = (Short) arg;
+            bits = 16;
+        } else if (arg instanceof Byte) {
+            value = (Byte) arg;
+            bits = 8;
+        } else {
+            throw new IllegalFormatConversionException(conversion, arg.getClass());
+        }
+        if (conversion == 'd') {
+            String digits = value < 0 ? Long.toString(value).substring(1) : Long.toString(value);
+            return signed(value < 0, digits, flags);
+        }
+        // Octal and hexadecimal show negative values as unsigned ones of the argument's size.
+        if (value < 0 && bits < 64) {
+            value += 1L << bits;
+        }
+        String digits = conversion == 'o' ? Long.toOctalString(value) : Long.toHexString(value);
+        return flags.indexOf('#') < 0 ? digits : (conversion == 'o' ? "0" : "0x") + digits;
+    }
+
+    private static String floating(char conversion, Object arg, String flags, int precision) {
+        if (precision < 0) {
+            precision = 6;
+        }
+        boolean negative;
+        String digits;
+        if (arg instanceof Double) {
+            double value = (Double) arg;
+            if (Double.isNaN(value)) {
+                return "NaN";
+            }
+            negative = Double.doubleToRawLongBits(value) < 0;
+            digits = Double.isInfinite(value) ? "Infinity" : formatFloating(Math.abs(value), conversion == 'e', precision);
+        } else if (arg instanceof Float) {
+            float value = (Float) arg;
+            if (Float.isNaN(value)) {
+                return "NaN";
+            }
+            negative = Float.floatToRawIntBits(value) < 0;
+            digits = Float.isInfinite(value) ? "Infinity" : formatFloating(Math.abs(value), conversion == 'e', precision);
+        } else {
+            throw new IllegalFormatConversionException(conversion, arg.getClass());
+        }
+        if (conversion == 'e' || digits.equals("Infinity")) {
+            return signed(negative, digits, flags.replace(",", ""));
+        }
+        return signed(negative, digits, flags);
+    }
+
+    /**
+     * Digits of a non-negative finite value rounded half up to {@code precision} decimals, in the
+     * form of {@code %e} when {@code scientific} and of {@code %f} otherwise.
+     */
+    private static native String formatFloating(double value, boolean scientific, int precision);
+
+    private static native String formatFloating(float value, boolean scientific, int precision);
+
+    /** Adds the sign and grouping the flags ask for to the digits of a number. */
+    private static String signed(boolean negative, String digits, String flags) {
+        StringBuilder builder = new StringBuilder();
+        if (negative) {
+            builder.append(flags.indexOf('(') >= 0 ? '(' : '-');
+        } else if (flags.indexOf('+') >= 0) {
+            builder.append('+');
+        } else if (flags.indexOf(' ') >= 0) {
+            builder.append(' ');
+        }
+        if (flags.indexOf(',') >= 0) {
+            int point = digits.indexOf('.');
+            int integer = point < 0 ? digits.length() : point;
+            for (int i = 0; i < integer; i++) {
+                if (i > 0 && (integer - i) % 3 == 0) {
+                    builder.append(',');
+                }
+                builder.append(digits.charAt(i));
+            }
+            builder.append(digits, integer, digits.length());
+        } else {
+            builder.append(digits);
+        }
+        if (negative && flags.indexOf('(') >= 0) {
+            builder.append(')');
+        }
+        return builder.toString();
+    }
+
+    public String toString() {
+        return out.toString();
+    }
+}
@@ -0,0 +1,17 @@
+package java.util;
+
+public class MissingFormatArgumentException extends IllegalFormatException {
+    private final String s;
+
+    public MissingFormatArgumentException(String s) {
+        super("Format specifier '" + s + "'");
+        if (s == null) {
+            throw new NullPointerException();
+        }
+        this.s = s;
+    }
+
+    public String getFormatSpecifier() {
+        return s;
+    }
+}
@@ -0,0 +1,17 @@
+package java.util;
+
+public class UnknownFormatConversionException extends IllegalFormatException {
+    private final String s;
+
+    public UnknownFormatConversionException(String s) {
+        super("Conversion = '" + s + "'");
+        if (s == null) {
+            throw new NullPointerException();
+        }
+        this.s = s;
+    }
+
+    public String getConversion() {
+        return s;
+    }
+}
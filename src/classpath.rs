@@ -0,0 +1,362 @@
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{Cursor, Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use byteorder::{LittleEndian, ReadBytesExt};
+use flate2::read::DeflateDecoder;
+use crate::jimage::Image;
+use crate::loader::{Class, ClassLoadError, Loader};
+use crate::manifest::{Manifest, MANIFEST_NAME};
+use crate::rt;
+
+const END_OF_CENTRAL_DIRECTORY: u32 = 0x06054b50;
+const CENTRAL_DIRECTORY_HEADER: u32 = 0x02014b50;
+const LOCAL_FILE_HEADER: u32 = 0x04034b50;
+
+/// Versioned entries only exist for releases that understand multi-release jars.
+const FIRST_VERSIONED_RELEASE: u16 = 9;
+
+/// The Java release emulated by default: the newest one whose class files the loader accepts.
+pub const DEFAULT_RELEASE: u16 = 16;
+
+const METHOD_STORED: u16 = 0;
+const METHOD_DEFLATED: u16 = 8;
+
+/// A file as described by the zip central directory.
+#[derive(Debug, Clone)]
+pub struct ArchiveEntry {
+    pub name: String,
+    pub method: u16,
+    pub crc32: u32,
+    pub compressed_size: u32,
+    pub size: u32,
+    local_header_offset: u32
+}
+
+/// A `.jar` / `.zip` file. The central directory is read once when the archive is opened,
+/// entry data is read from disk on every lookup.
+#[derive(Debug)]
+pub struct Archive {
+    path: PathBuf,
+    entries: HashMap<String, ArchiveEntry>,
+    manifest: Option<Manifest>,
+    /// Java release whose `META-INF/versions/N/` entries are visible, for multi-release jars only.
+    release: Option<u16>
+}
+
+impl Archive {
+    /// Opens an archive, reading multi-release jars as [`DEFAULT_RELEASE`].
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Archive, ClassLoadError> {
+        Archive::open_for_release(path, DEFAULT_RELEASE)
+    }
+
+    pub fn open_for_release<P: AsRef<Path>>(path: P, release: u16) -> Result<Archive, ClassLoadError> {
+        let mut archive = Archive::open_unversioned(path)?;
+        archive.manifest = archive.read(MANIFEST_NAME)?.map(|data| Manifest::parse(&data)).transpose()?;
+        if archive.manifest.as_ref().map(Manifest::is_multi_release).unwrap_or(false) {
+            archive.release = Some(release);
+        }
+        Ok(archive)
+    }
+
+    fn open_unversioned<P: AsRef<Path>>(path: P) -> Result<Archive, ClassLoadError> {
+        let path = path.as_ref().to_path_buf();
+        let mut file = File::open(&path)?;
+        let entries = Archive::read_central_directory(&mut file)
+            .map_err(|e| match e {
+                ClassLoadError::ClassFileReadFailure(e) => ClassLoadError::InvalidArchive(path.display().to_string(), e.to_string()),
+                e => e
+            })?
+            .into_iter()
+            .map(|entry| (entry.name.clone(), entry))
+            .collect();
+        Ok(Archive { path, entries, manifest: None, release: None })
+    }
+
+    fn invalid(&self, reason: &str) -> ClassLoadError {
+        ClassLoadError::InvalidArchive(self.path.display().to_string(), reason.to_string())
+    }
+
+    /// Locates the end of central directory record, which is followed by at most 64k of comment.
+    fn find_end_of_central_directory(file: &mut File) -> Result<Vec<u8>, ClassLoadError> {
+        let length = file.seek(SeekFrom::End(0))?;
+        let tail_length = length.min(22 + 0xffff);
+        file.seek(SeekFrom::Start(length - tail_length))?;
+        let mut tail = vec![0u8; tail_length as usize];
+        file.read_exact(&mut tail)?;
+        let start = (0..tail.len().saturating_sub(21)).rev()
+            .find(|i| tail[*i..*i + 4] == END_OF_CENTRAL_DIRECTORY.to_le_bytes())
+            .ok_or_else(|| std::io::Error::new(std::io::ErrorKind::InvalidData, "end of central directory not found"))?;
+        Ok(tail[start..start + 22].to_vec())
+    }
+
+    fn read_central_directory(file: &mut File) -> Result<Vec<ArchiveEntry>, ClassLoadError> {
+        let record = Archive::find_end_of_central_directory(file)?;
+        let mut cursor = Cursor::new(&record[10..]);
+        let entry_count = cursor.read_u16::<LittleEndian>()?;
+        let directory_size = cursor.read_u32::<LittleEndian>()?;
+        let directory_offset = cursor.read_u32::<LittleEndian>()?;
+        if entry_count == 0xffff || directory_offset == 0xffffffff {
+            return Err(std::io::Error::new(std::io::ErrorKind::InvalidData, "zip64 archives are not supported").into())
+        }
+
+        file.seek(SeekFrom::Start(directory_offset as u64))?;
+        let mut directory = vec![0u8; directory_size as usize];
+        file.read_exact(&mut directory)?;
+        let mut cursor = Cursor::new(&directory[..]);
+        let mut entries: Vec<ArchiveEntry> = Vec::with_capacity(entry_count as usize);
+        for _ in 0..entry_count {
+            if cursor.read_u32::<LittleEndian>()? != CENTRAL_DIRECTORY_HEADER {
+                return Err(std::io::Error::new(std::io::ErrorKind::InvalidData, "bad central directory header").into())
+            }
+            cursor.seek(SeekFrom::Current(6))?;
+            let method = cursor.read_u16::<LittleEndian>()?;
+            cursor.seek(SeekFrom::Current(4))?;
+            let crc32 = cursor.read_u32::<LittleEndian>()?;
+            let compressed_size = cursor.read_u32::<LittleEndian>()?;
+            let size = cursor.read_u32::<LittleEndian>()?;
+            let name_length = cursor.read_u16::<LittleEndian>()?;
+            let extra_length = cursor.read_u16::<LittleEndian>()?;
+            let comment_length = cursor.read_u16::<LittleEndian>()?;
+            cursor.seek(SeekFrom::Current(8))?;
+            let local_header_offset = cursor.read_u32::<LittleEndian>()?;
+            let mut name = vec![0u8; name_length as usize];
+            cursor.read_exact(&mut name)?;
+            cursor.seek(SeekFrom::Current(extra_length as i64 + comment_length as i64))?;
+            entries.push(ArchiveEntry {
+                name: String::from_utf8_lossy(&name).into_owned(),
+                method,
+                crc32,
+                compressed_size,
+                size,
+                local_header_offset
+            });
+        }
+        Ok(entries)
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn manifest(&self) -> Option<&Manifest> {
+        self.manifest.as_ref()
+    }
+
+    pub fn release(&self) -> Option<u16> {
+        self.release
+    }
+
+    /// The entry a lookup of `name` reads: the newest `META-INF/versions/N/name` not above the
+    /// release for multi-release jars, the base entry otherwise.
+    pub fn entry(&self, name: &str) -> Option<&ArchiveEntry> {
+        if let Some(release) = self.release.filter(|_| !name.starts_with("META-INF/")) {
+            for version in (FIRST_VERSIONED_RELEASE..=release).rev() {
+                if let Some(entry) = self.entries.get(&format!("META-INF/versions/{}/{}", version, name)) {
+                    return Some(entry)
+                }
+            }
+        }
+        self.entries.get(name)
+    }
+
+    pub fn entries(&self) -> impl Iterator<Item = &ArchiveEntry> {
+        self.entries.values()
+    }
+
+    /// Reads and decompresses an entry, verifying its CRC.
+    pub fn read(&self, name: &str) -> Result<Option<Vec<u8>>, ClassLoadError> {
+        let entry = match self.entry(name) {
+            Some(entry) => entry,
+            None => return Ok(None)
+        };
+        let mut file = File::open(&self.path)?;
+        file.seek(SeekFrom::Start(entry.local_header_offset as u64))?;
+        if file.read_u32::<LittleEndian>()? != LOCAL_FILE_HEADER {
+            return Err(self.invalid(&format!("bad local header for {}", name)))
+        }
+        file.seek(SeekFrom::Current(22))?;
+        let name_length = file.read_u16::<LittleEndian>()?;
+        let extra_length = file.read_u16::<LittleEndian>()?;
+        file.seek(SeekFrom::Current(name_length as i64 + extra_length as i64))?;
+
+        let compressed = file.take(entry.compressed_size as u64);
+        let mut data: Vec<u8> = Vec::with_capacity(entry.size as usize);
+        match entry.method {
+            METHOD_STORED => compressed.take(entry.size as u64).read_to_end(&mut data)?,
+            METHOD_DEFLATED => DeflateDecoder::new(compressed).read_to_end(&mut data)?,
+            method => return Err(self.invalid(&format!("unsupported compression method {} for {}", method, name)))
+        };
+        let mut crc = flate2::Crc::new();
+        crc.update(&data);
+        if data.len() != entry.size as usize || crc.sum() != entry.crc32 {
+            return Err(self.invalid(&format!("corrupt entry {}", name)))
+        }
+        Ok(Some(data))
+    }
+}
+
+#[derive(Debug)]
+pub enum ClassPathEntry {
+    Directory(PathBuf),
+    Archive(Archive),
+    /// The module image of a JDK, see [`ClassPath::java_home`].
+    Image(Image),
+    /// The classes bundled into the binary, see [`crate::rt`].
+    Runtime
+}
+
+impl ClassPathEntry {
+    /// Opens a directory or, for anything else, a jar / zip archive.
+    pub fn open<P: AsRef<Path>>(path: P, release: u16) -> Result<ClassPathEntry, ClassLoadError> {
+        let path = path.as_ref();
+        if path.is_dir() {
+            Ok(ClassPathEntry::Directory(path.to_path_buf()))
+        } else {
+            Ok(ClassPathEntry::Archive(Archive::open_for_release(path, release)?))
+        }
+    }
+
+    /// Reads a resource by its `/` separated name, e.g. `com/foo/Bar.class`.
+    pub fn read(&self, name: &str) -> Result<Option<Vec<u8>>, ClassLoadError> {
+        match self {
+            ClassPathEntry::Directory(root) => {
+                let path = name.split('/').fold(root.clone(), |path, part| path.join(part));
+                match std::fs::read(path) {
+                    Ok(data) => Ok(Some(data)),
+                    Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+                    Err(e) => Err(e.into())
+                }
+            }
+            ClassPathEntry::Archive(archive) => archive.read(name),
+            ClassPathEntry::Image(image) => image.read_resource(name),
+            ClassPathEntry::Runtime => Ok(name.strip_suffix(".class").and_then(rt::find_class).map(<[u8]>::to_vec))
+        }
+    }
+
+    pub fn path(&self) -> &Path {
+        match self {
+            ClassPathEntry::Directory(path) => path,
+            ClassPathEntry::Archive(archive) => archive.path(),
+            ClassPathEntry::Image(image) => image.path(),
+            ClassPathEntry::Runtime => Path::new("<runtime>")
+        }
+    }
+}
+
+/// An ordered list of directories and archives searched for classes, like `-cp`.
+#[derive(Debug)]
+pub struct ClassPath {
+    entries: Vec<ClassPathEntry>,
+    release: u16
+}
+
+impl Default for ClassPath {
+    fn default() -> Self {
+        ClassPath::for_release(DEFAULT_RELEASE)
+    }
+}
+
+#[cfg(windows)]
+pub const SEPARATOR: char = ';';
+#[cfg(not(windows))]
+pub const SEPARATOR: char = ':';
+
+impl ClassPath {
+    pub fn new() -> Self {
+        ClassPath::default()
+    }
+
+    /// A class path whose multi-release jars are read as the given Java release.
+    pub fn for_release(release: u16) -> Self {
+        ClassPath { entries: Vec::new(), release }
+    }
+
+    /// The class path of `java -jar`: the jar itself followed by its manifest `Class-Path`,
+    /// resolved against the jar's directory. Entries that do not exist are skipped.
+    pub fn for_jar<P: AsRef<Path>>(jar: P, release: u16) -> Result<ClassPath, ClassLoadError> {
+        let jar = jar.as_ref();
+        let mut ret = ClassPath::for_release(release);
+        ret.push(jar)?;
+        let base = jar.parent().unwrap_or_else(|| Path::new(""));
+        let dependencies = ret.manifest().map(Manifest::class_path).unwrap_or_default();
+        for dependency in dependencies {
+            let path = base.join(&dependency);
+            if path.exists() {
+                ret.push(path)?;
+            }
+        }
+        Ok(ret)
+    }
+
+    /// The manifest of the first archive, which is the launched jar for [`for_jar`](Self::for_jar).
+    pub fn manifest(&self) -> Option<&Manifest> {
+        match self.entries.first() {
+            Some(ClassPathEntry::Archive(archive)) => archive.manifest(),
+            _ => None
+        }
+    }
+
+    pub fn release(&self) -> u16 {
+        self.release
+    }
+
+    /// Parses a class path string such as `lib/a.jar:classes`.
+    pub fn parse(class_path: &str) -> Result<ClassPath, ClassLoadError> {
+        let mut ret = ClassPath::new();
+        for path in class_path.split(SEPARATOR).filter(|path| !path.is_empty()) {
+            ret.push(path)?;
+        }
+        Ok(ret)
+    }
+
+    /// The default bootstrap class path: just the bundled runtime.
+    pub fn runtime() -> Self {
+        let mut ret = ClassPath::new();
+        ret.entries.push(ClassPathEntry::Runtime);
+        ret
+    }
+
+    /// The bootstrap class path of a java home: the module image `lib/modules`, which holds the
+    /// classes of every module in place of the bundled runtime.
+    pub fn java_home<P: AsRef<Path>>(java_home: P) -> Result<Self, ClassLoadError> {
+        let image = Image::open(java_home.as_ref().join("lib").join("modules"))?;
+        if image.read_resource("java/lang/Object.class")?.is_none() {
+            return Err(ClassLoadError::InvalidImage(image.path().display().to_string(), "no java/lang/Object in java.base".to_string()))
+        }
+        let mut ret = ClassPath::new();
+        ret.entries.push(ClassPathEntry::Image(image));
+        Ok(ret)
+    }
+
+    pub fn push<P: AsRef<Path>>(&mut self, path: P) -> Result<(), ClassLoadError> {
+        self.entries.push(ClassPathEntry::open(path, self.release)?);
+        Ok(())
+    }
+
+    pub fn entries(&self) -> &[ClassPathEntry] {
+        &self.entries
+    }
+
+    /// Reads the first resource with the given name, in class path order.
+    pub fn find_resource(&self, name: &str) -> Result<Option<Vec<u8>>, ClassLoadError> {
+        for entry in &self.entries {
+            if let Some(data) = entry.read(name)? {
+                return Ok(Some(data))
+            }
+        }
+        Ok(None)
+    }
+
+    /// Reads the class file for a binary name such as `com/foo/Bar`.
+    pub fn find_class_bytes(&self, binary_name: &str) -> Result<Option<Vec<u8>>, ClassLoadError> {
+        self.find_resource(&format!("{}.class", binary_name))
+    }
+
+    pub fn load_class(&self, loader: &Loader, binary_name: &str) -> Result<Class, ClassLoadError> {
+        match self.find_class_bytes(binary_name)? {
+            Some(data) => loader.load_from_bytes(&data),
+            None => Err(ClassLoadError::ClassNotFound(binary_name.to_string()))
+        }
+    }
+}
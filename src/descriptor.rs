@@ -0,0 +1,128 @@
+use crate::loader::ClassLoadError;
+
+/// A field type as encoded in descriptors (JVMS 4.3.2).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FieldType {
+    Byte,
+    Char,
+    Double,
+    Float,
+    Int,
+    Long,
+    Short,
+    Boolean,
+    Object(String),
+    Array(Box<FieldType>)
+}
+
+impl FieldType {
+    pub fn parse(descriptor: &str) -> Result<FieldType, ClassLoadError> {
+        let (field_type, rest) = FieldType::parse_prefix(descriptor)?;
+        if !rest.is_empty() {
+            return Err(ClassLoadError::InvalidDescriptor(descriptor.to_string()))
+        }
+        Ok(field_type)
+    }
+
+    fn parse_prefix(descriptor: &str) -> Result<(FieldType, &str), ClassLoadError> {
+        let invalid = || ClassLoadError::InvalidDescriptor(descriptor.to_string());
+        let first = descriptor.chars().next().ok_or_else(invalid)?;
+        let rest = &descriptor[1..];
+        Ok(match first {
+            'B' => (FieldType::Byte, rest),
+            'C' => (FieldType::Char, rest),
+            'D' => (FieldType::Double, rest),
+            'F' => (FieldType::Float, rest),
+            'I' => (FieldType::Int, rest),
+            'J' => (FieldType::Long, rest),
+            'S' => (FieldType::Short, rest),
+            'Z' => (FieldType::Boolean, rest),
+            'L' => {
+                let end = rest.find(';').ok_or_else(invalid)?;
+                (FieldType::Object(rest[..end].to_string()), &rest[end + 1..])
+            }
+            '[' => {
+                let (component, rest) = FieldType::parse_prefix(rest)?;
+                (FieldType::Array(Box::new(component)), rest)
+            }
+            _ => return Err(invalid())
+        })
+    }
+
+    /// Number of local variable / operand stack slots taken by a value of this type.
+    pub fn slots(&self) -> usize {
+        match self {
+            FieldType::Long | FieldType::Double => 2,
+            _ => 1
+        }
+    }
+
+    pub fn is_reference(&self) -> bool {
+        matches!(self, FieldType::Object(_) | FieldType::Array(_))
+    }
+
+    /// The field descriptor of the type, e.g. `[Ljava/lang/String;`.
+    pub fn descriptor(&self) -> String {
+        match self {
+            FieldType::Byte => "B".to_string(),
+            FieldType::Char => "C".to_string(),
+            FieldType::Double => "D".to_string(),
+            FieldType::Float => "F".to_string(),
+            FieldType::Int => "I".to_string(),
+            FieldType::Long => "J".to_string(),
+            FieldType::Short => "S".to_string(),
+            FieldType::Boolean => "Z".to_string(),
+            FieldType::Object(name) => format!("L{};", name),
+            FieldType::Array(component) => format!("[{}", component.descriptor())
+        }
+    }
+
+    /// The type as written in Java source, e.g. `java.lang.String[]`.
+    pub fn java_name(&self) -> String {
+        match self {
+            FieldType::Byte => "byte".to_string(),
+            FieldType::Char => "char".to_string(),
+            FieldType::Double => "double".to_string(),
+            FieldType::Float => "float".to_string(),
+            FieldType::Int => "int".to_string(),
+            FieldType::Long => "long".to_string(),
+            FieldType::Short => "short".to_string(),
+            FieldType::Boolean => "boolean".to_string(),
+            FieldType::Object(name) => name.replace('/', "."),
+            FieldType::Array(component) => format!("{}[]", component.java_name())
+        }
+    }
+}
+
+/// A method descriptor (JVMS 4.3.3); `return_type` is `None` for `void`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MethodDescriptor {
+    pub parameters: Vec<FieldType>,
+    pub return_type: Option<FieldType>
+}
+
+impl MethodDescriptor {
+    pub fn parse(descriptor: &str) -> Result<MethodDescriptor, ClassLoadError> {
+        let invalid = || ClassLoadError::InvalidDescriptor(descriptor.to_string());
+        let mut rest = descriptor.strip_prefix('(').ok_or_else(invalid)?;
+        let mut parameters: Vec<FieldType> = Vec::new();
+        while !rest.starts_with(')') {
+            let (parameter, next) = FieldType::parse_prefix(rest).map_err(|_| invalid())?;
+            parameters.push(parameter);
+            rest = next;
+        }
+        let return_type = match &rest[1..] {
+            "V" => None,
+            other => Some(FieldType::parse(other).map_err(|_| invalid())?)
+        };
+        Ok(MethodDescriptor {
+            parameters,
+            return_type
+        })
+    }
+
+    /// Number of local variable slots taken by the parameters, not counting `this`.
+    pub fn parameter_slots(&self) -> usize {
+        self.parameters.iter().map(FieldType::slots).sum()
+    }
+}
@@ -0,0 +1,669 @@
+use std::io::{Result, Write};
+use crate::descriptor::{FieldType, MethodDescriptor};
+use crate::interpret::{mnemonic, Interpreter, Opcode};
+use crate::loader::{AccessFlags, Annotation, Attribute, AttributeValue, Class, ClassFileConstantPool, ConstantPoolTag, ElementValue, Field, Method, ParameterAnnotation, StackMapFrame, VerificationType};
+
+/// Column at which `javap` starts the `//` comment of an instruction.
+const COMMENT_COLUMN: usize = 34;
+
+pub fn method_handle_kind(kind: u8) -> &'static str {
+    match kind {
+        1 => "REF_getField",
+        2 => "REF_getStatic",
+        3 => "REF_putField",
+        4 => "REF_putStatic",
+        5 => "REF_invokeVirtual",
+        6 => "REF_invokeStatic",
+        7 => "REF_invokeSpecial",
+        8 => "REF_newInvokeSpecial",
+        9 => "REF_invokeInterface",
+        _ => "REF_unknown"
+    }
+}
+
+fn array_type_name(atype: u8) -> &'static str {
+    match atype {
+        4 => "boolean",
+        5 => "char",
+        6 => "float",
+        7 => "double",
+        8 => "byte",
+        9 => "short",
+        10 => "int",
+        11 => "long",
+        _ => "unknown"
+    }
+}
+
+/// Escapes a constant string the way `javap` prints it.
+fn escape(value: &str) -> String {
+    let mut ret = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '\n' => ret.push_str("\\n"),
+            '\r' => ret.push_str("\\r"),
+            '\t' => ret.push_str("\\t"),
+            '\u{8}' => ret.push_str("\\b"),
+            '\u{c}' => ret.push_str("\\f"),
+            '"' => ret.push_str("\\\""),
+            '\'' => ret.push_str("\\'"),
+            '\\' => ret.push_str("\\\\"),
+            c if (c as u32) < 0x20 || (0x7f ..= 0xffff).contains(&(c as u32)) => ret.push_str(&format!("\\u{:04x}", c as u32)),
+            c => ret.push(c)
+        }
+    }
+    ret
+}
+
+/// Formats a floating point constant like `Double.toString` does for common values.
+pub fn java_double_string(value: f64) -> String {
+    if value.is_nan() {
+        "NaN".to_string()
+    } else if value.is_infinite() {
+        if value > 0.0 { "Infinity".to_string() } else { "-Infinity".to_string() }
+    } else if value.fract() == 0.0 && value.abs() < 1e7 {
+        format!("{:.1}", value)
+    } else {
+        format!("{}", value)
+    }
+}
+
+fn quote_class(name: &str) -> String {
+    if name.starts_with('[') {
+        format!("\"{}\"", name)
+    } else {
+        name.to_string()
+    }
+}
+
+fn quote_member(name: &str) -> String {
+    if name.starts_with('<') {
+        format!("\"{}\"", name)
+    } else {
+        name.to_string()
+    }
+}
+
+fn flags_line(flags: &[AccessFlags]) -> String {
+    let names: Vec<&str> = flags.iter().map(AccessFlags::spec_name).collect();
+    format!("(0x{:04x}) {}", AccessFlags::to_value(flags), names.join(", ")).trim_end().to_string()
+}
+
+fn modifiers(flags: &[AccessFlags]) -> String {
+    let mut ret = String::new();
+    for flag in flags {
+        if let Some(modifier) = flag.modifier() {
+            ret.push_str(modifier);
+            ret.push(' ');
+        }
+    }
+    ret
+}
+
+/// Renders constant pool entries as symbolic text.
+pub struct ConstantPoolPrinter<'a> {
+    pool: &'a ClassFileConstantPool,
+    this_class: Option<&'a str>
+}
+
+impl<'a> ConstantPoolPrinter<'a> {
+    pub fn new(pool: &'a ClassFileConstantPool) -> Self {
+        ConstantPoolPrinter { pool, this_class: None }
+    }
+
+    fn utf8(&self, index: u16) -> String {
+        self.pool.utf8(index).cloned().unwrap_or_else(|_| format!("#{}", index))
+    }
+
+    fn class_name(&self, index: u16) -> String {
+        self.pool.class_name(index).map(|name| quote_class(name)).unwrap_or_else(|_| format!("#{}", index))
+    }
+
+    fn name_and_type(&self, index: u16) -> String {
+        match self.pool.name_and_type(index) {
+            Ok((name, descriptor)) => format!("{}:{}", quote_member(name), descriptor),
+            Err(_) => format!("#{}", index)
+        }
+    }
+
+    fn member(&self, class_index: u16, name_and_type_index: u16, in_code: bool) -> String {
+        let class_name = self.class_name(class_index);
+        if in_code && Some(class_name.as_str()) == self.this_class {
+            self.name_and_type(name_and_type_index)
+        } else {
+            format!("{}.{}", class_name, self.name_and_type(name_and_type_index))
+        }
+    }
+
+    /// Symbolic value of an entry, as shown in the trailing comment of the constant pool listing.
+    pub fn describe(&self, index: u16) -> String {
+        self.describe_with(index, false)
+    }
+
+    /// Like [`describe`](Self::describe), but omits the class of members declared by the class being listed.
+    fn describe_in_code(&self, index: u16) -> String {
+        self.describe_with(index, true)
+    }
+
+    fn describe_with(&self, index: u16, in_code: bool) -> String {
+        match self.pool.get(index) {
+            Some(ConstantPoolTag::Class(name_index)) => quote_class(&self.utf8(*name_index)),
+            Some(ConstantPoolTag::FieldRef(class_index, nat_index))
+            | Some(ConstantPoolTag::MethodRef(class_index, nat_index))
+            | Some(ConstantPoolTag::InterfaceMethodRef(class_index, nat_index)) => self.member(*class_index, *nat_index, in_code),
+            Some(ConstantPoolTag::String(string_index)) => escape(&self.utf8(*string_index)),
+            Some(ConstantPoolTag::Integer(value)) => format!("{}", *value as i32),
+            Some(ConstantPoolTag::Float(value)) => format!("{}f", java_double_string(f32::from_bits(*value) as f64)),
+            Some(ConstantPoolTag::Long(..)) => format!("{}l", self.pool.long(index).unwrap_or_default()),
+            Some(ConstantPoolTag::Double(..)) => format!("{}d", java_double_string(self.pool.double(index).unwrap_or_default())),
+            Some(ConstantPoolTag::NameAndType(..)) => self.name_and_type(index),
+            Some(ConstantPoolTag::Utf8(_, _, value)) => escape(value),
+            Some(ConstantPoolTag::MethodHandle(kind, reference_index)) => format!("{} {}", method_handle_kind(*kind), self.describe_with(*reference_index, in_code)),
+            Some(ConstantPoolTag::MethodType(descriptor_index)) => self.utf8(*descriptor_index),
+            Some(ConstantPoolTag::Dynamic(bootstrap_index, nat_index))
+            | Some(ConstantPoolTag::InvokeDynamic(bootstrap_index, nat_index)) => format!("#{}:{}", bootstrap_index, self.name_and_type(*nat_index)),
+            Some(ConstantPoolTag::Module(name_index)) | Some(ConstantPoolTag::Package(name_index)) => self.utf8(*name_index),
+            Some(ConstantPoolTag::Dummy) | None => format!("#{}", index)
+        }
+    }
+
+    /// Raw operands of an entry, e.g. `#2.#3` for a `Methodref`.
+    fn operands(&self, tag: &ConstantPoolTag) -> String {
+        match tag {
+            ConstantPoolTag::Class(index) | ConstantPoolTag::String(index) | ConstantPoolTag::MethodType(index)
+            | ConstantPoolTag::Module(index) | ConstantPoolTag::Package(index) => format!("#{}", index),
+            ConstantPoolTag::FieldRef(a, b) | ConstantPoolTag::MethodRef(a, b) | ConstantPoolTag::InterfaceMethodRef(a, b) => format!("#{}.#{}", a, b),
+            ConstantPoolTag::NameAndType(a, b) => format!("#{}:#{}", a, b),
+            ConstantPoolTag::Dynamic(a, b) | ConstantPoolTag::InvokeDynamic(a, b) => format!("#{}:#{}", a, b),
+            ConstantPoolTag::MethodHandle(kind, index) => format!("{}:#{}", kind, index),
+            _ => String::new()
+        }
+    }
+
+    /// Kind prefix used when an entry is referenced from an instruction, e.g. `Method` or `class`.
+    fn reference_kind(&self, index: u16) -> &'static str {
+        match self.pool.get(index) {
+            Some(ConstantPoolTag::Class(_)) => "class",
+            Some(ConstantPoolTag::FieldRef(..)) => "Field",
+            Some(ConstantPoolTag::MethodRef(..)) => "Method",
+            Some(ConstantPoolTag::InterfaceMethodRef(..)) => "InterfaceMethod",
+            Some(ConstantPoolTag::String(_)) => "String",
+            Some(ConstantPoolTag::Integer(_)) => "int",
+            Some(ConstantPoolTag::Float(_)) => "float",
+            Some(ConstantPoolTag::Long(..)) => "long",
+            Some(ConstantPoolTag::Double(..)) => "double",
+            Some(ConstantPoolTag::MethodHandle(..)) => "MethodHandle",
+            Some(ConstantPoolTag::MethodType(_)) => "MethodType",
+            Some(ConstantPoolTag::Dynamic(..)) => "Dynamic",
+            Some(ConstantPoolTag::InvokeDynamic(..)) => "InvokeDynamic",
+            _ => ""
+        }
+    }
+
+    fn verification_type(&self, value: &VerificationType) -> String {
+        match value {
+            VerificationType::Top => "top".to_string(),
+            VerificationType::Integer => "int".to_string(),
+            VerificationType::Float => "float".to_string(),
+            VerificationType::Double => "double".to_string(),
+            VerificationType::Long => "long".to_string(),
+            VerificationType::Null => "null".to_string(),
+            VerificationType::UninitializedThis => "this".to_string(),
+            VerificationType::Object(index) => format!("class {}", self.class_name(*index)),
+            VerificationType::Uninitialized(offset) => format!("uninitialized {}", offset)
+        }
+    }
+
+    fn element_value(&self, value: &ElementValue) -> String {
+        match value {
+            ElementValue::ConstValueIndex(tag, index) => format!("{}#{}", *tag as char, index),
+            ElementValue::EnumConstValue(type_index, name_index) => format!("e#{}.#{}", type_index, name_index),
+            ElementValue::ClassInfoIndex(index) => format!("c#{}", index),
+            ElementValue::AnnotationValue(annotation) => format!("@{}", self.annotation(annotation)),
+            ElementValue::ArrayValue(_, values) => {
+                let values: Vec<String> = values.iter().map(|v| self.element_value(v)).collect();
+                format!("[{}]", values.join(","))
+            }
+        }
+    }
+
+    fn annotation(&self, annotation: &Annotation) -> String {
+        let pairs: Vec<String> = annotation.element_value_pairs.iter()
+            .map(|pair| format!("#{}={}", pair.name_index, self.element_value(&pair.value)))
+            .collect();
+        format!("#{}({})", annotation.type_index, pairs.join(","))
+    }
+
+    fn element_value_source(&self, value: &ElementValue) -> String {
+        match value {
+            ElementValue::ConstValueIndex(b's', index) => format!("\"{}\"", escape(&self.utf8(*index))),
+            ElementValue::ConstValueIndex(b'Z', index) => match self.pool.get(*index) {
+                Some(ConstantPoolTag::Integer(0)) => "false".to_string(),
+                _ => "true".to_string()
+            },
+            ElementValue::ConstValueIndex(_, index) => self.describe(*index),
+            ElementValue::EnumConstValue(type_index, name_index) => format!("{}.{}", self.type_name(*type_index), self.utf8(*name_index)),
+            ElementValue::ClassInfoIndex(index) => format!("{}.class", self.type_name(*index)),
+            ElementValue::AnnotationValue(annotation) => self.annotation_source(annotation),
+            ElementValue::ArrayValue(_, values) => {
+                let values: Vec<String> = values.iter().map(|v| self.element_value_source(v)).collect();
+                format!("[{}]", values.join(","))
+            }
+        }
+    }
+
+    fn type_name(&self, descriptor_index: u16) -> String {
+        let descriptor = self.utf8(descriptor_index);
+        FieldType::parse(&descriptor).map(|t| t.java_name()).unwrap_or(descriptor)
+    }
+
+    fn annotation_source(&self, annotation: &Annotation) -> String {
+        let name = self.type_name(annotation.type_index);
+        if annotation.element_value_pairs.is_empty() {
+            return name
+        }
+        let pairs: Vec<String> = annotation.element_value_pairs.iter()
+            .map(|pair| format!("{}={}", self.utf8(pair.name_index), self.element_value_source(&pair.value)))
+            .collect();
+        format!("{}(\n{})", name, pairs.join("\n"))
+    }
+}
+
+struct Disassembler<'a, W: Write> {
+    class: &'a Class,
+    printer: ConstantPoolPrinter<'a>,
+    out: &'a mut W
+}
+
+impl<'a, W: Write> Disassembler<'a, W> {
+    fn class_header(&mut self) -> Result<()> {
+        let class = self.class;
+        if let Some(source_file) = &class.source_file_name {
+            writeln!(self.out, "  Compiled from \"{}\"", source_file)?;
+        }
+        let is_interface = class.access_flags.contains(&AccessFlags::Interface);
+        let mut declaration = String::new();
+        for flag in &class.access_flags {
+            match flag {
+                AccessFlags::Public | AccessFlags::Final => declaration.push_str(&format!("{} ", flag.modifier().unwrap())),
+                AccessFlags::Abstract if !is_interface => declaration.push_str("abstract "),
+                _ => {}
+            }
+        }
+        declaration.push_str(if is_interface { "interface " } else { "class " });
+        declaration.push_str(&class.class_name.replace('/', "."));
+        let interfaces: Vec<String> = class.interface_names.iter().map(|name| name.replace('/', ".")).collect();
+        if let Some(super_class) = &class.super_class_name {
+            if super_class != "java/lang/Object" {
+                declaration.push_str(&format!(" extends {}", super_class.replace('/', ".")));
+            }
+        }
+        if !interfaces.is_empty() {
+            let keyword = if is_interface { "extends" } else { "implements" };
+            declaration.push_str(&format!(" {} {}", keyword, interfaces.join(",")));
+        }
+        writeln!(self.out, "{}", declaration)?;
+        writeln!(self.out, "  minor version: {}", class.version_minor)?;
+        writeln!(self.out, "  major version: {}", class.version_major)?;
+        writeln!(self.out, "  flags: {}", flags_line(&class.access_flags))?;
+        writeln!(self.out, "{:<42}// {}", format!("  this_class: #{}", class.this_class), self.printer.describe(class.this_class))?;
+        if class.super_class == 0 {
+            writeln!(self.out, "  super_class: #0")?;
+        } else {
+            writeln!(self.out, "{:<42}// {}", format!("  super_class: #{}", class.super_class), self.printer.describe(class.super_class))?;
+        }
+        writeln!(self.out, "  interfaces: {}, fields: {}, methods: {}, attributes: {}",
+                 class.interfaces.len(), class.fields.len(), class.methods.len(), class.attributes.len())
+    }
+
+    fn constant_pool(&mut self) -> Result<()> {
+        writeln!(self.out, "Constant pool:")?;
+        let width = format!("{}", self.class.constant_pool.constant_pool.len()).len() + 3;
+        for (index, tag) in self.class.constant_pool.entries() {
+            let label = format!("{:>width$} = {:<18} ", format!("#{}", index), tag.kind(), width = width);
+            match tag {
+                ConstantPoolTag::Utf8(..) | ConstantPoolTag::Integer(_) | ConstantPoolTag::Float(_)
+                | ConstantPoolTag::Long(..) | ConstantPoolTag::Double(..) => {
+                    writeln!(self.out, "{}{}", label, self.printer.describe(index))?
+                }
+                _ => writeln!(self.out, "{}{:<operands$}// {}", label, self.printer.operands(tag), self.printer.describe(index),
+                              operands = 20 - width)?
+            }
+        }
+        Ok(())
+    }
+
+    fn field(&mut self, field: &Field) -> Result<()> {
+        let type_name = FieldType::parse(&field.descriptor).map(|t| t.java_name()).unwrap_or_else(|_| field.descriptor.clone());
+        writeln!(self.out, "  {}{} {};", modifiers(&field.access_flags), type_name, field.field_name)?;
+        writeln!(self.out, "    descriptor: {}", field.descriptor)?;
+        writeln!(self.out, "    flags: {}", flags_line(&field.access_flags))?;
+        for attribute in &field.attributes {
+            self.attribute(attribute, "    ")?;
+        }
+        Ok(())
+    }
+
+    fn method_declaration(&self, method: &Method) -> String {
+        let class_name = self.class.class_name.replace('/', ".");
+        if method.method_name == "<clinit>" {
+            return "static {}".to_string()
+        }
+        let descriptor = MethodDescriptor::parse(&method.descriptor);
+        let (parameters, return_type) = match &descriptor {
+            Ok(descriptor) => (
+                descriptor.parameters.iter().map(FieldType::java_name).collect::<Vec<String>>().join(", "),
+                descriptor.return_type.as_ref().map(FieldType::java_name).unwrap_or_else(|| "void".to_string())
+            ),
+            Err(_) => (method.descriptor.clone(), String::new())
+        };
+        let mut declaration = modifiers(&method.access_flags);
+        if method.method_name == "<init>" {
+            declaration.push_str(&format!("{}({})", class_name, parameters));
+        } else {
+            declaration.push_str(&format!("{} {}({})", return_type, method.method_name, parameters));
+        }
+        for attribute in &method.attributes {
+            if let AttributeValue::Exceptions(indexes) = &attribute.value {
+                let names: Vec<String> = indexes.iter().map(|i| self.printer.describe(*i).replace('/', ".")).collect();
+                declaration.push_str(&format!(" throws {}", names.join(", ")));
+            }
+        }
+        declaration
+    }
+
+    fn method(&mut self, method: &Method) -> Result<()> {
+        writeln!(self.out, "  {};", self.method_declaration(method))?;
+        writeln!(self.out, "    descriptor: {}", method.descriptor)?;
+        writeln!(self.out, "    flags: {}", flags_line(&method.access_flags))?;
+        if !method.code.is_empty() {
+            self.code(method)?;
+        }
+        for attribute in &method.attributes {
+            self.attribute(attribute, "    ")?;
+        }
+        Ok(())
+    }
+
+    fn code(&mut self, method: &Method) -> Result<()> {
+        let args_size = MethodDescriptor::parse(&method.descriptor).map(|d| d.parameter_slots()).unwrap_or(0)
+            + if method.is_static() { 0 } else { 1 };
+        writeln!(self.out, "    Code:")?;
+        writeln!(self.out, "      stack={}, locals={}, args_size={}", method.max_stack, method.max_locals, args_size)?;
+        let interpreter = Interpreter::new();
+        let mut pc = 0;
+        while pc < method.code.len() {
+            match interpreter.decode(&method.code, pc) {
+                Ok((opcode, next_pc)) => {
+                    self.instruction(&method.code, pc, &opcode)?;
+                    pc = next_pc;
+                }
+                Err(e) => {
+                    writeln!(self.out, "{:>10}: <invalid instruction: {:?}>", pc, e)?;
+                    break
+                }
+            }
+        }
+        if !method.exception_table.is_empty() {
+            writeln!(self.out, "      Exception table:")?;
+            writeln!(self.out, "         from    to  target type")?;
+            for entry in &method.exception_table {
+                let catch_type = if entry.catch_type == 0 {
+                    "any".to_string()
+                } else {
+                    format!("Class {}", self.printer.describe(entry.catch_type))
+                };
+                writeln!(self.out, "        {:>6}{:>6}{:>6}   {}", entry.pc_start, entry.pc_end, entry.handler_pc, catch_type)?;
+            }
+        }
+        for attribute in &method.code_attributes {
+            self.attribute(attribute, "      ")?;
+        }
+        Ok(())
+    }
+
+    fn instruction(&mut self, code: &[u8], pc: usize, opcode: &Opcode) -> Result<()> {
+        let byte_code = code[pc];
+        let is_wide = byte_code == 0xc4;
+        let name = if is_wide {
+            format!("{}_w", mnemonic(code[pc + 1]).unwrap_or("wide"))
+        } else {
+            mnemonic(byte_code).unwrap_or("<unknown>").to_string()
+        };
+        let target = |offset: &i32| pc as i64 + *offset as i64;
+        let has_implicit_operand = !is_wide && name.rsplit_once('_').map(|(_, suffix)| suffix.parse::<u8>().is_ok() || suffix == "m1").unwrap_or(false);
+        let (operands, reference) = match opcode {
+            Opcode::BiPush(v) => (format!("{}", v), None),
+            Opcode::SiPush(v) => (format!("{}", v), None),
+            Opcode::Ldc(index) | Opcode::LdcW(index) | Opcode::Ldc2W(index)
+            | Opcode::GetStatic(index) | Opcode::PutStatic(index) | Opcode::GetField(index) | Opcode::PutField(index)
+            | Opcode::InvokeVirtual(index) | Opcode::InvokeSpecial(index) | Opcode::InvokeStatic(index)
+            | Opcode::New(index) | Opcode::ANewArray(index) | Opcode::CheckCast(index) | Opcode::InstanceOf(index) => (format!("#{}", index), Some(*index)),
+            Opcode::InvokeInterface(index, count) => (format!("#{},  {}", index, count), Some(*index)),
+            Opcode::InvokeDynamic(index) => (format!("#{},  0", index), Some(*index)),
+            Opcode::MultiANewArray(index, dimensions) => (format!("#{},  {}", index, dimensions), Some(*index)),
+            Opcode::ILoad(index) | Opcode::LLoad(index) | Opcode::FLoad(index) | Opcode::DLoad(index) | Opcode::ALoad(index)
+            | Opcode::IStore(index) | Opcode::LStore(index) | Opcode::FStore(index) | Opcode::DStore(index) | Opcode::AStore(index)
+            | Opcode::Ret(index) if !has_implicit_operand => (format!("{}", index), None),
+            Opcode::IInc(index, value) => (format!("{}, {}", index, value), None),
+            Opcode::NewArray(atype) => (array_type_name(*atype).to_string(), None),
+            Opcode::TableSwitch(default, low, _, offsets) => {
+                writeln!(self.out, "{:>10}: {:<13} {{ // {} to {}", pc, name, low, *low as i64 + offsets.len() as i64 - 1)?;
+                for (i, offset) in offsets.iter().enumerate() {
+                    writeln!(self.out, "{:>24}: {}", *low as i64 + i as i64, target(offset))?;
+                }
+                writeln!(self.out, "{:>24}: {}", "default", target(default))?;
+                return writeln!(self.out, "            }}")
+            }
+            Opcode::LookupSwitch(default, pairs) => {
+                writeln!(self.out, "{:>10}: {:<13} {{ // {}", pc, name, pairs.len())?;
+                for (key, offset) in pairs {
+                    writeln!(self.out, "{:>24}: {}", key, target(offset))?;
+                }
+                writeln!(self.out, "{:>24}: {}", "default", target(default))?;
+                return writeln!(self.out, "            }}")
+            }
+            _ => match opcode.branch_offsets().first() {
+                Some(offset) => (format!("{}", target(offset)), None),
+                None => (String::new(), None)
+            }
+        };
+        match reference {
+            Some(index) => {
+                let instruction = format!("{:<13} {}", name, operands);
+                writeln!(self.out, "{:>10}: {:<width$}// {} {}", pc, instruction, self.printer.reference_kind(index),
+                         self.printer.describe_in_code(index), width = COMMENT_COLUMN)
+            }
+            None if operands.is_empty() => writeln!(self.out, "{:>10}: {}", pc, name),
+            None => writeln!(self.out, "{:>10}: {:<13} {}", pc, name, operands)
+        }
+    }
+
+    fn annotations(&mut self, annotations: &[Annotation], indent: &str) -> Result<()> {
+        for (i, annotation) in annotations.iter().enumerate() {
+            writeln!(self.out, "{}  {}: {}", indent, i, self.printer.annotation(annotation))?;
+            for line in self.printer.annotation_source(annotation).lines() {
+                writeln!(self.out, "{}    {}", indent, line)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn parameter_annotations(&mut self, parameters: &[ParameterAnnotation], indent: &str) -> Result<()> {
+        for (i, parameter) in parameters.iter().enumerate() {
+            writeln!(self.out, "{}  parameter {}:", indent, i)?;
+            self.annotations(&parameter.annotations, &format!("{}  ", indent))?;
+        }
+        Ok(())
+    }
+
+    fn stack_map_frame(&mut self, frame: &StackMapFrame, indent: &str) -> Result<()> {
+        writeln!(self.out, "{}  frame_type = {} /* {} */", indent, frame.frame_type, frame.kind())?;
+        if frame.frame_type >= 247 {
+            writeln!(self.out, "{}    offset_delta = {}", indent, frame.offset_delta)?;
+        }
+        let render = |types: &[VerificationType]| -> String {
+            let names: Vec<String> = types.iter().map(|t| self.printer.verification_type(t)).collect();
+            format!("[ {} ]", names.join(", "))
+        };
+        if !frame.locals.is_empty() || frame.frame_type == 255 {
+            writeln!(self.out, "{}    locals = {}", indent, render(&frame.locals))?;
+        }
+        if !frame.stack.is_empty() || frame.frame_type == 255 {
+            writeln!(self.out, "{}    stack = {}", indent, render(&frame.stack))?;
+        }
+        Ok(())
+    }
+
+    fn attribute(&mut self, attribute: &Attribute, indent: &str) -> Result<()> {
+        let printer = &self.printer;
+        match &attribute.value {
+            AttributeValue::ConstantValue(index) => {
+                writeln!(self.out, "{}ConstantValue: {} {}", indent, printer.reference_kind(*index), printer.describe(*index))
+            }
+            AttributeValue::SourceFile(index) => writeln!(self.out, "{}SourceFile: \"{}\"", indent, printer.utf8(*index)),
+            AttributeValue::Signature(index) => {
+                writeln!(self.out, "{}{:<40}// {}", indent, format!("Signature: #{}", index), printer.utf8(*index))
+            }
+            AttributeValue::LineNumberTable(entries) => {
+                writeln!(self.out, "{}LineNumberTable:", indent)?;
+                for entry in entries {
+                    writeln!(self.out, "{}  line {}: {}", indent, entry.line_number, entry.start_pc)?;
+                }
+                Ok(())
+            }
+            AttributeValue::LocalVariableTable(entries) | AttributeValue::LocalVariableTypeTable(entries) => {
+                writeln!(self.out, "{}{}:", indent, attribute.name)?;
+                writeln!(self.out, "{}  Start  Length  Slot  Name   Signature", indent)?;
+                for entry in entries {
+                    writeln!(self.out, "{}{:>7}{:>8}{:>6} {:>5}   {}", indent, entry.start_pc, entry.length, entry.index,
+                             printer.utf8(entry.name_index), printer.utf8(entry.descriptor_index))?;
+                }
+                Ok(())
+            }
+            AttributeValue::StackMapTable(frames) => {
+                writeln!(self.out, "{}StackMapTable: number_of_entries = {}", indent, frames.len())?;
+                for frame in frames {
+                    self.stack_map_frame(frame, indent)?;
+                }
+                Ok(())
+            }
+            AttributeValue::Exceptions(indexes) => {
+                writeln!(self.out, "{}Exceptions:", indent)?;
+                let names: Vec<String> = indexes.iter().map(|i| printer.describe(*i).replace('/', ".")).collect();
+                writeln!(self.out, "{}  throws {}", indent, names.join(", "))
+            }
+            AttributeValue::InnerClasses(entries) => {
+                writeln!(self.out, "{}InnerClasses:", indent)?;
+                for entry in entries {
+                    let inner_name = if entry.inner_name_index == 0 { String::new() } else { format!("#{}= ", entry.inner_name_index) };
+                    let outer = if entry.outer_class_info_index == 0 { String::new() } else { format!(" of #{}", entry.outer_class_info_index) };
+                    let mut comment = String::new();
+                    if entry.inner_name_index != 0 {
+                        comment.push_str(&format!("{}=", printer.utf8(entry.inner_name_index)));
+                    }
+                    comment.push_str(&format!("class {}", printer.describe(entry.inner_class_info_index)));
+                    if entry.outer_class_info_index != 0 {
+                        comment.push_str(&format!(" of class {}", printer.describe(entry.outer_class_info_index)));
+                    }
+                    writeln!(self.out, "{}  {}{}#{}{}; // {}", indent, modifiers(&entry.inner_class_access_flags), inner_name,
+                             entry.inner_class_info_index, outer, comment)?;
+                }
+                Ok(())
+            }
+            AttributeValue::EnclosingMethod(class_index, method_index) => {
+                let method = if *method_index == 0 { String::new() } else { format!(".{}", printer.describe(*method_index)) };
+                writeln!(self.out, "{}EnclosingMethod: #{}.#{}{:<10}// {}{}", indent, class_index, method_index, "", printer.describe(*class_index), method)
+            }
+            AttributeValue::BootstrapMethods(methods) => {
+                writeln!(self.out, "{}BootstrapMethods:", indent)?;
+                for (i, method) in methods.iter().enumerate() {
+                    writeln!(self.out, "{}  {}: #{} {}", indent, i, method.bootstrap_method_ref, printer.describe(method.bootstrap_method_ref))?;
+                    writeln!(self.out, "{}    Method arguments:", indent)?;
+                    for argument in &method.bootstrap_arguments {
+                        writeln!(self.out, "{}      #{} {}", indent, argument, printer.describe(*argument))?;
+                    }
+                }
+                Ok(())
+            }
+            AttributeValue::NestHost(index) => writeln!(self.out, "{}NestHost: class {}", indent, printer.describe(*index)),
+            AttributeValue::NestMembers(indexes) => {
+                writeln!(self.out, "{}NestMembers:", indent)?;
+                for index in indexes {
+                    writeln!(self.out, "{}  {}", indent, printer.describe(*index))?;
+                }
+                Ok(())
+            }
+            AttributeValue::MethodParameters(parameters) => {
+                writeln!(self.out, "{}MethodParameters:", indent)?;
+                writeln!(self.out, "{}  Name                           Flags", indent)?;
+                for parameter in parameters {
+                    let name = if parameter.name_index == 0 { "<no name>".to_string() } else { printer.utf8(parameter.name_index) };
+                    writeln!(self.out, "{}  {:<30} {}", indent, name, modifiers(&parameter.access_flags).trim_end())?;
+                }
+                Ok(())
+            }
+            AttributeValue::AnnotationDefault(value) => {
+                writeln!(self.out, "{}AnnotationDefault:", indent)?;
+                writeln!(self.out, "{}  default_value: {}", indent, printer.element_value(value))?;
+                writeln!(self.out, "{}    {}", indent, printer.element_value_source(value))
+            }
+            AttributeValue::Deprecated => writeln!(self.out, "{}Deprecated: true", indent),
+            AttributeValue::Synthetic => writeln!(self.out, "{}Synthetic: true", indent),
+            AttributeValue::RuntimeVisibleAnnotations(_, annotations) | AttributeValue::RuntimeInvisibleAnnotations(_, annotations) => {
+                writeln!(self.out, "{}{}:", indent, attribute.name)?;
+                self.annotations(annotations, indent)
+            }
+            AttributeValue::RuntimeVisibleParameterAnnotations(_, parameters) | AttributeValue::RuntimeInvisibleParameterAnnotations(_, parameters) => {
+                writeln!(self.out, "{}{}:", indent, attribute.name)?;
+                self.parameter_annotations(parameters, indent)
+            }
+            AttributeValue::Code(..) => writeln!(self.out, "{}Code: <nested>", indent),
+            AttributeValue::Unidentified(info) => {
+                writeln!(self.out, "{}{}: length = 0x{:x} (unknown attribute)", indent, attribute.name, info.len())?;
+                for chunk in info.chunks(16) {
+                    let bytes: Vec<String> = chunk.iter().map(|b| format!("{:02x}", b)).collect();
+                    writeln!(self.out, "{}  {}", indent, bytes.join(" "))?;
+                }
+                Ok(())
+            }
+        }
+    }
+
+    fn run(&mut self) -> Result<()> {
+        self.class_header()?;
+        self.constant_pool()?;
+        writeln!(self.out, "{{")?;
+        for (i, field) in self.class.fields.iter().enumerate() {
+            if i > 0 {
+                writeln!(self.out)?;
+            }
+            self.field(field)?;
+        }
+        for (i, method) in self.class.methods.iter().enumerate() {
+            if i > 0 || !self.class.fields.is_empty() {
+                writeln!(self.out)?;
+            }
+            self.method(method)?;
+        }
+        writeln!(self.out, "}}")?;
+        for attribute in &self.class.attributes {
+            self.attribute(attribute, "")?;
+        }
+        Ok(())
+    }
+}
+
+/// Writes a `javap -c -v` style listing of `class` to `out`.
+pub fn disassemble<W: Write>(class: &Class, out: &mut W) -> Result<()> {
+    let mut printer = ConstantPoolPrinter::new(&class.constant_pool);
+    printer.this_class = Some(&class.class_name);
+    Disassembler { class, printer, out }.run()
+}
+
+/// Convenience wrapper around [`disassemble`] returning the listing as a string.
+pub fn disassemble_to_string(class: &Class) -> String {
+    let mut out: Vec<u8> = Vec::new();
+    disassemble(class, &mut out).expect("writing to a Vec cannot fail");
+    String::from_utf8(out).unwrap_or_default()
+}
@@ -1,38 +1,2120 @@
-use std::error::Error;
-use crate::interpret::{Interpreter, InterpreterError, Opcode};
-use crate::vm::VM;
+use std::collections::HashMap;
+use std::io::Write;
+use std::rc::Rc;
+use crate::descriptor::{FieldType, MethodDescriptor};
+use crate::gc::ReferenceKind;
+use crate::interpret::{Interpreter, InterpreterError, JavaException, Opcode, StackFrame};
+use crate::jni::Jni;
+use crate::native::NativeRegistry;
+use crate::loader::{AccessFlags, BootstrapMethod, Class, ClassLoadError, ConstantPoolTag, Field, Method};
+use crate::registry::{ClassId, ClassState, LoaderId};
+use crate::vm::{GlobalHandle, Handle, ObjRef, VM};
 use crate::vm::VMValue;
 
+/// Default limit on nested Java invocations before `StackOverflowError` is thrown.
+pub const DEFAULT_MAX_FRAMES: usize = 1024;
+
+/// An activation of a Java method. Its local variables start at `locals` on the interop stack,
+/// the operand stack follows them.
+pub struct Frame {
+    pub class: ClassId,
+    pub class_file: Rc<Class>,
+    pub method: usize,
+    pub pc: usize,
+    pub locals: usize
+}
+
+impl Frame {
+    pub fn method(&self) -> &Method {
+        &self.class_file.methods[self.method]
+    }
+
+    fn stack_base(&self) -> usize {
+        self.locals + self.method().max_locals as usize
+    }
+}
+
+/// What the interpreter loop does after an instruction.
+enum Flow {
+    Next,
+    Branch(i32),
+    Return(Option<VMValue>)
+}
+
 pub struct VMEnv {
     vm: VM,
-    interpreter: Interpreter
+    interpreter: Interpreter,
+    frames: Vec<Frame>,
+    pub max_frames: usize,
+    /// Set while the references the collector queued are handed to Java code, which runs without
+    /// reaching further safepoints.
+    processing_references: bool,
+    /// Set once `-XX:+HeapDumpOnOutOfMemoryError` has dumped the heap, which it does only once.
+    pub(crate) heap_dumped: bool,
+    /// The Rust implementations of native methods.
+    pub natives: NativeRegistry,
+    /// Loaded JNI libraries and the state of the native code running.
+    pub(crate) jni: Jni,
+    /// Where `System.out` writes, the process's standard output unless replaced.
+    pub stdout: Box<dyn Write>,
+    /// Where `System.err` writes.
+    pub stderr: Box<dyn Write>,
+    /// The `java.lang.Thread` of the main thread, which the VM creates when it boots a JDK.
+    thread: Option<GlobalHandle>,
+    /// The classes spun for `LambdaMetafactory` call sites, and the call site of each.
+    lambdas: HashMap<ClassId, Rc<LambdaClass>>,
+    call_sites: HashMap<(ClassId, u16), ClassId>
+}
+
+/// How the methods of a lambda class call its implementation method. Instance methods take
+/// their receiver as first parameter.
+struct LambdaClass {
+    kind: u8,
+    class: ClassId,
+    method_index: usize,
+    captured: Vec<FieldType>,
+    parameters: Vec<FieldType>,
+    return_type: Option<FieldType>
+}
+
+/// Drops an exception thrown by Java code, keeping the errors of the VM itself.
+fn ignore_exception<T>(result: Result<T, InterpreterError>) -> Result<(), InterpreterError> {
+    match result {
+        Ok(_) | Err(InterpreterError::Exception(_)) => Ok(()),
+        Err(error) => Err(error)
+    }
+}
+
+/// `'int Foo.bar(long)'`, the way HotSpot names methods in linkage error messages.
+pub fn method_display_name(class_name: &str, method: &Method) -> String {
+    match MethodDescriptor::parse(&method.descriptor) {
+        Ok(descriptor) => {
+            let parameters: Vec<String> = descriptor.parameters.iter().map(FieldType::java_name).collect();
+            let return_type = descriptor.return_type.as_ref().map(FieldType::java_name).unwrap_or_else(|| "void".to_string());
+            format!("'{} {}.{}({})'", return_type, class_name.replace('/', "."), method.method_name, parameters.join(", "))
+        }
+        Err(_) => format!("'{}.{}{}'", class_name.replace('/', "."), method.method_name, method.descriptor)
+    }
+}
+
+fn division_by_zero() -> InterpreterError {
+    InterpreterError::exception("java/lang/ArithmeticException", "/ by zero")
+}
+
+/// `int` arithmetic with the semantics of JVMS 6.5: results wrap, shift distances use the low
+/// 5 bits and `Integer.MIN_VALUE / -1` overflows back to `Integer.MIN_VALUE`.
+pub fn int_arithmetic(opcode: &Opcode, a: i32, b: i32) -> Result<i32, InterpreterError> {
+    Ok(match opcode {
+        Opcode::IAdd => a.wrapping_add(b),
+        Opcode::ISub => a.wrapping_sub(b),
+        Opcode::IMul => a.wrapping_mul(b),
+        Opcode::IDiv => if b == 0 { return Err(division_by_zero()) } else { a.wrapping_div(b) },
+        Opcode::IRem => if b == 0 { return Err(division_by_zero()) } else { a.wrapping_rem(b) },
+        Opcode::IShl => a.wrapping_shl(b as u32 & 0x1f),
+        Opcode::IShr => a.wrapping_shr(b as u32 & 0x1f),
+        Opcode::IUShr => ((a as u32) >> (b as u32 & 0x1f)) as i32,
+        Opcode::IAnd => a & b,
+        Opcode::IOr => a | b,
+        Opcode::IXor => a ^ b,
+        _ => return Err(InterpreterError::TypeMismatch("int arithmetic".to_string(), format!("{:?}", opcode)))
+    })
+}
+
+/// `long` arithmetic, like [`int_arithmetic`] with shift distances masked to 6 bits. The shift
+/// instructions take their distance as an `int`, passed here widened to `long`.
+pub fn long_arithmetic(opcode: &Opcode, a: i64, b: i64) -> Result<i64, InterpreterError> {
+    Ok(match opcode {
+        Opcode::LAdd => a.wrapping_add(b),
+        Opcode::LSub => a.wrapping_sub(b),
+        Opcode::LMul => a.wrapping_mul(b),
+        Opcode::LDiv => if b == 0 { return Err(division_by_zero()) } else { a.wrapping_div(b) },
+        Opcode::LRem => if b == 0 { return Err(division_by_zero()) } else { a.wrapping_rem(b) },
+        Opcode::LShl => a.wrapping_shl(b as u32 & 0x3f),
+        Opcode::LShr => a.wrapping_shr(b as u32 & 0x3f),
+        Opcode::LUShr => ((a as u64) >> (b as u32 & 0x3f)) as i64,
+        Opcode::LAnd => a & b,
+        Opcode::LOr => a | b,
+        Opcode::LXor => a ^ b,
+        _ => return Err(InterpreterError::TypeMismatch("long arithmetic".to_string(), format!("{:?}", opcode)))
+    })
+}
+
+/// `float` and `double` arithmetic follows IEEE 754 round to nearest, except that remainder
+/// truncates the quotient like C's `fmod` (JVMS 6.5 `drem`), which is what Rust's `%` does.
+macro_rules! float_arithmetic {
+    ($name:ident, $type:ty, $add:ident, $sub:ident, $mul:ident, $div:ident, $rem:ident) => {
+        pub fn $name(opcode: &Opcode, a: $type, b: $type) -> Result<$type, InterpreterError> {
+            Ok(match opcode {
+                Opcode::$add => a + b,
+                Opcode::$sub => a - b,
+                Opcode::$mul => a * b,
+                Opcode::$div => a / b,
+                Opcode::$rem => a % b,
+                _ => return Err(InterpreterError::TypeMismatch(stringify!($type).to_string(), format!("{:?}", opcode)))
+            })
+        }
+    };
+}
+
+float_arithmetic!(float_arithmetic, f32, FAdd, FSub, FMul, FDiv, FRem);
+float_arithmetic!(double_arithmetic, f64, DAdd, DSub, DMul, DDiv, DRem);
+
+/// `fcmpl`/`fcmpg` and their `double` forms: -1, 0 or 1, and `nan_result` if either value is NaN.
+pub fn compare_floating(a: f64, b: f64, nan_result: i32) -> i32 {
+    a.partial_cmp(&b).map(|ordering| ordering as i32).unwrap_or(nan_result)
+}
+
+fn branch_if(condition: bool, offset: i32) -> Flow {
+    if condition { Flow::Branch(offset) } else { Flow::Next }
+}
+
+/// A primitive value in its computational form widened to `to` (JLS 5.1.2).
+fn widen(value: VMValue, to: &FieldType) -> VMValue {
+    match (value, to) {
+        (VMValue::Int(v), FieldType::Long) => VMValue::Long(v as i64),
+        (VMValue::Int(v), FieldType::Float) => VMValue::Float(v as f32),
+        (VMValue::Int(v), FieldType::Double) => VMValue::Double(v as f64),
+        (VMValue::Long(v), FieldType::Float) => VMValue::Float(v as f32),
+        (VMValue::Long(v), FieldType::Double) => VMValue::Double(v as f64),
+        (VMValue::Float(v), FieldType::Double) => VMValue::Double(v as f64),
+        (value, _) => value
+    }
+}
+
+/// The class that boxes values of a primitive type.
+fn wrapper_class_name(primitive: &FieldType) -> &'static str {
+    match primitive {
+        FieldType::Boolean => "java/lang/Boolean",
+        FieldType::Byte => "java/lang/Byte",
+        FieldType::Char => "java/lang/Character",
+        FieldType::Short => "java/lang/Short",
+        FieldType::Int => "java/lang/Integer",
+        FieldType::Long => "java/lang/Long",
+        FieldType::Float => "java/lang/Float",
+        _ => "java/lang/Double"
+    }
 }
 
 impl VMEnv {
 
     pub fn of(vm: VM, interpreter: Interpreter) -> Self {
-        return VMEnv { vm, interpreter }
+        VMEnv { vm, interpreter, frames: Vec::new(), max_frames: DEFAULT_MAX_FRAMES, processing_references: false, heap_dumped: false,
+                natives: NativeRegistry::with_runtime_natives(), jni: Jni::default(),
+                stdout: Box::new(std::io::stdout()), stderr: Box::new(std::io::stderr()), thread: None,
+                lambdas: HashMap::new(), call_sites: HashMap::new() }
+    }
+
+    pub fn vm(&self) -> &VM {
+        &self.vm
+    }
+
+    pub fn vm_mut(&mut self) -> &mut VM {
+        &mut self.vm
+    }
+
+    /// The active frames, innermost last.
+    pub fn frames(&self) -> &[Frame] {
+        &self.frames
     }
 
-    pub fn execute(&mut self, code: &Vec<u8>) -> Result<(), InterpreterError> {
-        for instruction in code {
-            match self.interpreter.decode(instruction.clone())? {
-                Opcode::AConstNull => self.aconst_null(),
-                Opcode::IConst(v) => self.iconst(v as i32),
-                Opcode::IAdd => self.iadd(),
-                Opcode::IAnd => {}
-                Opcode::I2B => {}
-                Opcode::I2C => {}
-                Opcode::I2D => {}
-                Opcode::I2F => {}
-                Opcode::I2L => {}
-                Opcode::I2S => {}
-                Opcode::IMul => {}
+    /// Initializes `class` and runs its `main(String[])` with the program arguments, as the
+    /// launcher does (JVMS 5.2).
+    pub fn run_main(&mut self, class: ClassId, args: &[String]) -> Result<(), InterpreterError> {
+        self.initialize_system()?;
+        self.initialize_class(class)?;
+        let class_file = self.vm.classes.class(class).class.clone();
+        let index = class_file.methods.iter()
+            .position(|m| m.method_name == "main" && m.descriptor == "([Ljava/lang/String;)V" && m.is_static())
+            .ok_or_else(|| InterpreterError::exception("java/lang/NoSuchMethodError", "main"))?;
+        let array_class = self.vm.classes.load_class(LoaderId::BOOTSTRAP, "[Ljava/lang/String;")?;
+        let array = self.vm.new_array(array_class, args.len() as i32)?;
+        let array = self.vm.new_handle(array);
+        for (index, arg) in args.iter().enumerate() {
+            let arg = self.vm.new_string(arg);
+            let arg = match arg {
+                Ok(arg) => arg,
+                Err(e) => {
+                    self.vm.release_handles(array);
+                    return Err(e)
+                }
+            };
+            self.vm.store_element(self.vm.handle(array), index as u32, &FieldType::Object("java/lang/String".to_string()), VMValue::Reference(arg));
+        }
+        let args = self.vm.handle(array);
+        self.vm.release_handles(array);
+        self.invoke(class, index, vec![VMValue::Reference(args)])?;
+        Ok(())
+    }
+
+    /// Boots the `java.lang.System` of a JDK as HotSpot does before it runs `main`: creates the
+    /// system and main thread groups and the main thread, then runs `System.initPhase1`, which
+    /// sets up the system properties and standard streams. The bundled runtime initializes its
+    /// `System` by itself, so nothing is done when there is no `initPhase1`.
+    fn initialize_system(&mut self) -> Result<(), InterpreterError> {
+        let system = self.vm.classes.load_class(LoaderId::BOOTSTRAP, "java/lang/System")?;
+        let init_phase1 = self.vm.classes.class(system).class.methods.iter().position(|m| m.method_name == "initPhase1" && m.is_static());
+        let init_phase1 = match init_phase1 {
+            Some(index) if self.thread.is_none() => index,
+            _ => return Ok(())
+        };
+        for name in ["java/lang/String", "java/lang/System", "java/lang/Class", "java/lang/ThreadGroup", "java/lang/Thread"] {
+            let class = self.vm.bootstrap_class(name)?;
+            self.initialize_class(class)?;
+        }
+        // HotSpot stores the facts about the platform in the constants of UnsafeConstants.
+        let constants = self.vm.bootstrap_class("jdk/internal/misc/UnsafeConstants")?;
+        self.initialize_class(constants)?;
+        for (name, value) in [("ADDRESS_SIZE0", 4), ("PAGE_SIZE", 4096), ("BIG_ENDIAN", cfg!(target_endian = "big") as i32), ("UNALIGNED_ACCESS", 1)] {
+            if let Some(index) = self.vm.classes.class(constants).class.fields.iter().position(|field| field.field_name == name && field.is_static()) {
+                self.vm.set_static(constants, index, VMValue::Int(value));
+            }
+        }
+
+        let scope = self.vm.handle_scope();
+        let result = self.create_main_thread();
+        self.vm.release_handles(scope);
+        result?;
+        // As in HotSpot, initializing Method has AccessibleObject hand its secrets to reflection.
+        for name in ["java/lang/reflect/Method", "java/lang/ref/Finalizer"] {
+            let class = self.vm.bootstrap_class(name)?;
+            self.initialize_class(class)?;
+        }
+        self.invoke(system, init_phase1, Vec::new())?;
+        Ok(())
+    }
+
+    fn create_main_thread(&mut self) -> Result<(), InterpreterError> {
+        let thread_group = self.vm.bootstrap_class("java/lang/ThreadGroup")?;
+        let system_group = self.vm.new_object(thread_group)?;
+        let system_group = self.vm.new_handle(system_group);
+        self.invoke_constructor(thread_group, "()V", vec![VMValue::Reference(self.vm.handle(system_group))])?;
+        let main_group = self.vm.new_object(thread_group)?;
+        let main_group = self.vm.new_handle(main_group);
+        let name = self.vm.new_string("main")?;
+        let name = self.vm.new_handle(name);
+        let args = vec![VMValue::Reference(self.vm.handle(main_group)), VMValue::Reference(self.vm.handle(system_group)), VMValue::Reference(self.vm.handle(name))];
+        self.invoke_constructor(thread_group, "(Ljava/lang/ThreadGroup;Ljava/lang/String;)V", args)?;
+
+        // The constructor of the thread asks for the current thread, which has to be complete enough
+        // to be its own parent.
+        let thread_class = self.vm.bootstrap_class("java/lang/Thread")?;
+        let thread = self.vm.new_object(thread_class)?;
+        self.vm.set_field(thread, "priority", VMValue::Int(5))?;
+        // Alive and runnable, see `jdk.internal.misc.VM.toThreadState`.
+        self.vm.set_field(thread, "threadStatus", VMValue::Int(5))?;
+        self.vm.set_field(thread, "eetop", VMValue::Long(1))?;
+        self.thread = Some(self.vm.new_global_handle(thread));
+        let args = vec![VMValue::Reference(thread), VMValue::Reference(self.vm.handle(main_group)), VMValue::Reference(self.vm.handle(name))];
+        self.invoke_constructor(thread_class, "(Ljava/lang/ThreadGroup;Ljava/lang/String;)V", args)?;
+        Ok(())
+    }
+
+    /// Runs a constructor declared by `class` on an object the VM allocated, the receiver of `args`.
+    pub(crate) fn invoke_constructor(&mut self, class: ClassId, descriptor: &str, args: Vec<VMValue>) -> Result<(), InterpreterError> {
+        let index = self.vm.classes.class(class).class.methods.iter().position(|m| m.method_name == "<init>" && m.descriptor == descriptor)
+            .ok_or_else(|| InterpreterError::exception("java/lang/NoSuchMethodError", format!("{}.<init>{}", self.vm.classes.class(class).java_name(), descriptor)))?;
+        self.invoke(class, index, args)?;
+        Ok(())
+    }
+
+    /// `Thread.start0`. The VM has a single thread, so the thread runs to completion on a stack of
+    /// its own before `start` returns, as if it had been scheduled first. Daemon threads are not
+    /// run: the reference handler and finalizer threads of `java.base` are among them, whose work
+    /// the VM does at safepoints (see [`VMEnv::process_pending_references`]).
+    pub(crate) fn start_thread(&mut self, thread: ObjRef) -> Result<(), InterpreterError> {
+        self.vm.set_field(thread, "eetop", VMValue::Long(1))?;
+        self.vm.set_field(thread, "threadStatus", VMValue::Int(5))?;
+        if self.vm.get_field(thread, "daemon")?.as_int()? != 0 {
+            return Ok(())
+        }
+        let handle = self.vm.new_global_handle(thread);
+        let starter = self.thread.replace(handle);
+        let frames = std::mem::take(&mut self.frames);
+        let result = self.run_thread(handle);
+        self.frames = frames;
+        self.thread = starter;
+        let thread = self.vm.global_handle(handle).unwrap();
+        self.vm.delete_global_handle(handle);
+        result?;
+        self.vm.set_field(thread, "eetop", VMValue::Long(0))?;
+        self.vm.set_field(thread, "threadStatus", VMValue::Int(2))?;
+        Ok(())
+    }
+
+    /// Calls `run()` on a thread, then hands an exception it throws to its uncaught exception
+    /// handler and lets the thread clean up as it exits.
+    fn run_thread(&mut self, handle: GlobalHandle) -> Result<(), InterpreterError> {
+        let thread = self.vm.global_handle(handle).unwrap();
+        let class = self.vm.heap.class_of(thread);
+        let (run_class, run) = self.vm.classes.select_method(class, "run", "()V")
+            .ok_or_else(|| InterpreterError::exception("java/lang/AbstractMethodError", "java.lang.Thread.run()"))?;
+        if let Err(InterpreterError::Exception(mut exception)) = self.invoke(run_class, run, vec![VMValue::Reference(thread)]) {
+            let throwable = self.throwable(&mut exception)?;
+            if let Some((declaring, index)) = self.vm.classes.find_method(class, "dispatchUncaughtException", "(Ljava/lang/Throwable;)V") {
+                let thread = self.vm.global_handle(handle).unwrap();
+                ignore_exception(self.invoke(declaring, index, vec![VMValue::Reference(thread), VMValue::Reference(throwable)]))?;
             }
         }
+        if let Some((declaring, index)) = self.vm.classes.find_method(class, "exit", "()V") {
+            let thread = self.vm.global_handle(handle).unwrap();
+            ignore_exception(self.invoke(declaring, index, vec![VMValue::Reference(thread)]))?;
+        }
         Ok(())
     }
 
+    /// The `java.lang.Thread` of the running thread, once the VM has booted a JDK.
+    pub fn current_thread(&self) -> Option<ObjRef> {
+        self.thread.and_then(|thread| self.vm.global_handle(thread))
+    }
+
+    /// `Class.forName`: loads a class through `loader` and optionally initializes it.
+    pub fn class_for_name(&mut self, loader: LoaderId, name: &str, initialize: bool) -> Result<ClassId, InterpreterError> {
+        let id = self.vm.classes.load_class(loader, &name.replace('.', "/"))?;
+        if initialize {
+            self.initialize_class(id)?;
+        }
+        Ok(id)
+    }
+
+    /// Calls a static method by name, initializing its class first as `invokestatic` would.
+    pub fn invoke_static(&mut self, class: ClassId, name: &str, descriptor: &str, args: Vec<VMValue>) -> Result<Option<VMValue>, InterpreterError> {
+        let class_name = self.vm.classes.class(class).name.clone();
+        let (declaring_class, index) = self.vm.classes.find_method(class, name, descriptor)
+            .ok_or_else(|| InterpreterError::exception("java/lang/NoSuchMethodError", format!("{}.{}{}", class_name.replace('/', "."), name, descriptor)))?;
+        // Initialization may move the objects passed in, so handles hold them meanwhile.
+        let scope = self.vm.handle_scope();
+        let handles: Vec<Option<Handle>> = args.iter().map(|arg| match arg {
+            VMValue::Reference(obj) => Some(self.vm.new_handle(*obj)),
+            _ => None
+        }).collect();
+        let initialized = self.initialize_class(declaring_class);
+        let args: Vec<VMValue> = args.into_iter().zip(handles)
+            .map(|(arg, handle)| handle.map_or(arg, |handle| VMValue::Reference(self.vm.handle(handle))))
+            .collect();
+        self.vm.release_handles(scope);
+        initialized?;
+        self.invoke(declaring_class, index, args)
+    }
+
+    /// Does the work of HotSpot's reference handler and finalizer threads: hands each reference
+    /// the collector cleared to `Reference.processPendingReference`, which enqueues it, then calls
+    /// `finalize()` on the objects that became finalizable. Like those threads, it ignores the
+    /// exceptions Java code throws and runs on a stack of its own. The interpreter runs it at every
+    /// instruction boundary; embedders may call it between invocations.
+    pub fn process_pending_references(&mut self) -> Result<(), InterpreterError> {
+        if self.processing_references || !self.vm.has_pending_references() {
+            return Ok(())
+        }
+        self.processing_references = true;
+        let frames = std::mem::take(&mut self.frames);
+        let result = self.run_reference_handler();
+        self.frames = frames;
+        self.processing_references = false;
+        result
+    }
+
+    fn run_reference_handler(&mut self) -> Result<(), InterpreterError> {
+        let reference = self.vm.classes.load_class(LoaderId::BOOTSTRAP, "java/lang/ref/Reference")?;
+        let finalizer = self.vm.classes.load_class(LoaderId::BOOTSTRAP, "java/lang/ref/Finalizer")?;
+        if let Some(process) = self.vm.classes.find_method(reference, "processPendingReferences", "()V") {
+            return self.run_jdk_reference_handler(process, finalizer)
+        }
+        // Finalizers may allocate and so queue more references.
+        while self.vm.has_pending_references() {
+            let mut finalize = false;
+            while let Some(pending) = self.vm.take_pending_reference() {
+                let kind = self.vm.classes.class(self.vm.heap.class_of(pending)).reference_kind;
+                finalize |= kind == Some(ReferenceKind::Final);
+                let args = vec![VMValue::Reference(pending)];
+                ignore_exception(self.invoke_static(reference, "processPendingReference", "(Ljava/lang/ref/Reference;)V", args))?;
+            }
+            if !finalize {
+                continue
+            }
+            while let Some(VMValue::Reference(finalizee)) = self.invoke_static(finalizer, "next", "()Ljava/lang/Object;", Vec::new())? {
+                let (class, index) = self.vm.classes.find_method(self.vm.heap.class_of(finalizee), "finalize", "()V")
+                    .ok_or_else(|| InterpreterError::exception("java/lang/NoSuchMethodError", "java.lang.Object.finalize()V"))?;
+                ignore_exception(self.invoke(class, index, vec![VMValue::Reference(finalizee)]))?;
+            }
+        }
+        Ok(())
+    }
+
+    /// The reference handler of a JDK's `java.base`, whose `Reference.processPendingReferences`
+    /// takes the pending references from the VM itself. Objects to finalize are then taken from
+    /// the queue of `Finalizer` as its finalizer thread does.
+    fn run_jdk_reference_handler(&mut self, (reference, process): (ClassId, usize), finalizer: ClassId) -> Result<(), InterpreterError> {
+        while self.vm.has_pending_references() {
+            let finalize = self.vm.pending_references.iter()
+                .any(|pending| self.vm.classes.class(self.vm.heap.class_of(*pending)).reference_kind == Some(ReferenceKind::Final));
+            ignore_exception(self.invoke(reference, process, Vec::new()))?;
+            if !finalize {
+                continue
+            }
+            let queue = self.invoke_static(finalizer, "getQueue", "()Ljava/lang/ref/ReferenceQueue;", Vec::new())?.unwrap_or(VMValue::Null).as_reference()?;
+            if let Some(queue) = queue {
+                let scope = self.vm.new_handle(queue);
+                let result = self.run_jdk_finalizers(scope);
+                self.vm.release_handles(scope);
+                result?;
+            }
+        }
+        Ok(())
+    }
+
+    fn run_jdk_finalizers(&mut self, queue: Handle) -> Result<(), InterpreterError> {
+        let (queue_class, poll) = self.vm.classes.find_method(self.vm.heap.class_of(self.vm.handle(queue)), "poll", "()Ljava/lang/ref/Reference;")
+            .ok_or_else(|| InterpreterError::exception("java/lang/NoSuchMethodError", "java.lang.ref.ReferenceQueue.poll()"))?;
+        let shared_secrets = self.vm.classes.load_class(LoaderId::BOOTSTRAP, "jdk/internal/access/SharedSecrets")?;
+        while let Some(VMValue::Reference(finalizer)) = self.invoke(queue_class, poll, vec![VMValue::Reference(self.vm.handle(queue))])? {
+            let finalizer = self.vm.new_handle(finalizer);
+            let java_lang_access = self.invoke_static(shared_secrets, "getJavaLangAccess", "()Ljdk/internal/access/JavaLangAccess;", Vec::new())?;
+            let (class, index) = self.vm.classes.find_method(self.vm.heap.class_of(self.vm.handle(finalizer)), "runFinalizer", "(Ljdk/internal/access/JavaLangAccess;)V")
+                .ok_or_else(|| InterpreterError::exception("java/lang/NoSuchMethodError", "java.lang.ref.Finalizer.runFinalizer"))?;
+            let args = vec![VMValue::Reference(self.vm.handle(finalizer)), java_lang_access.unwrap_or(VMValue::Null)];
+            self.vm.release_handles(finalizer);
+            ignore_exception(self.invoke(class, index, args))?;
+        }
+        Ok(())
+    }
+
+    /// Runs the initialization procedure of JVMS 5.5. The VM is single threaded, so a class that
+    /// is being initialized can only be requested again by its own initializer, which proceeds.
+    pub fn initialize_class(&mut self, id: ClassId) -> Result<(), InterpreterError> {
+        match self.vm.classes.class(id).state {
+            ClassState::Initialized | ClassState::BeingInitialized => return Ok(()),
+            ClassState::Erroneous => {
+                let name = self.vm.classes.class(id).java_name();
+                return Err(InterpreterError::exception("java/lang/NoClassDefFoundError", format!("Could not initialize class {}", name)))
+            }
+            _ => {}
+        }
+        self.vm.classes.link(id)?;
+        self.vm.classes.class_mut(id).state = ClassState::BeingInitialized;
+        match self.run_initializers(id) {
+            Ok(()) => {
+                self.vm.classes.class_mut(id).state = ClassState::Initialized;
+                Ok(())
+            }
+            Err(InterpreterError::Exception(exception)) => {
+                self.vm.classes.class_mut(id).state = ClassState::Erroneous;
+                if self.is_error(&exception.class_name) {
+                    Err(InterpreterError::Exception(exception))
+                } else {
+                    Err(InterpreterError::Exception(JavaException::new("java/lang/ExceptionInInitializerError", None).with_cause(exception)))
+                }
+            }
+            Err(e) => {
+                self.vm.classes.class_mut(id).state = ClassState::Erroneous;
+                Err(e)
+            }
+        }
+    }
+
+    fn run_initializers(&mut self, id: ClassId) -> Result<(), InterpreterError> {
+        let class = self.vm.classes.class(id);
+        let class_file = class.class.clone();
+        let is_interface = class.is_interface();
+        let super_class = class.super_class;
+        let interfaces = class.interfaces.clone();
+
+        for (index, field) in class_file.fields.iter().enumerate() {
+            if !field.is_static() {
+                continue
+            }
+            let value = match field.constant_value().and_then(|index| class_file.constant_pool.get(index)) {
+                Some(ConstantPoolTag::Integer(v)) => VMValue::Int(*v as i32),
+                Some(ConstantPoolTag::Float(v)) => VMValue::Float(f32::from_bits(*v)),
+                Some(ConstantPoolTag::Long(..)) => VMValue::Long(class_file.constant_pool.long(field.constant_value().unwrap())?),
+                Some(ConstantPoolTag::Double(..)) => VMValue::Double(class_file.constant_pool.double(field.constant_value().unwrap())?),
+                Some(ConstantPoolTag::String(utf8)) => VMValue::Reference(self.vm.intern_utf16(&class_file.constant_pool.utf16(*utf8)?)?),
+                _ => continue
+            };
+            self.vm.set_static(id, index, value);
+        }
+
+        if !is_interface {
+            if let Some(super_class) = super_class {
+                self.initialize_class(super_class)?;
+            }
+            // Superinterfaces are only initialized when they declare default methods.
+            for interface in interfaces {
+                let declares_defaults = self.vm.classes.class(interface).class.methods.iter()
+                    .any(|m| !m.is_static() && !m.access_flags.contains(&AccessFlags::Abstract));
+                if declares_defaults {
+                    self.initialize_class(interface)?;
+                }
+            }
+        }
+
+        if let Some(index) = class_file.methods.iter().position(|m| m.method_name == "<clinit>" && m.descriptor == "()V") {
+            self.invoke(id, index, Vec::new())?;
+        }
+        Ok(())
+    }
+
+    /// Whether the named exception class is a `java.lang.Error`, which initialization failures rethrow unwrapped.
+    fn is_error(&mut self, class_name: &str) -> bool {
+        let error = self.vm.classes.load_class(LoaderId::BOOTSTRAP, "java/lang/Error");
+        let class = self.vm.classes.load_class(LoaderId::BOOTSTRAP, class_name);
+        match (class, error) {
+            (Ok(class), Ok(error)) => self.vm.classes.is_subclass_of(class, error),
+            _ => false
+        }
+    }
+
+    /// Runs a method with the given arguments in a new frame.
+    pub fn invoke(&mut self, class: ClassId, method_index: usize, args: Vec<VMValue>) -> Result<Option<VMValue>, InterpreterError> {
+        let class_file = self.vm.classes.class(class).class.clone();
+        let method = &class_file.methods[method_index];
+        if method.access_flags.contains(&AccessFlags::Native) {
+            // Native code gets its arguments off the interop stack, so handles keep them alive. They
+            // are not read back: natives that allocate have to keep track of moved objects themselves.
+            let scope = self.vm.handle_scope();
+            for arg in &args {
+                if let VMValue::Reference(obj) = arg {
+                    self.vm.new_handle(*obj);
+                }
+            }
+            let result = self.invoke_native(class, method_index, args);
+            self.vm.release_handles(scope);
+            return result
+        }
+        if method.access_flags.contains(&AccessFlags::Abstract) {
+            return Err(InterpreterError::exception("java/lang/AbstractMethodError", method_display_name(&class_file.class_name, method)))
+        }
+        if self.frames.len() >= self.max_frames {
+            return Err(InterpreterError::Exception(JavaException::new("java/lang/StackOverflowError", None)))
+        }
+
+        let locals = self.vm.stack_pointer();
+        let mut slots = 0;
+        for arg in args {
+            let arg = arg.computational();
+            slots += if arg.is_category2() { 2 } else { 1 };
+            self.vm.push(arg);
+            if arg.is_category2() {
+                self.vm.push(VMValue::Null);
+            }
+        }
+        for _ in slots..method.max_locals as usize {
+            self.vm.push(VMValue::Null);
+        }
+        self.frames.push(Frame { class, class_file: class_file.clone(), method: method_index, pc: 0, locals });
+        let handles = self.vm.handle_scope();
+        let result = self.run_frame();
+        // Handles an instruction failed to release before throwing go with its frame.
+        self.vm.release_handles(handles);
+        self.frames.pop();
+        self.vm.truncate(locals);
+        result
+    }
+
+    /// Runs a native method through its binding in [`VMEnv::natives`], or else the function a JNI
+    /// library exports for it.
+    fn invoke_native(&mut self, class: ClassId, method_index: usize, args: Vec<VMValue>) -> Result<Option<VMValue>, InterpreterError> {
+        let class_file = self.vm.classes.class(class).class.clone();
+        let method = &class_file.methods[method_index];
+        if let Some(lambda) = self.lambdas.get(&class).cloned() {
+            return self.invoke_lambda(&lambda, &method.descriptor, args)
+        }
+        if let Some(function) = self.natives.lookup(&class_file.class_name, &method.method_name, &method.descriptor) {
+            return function(self, &args)
+        }
+        match self.find_jni_function(class, method_index) {
+            Some(function) => self.invoke_jni(function, class, method, &args),
+            None => Err(InterpreterError::exception("java/lang/UnsatisfiedLinkError", method_display_name(&class_file.class_name, method)))
+        }
+    }
+
+    fn run_frame(&mut self) -> Result<Option<VMValue>, InterpreterError> {
+        let frame = self.frames.last().unwrap();
+        let class_file = frame.class_file.clone();
+        let code = &class_file.methods[frame.method].code;
+        let mut pc = 0;
+        loop {
+            if self.vm.has_pending_references() {
+                self.process_pending_references()?;
+            }
+            let (opcode, next_pc) = self.interpreter.decode(code, pc)?;
+            self.frames.last_mut().unwrap().pc = pc;
+            let flow = match self.step(opcode) {
+                Err(InterpreterError::Exception(exception)) => {
+                    pc = self.catch_exception(exception)?;
+                    continue
+                }
+                other => other?
+            };
+            match flow {
+                Flow::Next => pc = next_pc,
+                Flow::Branch(offset) => {
+                    let target = pc as i64 + offset as i64;
+                    if target < 0 || target as usize >= code.len() {
+                        return Err(InterpreterError::InvalidBranchTarget(target.max(0) as usize))
+                    }
+                    pc = target as usize;
+                }
+                Flow::Return(value) => return Ok(value)
+            }
+            if pc >= code.len() {
+                return Err(InterpreterError::TruncatedInstruction(pc))
+            }
+        }
+    }
+
+    /// Looks for a handler of `exception` in the current frame's exception table (JVMS 2.10).
+    /// Returns the handler's pc with the operand stack holding just the exception, or the
+    /// exception to rethrow to the caller. A `catch_type` of 0 catches everything (`finally`).
+    fn catch_exception(&mut self, mut exception: JavaException) -> Result<usize, InterpreterError> {
+        if exception.stack_trace.is_empty() {
+            exception.stack_trace = self.stack_trace();
+        }
+        // Only an error the VM raised has no object yet; Java code may throw its own.
+        if exception.object.is_none() && exception.class_name == "java/lang/OutOfMemoryError" {
+            self.dump_heap_on_out_of_memory(&exception);
+        }
+        let frame = self.frame();
+        let (class, class_file, pc) = (frame.class, frame.class_file.clone(), frame.pc);
+        let handlers: Vec<(u16, u16)> = frame.method().exception_table.iter()
+            .filter(|entry| entry.pc_start as usize <= pc && pc < entry.pc_end as usize)
+            .map(|entry| (entry.handler_pc, entry.catch_type))
+            .collect();
+        for (handler_pc, catch_type) in handlers {
+            let throwable = self.throwable(&mut exception)?;
+            if catch_type != 0 {
+                let catch_class = self.vm.classes.resolve_class(class, class_file.constant_pool.class_name(catch_type)?)?;
+                let thrown_class = self.vm.heap.class_of(throwable);
+                if !self.vm.classes.is_subclass_of(thrown_class, catch_class) {
+                    continue
+                }
+            }
+            let stack_base = self.frame().stack_base();
+            self.vm.truncate(stack_base);
+            self.vm.push(VMValue::Reference(throwable));
+            return Ok(handler_pc as usize)
+        }
+        Err(InterpreterError::Exception(exception))
+    }
+
+    /// The active frames as a stack trace, innermost first.
+    pub(crate) fn stack_trace(&self) -> Vec<StackFrame> {
+        self.frames.iter().rev().map(|frame| StackFrame {
+            class_name: frame.class_file.class_name.clone(),
+            method_name: frame.method().method_name.clone(),
+            source_file: frame.class_file.source_file_name.clone(),
+            line_number: frame.method().line_number(frame.pc)
+        }).collect()
+    }
+
+    /// `Throwable.fillInStackTrace(int)`: records the active frames as the throwable's stack
+    /// trace, leaving out `fillInStackTrace` itself and the constructors of the throwable.
+    /// Returns the throwable, which may have moved.
+    pub(crate) fn fill_in_stack_trace(&mut self, throwable: ObjRef) -> Result<ObjRef, InterpreterError> {
+        let class = self.vm.heap.class_of(throwable);
+        let skipped = self.frames.iter().rev()
+            .take_while(|frame| frame.method().method_name == "fillInStackTrace")
+            .count();
+        let constructors = self.frames.iter().rev().skip(skipped)
+            .take_while(|frame| frame.method().method_name == "<init>" && self.vm.classes.is_subclass_of(class, frame.class))
+            .count();
+        let mut trace = self.stack_trace();
+        trace.drain(..skipped + constructors);
+        let scope = self.vm.new_handle(throwable);
+        let elements = self.stack_trace_elements(&trace);
+        let throwable = self.vm.handle(scope);
+        self.vm.release_handles(scope);
+        self.set_stack_trace(throwable, elements?)?;
+        Ok(throwable)
+    }
+
+    /// Stores the elements of a throwable's stack trace. The `Throwable` of a JDK keeps what the
+    /// VM recorded in `backtrace` and builds its `stackTrace` from it when asked.
+    fn set_stack_trace(&mut self, throwable: ObjRef, elements: ObjRef) -> Result<(), InterpreterError> {
+        let class = self.vm.heap.class_of(throwable);
+        if self.vm.classes.instance_field(class, "backtrace").is_none() {
+            return self.vm.set_field(throwable, "stackTrace", VMValue::Reference(elements))
+        }
+        let depth = self.vm.heap.array_length(elements) as i32;
+        self.vm.set_field(throwable, "backtrace", VMValue::Reference(elements))?;
+        self.vm.set_field(throwable, "depth", VMValue::Int(depth))
+    }
+
+    /// Creates the `StackTraceElement[]` of a stack trace.
+    fn stack_trace_elements(&mut self, trace: &[StackFrame]) -> Result<ObjRef, InterpreterError> {
+        let element_class = self.vm.bootstrap_class("java/lang/StackTraceElement")?;
+        let array_class = self.vm.classes.load_class(LoaderId::BOOTSTRAP, "[Ljava/lang/StackTraceElement;")?;
+        let array = self.vm.new_array(array_class, trace.len() as i32)?;
+        let scope = self.vm.new_handle(array);
+        let result = self.fill_stack_trace_elements(scope, element_class, trace);
+        let array = self.vm.handle(scope);
+        self.vm.release_handles(scope);
+        result.map(|_| array)
+    }
+
+    /// Fills the array held by `array` with new elements, reading it back after every allocation
+    /// in case the collector moved it.
+    fn fill_stack_trace_elements(&mut self, array: Handle, element_class: ClassId, trace: &[StackFrame]) -> Result<(), InterpreterError> {
+        let element_type = FieldType::Object("java/lang/StackTraceElement".to_string());
+        let has_class_object = self.vm.classes.instance_field(element_class, "declaringClassObject").is_some();
+        for (index, frame) in trace.iter().enumerate() {
+            let element = self.vm.new_object(element_class)?;
+            self.vm.store_element(self.vm.handle(array), index as u32, &element_type, VMValue::Reference(element));
+            let strings = [
+                ("declaringClass", Some(frame.class_name.replace('/', "."))),
+                ("methodName", Some(frame.method_name.clone())),
+                ("fileName", frame.source_file.clone())
+            ];
+            for (field, value) in strings {
+                if let Some(value) = value {
+                    let string = self.vm.new_string(&value)?;
+                    let element = self.vm.heap.read_element(self.vm.handle(array), index as u32, &element_type).as_reference()?.unwrap();
+                    self.vm.set_field(element, field, VMValue::Reference(string))?;
+                }
+            }
+            let element = self.vm.heap.read_element(self.vm.handle(array), index as u32, &element_type).as_reference()?.unwrap();
+            self.vm.set_field(element, "lineNumber", VMValue::Int(frame.line_number.map(i32::from).unwrap_or(-1)))?;
+            // A JDK's elements also refer to the class itself, by which they tell how to format it.
+            if has_class_object {
+                let class = self.vm.classes.classes().iter().find(|class| class.name == frame.class_name).map(|class| class.id);
+                if let Some(class) = class {
+                    let mirror = self.vm.class_mirror(class)?;
+                    let element = self.vm.heap.read_element(self.vm.handle(array), index as u32, &element_type).as_reference()?.unwrap();
+                    self.vm.set_field(element, "declaringClassObject", VMValue::Reference(mirror))?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Reads back the `stackTrace` of a throwable, or the `backtrace` of a JDK's throwable whose
+    /// `stackTrace` has not been built yet; an unfilled trace is empty.
+    fn read_stack_trace(&self, throwable: ObjRef) -> Result<Vec<StackFrame>, InterpreterError> {
+        let throwable_class = self.vm.heap.class_of(throwable);
+        let unassigned = self.vm.classes.find_field(throwable_class, "UNASSIGNED_STACK", "[Ljava/lang/StackTraceElement;")
+            .and_then(|(class, _)| self.vm.classes.class(class).static_value("UNASSIGNED_STACK"));
+        let array = match self.vm.get_field(throwable, "stackTrace")?.as_reference()? {
+            Some(array) if unassigned != Some(VMValue::Reference(array)) => array,
+            _ if self.vm.classes.instance_field(throwable_class, "backtrace").is_some() => {
+                match self.vm.get_field(throwable, "backtrace")?.as_reference()? {
+                    Some(backtrace) => backtrace,
+                    None => return Ok(Vec::new())
+                }
+            }
+            _ => return Ok(Vec::new())
+        };
+        let element_type = FieldType::Object("java/lang/StackTraceElement".to_string());
+        let string = |value: VMValue| -> Result<Option<String>, InterpreterError> {
+            value.as_reference()?.map(|s| self.vm.string_value(s)).transpose()
+        };
+        let mut trace: Vec<StackFrame> = Vec::new();
+        for index in 0..self.vm.heap.array_length(array) {
+            let element = match self.vm.heap.read_element(array, index, &element_type).as_reference()? {
+                Some(element) => element,
+                None => continue
+            };
+            let line_number = self.vm.get_field(element, "lineNumber")?.as_int()?;
+            trace.push(StackFrame {
+                class_name: string(self.vm.get_field(element, "declaringClass")?)?.unwrap_or_default().replace('.', "/"),
+                method_name: string(self.vm.get_field(element, "methodName")?)?.unwrap_or_default(),
+                source_file: string(self.vm.get_field(element, "fileName")?)?,
+                line_number: u16::try_from(line_number).ok()
+            });
+        }
+        Ok(trace)
+    }
+
+    /// The `Throwable` object of an exception, allocating it for exceptions raised by the VM.
+    /// Those are constructed with their class's no-argument constructor, then get their message
+    /// and cause stored directly, as HotSpot does for its preallocated exceptions.
+    pub fn throwable(&mut self, exception: &mut JavaException) -> Result<ObjRef, InterpreterError> {
+        if let Some(object) = exception.object {
+            return Ok(object)
+        }
+        // The cause is created first. It and the new object are held by handles, as objects move
+        // in a generational heap.
+        let scope = self.vm.handle_scope();
+        let cause = match exception.cause.as_deref_mut() {
+            Some(cause) => {
+                let cause = self.throwable(cause)?;
+                Some(self.vm.new_handle(cause))
+            }
+            None => None
+        };
+        let result = self.new_throwable(exception, cause);
+        self.vm.release_handles(scope);
+        let object = result?;
+        exception.object = Some(object);
+        Ok(object)
+    }
+
+    fn new_throwable(&mut self, exception: &JavaException, cause: Option<Handle>) -> Result<ObjRef, InterpreterError> {
+        let class = self.vm.bootstrap_class(&exception.class_name)?;
+        self.initialize_class(class)?;
+        let object = self.vm.new_object(class)?;
+        let object_handle = self.vm.new_handle(object);
+        if let Some((declaring, index)) = self.vm.classes.find_method(class, "<init>", "()V") {
+            self.invoke(declaring, index, vec![VMValue::Reference(object)])?;
+        }
+        if let Some(message) = &exception.message {
+            let message = self.vm.new_string(message)?;
+            self.vm.set_field(self.vm.handle(object_handle), "detailMessage", VMValue::Reference(message))?;
+        }
+        if let Some(cause) = cause {
+            self.vm.set_field(self.vm.handle(object_handle), "cause", VMValue::Reference(self.vm.handle(cause)))?;
+        }
+        // The constructor recorded where the object was allocated, not where the exception was raised.
+        if !exception.stack_trace.is_empty() {
+            let elements = self.stack_trace_elements(&exception.stack_trace)?;
+            self.set_stack_trace(self.vm.handle(object_handle), elements)?;
+        }
+        Ok(self.vm.handle(object_handle))
+    }
+
+    /// Describes a `Throwable` object thrown by Java code, following its chain of causes up to
+    /// the first one seen before.
+    pub fn java_exception(&self, throwable: ObjRef) -> Result<JavaException, InterpreterError> {
+        let mut chain: Vec<(ObjRef, JavaException)> = Vec::new();
+        let mut next = Some(throwable);
+        while let Some(object) = next.filter(|object| chain.iter().all(|(seen, _)| seen != object)) {
+            let class_name = self.vm.classes.class(self.vm.heap.class_of(object)).name.clone();
+            let message = match self.vm.get_field(object, "detailMessage")?.as_reference()? {
+                Some(message) => Some(self.vm.string_value(message)?),
+                None => None
+            };
+            let mut exception = JavaException::new(&class_name, message);
+            exception.object = Some(object);
+            exception.stack_trace = self.read_stack_trace(object)?;
+            chain.push((object, exception));
+            next = self.vm.get_field(object, "cause")?.as_reference()?;
+        }
+        let (_, mut exception) = chain.pop().unwrap();
+        while let Some((_, outer)) = chain.pop() {
+            exception = outer.with_cause(exception);
+        }
+        Ok(exception)
+    }
+
+    fn frame(&self) -> &Frame {
+        self.frames.last().unwrap()
+    }
+
+    fn pop(&mut self) -> Result<VMValue, InterpreterError> {
+        if self.vm.stack_pointer() <= self.frame().stack_base() {
+            return Err(InterpreterError::TypeMismatch("value".to_string(), "empty operand stack".to_string()))
+        }
+        Ok(self.vm.pop())
+    }
+
+    fn pop_int(&mut self) -> Result<i32, InterpreterError> {
+        self.pop()?.as_int()
+    }
+
+    /// Pops values totalling `words` stack words, returning them bottom first.
+    fn pop_words(&mut self, words: usize) -> Result<Vec<VMValue>, InterpreterError> {
+        let mut values: Vec<VMValue> = Vec::new();
+        let mut count = 0;
+        while count < words {
+            let value = self.pop()?;
+            count += if value.is_category2() { 2 } else { 1 };
+            values.push(value);
+        }
+        if count != words {
+            return Err(InterpreterError::TypeMismatch(format!("{} stack words", words), "a split long or double".to_string()))
+        }
+        values.reverse();
+        Ok(values)
+    }
+
+    /// The `dup*` family: copies the top `top` words below the `under` words beneath them.
+    fn dup_words(&mut self, top: usize, under: usize) -> Result<(), InterpreterError> {
+        let top = self.pop_words(top)?;
+        let under = self.pop_words(under)?;
+        for value in top.iter().chain(under.iter()).chain(top.iter()) {
+            self.vm.push(*value);
+        }
+        Ok(())
+    }
+
+    fn load(&mut self, index: u16) {
+        let value = self.vm.get(self.frame().locals + index as usize);
+        self.vm.push(value);
+    }
+
+    fn store(&mut self, index: u16) -> Result<(), InterpreterError> {
+        let value = self.pop()?;
+        let slot = self.frame().locals + index as usize;
+        self.vm.set(slot, value);
+        if value.is_category2() {
+            self.vm.set(slot + 1, VMValue::Null);
+        }
+        Ok(())
+    }
+
+    fn current_class(&self) -> ClassId {
+        self.frame().class
+    }
+
+    /// Resolves a field reference of the current class to its declaring class and field index.
+    fn resolve_field(&mut self, index: u16) -> Result<(ClassId, usize), InterpreterError> {
+        let class_file = self.frame().class_file.clone();
+        let (class_name, name, descriptor) = class_file.constant_pool.member_ref(index)?;
+        let class = self.vm.classes.resolve_class(self.current_class(), class_name)?;
+        self.vm.classes.find_field(class, name, descriptor)
+            .ok_or_else(|| InterpreterError::exception("java/lang/NoSuchFieldError", name.as_str()))
+    }
+
+    /// Resolves a method reference of the current class to its declaring class and method index.
+    fn resolve_method(&mut self, index: u16) -> Result<(ClassId, usize), InterpreterError> {
+        let class_file = self.frame().class_file.clone();
+        let (class_name, name, descriptor) = class_file.constant_pool.member_ref(index)?;
+        let class = self.vm.classes.resolve_class(self.current_class(), class_name)?;
+        self.vm.classes.find_method(class, name, descriptor).ok_or_else(|| {
+            let return_and_params = MethodDescriptor::parse(descriptor).map(|d| {
+                let parameters: Vec<String> = d.parameters.iter().map(FieldType::java_name).collect();
+                (d.return_type.as_ref().map(FieldType::java_name).unwrap_or_else(|| "void".to_string()), parameters.join(", "))
+            });
+            let message = match return_and_params {
+                Ok((return_type, parameters)) => format!("'{} {}.{}({})'", return_type, class_name.replace('/', "."), name, parameters),
+                Err(_) => format!("{}.{}{}", class_name.replace('/', "."), name, descriptor)
+            };
+            InterpreterError::exception("java/lang/NoSuchMethodError", message)
+        })
+    }
+
+    fn resolve_static_field(&mut self, index: u16) -> Result<(ClassId, usize, FieldType), InterpreterError> {
+        let (class, field_index) = self.resolve_field(index)?;
+        let class_file = self.vm.classes.class(class).class.clone();
+        let field = &class_file.fields[field_index];
+        if !field.is_static() {
+            return Err(InterpreterError::exception("java/lang/IncompatibleClassChangeError",
+                format!("Expected static field {}.{}", class_file.class_name.replace('/', "."), field.field_name)))
+        }
+        let field_type = FieldType::parse(&field.descriptor).map_err(InterpreterError::from)?;
+        self.initialize_class(class)?;
+        Ok((class, field_index, field_type))
+    }
+
+    /// Resolves an instance field reference to its type and offset within objects.
+    fn resolve_instance_field(&mut self, index: u16) -> Result<(FieldType, u32), InterpreterError> {
+        let (class, field_index) = self.resolve_field(index)?;
+        let runtime_class = self.vm.classes.class(class);
+        let field = &runtime_class.class.fields[field_index];
+        match (runtime_class.layout.field_offsets[field_index], &runtime_class.layout.field_types[field_index]) {
+            (Some(offset), Some(field_type)) => Ok((field_type.clone(), offset)),
+            _ => Err(InterpreterError::exception("java/lang/IncompatibleClassChangeError",
+                format!("Expected non-static field {}.{}", runtime_class.java_name(), field.field_name)))
+        }
+    }
+
+    fn pop_object(&mut self) -> Result<ObjRef, InterpreterError> {
+        match self.pop()? {
+            VMValue::Reference(obj) => Ok(obj),
+            VMValue::Null => Err(InterpreterError::Exception(JavaException::new("java/lang/NullPointerException", None))),
+            other => Err(InterpreterError::TypeMismatch("reference".to_string(), other.to_string()))
+        }
+    }
+
+    /// Pops the arguments of a method, plus its receiver unless it is static; the receiver comes first.
+    fn pop_arguments(&mut self, method: &Method) -> Result<Vec<VMValue>, InterpreterError> {
+        let descriptor = MethodDescriptor::parse(&method.descriptor).map_err(InterpreterError::from)?;
+        let count = descriptor.parameters.len() + if method.is_static() { 0 } else { 1 };
+        let mut args: Vec<VMValue> = Vec::with_capacity(count);
+        for _ in 0..count {
+            args.push(self.pop()?);
+        }
+        args.reverse();
+        Ok(args)
+    }
+
+    /// Runs an instance method invocation once the method to run has been selected.
+    fn invoke_instance(&mut self, class: ClassId, method_index: usize, args: Vec<VMValue>) -> Result<(), InterpreterError> {
+        if let Some(value) = self.invoke(class, method_index, args)? {
+            self.vm.push(value);
+        }
+        Ok(())
+    }
+
+    fn receiver(args: &[VMValue]) -> Result<ObjRef, InterpreterError> {
+        match args[0] {
+            VMValue::Reference(obj) => Ok(obj),
+            VMValue::Null => Err(InterpreterError::Exception(JavaException::new("java/lang/NullPointerException", None))),
+            other => Err(InterpreterError::TypeMismatch("reference".to_string(), other.to_string()))
+        }
+    }
+
+    /// Resolves the method of an `invokevirtual`, `invokespecial` or `invokeinterface` and pops its
+    /// arguments, rejecting static methods as `IncompatibleClassChangeError`.
+    fn resolve_instance_method(&mut self, index: u16) -> Result<(ClassId, usize, Vec<VMValue>), InterpreterError> {
+        let (class, method_index) = self.resolve_method(index)?;
+        let class_file = self.vm.classes.class(class).class.clone();
+        let method = &class_file.methods[method_index];
+        if method.is_static() {
+            return Err(InterpreterError::exception("java/lang/IncompatibleClassChangeError",
+                format!("Expecting non-static method {}", method_display_name(&class_file.class_name, method))))
+        }
+        let args = self.pop_arguments(method)?;
+        Ok((class, method_index, args))
+    }
+
+    /// The method `invokevirtual` and `invokeinterface` run: a private resolved method is invoked
+    /// as is, anything else is selected from the receiver's class.
+    fn select_method(&self, receiver: ObjRef, resolved: ClassId, method_index: usize) -> (ClassId, usize) {
+        let method = &self.vm.classes.class(resolved).class.methods[method_index];
+        if method.access_flags.contains(&AccessFlags::Private) {
+            return (resolved, method_index)
+        }
+        let receiver_class = self.vm.heap.class_of(receiver);
+        self.vm.classes.select_method(receiver_class, &method.method_name, &method.descriptor)
+            .unwrap_or((resolved, method_index))
+    }
+
+    /// Pops the index and array reference of an array access and checks the bounds.
+    fn pop_array_index(&mut self) -> Result<(ObjRef, u32, FieldType), InterpreterError> {
+        let index = self.pop_int()?;
+        let array = self.pop_object()?;
+        let length = self.vm.heap.array_length(array);
+        if index < 0 || index as u32 >= length {
+            return Err(InterpreterError::exception("java/lang/ArrayIndexOutOfBoundsException",
+                format!("Index {} out of bounds for length {}", index, length)))
+        }
+        let class = self.vm.heap.class_of(array);
+        let element_type = self.vm.classes.class(class).component_type.clone()
+            .ok_or_else(|| InterpreterError::TypeMismatch("array".to_string(), self.vm.classes.class(class).name.clone()))?;
+        Ok((array, index as u32, element_type))
+    }
+
+    /// Allocates the nested arrays of `multianewarray`, one dimension per count.
+    fn new_multi_array(&mut self, class: ClassId, counts: &[i32]) -> Result<ObjRef, InterpreterError> {
+        let array = self.vm.new_array(class, counts[0])?;
+        let scope = self.vm.new_handle(array);
+        if counts.len() > 1 {
+            let array_class = self.vm.classes.class(class);
+            let element_type = array_class.component_type.clone().unwrap();
+            let component = array_class.component_class
+                .ok_or_else(|| InterpreterError::TypeMismatch("array of arrays".to_string(), array_class.name.clone()))?;
+            for index in 0..counts[0] as u32 {
+                let element = self.new_multi_array(component, &counts[1..]);
+                let element = match element {
+                    Ok(element) => element,
+                    Err(e) => {
+                        self.vm.release_handles(scope);
+                        return Err(e)
+                    }
+                };
+                self.vm.store_element(self.vm.handle(scope), index, &element_type, VMValue::Reference(element));
+            }
+        }
+        let array = self.vm.handle(scope);
+        self.vm.release_handles(scope);
+        Ok(array)
+    }
+
+    /// Resolves a loadable constant of `class` (JVMS 5.1) the way `ldc` does. Everything but
+    /// numbers is cached, so each execution of an `ldc` produces the same object.
+    pub fn resolve_constant(&mut self, class: ClassId, index: u16) -> Result<VMValue, InterpreterError> {
+        if let Some(value) = self.vm.classes.class(class).resolved_constants.get(&index) {
+            return Ok(*value)
+        }
+        let class_file = self.vm.classes.class(class).class.clone();
+        let pool = &class_file.constant_pool;
+        let value = match pool.get(index).ok_or(ClassLoadError::ConstantPoolMissing(index))? {
+            ConstantPoolTag::Integer(v) => return Ok(VMValue::Int(*v as i32)),
+            ConstantPoolTag::Float(v) => return Ok(VMValue::Float(f32::from_bits(*v))),
+            ConstantPoolTag::Long(..) => return Ok(VMValue::Long(pool.long(index)?)),
+            ConstantPoolTag::Double(..) => return Ok(VMValue::Double(pool.double(index)?)),
+            ConstantPoolTag::String(utf8) => VMValue::Reference(self.vm.intern_utf16(&pool.utf16(*utf8)?)?),
+            ConstantPoolTag::Class(_) => {
+                let id = self.vm.classes.resolve_class(class, pool.class_name(index)?)?;
+                VMValue::Reference(self.vm.class_mirror(id)?)
+            }
+            ConstantPoolTag::MethodType(descriptor) => VMValue::Reference(self.method_type(class, pool.utf8(*descriptor)?)?),
+            ConstantPoolTag::MethodHandle(kind, reference) => VMValue::Reference(self.method_handle(class, *kind, *reference)?),
+            ConstantPoolTag::Dynamic(bootstrap, name_and_type) => self.resolve_dynamic(class, *bootstrap, *name_and_type)?,
+            other => return Err(ClassLoadError::ConstantPoolTypeMismatch("loadable constant".to_string(), format!("{:?}", other)).into())
+        };
+        self.vm.classes.class_mut(class).resolved_constants.insert(index, value);
+        Ok(value)
+    }
+
+    /// Creates the `java.lang.invoke.MethodType` of a method descriptor found in `from`.
+    fn method_type(&mut self, from: ClassId, descriptor: &str) -> Result<ObjRef, InterpreterError> {
+        let descriptor = MethodDescriptor::parse(descriptor)?;
+        let return_class = match &descriptor.return_type {
+            Some(return_type) => self.vm.classes.class_for_type(from, return_type)?,
+            None => self.vm.classes.primitive_class(None)
+        };
+        // Mirrors are created once and kept by their class, so asking again after an allocation
+        // gives where they are now.
+        self.vm.class_mirror(return_class)?;
+        let mut parameter_classes: Vec<ClassId> = Vec::new();
+        for parameter in &descriptor.parameters {
+            let parameter_class = self.vm.classes.class_for_type(from, parameter)?;
+            self.vm.class_mirror(parameter_class)?;
+            parameter_classes.push(parameter_class);
+        }
+        let class_array = self.vm.classes.load_class(LoaderId::BOOTSTRAP, "[Ljava/lang/Class;")?;
+        let method_type_class = self.vm.bootstrap_class("java/lang/invoke/MethodType")?;
+        let method_type = self.vm.new_object(method_type_class)?;
+        let scope = self.vm.new_handle(method_type);
+        let parameters = self.vm.new_array(class_array, parameter_classes.len() as i32);
+        let method_type = self.vm.handle(scope);
+        self.vm.release_handles(scope);
+        let parameters = parameters?;
+        for (index, parameter_class) in parameter_classes.into_iter().enumerate() {
+            let mirror = self.vm.class_mirror(parameter_class)?;
+            self.vm.store_element(parameters, index as u32, &FieldType::Object("java/lang/Class".to_string()), VMValue::Reference(mirror));
+        }
+        let return_type = self.vm.class_mirror(return_class)?;
+        self.vm.set_field(method_type, "rtype", VMValue::Reference(return_type))?;
+        self.vm.set_field(method_type, "ptypes", VMValue::Reference(parameters))?;
+        Ok(method_type)
+    }
+
+    /// Resolves a `CONSTANT_MethodHandle` (JVMS 5.4.3.5) of `from` to a direct method handle.
+    fn method_handle(&mut self, from: ClassId, kind: u8, reference: u16) -> Result<ObjRef, InterpreterError> {
+        let class_file = self.vm.classes.class(from).class.clone();
+        let (class_name, name, descriptor) = class_file.constant_pool.member_ref(reference)?;
+        let class = self.vm.classes.resolve_class(from, class_name)?;
+        let java_name = self.vm.classes.class(class).java_name();
+        let is_static = if kind <= 4 {
+            let (declaring, index) = self.vm.classes.find_field(class, name, descriptor)
+                .ok_or_else(|| InterpreterError::exception("java/lang/NoSuchFieldError", name.as_str()))?;
+            self.vm.classes.class(declaring).class.fields[index].is_static()
+        } else {
+            let (declaring, index) = self.vm.classes.find_method(class, name, descriptor)
+                .ok_or_else(|| InterpreterError::exception("java/lang/NoSuchMethodError", format!("{}.{}{}", java_name, name, descriptor)))?;
+            self.vm.classes.class(declaring).class.methods[index].is_static()
+        };
+        if is_static != matches!(kind, 2 | 4 | 6) || (kind == 8) != (name == "<init>") {
+            return Err(InterpreterError::exception("java/lang/IncompatibleClassChangeError",
+                format!("Invalid method handle kind {} for {}.{}", kind, java_name, name)))
+        }
+
+        // The handle's type puts the receiver first and turns field accesses into getters and setters.
+        let receiver = if class_name.starts_with('[') { class_name.clone() } else { format!("L{};", class_name) };
+        let type_descriptor = match kind {
+            1 => format!("({}){}", receiver, descriptor),
+            2 => format!("(){}", descriptor),
+            3 => format!("({}{})V", receiver, descriptor),
+            4 => format!("({})V", descriptor),
+            6 => descriptor.clone(),
+            8 => format!("{}{}", &descriptor[..=descriptor.find(')').unwrap_or(0)], receiver),
+            _ => format!("({}{}", receiver, &descriptor[1..])
+        };
+        let method_type = self.method_type(from, &type_descriptor)?;
+        let scope = self.vm.new_handle(method_type);
+        let handle = self.new_method_handle(class, name);
+        let method_type = self.vm.handle(scope);
+        self.vm.release_handles(scope);
+        let handle = handle?;
+        // Both were created before the handle and are kept by the VM, so this finds them where they are now.
+        let declaring_class = self.vm.class_mirror(class)?;
+        let name = self.vm.intern_string(name)?;
+        self.vm.set_field(handle, "type", VMValue::Reference(method_type))?;
+        self.vm.set_field(handle, "referenceKind", VMValue::Int(kind as i32))?;
+        self.vm.set_field(handle, "declaringClass", VMValue::Reference(declaring_class))?;
+        self.vm.set_field(handle, "name", VMValue::Reference(name))?;
+        Ok(handle)
+    }
+
+    /// Allocates a `MethodHandle` after the mirror and name string it is going to refer to.
+    fn new_method_handle(&mut self, class: ClassId, name: &str) -> Result<ObjRef, InterpreterError> {
+        self.vm.class_mirror(class)?;
+        self.vm.intern_string(name)?;
+        let handle_class = self.vm.bootstrap_class("java/lang/invoke/MethodHandle")?;
+        self.vm.new_object(handle_class)
+    }
+
+    /// Resolves a `CONSTANT_Dynamic` by calling its bootstrap method (JVMS 5.4.3.6) with a lookup
+    /// on `class`, the constant's name and type, then the static arguments. Only static bootstrap
+    /// methods whose parameters take the arguments as they are (no boxing or varargs) are supported.
+    fn resolve_dynamic(&mut self, class: ClassId, bootstrap_index: u16, name_and_type: u16) -> Result<VMValue, InterpreterError> {
+        let class_file = self.vm.classes.class(class).class.clone();
+        let pool = &class_file.constant_pool;
+        let (name, descriptor) = pool.name_and_type(name_and_type)?;
+        let bootstrap_error = |message: String| InterpreterError::exception("java/lang/BootstrapMethodError", message);
+        let bootstrap = class_file.bootstrap_methods().get(bootstrap_index as usize)
+            .ok_or_else(|| bootstrap_error(format!("Missing bootstrap method {}", bootstrap_index)))?;
+        let reference = match pool.get(bootstrap.bootstrap_method_ref) {
+            Some(ConstantPoolTag::MethodHandle(6, reference)) => *reference,
+            _ => return Err(bootstrap_error("bootstrap method must be a static method handle".to_string()))
+        };
+        let (bootstrap_class_name, bootstrap_name, bootstrap_descriptor) = pool.member_ref(reference)?;
+        let bootstrap_class = self.vm.classes.resolve_class(class, bootstrap_class_name)?;
+        let (declaring, method_index) = self.vm.classes.find_method(bootstrap_class, bootstrap_name, bootstrap_descriptor)
+            .ok_or_else(|| InterpreterError::exception("java/lang/NoSuchMethodError", format!("{}.{}{}", bootstrap_class_name.replace('/', "."), bootstrap_name, bootstrap_descriptor)))?;
+
+        let constant_type = FieldType::parse(descriptor)?;
+        let type_class = self.vm.classes.class_for_type(class, &constant_type)?;
+        let parameter_count = MethodDescriptor::parse(bootstrap_descriptor)?.parameters.len();
+        if parameter_count != bootstrap.bootstrap_arguments.len() + 3 {
+            return Err(bootstrap_error(format!("bootstrap method {} takes {} arguments, {} given", bootstrap_name, parameter_count, bootstrap.bootstrap_arguments.len() + 3)))
+        }
+
+        // Everything but the lookup is kept by the VM once created, so after the lookup has been
+        // allocated last the arguments are collected again without allocating.
+        self.vm.class_mirror(class)?;
+        self.vm.intern_string(name)?;
+        self.vm.class_mirror(type_class)?;
+        for argument in &bootstrap.bootstrap_arguments {
+            self.resolve_constant(class, *argument)?;
+        }
+        self.initialize_class(declaring)?;
+        let lookup_class = self.vm.bootstrap_class("java/lang/invoke/MethodHandles$Lookup")?;
+        let lookup = self.vm.new_object(lookup_class)?;
+        let caller = self.vm.class_mirror(class)?;
+        self.vm.set_field(lookup, "lookupClass", VMValue::Reference(caller))?;
+        let mut args = vec![
+            VMValue::Reference(lookup),
+            VMValue::Reference(self.vm.intern_string(name)?),
+            VMValue::Reference(self.vm.class_mirror(type_class)?)
+        ];
+        for argument in &bootstrap.bootstrap_arguments {
+            args.push(self.resolve_constant(class, *argument)?);
+        }
+        let result = self.invoke(declaring, method_index, args);
+        let result = match result {
+            Err(InterpreterError::Exception(e)) if !self.is_error(&e.class_name) => {
+                return Err(InterpreterError::Exception(JavaException::new("java/lang/BootstrapMethodError",
+                    Some("bootstrap method initialization exception".to_string())).with_cause(e)))
+            }
+            other => other?
+        };
+        let value = result.ok_or_else(|| bootstrap_error(format!("bootstrap method {} returned void", bootstrap_name)))?;
+        if constant_type.is_reference() != matches!(value, VMValue::Reference(_) | VMValue::Null) {
+            return Err(bootstrap_error(format!("bootstrap method {} returned {} for a {}", bootstrap_name, value.type_name(), constant_type.java_name())))
+        }
+        Ok(value.narrow_to(&constant_type).computational())
+    }
+
+    /// Runs an `invokedynamic` instruction. Only the bootstraps javac emits are supported, which
+    /// the VM carries out itself: `StringConcatFactory` for `+` on strings from Java 9 on,
+    /// converting every argument and constant with the matching `String.valueOf`,
+    /// `LambdaMetafactory` for lambdas and method references, and `ObjectMethods` for records.
+    fn invoke_dynamic(&mut self, index: u16) -> Result<(), InterpreterError> {
+        let class = self.current_class();
+        let class_file = self.vm.classes.class(class).class.clone();
+        let pool = &class_file.constant_pool;
+        let (bootstrap_index, name_and_type) = match pool.get(index) {
+            Some(ConstantPoolTag::InvokeDynamic(bootstrap_index, name_and_type)) => (*bootstrap_index, *name_and_type),
+            other => return Err(ClassLoadError::ConstantPoolTypeMismatch("InvokeDynamic".to_string(), format!("{:?}", other)).into())
+        };
+        let parameters = MethodDescriptor::parse(pool.name_and_type(name_and_type)?.1)?.parameters;
+        let bootstrap_error = |message: String| InterpreterError::exception("java/lang/BootstrapMethodError", message);
+        let bootstrap = class_file.bootstrap_methods().get(bootstrap_index as usize)
+            .ok_or_else(|| bootstrap_error(format!("Missing bootstrap method {}", bootstrap_index)))?;
+        let (bootstrap_class_name, bootstrap_name) = match pool.get(bootstrap.bootstrap_method_ref) {
+            Some(ConstantPoolTag::MethodHandle(6, reference)) => {
+                let (class_name, name, _) = pool.member_ref(*reference)?;
+                (class_name.as_str(), name.as_str())
+            }
+            _ => return Err(bootstrap_error("bootstrap method must be a static method handle".to_string()))
+        };
+        if bootstrap_class_name == "java/lang/invoke/LambdaMetafactory" && matches!(bootstrap_name, "metafactory" | "altMetafactory") {
+            let lambda_class = self.lambda_class(class, index, bootstrap, name_and_type)?;
+            return self.new_lambda(lambda_class)
+        }
+        if (bootstrap_class_name, bootstrap_name) == ("java/lang/runtime/ObjectMethods", "bootstrap") {
+            let method_name = pool.name_and_type(name_and_type)?.0.clone();
+            return self.record_method(class, bootstrap, &method_name)
+        }
+        // In a recipe, \u{1} stands for the next argument and \u{2} for the next constant.
+        let recipe = match (bootstrap_class_name, bootstrap_name) {
+            ("java/lang/invoke/StringConcatFactory", "makeConcat") => vec![1; parameters.len()],
+            ("java/lang/invoke/StringConcatFactory", "makeConcatWithConstants") => match bootstrap.bootstrap_arguments.first().and_then(|recipe| pool.get(*recipe)) {
+                Some(ConstantPoolTag::String(utf8)) => pool.utf16(*utf8)?,
+                _ => return Err(bootstrap_error("makeConcatWithConstants needs a recipe string".to_string()))
+            },
+            _ => return Err(bootstrap_error(format!("Unsupported bootstrap method {}.{}", bootstrap_class_name.replace('/', "."), bootstrap_name)))
+        };
+        if recipe.iter().filter(|unit| **unit == 1).count() != parameters.len()
+            || recipe.iter().filter(|unit| **unit == 2).count() > bootstrap.bootstrap_arguments.len().saturating_sub(1) {
+            return Err(bootstrap_error(format!("Mismatched recipe for {}", pool.name_and_type(name_and_type)?.0)))
+        }
+        // Resolving the constants may allocate, so it is done while the arguments are still on
+        // the stack; String.valueOf runs Java code, so handles hold them from then on.
+        for constant in &bootstrap.bootstrap_arguments[1.min(bootstrap.bootstrap_arguments.len())..] {
+            self.resolve_constant(class, *constant)?;
+        }
+        let mut args: Vec<VMValue> = Vec::with_capacity(parameters.len());
+        for _ in 0..parameters.len() {
+            args.push(self.pop()?);
+        }
+        args.reverse();
+        let scope = self.vm.handle_scope();
+        let handles: Vec<Option<Handle>> = args.iter().map(|arg| match arg {
+            VMValue::Reference(obj) => Some(self.vm.new_handle(*obj)),
+            _ => None
+        }).collect();
+        let mut units: Vec<u16> = Vec::new();
+        let (mut next_arg, mut next_constant) = (0, 1);
+        let mut result = Ok(());
+        for unit in recipe {
+            let piece = match unit {
+                1 => {
+                    let arg = handles[next_arg].map_or(args[next_arg], |handle| VMValue::Reference(self.vm.handle(handle)));
+                    next_arg += 1;
+                    self.string_of(arg, &parameters[next_arg - 1])
+                }
+                2 => {
+                    let constant = self.resolve_constant(class, bootstrap.bootstrap_arguments[next_constant])?;
+                    next_constant += 1;
+                    let constant_type = match constant {
+                        VMValue::Int(_) => FieldType::Int,
+                        VMValue::Long(_) => FieldType::Long,
+                        VMValue::Float(_) => FieldType::Float,
+                        VMValue::Double(_) => FieldType::Double,
+                        _ => FieldType::Object("java/lang/Object".to_string())
+                    };
+                    self.string_of(constant, &constant_type)
+                }
+                unit => Ok(vec![unit])
+            };
+            match piece {
+                Ok(piece) => units.extend(piece),
+                Err(e) => {
+                    result = Err(e);
+                    break
+                }
+            }
+        }
+        self.vm.release_handles(scope);
+        result?;
+        let string = self.vm.new_string_utf16(&units)?;
+        self.vm.push(VMValue::Reference(string));
+        Ok(())
+    }
+
+    /// The class of the lambdas a `LambdaMetafactory` call site creates, spun the first time the
+    /// site runs. Its fields hold the captured values and its methods, the interface method and
+    /// any bridges `altMetafactory` asks for, are native ones run by [`VMEnv::invoke_lambda`].
+    /// Serializable lambdas are plain serializable objects here, without `writeReplace`.
+    fn lambda_class(&mut self, host: ClassId, index: u16, bootstrap: &BootstrapMethod, name_and_type: u16) -> Result<ClassId, InterpreterError> {
+        if let Some(id) = self.call_sites.get(&(host, index)) {
+            return Ok(*id)
+        }
+        let class_file = self.vm.classes.class(host).class.clone();
+        let pool = &class_file.constant_pool;
+        let bootstrap_error = |message: &str| InterpreterError::exception("java/lang/BootstrapMethodError", message);
+        let argument = |index: usize| bootstrap.bootstrap_arguments.get(index).and_then(|argument| pool.get(*argument));
+        let method_type = |index: usize| match argument(index) {
+            Some(ConstantPoolTag::MethodType(descriptor)) => Ok(pool.utf8(*descriptor)?.clone()),
+            _ => Err(bootstrap_error("LambdaMetafactory needs method types"))
+        };
+        let int_argument = |index: usize| match argument(index) {
+            Some(ConstantPoolTag::Integer(value)) => Ok(*value as i32),
+            _ => Err(bootstrap_error("altMetafactory needs int arguments"))
+        };
+        let (method_name, factory_descriptor) = pool.name_and_type(name_and_type)?;
+        let factory = MethodDescriptor::parse(factory_descriptor)?;
+        let interface_name = match &factory.return_type {
+            Some(FieldType::Object(name)) => name.clone(),
+            _ => return Err(bootstrap_error("a lambda call site must return an interface"))
+        };
+        let mut interface_names = vec![interface_name];
+        let mut descriptors = vec![method_type(0)?];
+        let (kind, reference) = match argument(1) {
+            Some(ConstantPoolTag::MethodHandle(kind, reference)) => (*kind, *reference),
+            _ => return Err(bootstrap_error("LambdaMetafactory needs an implementation method handle"))
+        };
+        // altMetafactory: flags, then with FLAG_MARKERS the extra interfaces and with
+        // FLAG_BRIDGES the extra method types, each list preceded by its length.
+        if bootstrap.bootstrap_arguments.len() > 3 {
+            let flags = int_argument(3)?;
+            let mut next = 4;
+            if flags & 1 != 0 {
+                interface_names.push("java/io/Serializable".to_string());
+            }
+            if flags & 2 != 0 {
+                let count = int_argument(next)? as usize;
+                for marker in next + 1..=next + count {
+                    let marker = bootstrap.bootstrap_arguments.get(marker).ok_or_else(|| bootstrap_error("missing marker interface"))?;
+                    interface_names.push(pool.class_name(*marker)?.clone());
+                }
+                next += count + 1;
+            }
+            if flags & 4 != 0 {
+                let count = int_argument(next)? as usize;
+                for bridge in next + 1..=next + count {
+                    descriptors.push(method_type(bridge)?);
+                }
+            }
+        }
+
+        let (class_name, name, descriptor) = pool.member_ref(reference)?;
+        let implementation_class = self.vm.classes.resolve_class(host, class_name)?;
+        let (class, method_index) = self.vm.classes.find_method(implementation_class, name, descriptor)
+            .ok_or_else(|| InterpreterError::exception("java/lang/NoSuchMethodError",
+                format!("{}.{}{}", class_name.replace('/', "."), name, descriptor)))?;
+        let implementation = MethodDescriptor::parse(descriptor)?;
+        let mut parameters = implementation.parameters;
+        let return_type = match kind {
+            8 => Some(FieldType::Object(class_name.clone())),
+            _ => implementation.return_type
+        };
+        match kind {
+            5 | 7 | 9 => parameters.insert(0, FieldType::Object(class_name.clone())),
+            6 | 8 => {}
+            _ => return Err(bootstrap_error(&format!("Unsupported lambda implementation kind {}", kind)))
+        }
+        for descriptor in &descriptors {
+            if factory.parameters.len() + MethodDescriptor::parse(descriptor)?.parameters.len() != parameters.len() {
+                return Err(bootstrap_error(&format!("Type mismatch for lambda argument of {}.{}", class_name.replace('/', "."), name)))
+            }
+        }
+
+        let fields: Vec<Field> = factory.parameters.iter().enumerate().map(|(index, captured)| Field {
+            field_name: format!("arg${}", index + 1),
+            descriptor: captured.descriptor(),
+            access_flags: vec![AccessFlags::Private, AccessFlags::Final],
+            attributes: Vec::new()
+        }).collect();
+        descriptors.dedup();
+        let methods: Vec<Method> = descriptors.into_iter().map(|descriptor| Method {
+            method_name: method_name.clone(),
+            descriptor,
+            access_flags: vec![AccessFlags::Public, AccessFlags::Native],
+            max_stack: 0,
+            max_locals: 0,
+            code: Vec::new(),
+            exception_table: Vec::new(),
+            code_attributes: Vec::new(),
+            attributes: Vec::new()
+        }).collect();
+        let lambda_name = format!("{}$$Lambda${}", class_file.class_name, self.lambdas.len() + 1);
+        let id = self.vm.classes.define_lambda_class(host, lambda_name, interface_names, fields, methods)?;
+        self.lambdas.insert(id, Rc::new(LambdaClass { kind, class, method_index, captured: factory.parameters, parameters, return_type }));
+        self.call_sites.insert((host, index), id);
+        Ok(id)
+    }
+
+    /// Creates a lambda of `class` holding the captured values on the operand stack.
+    fn new_lambda(&mut self, class: ClassId) -> Result<(), InterpreterError> {
+        // The captured values are still on the stack, which keeps them alive while allocating.
+        let lambda = self.vm.new_object(class)?;
+        let captured = self.lambdas[&class].captured.clone();
+        for index in (0..captured.len()).rev() {
+            let value = self.pop()?;
+            self.vm.set_field(lambda, &format!("arg${}", index + 1), value)?;
+        }
+        self.vm.push(VMValue::Reference(lambda));
+        Ok(())
+    }
+
+    /// Runs a method of a lambda class: calls the implementation method with the captured values
+    /// followed by the arguments, adapted to its parameter types, and adapts what it returns.
+    fn invoke_lambda(&mut self, lambda: &LambdaClass, descriptor: &str, args: Vec<VMValue>) -> Result<Option<VMValue>, InterpreterError> {
+        let descriptor = MethodDescriptor::parse(descriptor)?;
+        let this = Self::receiver(&args)?;
+        let mut values: Vec<VMValue> = Vec::with_capacity(lambda.parameters.len());
+        for index in 0..lambda.captured.len() {
+            values.push(self.vm.get_field(this, &format!("arg${}", index + 1))?);
+        }
+        values.extend_from_slice(&args[1..]);
+        let types: Vec<&FieldType> = lambda.captured.iter().chain(&descriptor.parameters).collect();
+
+        // Boxing and initialization run Java code, so handles hold the values meanwhile.
+        let scope = self.vm.handle_scope();
+        let result = self.call_lambda_implementation(lambda, values, &types);
+        self.vm.release_handles(scope);
+        match (result?, &lambda.return_type, &descriptor.return_type) {
+            (Some(value), Some(from), Some(to)) => Ok(Some(self.adapt_value(value, from, to)?)),
+            _ => Ok(None)
+        }
+    }
+
+    fn call_lambda_implementation(&mut self, lambda: &LambdaClass, values: Vec<VMValue>, types: &[&FieldType]) -> Result<Option<VMValue>, InterpreterError> {
+        let mut handles: Vec<Option<Handle>> = values.iter().map(|value| match value {
+            VMValue::Reference(obj) => Some(self.vm.new_handle(*obj)),
+            _ => None
+        }).collect();
+        if matches!(lambda.kind, 6 | 8) {
+            self.initialize_class(lambda.class)?;
+        }
+        let instance = match lambda.kind {
+            8 => Some(self.vm.new_object(lambda.class).map(|obj| self.vm.new_handle(obj))?),
+            _ => None
+        };
+        let mut adapted: Vec<VMValue> = Vec::with_capacity(values.len());
+        for (index, value) in values.into_iter().enumerate() {
+            let value = handles[index].map_or(value, |handle| VMValue::Reference(self.vm.handle(handle)));
+            let value = self.adapt_value(value, types[index], &lambda.parameters[index])?;
+            handles[index] = match value {
+                VMValue::Reference(obj) => Some(self.vm.new_handle(obj)),
+                _ => None
+            };
+            adapted.push(value);
+        }
+        let mut args: Vec<VMValue> = adapted.into_iter().zip(handles)
+            .map(|(value, handle)| handle.map_or(value, |handle| VMValue::Reference(self.vm.handle(handle))))
+            .collect();
+        match lambda.kind {
+            5 | 9 => {
+                let (class, method_index) = self.select_method(Self::receiver(&args)?, lambda.class, lambda.method_index);
+                self.invoke(class, method_index, args)
+            }
+            8 => {
+                let instance = instance.unwrap();
+                args.insert(0, VMValue::Reference(self.vm.handle(instance)));
+                self.invoke(lambda.class, lambda.method_index, args)?;
+                Ok(Some(VMValue::Reference(self.vm.handle(instance))))
+            }
+            _ => self.invoke(lambda.class, lambda.method_index, args)
+        }
+    }
+
+    /// Converts a value the way `LambdaMetafactory` adapts arguments and results: primitives are
+    /// widened, boxed or unboxed, references are passed as they are.
+    pub(crate) fn adapt_value(&mut self, value: VMValue, from: &FieldType, to: &FieldType) -> Result<VMValue, InterpreterError> {
+        let is_reference = |field_type: &FieldType| matches!(field_type, FieldType::Object(_) | FieldType::Array(_));
+        match (is_reference(from), is_reference(to)) {
+            (false, false) => Ok(widen(value.computational(), to)),
+            (false, true) => {
+                let wrapper = wrapper_class_name(from);
+                let class = self.vm.bootstrap_class(wrapper)?;
+                let descriptor = format!("({})L{};", from.descriptor(), wrapper);
+                Ok(self.invoke_static(class, "valueOf", &descriptor, vec![value])?.unwrap_or(VMValue::Null))
+            }
+            (true, false) => match value {
+                VMValue::Reference(obj) => Ok(widen(self.vm.get_field(obj, "value")?.computational(), to)),
+                _ => Err(InterpreterError::Exception(JavaException::new("java/lang/NullPointerException", None)))
+            },
+            (true, true) => Ok(value)
+        }
+    }
+
+    /// Runs the `toString`, `hashCode` or `equals` of a record, which `ObjectMethods` bootstraps
+    /// with the getters of the components, on the receiver and argument on the operand stack.
+    fn record_method(&mut self, host: ClassId, bootstrap: &BootstrapMethod, method_name: &str) -> Result<(), InterpreterError> {
+        let class_file = self.vm.classes.class(host).class.clone();
+        let pool = &class_file.constant_pool;
+        let mut components: Vec<(String, FieldType)> = Vec::new();
+        for getter in bootstrap.bootstrap_arguments.iter().skip(2) {
+            match pool.get(*getter) {
+                Some(ConstantPoolTag::MethodHandle(1, reference)) => {
+                    let (_, name, descriptor) = pool.member_ref(*reference)?;
+                    components.push((name.clone(), FieldType::parse(descriptor)?));
+                }
+                _ => return Err(InterpreterError::exception("java/lang/BootstrapMethodError", "ObjectMethods needs field getters"))
+            }
+        }
+        let other = match method_name {
+            "equals" => Some(self.pop()?),
+            _ => None
+        };
+        let receiver = self.pop_object()?;
+        let scope = self.vm.handle_scope();
+        let receiver = self.vm.new_handle(receiver);
+        let other = match other {
+            Some(VMValue::Reference(other)) => Some(self.vm.new_handle(other)),
+            _ => None
+        };
+        let result = match method_name {
+            "toString" => self.record_to_string(receiver, &components),
+            "hashCode" => self.record_hash_code(receiver, &components),
+            "equals" => self.record_equals(receiver, other, &components),
+            _ => Err(InterpreterError::exception("java/lang/BootstrapMethodError", format!("Unsupported record method {}", method_name)))
+        };
+        self.vm.release_handles(scope);
+        self.vm.push(result?);
+        Ok(())
+    }
+
+    /// `Point[x=1, y=2]`.
+    fn record_to_string(&mut self, record: Handle, components: &[(String, FieldType)]) -> Result<VMValue, InterpreterError> {
+        let class = self.vm.heap.class_of(self.vm.handle(record));
+        let java_name = self.vm.classes.class(class).java_name();
+        let simple_name = java_name.rsplit(['.', '$']).next().unwrap_or_default();
+        let mut units: Vec<u16> = format!("{}[", simple_name).encode_utf16().collect();
+        for (index, (name, field_type)) in components.iter().enumerate() {
+            if index > 0 {
+                units.extend(", ".encode_utf16());
+            }
+            units.extend(format!("{}=", name).encode_utf16());
+            let value = self.vm.get_field(self.vm.handle(record), name)?;
+            units.extend(self.string_of(value, field_type)?);
+        }
+        units.push(']' as u16);
+        Ok(VMValue::Reference(self.vm.new_string_utf16(&units)?))
+    }
+
+    /// 31 times the hash so far plus the hash of each component, starting from 0.
+    fn record_hash_code(&mut self, record: Handle, components: &[(String, FieldType)]) -> Result<VMValue, InterpreterError> {
+        let mut hash: i32 = 0;
+        for (name, field_type) in components {
+            let value = self.vm.get_field(self.vm.handle(record), name)?;
+            let (class, descriptor) = match field_type {
+                FieldType::Object(_) | FieldType::Array(_) => ("java/util/Objects", "(Ljava/lang/Object;)I".to_string()),
+                primitive => (wrapper_class_name(primitive), format!("({})I", primitive.descriptor()))
+            };
+            let class = self.vm.bootstrap_class(class)?;
+            let component_hash = self.invoke_static(class, "hashCode", &descriptor, vec![value.computational()])?.unwrap_or(VMValue::Int(0)).as_int()?;
+            hash = hash.wrapping_mul(31).wrapping_add(component_hash);
+        }
+        Ok(VMValue::Int(hash))
+    }
+
+    /// Whether `other` is a record of the same class with equal components: references by
+    /// `Objects.equals`, floating point values by their wrapper's `compare`, others by `==`.
+    fn record_equals(&mut self, record: Handle, other: Option<Handle>, components: &[(String, FieldType)]) -> Result<VMValue, InterpreterError> {
+        let other = match other {
+            Some(other) if self.vm.heap.class_of(self.vm.handle(other)) == self.vm.heap.class_of(self.vm.handle(record)) => other,
+            _ => return Ok(VMValue::Int(0))
+        };
+        for (name, field_type) in components {
+            let first = self.vm.get_field(self.vm.handle(record), name)?.computational();
+            let second = self.vm.get_field(self.vm.handle(other), name)?.computational();
+            let equal = match field_type {
+                FieldType::Object(_) | FieldType::Array(_) => {
+                    let objects = self.vm.bootstrap_class("java/util/Objects")?;
+                    let equal = self.invoke_static(objects, "equals", "(Ljava/lang/Object;Ljava/lang/Object;)Z", vec![first, second])?;
+                    equal.unwrap_or(VMValue::Int(0)).as_int()? != 0
+                }
+                FieldType::Float => first.as_float()?.to_bits() == second.as_float()?.to_bits() || (first.as_float()?.is_nan() && second.as_float()?.is_nan()),
+                FieldType::Double => first.as_double()?.to_bits() == second.as_double()?.to_bits() || (first.as_double()?.is_nan() && second.as_double()?.is_nan()),
+                _ => first == second
+            };
+            if !equal {
+                return Ok(VMValue::Int(0))
+            }
+        }
+        Ok(VMValue::Int(1))
+    }
+
+    /// The UTF-16 code units of `String.valueOf` of a value of type `value_type`.
+    fn string_of(&mut self, value: VMValue, value_type: &FieldType) -> Result<Vec<u16>, InterpreterError> {
+        let descriptor = match value_type {
+            FieldType::Boolean => "(Z)Ljava/lang/String;",
+            FieldType::Char => "(C)Ljava/lang/String;",
+            FieldType::Byte | FieldType::Short | FieldType::Int => "(I)Ljava/lang/String;",
+            FieldType::Long => "(J)Ljava/lang/String;",
+            FieldType::Float => "(F)Ljava/lang/String;",
+            FieldType::Double => "(D)Ljava/lang/String;",
+            FieldType::Object(_) | FieldType::Array(_) => "(Ljava/lang/Object;)Ljava/lang/String;"
+        };
+        let string_class = self.vm.bootstrap_class("java/lang/String")?;
+        match self.invoke_static(string_class, "valueOf", descriptor, vec![value])?.map(|string| string.as_reference()) {
+            Some(Ok(Some(string))) => self.vm.string_utf16(string),
+            _ => Err(InterpreterError::TypeMismatch("java.lang.String".to_string(), "null".to_string()))
+        }
+    }
+
+    fn step(&mut self, opcode: Opcode) -> Result<Flow, InterpreterError> {
+        match opcode {
+            Opcode::Nop => {}
+            Opcode::AConstNull => self.aconst_null(),
+            Opcode::IConst(v) => self.iconst(v as i32),
+            Opcode::ILoad(index) | Opcode::LLoad(index) | Opcode::FLoad(index)
+            | Opcode::DLoad(index) | Opcode::ALoad(index) => self.load(index),
+            Opcode::IStore(index) | Opcode::LStore(index) | Opcode::FStore(index)
+            | Opcode::DStore(index) | Opcode::AStore(index) => self.store(index)?,
+            Opcode::IALoad | Opcode::LALoad | Opcode::FALoad | Opcode::DALoad
+            | Opcode::AALoad | Opcode::BALoad | Opcode::CALoad | Opcode::SALoad => {
+                let (array, index, element_type) = self.pop_array_index()?;
+                let value = self.vm.heap.read_element(array, index, &element_type);
+                self.vm.push(value);
+            }
+            Opcode::IAStore | Opcode::LAStore | Opcode::FAStore | Opcode::DAStore
+            | Opcode::AAStore | Opcode::BAStore | Opcode::CAStore | Opcode::SAStore => {
+                let value = self.pop()?;
+                let (array, index, element_type) = self.pop_array_index()?;
+                if let VMValue::Reference(obj) = value {
+                    let array_class = self.vm.heap.class_of(array);
+                    let value_class = self.vm.heap.class_of(obj);
+                    match self.vm.classes.class(array_class).component_class {
+                        Some(component) if !self.vm.classes.is_assignable(value_class, component) => {
+                            return Err(InterpreterError::exception("java/lang/ArrayStoreException", self.vm.classes.class(value_class).java_name()))
+                        }
+                        _ => {}
+                    }
+                }
+                if !self.vm.store_element(array, index, &element_type, value) {
+                    return Err(InterpreterError::TypeMismatch(element_type.java_name(), value.to_string()))
+                }
+            }
+            Opcode::IInc(index, delta) => {
+                let slot = self.frame().locals + index as usize;
+                let value = self.vm.get(slot).as_int()?;
+                self.vm.set(slot, VMValue::Int(value.wrapping_add(delta as i32)));
+            }
+            Opcode::Pop => {
+                self.pop_words(1)?;
+            }
+            Opcode::Pop2 => {
+                self.pop_words(2)?;
+            }
+            Opcode::Dup => self.dup_words(1, 0)?,
+            Opcode::DupX1 => self.dup_words(1, 1)?,
+            Opcode::DupX2 => self.dup_words(1, 2)?,
+            Opcode::Dup2 => self.dup_words(2, 0)?,
+            Opcode::Dup2X1 => self.dup_words(2, 1)?,
+            Opcode::Dup2X2 => self.dup_words(2, 2)?,
+            Opcode::Swap => {
+                let top = self.pop_words(1)?;
+                let under = self.pop_words(1)?;
+                self.vm.push(top[0]);
+                self.vm.push(under[0]);
+            }
+            Opcode::BiPush(v) => self.vm.push(VMValue::Int(v as i32)),
+            Opcode::SiPush(v) => self.vm.push(VMValue::Int(v as i32)),
+            Opcode::Ldc(index) | Opcode::LdcW(index) | Opcode::Ldc2W(index) => {
+                let value = self.resolve_constant(self.current_class(), index)?;
+                self.vm.push(value);
+            }
+            Opcode::LConst(v) => self.vm.push(VMValue::Long(v)),
+            Opcode::FConst(v) => self.vm.push(VMValue::Float(v)),
+            Opcode::DConst(v) => self.vm.push(VMValue::Double(v)),
+            Opcode::IAdd | Opcode::ISub | Opcode::IMul | Opcode::IDiv | Opcode::IRem | Opcode::IShl
+            | Opcode::IShr | Opcode::IUShr | Opcode::IAnd | Opcode::IOr | Opcode::IXor => {
+                let b = self.pop_int()?;
+                let a = self.pop_int()?;
+                self.vm.push(VMValue::Int(int_arithmetic(&opcode, a, b)?));
+            }
+            Opcode::LAdd | Opcode::LSub | Opcode::LMul | Opcode::LDiv | Opcode::LRem
+            | Opcode::LAnd | Opcode::LOr | Opcode::LXor => {
+                let b = self.pop()?.as_long()?;
+                let a = self.pop()?.as_long()?;
+                self.vm.push(VMValue::Long(long_arithmetic(&opcode, a, b)?));
+            }
+            Opcode::LShl | Opcode::LShr | Opcode::LUShr => {
+                let b = self.pop_int()?;
+                let a = self.pop()?.as_long()?;
+                self.vm.push(VMValue::Long(long_arithmetic(&opcode, a, b as i64)?));
+            }
+            Opcode::FAdd | Opcode::FSub | Opcode::FMul | Opcode::FDiv | Opcode::FRem => {
+                let b = self.pop()?.as_float()?;
+                let a = self.pop()?.as_float()?;
+                self.vm.push(VMValue::Float(float_arithmetic(&opcode, a, b)?));
+            }
+            Opcode::DAdd | Opcode::DSub | Opcode::DMul | Opcode::DDiv | Opcode::DRem => {
+                let b = self.pop()?.as_double()?;
+                let a = self.pop()?.as_double()?;
+                self.vm.push(VMValue::Double(double_arithmetic(&opcode, a, b)?));
+            }
+            Opcode::FNeg => {
+                let a = self.pop()?.as_float()?;
+                self.vm.push(VMValue::Float(-a));
+            }
+            Opcode::DNeg => {
+                let a = self.pop()?.as_double()?;
+                self.vm.push(VMValue::Double(-a));
+            }
+            Opcode::FCmpL | Opcode::FCmpG => {
+                let b = self.pop()?.as_float()?;
+                let a = self.pop()?.as_float()?;
+                let nan_result = if matches!(opcode, Opcode::FCmpL) { -1 } else { 1 };
+                self.vm.push(VMValue::Int(compare_floating(a as f64, b as f64, nan_result)));
+            }
+            Opcode::DCmpL | Opcode::DCmpG => {
+                let b = self.pop()?.as_double()?;
+                let a = self.pop()?.as_double()?;
+                let nan_result = if matches!(opcode, Opcode::DCmpL) { -1 } else { 1 };
+                self.vm.push(VMValue::Int(compare_floating(a, b, nan_result)));
+            }
+            // Rust's `as` rounds to nearest when converting to floating point and, from floating
+            // point, saturates at the integer range and maps NaN to 0, exactly as JVMS 2.8.3 requires.
+            Opcode::I2F | Opcode::I2D | Opcode::L2F | Opcode::L2D | Opcode::F2I | Opcode::F2L
+            | Opcode::F2D | Opcode::D2I | Opcode::D2L | Opcode::D2F => {
+                let value = self.pop()?;
+                let converted = match opcode {
+                    Opcode::I2F => VMValue::Float(value.as_int()? as f32),
+                    Opcode::I2D => VMValue::Double(value.as_int()? as f64),
+                    Opcode::L2F => VMValue::Float(value.as_long()? as f32),
+                    Opcode::L2D => VMValue::Double(value.as_long()? as f64),
+                    Opcode::F2I => VMValue::Int(value.as_float()? as i32),
+                    Opcode::F2L => VMValue::Long(value.as_float()? as i64),
+                    Opcode::F2D => VMValue::Double(value.as_float()? as f64),
+                    Opcode::D2I => VMValue::Int(value.as_double()? as i32),
+                    Opcode::D2L => VMValue::Long(value.as_double()? as i64),
+                    _ => VMValue::Float(value.as_double()? as f32)
+                };
+                self.vm.push(converted);
+            }
+            Opcode::INeg => {
+                let a = self.pop_int()?;
+                self.vm.push(VMValue::Int(a.wrapping_neg()));
+            }
+            Opcode::LNeg => {
+                let a = self.pop()?.as_long()?;
+                self.vm.push(VMValue::Long(a.wrapping_neg()));
+            }
+            Opcode::LCmp => {
+                let b = self.pop()?.as_long()?;
+                let a = self.pop()?.as_long()?;
+                self.vm.push(VMValue::Int(a.cmp(&b) as i32));
+            }
+            Opcode::I2L => {
+                let a = self.pop_int()?;
+                self.vm.push(VMValue::Long(a as i64));
+            }
+            Opcode::L2I => {
+                let a = self.pop()?.as_long()?;
+                self.vm.push(VMValue::Int(a as i32));
+            }
+            Opcode::I2B => {
+                let a = self.pop_int()?;
+                self.vm.push(VMValue::Int(a as i8 as i32));
+            }
+            Opcode::I2C => {
+                let a = self.pop_int()?;
+                self.vm.push(VMValue::Int(a as u16 as i32));
+            }
+            Opcode::I2S => {
+                let a = self.pop_int()?;
+                self.vm.push(VMValue::Int(a as i16 as i32));
+            }
+            Opcode::IfEq(offset) => return Ok(branch_if(self.pop_int()? == 0, offset)),
+            Opcode::IfNe(offset) => return Ok(branch_if(self.pop_int()? != 0, offset)),
+            Opcode::IfLt(offset) => return Ok(branch_if(self.pop_int()? < 0, offset)),
+            Opcode::IfGe(offset) => return Ok(branch_if(self.pop_int()? >= 0, offset)),
+            Opcode::IfGt(offset) => return Ok(branch_if(self.pop_int()? > 0, offset)),
+            Opcode::IfLe(offset) => return Ok(branch_if(self.pop_int()? <= 0, offset)),
+            Opcode::IfICmpEq(offset) | Opcode::IfICmpNe(offset) | Opcode::IfICmpLt(offset)
+            | Opcode::IfICmpGe(offset) | Opcode::IfICmpGt(offset) | Opcode::IfICmpLe(offset) => {
+                let b = self.pop_int()?;
+                let a = self.pop_int()?;
+                let condition = match opcode {
+                    Opcode::IfICmpEq(_) => a == b,
+                    Opcode::IfICmpNe(_) => a != b,
+                    Opcode::IfICmpLt(_) => a < b,
+                    Opcode::IfICmpGe(_) => a >= b,
+                    Opcode::IfICmpGt(_) => a > b,
+                    _ => a <= b
+                };
+                return Ok(branch_if(condition, offset))
+            }
+            Opcode::IfACmpEq(offset) | Opcode::IfACmpNe(offset) => {
+                let b = self.pop()?;
+                let a = self.pop()?;
+                let same = match (a, b) {
+                    (VMValue::Null, VMValue::Null) => true,
+                    (VMValue::Reference(a), VMValue::Reference(b)) => a == b,
+                    _ => false
+                };
+                return Ok(branch_if(same == matches!(opcode, Opcode::IfACmpEq(_)), offset))
+            }
+            Opcode::IfNull(offset) => return Ok(branch_if(matches!(self.pop()?, VMValue::Null), offset)),
+            Opcode::IfNonNull(offset) => return Ok(branch_if(!matches!(self.pop()?, VMValue::Null), offset)),
+            Opcode::Goto(offset) | Opcode::GotoW(offset) => return Ok(Flow::Branch(offset)),
+            Opcode::Jsr(offset) | Opcode::JsrW(offset) => {
+                let length = if matches!(opcode, Opcode::Jsr(_)) { 3 } else { 5 };
+                self.vm.push(VMValue::ReturnAddress((self.frame().pc + length) as u32));
+                return Ok(Flow::Branch(offset))
+            }
+            Opcode::Ret(index) => {
+                let target = self.vm.get(self.frame().locals + index as usize).as_return_address()?;
+                return Ok(Flow::Branch(target as i32 - self.frame().pc as i32))
+            }
+            Opcode::TableSwitch(default, low, high, offsets) => {
+                let index = self.pop_int()?;
+                let offset = if index < low || index > high { default } else { offsets[(index as i64 - low as i64) as usize] };
+                return Ok(Flow::Branch(offset))
+            }
+            Opcode::LookupSwitch(default, pairs) => {
+                let key = self.pop_int()?;
+                let offset = pairs.iter().find(|(k, _)| *k == key).map(|(_, offset)| *offset).unwrap_or(default);
+                return Ok(Flow::Branch(offset))
+            }
+            Opcode::IReturn | Opcode::LReturn | Opcode::FReturn | Opcode::DReturn | Opcode::AReturn => {
+                return Ok(Flow::Return(Some(self.pop()?)))
+            }
+            Opcode::Return => return Ok(Flow::Return(None)),
+            Opcode::GetStatic(index) => {
+                let (class, field_index, _) = self.resolve_static_field(index)?;
+                let value = self.vm.classes.class(class).statics[field_index];
+                self.vm.push(value);
+            }
+            Opcode::PutStatic(index) => {
+                let (class, field_index, field_type) = self.resolve_static_field(index)?;
+                let value = self.pop()?;
+                let value = match (field_type, value) {
+                    (FieldType::Boolean, VMValue::Int(v)) => VMValue::Int(v & 1),
+                    (FieldType::Byte, VMValue::Int(v)) => VMValue::Int(v as i8 as i32),
+                    (FieldType::Char, VMValue::Int(v)) => VMValue::Int(v as u16 as i32),
+                    (FieldType::Short, VMValue::Int(v)) => VMValue::Int(v as i16 as i32),
+                    (_, value) => value
+                };
+                self.vm.set_static(class, field_index, value);
+            }
+            Opcode::InvokeStatic(index) => {
+                let (class, method_index) = self.resolve_method(index)?;
+                let class_file = self.vm.classes.class(class).class.clone();
+                let method = &class_file.methods[method_index];
+                if !method.is_static() {
+                    return Err(InterpreterError::exception("java/lang/IncompatibleClassChangeError",
+                        format!("Expected static method {}", method_display_name(&class_file.class_name, method))))
+                }
+                // Initialization may allocate, so the arguments stay on the stack until it is done.
+                self.initialize_class(class)?;
+                let args = self.pop_arguments(method)?;
+                if let Some(value) = self.invoke(class, method_index, args)? {
+                    self.vm.push(value);
+                }
+            }
+            Opcode::New(index) => {
+                let class_file = self.frame().class_file.clone();
+                let class_name = class_file.constant_pool.class_name(index).map_err(InterpreterError::from)?;
+                let class = self.vm.classes.resolve_class(self.current_class(), class_name)?;
+                let runtime_class = self.vm.classes.class(class);
+                if runtime_class.is_interface() || runtime_class.class.access_flags.contains(&AccessFlags::Abstract) {
+                    return Err(InterpreterError::exception("java/lang/InstantiationError", runtime_class.java_name()))
+                }
+                self.initialize_class(class)?;
+                let finalizable = self.vm.classes.class(class).finalizable;
+                let obj = self.vm.new_object(class)?;
+                self.record_allocation(obj);
+                self.vm.push(VMValue::Reference(obj));
+                if finalizable {
+                    let finalizer = self.vm.classes.load_class(LoaderId::BOOTSTRAP, "java/lang/ref/Finalizer")?;
+                    self.invoke_static(finalizer, "register", "(Ljava/lang/Object;)V", vec![self.vm.peek(0)])?;
+                }
+            }
+            Opcode::NewArray(atype) => {
+                let name = match atype {
+                    4 => "[Z",
+                    5 => "[C",
+                    6 => "[F",
+                    7 => "[D",
+                    8 => "[B",
+                    9 => "[S",
+                    10 => "[I",
+                    11 => "[J",
+                    _ => return Err(InterpreterError::TypeMismatch("array type".to_string(), atype.to_string()))
+                };
+                let length = self.pop_int()?;
+                let class = self.vm.classes.load_class(LoaderId::BOOTSTRAP, name)?;
+                let array = self.vm.new_array(class, length)?;
+                self.record_allocation(array);
+                self.vm.push(VMValue::Reference(array));
+            }
+            Opcode::CheckCast(index) | Opcode::InstanceOf(index) => {
+                // Like HotSpot, null passes without resolving the class.
+                let value = self.pop()?;
+                let result = match value.as_reference()? {
+                    Some(obj) => {
+                        let class_file = self.frame().class_file.clone();
+                        let class_name = class_file.constant_pool.class_name(index).map_err(InterpreterError::from)?;
+                        let target = self.vm.classes.resolve_class(self.current_class(), class_name)?;
+                        let class = self.vm.heap.class_of(obj);
+                        let assignable = self.vm.classes.is_assignable(class, target);
+                        if !assignable && matches!(opcode, Opcode::CheckCast(_)) {
+                            return Err(InterpreterError::exception("java/lang/ClassCastException", self.vm.classes.class_cast_message(class, target)))
+                        }
+                        assignable
+                    }
+                    None => false
+                };
+                match opcode {
+                    Opcode::CheckCast(_) => self.vm.push(value),
+                    _ => self.vm.push(VMValue::Int(result as i32))
+                }
+            }
+            Opcode::ANewArray(index) => {
+                let class_file = self.frame().class_file.clone();
+                let component = class_file.constant_pool.class_name(index).map_err(InterpreterError::from)?;
+                let name = if component.starts_with('[') { format!("[{}", component) } else { format!("[L{};", component) };
+                let length = self.pop_int()?;
+                let class = self.vm.classes.resolve_class(self.current_class(), &name)?;
+                let array = self.vm.new_array(class, length)?;
+                self.record_allocation(array);
+                self.vm.push(VMValue::Reference(array));
+            }
+            Opcode::MultiANewArray(index, dimensions) => {
+                let class_file = self.frame().class_file.clone();
+                let name = class_file.constant_pool.class_name(index).map_err(InterpreterError::from)?;
+                let class = self.vm.classes.resolve_class(self.current_class(), name)?;
+                let mut counts: Vec<i32> = Vec::with_capacity(dimensions as usize);
+                for _ in 0..dimensions {
+                    counts.push(self.pop_int()?);
+                }
+                counts.reverse();
+                if let Some(count) = counts.iter().find(|count| **count < 0) {
+                    return Err(InterpreterError::exception("java/lang/NegativeArraySizeException", count.to_string()))
+                }
+                let array = self.new_multi_array(class, &counts)?;
+                self.record_allocation(array);
+                self.vm.push(VMValue::Reference(array));
+            }
+            Opcode::ArrayLength => {
+                let array = self.pop_object()?;
+                let length = self.vm.heap.array_length(array);
+                self.vm.push(VMValue::Int(length as i32));
+            }
+            Opcode::GetField(index) => {
+                let (field_type, offset) = self.resolve_instance_field(index)?;
+                let obj = self.pop_object()?;
+                let value = self.vm.heap.read_field(obj, offset, &field_type);
+                self.vm.push(value);
+            }
+            Opcode::PutField(index) => {
+                let (field_type, offset) = self.resolve_instance_field(index)?;
+                let value = self.pop()?;
+                let obj = self.pop_object()?;
+                if !self.vm.store_field(obj, offset, &field_type, value) {
+                    return Err(InterpreterError::TypeMismatch(field_type.java_name(), value.to_string()))
+                }
+            }
+            Opcode::InvokeVirtual(index) => {
+                let (class, method_index, args) = self.resolve_instance_method(index)?;
+                let receiver = Self::receiver(&args)?;
+                let (class, method_index) = self.select_method(receiver, class, method_index);
+                self.invoke_instance(class, method_index, args)?;
+            }
+            Opcode::InvokeInterface(index, _) => {
+                let (interface, method_index, args) = self.resolve_instance_method(index)?;
+                let receiver = Self::receiver(&args)?;
+                let receiver_class = self.vm.heap.class_of(receiver);
+                if !self.vm.classes.is_subclass_of(receiver_class, interface) {
+                    return Err(InterpreterError::exception("java/lang/IncompatibleClassChangeError",
+                        format!("Class {} does not implement the requested interface {}",
+                            self.vm.classes.class(receiver_class).java_name(), self.vm.classes.class(interface).java_name())))
+                }
+                let (class, method_index) = self.select_method(receiver, interface, method_index);
+                self.invoke_instance(class, method_index, args)?;
+            }
+            Opcode::InvokeSpecial(index) => {
+                let (class, method_index, args) = self.resolve_instance_method(index)?;
+                Self::receiver(&args)?;
+                // With ACC_SUPER, a call to a superclass method other than a constructor starts
+                // the lookup from the direct superclass of the current class (JVMS 6.5 invokespecial).
+                let current = self.current_class();
+                let current_class = self.vm.classes.class(current);
+                let method = &self.vm.classes.class(class).class.methods[method_index];
+                let super_call = method.method_name != "<init>"
+                    && !self.vm.classes.class(class).is_interface()
+                    && current_class.class.access_flags.contains(&AccessFlags::Super)
+                    && class != current
+                    && self.vm.classes.is_subclass_of(current, class);
+                let (class, method_index) = match (super_call, current_class.super_class) {
+                    (true, Some(super_class)) => {
+                        let (name, descriptor) = (method.method_name.clone(), method.descriptor.clone());
+                        self.vm.classes.find_method(super_class, &name, &descriptor).unwrap_or((class, method_index))
+                    }
+                    _ => (class, method_index)
+                };
+                self.invoke_instance(class, method_index, args)?;
+            }
+            Opcode::AThrow => {
+                let throwable = self.pop_object()?;
+                return Err(InterpreterError::Exception(self.java_exception(throwable)?))
+            }
+            Opcode::MonitorEnter => {
+                let obj = self.pop_object()?;
+                let count = self.vm.heap.monitor_count(obj);
+                self.vm.heap.set_monitor_count(obj, count + 1);
+            }
+            Opcode::MonitorExit => {
+                let obj = self.pop_object()?;
+                match self.vm.heap.monitor_count(obj) {
+                    0 => return Err(InterpreterError::Exception(JavaException::new("java/lang/IllegalMonitorStateException", None))),
+                    count => self.vm.heap.set_monitor_count(obj, count - 1)
+                }
+            }
+            Opcode::InvokeDynamic(index) => self.invoke_dynamic(index)?,
+        }
+        Ok(Flow::Next)
+    }
+
     pub fn iconst(&mut self, val: i32) {
         self.vm.push(VMValue::Int(val));
     }
@@ -42,13 +2124,13 @@ impl VMEnv {
     }
 
     pub fn iadd(&mut self) {
-        let a = self.vm.pop().int();
-        let b = self.vm.pop().int();
-        self.vm.push(VMValue::Int(a + b));
+        let a = self.vm.pop().as_int().unwrap();
+        let b = self.vm.pop().as_int().unwrap();
+        self.vm.push(VMValue::Int(a.wrapping_add(b)));
     }
 
     pub fn print(&mut self) {
         let v = self.vm.pop();
         println!("Interop > print {}", v);
     }
-}
\ No newline at end of file
+}
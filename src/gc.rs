@@ -0,0 +1,691 @@
+//! Tracing garbage collection. The VM allocates through a [`GarbageCollector`], which decides where
+//! objects go in the [`Heap`] and how their space is reclaimed. Both collectors stop the world and
+//! start from the precise roots of [`VM::roots`].
+//!
+//! [`MarkSweep`] never moves objects. [`Generational`] copies and slides them, updating every root
+//! as it goes, so with it an [`ObjRef`] held by Rust code is only good until the next allocation
+//! unless it is read back from a root such as a [`Handle`](crate::vm::Handle).
+//!
+//! Both discover instances of `java.lang.ref.Reference` while tracing and deal with their referents
+//! once everything strongly reachable is found. The references they clear, and those of objects due
+//! for finalization, become pending; the VM hands them to Java code at the next safepoint.
+
+use std::collections::HashSet;
+use std::time::{Duration, Instant};
+use crate::registry::{ClassId, ClassRegistry};
+use crate::vm::{Heap, ObjRef, RootKind, VM};
+
+/// Bits of the header word a collector owns; the rest holds a forwarding address.
+const MARKED: u32 = 1;
+/// Set on an old object while it is in the remembered set.
+const REMEMBERED: u32 = 2;
+/// Set on a nursery object that was copied out of the nursery.
+const FORWARDED: u32 = 4;
+const FLAGS: u32 = Heap::ALIGNMENT - 1;
+
+/// Which collector a VM runs, see [`VMOptions`](crate::vm::VMOptions).
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub enum CollectorKind {
+    #[default]
+    MarkSweep,
+    Generational
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum CollectionKind {
+    /// Only the nursery was collected.
+    Minor,
+    Full
+}
+
+/// How a subclass of `java.lang.ref.Reference` holds on to its referent, strongest first.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ReferenceKind {
+    Soft,
+    Weak,
+    /// `java.lang.ref.FinalReference`, which tracks an object to finalize.
+    Final,
+    Phantom
+}
+
+/// What the VM asks a collection for.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub struct CollectionRequest {
+    /// Collect the whole heap rather than whatever the collector chooses to.
+    pub full: bool,
+    /// Clear soft references whose referent is otherwise unreachable, as is done before an
+    /// `OutOfMemoryError`; they are kept otherwise.
+    pub clear_soft_references: bool
+}
+
+/// What one collection did. `live_objects` counts the survivors of the part of the heap that was
+/// collected.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CollectionStats {
+    pub kind: CollectionKind,
+    pub live_objects: usize,
+    pub freed_objects: usize,
+    pub freed_bytes: usize,
+    /// [`Heap::used`] before and after the collection.
+    pub used_before: usize,
+    pub used_after: usize,
+    pub pause: Duration
+}
+
+impl CollectionStats {
+    fn new(kind: CollectionKind) -> Self {
+        CollectionStats { kind, live_objects: 0, freed_objects: 0, freed_bytes: 0, used_before: 0, used_after: 0, pause: Duration::ZERO }
+    }
+}
+
+/// Totals over every collection a VM ran.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct GcStats {
+    pub minor_collections: usize,
+    pub full_collections: usize,
+    pub total_pause: Duration,
+    pub max_pause: Duration
+}
+
+impl GcStats {
+    fn record(&mut self, stats: &CollectionStats) {
+        match stats.kind {
+            CollectionKind::Minor => self.minor_collections += 1,
+            CollectionKind::Full => self.full_collections += 1
+        }
+        self.total_pause += stats.pause;
+        self.max_pause = self.max_pause.max(stats.pause);
+    }
+
+    pub fn collections(&self) -> usize {
+        self.minor_collections + self.full_collections
+    }
+}
+
+/// A way of managing the heap. The VM asks the collector for room for every new object and, when
+/// there is none, runs a collection with the current roots. Every reference the VM stores into a
+/// field, an array element or a static goes past one of the write barriers first.
+pub trait GarbageCollector {
+    fn name(&self) -> &'static str;
+
+    /// Finds `size` bytes for a new object and returns their address, or `None` when a collection
+    /// has to run first.
+    fn allocate(&mut self, heap: &mut Heap, size: u32) -> Option<u32>;
+
+    /// Reclaims the space of objects no root reaches. A collector that moves objects updates
+    /// `roots` in place. References that became pending are added to `pending`.
+    fn collect(&mut self, heap: &mut Heap, classes: &ClassRegistry, roots: &mut [(RootKind, ObjRef)], request: CollectionRequest,
+               pending: &mut Vec<ObjRef>) -> CollectionStats;
+
+    /// Called after `value` was stored into a field or an element of `holder`.
+    fn write_barrier(&mut self, _heap: &mut Heap, _holder: ObjRef, _value: ObjRef) {}
+
+    /// Called after `value` was stored into a static field of `class`.
+    fn static_write_barrier(&mut self, _class: ClassId, _value: ObjRef) {}
+}
+
+/// The addresses of the reference slots of an object: its reference fields or, for arrays of
+/// references, its elements.
+pub(crate) fn reference_slots(heap: &Heap, classes: &ClassRegistry, obj: ObjRef) -> Vec<u32> {
+    let start = obj.0 + Heap::HEADER_SIZE;
+    let class = classes.class(heap.class_of(obj));
+    match &class.component_type {
+        Some(element_type) if element_type.is_reference() => (0..heap.array_length(obj)).map(|index| start + index * 4).collect(),
+        Some(_) => Vec::new(),
+        None => class.layout.reference_offsets.iter().map(|offset| start + offset).collect()
+    }
+}
+
+/// The address of the referent slot of a reference object.
+fn referent_slot(heap: &Heap, classes: &ClassRegistry, obj: ObjRef) -> Option<u32> {
+    let class = classes.class(heap.class_of(obj));
+    class.reference_kind?;
+    class.layout.referent_offset.map(|offset| obj.0 + Heap::HEADER_SIZE + offset)
+}
+
+/// What reference processing needs from a collector's tracing.
+trait Tracer {
+    /// Whether `obj` has been reached.
+    fn is_alive(&self, heap: &Heap, obj: ObjRef) -> bool;
+
+    /// Where a reached object is now.
+    fn forward(&self, heap: &Heap, obj: ObjRef) -> ObjRef;
+
+    /// Reaches `obj` and everything it refers to, returning where it is now.
+    fn keep_alive(&mut self, heap: &mut Heap, obj: ObjRef) -> ObjRef;
+
+    /// Takes the reference objects reached since the last call, at their current addresses.
+    fn take_discovered(&mut self) -> Vec<ObjRef>;
+
+    /// Makes further tracing follow referents like any other field.
+    fn stop_discovery(&mut self);
+}
+
+/// Processes the references a tracer discovered in the order `java.lang.ref` specifies. Soft
+/// referents are kept alive unless the request says otherwise; then soft and weak references to
+/// objects nothing else reaches are cleared; then objects due for finalization are kept alive for
+/// their finalizer; then phantom references to objects that are still unreachable are cleared.
+/// Cleared references and those of objects to finalize are added to `pending`.
+fn process_references(tracer: &mut impl Tracer, heap: &mut Heap, classes: &ClassRegistry, clear_soft: bool, pending: &mut Vec<ObjRef>) {
+    let kind = |heap: &Heap, reference: ObjRef| classes.class(heap.class_of(reference)).reference_kind.unwrap();
+    let mut references: Vec<ObjRef> = Vec::new();
+    // Keeping soft referents alive can discover more references.
+    loop {
+        let discovered = tracer.take_discovered();
+        if discovered.is_empty() {
+            break
+        }
+        for reference in discovered {
+            let slot = referent_slot(heap, classes, reference).unwrap();
+            if let (ReferenceKind::Soft, false, Some(referent)) = (kind(heap, reference), clear_soft, heap.reference_at(slot)) {
+                let referent = tracer.keep_alive(heap, referent);
+                heap.set_reference_at(slot, referent);
+            }
+            references.push(reference);
+        }
+    }
+    let passes: [&[ReferenceKind]; 3] = [&[ReferenceKind::Soft, ReferenceKind::Weak], &[ReferenceKind::Final], &[ReferenceKind::Phantom]];
+    for pass in passes {
+        if pass == [ReferenceKind::Final] {
+            tracer.stop_discovery();
+        }
+        // Whether a referent is dead is decided for the whole pass, before any finalizable
+        // object is kept alive for its finalizer.
+        let mut dead = Vec::new();
+        for reference in references.iter().copied() {
+            if !pass.contains(&kind(heap, reference)) {
+                continue
+            }
+            let slot = referent_slot(heap, classes, reference).unwrap();
+            match heap.reference_at(slot) {
+                Some(referent) if tracer.is_alive(heap, referent) => {
+                    let referent = tracer.forward(heap, referent);
+                    heap.set_reference_at(slot, referent);
+                }
+                Some(referent) => dead.push((reference, slot, referent)),
+                None => {}
+            }
+        }
+        for (reference, slot, referent) in dead {
+            if pass == [ReferenceKind::Final] {
+                let referent = tracer.keep_alive(heap, referent);
+                heap.set_reference_at(slot, referent);
+            } else {
+                heap.clear_reference_at(slot);
+            }
+            pending.push(reference);
+        }
+    }
+}
+
+/// Marking in place, for the collectors that do not copy while they trace.
+struct Marker<'a> {
+    classes: &'a ClassRegistry,
+    stack: Vec<ObjRef>,
+    discovered: Vec<ObjRef>,
+    discovering: bool,
+    marked: usize
+}
+
+impl<'a> Marker<'a> {
+    fn new(classes: &'a ClassRegistry) -> Self {
+        Marker { classes, stack: Vec::new(), discovered: Vec::new(), discovering: true, marked: 0 }
+    }
+
+    fn mark(&mut self, heap: &mut Heap, objects: impl Iterator<Item = ObjRef>) {
+        self.stack.extend(objects);
+        while let Some(obj) = self.stack.pop() {
+            let word = heap.gc_word(obj);
+            if word & MARKED != 0 {
+                continue
+            }
+            heap.set_gc_word(obj, word | MARKED);
+            self.marked += 1;
+            let referent = if self.discovering { referent_slot(heap, self.classes, obj) } else { None };
+            if referent.is_some() {
+                self.discovered.push(obj);
+            }
+            for slot in reference_slots(heap, self.classes, obj) {
+                if let Some(target) = heap.reference_at(slot).filter(|_| Some(slot) != referent) {
+                    self.stack.push(target);
+                }
+            }
+        }
+    }
+}
+
+impl Tracer for Marker<'_> {
+    fn is_alive(&self, heap: &Heap, obj: ObjRef) -> bool {
+        heap.gc_word(obj) & MARKED != 0
+    }
+
+    fn forward(&self, _heap: &Heap, obj: ObjRef) -> ObjRef {
+        obj
+    }
+
+    fn keep_alive(&mut self, heap: &mut Heap, obj: ObjRef) -> ObjRef {
+        self.mark(heap, std::iter::once(obj));
+        obj
+    }
+
+    fn take_discovered(&mut self) -> Vec<ObjRef> {
+        std::mem::take(&mut self.discovered)
+    }
+
+    fn stop_discovery(&mut self) {
+        self.discovering = false;
+    }
+}
+
+/// Grows the arena by `size` bytes if the maximum heap size allows it.
+fn grow(heap: &mut Heap, size: u32) -> Option<u32> {
+    let address = heap.size();
+    if address + size as usize > heap.max_size() {
+        return None
+    }
+    heap.resize(address + size as usize);
+    Some(address as u32)
+}
+
+/// A collector that never moves objects: first-fit allocation from a list of free chunks before
+/// the arena grows, and a mark-sweep of the whole heap that merges neighbouring free chunks and
+/// gives a free chunk at the end of the arena back.
+#[derive(Default)]
+pub struct MarkSweep {
+    /// Free chunks as (address, size).
+    free: Vec<(u32, u32)>
+}
+
+impl GarbageCollector for MarkSweep {
+    fn name(&self) -> &'static str {
+        "MarkSweep"
+    }
+
+    fn allocate(&mut self, heap: &mut Heap, size: u32) -> Option<u32> {
+        match self.free.iter().position(|(_, chunk)| *chunk >= size) {
+            Some(index) => {
+                let (address, chunk) = self.free[index];
+                if chunk > size {
+                    self.free[index] = (address + size, chunk - size);
+                    heap.format_free(address + size, chunk - size);
+                } else {
+                    self.free.remove(index);
+                }
+                Some(address)
+            }
+            None => grow(heap, size)
+        }
+    }
+
+    fn collect(&mut self, heap: &mut Heap, classes: &ClassRegistry, roots: &mut [(RootKind, ObjRef)], request: CollectionRequest,
+               pending: &mut Vec<ObjRef>) -> CollectionStats {
+        let mut stats = CollectionStats::new(CollectionKind::Full);
+        let mut marker = Marker::new(classes);
+        marker.mark(heap, roots.iter().map(|(_, obj)| *obj));
+        process_references(&mut marker, heap, classes, request.clear_soft_references, pending);
+        stats.live_objects = marker.marked;
+        let mut free: Vec<(u32, u32)> = Vec::new();
+        let mut address = Heap::ALIGNMENT;
+        while (address as usize) < heap.size() {
+            let obj = ObjRef(address);
+            let size = heap.size_of(obj);
+            let is_free = heap.class_of(obj).0 == Heap::FREE;
+            if !is_free && heap.gc_word(obj) & MARKED != 0 {
+                heap.set_gc_word(obj, 0);
+            } else {
+                if !is_free {
+                    stats.freed_objects += 1;
+                    stats.freed_bytes += size as usize;
+                }
+                match free.last_mut() {
+                    Some((start, chunk)) if *start + *chunk == address => *chunk += size,
+                    _ => free.push((address, size))
+                }
+            }
+            address += size;
+        }
+        if let Some((start, chunk)) = free.last().copied() {
+            if (start + chunk) as usize == heap.size() {
+                heap.resize(start as usize);
+                free.pop();
+            }
+        }
+        for (start, chunk) in &free {
+            heap.format_free(*start, *chunk);
+        }
+        self.free = free;
+        stats
+    }
+}
+
+/// A generational collector after HotSpot's serial one. New objects are bump allocated in a nursery
+/// of fixed size at the start of the arena, except those larger than half of it. A minor collection
+/// copies the nursery objects that are still reachable into the old generation behind it, finding
+/// the old objects that point into the nursery through the remembered set the write barrier keeps.
+/// A full collection marks the whole heap and slides the live objects to the start of the old
+/// generation, nursery objects after old ones for as long as the maximum heap size allows.
+pub struct Generational {
+    nursery_end: u32,
+    /// Next free address in the nursery.
+    top: u32,
+    /// Old objects given a reference to a nursery object since the last collection.
+    remembered: Vec<ObjRef>,
+    /// Classes given a reference to a nursery object in a static since the last collection.
+    remembered_classes: HashSet<ClassId>,
+    /// Set when a full collection left objects in the nursery, so that statics were not tracked.
+    scan_all_statics: bool
+}
+
+impl Generational {
+    /// Sets up a nursery of `nursery_size` bytes at the start of an empty heap.
+    pub fn new(heap: &mut Heap, nursery_size: usize) -> Self {
+        let size = nursery_size as u32 / Heap::ALIGNMENT * Heap::ALIGNMENT;
+        heap.resize((Heap::ALIGNMENT + size) as usize);
+        heap.format_free(Heap::ALIGNMENT, size);
+        Generational {
+            nursery_end: Heap::ALIGNMENT + size,
+            top: Heap::ALIGNMENT,
+            remembered: Vec::new(),
+            remembered_classes: HashSet::new(),
+            scan_all_statics: false
+        }
+    }
+
+    fn is_young(&self, obj: ObjRef) -> bool {
+        obj.0 < self.nursery_end
+    }
+
+    fn reset_nursery(&mut self, heap: &mut Heap, top: u32) {
+        self.top = top;
+        heap.format_free(top, self.nursery_end - top);
+    }
+
+    fn remember(&mut self, heap: &mut Heap, holder: ObjRef) {
+        let word = heap.gc_word(holder);
+        if word & REMEMBERED == 0 {
+            heap.set_gc_word(holder, word | REMEMBERED);
+            self.remembered.push(holder);
+        }
+    }
+
+    /// The minor collection, a Cheney scan whose to-space is the end of the old generation.
+    fn scavenge(&mut self, heap: &mut Heap, classes: &ClassRegistry, roots: &mut [(RootKind, ObjRef)], request: CollectionRequest,
+                pending: &mut Vec<ObjRef>) -> CollectionStats {
+        let mut stats = CollectionStats::new(CollectionKind::Minor);
+        for obj in heap.objects_in(Heap::ALIGNMENT, self.top) {
+            stats.freed_objects += 1;
+            stats.freed_bytes += heap.size_of(obj) as usize;
+        }
+        let promoted = heap.size() as u32;
+        let mut scavenger = Scavenger { classes, nursery_end: self.nursery_end, scan: promoted, discovered: Vec::new(), discovering: true };
+        for (kind, root) in roots.iter_mut() {
+            if let RootKind::Static(class) = kind {
+                if !self.scan_all_statics && !self.remembered_classes.contains(class) {
+                    continue
+                }
+            }
+            *root = scavenger.evacuate(heap, *root);
+        }
+        for holder in std::mem::take(&mut self.remembered) {
+            heap.set_gc_word(holder, heap.gc_word(holder) & !REMEMBERED);
+            scavenger.scan_object(heap, holder);
+        }
+        scavenger.scan(heap);
+        process_references(&mut scavenger, heap, classes, request.clear_soft_references, pending);
+        stats.live_objects = heap.objects_in(promoted, heap.size() as u32).count();
+        stats.freed_objects -= stats.live_objects;
+        stats.freed_bytes -= heap.size() - promoted as usize;
+        self.remembered_classes.clear();
+        self.scan_all_statics = false;
+        self.reset_nursery(heap, Heap::ALIGNMENT);
+        stats
+    }
+
+    /// The full collection, a Lisp 2 style mark-compact. Objects only ever slide towards lower
+    /// addresses within their generation, and nursery objects are copied into the old generation
+    /// only after every old object has moved, so no object is overwritten before it has moved.
+    fn mark_compact(&mut self, heap: &mut Heap, classes: &ClassRegistry, roots: &mut [(RootKind, ObjRef)], request: CollectionRequest,
+                    pending: &mut Vec<ObjRef>) -> CollectionStats {
+        let mut stats = CollectionStats::new(CollectionKind::Full);
+        let mut marker = Marker::new(classes);
+        marker.mark(heap, roots.iter().map(|(_, obj)| *obj));
+        process_references(&mut marker, heap, classes, request.clear_soft_references, pending);
+        let objects: Vec<ObjRef> = heap.objects_in(self.nursery_end, heap.size() as u32)
+            .chain(heap.objects_in(Heap::ALIGNMENT, self.top))
+            .collect();
+        let mut live: Vec<ObjRef> = Vec::new();
+        let mut old_top = self.nursery_end;
+        let mut young_top = Heap::ALIGNMENT;
+        for obj in objects {
+            let size = heap.size_of(obj);
+            if heap.gc_word(obj) & MARKED == 0 {
+                stats.freed_objects += 1;
+                stats.freed_bytes += size as usize;
+                continue
+            }
+            let top = if !self.is_young(obj) || (old_top + size) as usize <= heap.max_size() { &mut old_top } else { &mut young_top };
+            heap.set_gc_word(obj, *top | MARKED);
+            *top += size;
+            live.push(obj);
+        }
+        stats.live_objects = live.len();
+        let forward = |heap: &Heap, obj: ObjRef| ObjRef(heap.gc_word(obj) & !FLAGS);
+        for root in roots.iter_mut().map(|(_, root)| root).chain(pending.iter_mut()) {
+            *root = forward(heap, *root);
+        }
+        for obj in &live {
+            for slot in reference_slots(heap, classes, *obj) {
+                if let Some(target) = heap.reference_at(slot) {
+                    heap.set_reference_at(slot, forward(heap, target));
+                }
+            }
+        }
+        if heap.size() < old_top as usize {
+            heap.resize(old_top as usize);
+        }
+        for obj in live {
+            let moved = heap.copy_object(obj, forward(heap, obj).0);
+            heap.set_gc_word(moved, 0);
+        }
+        heap.resize(old_top as usize);
+        self.reset_nursery(heap, young_top);
+        self.remembered.clear();
+        self.remembered_classes.clear();
+        self.scan_all_statics = young_top > Heap::ALIGNMENT;
+        if self.scan_all_statics {
+            let old: Vec<ObjRef> = heap.objects_in(self.nursery_end, old_top).collect();
+            for obj in old {
+                let slots = reference_slots(heap, classes, obj);
+                if slots.into_iter().any(|slot| heap.reference_at(slot).is_some_and(|target| self.is_young(target))) {
+                    self.remember(heap, obj);
+                }
+            }
+        }
+        stats
+    }
+}
+
+/// The tracing of a minor collection: nursery objects are copied to the end of the old generation
+/// when first reached, and the copies are scanned in order.
+struct Scavenger<'a> {
+    classes: &'a ClassRegistry,
+    nursery_end: u32,
+    /// The next copy to scan.
+    scan: u32,
+    discovered: Vec<ObjRef>,
+    discovering: bool
+}
+
+impl Scavenger<'_> {
+    fn is_young(&self, obj: ObjRef) -> bool {
+        obj.0 < self.nursery_end
+    }
+
+    /// Copies a nursery object the first time it is reached and returns where it is now.
+    fn evacuate(&self, heap: &mut Heap, obj: ObjRef) -> ObjRef {
+        if !self.is_young(obj) {
+            return obj
+        }
+        let word = heap.gc_word(obj);
+        if word & FORWARDED != 0 {
+            return ObjRef(word & !FLAGS)
+        }
+        let address = heap.size() as u32;
+        heap.resize(address as usize + heap.size_of(obj) as usize);
+        let copy = heap.copy_object(obj, address);
+        heap.set_gc_word(copy, 0);
+        heap.set_gc_word(obj, address | FORWARDED);
+        copy
+    }
+
+    /// Evacuates what an object refers to, leaving the referent of a reference object to be
+    /// processed later.
+    fn scan_object(&mut self, heap: &mut Heap, obj: ObjRef) {
+        let referent = if self.discovering { referent_slot(heap, self.classes, obj) } else { None };
+        if referent.is_some() {
+            self.discovered.push(obj);
+        }
+        for slot in reference_slots(heap, self.classes, obj) {
+            if let Some(target) = heap.reference_at(slot).filter(|_| Some(slot) != referent) {
+                let target = self.evacuate(heap, target);
+                heap.set_reference_at(slot, target);
+            }
+        }
+    }
+
+    /// Scans the copies made since the last scan, which may copy more.
+    fn scan(&mut self, heap: &mut Heap) {
+        while (self.scan as usize) < heap.size() {
+            let obj = ObjRef(self.scan);
+            self.scan_object(heap, obj);
+            self.scan += heap.size_of(obj);
+        }
+    }
+}
+
+impl Tracer for Scavenger<'_> {
+    fn is_alive(&self, heap: &Heap, obj: ObjRef) -> bool {
+        !self.is_young(obj) || heap.gc_word(obj) & FORWARDED != 0
+    }
+
+    fn forward(&self, heap: &Heap, obj: ObjRef) -> ObjRef {
+        if self.is_young(obj) { ObjRef(heap.gc_word(obj) & !FLAGS) } else { obj }
+    }
+
+    fn keep_alive(&mut self, heap: &mut Heap, obj: ObjRef) -> ObjRef {
+        let obj = self.evacuate(heap, obj);
+        self.scan(heap);
+        obj
+    }
+
+    fn take_discovered(&mut self) -> Vec<ObjRef> {
+        std::mem::take(&mut self.discovered)
+    }
+
+    fn stop_discovery(&mut self) {
+        self.discovering = false;
+    }
+}
+
+impl GarbageCollector for Generational {
+    fn name(&self) -> &'static str {
+        "Generational"
+    }
+
+    fn allocate(&mut self, heap: &mut Heap, size: u32) -> Option<u32> {
+        if size > (self.nursery_end - Heap::ALIGNMENT) / 2 {
+            return grow(heap, size)
+        }
+        if self.top + size > self.nursery_end {
+            return None
+        }
+        let address = self.top;
+        self.reset_nursery(heap, address + size);
+        Some(address)
+    }
+
+    fn collect(&mut self, heap: &mut Heap, classes: &ClassRegistry, roots: &mut [(RootKind, ObjRef)], request: CollectionRequest,
+               pending: &mut Vec<ObjRef>) -> CollectionStats {
+        // A minor collection promotes everything that survives, which has to fit.
+        let nursery_used = (self.top - Heap::ALIGNMENT) as usize;
+        if request.full || heap.size() + nursery_used > heap.max_size() {
+            self.mark_compact(heap, classes, roots, request, pending)
+        } else {
+            self.scavenge(heap, classes, roots, request, pending)
+        }
+    }
+
+    fn write_barrier(&mut self, heap: &mut Heap, holder: ObjRef, value: ObjRef) {
+        if !self.is_young(holder) && self.is_young(value) {
+            self.remember(heap, holder);
+        }
+    }
+
+    fn static_write_barrier(&mut self, class: ClassId, value: ObjRef) {
+        if self.is_young(value) {
+            self.remembered_classes.insert(class);
+        }
+    }
+}
+
+impl VM {
+    /// Runs a full collection and returns what it did.
+    pub fn collect_garbage(&mut self) -> CollectionStats {
+        self.collect(CollectionRequest { full: true, clear_soft_references: false }, "System.gc()")
+    }
+
+    /// Runs a collection with the current roots and moves them along with their objects.
+    pub(crate) fn collect(&mut self, request: CollectionRequest, cause: &str) -> CollectionStats {
+        let used_before = self.heap.used();
+        let start = Instant::now();
+        let mut roots: Vec<(RootKind, ObjRef)> = Vec::new();
+        self.visit_roots_mut(|kind, obj| roots.push((kind, *obj)));
+        let mut pending: Vec<ObjRef> = Vec::new();
+        let mut stats = self.collector.collect(&mut self.heap, &self.classes, &mut roots, request, &mut pending);
+        let mut moved = roots.into_iter();
+        self.visit_roots_mut(|_, obj| *obj = moved.next().unwrap().1);
+        self.pending_references.extend(pending);
+        stats.pause = start.elapsed();
+        stats.used_before = used_before;
+        stats.used_after = self.heap.used();
+        if self.options.log_gc {
+            let pause = match stats.kind {
+                CollectionKind::Minor => "Pause Young",
+                CollectionKind::Full => "Pause Full"
+            };
+            println!("[{:.3}s][info][gc] GC({}) {} ({}) {}K->{}K({}K) {:.3}ms", self.started.elapsed().as_secs_f64(),
+                self.gc_stats.collections(), pause, cause, stats.used_before >> 10, stats.used_after >> 10,
+                self.heap.size() >> 10, stats.pause.as_secs_f64() * 1000.0);
+        }
+        self.gc_stats.record(&stats);
+        stats
+    }
+
+    pub fn gc_stats(&self) -> GcStats {
+        self.gc_stats
+    }
+
+    pub fn collector_name(&self) -> &'static str {
+        self.collector.name()
+    }
+
+    /// The objects `obj` refers to through its reference fields or, for arrays, its elements.
+    pub fn references(&self, obj: ObjRef) -> Vec<ObjRef> {
+        reference_slots(&self.heap, &self.classes, obj).into_iter()
+            .filter_map(|slot| self.heap.reference_at(slot))
+            .collect()
+    }
+}
+
+/// Parses a heap size the way `-Xmx` spells it: bytes, or a number with a `k`, `m` or `g` suffix.
+pub fn parse_heap_size(value: &str) -> Option<usize> {
+    let (digits, unit) = match value.char_indices().last()? {
+        (index, 'k' | 'K') => (&value[..index], 1 << 10),
+        (index, 'm' | 'M') => (&value[..index], 1 << 20),
+        (index, 'g' | 'G') => (&value[..index], 1 << 30),
+        _ => (value, 1)
+    };
+    let size = digits.parse::<usize>().ok()?.checked_mul(unit)?;
+    // References are 32-bit heap addresses.
+    (size >= Heap::HEADER_SIZE as usize && size <= u32::MAX as usize).then_some(size)
+}
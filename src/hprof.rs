@@ -0,0 +1,465 @@
+//! Heap dumps in HotSpot's HPROF binary format (`JAVA PROFILE 1.0.2`), which Eclipse MAT, VisualVM
+//! and other heap analyzers read.
+//!
+//! A dump holds the name of every class, the stack of the running thread, a class dump for every
+//! class with its static values and instance field descriptors, every object and array in the heap
+//! and the GC roots. Identifiers are 8 bytes wide; objects are identified by their address, classes
+//! by the address of their mirror, or by a number above every heap address when they have none.
+
+use std::collections::{HashMap, HashSet};
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+use crate::descriptor::FieldType;
+use crate::env::VMEnv;
+use crate::interpret::JavaException;
+use crate::loader::AccessFlags;
+use crate::registry::{ClassId, RuntimeClass};
+use crate::vm::{Heap, ObjRef, RootKind, VMValue, VM};
+
+const HEADER: &[u8] = b"JAVA PROFILE 1.0.2\0";
+const ID_SIZE: u32 = 8;
+
+const TAG_UTF8: u8 = 0x01;
+const TAG_LOAD_CLASS: u8 = 0x02;
+const TAG_STACK_FRAME: u8 = 0x04;
+const TAG_STACK_TRACE: u8 = 0x05;
+const TAG_HEAP_DUMP_SEGMENT: u8 = 0x1c;
+const TAG_HEAP_DUMP_END: u8 = 0x2c;
+
+const ROOT_UNKNOWN: u8 = 0xff;
+const ROOT_JNI_GLOBAL: u8 = 0x01;
+const ROOT_JAVA_FRAME: u8 = 0x03;
+const ROOT_STICKY_CLASS: u8 = 0x05;
+const CLASS_DUMP: u8 = 0x20;
+const INSTANCE_DUMP: u8 = 0x21;
+const OBJECT_ARRAY_DUMP: u8 = 0x22;
+const PRIMITIVE_ARRAY_DUMP: u8 = 0x23;
+
+/// The serial number of the only thread.
+const THREAD_SERIAL: u32 = 1;
+/// The stack trace objects and classes refer to, which is empty as allocation sites are not kept.
+const EMPTY_TRACE_SERIAL: u32 = 1;
+const THREAD_TRACE_SERIAL: u32 = 2;
+/// Heap dump segments are written out once they grow past this size.
+const SEGMENT_SIZE: usize = 1 << 20;
+/// IDs of classes without a mirror start here, above every heap address.
+const CLASS_ID_BASE: u64 = 1 << 32;
+
+/// The HPROF basic type of a field or array element.
+fn basic_type(field_type: &FieldType) -> u8 {
+    match field_type {
+        FieldType::Object(_) | FieldType::Array(_) => 2,
+        FieldType::Boolean => 4,
+        FieldType::Char => 5,
+        FieldType::Float => 6,
+        FieldType::Double => 7,
+        FieldType::Byte => 8,
+        FieldType::Short => 9,
+        FieldType::Int => 10,
+        FieldType::Long => 11
+    }
+}
+
+/// Big-endian writing of the values records are made of.
+trait Put {
+    fn u1(&mut self, value: u8);
+    fn u2(&mut self, value: u16);
+    fn u4(&mut self, value: u32);
+    fn u8(&mut self, value: u64);
+
+    fn id(&mut self, value: u64) {
+        self.u8(value)
+    }
+}
+
+impl Put for Vec<u8> {
+    fn u1(&mut self, value: u8) {
+        self.push(value)
+    }
+
+    fn u2(&mut self, value: u16) {
+        self.extend_from_slice(&value.to_be_bytes())
+    }
+
+    fn u4(&mut self, value: u32) {
+        self.extend_from_slice(&value.to_be_bytes())
+    }
+
+    fn u8(&mut self, value: u64) {
+        self.extend_from_slice(&value.to_be_bytes())
+    }
+}
+
+struct HprofWriter<'a, W: Write> {
+    out: W,
+    vm: &'a VM,
+    /// IDs of the strings written so far.
+    strings: HashMap<String, u64>,
+    /// The ID of every class, by class ID; `None` for the pseudo classes of primitive types.
+    class_ids: Vec<Option<u64>>,
+    segment: Vec<u8>
+}
+
+impl<'a, W: Write> HprofWriter<'a, W> {
+    fn record(&mut self, tag: u8, body: &[u8]) -> io::Result<()> {
+        let mut header = Vec::with_capacity(9);
+        header.u1(tag);
+        header.u4(0);
+        header.u4(body.len() as u32);
+        self.out.write_all(&header)?;
+        self.out.write_all(body)
+    }
+
+    /// The ID of a string, writing its UTF8 record the first time.
+    fn string(&mut self, value: &str) -> io::Result<u64> {
+        if let Some(id) = self.strings.get(value) {
+            return Ok(*id)
+        }
+        let id = self.strings.len() as u64 + 1;
+        let mut body = Vec::new();
+        body.id(id);
+        body.extend_from_slice(value.as_bytes());
+        self.record(TAG_UTF8, &body)?;
+        self.strings.insert(value.to_string(), id);
+        Ok(id)
+    }
+
+    fn class_id(&self, class: ClassId) -> u64 {
+        self.class_ids[class.0 as usize].unwrap_or(0)
+    }
+
+    fn flush_segment(&mut self, force: bool) -> io::Result<()> {
+        if self.segment.len() >= SEGMENT_SIZE || (force && !self.segment.is_empty()) {
+            let segment = std::mem::take(&mut self.segment);
+            self.record(TAG_HEAP_DUMP_SEGMENT, &segment)?;
+        }
+        Ok(())
+    }
+
+    fn put_value(&mut self, field_type: &FieldType, value: VMValue) {
+        let segment = &mut self.segment;
+        match (field_type, value) {
+            (FieldType::Boolean | FieldType::Byte, VMValue::Int(v)) => segment.u1(v as u8),
+            (FieldType::Char | FieldType::Short, VMValue::Int(v)) => segment.u2(v as u16),
+            (FieldType::Int, VMValue::Int(v)) => segment.u4(v as u32),
+            (FieldType::Float, VMValue::Float(v)) => segment.u4(v.to_bits()),
+            (FieldType::Long, VMValue::Long(v)) => segment.u8(v as u64),
+            (FieldType::Double, VMValue::Double(v)) => segment.u8(v.to_bits()),
+            (_, VMValue::Reference(obj)) => segment.id(obj.0 as u64),
+            // Statics of classes that were never prepared.
+            (field_type, _) => segment.extend(std::iter::repeat_n(0, value_size(field_type) as usize))
+        }
+    }
+
+    /// LOAD CLASS records for every class but the primitive pseudo classes.
+    fn load_classes(&mut self) -> io::Result<()> {
+        let vm = self.vm;
+        for class in vm.classes.classes() {
+            if self.class_ids[class.id.0 as usize].is_none() {
+                continue
+            }
+            let name = self.string(&class.name)?;
+            let mut body = Vec::new();
+            body.u4(class.id.0 + 1);
+            body.id(self.class_id(class.id));
+            body.u4(EMPTY_TRACE_SERIAL);
+            body.id(name);
+            self.record(TAG_LOAD_CLASS, &body)?;
+        }
+        Ok(())
+    }
+
+    /// The empty trace and that of the running thread, innermost frame first.
+    fn stack_traces(&mut self, env: &VMEnv) -> io::Result<()> {
+        let mut body = Vec::new();
+        body.u4(EMPTY_TRACE_SERIAL);
+        body.u4(0);
+        body.u4(0);
+        self.record(TAG_STACK_TRACE, &body)?;
+        let mut frame_ids = Vec::new();
+        for frame in env.frames().iter().rev() {
+            let method = frame.method();
+            let frame_id = frame_ids.len() as u64 + 1;
+            let name = self.string(&method.method_name)?;
+            let signature = self.string(&method.descriptor)?;
+            let source_file = match &frame.class_file.source_file_name {
+                Some(source_file) => self.string(source_file)?,
+                None => 0
+            };
+            let line = if method.access_flags.contains(&AccessFlags::Native) {
+                -3
+            } else {
+                method.line_number(frame.pc).map_or(0, |line| line as i32)
+            };
+            let mut body = Vec::new();
+            body.id(frame_id);
+            body.id(name);
+            body.id(signature);
+            body.id(source_file);
+            body.u4(frame.class.0 + 1);
+            body.u4(line as u32);
+            self.record(TAG_STACK_FRAME, &body)?;
+            frame_ids.push(frame_id);
+        }
+        let mut body = Vec::new();
+        body.u4(THREAD_TRACE_SERIAL);
+        body.u4(THREAD_SERIAL);
+        body.u4(frame_ids.len() as u32);
+        for id in frame_ids {
+            body.id(id);
+        }
+        self.record(TAG_STACK_TRACE, &body)
+    }
+
+    /// Stack slots are roots of the frame they belong to, the other roots of the VM have no
+    /// counterpart in HPROF but JNI global references for handles.
+    fn roots(&mut self, env: &VMEnv) -> io::Result<()> {
+        let vm = self.vm;
+        let frames = env.frames();
+        for index in 0..vm.stack_pointer() {
+            let obj = match vm.get(index) {
+                VMValue::Reference(obj) => obj,
+                _ => continue
+            };
+            match frames.iter().rposition(|frame| frame.locals <= index) {
+                Some(frame) => {
+                    self.segment.u1(ROOT_JAVA_FRAME);
+                    self.segment.id(obj.0 as u64);
+                    self.segment.u4(THREAD_SERIAL);
+                    self.segment.u4((frames.len() - 1 - frame) as u32);
+                }
+                None => {
+                    self.segment.u1(ROOT_UNKNOWN);
+                    self.segment.id(obj.0 as u64);
+                }
+            }
+        }
+        for (kind, obj) in vm.roots() {
+            match kind {
+                // Statics are in the class dumps and mirrors are the classes themselves.
+                RootKind::Stack | RootKind::Static(_) => continue,
+                RootKind::Class(class) if vm.classes.class(class).mirror == Some(obj) => continue,
+                RootKind::Handle | RootKind::GlobalHandle => {
+                    self.segment.u1(ROOT_JNI_GLOBAL);
+                    self.segment.id(obj.0 as u64);
+                    self.segment.id(obj.0 as u64);
+                }
+                RootKind::Class(_) | RootKind::InternedString | RootKind::PendingReference => {
+                    self.segment.u1(ROOT_UNKNOWN);
+                    self.segment.id(obj.0 as u64);
+                }
+            }
+        }
+        for class in vm.classes.classes() {
+            if self.class_ids[class.id.0 as usize].is_some() {
+                self.segment.u1(ROOT_STICKY_CLASS);
+                self.segment.id(self.class_id(class.id));
+            }
+        }
+        self.flush_segment(false)
+    }
+
+    fn class_dump(&mut self, class: &RuntimeClass) -> io::Result<()> {
+        let mut statics = Vec::new();
+        let mut fields = Vec::new();
+        for (index, field) in class.class.fields.iter().enumerate() {
+            let field_type = match FieldType::parse(&field.descriptor) {
+                Ok(field_type) => field_type,
+                Err(_) => continue
+            };
+            let name = self.string(&field.field_name)?;
+            if field.is_static() {
+                let value = class.statics.get(index).copied().unwrap_or(VMValue::Null);
+                statics.push((name, field_type, value));
+            } else {
+                fields.push((name, field_type));
+            }
+        }
+        let instance_size = if class.is_array() || class.is_interface() { 0 } else { Heap::object_size(class.layout.size) };
+        let super_id = class.super_class.map_or(0, |super_class| self.class_id(super_class));
+        let id = self.class_id(class.id);
+        let segment = &mut self.segment;
+        segment.u1(CLASS_DUMP);
+        segment.id(id);
+        segment.u4(EMPTY_TRACE_SERIAL);
+        segment.id(super_id);
+        // Class loader, signers, protection domain and two reserved IDs.
+        for _ in 0..5 {
+            segment.id(0);
+        }
+        segment.u4(instance_size);
+        segment.u2(0);
+        segment.u2(statics.len() as u16);
+        for (name, field_type, value) in statics {
+            self.segment.id(name);
+            self.segment.u1(basic_type(&field_type));
+            self.put_value(&field_type, value);
+        }
+        self.segment.u2(fields.len() as u16);
+        for (name, field_type) in fields {
+            self.segment.id(name);
+            self.segment.u1(basic_type(&field_type));
+        }
+        self.flush_segment(false)
+    }
+
+    fn object_dump(&mut self, obj: ObjRef) -> io::Result<()> {
+        let vm = self.vm;
+        let class = vm.classes.class(vm.heap.class_of(obj));
+        match &class.component_type {
+            Some(element_type) if element_type.is_reference() => {
+                let length = vm.heap.array_length(obj);
+                self.segment.u1(OBJECT_ARRAY_DUMP);
+                self.segment.id(obj.0 as u64);
+                self.segment.u4(EMPTY_TRACE_SERIAL);
+                self.segment.u4(length);
+                self.segment.id(self.class_id(class.id));
+                for index in 0..length {
+                    let element = vm.heap.read_element(obj, index, element_type);
+                    self.put_value(element_type, element);
+                }
+            }
+            Some(element_type) => {
+                let length = vm.heap.array_length(obj);
+                self.segment.u1(PRIMITIVE_ARRAY_DUMP);
+                self.segment.id(obj.0 as u64);
+                self.segment.u4(EMPTY_TRACE_SERIAL);
+                self.segment.u4(length);
+                self.segment.u1(basic_type(element_type));
+                for index in 0..length {
+                    let element = vm.heap.read_element(obj, index, element_type);
+                    self.put_value(element_type, element);
+                }
+            }
+            None => {
+                // Field values go from the object's class up to `java.lang.Object`.
+                let mut values = Vec::new();
+                let mut current = Some(class.id);
+                while let Some(id) = current {
+                    let class = vm.classes.class(id);
+                    let layout = &class.layout;
+                    for (offset, field_type) in layout.field_offsets.iter().zip(&layout.field_types) {
+                        if let (Some(offset), Some(field_type)) = (offset, field_type) {
+                            values.push((field_type, vm.heap.read_field(obj, *offset, field_type)));
+                        }
+                    }
+                    current = class.super_class;
+                }
+                let size: u32 = values.iter().map(|(field_type, _)| value_size(field_type)).sum();
+                self.segment.u1(INSTANCE_DUMP);
+                self.segment.id(obj.0 as u64);
+                self.segment.u4(EMPTY_TRACE_SERIAL);
+                self.segment.id(self.class_id(class.id));
+                self.segment.u4(size);
+                for (field_type, value) in values {
+                    self.put_value(field_type, value);
+                }
+            }
+        }
+        self.flush_segment(false)
+    }
+
+    fn write(mut self, env: &VMEnv) -> io::Result<()> {
+        let vm = self.vm;
+        let millis = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |time| time.as_millis() as u64);
+        let mut header = HEADER.to_vec();
+        header.u4(ID_SIZE);
+        header.u8(millis);
+        self.out.write_all(&header)?;
+
+        self.load_classes()?;
+        self.stack_traces(env)?;
+        self.roots(env)?;
+        // Mirrors are dumped as the classes, except those of primitive types, which are plain objects.
+        let mirrors: HashSet<ObjRef> = vm.classes.classes().iter()
+            .filter(|class| self.class_ids[class.id.0 as usize].is_some())
+            .filter_map(|class| class.mirror)
+            .collect();
+        for class in vm.classes.classes() {
+            if self.class_ids[class.id.0 as usize].is_some() {
+                self.class_dump(class)?;
+            }
+        }
+        for obj in vm.heap.objects() {
+            if !mirrors.contains(&obj) {
+                self.object_dump(obj)?;
+            }
+        }
+        self.flush_segment(true)?;
+        self.record(TAG_HEAP_DUMP_END, &[])?;
+        self.out.flush()
+    }
+}
+
+/// Bytes a value of the type takes in a dump.
+fn value_size(field_type: &FieldType) -> u32 {
+    match field_type {
+        FieldType::Object(_) | FieldType::Array(_) => ID_SIZE,
+        field_type => Heap::field_size(field_type)
+    }
+}
+
+impl VMEnv {
+    /// Writes an HPROF dump of the heap, with the stack of the running code.
+    pub fn write_heap_dump(&self, out: impl Write) -> io::Result<()> {
+        let vm = self.vm();
+        let class_ids = vm.classes.classes().iter().map(|class| match class.mirror {
+            _ if vm.classes.is_primitive(class.id) => None,
+            Some(mirror) => Some(mirror.0 as u64),
+            None => Some(CLASS_ID_BASE + class.id.0 as u64)
+        }).collect();
+        let writer = HprofWriter { out, vm, strings: HashMap::new(), class_ids, segment: Vec::new() };
+        writer.write(self)
+    }
+
+    /// Writes an HPROF dump of the heap to a new file and returns its size. Like `jmap`, it does
+    /// not overwrite an existing file.
+    pub fn dump_heap(&self, path: impl AsRef<Path>) -> io::Result<u64> {
+        let file = File::options().write(true).create_new(true).open(path)?;
+        let mut out = BufWriter::new(file);
+        self.write_heap_dump(&mut out)?;
+        let file = out.into_inner().map_err(|e| e.into_error())?;
+        file.metadata().map(|metadata| metadata.len())
+    }
+
+    /// Dumps the heap when the VM first runs out of it with `-XX:+HeapDumpOnOutOfMemoryError`,
+    /// reporting on standard output like HotSpot.
+    pub(crate) fn dump_heap_on_out_of_memory(&mut self, exception: &JavaException) {
+        if !self.vm().options.heap_dump_on_out_of_memory || self.heap_dumped {
+            return
+        }
+        self.heap_dumped = true;
+        let path = heap_dump_file(self.vm().options.heap_dump_path.as_deref());
+        println!("{}", exception);
+        println!("Dumping heap to {} ...", path.display());
+        let start = Instant::now();
+        match self.dump_heap(&path) {
+            Ok(size) => println!("Heap dump file created [{} bytes in {:.3} secs]", size, start.elapsed().as_secs_f64()),
+            Err(e) => println!("Unable to create {}: {}", path.display(), io_error_message(&e))
+        }
+    }
+}
+
+/// Where `-XX:HeapDumpPath` puts a dump: the given file, or `java_pid<pid>.hprof` in the given
+/// directory or the working directory.
+pub fn heap_dump_file(path: Option<&Path>) -> PathBuf {
+    let name = format!("java_pid{}.hprof", std::process::id());
+    match path {
+        Some(path) if path.is_dir() => path.join(name),
+        Some(path) => path.to_path_buf(),
+        None => PathBuf::from(name)
+    }
+}
+
+/// The message of an I/O error without the OS error code Rust appends.
+fn io_error_message(error: &io::Error) -> String {
+    match error.kind() {
+        io::ErrorKind::AlreadyExists => "File exists".to_string(),
+        _ => {
+            let message = error.to_string();
+            message.split(" (os error").next().unwrap_or(&message).to_string()
+        }
+    }
+}
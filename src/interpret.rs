@@ -1,49 +1,587 @@
+use std::fmt::{Display, Formatter};
+use crate::loader::ClassLoadError;
+use crate::vm::ObjRef;
+
+#[derive(Debug, Clone, PartialEq)]
 pub enum Opcode {
+    Nop,
     AConstNull,
     IConst(i8),
+    LConst(i64),
+    FConst(f32),
+    DConst(f64),
+    BiPush(i8),
+    SiPush(i16),
+    Ldc(u16),
+    LdcW(u16),
+    Ldc2W(u16),
+    ILoad(u16),
+    LLoad(u16),
+    FLoad(u16),
+    DLoad(u16),
+    ALoad(u16),
+    IALoad,
+    LALoad,
+    FALoad,
+    DALoad,
+    AALoad,
+    BALoad,
+    CALoad,
+    SALoad,
+    IStore(u16),
+    LStore(u16),
+    FStore(u16),
+    DStore(u16),
+    AStore(u16),
+    IAStore,
+    LAStore,
+    FAStore,
+    DAStore,
+    AAStore,
+    BAStore,
+    CAStore,
+    SAStore,
+    Pop,
+    Pop2,
+    Dup,
+    DupX1,
+    DupX2,
+    Dup2,
+    Dup2X1,
+    Dup2X2,
+    Swap,
     IAdd,
+    LAdd,
+    FAdd,
+    DAdd,
+    ISub,
+    LSub,
+    FSub,
+    DSub,
+    IMul,
+    LMul,
+    FMul,
+    DMul,
+    IDiv,
+    LDiv,
+    FDiv,
+    DDiv,
+    IRem,
+    LRem,
+    FRem,
+    DRem,
+    INeg,
+    LNeg,
+    FNeg,
+    DNeg,
+    IShl,
+    LShl,
+    IShr,
+    LShr,
+    IUShr,
+    LUShr,
     IAnd,
+    LAnd,
+    IOr,
+    LOr,
+    IXor,
+    LXor,
+    IInc(u16, i16),
+    I2L,
+    I2F,
+    I2D,
+    L2I,
+    L2F,
+    L2D,
+    F2I,
+    F2L,
+    F2D,
+    D2I,
+    D2L,
+    D2F,
     I2B,
     I2C,
-    I2D,
-    I2F,
-    I2L,
     I2S,
-    IMul
+    LCmp,
+    FCmpL,
+    FCmpG,
+    DCmpL,
+    DCmpG,
+    IfEq(i32),
+    IfNe(i32),
+    IfLt(i32),
+    IfGe(i32),
+    IfGt(i32),
+    IfLe(i32),
+    IfICmpEq(i32),
+    IfICmpNe(i32),
+    IfICmpLt(i32),
+    IfICmpGe(i32),
+    IfICmpGt(i32),
+    IfICmpLe(i32),
+    IfACmpEq(i32),
+    IfACmpNe(i32),
+    Goto(i32),
+    Jsr(i32),
+    Ret(u16),
+    TableSwitch(i32, i32, i32, Vec<i32>),
+    LookupSwitch(i32, Vec<(i32, i32)>),
+    IReturn,
+    LReturn,
+    FReturn,
+    DReturn,
+    AReturn,
+    Return,
+    GetStatic(u16),
+    PutStatic(u16),
+    GetField(u16),
+    PutField(u16),
+    InvokeVirtual(u16),
+    InvokeSpecial(u16),
+    InvokeStatic(u16),
+    InvokeInterface(u16, u8),
+    InvokeDynamic(u16),
+    New(u16),
+    NewArray(u8),
+    ANewArray(u16),
+    ArrayLength,
+    AThrow,
+    CheckCast(u16),
+    InstanceOf(u16),
+    MonitorEnter,
+    MonitorExit,
+    MultiANewArray(u16, u8),
+    IfNull(i32),
+    IfNonNull(i32),
+    GotoW(i32),
+    JsrW(i32)
+}
+
+impl Opcode {
+    /// Returns the branch targets of a control transfer instruction as offsets relative to its own pc.
+    pub fn branch_offsets(&self) -> Vec<i32> {
+        match self {
+            Opcode::IfEq(o) | Opcode::IfNe(o) | Opcode::IfLt(o) | Opcode::IfGe(o) | Opcode::IfGt(o) | Opcode::IfLe(o)
+            | Opcode::IfICmpEq(o) | Opcode::IfICmpNe(o) | Opcode::IfICmpLt(o) | Opcode::IfICmpGe(o)
+            | Opcode::IfICmpGt(o) | Opcode::IfICmpLe(o) | Opcode::IfACmpEq(o) | Opcode::IfACmpNe(o)
+            | Opcode::Goto(o) | Opcode::Jsr(o) | Opcode::IfNull(o) | Opcode::IfNonNull(o)
+            | Opcode::GotoW(o) | Opcode::JsrW(o) => vec![*o],
+            Opcode::TableSwitch(default, _, _, offsets) => {
+                let mut ret = offsets.clone();
+                ret.push(*default);
+                ret
+            }
+            Opcode::LookupSwitch(default, pairs) => {
+                let mut ret: Vec<i32> = pairs.iter().map(|(_, offset)| *offset).collect();
+                ret.push(*default);
+                ret
+            }
+            _ => vec![]
+        }
+    }
+}
+
+/// Mnemonics indexed by opcode byte, as printed by `javap`.
+const MNEMONICS: [&str; 202] = [
+    "nop", "aconst_null", "iconst_m1", "iconst_0", "iconst_1", "iconst_2", "iconst_3", "iconst_4",
+    "iconst_5", "lconst_0", "lconst_1", "fconst_0", "fconst_1", "fconst_2", "dconst_0", "dconst_1",
+    "bipush", "sipush", "ldc", "ldc_w", "ldc2_w", "iload", "lload", "fload",
+    "dload", "aload", "iload_0", "iload_1", "iload_2", "iload_3", "lload_0", "lload_1",
+    "lload_2", "lload_3", "fload_0", "fload_1", "fload_2", "fload_3", "dload_0", "dload_1",
+    "dload_2", "dload_3", "aload_0", "aload_1", "aload_2", "aload_3", "iaload", "laload",
+    "faload", "daload", "aaload", "baload", "caload", "saload", "istore", "lstore",
+    "fstore", "dstore", "astore", "istore_0", "istore_1", "istore_2", "istore_3", "lstore_0",
+    "lstore_1", "lstore_2", "lstore_3", "fstore_0", "fstore_1", "fstore_2", "fstore_3", "dstore_0",
+    "dstore_1", "dstore_2", "dstore_3", "astore_0", "astore_1", "astore_2", "astore_3", "iastore",
+    "lastore", "fastore", "dastore", "aastore", "bastore", "castore", "sastore", "pop",
+    "pop2", "dup", "dup_x1", "dup_x2", "dup2", "dup2_x1", "dup2_x2", "swap",
+    "iadd", "ladd", "fadd", "dadd", "isub", "lsub", "fsub", "dsub",
+    "imul", "lmul", "fmul", "dmul", "idiv", "ldiv", "fdiv", "ddiv",
+    "irem", "lrem", "frem", "drem", "ineg", "lneg", "fneg", "dneg",
+    "ishl", "lshl", "ishr", "lshr", "iushr", "lushr", "iand", "land",
+    "ior", "lor", "ixor", "lxor", "iinc", "i2l", "i2f", "i2d",
+    "l2i", "l2f", "l2d", "f2i", "f2l", "f2d", "d2i", "d2l",
+    "d2f", "i2b", "i2c", "i2s", "lcmp", "fcmpl", "fcmpg", "dcmpl",
+    "dcmpg", "ifeq", "ifne", "iflt", "ifge", "ifgt", "ifle", "if_icmpeq",
+    "if_icmpne", "if_icmplt", "if_icmpge", "if_icmpgt", "if_icmple", "if_acmpeq", "if_acmpne", "goto",
+    "jsr", "ret", "tableswitch", "lookupswitch", "ireturn", "lreturn", "freturn", "dreturn",
+    "areturn", "return", "getstatic", "putstatic", "getfield", "putfield", "invokevirtual", "invokespecial",
+    "invokestatic", "invokeinterface", "invokedynamic", "new", "newarray", "anewarray", "arraylength", "athrow",
+    "checkcast", "instanceof", "monitorenter", "monitorexit", "wide", "multianewarray", "ifnull", "ifnonnull",
+    "goto_w", "jsr_w"
+];
+
+pub fn mnemonic(byte_code: u8) -> Option<&'static str> {
+    MNEMONICS.get(byte_code as usize).copied()
+}
+
+/// A method activation in a Java stack trace, the Rust side of `java.lang.StackTraceElement`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StackFrame {
+    pub class_name: String,
+    pub method_name: String,
+    /// The class's `SourceFile` attribute.
+    pub source_file: Option<String>,
+    /// The line from the method's `LineNumberTable`.
+    pub line_number: Option<u16>
+}
+
+impl Display for StackFrame {
+    /// Formats like `StackTraceElement.toString()`, e.g. `Foo.bar(Foo.java:12)`.
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}.{}(", self.class_name.replace('/', "."), self.method_name)?;
+        match (&self.source_file, self.line_number) {
+            (Some(file), Some(line)) => write!(f, "{}:{})", file, line),
+            (Some(file), None) => write!(f, "{})", file),
+            (None, _) => f.write_str("Unknown Source)")
+        }
+    }
+}
+
+/// A Java exception raised by the VM or thrown by Java code, identified by the binary name of its class.
+/// `object` is the `Throwable` on the heap once one exists; the VM raises exceptions without one
+/// and only allocates it when a handler is about to catch it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct JavaException {
+    pub class_name: String,
+    pub message: Option<String>,
+    pub cause: Option<Box<JavaException>>,
+    pub object: Option<ObjRef>,
+    /// Where the exception was thrown, innermost frame first.
+    pub stack_trace: Vec<StackFrame>
+}
+
+impl JavaException {
+    pub fn new(class_name: &str, message: Option<String>) -> Self {
+        JavaException { class_name: class_name.to_string(), message, cause: None, object: None, stack_trace: Vec::new() }
+    }
+
+    pub fn with_cause(mut self, cause: JavaException) -> Self {
+        self.cause = Some(Box::new(cause));
+        self
+    }
+
+    /// The exception, its stack trace and its causes as `Throwable.printStackTrace()` prints them.
+    /// Frames a cause shares with the trace enclosing it are elided as `... n more`.
+    pub fn stack_trace_text(&self) -> String {
+        let mut text = format!("{}\n", self);
+        for frame in &self.stack_trace {
+            text.push_str(&format!("\tat {}\n", frame));
+        }
+        let mut enclosing = &self.stack_trace;
+        let mut cause = self.cause.as_deref();
+        while let Some(exception) = cause {
+            let common = exception.stack_trace.iter().rev().zip(enclosing.iter().rev())
+                .take_while(|(a, b)| a == b)
+                .count();
+            text.push_str(&format!("Caused by: {}\n", exception));
+            for frame in &exception.stack_trace[..exception.stack_trace.len() - common] {
+                text.push_str(&format!("\tat {}\n", frame));
+            }
+            if common > 0 {
+                text.push_str(&format!("\t... {} more\n", common));
+            }
+            enclosing = &exception.stack_trace;
+            cause = exception.cause.as_deref();
+        }
+        text
+    }
+}
+
+impl Display for JavaException {
+    /// Formats like `Throwable.toString()`, e.g. `java.lang.NoClassDefFoundError: Foo`.
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.class_name.replace('/', "."))?;
+        match &self.message {
+            Some(message) => write!(f, ": {}", message),
+            None => Ok(())
+        }
+    }
 }
 
 #[derive(Debug)]
 pub enum InterpreterError {
-    UnimplementedOpcode(u8)
+    UnimplementedOpcode(u8),
+    TruncatedInstruction(usize),
+    InvalidWideOpcode(u8),
+    /// A Java exception that was not caught by the running code.
+    Exception(JavaException),
+    /// The operand stack held a value of the wrong type for the instruction.
+    TypeMismatch(String, String),
+    InvalidBranchTarget(usize),
+    /// `System.exit` was called: the program stops with this status without unwinding.
+    Exit(i32)
+}
+
+impl InterpreterError {
+    pub fn exception(class_name: &str, message: impl Into<String>) -> Self {
+        InterpreterError::Exception(JavaException::new(class_name, Some(message.into())))
+    }
+
+    /// Where an uncaught Java exception was thrown, innermost frame first.
+    pub fn stack_trace(&self) -> Option<&[StackFrame]> {
+        match self {
+            InterpreterError::Exception(exception) => Some(&exception.stack_trace),
+            _ => None
+        }
+    }
+}
+
+impl From<ClassLoadError> for InterpreterError {
+    fn from(e: ClassLoadError) -> Self {
+        let (class_name, message) = e.java_exception();
+        InterpreterError::exception(class_name, message)
+    }
 }
 
 pub struct Interpreter {
 }
 
+fn read_u8(code: &[u8], pc: usize) -> Result<u8, InterpreterError> {
+    code.get(pc).copied().ok_or(InterpreterError::TruncatedInstruction(pc))
+}
+
+fn read_u16(code: &[u8], pc: usize) -> Result<u16, InterpreterError> {
+    let bytes = code.get(pc .. pc + 2).ok_or(InterpreterError::TruncatedInstruction(pc))?;
+    Ok(u16::from_be_bytes(bytes.try_into().unwrap()))
+}
+
+fn read_i32(code: &[u8], pc: usize) -> Result<i32, InterpreterError> {
+    let bytes = code.get(pc .. pc + 4).ok_or(InterpreterError::TruncatedInstruction(pc))?;
+    Ok(i32::from_be_bytes(bytes.try_into().unwrap()))
+}
+
+impl Default for Interpreter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl Interpreter {
     pub fn new() -> Self {
-        return Interpreter { }
+        Interpreter { }
     }
 
-    pub fn decode(&self, byte_code: u8) -> Result<Opcode, InterpreterError> {
+    /// Decodes the instruction starting at `pc`, returning it with the pc of the following instruction.
+    pub fn decode(&self, code: &[u8], pc: usize) -> Result<(Opcode, usize), InterpreterError> {
+        let byte_code = read_u8(code, pc)?;
+        let u8_operand = || read_u8(code, pc + 1);
+        let u16_operand = || read_u16(code, pc + 1);
+        let branch16 = || read_u16(code, pc + 1).map(|v| v as i16 as i32);
+        let branch32 = || read_i32(code, pc + 1);
+        let local = |op: fn(u16) -> Opcode| -> Result<(Opcode, usize), InterpreterError> {
+            Ok((op(u8_operand()? as u16), pc + 2))
+        };
+        let cp = |op: fn(u16) -> Opcode| -> Result<(Opcode, usize), InterpreterError> {
+            Ok((op(u16_operand()?), pc + 3))
+        };
+        let branch = |op: fn(i32) -> Opcode| -> Result<(Opcode, usize), InterpreterError> {
+            Ok((op(branch16()?), pc + 3))
+        };
+        let simple = |op: Opcode| -> Result<(Opcode, usize), InterpreterError> { Ok((op, pc + 1)) };
         match byte_code {
-            0x01 => Ok(Opcode::AConstNull),
-            0x02 => Ok(Opcode::IConst(-1)),
-            0x03 => Ok(Opcode::IConst(0)),
-            0x04 => Ok(Opcode::IConst(1)),
-            0x05 => Ok(Opcode::IConst(2)),
-            0x06 => Ok(Opcode::IConst(3)),
-            0x07 => Ok(Opcode::IConst(4)),
-            0x08 => Ok(Opcode::IConst(5)),
-            0x60 => Ok(Opcode::IAdd),
-            0x7e => Ok(Opcode::IAnd),
-            0x91 => Ok(Opcode::I2B),
-            0x92 => Ok(Opcode::I2C),
-            0x87 => Ok(Opcode::I2D),
-            0x86 => Ok(Opcode::I2F),
-            0x85 => Ok(Opcode::I2L),
-            0x93 => Ok(Opcode::I2S),
+            0x00 => simple(Opcode::Nop),
+            0x01 => simple(Opcode::AConstNull),
+            0x02 ..= 0x08 => simple(Opcode::IConst(byte_code as i8 - 0x03)),
+            0x09 | 0x0a => simple(Opcode::LConst((byte_code - 0x09) as i64)),
+            0x0b ..= 0x0d => simple(Opcode::FConst((byte_code - 0x0b) as f32)),
+            0x0e | 0x0f => simple(Opcode::DConst((byte_code - 0x0e) as f64)),
+            0x10 => Ok((Opcode::BiPush(u8_operand()? as i8), pc + 2)),
+            0x11 => Ok((Opcode::SiPush(u16_operand()? as i16), pc + 3)),
+            0x12 => Ok((Opcode::Ldc(u8_operand()? as u16), pc + 2)),
+            0x13 => cp(Opcode::LdcW),
+            0x14 => cp(Opcode::Ldc2W),
+            0x15 => local(Opcode::ILoad),
+            0x16 => local(Opcode::LLoad),
+            0x17 => local(Opcode::FLoad),
+            0x18 => local(Opcode::DLoad),
+            0x19 => local(Opcode::ALoad),
+            0x1a ..= 0x1d => simple(Opcode::ILoad((byte_code - 0x1a) as u16)),
+            0x1e ..= 0x21 => simple(Opcode::LLoad((byte_code - 0x1e) as u16)),
+            0x22 ..= 0x25 => simple(Opcode::FLoad((byte_code - 0x22) as u16)),
+            0x26 ..= 0x29 => simple(Opcode::DLoad((byte_code - 0x26) as u16)),
+            0x2a ..= 0x2d => simple(Opcode::ALoad((byte_code - 0x2a) as u16)),
+            0x2e => simple(Opcode::IALoad),
+            0x2f => simple(Opcode::LALoad),
+            0x30 => simple(Opcode::FALoad),
+            0x31 => simple(Opcode::DALoad),
+            0x32 => simple(Opcode::AALoad),
+            0x33 => simple(Opcode::BALoad),
+            0x34 => simple(Opcode::CALoad),
+            0x35 => simple(Opcode::SALoad),
+            0x36 => local(Opcode::IStore),
+            0x37 => local(Opcode::LStore),
+            0x38 => local(Opcode::FStore),
+            0x39 => local(Opcode::DStore),
+            0x3a => local(Opcode::AStore),
+            0x3b ..= 0x3e => simple(Opcode::IStore((byte_code - 0x3b) as u16)),
+            0x3f ..= 0x42 => simple(Opcode::LStore((byte_code - 0x3f) as u16)),
+            0x43 ..= 0x46 => simple(Opcode::FStore((byte_code - 0x43) as u16)),
+            0x47 ..= 0x4a => simple(Opcode::DStore((byte_code - 0x47) as u16)),
+            0x4b ..= 0x4e => simple(Opcode::AStore((byte_code - 0x4b) as u16)),
+            0x4f => simple(Opcode::IAStore),
+            0x50 => simple(Opcode::LAStore),
+            0x51 => simple(Opcode::FAStore),
+            0x52 => simple(Opcode::DAStore),
+            0x53 => simple(Opcode::AAStore),
+            0x54 => simple(Opcode::BAStore),
+            0x55 => simple(Opcode::CAStore),
+            0x56 => simple(Opcode::SAStore),
+            0x57 => simple(Opcode::Pop),
+            0x58 => simple(Opcode::Pop2),
+            0x59 => simple(Opcode::Dup),
+            0x5a => simple(Opcode::DupX1),
+            0x5b => simple(Opcode::DupX2),
+            0x5c => simple(Opcode::Dup2),
+            0x5d => simple(Opcode::Dup2X1),
+            0x5e => simple(Opcode::Dup2X2),
+            0x5f => simple(Opcode::Swap),
+            0x60 => simple(Opcode::IAdd),
+            0x61 => simple(Opcode::LAdd),
+            0x62 => simple(Opcode::FAdd),
+            0x63 => simple(Opcode::DAdd),
+            0x64 => simple(Opcode::ISub),
+            0x65 => simple(Opcode::LSub),
+            0x66 => simple(Opcode::FSub),
+            0x67 => simple(Opcode::DSub),
+            0x68 => simple(Opcode::IMul),
+            0x69 => simple(Opcode::LMul),
+            0x6a => simple(Opcode::FMul),
+            0x6b => simple(Opcode::DMul),
+            0x6c => simple(Opcode::IDiv),
+            0x6d => simple(Opcode::LDiv),
+            0x6e => simple(Opcode::FDiv),
+            0x6f => simple(Opcode::DDiv),
+            0x70 => simple(Opcode::IRem),
+            0x71 => simple(Opcode::LRem),
+            0x72 => simple(Opcode::FRem),
+            0x73 => simple(Opcode::DRem),
+            0x74 => simple(Opcode::INeg),
+            0x75 => simple(Opcode::LNeg),
+            0x76 => simple(Opcode::FNeg),
+            0x77 => simple(Opcode::DNeg),
+            0x78 => simple(Opcode::IShl),
+            0x79 => simple(Opcode::LShl),
+            0x7a => simple(Opcode::IShr),
+            0x7b => simple(Opcode::LShr),
+            0x7c => simple(Opcode::IUShr),
+            0x7d => simple(Opcode::LUShr),
+            0x7e => simple(Opcode::IAnd),
+            0x7f => simple(Opcode::LAnd),
+            0x80 => simple(Opcode::IOr),
+            0x81 => simple(Opcode::LOr),
+            0x82 => simple(Opcode::IXor),
+            0x83 => simple(Opcode::LXor),
+            0x84 => Ok((Opcode::IInc(u8_operand()? as u16, read_u8(code, pc + 2)? as i8 as i16), pc + 3)),
+            0x85 => simple(Opcode::I2L),
+            0x86 => simple(Opcode::I2F),
+            0x87 => simple(Opcode::I2D),
+            0x88 => simple(Opcode::L2I),
+            0x89 => simple(Opcode::L2F),
+            0x8a => simple(Opcode::L2D),
+            0x8b => simple(Opcode::F2I),
+            0x8c => simple(Opcode::F2L),
+            0x8d => simple(Opcode::F2D),
+            0x8e => simple(Opcode::D2I),
+            0x8f => simple(Opcode::D2L),
+            0x90 => simple(Opcode::D2F),
+            0x91 => simple(Opcode::I2B),
+            0x92 => simple(Opcode::I2C),
+            0x93 => simple(Opcode::I2S),
+            0x94 => simple(Opcode::LCmp),
+            0x95 => simple(Opcode::FCmpL),
+            0x96 => simple(Opcode::FCmpG),
+            0x97 => simple(Opcode::DCmpL),
+            0x98 => simple(Opcode::DCmpG),
+            0x99 => branch(Opcode::IfEq),
+            0x9a => branch(Opcode::IfNe),
+            0x9b => branch(Opcode::IfLt),
+            0x9c => branch(Opcode::IfGe),
+            0x9d => branch(Opcode::IfGt),
+            0x9e => branch(Opcode::IfLe),
+            0x9f => branch(Opcode::IfICmpEq),
+            0xa0 => branch(Opcode::IfICmpNe),
+            0xa1 => branch(Opcode::IfICmpLt),
+            0xa2 => branch(Opcode::IfICmpGe),
+            0xa3 => branch(Opcode::IfICmpGt),
+            0xa4 => branch(Opcode::IfICmpLe),
+            0xa5 => branch(Opcode::IfACmpEq),
+            0xa6 => branch(Opcode::IfACmpNe),
+            0xa7 => branch(Opcode::Goto),
+            0xa8 => branch(Opcode::Jsr),
+            0xa9 => local(Opcode::Ret),
+            0xaa => {
+                // operands are aligned to a multiple of four bytes from the start of the method code
+                let base = (pc + 4) & !3;
+                let default = read_i32(code, base)?;
+                let low = read_i32(code, base + 4)?;
+                let high = read_i32(code, base + 8)?;
+                let count = (high as i64 - low as i64 + 1).max(0) as usize;
+                let mut offsets: Vec<i32> = Vec::with_capacity(count);
+                for i in 0 .. count {
+                    offsets.push(read_i32(code, base + 12 + i * 4)?);
+                }
+                Ok((Opcode::TableSwitch(default, low, high, offsets), base + 12 + count * 4))
+            }
+            0xab => {
+                let base = (pc + 4) & !3;
+                let default = read_i32(code, base)?;
+                let npairs = read_i32(code, base + 4)?.max(0) as usize;
+                let mut pairs: Vec<(i32, i32)> = Vec::with_capacity(npairs);
+                for i in 0 .. npairs {
+                    pairs.push((read_i32(code, base + 8 + i * 8)?, read_i32(code, base + 12 + i * 8)?));
+                }
+                Ok((Opcode::LookupSwitch(default, pairs), base + 8 + npairs * 8))
+            }
+            0xac => simple(Opcode::IReturn),
+            0xad => simple(Opcode::LReturn),
+            0xae => simple(Opcode::FReturn),
+            0xaf => simple(Opcode::DReturn),
+            0xb0 => simple(Opcode::AReturn),
+            0xb1 => simple(Opcode::Return),
+            0xb2 => cp(Opcode::GetStatic),
+            0xb3 => cp(Opcode::PutStatic),
+            0xb4 => cp(Opcode::GetField),
+            0xb5 => cp(Opcode::PutField),
+            0xb6 => cp(Opcode::InvokeVirtual),
+            0xb7 => cp(Opcode::InvokeSpecial),
+            0xb8 => cp(Opcode::InvokeStatic),
+            0xb9 => Ok((Opcode::InvokeInterface(u16_operand()?, read_u8(code, pc + 3)?), pc + 5)),
+            0xba => Ok((Opcode::InvokeDynamic(u16_operand()?), pc + 5)),
+            0xbb => cp(Opcode::New),
+            0xbc => Ok((Opcode::NewArray(u8_operand()?), pc + 2)),
+            0xbd => cp(Opcode::ANewArray),
+            0xbe => simple(Opcode::ArrayLength),
+            0xbf => simple(Opcode::AThrow),
+            0xc0 => cp(Opcode::CheckCast),
+            0xc1 => cp(Opcode::InstanceOf),
+            0xc2 => simple(Opcode::MonitorEnter),
+            0xc3 => simple(Opcode::MonitorExit),
+            0xc4 => {
+                let modified = u8_operand()?;
+                let index = read_u16(code, pc + 2)?;
+                match modified {
+                    0x15 => Ok((Opcode::ILoad(index), pc + 4)),
+                    0x16 => Ok((Opcode::LLoad(index), pc + 4)),
+                    0x17 => Ok((Opcode::FLoad(index), pc + 4)),
+                    0x18 => Ok((Opcode::DLoad(index), pc + 4)),
+                    0x19 => Ok((Opcode::ALoad(index), pc + 4)),
+                    0x36 => Ok((Opcode::IStore(index), pc + 4)),
+                    0x37 => Ok((Opcode::LStore(index), pc + 4)),
+                    0x38 => Ok((Opcode::FStore(index), pc + 4)),
+                    0x39 => Ok((Opcode::DStore(index), pc + 4)),
+                    0x3a => Ok((Opcode::AStore(index), pc + 4)),
+                    0xa9 => Ok((Opcode::Ret(index), pc + 4)),
+                    0x84 => Ok((Opcode::IInc(index, read_u16(code, pc + 4)? as i16), pc + 6)),
+                    _ => Err(InterpreterError::InvalidWideOpcode(modified))
+                }
+            }
+            0xc5 => Ok((Opcode::MultiANewArray(u16_operand()?, read_u8(code, pc + 3)?), pc + 4)),
+            0xc6 => branch(Opcode::IfNull),
+            0xc7 => branch(Opcode::IfNonNull),
+            0xc8 => Ok((Opcode::GotoW(branch32()?), pc + 5)),
+            0xc9 => Ok((Opcode::JsrW(branch32()?), pc + 5)),
             _ => Err(InterpreterError::UnimplementedOpcode(byte_code))
         }
     }
-}
\ No newline at end of file
+}
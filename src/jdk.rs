@@ -0,0 +1,1033 @@
+//! The natives the `java.base` of a JDK needs, which `--java-home` boots in place of the bundled
+//! runtime. They stand in for the parts of HotSpot that JDK 17 reaches during `System.initPhase1`
+//! and ordinary programs: the reflection basics of `java.lang.Class`, the one `java.lang.Thread`
+//! there is, `jdk.internal.misc.Unsafe` on the heap layout of [`crate::vm`], and the system
+//! properties. The module system (`initPhase2`) and the Java class loaders (`initPhase3`) are not
+//! started: classes keep being loaded by the [`ClassRegistry`](crate::registry::ClassRegistry).
+
+use std::io::{Read, Write};
+use std::path::PathBuf;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use crate::classpath::{ClassPathEntry, SEPARATOR};
+use crate::descriptor::{FieldType, MethodDescriptor};
+use crate::env::VMEnv;
+use crate::interpret::{InterpreterError, JavaException};
+use crate::loader::AccessFlags;
+use crate::native::{non_null, receiver, reference, NativeRegistry, NativeResult};
+use crate::natives;
+use crate::registry::{ClassId, LoaderId};
+use crate::vm::{Handle, Heap, ObjRef, VMValue};
+
+/// Binds the natives of a JDK's `java.base` that the bundled runtime does not declare.
+pub fn register_natives(registry: &mut NativeRegistry) {
+    natives!(registry,
+        "java/lang/Object" {
+            // There is a single thread, so nothing can be notified and waits wake up spuriously.
+            "notify" "()V" => |_, _| Ok(None),
+            "notifyAll" "()V" => |_, _| Ok(None),
+            "wait" "(J)V" => |_, _| Ok(None)
+        }
+        "java/lang/Class" {
+            "registerNatives" "()V" => |_, _| Ok(None),
+            "desiredAssertionStatus0" "(Ljava/lang/Class;)Z" => |_, _| Ok(Some(VMValue::Int(0))),
+            "getPrimitiveClass" "(Ljava/lang/String;)Ljava/lang/Class;" => class_get_primitive_class,
+            "forName0" "(Ljava/lang/String;ZLjava/lang/ClassLoader;Ljava/lang/Class;)Ljava/lang/Class;" => class_for_name,
+            "initClassName" "()Ljava/lang/String;" => |env, args| {
+                let name = env.vm().classes.class(this_class(env, args)?).java_name();
+                let name = env.vm_mut().intern_string(&name)?;
+                env.vm_mut().set_field(receiver(args)?, "name", VMValue::Reference(name))?;
+                Ok(Some(VMValue::Reference(name)))
+            },
+            "isArray" "()Z" => |env, args| class_test(env, args, |env, class| env.vm().classes.class(class).is_array()),
+            "isPrimitive" "()Z" => |env, args| class_test(env, args, |env, class| env.vm().classes.is_primitive(class)),
+            "isInterface" "()Z" => |env, args| class_test(env, args, |env, class| env.vm().classes.class(class).is_interface()),
+            "isHidden" "()Z" => |_, _| Ok(Some(VMValue::Int(0))),
+            "isRecord0" "()Z" => |env, args| class_test(env, args, |env, class| {
+                env.vm().classes.class(class).class.super_class_name.as_deref() == Some("java/lang/Record")
+            }),
+            "isInstance" "(Ljava/lang/Object;)Z" => |env, args| {
+                let class = this_class(env, args)?;
+                let is_instance = match reference(args, 1)? {
+                    Some(obj) => env.vm().classes.is_assignable(env.vm().heap.class_of(obj), class),
+                    None => false
+                };
+                Ok(Some(VMValue::Int(is_instance as i32)))
+            },
+            "isAssignableFrom" "(Ljava/lang/Class;)Z" => |env, args| {
+                let (class, other) = (this_class(env, args)?, env.vm().mirrored_class(non_null(reference(args, 1)?)?)?);
+                let classes = &env.vm().classes;
+                let assignable = if classes.is_primitive(class) || classes.is_primitive(other) { class == other } else { classes.is_assignable(other, class) };
+                Ok(Some(VMValue::Int(assignable as i32)))
+            },
+            "getModifiers" "()I" => |env, args| Ok(Some(VMValue::Int(class_modifiers(env, this_class(env, args)?) as i32))),
+            "getSuperclass" "()Ljava/lang/Class;" => |env, args| {
+                let class = this_class(env, args)?;
+                match env.vm().classes.class(class).super_class.filter(|_| !env.vm().classes.class(class).is_interface()) {
+                    Some(super_class) => Ok(Some(VMValue::Reference(env.vm_mut().class_mirror(super_class)?))),
+                    None => Ok(Some(VMValue::Null))
+                }
+            },
+            "getInterfaces0" "()[Ljava/lang/Class;" => |env, args| {
+                let interfaces = env.vm().classes.class(this_class(env, args)?).interfaces.clone();
+                Ok(Some(VMValue::Reference(class_array(env, &interfaces)?)))
+            },
+            "getNestHost0" "()Ljava/lang/Class;" => |env, args| {
+                let class = this_class(env, args)?;
+                let host = nest_host(env, class)?;
+                Ok(Some(VMValue::Reference(env.vm_mut().class_mirror(host)?)))
+            },
+            "getDeclaringClass0" "()Ljava/lang/Class;" => |env, args| {
+                let class = this_class(env, args)?;
+                let class_file = env.vm().classes.class(class).class.clone();
+                match class_file.inner_class_entry().filter(|entry| entry.outer_class_info_index != 0) {
+                    Some(entry) => {
+                        let outer = env.vm_mut().classes.resolve_class(class, class_file.constant_pool.class_name(entry.outer_class_info_index)?)?;
+                        Ok(Some(VMValue::Reference(env.vm_mut().class_mirror(outer)?)))
+                    }
+                    None => Ok(Some(VMValue::Null))
+                }
+            },
+            "getSimpleBinaryName0" "()Ljava/lang/String;" => |env, args| {
+                let class_file = env.vm().classes.class(this_class(env, args)?).class.clone();
+                match class_file.inner_class_entry().filter(|entry| entry.inner_name_index != 0) {
+                    Some(entry) => Ok(Some(VMValue::Reference(env.vm_mut().intern_string(class_file.constant_pool.utf8(entry.inner_name_index)?)?))),
+                    None => Ok(Some(VMValue::Null))
+                }
+            },
+            "getEnclosingMethod0" "()[Ljava/lang/Object;" => class_enclosing_method,
+            "getGenericSignature0" "()Ljava/lang/String;" => |_, _| Ok(Some(VMValue::Null)),
+            "getProtectionDomain0" "()Ljava/security/ProtectionDomain;" => |_, _| Ok(Some(VMValue::Null)),
+            "getRawAnnotations" "()[B" => |_, _| Ok(Some(VMValue::Null)),
+            // Only annotations are parsed against it, and reflection objects carry none.
+            "getConstantPool" "()Ljdk/internal/reflect/ConstantPool;" => |_, _| Ok(Some(VMValue::Null)),
+            "getDeclaredConstructors0" "(Z)[Ljava/lang/reflect/Constructor;" => |env, args| declared_members(env, args, "java/lang/reflect/Constructor"),
+            "getDeclaredMethods0" "(Z)[Ljava/lang/reflect/Method;" => |env, args| declared_members(env, args, "java/lang/reflect/Method"),
+            "getDeclaredFields0" "(Z)[Ljava/lang/reflect/Field;" => |env, args| declared_members(env, args, "java/lang/reflect/Field")
+        }
+        "java/lang/ClassLoader" {
+            "registerNatives" "()V" => |_, _| Ok(None),
+            "findBootstrapClass" "(Ljava/lang/String;)Ljava/lang/Class;" => |env, args| {
+                let name = env.vm().string_value(non_null(reference(args, 0)?)?)?;
+                match env.vm_mut().classes.load_class(LoaderId::BOOTSTRAP, &name.replace('.', "/")) {
+                    Ok(class) => Ok(Some(VMValue::Reference(env.vm_mut().class_mirror(class)?))),
+                    Err(_) => Ok(Some(VMValue::Null))
+                }
+            },
+            // Java class loaders are not started, so they have loaded nothing themselves.
+            "findLoadedClass0" "(Ljava/lang/String;)Ljava/lang/Class;" => |_, _| Ok(Some(VMValue::Null))
+        }
+        "jdk/internal/loader/BootLoader" {
+            "setBootLoaderUnnamedModule0" "(Ljava/lang/Module;)V" => set_boot_loader_unnamed_module
+        }
+        "java/lang/System" {
+            "registerNatives" "()V" => |_, _| Ok(None),
+            "setIn0" "(Ljava/io/InputStream;)V" => |env, args| set_system_stream(env, "in", args),
+            "setOut0" "(Ljava/io/PrintStream;)V" => |env, args| set_system_stream(env, "out", args),
+            "setErr0" "(Ljava/io/PrintStream;)V" => |env, args| set_system_stream(env, "err", args)
+        }
+        "java/lang/Thread" {
+            "registerNatives" "()V" => |_, _| Ok(None),
+            "currentThread" "()Ljava/lang/Thread;" => |env, _| Ok(Some(env.current_thread().into())),
+            "getThreads" "()[Ljava/lang/Thread;" => |env, _| {
+                let array_class = env.vm_mut().classes.load_class(LoaderId::BOOTSTRAP, "[Ljava/lang/Thread;")?;
+                let array = env.vm_mut().new_array(array_class, 1)?;
+                let thread = env.current_thread().into();
+                env.vm_mut().store_element(array, 0, &FieldType::Object("java/lang/Thread".to_string()), thread);
+                Ok(Some(VMValue::Reference(array)))
+            },
+            "holdsLock" "(Ljava/lang/Object;)Z" => |env, args| {
+                let obj = non_null(reference(args, 0)?)?;
+                Ok(Some(VMValue::Int((env.vm().heap.monitor_count(obj) > 0) as i32)))
+            },
+            "sleep" "(J)V" => |_, args| {
+                std::thread::sleep(Duration::from_millis(args[0].as_long()?.max(0) as u64));
+                Ok(None)
+            },
+            "start0" "()V" => |env, args| {
+                env.start_thread(receiver(args)?)?;
+                Ok(None)
+            },
+            "yield" "()V" => |_, _| Ok(None),
+            "setPriority0" "(I)V" => |_, _| Ok(None),
+            "setNativeName" "(Ljava/lang/String;)V" => |_, _| Ok(None),
+            "interrupt0" "()V" => |_, _| Ok(None),
+            "clearInterruptEvent" "()V" => |_, _| Ok(None)
+        }
+        "java/lang/Runtime" {
+            "availableProcessors" "()I" => |_, _| Ok(Some(VMValue::Int(1))),
+            "freeMemory" "()J" => |env, _| {
+                let heap = &env.vm().heap;
+                Ok(Some(VMValue::Long((heap.size() - heap.used()) as i64)))
+            },
+            "totalMemory" "()J" => |env, _| Ok(Some(VMValue::Long(env.vm().heap.size() as i64))),
+            "maxMemory" "()J" => |env, _| Ok(Some(VMValue::Long(env.vm().heap.max_size() as i64))),
+            "gc" "()V" => |env, _| {
+                env.vm_mut().collect_garbage();
+                Ok(None)
+            }
+        }
+        "java/lang/Shutdown" {
+            "beforeHalt" "()V" => |_, _| Ok(None),
+            "halt0" "(I)V" => |_, args| Err(InterpreterError::Exit(args[0].as_int()?))
+        }
+        "java/lang/StringUTF16" {
+            "isBigEndian" "()Z" => |_, _| Ok(Some(VMValue::Int(cfg!(target_endian = "big") as i32)))
+        }
+        "java/lang/StrictMath" {
+            "sin" "(D)D" => |_, args| strict_math(args, f64::sin),
+            "cos" "(D)D" => |_, args| strict_math(args, f64::cos),
+            "tan" "(D)D" => |_, args| strict_math(args, f64::tan),
+            "asin" "(D)D" => |_, args| strict_math(args, f64::asin),
+            "acos" "(D)D" => |_, args| strict_math(args, f64::acos),
+            "atan" "(D)D" => |_, args| strict_math(args, f64::atan),
+            "sinh" "(D)D" => |_, args| strict_math(args, f64::sinh),
+            "cosh" "(D)D" => |_, args| strict_math(args, f64::cosh),
+            "tanh" "(D)D" => |_, args| strict_math(args, f64::tanh),
+            "log" "(D)D" => |_, args| strict_math(args, f64::ln),
+            "log10" "(D)D" => |_, args| strict_math(args, f64::log10),
+            "sqrt" "(D)D" => |_, args| strict_math(args, f64::sqrt),
+            "expm1" "(D)D" => |_, args| strict_math(args, f64::exp_m1),
+            "log1p" "(D)D" => |_, args| strict_math(args, f64::ln_1p),
+            "atan2" "(DD)D" => |_, args| Ok(Some(VMValue::Double(args[0].as_double()?.atan2(args[1].as_double()?)))),
+            "IEEEremainder" "(DD)D" => |_, args| {
+                let (x, y) = (args[0].as_double()?, args[1].as_double()?);
+                Ok(Some(VMValue::Double(x - (x / y).round_ties_even() * y)))
+            }
+        }
+        "java/lang/StackTraceElement" {
+            "initStackTraceElements" "([Ljava/lang/StackTraceElement;Ljava/lang/Throwable;)V" => init_stack_trace_elements
+        }
+        "java/lang/ref/Reference" {
+            "getAndClearReferencePendingList" "()Ljava/lang/ref/Reference;" => reference_pending_list,
+            "hasReferencePendingList" "()Z" => |env, _| Ok(Some(VMValue::Int(env.vm().has_pending_references() as i32))),
+            // Only called while references are pending, see `VMEnv::process_pending_references`.
+            "waitForReferencePendingList" "()V" => |_, _| Ok(None),
+            "refersTo0" "(Ljava/lang/Object;)Z" => reference_refers_to,
+            "clear0" "()V" => |env, args| {
+                env.vm_mut().set_field(receiver(args)?, "referent", VMValue::Null)?;
+                Ok(None)
+            }
+        }
+        "java/lang/ref/PhantomReference" {
+            "refersTo0" "(Ljava/lang/Object;)Z" => reference_refers_to
+        }
+        "java/security/AccessController" {
+            "getStackAccessControlContext" "()Ljava/security/AccessControlContext;" => |_, _| Ok(Some(VMValue::Null)),
+            "getInheritedAccessControlContext" "()Ljava/security/AccessControlContext;" => |_, _| Ok(Some(VMValue::Null)),
+            "getProtectionDomain" "(Ljava/lang/Class;)Ljava/security/ProtectionDomain;" => |_, _| Ok(Some(VMValue::Null)),
+            "ensureMaterializedForStackWalk" "(Ljava/lang/Object;)V" => |_, _| Ok(None)
+        }
+        "jdk/internal/reflect/Reflection" {
+            "getCallerClass" "()Ljava/lang/Class;" => reflection_get_caller_class,
+            "getClassAccessFlags" "(Ljava/lang/Class;)I" => |env, args| {
+                let class = env.vm().mirrored_class(non_null(reference(args, 0)?)?)?;
+                Ok(Some(VMValue::Int(AccessFlags::to_value(&env.vm().classes.class(class).class.access_flags) as i32)))
+            },
+            "areNestMates" "(Ljava/lang/Class;Ljava/lang/Class;)Z" => |env, args| {
+                let first = env.vm().mirrored_class(non_null(reference(args, 0)?)?)?;
+                let second = env.vm().mirrored_class(non_null(reference(args, 1)?)?)?;
+                Ok(Some(VMValue::Int((nest_host(env, first)? == nest_host(env, second)?) as i32)))
+            }
+        }
+        "jdk/internal/reflect/NativeConstructorAccessorImpl" {
+            "newInstance0" "(Ljava/lang/reflect/Constructor;[Ljava/lang/Object;)Ljava/lang/Object;" => constructor_new_instance
+        }
+        "jdk/internal/reflect/NativeMethodAccessorImpl" {
+            "invoke0" "(Ljava/lang/reflect/Method;Ljava/lang/Object;[Ljava/lang/Object;)Ljava/lang/Object;" => method_invoke
+        }
+        "jdk/internal/misc/CDS" {
+            "isDumpingClassList0" "()Z" => |_, _| Ok(Some(VMValue::Int(0))),
+            "isDumpingArchive0" "()Z" => |_, _| Ok(Some(VMValue::Int(0))),
+            "isSharingEnabled0" "()Z" => |_, _| Ok(Some(VMValue::Int(0))),
+            "getRandomSeedForDumping" "()J" => |_, _| Ok(Some(VMValue::Long(0))),
+            "initializeFromArchive" "(Ljava/lang/Class;)V" => |_, _| Ok(None),
+            "defineArchivedModules" "(Ljava/lang/ClassLoader;Ljava/lang/ClassLoader;)V" => |_, _| Ok(None),
+            "logLambdaFormInvoker" "(Ljava/lang/String;)V" => |_, _| Ok(None)
+        }
+        "jdk/internal/misc/VM" {
+            "initialize" "()V" => |_, _| Ok(None),
+            "latestUserDefinedLoader0" "()Ljava/lang/ClassLoader;" => |_, _| Ok(Some(VMValue::Null)),
+            "getNanoTimeAdjustment" "(J)J" => |_, args| {
+                // The nanoseconds since `offset` seconds after the epoch, or -1 when they overflow.
+                let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default();
+                let seconds = now.as_secs() as i64 - args[0].as_long()?;
+                let adjustment = seconds.checked_mul(1_000_000_000).and_then(|nanos| nanos.checked_add(now.subsec_nanos() as i64));
+                Ok(Some(VMValue::Long(adjustment.unwrap_or(-1))))
+            },
+            "getRuntimeArguments" "()[Ljava/lang/String;" => |env, _| {
+                let array_class = env.vm_mut().classes.load_class(LoaderId::BOOTSTRAP, "[Ljava/lang/String;")?;
+                Ok(Some(VMValue::Reference(env.vm_mut().new_array(array_class, 0)?)))
+            }
+        }
+        "jdk/internal/misc/Signal" {
+            "findSignal0" "(Ljava/lang/String;)I" => |env, args| {
+                let name = env.vm().string_value(non_null(reference(args, 0)?)?)?;
+                let number = ["HUP", "INT", "QUIT"].iter().position(|signal| *signal == name).map(|index| index as i32 + 1);
+                Ok(Some(VMValue::Int(number.or((name == "TERM").then_some(15)).unwrap_or(-1))))
+            },
+            // Signals keep their default handlers, which is what 0 stands for.
+            "handle0" "(IJ)J" => |_, _| Ok(Some(VMValue::Long(0)))
+        }
+        "jdk/internal/misc/ScopedMemoryAccess" {
+            "registerNatives" "()V" => |_, _| Ok(None)
+        }
+        "jdk/internal/util/SystemProps$Raw" {
+            "vmProperties" "()[Ljava/lang/String;" => vm_properties,
+            "platformProperties" "()[Ljava/lang/String;" => platform_properties
+        }
+        "java/io/FileDescriptor" {
+            "initIDs" "()V" => |_, _| Ok(None),
+            "getHandle" "(I)J" => |_, _| Ok(Some(VMValue::Long(-1))),
+            "getAppend" "(I)Z" => |_, _| Ok(Some(VMValue::Int(0))),
+            "close0" "()V" => |_, _| Ok(None)
+        }
+        "java/io/UnixFileSystem" {
+            "initIDs" "()V" => |_, _| Ok(None),
+            "canonicalize0" "(Ljava/lang/String;)Ljava/lang/String;" => |env, args| {
+                let path = env.vm().string_value(non_null(reference(args, 1)?)?)?;
+                // Like HotSpot's canonicalize, a path that does not exist keeps its own form.
+                let canonical = std::fs::canonicalize(&path).map(|path| path.display().to_string()).unwrap_or(path);
+                Ok(Some(VMValue::Reference(env.vm_mut().new_string(&canonical)?)))
+            },
+            "getBooleanAttributes0" "(Ljava/io/File;)I" => |env, args| {
+                // BA_EXISTS, BA_REGULAR and BA_DIRECTORY of java.io.FileSystem.
+                let attributes = std::fs::metadata(file_path(env, args)?)
+                    .map_or(0, |metadata| 0x01 | if metadata.is_file() { 0x02 } else { 0 } | if metadata.is_dir() { 0x04 } else { 0 });
+                Ok(Some(VMValue::Int(attributes)))
+            },
+            "checkAccess" "(Ljava/io/File;I)Z" => |env, args| Ok(Some(VMValue::Int(std::fs::metadata(file_path(env, args)?).is_ok() as i32))),
+            "getLastModifiedTime" "(Ljava/io/File;)J" => |env, args| {
+                let modified = std::fs::metadata(file_path(env, args)?).and_then(|metadata| metadata.modified()).ok()
+                    .and_then(|modified| modified.duration_since(UNIX_EPOCH).ok());
+                Ok(Some(VMValue::Long(modified.map_or(0, |modified| modified.as_millis() as i64))))
+            },
+            "getLength" "(Ljava/io/File;)J" => |env, args| {
+                Ok(Some(VMValue::Long(std::fs::metadata(file_path(env, args)?).map_or(0, |metadata| metadata.len() as i64))))
+            }
+        }
+        "java/lang/ProcessEnvironment" {
+            "environ" "()[[B" => process_environment
+        }
+        "java/io/FileInputStream" {
+            "initIDs" "()V" => |_, _| Ok(None),
+            "readBytes" "([BII)I" => file_input_stream_read_bytes,
+            "available0" "()I" => |_, _| Ok(Some(VMValue::Int(0)))
+        }
+        "java/io/FileOutputStream" {
+            "initIDs" "()V" => |_, _| Ok(None),
+            "writeBytes" "([BIIZ)V" => |env, args| {
+                let fd = file_descriptor(env, receiver(args)?)?;
+                crate::native::write_bytes(env, fd, non_null(reference(args, 1)?)?, args[2].as_int()?, args[3].as_int()?)?;
+                Ok(None)
+            },
+            "write" "(IZ)V" => |env, args| {
+                let fd = file_descriptor(env, receiver(args)?)?;
+                let written = match fd {
+                    1 => env.stdout.write_all(&[args[1].as_int()? as u8]),
+                    2 => env.stderr.write_all(&[args[1].as_int()? as u8]),
+                    _ => return Err(InterpreterError::exception("java/io/IOException", "Bad file descriptor"))
+                };
+                written.map_err(|e| InterpreterError::exception("java/io/IOException", e.to_string()))?;
+                Ok(None)
+            }
+        }
+        "java/util/concurrent/atomic/AtomicLong" {
+            "VMSupportsCS8" "()Z" => |_, _| Ok(Some(VMValue::Int(1)))
+        }
+        "jdk/internal/misc/Unsafe" {
+            "registerNatives" "()V" => |_, _| Ok(None),
+            "arrayBaseOffset0" "(Ljava/lang/Class;)I" => |_, _| Ok(Some(VMValue::Int(Heap::HEADER_SIZE as i32))),
+            "arrayIndexScale0" "(Ljava/lang/Class;)I" => |env, args| {
+                let class = env.vm().mirrored_class(non_null(reference(args, 1)?)?)?;
+                let element_type = env.vm().classes.class(class).component_type.clone()
+                    .ok_or_else(|| InterpreterError::exception("java/lang/IllegalArgumentException", "not an array class"))?;
+                Ok(Some(VMValue::Int(Heap::field_size(&element_type) as i32)))
+            },
+            "objectFieldOffset1" "(Ljava/lang/Class;Ljava/lang/String;)J" => |env, args| {
+                let class = env.vm().mirrored_class(non_null(reference(args, 1)?)?)?;
+                let name = env.vm().string_value(non_null(reference(args, 2)?)?)?;
+                let (offset, _) = env.vm().classes.instance_field(class, &name)
+                    .ok_or_else(|| InterpreterError::exception("java/lang/InternalError", name))?;
+                Ok(Some(VMValue::Long((Heap::HEADER_SIZE + offset) as i64)))
+            },
+            "shouldBeInitialized0" "(Ljava/lang/Class;)Z" => |env, args| {
+                let class = env.vm().mirrored_class(non_null(reference(args, 1)?)?)?;
+                Ok(Some(VMValue::Int((env.vm().classes.class(class).state < crate::registry::ClassState::Initialized) as i32)))
+            },
+            "ensureClassInitialized0" "(Ljava/lang/Class;)V" => |env, args| {
+                let class = env.vm().mirrored_class(non_null(reference(args, 1)?)?)?;
+                env.initialize_class(class)?;
+                Ok(None)
+            },
+            "allocateInstance" "(Ljava/lang/Class;)Ljava/lang/Object;" => |env, args| {
+                let class = env.vm().mirrored_class(non_null(reference(args, 1)?)?)?;
+                let runtime_class = env.vm().classes.class(class);
+                if runtime_class.is_interface() || runtime_class.is_array() || runtime_class.class.access_flags.contains(&AccessFlags::Abstract) {
+                    return Err(InterpreterError::exception("java/lang/InstantiationException", runtime_class.java_name()))
+                }
+                env.initialize_class(class)?;
+                Ok(Some(VMValue::Reference(env.vm_mut().new_object(class)?)))
+            },
+            "throwException" "(Ljava/lang/Throwable;)V" => |env, args| {
+                let throwable = non_null(reference(args, 1)?)?;
+                Err(InterpreterError::Exception(env.java_exception(throwable)?))
+            },
+            "loadFence" "()V" => |_, _| Ok(None),
+            "storeFence" "()V" => |_, _| Ok(None),
+            "fullFence" "()V" => |_, _| Ok(None),
+            "copyMemory0" "(Ljava/lang/Object;JLjava/lang/Object;JJ)V" => unsafe_copy_memory,
+            "setMemory0" "(Ljava/lang/Object;JJB)V" => |env, args| {
+                let obj = non_null(reference(args, 1)?)?;
+                let (offset, bytes) = (field_offset(args[2].as_long()?)?, args[3].as_long()? as u32);
+                for index in 0..bytes {
+                    env.vm_mut().heap.write_field(obj, offset + index, &FieldType::Byte, args[4]);
+                }
+                Ok(None)
+            },
+            "getReference" "(Ljava/lang/Object;J)Ljava/lang/Object;" => |env, args| unsafe_get(env, args, object_type()),
+            "getReferenceVolatile" "(Ljava/lang/Object;J)Ljava/lang/Object;" => |env, args| unsafe_get(env, args, object_type()),
+            "putReference" "(Ljava/lang/Object;JLjava/lang/Object;)V" => |env, args| unsafe_put(env, args, object_type()),
+            "putReferenceVolatile" "(Ljava/lang/Object;JLjava/lang/Object;)V" => |env, args| unsafe_put(env, args, object_type()),
+            "getBoolean" "(Ljava/lang/Object;J)Z" => |env, args| unsafe_get(env, args, FieldType::Boolean),
+            "getBooleanVolatile" "(Ljava/lang/Object;J)Z" => |env, args| unsafe_get(env, args, FieldType::Boolean),
+            "putBoolean" "(Ljava/lang/Object;JZ)V" => |env, args| unsafe_put(env, args, FieldType::Boolean),
+            "putBooleanVolatile" "(Ljava/lang/Object;JZ)V" => |env, args| unsafe_put(env, args, FieldType::Boolean),
+            "getByte" "(Ljava/lang/Object;J)B" => |env, args| unsafe_get(env, args, FieldType::Byte),
+            "getByteVolatile" "(Ljava/lang/Object;J)B" => |env, args| unsafe_get(env, args, FieldType::Byte),
+            "putByte" "(Ljava/lang/Object;JB)V" => |env, args| unsafe_put(env, args, FieldType::Byte),
+            "putByteVolatile" "(Ljava/lang/Object;JB)V" => |env, args| unsafe_put(env, args, FieldType::Byte),
+            "getShort" "(Ljava/lang/Object;J)S" => |env, args| unsafe_get(env, args, FieldType::Short),
+            "getShortVolatile" "(Ljava/lang/Object;J)S" => |env, args| unsafe_get(env, args, FieldType::Short),
+            "putShort" "(Ljava/lang/Object;JS)V" => |env, args| unsafe_put(env, args, FieldType::Short),
+            "putShortVolatile" "(Ljava/lang/Object;JS)V" => |env, args| unsafe_put(env, args, FieldType::Short),
+            "getChar" "(Ljava/lang/Object;J)C" => |env, args| unsafe_get(env, args, FieldType::Char),
+            "getCharVolatile" "(Ljava/lang/Object;J)C" => |env, args| unsafe_get(env, args, FieldType::Char),
+            "putChar" "(Ljava/lang/Object;JC)V" => |env, args| unsafe_put(env, args, FieldType::Char),
+            "putCharVolatile" "(Ljava/lang/Object;JC)V" => |env, args| unsafe_put(env, args, FieldType::Char),
+            "getInt" "(Ljava/lang/Object;J)I" => |env, args| unsafe_get(env, args, FieldType::Int),
+            "getIntVolatile" "(Ljava/lang/Object;J)I" => |env, args| unsafe_get(env, args, FieldType::Int),
+            "putInt" "(Ljava/lang/Object;JI)V" => |env, args| unsafe_put(env, args, FieldType::Int),
+            "putIntVolatile" "(Ljava/lang/Object;JI)V" => |env, args| unsafe_put(env, args, FieldType::Int),
+            "getLong" "(Ljava/lang/Object;J)J" => |env, args| unsafe_get(env, args, FieldType::Long),
+            "getLongVolatile" "(Ljava/lang/Object;J)J" => |env, args| unsafe_get(env, args, FieldType::Long),
+            "putLong" "(Ljava/lang/Object;JJ)V" => |env, args| unsafe_put(env, args, FieldType::Long),
+            "putLongVolatile" "(Ljava/lang/Object;JJ)V" => |env, args| unsafe_put(env, args, FieldType::Long),
+            "getFloat" "(Ljava/lang/Object;J)F" => |env, args| unsafe_get(env, args, FieldType::Float),
+            "getFloatVolatile" "(Ljava/lang/Object;J)F" => |env, args| unsafe_get(env, args, FieldType::Float),
+            "putFloat" "(Ljava/lang/Object;JF)V" => |env, args| unsafe_put(env, args, FieldType::Float),
+            "putFloatVolatile" "(Ljava/lang/Object;JF)V" => |env, args| unsafe_put(env, args, FieldType::Float),
+            "getDouble" "(Ljava/lang/Object;J)D" => |env, args| unsafe_get(env, args, FieldType::Double),
+            "getDoubleVolatile" "(Ljava/lang/Object;J)D" => |env, args| unsafe_get(env, args, FieldType::Double),
+            "putDouble" "(Ljava/lang/Object;JD)V" => |env, args| unsafe_put(env, args, FieldType::Double),
+            "putDoubleVolatile" "(Ljava/lang/Object;JD)V" => |env, args| unsafe_put(env, args, FieldType::Double),
+            "compareAndSetReference" "(Ljava/lang/Object;JLjava/lang/Object;Ljava/lang/Object;)Z" => |env, args| unsafe_compare_and_set(env, args, object_type()),
+            "compareAndExchangeReference" "(Ljava/lang/Object;JLjava/lang/Object;Ljava/lang/Object;)Ljava/lang/Object;" =>
+                |env, args| unsafe_compare_and_exchange(env, args, object_type()),
+            "compareAndSetInt" "(Ljava/lang/Object;JII)Z" => |env, args| unsafe_compare_and_set(env, args, FieldType::Int),
+            "compareAndExchangeInt" "(Ljava/lang/Object;JII)I" => |env, args| unsafe_compare_and_exchange(env, args, FieldType::Int),
+            "compareAndSetLong" "(Ljava/lang/Object;JJJ)Z" => |env, args| unsafe_compare_and_set(env, args, FieldType::Long),
+            "compareAndExchangeLong" "(Ljava/lang/Object;JJJ)J" => |env, args| unsafe_compare_and_exchange(env, args, FieldType::Long)
+        }
+    );
+}
+
+/// The class of the `java.lang.Class` receiver of a native.
+fn this_class(env: &VMEnv, args: &[VMValue]) -> Result<ClassId, InterpreterError> {
+    env.vm().mirrored_class(receiver(args)?)
+}
+
+fn class_test(env: &mut VMEnv, args: &[VMValue], test: fn(&VMEnv, ClassId) -> bool) -> NativeResult {
+    let class = this_class(env, args)?;
+    Ok(Some(VMValue::Int(test(env, class) as i32)))
+}
+
+/// `Class.getPrimitiveClass`: the mirror of a primitive type or `void` by name.
+fn class_get_primitive_class(env: &mut VMEnv, args: &[VMValue]) -> NativeResult {
+    let name = env.vm().string_value(non_null(reference(args, 0)?)?)?;
+    let field_type = match name.as_str() {
+        "void" => None,
+        "boolean" => Some(FieldType::Boolean),
+        "byte" => Some(FieldType::Byte),
+        "char" => Some(FieldType::Char),
+        "short" => Some(FieldType::Short),
+        "int" => Some(FieldType::Int),
+        "long" => Some(FieldType::Long),
+        "float" => Some(FieldType::Float),
+        "double" => Some(FieldType::Double),
+        _ => return Err(InterpreterError::exception("java/lang/ClassNotFoundException", name))
+    };
+    let class = env.vm_mut().classes.primitive_class(field_type.as_ref());
+    Ok(Some(VMValue::Reference(env.vm_mut().class_mirror(class)?)))
+}
+
+/// `Class.forName0`. A class loader object stands for the application loader, as Java class loaders
+/// are not started.
+fn class_for_name(env: &mut VMEnv, args: &[VMValue]) -> NativeResult {
+    let name = env.vm().string_value(non_null(reference(args, 0)?)?)?;
+    let loader = if reference(args, 2)?.is_some() { LoaderId::APPLICATION } else { LoaderId::BOOTSTRAP };
+    if name.contains('/') {
+        return Err(InterpreterError::exception("java/lang/ClassNotFoundException", name))
+    }
+    let class = env.class_for_name(loader, &name, args[1].as_int()? != 0)?;
+    Ok(Some(VMValue::Reference(env.vm_mut().class_mirror(class)?)))
+}
+
+/// `Class.getModifiers`: the access flags of a class as the language sees them. Arrays take theirs
+/// from the component type, primitive types are `public final abstract`.
+fn class_modifiers(env: &VMEnv, class: ClassId) -> u16 {
+    let classes = &env.vm().classes;
+    let runtime_class = classes.class(class);
+    if let Some(component) = runtime_class.component_class {
+        return class_modifiers(env, component) & 0x0007 | 0x0410
+    }
+    if runtime_class.is_array() || classes.is_primitive(class) {
+        return 0x0411
+    }
+    // Nested classes have the modifiers of their source in the `InnerClasses` attribute.
+    match runtime_class.class.inner_class_entry() {
+        Some(entry) => AccessFlags::to_value(&entry.inner_class_access_flags) & !AccessFlags::Super.mask(),
+        None => AccessFlags::to_value(&runtime_class.class.access_flags) & !AccessFlags::Super.mask()
+    }
+}
+
+/// `Class.getEnclosingMethod0`: for local and anonymous classes, the enclosing class and the
+/// name and descriptor of the enclosing method, which are null outside of methods.
+fn class_enclosing_method(env: &mut VMEnv, args: &[VMValue]) -> NativeResult {
+    let class = this_class(env, args)?;
+    let class_file = env.vm().classes.class(class).class.clone();
+    let (class_index, method_index) = match class_file.enclosing_method() {
+        Some(enclosing_method) => enclosing_method,
+        None => return Ok(Some(VMValue::Null))
+    };
+    let pool = &class_file.constant_pool;
+    let enclosing_class = env.vm_mut().classes.resolve_class(class, pool.class_name(class_index)?)?;
+    let (name, descriptor) = match method_index {
+        0 => (None, None),
+        _ => pool.name_and_type(method_index).map(|(name, descriptor)| (Some(name.clone()), Some(descriptor.clone())))?
+    };
+    let array_class = env.vm_mut().classes.load_class(LoaderId::BOOTSTRAP, "[Ljava/lang/Object;")?;
+    let array = env.vm_mut().new_array(array_class, 3)?;
+    let scope = env.vm_mut().new_handle(array);
+    let result = (|| {
+        let mirror = env.vm_mut().class_mirror(enclosing_class)?;
+        let array = env.vm().handle(scope);
+        env.vm_mut().store_element(array, 0, &object_type(), VMValue::Reference(mirror));
+        for (index, value) in [(1, name), (2, descriptor)] {
+            if let Some(value) = value {
+                let string = env.vm_mut().intern_string(&value)?;
+                let array = env.vm().handle(scope);
+                env.vm_mut().store_element(array, index, &object_type(), VMValue::Reference(string));
+            }
+        }
+        Ok(Some(VMValue::Reference(env.vm().handle(scope))))
+    })();
+    env.vm_mut().release_handles(scope);
+    result
+}
+
+/// The host of the nest a class belongs to, from its `NestHost` attribute.
+fn nest_host(env: &mut VMEnv, class: ClassId) -> Result<ClassId, InterpreterError> {
+    let class_file = env.vm().classes.class(class).class.clone();
+    match class_file.nest_host() {
+        Some(host) => Ok(env.vm_mut().classes.resolve_class(class, &host)?),
+        None => Ok(class)
+    }
+}
+
+/// A `Class[]` holding the mirrors of some classes.
+fn class_array(env: &mut VMEnv, classes: &[ClassId]) -> Result<ObjRef, InterpreterError> {
+    let array_class = env.vm_mut().classes.load_class(LoaderId::BOOTSTRAP, "[Ljava/lang/Class;")?;
+    let scope = env.vm().handle_scope();
+    let result = (|| {
+        let array = env.vm_mut().new_array(array_class, classes.len() as i32)?;
+        let array = env.vm_mut().new_handle(array);
+        for (index, class) in classes.iter().enumerate() {
+            let mirror = env.vm_mut().class_mirror(*class)?;
+            let array = env.vm().handle(array);
+            env.vm_mut().store_element(array, index as u32, &FieldType::Object("java/lang/Class".to_string()), VMValue::Reference(mirror));
+        }
+        Ok(env.vm().handle(array))
+    })();
+    env.vm_mut().release_handles(scope);
+    result
+}
+
+/// `ProcessEnvironment.environ`: the names and values of the process environment, alternating in
+/// one `byte[][]`.
+fn process_environment(env: &mut VMEnv, _: &[VMValue]) -> NativeResult {
+    use std::os::unix::ffi::OsStrExt;
+    let variables: Vec<Vec<u8>> = std::env::vars_os()
+        .flat_map(|(name, value)| [name.as_bytes().to_vec(), value.as_bytes().to_vec()])
+        .collect();
+    let array_class = env.vm_mut().classes.load_class(LoaderId::BOOTSTRAP, "[[B")?;
+    let bytes_class = env.vm_mut().classes.load_class(LoaderId::BOOTSTRAP, "[B")?;
+    let scope = env.vm().handle_scope();
+    let result = (|| {
+        let array = env.vm_mut().new_array(array_class, variables.len() as i32)?;
+        let array = env.vm_mut().new_handle(array);
+        for (index, variable) in variables.iter().enumerate() {
+            let bytes = env.vm_mut().new_array(bytes_class, variable.len() as i32)?;
+            for (offset, byte) in variable.iter().enumerate() {
+                env.vm_mut().heap.write_element(bytes, offset as u32, &FieldType::Byte, VMValue::Int(*byte as i8 as i32));
+            }
+            let array = env.vm().handle(array);
+            env.vm_mut().store_element(array, index as u32, &FieldType::Array(Box::new(FieldType::Byte)), VMValue::Reference(bytes));
+        }
+        Ok(Some(VMValue::Reference(env.vm().handle(array))))
+    })();
+    env.vm_mut().release_handles(scope);
+    result
+}
+
+/// `System.setIn0` and friends, which replace the final static fields `System.in`, `out` and `err`.
+fn set_system_stream(env: &mut VMEnv, name: &str, args: &[VMValue]) -> NativeResult {
+    let system = env.vm_mut().classes.load_class(LoaderId::BOOTSTRAP, "java/lang/System")?;
+    let index = env.vm().classes.class(system).class.fields.iter().position(|field| field.field_name == name && field.is_static())
+        .ok_or_else(|| InterpreterError::exception("java/lang/NoSuchFieldError", name))?;
+    env.vm_mut().set_static(system, index, args[0]);
+    Ok(None)
+}
+
+fn strict_math(args: &[VMValue], function: fn(f64) -> f64) -> NativeResult {
+    Ok(Some(VMValue::Double(function(args[0].as_double()?))))
+}
+
+/// `StackTraceElement.initStackTraceElements`: copies the elements `Throwable.fillInStackTrace`
+/// kept in the `backtrace` of the throwable into the new elements.
+fn init_stack_trace_elements(env: &mut VMEnv, args: &[VMValue]) -> NativeResult {
+    let (elements, throwable) = (non_null(reference(args, 0)?)?, non_null(reference(args, 1)?)?);
+    let backtrace = non_null(env.vm().get_field(throwable, "backtrace")?.as_reference()?)?;
+    let element_type = FieldType::Object("java/lang/StackTraceElement".to_string());
+    let length = env.vm().heap.array_length(elements).min(env.vm().heap.array_length(backtrace));
+    for index in 0..length {
+        let from = env.vm().heap.read_element(backtrace, index, &element_type).as_reference()?;
+        let to = env.vm().heap.read_element(elements, index, &element_type).as_reference()?;
+        if let (Some(from), Some(to)) = (from, to) {
+            for field in ["declaringClassObject", "declaringClass", "methodName", "fileName", "lineNumber"] {
+                let value = env.vm().get_field(from, field)?;
+                env.vm_mut().set_field(to, field, value)?;
+            }
+        }
+    }
+    Ok(None)
+}
+
+/// `Reference.getAndClearReferencePendingList`: the references the collector queued, linked
+/// through their `discovered` field.
+fn reference_pending_list(env: &mut VMEnv, _: &[VMValue]) -> NativeResult {
+    let mut head = VMValue::Null;
+    while let Some(reference) = env.vm_mut().take_pending_reference() {
+        env.vm_mut().set_field(reference, "discovered", head)?;
+        head = VMValue::Reference(reference);
+    }
+    Ok(Some(head))
+}
+
+/// `Reference.refersTo0`, which compares the referent without making it strongly reachable.
+fn reference_refers_to(env: &mut VMEnv, args: &[VMValue]) -> NativeResult {
+    let referent = env.vm().get_field(receiver(args)?, "referent")?.as_reference()?;
+    Ok(Some(VMValue::Int((referent == reference(args, 1)?) as i32)))
+}
+
+/// `Reflection.getCallerClass`: the class of the method that called the method calling it,
+/// skipping the frames of reflection, `Method.invoke` and the accessors it goes through.
+fn reflection_get_caller_class(env: &mut VMEnv, _: &[VMValue]) -> NativeResult {
+    let caller = env.frames().iter().rev().skip(1)
+        .find(|frame| {
+            let invoke = frame.class_file.class_name == "java/lang/reflect/Method" && frame.method().method_name == "invoke";
+            !(invoke || is_reflection_accessor(&frame.class_file.class_name))
+        })
+        .map(|frame| frame.class);
+    match caller {
+        Some(class) => Ok(Some(VMValue::Reference(env.vm_mut().class_mirror(class)?))),
+        None => Ok(Some(VMValue::Null))
+    }
+}
+
+/// `BootLoader.setBootLoaderUnnamedModule0`: makes the module the one of every class, including
+/// the classes whose mirrors already exist.
+fn set_boot_loader_unnamed_module(env: &mut VMEnv, args: &[VMValue]) -> NativeResult {
+    let module = non_null(reference(args, 0)?)?;
+    let handle = env.vm_mut().new_global_handle(module);
+    env.vm_mut().unnamed_module = Some(handle);
+    let mirrors: Vec<ObjRef> = env.vm().classes.classes().iter().filter_map(|class| class.mirror).collect();
+    for mirror in mirrors {
+        env.vm_mut().set_field(mirror, "module", VMValue::Reference(module))?;
+    }
+    Ok(None)
+}
+
+/// Whether a class is one of the accessors `Method.invoke` and `Constructor.newInstance` go through.
+fn is_reflection_accessor(class_name: &str) -> bool {
+    class_name.starts_with("jdk/internal/reflect/") && (class_name.ends_with("MethodAccessorImpl") || class_name.ends_with("ConstructorAccessorImpl"))
+}
+
+/// `Class.getDeclaredConstructors0`, `getDeclaredMethods0` and `getDeclaredFields0`: the
+/// `java.lang.reflect` objects of the members a class declares, whose `slot` is the index of the
+/// member in the class file. Exceptions, generic signatures and annotations are left out.
+fn declared_members(env: &mut VMEnv, args: &[VMValue], member_class: &str) -> NativeResult {
+    let class = this_class(env, args)?;
+    let public_only = args[1].as_int()? != 0;
+    let class_file = env.vm().classes.class(class).class.clone();
+    let slots: Vec<usize> = match member_class {
+        "java/lang/reflect/Field" => class_file.fields.iter().enumerate()
+            .filter(|(_, field)| !public_only || field.access_flags.contains(&AccessFlags::Public))
+            .map(|(slot, _)| slot)
+            .collect(),
+        _ => class_file.methods.iter().enumerate()
+            .filter(|(_, method)| (method.method_name == "<init>") == (member_class == "java/lang/reflect/Constructor") && method.method_name != "<clinit>")
+            .filter(|(_, method)| !public_only || method.access_flags.contains(&AccessFlags::Public))
+            .map(|(slot, _)| slot)
+            .collect()
+    };
+    let reflect_class = env.vm_mut().bootstrap_class(member_class)?;
+    let array_class = env.vm_mut().classes.load_class(LoaderId::BOOTSTRAP, &format!("[L{};", member_class))?;
+    let scope = env.vm().handle_scope();
+    let result = (|| {
+        let array = env.vm_mut().new_array(array_class, slots.len() as i32)?;
+        let array = env.vm_mut().new_handle(array);
+        for (index, slot) in slots.into_iter().enumerate() {
+            let member = reflect_member(env, class, reflect_class, slot)?;
+            let array = env.vm().handle(array);
+            env.vm_mut().store_element(array, index as u32, &FieldType::Object(member_class.to_string()), VMValue::Reference(member));
+        }
+        Ok(Some(VMValue::Reference(env.vm().handle(array))))
+    })();
+    env.vm_mut().release_handles(scope);
+    result
+}
+
+/// The `Constructor`, `Method` or `Field` of `reflect_class` for the member of `class` at `slot`.
+fn reflect_member(env: &mut VMEnv, class: ClassId, reflect_class: ClassId, slot: usize) -> Result<ObjRef, InterpreterError> {
+    let class_file = env.vm().classes.class(class).class.clone();
+    let is_field = env.vm().classes.class(reflect_class).name == "java/lang/reflect/Field";
+    let (name, descriptor, access_flags) = match is_field {
+        true => (&class_file.fields[slot].field_name, &class_file.fields[slot].descriptor, &class_file.fields[slot].access_flags),
+        false => (&class_file.methods[slot].method_name, &class_file.methods[slot].descriptor, &class_file.methods[slot].access_flags)
+    };
+    let (parameters, value_type) = match is_field {
+        true => (Vec::new(), Some(FieldType::parse(descriptor)?)),
+        false => {
+            let descriptor = MethodDescriptor::parse(descriptor)?;
+            (descriptor.parameters, descriptor.return_type)
+        }
+    };
+    let mut parameter_classes: Vec<ClassId> = Vec::with_capacity(parameters.len());
+    for parameter in &parameters {
+        parameter_classes.push(env.vm_mut().classes.class_for_type(class, parameter)?);
+    }
+    let value_class = match &value_type {
+        Some(value_type) => env.vm_mut().classes.class_for_type(class, value_type)?,
+        None => env.vm_mut().classes.primitive_class(None)
+    };
+
+    let member = env.vm_mut().new_object(reflect_class)?;
+    let member = env.vm_mut().new_handle(member);
+    // Each value is created before the member is looked up, which may have moved meanwhile.
+    fn set(env: &mut VMEnv, member: Handle, field: &str, value: VMValue) -> Result<(), InterpreterError> {
+        let member = env.vm().handle(member);
+        env.vm_mut().set_field(member, field, value)
+    }
+    let mirror = env.vm_mut().class_mirror(class)?;
+    set(env, member, "clazz", VMValue::Reference(mirror))?;
+    set(env, member, "slot", VMValue::Int(slot as i32))?;
+    set(env, member, "modifiers", VMValue::Int(AccessFlags::to_value(access_flags) as i32))?;
+    if !matches!(env.vm().classes.class(reflect_class).name.as_str(), "java/lang/reflect/Constructor") {
+        let name = env.vm_mut().intern_string(name)?;
+        set(env, member, "name", VMValue::Reference(name))?;
+        let value_mirror = env.vm_mut().class_mirror(value_class)?;
+        set(env, member, if is_field { "type" } else { "returnType" }, VMValue::Reference(value_mirror))?;
+    }
+    if is_field {
+        let trusted_final = access_flags.contains(&AccessFlags::Final) && access_flags.contains(&AccessFlags::Static);
+        set(env, member, "trustedFinal", VMValue::Boolean(trusted_final))?;
+    } else {
+        let parameter_types = class_array(env, &parameter_classes)?;
+        set(env, member, "parameterTypes", VMValue::Reference(parameter_types))?;
+        let exception_types = class_array(env, &[])?;
+        set(env, member, "exceptionTypes", VMValue::Reference(exception_types))?;
+    }
+    Ok(env.vm().handle(member))
+}
+
+/// `NativeConstructorAccessorImpl.newInstance0`: creates an object and runs the constructor a
+/// `Constructor` reflects on it.
+fn constructor_new_instance(env: &mut VMEnv, args: &[VMValue]) -> NativeResult {
+    let constructor = non_null(reference(args, 0)?)?;
+    let class = env.vm().mirrored_class(non_null(env.vm().get_field(constructor, "clazz")?.as_reference()?)?)?;
+    let slot = env.vm().get_field(constructor, "slot")?.as_int()? as usize;
+    let runtime_class = env.vm().classes.class(class);
+    if runtime_class.is_interface() || runtime_class.class.access_flags.contains(&AccessFlags::Abstract) {
+        return Err(InterpreterError::exception("java/lang/InstantiationException", runtime_class.java_name()))
+    }
+    let scope = env.vm().handle_scope();
+    let arguments = reference(args, 1)?.map(|arguments| env.vm_mut().new_handle(arguments));
+    let result = (|| {
+        env.initialize_class(class)?;
+        let instance = env.vm_mut().new_object(class)?;
+        let instance = env.vm_mut().new_handle(instance);
+        let mut values = vec![VMValue::Reference(env.vm().handle(instance))];
+        values.extend(reflection_arguments(env, class, slot, arguments)?);
+        invoke_reflected(env, class, slot, values)?;
+        Ok(Some(VMValue::Reference(env.vm().handle(instance))))
+    })();
+    env.vm_mut().release_handles(scope);
+    result
+}
+
+/// `NativeMethodAccessorImpl.invoke0`: runs the method a `Method` reflects, selected from the
+/// receiver's class unless it is static or private, and boxes what it returns.
+fn method_invoke(env: &mut VMEnv, args: &[VMValue]) -> NativeResult {
+    let method = non_null(reference(args, 0)?)?;
+    let class = env.vm().mirrored_class(non_null(env.vm().get_field(method, "clazz")?.as_reference()?)?)?;
+    let slot = env.vm().get_field(method, "slot")?.as_int()? as usize;
+    let class_file = env.vm().classes.class(class).class.clone();
+    let target = &class_file.methods[slot];
+    let scope = env.vm().handle_scope();
+    let receiver = reference(args, 1)?.map(|receiver| env.vm_mut().new_handle(receiver));
+    let arguments = reference(args, 2)?.map(|arguments| env.vm_mut().new_handle(arguments));
+    let result = (|| {
+        let (selected_class, selected_slot, mut values) = if target.is_static() {
+            env.initialize_class(class)?;
+            (class, slot, Vec::new())
+        } else {
+            let receiver = env.vm().handle(receiver.ok_or_else(|| InterpreterError::Exception(JavaException::new("java/lang/NullPointerException", None)))?);
+            let receiver_class = env.vm().heap.class_of(receiver);
+            if !env.vm().classes.is_assignable(receiver_class, class) {
+                return Err(InterpreterError::exception("java/lang/IllegalArgumentException", "object is not an instance of declaring class"))
+            }
+            let (selected_class, selected_slot) = match target.access_flags.contains(&AccessFlags::Private) {
+                true => (class, slot),
+                false => env.vm().classes.select_method(receiver_class, &target.method_name, &target.descriptor).unwrap_or((class, slot))
+            };
+            (selected_class, selected_slot, vec![VMValue::Reference(receiver)])
+        };
+        values.extend(reflection_arguments(env, class, slot, arguments)?);
+        let result = invoke_reflected(env, selected_class, selected_slot, values)?;
+        match (result, MethodDescriptor::parse(&target.descriptor)?.return_type) {
+            (Some(value), Some(return_type)) => Ok(Some(env.adapt_value(value, &return_type, &object_type())?)),
+            _ => Ok(Some(VMValue::Null))
+        }
+    })();
+    env.vm_mut().release_handles(scope);
+    result
+}
+
+/// The arguments of a reflective call from its `Object[]`, unboxed where the method takes primitives.
+fn reflection_arguments(env: &mut VMEnv, class: ClassId, slot: usize, arguments: Option<Handle>) -> Result<Vec<VMValue>, InterpreterError> {
+    let class_file = env.vm().classes.class(class).class.clone();
+    let parameters = MethodDescriptor::parse(&class_file.methods[slot].descriptor)?.parameters;
+    let arguments = arguments.map(|arguments| env.vm().handle(arguments));
+    let length = arguments.map_or(0, |arguments| env.vm().heap.array_length(arguments)) as usize;
+    if length != parameters.len() {
+        return Err(InterpreterError::exception("java/lang/IllegalArgumentException",
+            format!("wrong number of arguments: {} expected: {}", length, parameters.len())))
+    }
+    let mut values: Vec<VMValue> = Vec::with_capacity(length);
+    for (index, parameter) in parameters.iter().enumerate() {
+        // Unboxing reads a field and allocates nothing, so the array stays where it is.
+        let argument = env.vm().heap.read_element(arguments.unwrap(), index as u32, &object_type());
+        values.push(env.adapt_value(argument, &object_type(), parameter)?);
+    }
+    Ok(values)
+}
+
+/// Invokes a method for reflection, which wraps what it throws in an `InvocationTargetException`.
+fn invoke_reflected(env: &mut VMEnv, class: ClassId, slot: usize, values: Vec<VMValue>) -> NativeResult {
+    let mut exception = match env.invoke(class, slot, values) {
+        Err(InterpreterError::Exception(exception)) => exception,
+        other => return other
+    };
+    let throwable = env.throwable(&mut exception)?;
+    let throwable = env.vm_mut().new_handle(throwable);
+    let wrapper_class = env.vm_mut().bootstrap_class("java/lang/reflect/InvocationTargetException")?;
+    let wrapper = env.vm_mut().new_object(wrapper_class)?;
+    let wrapper = env.vm_mut().new_handle(wrapper);
+    let args = vec![VMValue::Reference(env.vm().handle(wrapper)), VMValue::Reference(env.vm().handle(throwable))];
+    env.invoke_constructor(wrapper_class, "(Ljava/lang/Throwable;)V", args)?;
+    Err(InterpreterError::Exception(env.java_exception(env.vm().handle(wrapper))?))
+}
+
+/// The home of the JDK whose module image is the bootstrap class path.
+fn java_home(env: &VMEnv) -> Option<PathBuf> {
+    let class_path = &env.vm().classes.loader_info(LoaderId::BOOTSTRAP).ok()?.class_path;
+    class_path.entries().iter().find_map(|entry| match entry {
+        ClassPathEntry::Image(image) => image.path().parent()?.parent().map(PathBuf::from),
+        _ => None
+    })
+}
+
+/// A `String[]`, with null for the missing values.
+fn string_array(env: &mut VMEnv, values: &[Option<String>]) -> Result<ObjRef, InterpreterError> {
+    let array_class = env.vm_mut().classes.load_class(LoaderId::BOOTSTRAP, "[Ljava/lang/String;")?;
+    let array = env.vm_mut().new_array(array_class, values.len() as i32)?;
+    let scope = env.vm_mut().new_handle(array);
+    let result = (|| {
+        for (index, value) in values.iter().enumerate() {
+            if let Some(value) = value {
+                let string = env.vm_mut().new_string(value)?;
+                let array = env.vm().handle(scope);
+                env.vm_mut().store_element(array, index as u32, &FieldType::Object("java/lang/String".to_string()), VMValue::Reference(string));
+            }
+        }
+        Ok(env.vm().handle(scope))
+    })();
+    env.vm_mut().release_handles(scope);
+    result
+}
+
+/// `SystemProps.Raw.vmProperties`: the properties the VM defines, as pairs of names and values.
+fn vm_properties(env: &mut VMEnv, _: &[VMValue]) -> NativeResult {
+    let java_home = java_home(env).map(|path| path.display().to_string()).unwrap_or_default();
+    let library_path: Vec<String> = env.vm().options.library_path.iter().map(|path| path.display().to_string()).collect();
+    let properties = [
+        ("java.home", java_home.clone()),
+        ("java.vm.specification.name", "Java Virtual Machine Specification".to_string()),
+        ("java.vm.specification.vendor", "Oracle Corporation".to_string()),
+        ("java.vm.specification.version", "17".to_string()),
+        ("java.vm.name", "rusty-jvm".to_string()),
+        ("java.vm.vendor", "rusty-jvm".to_string()),
+        ("java.vm.version", env!("CARGO_PKG_VERSION").to_string()),
+        ("java.vm.info", "interpreted mode".to_string()),
+        ("java.library.path", library_path.join(&SEPARATOR.to_string())),
+        ("sun.boot.library.path", format!("{}/lib", java_home)),
+        ("java.class.path", String::new()),
+        ("sun.java.launcher", "SUN_STANDARD".to_string()),
+        ("jdk.debug", "release".to_string())
+    ];
+    let values: Vec<Option<String>> = properties.into_iter().flat_map(|(name, value)| [Some(name.to_string()), Some(value)]).collect();
+    Ok(Some(VMValue::Reference(string_array(env, &values)?)))
+}
+
+/// `SystemProps.Raw.platformProperties`: the properties of the platform, at the indexes `Raw`
+/// declares constants for.
+fn platform_properties(env: &mut VMEnv, _: &[VMValue]) -> NativeResult {
+    let raw = env.vm_mut().classes.load_class(LoaderId::BOOTSTRAP, "jdk/internal/util/SystemProps$Raw")?;
+    let index = |name: &str| env.vm().classes.class(raw).static_value(name).and_then(|value| value.as_int().ok()).map(|index| index as usize);
+    let length = index("FIXED_LENGTH").ok_or_else(|| InterpreterError::exception("java/lang/InternalError", "SystemProps.Raw.FIXED_LENGTH"))?;
+    let var = |name: &str| std::env::var(name).ok();
+    let user_dir = std::env::current_dir().map(|path| path.display().to_string()).ok();
+    let properties = [
+        ("_file_encoding_NDX", Some("UTF-8".to_string())),
+        ("_sun_jnu_encoding_NDX", Some("UTF-8".to_string())),
+        ("_file_separator_NDX", Some("/".to_string())),
+        ("_path_separator_NDX", Some(SEPARATOR.to_string())),
+        ("_line_separator_NDX", Some("\n".to_string())),
+        ("_java_io_tmpdir_NDX", Some(std::env::temp_dir().display().to_string())),
+        ("_os_name_NDX", Some(match std::env::consts::OS { "linux" => "Linux", "macos" => "Mac OS X", os => os }.to_string())),
+        ("_os_arch_NDX", Some(match std::env::consts::ARCH { "x86_64" => "amd64", arch => arch }.to_string())),
+        ("_os_version_NDX", Some("unknown".to_string())),
+        ("_sun_arch_data_model_NDX", Some("64".to_string())),
+        ("_sun_cpu_endian_NDX", Some(if cfg!(target_endian = "big") { "big" } else { "little" }.to_string())),
+        ("_sun_io_unicode_encoding_NDX", Some("UnicodeLittle".to_string())),
+        ("_display_language_NDX", Some("en".to_string())),
+        ("_format_language_NDX", Some("en".to_string())),
+        ("_user_dir_NDX", user_dir),
+        ("_user_home_NDX", var("HOME").or_else(|| Some("?".to_string()))),
+        ("_user_name_NDX", var("USER").or_else(|| Some("?".to_string())))
+    ];
+    let mut values: Vec<Option<String>> = vec![None; length];
+    for (name, value) in properties {
+        if let Some(index) = index(name).filter(|index| *index < length) {
+            values[index] = value;
+        }
+    }
+    Ok(Some(VMValue::Reference(string_array(env, &values)?)))
+}
+
+/// The `fd` of the `FileDescriptor` of a `FileInputStream` or `FileOutputStream`.
+fn file_descriptor(env: &VMEnv, stream: ObjRef) -> Result<i32, InterpreterError> {
+    let fd = non_null(env.vm().get_field(stream, "fd")?.as_reference()?)?;
+    env.vm().get_field(fd, "fd")?.as_int()
+}
+
+/// The path of the `java.io.File` argument of a `UnixFileSystem` native.
+fn file_path(env: &VMEnv, args: &[VMValue]) -> Result<String, InterpreterError> {
+    let file = non_null(reference(args, 1)?)?;
+    env.vm().string_value(non_null(env.vm().get_field(file, "path")?.as_reference()?)?)
+}
+
+/// `FileInputStream.readBytes` of the standard input, the only file the VM opens.
+fn file_input_stream_read_bytes(env: &mut VMEnv, args: &[VMValue]) -> NativeResult {
+    if file_descriptor(env, receiver(args)?)? != 0 {
+        return Err(InterpreterError::exception("java/io/IOException", "Bad file descriptor"))
+    }
+    let array = non_null(reference(args, 1)?)?;
+    let (offset, length) = (args[2].as_int()?, args[3].as_int()?);
+    let mut buffer = vec![0; length.max(0) as usize];
+    let read = std::io::stdin().read(&mut buffer).map_err(|e| InterpreterError::exception("java/io/IOException", e.to_string()))?;
+    if read == 0 && length > 0 {
+        return Ok(Some(VMValue::Int(-1)))
+    }
+    for (index, byte) in buffer[..read].iter().enumerate() {
+        env.vm_mut().heap.write_element(array, (offset as usize + index) as u32, &FieldType::Byte, VMValue::Int(*byte as i8 as i32));
+    }
+    Ok(Some(VMValue::Int(read as i32)))
+}
+
+fn object_type() -> FieldType {
+    FieldType::Object("java/lang/Object".to_string())
+}
+
+/// The offset of a field or array element within the field area of an object, from an `Unsafe`
+/// offset, which counts from the start of the object like HotSpot's.
+fn field_offset(offset: i64) -> Result<u32, InterpreterError> {
+    u32::try_from(offset - Heap::HEADER_SIZE as i64)
+        .map_err(|_| InterpreterError::exception("java/lang/InternalError", format!("unsupported Unsafe offset {}", offset)))
+}
+
+/// The object and offset `Unsafe` accesses, after the receiver. Raw memory, a null object with an
+/// address, is not supported.
+fn unsafe_target(args: &[VMValue]) -> Result<(ObjRef, u32), InterpreterError> {
+    let obj = reference(args, 1)?
+        .ok_or_else(|| InterpreterError::exception("java/lang/InternalError", "Unsafe access to off-heap memory is not supported"))?;
+    Ok((obj, field_offset(args[2].as_long()?)?))
+}
+
+fn unsafe_get(env: &mut VMEnv, args: &[VMValue], field_type: FieldType) -> NativeResult {
+    let (obj, offset) = unsafe_target(args)?;
+    Ok(Some(env.vm().heap.read_field(obj, offset, &field_type)))
+}
+
+fn unsafe_put(env: &mut VMEnv, args: &[VMValue], field_type: FieldType) -> NativeResult {
+    let (obj, offset) = unsafe_target(args)?;
+    env.vm_mut().store_field(obj, offset, &field_type, args[3]);
+    Ok(None)
+}
+
+/// Stores the new value if the field holds the expected one, returning the value it held.
+fn compare_and_exchange(env: &mut VMEnv, args: &[VMValue], field_type: &FieldType) -> Result<(VMValue, bool), InterpreterError> {
+    let (obj, offset) = unsafe_target(args)?;
+    let witness = env.vm().heap.read_field(obj, offset, field_type);
+    let swapped = witness == args[3].computational();
+    if swapped {
+        env.vm_mut().store_field(obj, offset, field_type, args[4]);
+    }
+    Ok((witness, swapped))
+}
+
+fn unsafe_compare_and_set(env: &mut VMEnv, args: &[VMValue], field_type: FieldType) -> NativeResult {
+    let (_, swapped) = compare_and_exchange(env, args, &field_type)?;
+    Ok(Some(VMValue::Int(swapped as i32)))
+}
+
+fn unsafe_compare_and_exchange(env: &mut VMEnv, args: &[VMValue], field_type: FieldType) -> NativeResult {
+    let (witness, _) = compare_and_exchange(env, args, &field_type)?;
+    Ok(Some(witness))
+}
+
+/// `Unsafe.copyMemory0` between the fields or elements of two objects.
+fn unsafe_copy_memory(env: &mut VMEnv, args: &[VMValue]) -> NativeResult {
+    let src = reference(args, 1)?;
+    let dest = reference(args, 3)?;
+    let (src, dest) = match (src, dest) {
+        (Some(src), Some(dest)) => (src, dest),
+        _ => return Err(InterpreterError::exception("java/lang/InternalError", "Unsafe access to off-heap memory is not supported"))
+    };
+    let (src_offset, dest_offset) = (field_offset(args[2].as_long()?)?, field_offset(args[4].as_long()?)?);
+    let bytes: Vec<VMValue> = (0..args[5].as_long()? as u32).map(|index| env.vm().heap.read_field(src, src_offset + index, &FieldType::Byte)).collect();
+    for (index, byte) in bytes.into_iter().enumerate() {
+        env.vm_mut().heap.write_field(dest, dest_offset + index as u32, &FieldType::Byte, byte);
+    }
+    Ok(None)
+}
@@ -0,0 +1,402 @@
+//! The jimage container a JDK keeps its modules in (`<java.home>/lib/modules`), read the way
+//! HotSpot's `ImageFileReader` does: the index (header, redirect table, offsets, locations and
+//! strings) is read once when the image is opened, resource data is read from disk on every lookup.
+
+use std::fs::File;
+use std::io::{Cursor, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use byteorder::{BigEndian, ByteOrder, LittleEndian, ReadBytesExt, WriteBytesExt};
+use flate2::read::ZlibDecoder;
+use crate::loader::ClassLoadError;
+
+const IMAGE_MAGIC: u32 = 0xCAFEDADA;
+const MAJOR_VERSION: u32 = 1;
+const MINOR_VERSION: u32 = 0;
+const HEADER_SIZE: usize = 7 * 4;
+
+/// Seed of the name hash, and its multiplier.
+pub const HASH_MULTIPLIER: u32 = 0x01000193;
+
+const ATTRIBUTE_END: u8 = 0;
+const ATTRIBUTE_MODULE: u8 = 1;
+const ATTRIBUTE_PARENT: u8 = 2;
+const ATTRIBUTE_BASE: u8 = 3;
+const ATTRIBUTE_EXTENSION: u8 = 4;
+const ATTRIBUTE_OFFSET: u8 = 5;
+const ATTRIBUTE_COMPRESSED: u8 = 6;
+const ATTRIBUTE_UNCOMPRESSED: u8 = 7;
+
+/// Every compression layer of a resource starts with this header.
+const RESOURCE_HEADER_MAGIC: u32 = 0xCAFEFAFA;
+const RESOURCE_HEADER_SIZE: usize = 29;
+
+/// Constant pool tags the string sharing compressor writes in place of `CONSTANT_Utf8`.
+const EXTERNALIZED_STRING: u8 = 23;
+const EXTERNALIZED_DESCRIPTOR: u8 = 25;
+
+/// The jimage hash of a name: FNV-1 over its bytes, starting from `seed`.
+pub fn hash_code(name: &[u8], seed: u32) -> u32 {
+    name.iter().fold(seed, |hash, byte| hash.wrapping_mul(HASH_MULTIPLIER) ^ *byte as u32) & 0x7fffffff
+}
+
+/// Where a resource lives in the image. The name parts are stored separately in the strings
+/// table, the full name is `/module/parent/base.extension` with empty parts left out.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Location {
+    pub module: String,
+    pub parent: String,
+    pub base: String,
+    pub extension: String,
+    /// Offset of the resource data from the end of the index.
+    pub offset: u64,
+    /// Stored size when the resource is compressed, 0 otherwise.
+    pub compressed_size: u64,
+    pub uncompressed_size: u64
+}
+
+impl Location {
+    pub fn full_name(&self) -> String {
+        let mut name = String::new();
+        if !self.module.is_empty() {
+            name.push('/');
+            name.push_str(&self.module);
+            name.push('/');
+        }
+        if !self.parent.is_empty() {
+            name.push_str(&self.parent);
+            name.push('/');
+        }
+        name.push_str(&self.base);
+        if !self.extension.is_empty() {
+            name.push('.');
+            name.push_str(&self.extension);
+        }
+        name
+    }
+}
+
+#[derive(Debug)]
+pub struct Image {
+    path: PathBuf,
+    big_endian: bool,
+    resource_count: u32,
+    redirect: Vec<i32>,
+    offsets: Vec<u32>,
+    locations: Vec<u8>,
+    strings: Vec<u8>,
+    /// Size of everything before the resource data.
+    index_size: u64
+}
+
+impl Image {
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Image, ClassLoadError> {
+        let path = path.as_ref().to_path_buf();
+        let mut file = File::open(&path)?;
+        let invalid = |reason: &str| ClassLoadError::InvalidImage(path.display().to_string(), reason.to_string());
+        let mut header = [0u8; HEADER_SIZE];
+        file.read_exact(&mut header).map_err(|_| invalid("truncated header"))?;
+        // The image is written in the byte order of the platform that built it, the magic tells which.
+        let big_endian = match LittleEndian::read_u32(&header) {
+            IMAGE_MAGIC => false,
+            magic if magic.swap_bytes() == IMAGE_MAGIC => true,
+            _ => return Err(invalid("bad magic"))
+        };
+        let field = |index: usize| if big_endian {
+            BigEndian::read_u32(&header[index * 4..])
+        } else {
+            LittleEndian::read_u32(&header[index * 4..])
+        };
+        let version = field(1);
+        if version >> 16 != MAJOR_VERSION || version & 0xffff != MINOR_VERSION {
+            return Err(invalid(&format!("unsupported version {}.{}", version >> 16, version & 0xffff)))
+        }
+        let resource_count = field(3);
+        let table_length = field(4) as usize;
+        let locations_size = field(5) as usize;
+        let strings_size = field(6) as usize;
+        let index_size = HEADER_SIZE + table_length * 8 + locations_size + strings_size;
+        if index_size as u64 > file.metadata()?.len() {
+            return Err(invalid("truncated index"))
+        }
+
+        let mut index = vec![0u8; index_size - HEADER_SIZE];
+        file.read_exact(&mut index)?;
+        let (tables, rest) = index.split_at(table_length * 8);
+        let (locations, strings) = rest.split_at(locations_size);
+        let mut redirect = vec![0i32; table_length];
+        let mut offsets = vec![0u32; table_length];
+        if big_endian {
+            BigEndian::read_i32_into(&tables[..table_length * 4], &mut redirect);
+            BigEndian::read_u32_into(&tables[table_length * 4..], &mut offsets);
+        } else {
+            LittleEndian::read_i32_into(&tables[..table_length * 4], &mut redirect);
+            LittleEndian::read_u32_into(&tables[table_length * 4..], &mut offsets);
+        }
+        Ok(Image {
+            big_endian,
+            resource_count,
+            redirect,
+            offsets,
+            locations: locations.to_vec(),
+            strings: strings.to_vec(),
+            index_size: index_size as u64,
+            path
+        })
+    }
+
+    fn invalid(&self, reason: &str) -> ClassLoadError {
+        ClassLoadError::InvalidImage(self.path.display().to_string(), reason.to_string())
+    }
+
+    fn read_u32(&self, bytes: &[u8]) -> u32 {
+        if self.big_endian { BigEndian::read_u32(bytes) } else { LittleEndian::read_u32(bytes) }
+    }
+
+    fn read_u64(&self, bytes: &[u8]) -> u64 {
+        if self.big_endian { BigEndian::read_u64(bytes) } else { LittleEndian::read_u64(bytes) }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn resource_count(&self) -> u32 {
+        self.resource_count
+    }
+
+    /// The NUL terminated entry of the strings table at `offset`, as stored (modified UTF-8).
+    pub fn string_bytes(&self, offset: u32) -> Result<&[u8], ClassLoadError> {
+        let tail = self.strings.get(offset as usize..).ok_or_else(|| self.invalid(&format!("string offset {} out of range", offset)))?;
+        let end = tail.iter().position(|b| *b == 0).ok_or_else(|| self.invalid("unterminated string"))?;
+        Ok(&tail[..end])
+    }
+
+    pub fn string(&self, offset: u32) -> Result<String, ClassLoadError> {
+        String::from_utf8(self.string_bytes(offset)?.to_vec()).map_err(|_| self.invalid(&format!("string at {} is not UTF-8", offset)))
+    }
+
+    /// Decodes the attribute stream at `offset` of the locations table.
+    fn location_at(&self, offset: u32) -> Result<Location, ClassLoadError> {
+        let mut attributes = self.locations.get(offset as usize..).ok_or_else(|| self.invalid(&format!("location offset {} out of range", offset)))?;
+        let mut values = [0u64; ATTRIBUTE_UNCOMPRESSED as usize + 1];
+        loop {
+            let (&byte, rest) = attributes.split_first().ok_or_else(|| self.invalid("unterminated location"))?;
+            let kind = byte >> 3;
+            if kind == ATTRIBUTE_END {
+                break
+            }
+            let length = (byte & 7) as usize + 1;
+            if kind > ATTRIBUTE_UNCOMPRESSED || rest.len() < length {
+                return Err(self.invalid(&format!("bad location attribute {}", kind)))
+            }
+            values[kind as usize] = rest[..length].iter().fold(0, |value, b| value << 8 | *b as u64);
+            attributes = &rest[length..];
+        }
+        let string = |kind: u8| self.string(values[kind as usize] as u32);
+        Ok(Location {
+            module: string(ATTRIBUTE_MODULE)?,
+            parent: string(ATTRIBUTE_PARENT)?,
+            base: string(ATTRIBUTE_BASE)?,
+            extension: string(ATTRIBUTE_EXTENSION)?,
+            offset: values[ATTRIBUTE_OFFSET as usize],
+            compressed_size: values[ATTRIBUTE_COMPRESSED as usize],
+            uncompressed_size: values[ATTRIBUTE_UNCOMPRESSED as usize]
+        })
+    }
+
+    /// Finds a resource by its full name, e.g. `/java.base/java/lang/Object.class`. The redirect
+    /// table is a perfect hash: a negative entry is the index itself, a positive one the seed to
+    /// rehash the name with. The location found is checked against the name, since names that
+    /// are not in the image land on some other resource.
+    pub fn location(&self, name: &str) -> Result<Option<Location>, ClassLoadError> {
+        let length = self.redirect.len() as u32;
+        if length == 0 {
+            return Ok(None)
+        }
+        let index = match self.redirect[(hash_code(name.as_bytes(), HASH_MULTIPLIER) % length) as usize] {
+            0 => return Ok(None),
+            value if value < 0 => (-1 - value as i64) as u32,
+            seed => hash_code(name.as_bytes(), seed as u32) % length
+        };
+        let offset = *self.offsets.get(index as usize).ok_or_else(|| self.invalid(&format!("redirect to {} out of range", index)))?;
+        let location = self.location_at(offset)?;
+        Ok(Some(location).filter(|location| location.full_name() == name))
+    }
+
+    /// Full names of every resource in the image, in table order.
+    pub fn names(&self) -> Result<Vec<String>, ClassLoadError> {
+        self.offsets.iter().map(|offset| self.location_at(*offset).map(|location| location.full_name())).collect()
+    }
+
+    /// Reads and decompresses the data of a resource.
+    pub fn read_location(&self, location: &Location) -> Result<Vec<u8>, ClassLoadError> {
+        let stored_size = if location.compressed_size != 0 { location.compressed_size } else { location.uncompressed_size };
+        let mut file = File::open(&self.path)?;
+        file.seek(SeekFrom::Start(self.index_size + location.offset))?;
+        let mut data = Vec::with_capacity(stored_size as usize);
+        file.take(stored_size).read_to_end(&mut data)?;
+        if data.len() as u64 != stored_size {
+            return Err(self.invalid(&format!("truncated resource {}", location.full_name())))
+        }
+        if location.compressed_size != 0 {
+            data = self.decompress(data)?;
+        }
+        if data.len() as u64 != location.uncompressed_size {
+            return Err(self.invalid(&format!("corrupt resource {}", location.full_name())))
+        }
+        Ok(data)
+    }
+
+    /// Reads a resource by its full name.
+    pub fn read(&self, name: &str) -> Result<Option<Vec<u8>>, ClassLoadError> {
+        self.location(name)?.map(|location| self.read_location(&location)).transpose()
+    }
+
+    /// The module a `/` separated package belongs to, from the image's `/packages/<package>` entry.
+    pub fn package_module(&self, package: &str) -> Result<Option<String>, ClassLoadError> {
+        let modules = match self.read(&format!("/packages/{}", package.replace('/', ".")))? {
+            Some(modules) => modules,
+            None => return Ok(None)
+        };
+        // (is empty, module name offset) pairs; a module only listing the package as a directory is empty.
+        for pair in modules.chunks_exact(8) {
+            if self.read_u32(pair) == 0 {
+                return self.string(self.read_u32(&pair[4..])).map(Some)
+            }
+        }
+        Ok(None)
+    }
+
+    /// Reads a resource by its class path name, e.g. `java/lang/Object.class`, from the module
+    /// that contains its package.
+    pub fn read_resource(&self, name: &str) -> Result<Option<Vec<u8>>, ClassLoadError> {
+        let package = match name.rfind('/') {
+            Some(end) => &name[..end],
+            None => return Ok(None)
+        };
+        match self.package_module(package)? {
+            Some(module) => self.read(&format!("/{}/{}", module, name)),
+            None => Ok(None)
+        }
+    }
+
+    /// Strips compression layers until the data no longer starts with a resource header.
+    fn decompress(&self, mut data: Vec<u8>) -> Result<Vec<u8>, ClassLoadError> {
+        while data.len() >= RESOURCE_HEADER_SIZE && self.read_u32(&data) == RESOURCE_HEADER_MAGIC {
+            let size = self.read_u64(&data[4..]) as usize;
+            let uncompressed_size = self.read_u64(&data[12..]) as usize;
+            let decompressor = self.string(self.read_u32(&data[20..]))?;
+            let compressed = data.get(RESOURCE_HEADER_SIZE..RESOURCE_HEADER_SIZE + size)
+                .ok_or_else(|| self.invalid("truncated compressed resource"))?;
+            let mut decompressed = Vec::with_capacity(uncompressed_size);
+            match decompressor.as_str() {
+                "zip" => {
+                    ZlibDecoder::new(compressed).read_to_end(&mut decompressed).map_err(|e| self.invalid(&e.to_string()))?;
+                }
+                "compact-cp" => {
+                    self.expand_shared_strings(compressed, &mut decompressed).map_err(|e| match e {
+                        ClassLoadError::ClassFileReadFailure(e) => self.invalid(&e.to_string()),
+                        e => e
+                    })?;
+                }
+                name => return Err(self.invalid(&format!("unknown decompressor {}", name)))
+            }
+            if decompressed.len() != uncompressed_size {
+                return Err(self.invalid(&format!("{} decompressed to {} bytes, expected {}", decompressor, decompressed.len(), uncompressed_size)))
+            }
+            data = decompressed;
+        }
+        Ok(data)
+    }
+
+    /// Undoes jlink's string sharing: constant pool strings were moved to the strings table, and
+    /// descriptors were split into the descriptor without class names plus (package, class) indexes.
+    fn expand_shared_strings(&self, data: &[u8], out: &mut Vec<u8>) -> Result<(), ClassLoadError> {
+        let mut reader = Cursor::new(data);
+        // magic, minor and major version, constant pool count
+        let mut header = [0u8; 10];
+        reader.read_exact(&mut header)?;
+        out.extend_from_slice(&header);
+        let count = BigEndian::read_u16(&header[8..]);
+        let mut index = 1;
+        while index < count {
+            let tag = reader.read_u8()?;
+            match tag {
+                EXTERNALIZED_STRING => {
+                    let string = self.string_bytes(read_compressed_int(&mut reader)?)?;
+                    out.write_u8(1)?;
+                    out.write_u16::<BigEndian>(string.len() as u16)?;
+                    out.extend_from_slice(string);
+                }
+                EXTERNALIZED_DESCRIPTOR => {
+                    let descriptor = self.string_bytes(read_compressed_int(&mut reader)?)?;
+                    let indexes_length = read_compressed_int(&mut reader)? as usize;
+                    let mut indexes = vec![0u8; indexes_length];
+                    reader.read_exact(&mut indexes)?;
+                    let mut indexes = Cursor::new(&indexes[..]);
+                    let mut expanded: Vec<u8> = Vec::with_capacity(descriptor.len());
+                    for &c in descriptor {
+                        expanded.push(c);
+                        if c == b'L' && indexes_length > 0 {
+                            let package = self.string_bytes(read_compressed_int(&mut indexes)?)?;
+                            if !package.is_empty() {
+                                expanded.extend_from_slice(package);
+                                expanded.push(b'/');
+                            }
+                            expanded.extend_from_slice(self.string_bytes(read_compressed_int(&mut indexes)?)?);
+                        }
+                    }
+                    out.write_u8(1)?;
+                    out.write_u16::<BigEndian>(expanded.len() as u16)?;
+                    out.write_all(&expanded)?;
+                }
+                1 => {
+                    let length = reader.read_u16::<BigEndian>()?;
+                    out.write_u8(tag)?;
+                    out.write_u16::<BigEndian>(length)?;
+                    let mut string = vec![0u8; length as usize];
+                    reader.read_exact(&mut string)?;
+                    out.write_all(&string)?;
+                }
+                _ => {
+                    let size = match tag {
+                        7 | 8 | 16 | 19 | 20 => 2,
+                        15 => 3,
+                        3 | 4 | 9 | 10 | 11 | 12 | 17 | 18 => 4,
+                        5 | 6 => 8,
+                        _ => return Err(ClassLoadError::UnknownConstantPoolTag(tag))
+                    };
+                    let mut entry = vec![0u8; size];
+                    reader.read_exact(&mut entry)?;
+                    out.write_u8(tag)?;
+                    out.write_all(&entry)?;
+                    // longs and doubles take two constant pool slots
+                    if tag == 5 || tag == 6 {
+                        index += 1;
+                    }
+                }
+            }
+            index += 1;
+        }
+        reader.read_to_end(out)?;
+        Ok(())
+    }
+}
+
+/// Reads an index written by jlink's `CompressIndexes`: a byte with the high bit set holds the
+/// length (1 to 3 bytes) in bits 5-6 and the top five bits of the value, anything else starts a
+/// plain big-endian int.
+fn read_compressed_int<R: Read>(reader: &mut R) -> std::io::Result<u32> {
+    let first = reader.read_u8()?;
+    if first & 0x80 == 0 {
+        let mut rest = [0u8; 3];
+        reader.read_exact(&mut rest)?;
+        return Ok(u32::from_be_bytes([first, rest[0], rest[1], rest[2]]))
+    }
+    let length = (first & 0x60) >> 5;
+    let mut value = (first & 0x1f) as u32;
+    for _ in 1..length {
+        value = value << 8 | reader.read_u8()? as u32;
+    }
+    Ok(value)
+}
@@ -0,0 +1,1305 @@
+//! The Java Native Interface: libraries loaded with `System.load` and `System.loadLibrary`, the
+//! native methods they export as `Java_<class>_<method>` symbols or bind with `RegisterNatives`,
+//! and the `JNIEnv` function table their code calls back into the VM with.
+//!
+//! Calls between Rust and C follow the C calling conventions of x86-64 and AArch64 Linux without
+//! libffi. Both pass integers and pointers in integer registers, `float` and `double` in vector
+//! registers, and what does not fit in 8 byte stack slots in order, so a call can be made with a
+//! fixed number of integer, floating point and stack arguments whatever the method's descriptor;
+//! the callee ignores those it does not declare. The variadic `Call<Type>Method` functions read
+//! their arguments back the same way.
+//!
+//! References handed to native code are [`Handle`]s, released when the native method returns, and
+//! [`GlobalHandle`]s. Exceptions thrown by Java code that native code called, or with `Throw`,
+//! stay pending until the native method returns, then propagate.
+
+use std::collections::HashMap;
+use std::ffi::{c_char, c_void, CStr, CString};
+use std::path::{Path, PathBuf};
+use std::ptr;
+use crate::classpath::SEPARATOR;
+use crate::descriptor::{FieldType, MethodDescriptor};
+use crate::env::VMEnv;
+use crate::interpret::{InterpreterError, JavaException};
+use crate::loader::{AccessFlags, Method};
+use crate::native::{reference, NativeResult};
+use crate::registry::{ClassId, LoaderId};
+use crate::strings;
+use crate::vm::{GlobalHandle, Handle, ObjRef, VMValue};
+
+#[allow(non_camel_case_types)]
+pub type jint = i32;
+#[allow(non_camel_case_types)]
+pub type jlong = i64;
+#[allow(non_camel_case_types)]
+pub type jboolean = u8;
+#[allow(non_camel_case_types)]
+pub type jobject = *mut c_void;
+#[allow(non_camel_case_types)]
+pub type jmethodID = *mut c_void;
+
+/// A JNI argument array element, `jvalue` in `jni.h`.
+#[allow(non_camel_case_types)]
+#[repr(C)]
+#[derive(Copy, Clone)]
+pub union jvalue {
+    pub z: jboolean,
+    pub b: i8,
+    pub c: u16,
+    pub s: i16,
+    pub i: jint,
+    pub j: jlong,
+    pub f: f32,
+    pub d: f64,
+    pub l: jobject
+}
+
+/// An entry of the array `RegisterNatives` takes.
+#[repr(C)]
+pub struct JNINativeMethod {
+    pub name: *const c_char,
+    pub signature: *const c_char,
+    pub fn_ptr: *mut c_void
+}
+
+pub const JNI_OK: jint = 0;
+pub const JNI_ERR: jint = -1;
+pub const JNI_EVERSION: jint = -3;
+pub const JNI_VERSION_1_1: jint = 0x00010001;
+pub const JNI_VERSION_10: jint = 0x000a0000;
+
+/// The versions `JNI_OnLoad` and `GetEnv` may ask for; `GetVersion` answers the latest, that of
+/// JDK 16.
+const SUPPORTED_VERSIONS: [jint; 7] = [0x00010001, 0x00010002, 0x00010004, 0x00010006, 0x00010008, 0x00090000, JNI_VERSION_10];
+
+/// Entries of the `JNIEnv` function table of JDK 16, the four reserved ones included.
+const FUNCTION_COUNT: usize = 234;
+/// Entries of the `JavaVM` function table, the three reserved ones included.
+const INVOKE_FUNCTION_COUNT: usize = 8;
+
+#[cfg(target_arch = "x86_64")]
+const INTEGER_REGISTERS: usize = 6;
+#[cfg(not(target_arch = "x86_64"))]
+const INTEGER_REGISTERS: usize = 8;
+const FLOAT_REGISTERS: usize = 8;
+/// Stack slots passed to every native method; methods needing more cannot be called.
+const STACK_SLOTS: usize = 16;
+
+/// What native code gets as a `JNIEnv *`: a pointer to the function table, followed by the VM
+/// the functions work on.
+#[repr(C)]
+pub struct JniEnv {
+    functions: *const *const c_void,
+    env: *mut VMEnv
+}
+
+/// What native code gets as a `JavaVM *`. There is a single thread, so `GetEnv` always has the
+/// one `JNIEnv` to give.
+#[repr(C)]
+pub struct JavaVm {
+    functions: *const *const c_void,
+    env: *mut JniEnv
+}
+
+/// A library loaded for a class loader. Libraries are never unloaded, like classes.
+pub struct NativeLibrary {
+    pub path: PathBuf,
+    pub loader: LoaderId,
+    handle: *mut c_void
+}
+
+/// An exception raised while native code runs, thrown once it returns.
+enum Pending {
+    Throwable(GlobalHandle),
+    /// An error of the VM itself, which Java code cannot catch.
+    Error(InterpreterError)
+}
+
+/// The JNI state of a [`VMEnv`].
+pub struct Jni {
+    libraries: Vec<NativeLibrary>,
+    /// Native methods linked so far or bound with `RegisterNatives`, by class and method index.
+    bound: HashMap<(ClassId, usize), *const c_void>,
+    /// The loader `FindClass` uses: the defining loader of the class whose native method runs, or
+    /// the loader a library is loaded for while its `JNI_OnLoad` runs.
+    loader: LoaderId,
+    pending: Option<Pending>,
+    /// Where the frames opened by `PushLocalFrame` start.
+    local_frames: Vec<Handle>,
+    env: Box<JniEnv>,
+    java_vm: Box<JavaVm>,
+    _functions: Box<[*const c_void; FUNCTION_COUNT]>,
+    _invoke_functions: Box<[*const c_void; INVOKE_FUNCTION_COUNT]>
+}
+
+impl Default for Jni {
+    fn default() -> Self {
+        let functions = function_table();
+        let invoke_functions = invoke_function_table();
+        let mut env = Box::new(JniEnv { functions: functions.as_ptr(), env: ptr::null_mut() });
+        let java_vm = Box::new(JavaVm { functions: invoke_functions.as_ptr(), env: &mut *env });
+        Jni {
+            libraries: Vec::new(),
+            bound: HashMap::new(),
+            loader: LoaderId::APPLICATION,
+            pending: None,
+            local_frames: Vec::new(),
+            env,
+            java_vm,
+            _functions: functions,
+            _invoke_functions: invoke_functions
+        }
+    }
+}
+
+/// Escapes a class name, method name or descriptor for a native method symbol (JNI spec 2.2.1):
+/// `/` becomes `_`, `_`, `;` and `[` become `_1`, `_2` and `_3`, and characters other than ASCII
+/// letters and digits `_0xxxx`.
+pub fn mangle(name: &str) -> String {
+    let mut mangled = String::with_capacity(name.len());
+    for unit in name.encode_utf16() {
+        match char::from_u32(unit as u32) {
+            Some('/') => mangled.push('_'),
+            Some('_') => mangled.push_str("_1"),
+            Some(';') => mangled.push_str("_2"),
+            Some('[') => mangled.push_str("_3"),
+            Some(c) if c.is_ascii_alphanumeric() => mangled.push(c),
+            _ => mangled.push_str(&format!("_0{:04x}", unit))
+        }
+    }
+    mangled
+}
+
+/// `Java_<class>_<method>`, the symbol a native method is looked up by first.
+pub fn short_name(class_name: &str, method_name: &str) -> String {
+    format!("Java_{}_{}", mangle(class_name), mangle(method_name))
+}
+
+/// The short name followed by `__` and the parameter descriptor, which tells overloads apart.
+pub fn long_name(class_name: &str, method_name: &str, descriptor: &str) -> String {
+    let parameters = descriptor.strip_prefix('(').and_then(|rest| rest.split(')').next()).unwrap_or("");
+    format!("{}__{}", short_name(class_name, method_name), mangle(parameters))
+}
+
+/// `libfoo.so` for `foo`, the file `System.loadLibrary` looks for.
+pub fn map_library_name(name: &str) -> String {
+    format!("{}{}{}", std::env::consts::DLL_PREFIX, name, std::env::consts::DLL_SUFFIX)
+}
+
+#[cfg(unix)]
+mod dl {
+    use std::ffi::{c_char, c_int, c_void};
+
+    pub const RTLD_NOW: c_int = 2;
+
+    #[cfg_attr(target_os = "linux", link(name = "dl"))]
+    extern "C" {
+        pub fn dlopen(filename: *const c_char, flags: c_int) -> *mut c_void;
+        pub fn dlsym(handle: *mut c_void, symbol: *const c_char) -> *mut c_void;
+        pub fn dlerror() -> *mut c_char;
+    }
+}
+
+const SUPPORTED_PLATFORM: bool = cfg!(all(unix, any(target_arch = "x86_64", target_arch = "aarch64")));
+
+#[cfg(unix)]
+fn open_library(path: &Path) -> Result<*mut c_void, String> {
+    use std::os::unix::ffi::OsStrExt;
+    let path = CString::new(path.as_os_str().as_bytes()).map_err(|_| "invalid file name".to_string())?;
+    // SAFETY: dlopen and dlerror are given NUL terminated strings and return NULL or valid ones.
+    unsafe {
+        let handle = dl::dlopen(path.as_ptr(), dl::RTLD_NOW);
+        if handle.is_null() {
+            let error = dl::dlerror();
+            return Err(if error.is_null() { "cannot open shared object file".to_string() } else { CStr::from_ptr(error).to_string_lossy().into_owned() })
+        }
+        Ok(handle)
+    }
+}
+
+#[cfg(not(unix))]
+fn open_library(_path: &Path) -> Result<*mut c_void, String> {
+    Err("JNI libraries are not supported on this platform".to_string())
+}
+
+#[cfg(unix)]
+fn find_symbol(library: &NativeLibrary, name: &str) -> Option<*const c_void> {
+    let name = CString::new(name).ok()?;
+    // SAFETY: the handle came from dlopen and is never closed.
+    let symbol = unsafe { dl::dlsym(library.handle, name.as_ptr()) };
+    (!symbol.is_null()).then_some(symbol as *const c_void)
+}
+
+#[cfg(not(unix))]
+fn find_symbol(_library: &NativeLibrary, _name: &str) -> Option<*const c_void> {
+    None
+}
+
+/// A Java value as a C argument.
+enum CValue {
+    Integer(u64),
+    /// A `double`, or a `float` in the low half.
+    Float(f64)
+}
+
+impl CValue {
+    /// Converts an argument of a native method; references become local references.
+    fn of(env: &mut VMEnv, field_type: &FieldType, value: VMValue) -> Result<CValue, InterpreterError> {
+        Ok(match field_type {
+            FieldType::Long => CValue::Integer(value.as_long()? as u64),
+            FieldType::Float => CValue::Float(f64::from_bits(value.as_float()?.to_bits() as u64)),
+            FieldType::Double => CValue::Float(value.as_double()?),
+            FieldType::Object(_) | FieldType::Array(_) => CValue::Integer(env.new_local_ref(value.as_reference()?) as u64),
+            _ => CValue::Integer(value.as_int()? as i64 as u64)
+        })
+    }
+}
+
+/// The arguments of a call into C, assigned to registers and stack slots as the C calling
+/// convention does.
+#[derive(Default)]
+struct CallArgs {
+    integers: Vec<u64>,
+    floats: Vec<f64>,
+    stack: Vec<u64>
+}
+
+impl CallArgs {
+    fn integer(&mut self, value: u64) {
+        if self.integers.len() < INTEGER_REGISTERS {
+            self.integers.push(value);
+        } else {
+            self.stack.push(value);
+        }
+    }
+
+    /// A `double`, or a `float` in the low half of the register.
+    fn float(&mut self, value: f64) {
+        if self.floats.len() < FLOAT_REGISTERS {
+            self.floats.push(value);
+        } else {
+            self.stack.push(value.to_bits());
+        }
+    }
+
+    fn push(&mut self, value: CValue) {
+        match value {
+            CValue::Integer(value) => self.integer(value),
+            CValue::Float(value) => self.float(value)
+        }
+    }
+
+    /// Calls `function` with these arguments. `R` is `u64` for functions returning integers or
+    /// pointers and `f64` for those returning `float` or `double`.
+    ///
+    /// # Safety
+    /// `function` must be a C function taking at most these arguments, in this order.
+    unsafe fn call<R: Copy>(&self, function: *const c_void) -> Result<R, InterpreterError> {
+        if !SUPPORTED_PLATFORM || self.stack.len() > STACK_SLOTS {
+            return Err(InterpreterError::exception("java/lang/UnsatisfiedLinkError", "too many arguments for a native method"))
+        }
+        let mut i = [0u64; INTEGER_REGISTERS];
+        i[..self.integers.len()].copy_from_slice(&self.integers);
+        let mut f = [0f64; FLOAT_REGISTERS];
+        f[..self.floats.len()].copy_from_slice(&self.floats);
+        let mut s = [0u64; STACK_SLOTS];
+        s[..self.stack.len()].copy_from_slice(&self.stack);
+        Ok(call_raw(function, &i, &f, &s))
+    }
+}
+
+#[cfg(target_arch = "x86_64")]
+unsafe fn call_raw<R: Copy>(function: *const c_void, i: &[u64; INTEGER_REGISTERS], f: &[f64; FLOAT_REGISTERS], s: &[u64; STACK_SLOTS]) -> R {
+    type Raw<R> = unsafe extern "C" fn(u64, u64, u64, u64, u64, u64, f64, f64, f64, f64, f64, f64, f64, f64,
+                                       u64, u64, u64, u64, u64, u64, u64, u64, u64, u64, u64, u64, u64, u64, u64, u64) -> R;
+    let function: Raw<R> = std::mem::transmute(function);
+    function(i[0], i[1], i[2], i[3], i[4], i[5], f[0], f[1], f[2], f[3], f[4], f[5], f[6], f[7],
+             s[0], s[1], s[2], s[3], s[4], s[5], s[6], s[7], s[8], s[9], s[10], s[11], s[12], s[13], s[14], s[15])
+}
+
+#[cfg(not(target_arch = "x86_64"))]
+unsafe fn call_raw<R: Copy>(function: *const c_void, i: &[u64; INTEGER_REGISTERS], f: &[f64; FLOAT_REGISTERS], s: &[u64; STACK_SLOTS]) -> R {
+    type Raw<R> = unsafe extern "C" fn(u64, u64, u64, u64, u64, u64, u64, u64, f64, f64, f64, f64, f64, f64, f64, f64,
+                                       u64, u64, u64, u64, u64, u64, u64, u64, u64, u64, u64, u64, u64, u64, u64, u64) -> R;
+    let function: Raw<R> = std::mem::transmute(function);
+    function(i[0], i[1], i[2], i[3], i[4], i[5], i[6], i[7], f[0], f[1], f[2], f[3], f[4], f[5], f[6], f[7],
+             s[0], s[1], s[2], s[3], s[4], s[5], s[6], s[7], s[8], s[9], s[10], s[11], s[12], s[13], s[14], s[15])
+}
+
+/// The variadic arguments of a JNI function, read from the registers and stack slots the caller
+/// passed them in. C promotes `float` arguments to `double` and smaller integers to `int`.
+struct VarArgs {
+    integers: std::vec::IntoIter<u64>,
+    floats: std::vec::IntoIter<f64>,
+    stack: std::vec::IntoIter<u64>
+}
+
+impl VarArgs {
+    fn integer(&mut self) -> u64 {
+        self.integers.next().or_else(|| self.stack.next()).unwrap_or(0)
+    }
+
+    fn float(&mut self) -> f64 {
+        self.floats.next().unwrap_or_else(|| f64::from_bits(self.stack.next().unwrap_or(0)))
+    }
+}
+
+/// Where the Java arguments of a `Call<Type>Method` function come from.
+enum Arguments {
+    VarArgs(VarArgs),
+    Array(*const jvalue)
+}
+
+impl Arguments {
+    /// Reads one argument per parameter of `descriptor`.
+    ///
+    /// # Safety
+    /// An array must have an element for each parameter.
+    unsafe fn read(self, env: &VMEnv, descriptor: &MethodDescriptor) -> Vec<VMValue> {
+        let mut values = Vec::with_capacity(descriptor.parameters.len());
+        match self {
+            Arguments::VarArgs(mut args) => for parameter in &descriptor.parameters {
+                values.push(match parameter {
+                    FieldType::Boolean => VMValue::Int((args.integer() as u32 != 0) as i32),
+                    FieldType::Byte => VMValue::Int(args.integer() as i8 as i32),
+                    FieldType::Char => VMValue::Int(args.integer() as u16 as i32),
+                    FieldType::Short => VMValue::Int(args.integer() as i16 as i32),
+                    FieldType::Int => VMValue::Int(args.integer() as i32),
+                    FieldType::Long => VMValue::Long(args.integer() as i64),
+                    FieldType::Float => VMValue::Float(args.float() as f32),
+                    FieldType::Double => VMValue::Double(args.float()),
+                    FieldType::Object(_) | FieldType::Array(_) => env.resolve_ref(args.integer() as jobject).map_or(VMValue::Null, VMValue::Reference)
+                });
+            }
+            Arguments::Array(args) => for (index, parameter) in descriptor.parameters.iter().enumerate() {
+                let arg = *args.add(index);
+                values.push(match parameter {
+                    FieldType::Boolean => VMValue::Int((arg.z != 0) as i32),
+                    FieldType::Byte => VMValue::Int(arg.b as i32),
+                    FieldType::Char => VMValue::Int(arg.c as i32),
+                    FieldType::Short => VMValue::Int(arg.s as i32),
+                    FieldType::Int => VMValue::Int(arg.i),
+                    FieldType::Long => VMValue::Long(arg.j),
+                    FieldType::Float => VMValue::Float(arg.f),
+                    FieldType::Double => VMValue::Double(arg.d),
+                    FieldType::Object(_) | FieldType::Array(_) => env.resolve_ref(arg.l).map_or(VMValue::Null, VMValue::Reference)
+                });
+            }
+        }
+        values
+    }
+}
+
+/// How a `Call<Type>Method` function selects the method it runs.
+#[derive(Copy, Clone)]
+enum CallKind {
+    /// Dispatched on the class of the receiver.
+    Virtual,
+    Static
+}
+
+/// The result types of the `Call<Type>Method` functions.
+trait JniValue: Sized {
+    const ZERO: Self;
+
+    fn from_value(env: &mut VMEnv, value: Option<VMValue>) -> Self;
+}
+
+macro_rules! jni_values {
+    ($($type:ty: $value:pat => $converted:expr),* $(,)?) => {$(
+        impl JniValue for $type {
+            const ZERO: Self = 0 as $type;
+
+            fn from_value(_: &mut VMEnv, value: Option<VMValue>) -> Self {
+                match value {
+                    Some($value) => $converted,
+                    _ => 0 as $type
+                }
+            }
+        }
+    )*};
+}
+
+jni_values!(
+    jboolean: VMValue::Int(v) => (v != 0) as jboolean,
+    i8: VMValue::Int(v) => v as i8,
+    u16: VMValue::Int(v) => v as u16,
+    i16: VMValue::Int(v) => v as i16,
+    jint: VMValue::Int(v) => v,
+    jlong: VMValue::Long(v) => v,
+    f32: VMValue::Float(v) => v,
+    f64: VMValue::Double(v) => v
+);
+
+impl JniValue for () {
+    const ZERO: Self = ();
+
+    fn from_value(_: &mut VMEnv, _: Option<VMValue>) -> Self {}
+}
+
+impl JniValue for jobject {
+    const ZERO: Self = ptr::null_mut();
+
+    fn from_value(env: &mut VMEnv, value: Option<VMValue>) -> Self {
+        match value {
+            Some(VMValue::Reference(obj)) => env.new_local_ref(Some(obj)),
+            _ => ptr::null_mut()
+        }
+    }
+}
+
+/// A method as a `jmethodID`: its class and index, offset so that it is never null.
+fn method_id(class: ClassId, index: usize) -> jmethodID {
+    ((((class.0 as usize) << 32) | index) + 1) as jmethodID
+}
+
+fn method_of(method: jmethodID) -> (ClassId, usize) {
+    let bits = method as usize - 1;
+    (ClassId((bits >> 32) as u32), bits & 0xffff_ffff)
+}
+
+impl VMEnv {
+    /// The JNI libraries loaded so far.
+    pub fn native_libraries(&self) -> &[NativeLibrary] {
+        &self.jni.libraries
+    }
+
+    /// Loads a JNI library for `loader` and runs its `JNI_OnLoad`. Loading a library again for the
+    /// same loader does nothing.
+    pub fn load_library(&mut self, path: &Path, loader: LoaderId) -> Result<(), InterpreterError> {
+        let display = path.display().to_string();
+        if let Some(library) = self.jni.libraries.iter().find(|library| library.path == path) {
+            if library.loader == loader {
+                return Ok(())
+            }
+            return Err(InterpreterError::exception("java/lang/UnsatisfiedLinkError", format!("Native Library {} already loaded in another classloader", display)))
+        }
+        let handle = open_library(path).map_err(|error| InterpreterError::exception("java/lang/UnsatisfiedLinkError", format!("{}: {}", display, error)))?;
+        let library = NativeLibrary { path: path.to_path_buf(), loader, handle };
+        if let Some(on_load) = find_symbol(&library, "JNI_OnLoad") {
+            let java_vm: *mut JavaVm = &mut *self.jni.java_vm;
+            let version = self.enter_native(loader, |_, _| {
+                let mut args = CallArgs::default();
+                args.integer(java_vm as u64);
+                args.integer(0);
+                // SAFETY: JNI_OnLoad takes a JavaVM * and a reserved pointer and returns a jint.
+                Ok(unsafe { args.call::<u64>(on_load)? } as jint)
+            })?;
+            if !SUPPORTED_VERSIONS.contains(&version) {
+                return Err(InterpreterError::exception("java/lang/UnsatisfiedLinkError", format!("unsupported JNI version 0x{:08X} required by {}", version, display)))
+            }
+        }
+        self.jni.libraries.push(library);
+        Ok(())
+    }
+
+    /// Finds the C function of a native method: the one bound with `RegisterNatives`, or the
+    /// symbol exported under its short or long name by a library of its class's loader.
+    pub(crate) fn find_jni_function(&mut self, class: ClassId, index: usize) -> Option<*const c_void> {
+        if let Some(function) = self.jni.bound.get(&(class, index)) {
+            return Some(*function)
+        }
+        let runtime_class = self.vm().classes.class(class);
+        let method = &runtime_class.class.methods[index];
+        let names = [short_name(&runtime_class.name, &method.method_name), long_name(&runtime_class.name, &method.method_name, &method.descriptor)];
+        let function = self.jni.libraries.iter()
+            .filter(|library| library.loader == runtime_class.defining_loader)
+            .find_map(|library| names.iter().find_map(|name| find_symbol(library, name)))?;
+        self.jni.bound.insert((class, index), function);
+        Some(function)
+    }
+
+    /// Calls the C function of a native method with the `JNIEnv`, the class or receiver, and the
+    /// arguments.
+    pub(crate) fn invoke_jni(&mut self, function: *const c_void, class: ClassId, method: &Method, args: &[VMValue]) -> NativeResult {
+        let descriptor = MethodDescriptor::parse(&method.descriptor)?;
+        let loader = self.vm().classes.class(class).defining_loader;
+        self.enter_native(loader, |env, jni_env| {
+            let mut call = CallArgs::default();
+            call.integer(jni_env as u64);
+            let mut args = args.iter().copied();
+            // The arguments become local references before the mirror is created, which may move them.
+            let receiver = if method.is_static() { None } else { Some(args.next().unwrap_or(VMValue::Null)) };
+            let values: Vec<CValue> = descriptor.parameters.iter().zip(args)
+                .map(|(parameter, arg)| CValue::of(env, parameter, arg))
+                .collect::<Result<_, _>>()?;
+            let target = match receiver {
+                Some(receiver) => env.new_local_ref(receiver.as_reference()?),
+                None => {
+                    let mirror = env.vm_mut().class_mirror(class)?;
+                    env.new_local_ref(Some(mirror))
+                }
+            };
+            call.integer(target as u64);
+            for value in values {
+                call.push(value);
+            }
+            // SAFETY: the function is the implementation of a native method of this descriptor.
+            Ok(match &descriptor.return_type {
+                None => {
+                    unsafe { call.call::<u64>(function)? };
+                    None
+                }
+                Some(FieldType::Float) => Some(VMValue::Float(f32::from_bits(unsafe { call.call::<f64>(function)? }.to_bits() as u32))),
+                Some(FieldType::Double) => Some(VMValue::Double(unsafe { call.call::<f64>(function)? })),
+                Some(return_type) => {
+                    let raw = unsafe { call.call::<u64>(function)? };
+                    Some(match return_type {
+                        FieldType::Boolean => VMValue::Int((raw as u8 != 0) as i32),
+                        FieldType::Byte => VMValue::Int(raw as i8 as i32),
+                        FieldType::Char => VMValue::Int(raw as u16 as i32),
+                        FieldType::Short => VMValue::Int(raw as i16 as i32),
+                        FieldType::Long => VMValue::Long(raw as i64),
+                        FieldType::Object(_) | FieldType::Array(_) => env.resolve_ref(raw as jobject).map_or(VMValue::Null, VMValue::Reference),
+                        _ => VMValue::Int(raw as i32)
+                    })
+                }
+            })
+        })
+    }
+
+    /// Runs native code with the JNI state set up for it: `loader` for `FindClass`, a scope for its
+    /// local references and no exception pending. An exception left pending is thrown afterwards.
+    fn enter_native<R>(&mut self, loader: LoaderId, call: impl FnOnce(&mut VMEnv, *mut JniEnv) -> Result<R, InterpreterError>) -> Result<R, InterpreterError> {
+        let env: *mut VMEnv = self;
+        self.jni.env.env = env;
+        let jni_env: *mut JniEnv = &mut *self.jni.env;
+        let outer_loader = std::mem::replace(&mut self.jni.loader, loader);
+        let outer_pending = self.jni.pending.take();
+        let local_frames = self.jni.local_frames.len();
+        let scope = self.vm().handle_scope();
+        let result = call(self, jni_env);
+        let pending = std::mem::replace(&mut self.jni.pending, outer_pending);
+        self.jni.loader = outer_loader;
+        self.jni.local_frames.truncate(local_frames);
+        self.vm_mut().release_handles(scope);
+        match pending {
+            Some(Pending::Throwable(handle)) => {
+                let throwable = self.vm().global_handle(handle);
+                self.vm_mut().delete_global_handle(handle);
+                match throwable {
+                    Some(throwable) => Err(InterpreterError::Exception(self.java_exception(throwable)?)),
+                    None => result
+                }
+            }
+            Some(Pending::Error(error)) => Err(error),
+            None => result
+        }
+    }
+
+    /// Makes an error raised in a JNI function pending; Java exceptions get their object.
+    fn set_pending(&mut self, error: InterpreterError) {
+        let pending = match error {
+            InterpreterError::Exception(mut exception) => {
+                if exception.stack_trace.is_empty() {
+                    exception.stack_trace = self.stack_trace();
+                }
+                match self.throwable(&mut exception) {
+                    Ok(throwable) => Pending::Throwable(self.vm_mut().new_global_handle(throwable)),
+                    Err(error) => Pending::Error(error)
+                }
+            }
+            error => Pending::Error(error)
+        };
+        if let Some(Pending::Throwable(handle)) = self.jni.pending.replace(pending) {
+            self.vm_mut().delete_global_handle(handle);
+        }
+    }
+
+    fn pending_throwable(&self) -> Option<ObjRef> {
+        match &self.jni.pending {
+            Some(Pending::Throwable(handle)) => self.vm().global_handle(*handle),
+            _ => None
+        }
+    }
+
+    fn clear_pending(&mut self) {
+        if let Some(Pending::Throwable(handle)) = self.jni.pending.take() {
+            self.vm_mut().delete_global_handle(handle);
+        }
+    }
+
+    /// A new local reference, as native code sees objects: a handle index shifted left, so that
+    /// the low bit tells global references apart. Null stays null.
+    pub fn new_local_ref(&mut self, obj: Option<ObjRef>) -> jobject {
+        match obj {
+            Some(obj) => ((self.vm_mut().new_handle(obj).0 + 1) << 1) as jobject,
+            None => ptr::null_mut()
+        }
+    }
+
+    pub fn new_global_ref(&mut self, obj: Option<ObjRef>) -> jobject {
+        match obj {
+            Some(obj) => (((self.vm_mut().new_global_handle(obj).0 + 1) << 1) | 1) as jobject,
+            None => ptr::null_mut()
+        }
+    }
+
+    /// The object of a local or global reference; `None` for null and for deleted references.
+    pub fn resolve_ref(&self, reference: jobject) -> Option<ObjRef> {
+        let bits = reference as usize;
+        if bits == 0 {
+            return None
+        }
+        let index = (bits >> 1) - 1;
+        if bits & 1 == 1 {
+            self.vm().global_handle(GlobalHandle(index))
+        } else if index < self.vm().handle_scope().0 {
+            Some(self.vm().handle(Handle(index))).filter(|obj| obj.0 != 0)
+        } else {
+            None
+        }
+    }
+
+    fn resolve_class(&self, class: jobject) -> Result<ClassId, InterpreterError> {
+        match self.resolve_ref(class) {
+            Some(mirror) => self.vm().mirrored_class(mirror),
+            None => Err(InterpreterError::Exception(JavaException::new("java/lang/NullPointerException", None)))
+        }
+    }
+
+    fn resolve_object(&self, obj: jobject) -> Result<ObjRef, InterpreterError> {
+        self.resolve_ref(obj).ok_or_else(|| InterpreterError::Exception(JavaException::new("java/lang/NullPointerException", None)))
+    }
+
+    /// Runs the method of a `Call<Type>Method` or `NewObject` function.
+    ///
+    /// # Safety
+    /// The arguments must match the method's descriptor.
+    unsafe fn call_java(&mut self, kind: CallKind, target: jobject, method: jmethodID, args: Arguments) -> Result<Option<VMValue>, InterpreterError> {
+        let (class, index) = method_of(method);
+        let class_file = self.vm().classes.class(class).class.clone();
+        let method = &class_file.methods[index];
+        let descriptor = MethodDescriptor::parse(&method.descriptor)?;
+        let mut values = args.read(self, &descriptor);
+        match kind {
+            CallKind::Static => {
+                self.initialize_class(class)?;
+                self.invoke(class, index, values)
+            }
+            CallKind::Virtual => {
+                let receiver = self.resolve_object(target)?;
+                let (class, index) = match method.method_name.as_str() {
+                    "<init>" => (class, index),
+                    name => self.vm().classes.select_method(self.vm().heap.class_of(receiver), name, &method.descriptor)
+                        .ok_or_else(|| InterpreterError::exception("java/lang/AbstractMethodError", name))?
+                };
+                values.insert(0, VMValue::Reference(receiver));
+                self.invoke(class, index, values)
+            }
+        }
+    }
+
+    fn method_id(&mut self, class: jobject, name: *const c_char, signature: *const c_char, is_static: bool) -> Result<jmethodID, InterpreterError> {
+        let class = self.resolve_class(class)?;
+        // SAFETY: JNI passes NUL terminated Modified UTF-8 strings.
+        let (name, signature) = unsafe { (c_string(name)?, c_string(signature)?) };
+        self.initialize_class(class)?;
+        match self.vm().classes.find_method(class, &name, &signature) {
+            Some((declaring, index)) if self.vm().classes.class(declaring).class.methods[index].is_static() == is_static => Ok(method_id(declaring, index)),
+            _ => Err(InterpreterError::exception("java/lang/NoSuchMethodError", name))
+        }
+    }
+}
+
+/// Reads a NUL terminated Modified UTF-8 string from native code.
+///
+/// # Safety
+/// `string` must be null or point to a NUL terminated string.
+unsafe fn c_string(string: *const c_char) -> Result<String, InterpreterError> {
+    if string.is_null() {
+        return Err(InterpreterError::Exception(JavaException::new("java/lang/NullPointerException", None)))
+    }
+    let bytes = CStr::from_ptr(string).to_bytes();
+    Ok(strings::modified_utf8_to_string(bytes).unwrap_or_else(|| String::from_utf8_lossy(bytes).into_owned()))
+}
+
+/// Runs the body of a JNI function. An error makes an exception pending and the function
+/// returns `default`.
+///
+/// # Safety
+/// `env` must be the `JNIEnv` the VM passed to the running native code.
+unsafe fn jni_function<T>(env: *mut JniEnv, default: T, body: impl FnOnce(&mut VMEnv) -> Result<T, InterpreterError>) -> T {
+    let env = &mut *(*env).env;
+    match body(env) {
+        Ok(value) => value,
+        Err(error) => {
+            env.set_pending(error);
+            default
+        }
+    }
+}
+
+unsafe fn call_method<T: JniValue>(env: *mut JniEnv, kind: CallKind, target: jobject, method: jmethodID, args: Arguments) -> T {
+    jni_function(env, T::ZERO, |env| {
+        let value = env.call_java(kind, target, method, args)?;
+        Ok(T::from_value(env, value))
+    })
+}
+
+unsafe fn new_object(env: *mut JniEnv, class: jobject, constructor: jmethodID, args: Arguments) -> jobject {
+    jni_function(env, ptr::null_mut(), |env| {
+        let class = env.resolve_class(class)?;
+        env.initialize_class(class)?;
+        let obj = env.vm_mut().new_object(class)?;
+        let obj = env.new_local_ref(Some(obj));
+        env.call_java(CallKind::Virtual, obj, constructor, args)?;
+        Ok(obj)
+    })
+}
+
+/// Defines a variadic JNI function taking two fixed arguments after the `JNIEnv`, with parameters
+/// for the registers and stack slots its variadic arguments may be in.
+#[cfg(target_arch = "x86_64")]
+macro_rules! variadic {
+    ($name:ident($env:ident, $target:ident, $method:ident, $args:ident) -> $type:ty $body:block) => {
+        #[allow(clippy::too_many_arguments)]
+        unsafe extern "C" fn $name($env: *mut JniEnv, $target: jobject, $method: jmethodID, i3: u64, i4: u64, i5: u64,
+                                   f0: f64, f1: f64, f2: f64, f3: f64, f4: f64, f5: f64, f6: f64, f7: f64,
+                                   s0: u64, s1: u64, s2: u64, s3: u64, s4: u64, s5: u64, s6: u64, s7: u64,
+                                   s8: u64, s9: u64, s10: u64, s11: u64, s12: u64, s13: u64, s14: u64, s15: u64) -> $type {
+            let $args = Arguments::VarArgs(VarArgs {
+                integers: vec![i3, i4, i5].into_iter(),
+                floats: vec![f0, f1, f2, f3, f4, f5, f6, f7].into_iter(),
+                stack: vec![s0, s1, s2, s3, s4, s5, s6, s7, s8, s9, s10, s11, s12, s13, s14, s15].into_iter()
+            });
+            $body
+        }
+    };
+}
+
+#[cfg(not(target_arch = "x86_64"))]
+macro_rules! variadic {
+    ($name:ident($env:ident, $target:ident, $method:ident, $args:ident) -> $type:ty $body:block) => {
+        #[allow(clippy::too_many_arguments)]
+        unsafe extern "C" fn $name($env: *mut JniEnv, $target: jobject, $method: jmethodID, i3: u64, i4: u64, i5: u64, i6: u64, i7: u64,
+                                   f0: f64, f1: f64, f2: f64, f3: f64, f4: f64, f5: f64, f6: f64, f7: f64,
+                                   s0: u64, s1: u64, s2: u64, s3: u64, s4: u64, s5: u64, s6: u64, s7: u64,
+                                   s8: u64, s9: u64, s10: u64, s11: u64, s12: u64, s13: u64, s14: u64, s15: u64) -> $type {
+            let $args = Arguments::VarArgs(VarArgs {
+                integers: vec![i3, i4, i5, i6, i7].into_iter(),
+                floats: vec![f0, f1, f2, f3, f4, f5, f6, f7].into_iter(),
+                stack: vec![s0, s1, s2, s3, s4, s5, s6, s7, s8, s9, s10, s11, s12, s13, s14, s15].into_iter()
+            });
+            $body
+        }
+    };
+}
+
+/// Defines `Call<Type>Method`, `Call<Type>MethodA`, `CallStatic<Type>Method` and
+/// `CallStatic<Type>MethodA` for each type, with the table slot of the first; the static ones
+/// are 80 slots further. The `va_list` forms in between are not supported.
+macro_rules! call_functions {
+    ($($slot:literal $type:ty: $call:ident $call_a:ident $call_static:ident $call_static_a:ident;)*) => {
+        $(
+            variadic!($call(env, target, method, args) -> $type { call_method(env, CallKind::Virtual, target, method, args) });
+            unsafe extern "C" fn $call_a(env: *mut JniEnv, target: jobject, method: jmethodID, args: *const jvalue) -> $type {
+                call_method(env, CallKind::Virtual, target, method, Arguments::Array(args))
+            }
+            variadic!($call_static(env, class, method, args) -> $type { call_method(env, CallKind::Static, class, method, args) });
+            unsafe extern "C" fn $call_static_a(env: *mut JniEnv, class: jobject, method: jmethodID, args: *const jvalue) -> $type {
+                call_method(env, CallKind::Static, class, method, Arguments::Array(args))
+            }
+        )*
+
+        fn call_function_slots() -> Vec<(usize, *const c_void)> {
+            vec![$(
+                ($slot, $call as *const c_void),
+                ($slot + 2, $call_a as *const c_void),
+                ($slot + 80, $call_static as *const c_void),
+                ($slot + 82, $call_static_a as *const c_void)
+            ),*]
+        }
+    };
+}
+
+call_functions! {
+    34 jobject: call_object_method call_object_method_a call_static_object_method call_static_object_method_a;
+    37 jboolean: call_boolean_method call_boolean_method_a call_static_boolean_method call_static_boolean_method_a;
+    40 i8: call_byte_method call_byte_method_a call_static_byte_method call_static_byte_method_a;
+    43 u16: call_char_method call_char_method_a call_static_char_method call_static_char_method_a;
+    46 i16: call_short_method call_short_method_a call_static_short_method call_static_short_method_a;
+    49 jint: call_int_method call_int_method_a call_static_int_method call_static_int_method_a;
+    52 jlong: call_long_method call_long_method_a call_static_long_method call_static_long_method_a;
+    55 f32: call_float_method call_float_method_a call_static_float_method call_static_float_method_a;
+    58 f64: call_double_method call_double_method_a call_static_double_method call_static_double_method_a;
+    61 (): call_void_method call_void_method_a call_static_void_method call_static_void_method_a;
+}
+
+variadic!(new_object_variadic(env, class, constructor, args) -> jobject { new_object(env, class, constructor, args) });
+
+unsafe extern "C" fn new_object_a(env: *mut JniEnv, class: jobject, constructor: jmethodID, args: *const jvalue) -> jobject {
+    new_object(env, class, constructor, Arguments::Array(args))
+}
+
+unsafe extern "C" fn get_version(_env: *mut JniEnv) -> jint {
+    JNI_VERSION_10
+}
+
+unsafe extern "C" fn find_class(env: *mut JniEnv, name: *const c_char) -> jobject {
+    jni_function(env, ptr::null_mut(), |env| {
+        let name = c_string(name)?;
+        let class = env.class_for_name(env.jni.loader, &name, true)?;
+        let mirror = env.vm_mut().class_mirror(class)?;
+        Ok(env.new_local_ref(Some(mirror)))
+    })
+}
+
+unsafe extern "C" fn get_superclass(env: *mut JniEnv, class: jobject) -> jobject {
+    jni_function(env, ptr::null_mut(), |env| {
+        let class = env.resolve_class(class)?;
+        match env.vm().classes.class(class).super_class.filter(|_| !env.vm().classes.class(class).is_interface()) {
+            Some(super_class) => {
+                let mirror = env.vm_mut().class_mirror(super_class)?;
+                Ok(env.new_local_ref(Some(mirror)))
+            }
+            None => Ok(ptr::null_mut())
+        }
+    })
+}
+
+unsafe extern "C" fn is_assignable_from(env: *mut JniEnv, from: jobject, to: jobject) -> jboolean {
+    jni_function(env, 0, |env| Ok(env.vm().classes.is_assignable(env.resolve_class(from)?, env.resolve_class(to)?) as jboolean))
+}
+
+unsafe extern "C" fn throw(env: *mut JniEnv, throwable: jobject) -> jint {
+    jni_function(env, JNI_ERR, |env| {
+        let throwable = env.resolve_object(throwable)?;
+        let handle = env.vm_mut().new_global_handle(throwable);
+        env.clear_pending();
+        env.jni.pending = Some(Pending::Throwable(handle));
+        Ok(JNI_OK)
+    })
+}
+
+unsafe extern "C" fn throw_new(env: *mut JniEnv, class: jobject, message: *const c_char) -> jint {
+    jni_function(env, JNI_ERR, |env| {
+        let class_id = env.resolve_class(class)?;
+        let constructor = env.vm().classes.class(class_id).class.methods.iter()
+            .position(|m| m.method_name == "<init>" && m.descriptor == "(Ljava/lang/String;)V")
+            .ok_or_else(|| InterpreterError::exception("java/lang/NoSuchMethodError", "<init>"))?;
+        let message = match message.is_null() {
+            true => jvalue { l: ptr::null_mut() },
+            false => {
+                let message = env.vm_mut().new_string(&c_string(message)?)?;
+                jvalue { l: env.new_local_ref(Some(message)) }
+            }
+        };
+        env.initialize_class(class_id)?;
+        let throwable = env.vm_mut().new_object(class_id)?;
+        let throwable = env.new_local_ref(Some(throwable));
+        env.call_java(CallKind::Virtual, throwable, method_id(class_id, constructor), Arguments::Array(&message))?;
+        let throwable = env.resolve_object(throwable)?;
+        let handle = env.vm_mut().new_global_handle(throwable);
+        env.clear_pending();
+        env.jni.pending = Some(Pending::Throwable(handle));
+        Ok(JNI_OK)
+    })
+}
+
+unsafe extern "C" fn exception_occurred(env: *mut JniEnv) -> jobject {
+    jni_function(env, ptr::null_mut(), |env| {
+        let throwable = env.pending_throwable();
+        Ok(env.new_local_ref(throwable))
+    })
+}
+
+unsafe extern "C" fn exception_describe(env: *mut JniEnv) {
+    jni_function(env, (), |env| {
+        if let Some(throwable) = env.pending_throwable() {
+            env.clear_pending();
+            eprint!("Exception in thread \"main\" {}", env.java_exception(throwable)?.stack_trace_text());
+        }
+        Ok(())
+    })
+}
+
+unsafe extern "C" fn exception_clear(env: *mut JniEnv) {
+    jni_function(env, (), |env| {
+        env.clear_pending();
+        Ok(())
+    })
+}
+
+unsafe extern "C" fn exception_check(env: *mut JniEnv) -> jboolean {
+    jni_function(env, 0, |env| Ok(env.jni.pending.is_some() as jboolean))
+}
+
+unsafe extern "C" fn fatal_error(_env: *mut JniEnv, message: *const c_char) {
+    let message = c_string(message).unwrap_or_default();
+    eprintln!("FATAL ERROR in native method: {}", message);
+    std::process::abort()
+}
+
+unsafe extern "C" fn unsupported() {
+    eprintln!("FATAL ERROR in native method: unsupported JNI function");
+    std::process::abort()
+}
+
+unsafe extern "C" fn push_local_frame(env: *mut JniEnv, _capacity: jint) -> jint {
+    jni_function(env, JNI_ERR, |env| {
+        let scope = env.vm().handle_scope();
+        env.jni.local_frames.push(scope);
+        Ok(JNI_OK)
+    })
+}
+
+unsafe extern "C" fn pop_local_frame(env: *mut JniEnv, result: jobject) -> jobject {
+    jni_function(env, ptr::null_mut(), |env| {
+        let result = env.resolve_ref(result);
+        if let Some(scope) = env.jni.local_frames.pop() {
+            env.vm_mut().release_handles(scope);
+        }
+        Ok(env.new_local_ref(result))
+    })
+}
+
+unsafe extern "C" fn new_global_ref(env: *mut JniEnv, obj: jobject) -> jobject {
+    jni_function(env, ptr::null_mut(), |env| {
+        let obj = env.resolve_ref(obj);
+        Ok(env.new_global_ref(obj))
+    })
+}
+
+unsafe extern "C" fn delete_global_ref(env: *mut JniEnv, global: jobject) {
+    jni_function(env, (), |env| {
+        let bits = global as usize;
+        if bits & 1 == 1 {
+            env.vm_mut().delete_global_handle(GlobalHandle((bits >> 1) - 1));
+        }
+        Ok(())
+    })
+}
+
+unsafe extern "C" fn delete_local_ref(env: *mut JniEnv, local: jobject) {
+    jni_function(env, (), |env| {
+        let bits = local as usize;
+        if bits != 0 && bits & 1 == 0 && (bits >> 1) - 1 < env.vm().handle_scope().0 {
+            env.vm_mut().clear_handle(Handle((bits >> 1) - 1));
+        }
+        Ok(())
+    })
+}
+
+unsafe extern "C" fn is_same_object(env: *mut JniEnv, a: jobject, b: jobject) -> jboolean {
+    jni_function(env, 0, |env| Ok((env.resolve_ref(a) == env.resolve_ref(b)) as jboolean))
+}
+
+unsafe extern "C" fn new_local_ref(env: *mut JniEnv, obj: jobject) -> jobject {
+    jni_function(env, ptr::null_mut(), |env| {
+        let obj = env.resolve_ref(obj);
+        Ok(env.new_local_ref(obj))
+    })
+}
+
+unsafe extern "C" fn ensure_local_capacity(_env: *mut JniEnv, _capacity: jint) -> jint {
+    JNI_OK
+}
+
+unsafe extern "C" fn alloc_object(env: *mut JniEnv, class: jobject) -> jobject {
+    jni_function(env, ptr::null_mut(), |env| {
+        let class = env.resolve_class(class)?;
+        let runtime_class = env.vm().classes.class(class);
+        if runtime_class.is_interface() || runtime_class.is_array() || runtime_class.class.access_flags.contains(&AccessFlags::Abstract) {
+            return Err(InterpreterError::exception("java/lang/InstantiationException", runtime_class.java_name()))
+        }
+        env.initialize_class(class)?;
+        let obj = env.vm_mut().new_object(class)?;
+        Ok(env.new_local_ref(Some(obj)))
+    })
+}
+
+unsafe extern "C" fn get_object_class(env: *mut JniEnv, obj: jobject) -> jobject {
+    jni_function(env, ptr::null_mut(), |env| {
+        let class = env.vm().heap.class_of(env.resolve_object(obj)?);
+        let mirror = env.vm_mut().class_mirror(class)?;
+        Ok(env.new_local_ref(Some(mirror)))
+    })
+}
+
+unsafe extern "C" fn is_instance_of(env: *mut JniEnv, obj: jobject, class: jobject) -> jboolean {
+    jni_function(env, 0, |env| {
+        let class = env.resolve_class(class)?;
+        Ok(match env.resolve_ref(obj) {
+            Some(obj) => env.vm().classes.is_assignable(env.vm().heap.class_of(obj), class) as jboolean,
+            None => 1
+        })
+    })
+}
+
+unsafe extern "C" fn get_method_id(env: *mut JniEnv, class: jobject, name: *const c_char, signature: *const c_char) -> jmethodID {
+    jni_function(env, ptr::null_mut(), |env| env.method_id(class, name, signature, false))
+}
+
+unsafe extern "C" fn get_static_method_id(env: *mut JniEnv, class: jobject, name: *const c_char, signature: *const c_char) -> jmethodID {
+    jni_function(env, ptr::null_mut(), |env| env.method_id(class, name, signature, true))
+}
+
+unsafe extern "C" fn new_string(env: *mut JniEnv, units: *const u16, length: jint) -> jobject {
+    jni_function(env, ptr::null_mut(), |env| {
+        let units = if length > 0 { std::slice::from_raw_parts(units, length as usize) } else { &[] };
+        let string = env.vm_mut().new_string_utf16(units)?;
+        Ok(env.new_local_ref(Some(string)))
+    })
+}
+
+unsafe extern "C" fn get_string_length(env: *mut JniEnv, string: jobject) -> jint {
+    jni_function(env, 0, |env| Ok(env.vm().string_utf16(env.resolve_object(string)?)?.len() as jint))
+}
+
+unsafe extern "C" fn get_string_chars(env: *mut JniEnv, string: jobject, is_copy: *mut jboolean) -> *const u16 {
+    jni_function(env, ptr::null(), |env| {
+        let units = env.vm().string_utf16(env.resolve_object(string)?)?;
+        if !is_copy.is_null() {
+            *is_copy = 1;
+        }
+        Ok(Box::into_raw(units.into_boxed_slice()) as *const u16)
+    })
+}
+
+unsafe extern "C" fn release_string_chars(env: *mut JniEnv, string: jobject, chars: *const u16) {
+    jni_function(env, (), |env| {
+        let length = env.vm().string_utf16(env.resolve_object(string)?)?.len();
+        drop(Box::from_raw(ptr::slice_from_raw_parts_mut(chars as *mut u16, length)));
+        Ok(())
+    })
+}
+
+unsafe extern "C" fn new_string_utf(env: *mut JniEnv, bytes: *const c_char) -> jobject {
+    jni_function(env, ptr::null_mut(), |env| {
+        if bytes.is_null() {
+            return Ok(ptr::null_mut())
+        }
+        let bytes = CStr::from_ptr(bytes).to_bytes();
+        let string = match strings::decode_modified_utf8(bytes) {
+            Some(units) => env.vm_mut().new_string_utf16(&units)?,
+            None => env.vm_mut().new_string(&String::from_utf8_lossy(bytes))?
+        };
+        Ok(env.new_local_ref(Some(string)))
+    })
+}
+
+unsafe extern "C" fn get_string_utf_length(env: *mut JniEnv, string: jobject) -> jint {
+    jni_function(env, 0, |env| Ok(strings::encode_modified_utf8(&env.vm().string_utf16(env.resolve_object(string)?)?).len() as jint))
+}
+
+unsafe extern "C" fn get_string_utf_chars(env: *mut JniEnv, string: jobject, is_copy: *mut jboolean) -> *const c_char {
+    jni_function(env, ptr::null(), |env| {
+        let bytes = strings::encode_modified_utf8(&env.vm().string_utf16(env.resolve_object(string)?)?);
+        if !is_copy.is_null() {
+            *is_copy = 1;
+        }
+        // Modified UTF-8 has no zero bytes.
+        Ok(CString::from_vec_unchecked(bytes).into_raw())
+    })
+}
+
+unsafe extern "C" fn release_string_utf_chars(_env: *mut JniEnv, _string: jobject, chars: *const c_char) {
+    if !chars.is_null() {
+        drop(CString::from_raw(chars as *mut c_char));
+    }
+}
+
+unsafe extern "C" fn get_array_length(env: *mut JniEnv, array: jobject) -> jint {
+    jni_function(env, 0, |env| Ok(env.vm().heap.array_length(env.resolve_object(array)?) as jint))
+}
+
+unsafe extern "C" fn register_natives(env: *mut JniEnv, class: jobject, methods: *const JNINativeMethod, count: jint) -> jint {
+    jni_function(env, JNI_ERR, |env| {
+        let class = env.resolve_class(class)?;
+        for index in 0..count.max(0) as usize {
+            let native = &*methods.add(index);
+            let (name, signature) = (c_string(native.name)?, c_string(native.signature)?);
+            let class_file = env.vm().classes.class(class).class.clone();
+            let method = class_file.methods.iter()
+                .position(|m| m.method_name == name && m.descriptor == signature && m.access_flags.contains(&AccessFlags::Native))
+                .ok_or_else(|| InterpreterError::exception("java/lang/NoSuchMethodError", format!("Method '{}{}' name or signature does not match", name, signature)))?;
+            env.jni.bound.insert((class, method), native.fn_ptr as *const c_void);
+        }
+        Ok(JNI_OK)
+    })
+}
+
+unsafe extern "C" fn unregister_natives(env: *mut JniEnv, class: jobject) -> jint {
+    jni_function(env, JNI_ERR, |env| {
+        let class = env.resolve_class(class)?;
+        env.jni.bound.retain(|(bound, _), _| *bound != class);
+        Ok(JNI_OK)
+    })
+}
+
+unsafe extern "C" fn monitor_enter(env: *mut JniEnv, obj: jobject) -> jint {
+    jni_function(env, JNI_ERR, |env| {
+        let obj = env.resolve_object(obj)?;
+        let count = env.vm().heap.monitor_count(obj);
+        env.vm_mut().heap.set_monitor_count(obj, count + 1);
+        Ok(JNI_OK)
+    })
+}
+
+unsafe extern "C" fn monitor_exit(env: *mut JniEnv, obj: jobject) -> jint {
+    jni_function(env, JNI_ERR, |env| {
+        let obj = env.resolve_object(obj)?;
+        match env.vm().heap.monitor_count(obj) {
+            0 => Err(InterpreterError::Exception(JavaException::new("java/lang/IllegalMonitorStateException", None))),
+            count => {
+                env.vm_mut().heap.set_monitor_count(obj, count - 1);
+                Ok(JNI_OK)
+            }
+        }
+    })
+}
+
+unsafe extern "C" fn get_java_vm(env: *mut JniEnv, java_vm: *mut *mut JavaVm) -> jint {
+    jni_function(env, JNI_ERR, |env| {
+        *java_vm = &mut *env.jni.java_vm;
+        Ok(JNI_OK)
+    })
+}
+
+unsafe extern "C" fn get_object_ref_type(env: *mut JniEnv, obj: jobject) -> jint {
+    jni_function(env, 0, |env| Ok(match env.resolve_ref(obj) {
+        None => 0,
+        Some(_) if obj as usize & 1 == 1 => 2,
+        Some(_) => 1
+    }))
+}
+
+fn function_table() -> Box<[*const c_void; FUNCTION_COUNT]> {
+    let mut table = Box::new([unsupported as *const c_void; FUNCTION_COUNT]);
+    for slot in table.iter_mut().take(4) {
+        *slot = ptr::null();
+    }
+    let functions = [
+        (4, get_version as *const c_void),
+        (6, find_class as *const c_void),
+        (10, get_superclass as *const c_void),
+        (11, is_assignable_from as *const c_void),
+        (13, throw as *const c_void),
+        (14, throw_new as *const c_void),
+        (15, exception_occurred as *const c_void),
+        (16, exception_describe as *const c_void),
+        (17, exception_clear as *const c_void),
+        (18, fatal_error as *const c_void),
+        (19, push_local_frame as *const c_void),
+        (20, pop_local_frame as *const c_void),
+        (21, new_global_ref as *const c_void),
+        (22, delete_global_ref as *const c_void),
+        (23, delete_local_ref as *const c_void),
+        (24, is_same_object as *const c_void),
+        (25, new_local_ref as *const c_void),
+        (26, ensure_local_capacity as *const c_void),
+        (27, alloc_object as *const c_void),
+        (28, new_object_variadic as *const c_void),
+        (30, new_object_a as *const c_void),
+        (31, get_object_class as *const c_void),
+        (32, is_instance_of as *const c_void),
+        (33, get_method_id as *const c_void),
+        (113, get_static_method_id as *const c_void),
+        (163, new_string as *const c_void),
+        (164, get_string_length as *const c_void),
+        (165, get_string_chars as *const c_void),
+        (166, release_string_chars as *const c_void),
+        (167, new_string_utf as *const c_void),
+        (168, get_string_utf_length as *const c_void),
+        (169, get_string_utf_chars as *const c_void),
+        (170, release_string_utf_chars as *const c_void),
+        (171, get_array_length as *const c_void),
+        (215, register_natives as *const c_void),
+        (216, unregister_natives as *const c_void),
+        (217, monitor_enter as *const c_void),
+        (218, monitor_exit as *const c_void),
+        (219, get_java_vm as *const c_void),
+        (228, exception_check as *const c_void),
+        (232, get_object_ref_type as *const c_void)
+    ];
+    for (slot, function) in functions.into_iter().chain(call_function_slots()) {
+        table[slot] = function;
+    }
+    table
+}
+
+unsafe extern "C" fn destroy_java_vm(_java_vm: *mut JavaVm) -> jint {
+    JNI_ERR
+}
+
+unsafe extern "C" fn attach_current_thread(java_vm: *mut JavaVm, env: *mut *mut JniEnv, _args: *mut c_void) -> jint {
+    *env = (*java_vm).env;
+    JNI_OK
+}
+
+unsafe extern "C" fn detach_current_thread(_java_vm: *mut JavaVm) -> jint {
+    JNI_OK
+}
+
+unsafe extern "C" fn get_env(java_vm: *mut JavaVm, env: *mut *mut JniEnv, version: jint) -> jint {
+    if !SUPPORTED_VERSIONS.contains(&version) {
+        *env = ptr::null_mut();
+        return JNI_EVERSION
+    }
+    *env = (*java_vm).env;
+    JNI_OK
+}
+
+fn invoke_function_table() -> Box<[*const c_void; INVOKE_FUNCTION_COUNT]> {
+    Box::new([
+        ptr::null(),
+        ptr::null(),
+        ptr::null(),
+        destroy_java_vm as *const c_void,
+        attach_current_thread as *const c_void,
+        detach_current_thread as *const c_void,
+        get_env as *const c_void,
+        attach_current_thread as *const c_void
+    ])
+}
+
+/// The loader of the class whose code called a native, the application loader when Rust code did.
+fn caller_loader(env: &VMEnv) -> LoaderId {
+    env.frames().last().map_or(LoaderId::APPLICATION, |frame| env.vm().classes.class(frame.class).defining_loader)
+}
+
+fn string_arg(env: &VMEnv, args: &[VMValue]) -> Result<String, InterpreterError> {
+    match reference(args, 0)? {
+        Some(string) => env.vm().string_value(string),
+        None => Err(InterpreterError::Exception(JavaException::new("java/lang/NullPointerException", None)))
+    }
+}
+
+/// `System.load(String)`.
+pub fn system_load(env: &mut VMEnv, args: &[VMValue]) -> NativeResult {
+    let filename = string_arg(env, args)?;
+    if !Path::new(&filename).is_absolute() {
+        return Err(InterpreterError::exception("java/lang/UnsatisfiedLinkError", format!("Expecting an absolute path of the library: {}", filename)))
+    }
+    env.load_library(Path::new(&filename), caller_loader(env))?;
+    Ok(None)
+}
+
+/// `System.loadLibrary(String)`: the first file named by [`map_library_name`] in the library path.
+pub fn system_load_library(env: &mut VMEnv, args: &[VMValue]) -> NativeResult {
+    let name = string_arg(env, args)?;
+    if name.contains(std::path::MAIN_SEPARATOR) || name.contains('/') {
+        return Err(InterpreterError::exception("java/lang/UnsatisfiedLinkError", format!("Directory separator should not appear in library name: {}", name)))
+    }
+    let library_path = env.vm().options.library_path.clone();
+    let file = library_path.iter().map(|directory| directory.join(map_library_name(&name))).find(|file| file.is_file());
+    match file {
+        Some(file) => {
+            let file = file.canonicalize().unwrap_or(file);
+            env.load_library(&file, caller_loader(env))?;
+            Ok(None)
+        }
+        None => {
+            let paths: Vec<String> = library_path.iter().map(|path| path.display().to_string()).collect();
+            Err(InterpreterError::exception("java/lang/UnsatisfiedLinkError", format!("no {} in java.library.path: {}", name, paths.join(&SEPARATOR.to_string()))))
+        }
+    }
+}
+
+/// `System.mapLibraryName(String)`.
+pub fn system_map_library_name(env: &mut VMEnv, args: &[VMValue]) -> NativeResult {
+    let name = string_arg(env, args)?;
+    let mapped = env.vm_mut().new_string(&map_library_name(&name))?;
+    Ok(Some(VMValue::Reference(mapped)))
+}
@@ -0,0 +1,573 @@
+use std::fmt::Write;
+use crate::disasm::method_handle_kind;
+use crate::interpret::{mnemonic, Interpreter, Opcode};
+use crate::loader::{AccessFlags, Annotation, Attribute, AttributeValue, Class, ConstantPoolTag, ElementValue, ExceptionEntry, Field, LocalVariableEntry, Method, StackMapFrame, VerificationType};
+
+/// Identifies the layout produced by [`class_to_json`]; bumped on any incompatible change.
+/// The layout itself is described in `docs/class-json.md`.
+pub const SCHEMA: &str = "rusty-jvm/class/1";
+
+/// A JSON document tree. Object members keep their insertion order so output is stable.
+#[derive(Debug, Clone, PartialEq)]
+pub enum JsonValue {
+    Null,
+    Bool(bool),
+    Integer(i64),
+    Number(f64),
+    String(String),
+    Array(Vec<JsonValue>),
+    Object(Vec<(String, JsonValue)>)
+}
+
+impl JsonValue {
+    fn object() -> Self {
+        JsonValue::Object(Vec::new())
+    }
+
+    fn with(mut self, key: &str, value: impl Into<JsonValue>) -> Self {
+        if let JsonValue::Object(members) = &mut self {
+            members.push((key.to_string(), value.into()));
+        }
+        self
+    }
+
+    pub fn get(&self, key: &str) -> Option<&JsonValue> {
+        match self {
+            JsonValue::Object(members) => members.iter().find(|(k, _)| k == key).map(|(_, v)| v),
+            _ => None
+        }
+    }
+
+    fn write_string(out: &mut String, value: &str) {
+        out.push('"');
+        for c in value.chars() {
+            match c {
+                '"' => out.push_str("\\\""),
+                '\\' => out.push_str("\\\\"),
+                '\n' => out.push_str("\\n"),
+                '\r' => out.push_str("\\r"),
+                '\t' => out.push_str("\\t"),
+                c if (c as u32) < 0x20 => write!(out, "\\u{:04x}", c as u32).unwrap(),
+                c => out.push(c)
+            }
+        }
+        out.push('"');
+    }
+
+    fn write(&self, out: &mut String, indent: Option<usize>) {
+        let newline = |out: &mut String, level: usize| {
+            if let Some(width) = indent {
+                out.push('\n');
+                out.push_str(&" ".repeat(width * level));
+            }
+        };
+        self.write_level(out, indent, 0, &newline);
+    }
+
+    fn write_level(&self, out: &mut String, indent: Option<usize>, level: usize, newline: &dyn Fn(&mut String, usize)) {
+        match self {
+            JsonValue::Null => out.push_str("null"),
+            JsonValue::Bool(v) => out.push_str(if *v { "true" } else { "false" }),
+            JsonValue::Integer(v) => write!(out, "{}", v).unwrap(),
+            JsonValue::Number(v) if v.is_finite() => write!(out, "{:?}", v).unwrap(),
+            JsonValue::Number(v) => JsonValue::write_string(out, &crate::disasm::java_double_string(*v)),
+            JsonValue::String(v) => JsonValue::write_string(out, v),
+            JsonValue::Array(values) if values.is_empty() => out.push_str("[]"),
+            JsonValue::Array(values) => {
+                out.push('[');
+                for (i, value) in values.iter().enumerate() {
+                    if i > 0 {
+                        out.push(',');
+                    }
+                    newline(out, level + 1);
+                    value.write_level(out, indent, level + 1, newline);
+                }
+                newline(out, level);
+                out.push(']');
+            }
+            JsonValue::Object(members) if members.is_empty() => out.push_str("{}"),
+            JsonValue::Object(members) => {
+                out.push('{');
+                for (i, (key, value)) in members.iter().enumerate() {
+                    if i > 0 {
+                        out.push(',');
+                    }
+                    newline(out, level + 1);
+                    JsonValue::write_string(out, key);
+                    out.push_str(if indent.is_some() { ": " } else { ":" });
+                    value.write_level(out, indent, level + 1, newline);
+                }
+                newline(out, level);
+                out.push('}');
+            }
+        }
+    }
+
+    pub fn to_compact_string(&self) -> String {
+        let mut out = String::new();
+        self.write(&mut out, None);
+        out
+    }
+
+    pub fn to_pretty_string(&self) -> String {
+        let mut out = String::new();
+        self.write(&mut out, Some(2));
+        out
+    }
+}
+
+impl From<bool> for JsonValue {
+    fn from(v: bool) -> Self {
+        JsonValue::Bool(v)
+    }
+}
+
+impl From<i64> for JsonValue {
+    fn from(v: i64) -> Self {
+        JsonValue::Integer(v)
+    }
+}
+
+impl From<u32> for JsonValue {
+    fn from(v: u32) -> Self {
+        JsonValue::Integer(v as i64)
+    }
+}
+
+impl From<u16> for JsonValue {
+    fn from(v: u16) -> Self {
+        JsonValue::Integer(v as i64)
+    }
+}
+
+impl From<u8> for JsonValue {
+    fn from(v: u8) -> Self {
+        JsonValue::Integer(v as i64)
+    }
+}
+
+impl From<usize> for JsonValue {
+    fn from(v: usize) -> Self {
+        JsonValue::Integer(v as i64)
+    }
+}
+
+impl From<f64> for JsonValue {
+    fn from(v: f64) -> Self {
+        JsonValue::Number(v)
+    }
+}
+
+impl From<&str> for JsonValue {
+    fn from(v: &str) -> Self {
+        JsonValue::String(v.to_string())
+    }
+}
+
+impl From<String> for JsonValue {
+    fn from(v: String) -> Self {
+        JsonValue::String(v)
+    }
+}
+
+impl From<&String> for JsonValue {
+    fn from(v: &String) -> Self {
+        JsonValue::String(v.clone())
+    }
+}
+
+impl<T: Into<JsonValue>> From<Option<T>> for JsonValue {
+    fn from(v: Option<T>) -> Self {
+        v.map(Into::into).unwrap_or(JsonValue::Null)
+    }
+}
+
+impl<T: Into<JsonValue>> From<Vec<T>> for JsonValue {
+    fn from(v: Vec<T>) -> Self {
+        JsonValue::Array(v.into_iter().map(Into::into).collect())
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct JsonOptions {
+    /// Adds the symbolic value next to every constant pool index.
+    pub resolve_constants: bool,
+    /// Includes decoded instructions in addition to the raw bytecode.
+    pub instructions: bool,
+    pub pretty: bool
+}
+
+struct Exporter<'a> {
+    class: &'a Class,
+    options: JsonOptions
+}
+
+impl<'a> Exporter<'a> {
+    /// A constant pool reference: `{"index": n}` plus `"value"` when resolving, or `null` for index 0.
+    fn constant(&self, index: u16) -> JsonValue {
+        if index == 0 {
+            return JsonValue::Null
+        }
+        let value = JsonValue::object().with("index", index);
+        if self.options.resolve_constants {
+            value.with("value", self.resolve(index))
+        } else {
+            value
+        }
+    }
+
+    /// Symbolic value of an entry: numbers stay numbers, everything else becomes unquoted text
+    /// such as `java/lang/Object.<init>:()V`.
+    fn resolve(&self, index: u16) -> JsonValue {
+        let pool = &self.class.constant_pool;
+        let utf8 = |index: u16| pool.utf8(index).cloned().unwrap_or_else(|_| format!("#{}", index));
+        let name_and_type = |index: u16| match pool.name_and_type(index) {
+            Ok((name, descriptor)) => format!("{}:{}", name, descriptor),
+            Err(_) => format!("#{}", index)
+        };
+        let text = match pool.get(index) {
+            Some(ConstantPoolTag::Integer(value)) => return JsonValue::Integer(*value as i32 as i64),
+            Some(ConstantPoolTag::Float(value)) => return JsonValue::Number(f32::from_bits(*value) as f64),
+            Some(ConstantPoolTag::Long(..)) => return JsonValue::Integer(pool.long(index).unwrap_or_default()),
+            Some(ConstantPoolTag::Double(..)) => return JsonValue::Number(pool.double(index).unwrap_or_default()),
+            Some(ConstantPoolTag::Utf8(_, _, value)) => value.clone(),
+            Some(ConstantPoolTag::Class(name_index))
+            | Some(ConstantPoolTag::String(name_index))
+            | Some(ConstantPoolTag::MethodType(name_index))
+            | Some(ConstantPoolTag::Module(name_index))
+            | Some(ConstantPoolTag::Package(name_index)) => utf8(*name_index),
+            Some(ConstantPoolTag::FieldRef(..))
+            | Some(ConstantPoolTag::MethodRef(..))
+            | Some(ConstantPoolTag::InterfaceMethodRef(..)) => match pool.member_ref(index) {
+                Ok((class, name, descriptor)) => format!("{}.{}:{}", class, name, descriptor),
+                Err(_) => format!("#{}", index)
+            },
+            Some(ConstantPoolTag::NameAndType(..)) => name_and_type(index),
+            Some(ConstantPoolTag::MethodHandle(kind, reference_index)) => match self.resolve(*reference_index) {
+                JsonValue::String(reference) => format!("{} {}", method_handle_kind(*kind), reference),
+                _ => format!("#{}", index)
+            },
+            Some(ConstantPoolTag::Dynamic(bootstrap_index, nat_index))
+            | Some(ConstantPoolTag::InvokeDynamic(bootstrap_index, nat_index)) => format!("#{}:{}", bootstrap_index, name_and_type(*nat_index)),
+            Some(ConstantPoolTag::Dummy) | None => return JsonValue::Null
+        };
+        JsonValue::String(text)
+    }
+
+    fn access_flags(&self, flags: &[AccessFlags]) -> JsonValue {
+        JsonValue::object()
+            .with("value", AccessFlags::to_value(flags))
+            .with("flags", flags.iter().map(|f| f.spec_name()).collect::<Vec<&str>>())
+    }
+
+    fn constant_pool_entry(&self, index: u16, tag: &ConstantPoolTag) -> JsonValue {
+        let entry = JsonValue::object().with("index", index).with("tag", tag.kind());
+        let pool = &self.class.constant_pool;
+        let entry = match tag {
+            ConstantPoolTag::Class(name_index) => entry.with("name", self.constant(*name_index)),
+            ConstantPoolTag::FieldRef(class_index, nat_index)
+            | ConstantPoolTag::MethodRef(class_index, nat_index)
+            | ConstantPoolTag::InterfaceMethodRef(class_index, nat_index) => entry
+                .with("class", self.constant(*class_index))
+                .with("name_and_type", self.constant(*nat_index)),
+            ConstantPoolTag::String(string_index) => entry.with("string", self.constant(*string_index)),
+            ConstantPoolTag::Integer(value) => entry.with("value", *value as i32 as i64),
+            ConstantPoolTag::Float(value) => entry.with("value", f32::from_bits(*value) as f64).with("bits", format!("{:08x}", value)),
+            ConstantPoolTag::Long(..) => entry.with("value", pool.long(index).unwrap_or_default()),
+            ConstantPoolTag::Double(..) => {
+                let value = pool.double(index).unwrap_or_default();
+                entry.with("value", value).with("bits", format!("{:016x}", value.to_bits()))
+            }
+            ConstantPoolTag::NameAndType(name_index, descriptor_index) => entry
+                .with("name", self.constant(*name_index))
+                .with("descriptor", self.constant(*descriptor_index)),
+            ConstantPoolTag::Utf8(_, _, value) => entry.with("value", value),
+            ConstantPoolTag::MethodHandle(kind, reference_index) => entry
+                .with("reference_kind", method_handle_kind(*kind))
+                .with("reference", self.constant(*reference_index)),
+            ConstantPoolTag::MethodType(descriptor_index) => entry.with("descriptor", self.constant(*descriptor_index)),
+            ConstantPoolTag::Dynamic(bootstrap_index, nat_index)
+            | ConstantPoolTag::InvokeDynamic(bootstrap_index, nat_index) => entry
+                .with("bootstrap_method", *bootstrap_index)
+                .with("name_and_type", self.constant(*nat_index)),
+            ConstantPoolTag::Module(name_index) | ConstantPoolTag::Package(name_index) => entry.with("name", self.constant(*name_index)),
+            ConstantPoolTag::Dummy => entry
+        };
+        if self.options.resolve_constants && !matches!(tag, ConstantPoolTag::Utf8(..)) && entry.get("value").is_none() {
+            entry.with("resolved", self.resolve(index))
+        } else {
+            entry
+        }
+    }
+
+    fn element_value(&self, value: &ElementValue) -> JsonValue {
+        match value {
+            ElementValue::ConstValueIndex(tag, index) => JsonValue::object()
+                .with("tag", (*tag as char).to_string())
+                .with("value", self.constant(*index)),
+            ElementValue::EnumConstValue(type_index, name_index) => JsonValue::object()
+                .with("tag", "e")
+                .with("type", self.constant(*type_index))
+                .with("name", self.constant(*name_index)),
+            ElementValue::ClassInfoIndex(index) => JsonValue::object()
+                .with("tag", "c")
+                .with("class", self.constant(*index)),
+            ElementValue::AnnotationValue(annotation) => JsonValue::object()
+                .with("tag", "@")
+                .with("annotation", self.annotation(annotation)),
+            ElementValue::ArrayValue(_, values) => JsonValue::object()
+                .with("tag", "[")
+                .with("values", values.iter().map(|v| self.element_value(v)).collect::<Vec<JsonValue>>())
+        }
+    }
+
+    fn annotation(&self, annotation: &Annotation) -> JsonValue {
+        let elements: Vec<JsonValue> = annotation.element_value_pairs.iter()
+            .map(|pair| JsonValue::object()
+                .with("name", self.constant(pair.name_index))
+                .with("value", self.element_value(&pair.value)))
+            .collect();
+        JsonValue::object()
+            .with("type", self.constant(annotation.type_index))
+            .with("elements", elements)
+    }
+
+    fn annotations(&self, annotations: &[Annotation]) -> JsonValue {
+        annotations.iter().map(|a| self.annotation(a)).collect::<Vec<JsonValue>>().into()
+    }
+
+    fn local_variables(&self, entries: &[LocalVariableEntry], descriptor_key: &str) -> JsonValue {
+        entries.iter().map(|entry| JsonValue::object()
+            .with("start_pc", entry.start_pc)
+            .with("length", entry.length)
+            .with("name", self.constant(entry.name_index))
+            .with(descriptor_key, self.constant(entry.descriptor_index))
+            .with("slot", entry.index))
+            .collect::<Vec<JsonValue>>()
+            .into()
+    }
+
+    fn verification_type(&self, value: &VerificationType) -> JsonValue {
+        let kind = |name: &str| JsonValue::object().with("kind", name);
+        match value {
+            VerificationType::Top => kind("top"),
+            VerificationType::Integer => kind("int"),
+            VerificationType::Float => kind("float"),
+            VerificationType::Double => kind("double"),
+            VerificationType::Long => kind("long"),
+            VerificationType::Null => kind("null"),
+            VerificationType::UninitializedThis => kind("uninitialized_this"),
+            VerificationType::Object(index) => kind("object").with("class", self.constant(*index)),
+            VerificationType::Uninitialized(offset) => kind("uninitialized").with("offset", *offset)
+        }
+    }
+
+    fn stack_map_frame(&self, frame: &StackMapFrame) -> JsonValue {
+        JsonValue::object()
+            .with("frame_type", frame.frame_type)
+            .with("kind", frame.kind())
+            .with("offset_delta", frame.offset_delta)
+            .with("locals", frame.locals.iter().map(|v| self.verification_type(v)).collect::<Vec<JsonValue>>())
+            .with("stack", frame.stack.iter().map(|v| self.verification_type(v)).collect::<Vec<JsonValue>>())
+    }
+
+    fn attribute(&self, attribute: &Attribute) -> JsonValue {
+        let ret = JsonValue::object().with("name", &attribute.name);
+        let constants = |indexes: &[u16]| -> JsonValue {
+            indexes.iter().map(|i| self.constant(*i)).collect::<Vec<JsonValue>>().into()
+        };
+        match &attribute.value {
+            AttributeValue::ConstantValue(index) => ret.with("value", self.constant(*index)),
+            AttributeValue::SourceFile(index) => ret.with("source_file", self.constant(*index)),
+            AttributeValue::Signature(index) => ret.with("signature", self.constant(*index)),
+            AttributeValue::LineNumberTable(entries) => ret.with("entries", entries.iter()
+                .map(|e| JsonValue::object().with("start_pc", e.start_pc).with("line", e.line_number))
+                .collect::<Vec<JsonValue>>()),
+            AttributeValue::LocalVariableTable(entries) => ret.with("entries", self.local_variables(entries, "descriptor")),
+            AttributeValue::LocalVariableTypeTable(entries) => ret.with("entries", self.local_variables(entries, "signature")),
+            AttributeValue::StackMapTable(frames) => ret.with("frames", frames.iter()
+                .map(|f| self.stack_map_frame(f))
+                .collect::<Vec<JsonValue>>()),
+            AttributeValue::Exceptions(indexes) => ret.with("exceptions", constants(indexes)),
+            AttributeValue::InnerClasses(entries) => ret.with("classes", entries.iter()
+                .map(|e| JsonValue::object()
+                    .with("inner_class", self.constant(e.inner_class_info_index))
+                    .with("outer_class", self.constant(e.outer_class_info_index))
+                    .with("inner_name", self.constant(e.inner_name_index))
+                    .with("access_flags", self.access_flags(&e.inner_class_access_flags)))
+                .collect::<Vec<JsonValue>>()),
+            AttributeValue::EnclosingMethod(class_index, method_index) => ret
+                .with("class", self.constant(*class_index))
+                .with("method", self.constant(*method_index)),
+            AttributeValue::BootstrapMethods(methods) => ret.with("methods", methods.iter()
+                .map(|m| JsonValue::object()
+                    .with("method_handle", self.constant(m.bootstrap_method_ref))
+                    .with("arguments", constants(&m.bootstrap_arguments)))
+                .collect::<Vec<JsonValue>>()),
+            AttributeValue::NestHost(index) => ret.with("host", self.constant(*index)),
+            AttributeValue::NestMembers(indexes) => ret.with("members", constants(indexes)),
+            AttributeValue::MethodParameters(parameters) => ret.with("parameters", parameters.iter()
+                .map(|p| JsonValue::object()
+                    .with("name", self.constant(p.name_index))
+                    .with("access_flags", self.access_flags(&p.access_flags)))
+                .collect::<Vec<JsonValue>>()),
+            AttributeValue::AnnotationDefault(value) => ret.with("value", self.element_value(value)),
+            AttributeValue::Deprecated | AttributeValue::Synthetic => ret,
+            AttributeValue::RuntimeVisibleAnnotations(_, annotations)
+            | AttributeValue::RuntimeInvisibleAnnotations(_, annotations) => ret.with("annotations", self.annotations(annotations)),
+            AttributeValue::RuntimeVisibleParameterAnnotations(_, parameters)
+            | AttributeValue::RuntimeInvisibleParameterAnnotations(_, parameters) => ret.with("parameters", parameters.iter()
+                .map(|p| self.annotations(&p.annotations))
+                .collect::<Vec<JsonValue>>()),
+            AttributeValue::Code(..) => ret,
+            AttributeValue::Unidentified(info) => ret.with("info", hex(info))
+        }
+    }
+
+    fn attributes(&self, attributes: &[Attribute]) -> JsonValue {
+        attributes.iter().map(|a| self.attribute(a)).collect::<Vec<JsonValue>>().into()
+    }
+
+    fn exception_entry(&self, entry: &ExceptionEntry) -> JsonValue {
+        JsonValue::object()
+            .with("start_pc", entry.pc_start)
+            .with("end_pc", entry.pc_end)
+            .with("handler_pc", entry.handler_pc)
+            .with("catch_type", self.constant(entry.catch_type))
+    }
+
+    fn instruction(&self, code: &[u8], pc: usize, opcode: &Opcode) -> JsonValue {
+        let name = if code[pc] == 0xc4 {
+            format!("{}_w", mnemonic(code[pc + 1]).unwrap_or("wide"))
+        } else {
+            mnemonic(code[pc]).unwrap_or("<unknown>").to_string()
+        };
+        let ret = JsonValue::object().with("pc", pc).with("opcode", name);
+        let target = |offset: &i32| pc as i64 + *offset as i64;
+        match opcode {
+            Opcode::BiPush(v) => ret.with("value", *v as i64),
+            Opcode::SiPush(v) => ret.with("value", *v as i64),
+            Opcode::Ldc(index) | Opcode::LdcW(index) | Opcode::Ldc2W(index)
+            | Opcode::GetStatic(index) | Opcode::PutStatic(index) | Opcode::GetField(index) | Opcode::PutField(index)
+            | Opcode::InvokeVirtual(index) | Opcode::InvokeSpecial(index) | Opcode::InvokeStatic(index)
+            | Opcode::InvokeDynamic(index) | Opcode::New(index) | Opcode::ANewArray(index)
+            | Opcode::CheckCast(index) | Opcode::InstanceOf(index) => ret.with("constant", self.constant(*index)),
+            Opcode::InvokeInterface(index, count) => ret.with("constant", self.constant(*index)).with("count", *count),
+            Opcode::MultiANewArray(index, dimensions) => ret.with("constant", self.constant(*index)).with("dimensions", *dimensions),
+            Opcode::ILoad(index) | Opcode::LLoad(index) | Opcode::FLoad(index) | Opcode::DLoad(index) | Opcode::ALoad(index)
+            | Opcode::IStore(index) | Opcode::LStore(index) | Opcode::FStore(index) | Opcode::DStore(index)
+            | Opcode::AStore(index) | Opcode::Ret(index) => ret.with("local", *index),
+            Opcode::IInc(index, value) => ret.with("local", *index).with("value", *value as i64),
+            Opcode::NewArray(atype) => ret.with("atype", *atype),
+            Opcode::TableSwitch(default, low, _, offsets) => ret
+                .with("default", target(default))
+                .with("cases", offsets.iter().enumerate()
+                    .map(|(i, offset)| JsonValue::object().with("key", *low as i64 + i as i64).with("target", target(offset)))
+                    .collect::<Vec<JsonValue>>()),
+            Opcode::LookupSwitch(default, pairs) => ret
+                .with("default", target(default))
+                .with("cases", pairs.iter()
+                    .map(|(key, offset)| JsonValue::object().with("key", *key as i64).with("target", target(offset)))
+                    .collect::<Vec<JsonValue>>()),
+            _ => match opcode.branch_offsets().first() {
+                Some(offset) => ret.with("target", target(offset)),
+                None => ret
+            }
+        }
+    }
+
+    fn instructions(&self, code: &[u8]) -> JsonValue {
+        let interpreter = Interpreter::new();
+        let mut ret: Vec<JsonValue> = Vec::new();
+        let mut pc = 0;
+        while pc < code.len() {
+            match interpreter.decode(code, pc) {
+                Ok((opcode, next_pc)) => {
+                    ret.push(self.instruction(code, pc, &opcode));
+                    pc = next_pc;
+                }
+                Err(e) => {
+                    ret.push(JsonValue::object().with("pc", pc).with("error", format!("{:?}", e)));
+                    break
+                }
+            }
+        }
+        ret.into()
+    }
+
+    fn field(&self, field: &Field) -> JsonValue {
+        JsonValue::object()
+            .with("name", &field.field_name)
+            .with("descriptor", &field.descriptor)
+            .with("access_flags", self.access_flags(&field.access_flags))
+            .with("attributes", self.attributes(&field.attributes))
+    }
+
+    fn method(&self, method: &Method) -> JsonValue {
+        let has_code = !method.code.is_empty();
+        let code = if has_code {
+            let code = JsonValue::object()
+                .with("max_stack", method.max_stack)
+                .with("max_locals", method.max_locals)
+                .with("bytecode", hex(&method.code));
+            let code = if self.options.instructions {
+                code.with("instructions", self.instructions(&method.code))
+            } else {
+                code
+            };
+            code.with("exception_table", method.exception_table.iter().map(|e| self.exception_entry(e)).collect::<Vec<JsonValue>>())
+                .with("attributes", self.attributes(&method.code_attributes))
+        } else {
+            JsonValue::Null
+        };
+        JsonValue::object()
+            .with("name", &method.method_name)
+            .with("descriptor", &method.descriptor)
+            .with("access_flags", self.access_flags(&method.access_flags))
+            .with("code", code)
+            .with("attributes", self.attributes(&method.attributes))
+    }
+
+    fn class(&self) -> JsonValue {
+        let class = self.class;
+        JsonValue::object()
+            .with("schema", SCHEMA)
+            .with("name", &class.class_name)
+            .with("super_class", class.super_class_name.as_ref())
+            .with("interfaces", class.interface_names.clone())
+            .with("source_file", class.source_file_name.as_ref())
+            .with("version", JsonValue::object().with("major", class.version_major).with("minor", class.version_minor))
+            .with("access_flags", self.access_flags(&class.access_flags))
+            .with("this_class", self.constant(class.this_class))
+            .with("super_class_index", self.constant(class.super_class))
+            .with("constant_pool", class.constant_pool.entries()
+                .map(|(index, tag)| self.constant_pool_entry(index, tag))
+                .collect::<Vec<JsonValue>>())
+            .with("fields", class.fields.iter().map(|f| self.field(f)).collect::<Vec<JsonValue>>())
+            .with("methods", class.methods.iter().map(|m| self.method(m)).collect::<Vec<JsonValue>>())
+            .with("attributes", self.attributes(&class.attributes))
+    }
+}
+
+fn hex(bytes: &[u8]) -> String {
+    let mut ret = String::with_capacity(bytes.len() * 2);
+    for byte in bytes {
+        write!(ret, "{:02x}", byte).unwrap();
+    }
+    ret
+}
+
+/// Builds the JSON document for a loaded class.
+pub fn class_to_json_value(class: &Class, options: JsonOptions) -> JsonValue {
+    Exporter { class, options }.class()
+}
+
+/// Serializes a loaded class following the [`SCHEMA`] layout.
+pub fn class_to_json(class: &Class, options: JsonOptions) -> String {
+    let value = class_to_json_value(class, options);
+    if options.pretty {
+        value.to_pretty_string()
+    } else {
+        value.to_compact_string()
+    }
+}
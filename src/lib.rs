@@ -0,0 +1,19 @@
+pub mod vm;
+pub mod env;
+pub mod interpret;
+pub mod loader;
+pub mod descriptor;
+pub mod disasm;
+pub mod json;
+pub mod classpath;
+pub mod jimage;
+pub mod manifest;
+pub mod rt;
+pub mod registry;
+pub mod gc;
+pub mod hprof;
+pub mod profile;
+pub mod strings;
+pub mod native;
+pub mod jni;
+pub mod jdk;
@@ -1,38 +1,79 @@
-use std::collections::HashMap;
 use std::fs::File;
-use std::io::{Cursor, Error, Read, Seek, SeekFrom};
+use std::io::{Cursor, Error, Read};
 use byteorder::{BigEndian, ReadBytesExt};
+use crate::strings;
 use crate::loader::ClassLoadError::UnknownElementValueTag;
 use crate::loader::ElementValue::{AnnotationValue, ArrayValue, ClassInfoIndex, ConstValueIndex, EnumConstValue};
 
 #[derive(Debug)]
 pub enum ClassLoadError {
     MagicMismatch(u32),
-    VersionUnsupported(u16, u16),
+    /// Class name, major and minor version.
+    VersionUnsupported(String, u16, u16),
     ClassFileReadFailure(Error),
     ConstantPoolMissing(u16),
     AttributeMissing(String),
     AttributeTypeMismatch(String, String),
     ConstantPoolTypeMismatch(String, String),
-    UnknownElementValueTag(u8)
+    UnknownElementValueTag(u8),
+    UnknownConstantPoolTag(u8),
+    IllegalUtf8,
+    UnknownStackMapFrameType(u8),
+    UnknownVerificationType(u8),
+    InvalidDescriptor(String),
+    ClassNotFound(String),
+    NoClassDefFound(String),
+    InvalidArchive(String, String),
+    InvalidImage(String, String),
+    InvalidManifest(String),
+    DuplicateClassDefinition(String, String),
+    ClassCircularity(String),
+    IncompatibleClassChange(String),
+    WrongName(String, String),
+    ProhibitedPackage(String),
+    UnknownLoader(u32),
+    Verify(String, String)
 }
 
+/// Major versions of the class files the loader accepts: Java 7 (51) up to Java 17 (61).
+pub const MIN_CLASS_FILE_VERSION: u16 = 51;
+pub const MAX_CLASS_FILE_VERSION: u16 = 61;
+
 impl From<Error> for ClassLoadError {
     fn from(e: Error) -> Self {
         ClassLoadError::ClassFileReadFailure(e)
     }
 }
 
-trait AttributesContainer {
-    fn get_attributes(&self) -> &AttributeInfo;
-}
-
-impl dyn AttributesContainer {
-    
+impl ClassLoadError {
+    /// The Java exception class (binary name) and message this error surfaces as.
+    pub fn java_exception(&self) -> (&'static str, String) {
+        let java_name = |name: &str| name.replace('/', ".");
+        match self {
+            ClassLoadError::ClassNotFound(name) => ("java/lang/ClassNotFoundException", java_name(name)),
+            ClassLoadError::NoClassDefFound(name) => ("java/lang/NoClassDefFoundError", name.clone()),
+            ClassLoadError::DuplicateClassDefinition(loader, name) =>
+                ("java/lang/LinkageError", format!("loader {} attempted duplicate class definition for {}.", loader, java_name(name))),
+            ClassLoadError::ClassCircularity(name) => ("java/lang/ClassCircularityError", java_name(name)),
+            ClassLoadError::IncompatibleClassChange(message) => ("java/lang/IncompatibleClassChangeError", message.clone()),
+            ClassLoadError::WrongName(expected, actual) =>
+                ("java/lang/NoClassDefFoundError", format!("{} (wrong name: {})", expected, actual)),
+            ClassLoadError::ProhibitedPackage(package) => ("java/lang/SecurityException", format!("Prohibited package name: {}", java_name(package))),
+            ClassLoadError::VersionUnsupported(name, major, minor) if *major > MAX_CLASS_FILE_VERSION =>
+                ("java/lang/UnsupportedClassVersionError", format!("{} has been compiled by a more recent version of the Java Runtime (class file version {}.{}), \
+                    this version of the Java Runtime only recognizes class file versions up to {}.0", java_name(name), major, minor, MAX_CLASS_FILE_VERSION)),
+            ClassLoadError::VersionUnsupported(name, major, minor) =>
+                ("java/lang/UnsupportedClassVersionError", format!("{} (class file version {}.{}) is not supported", java_name(name), major, minor)),
+            ClassLoadError::Verify(class_name, reason) => ("java/lang/VerifyError", format!("{} in class {}", reason, java_name(class_name))),
+            ClassLoadError::IllegalUtf8 => ("java/lang/ClassFormatError", "Illegal UTF8 string in constant pool".to_string()),
+            ClassLoadError::UnknownLoader(id) => ("java/lang/InternalError", format!("unknown class loader {}", id)),
+            e => ("java/lang/ClassFormatError", format!("{:?}", e))
+        }
+    }
 }
 
 #[derive(Debug)]
-enum ConstantPoolTag {
+pub enum ConstantPoolTag {
     Class(u16),
     FieldRef(u16, u16),
     MethodRef(u16, u16),
@@ -46,19 +87,22 @@ enum ConstantPoolTag {
     Utf8(u16, Vec<u8>, String),
     MethodHandle(u8, u16),
     MethodType(u16),
+    Dynamic(u16, u16),
     InvokeDynamic(u16, u16),
+    Module(u16),
+    Package(u16),
     Dummy
 }
 
 impl ConstantPoolTag {
-    fn from_reader(reader: &mut File) -> Result<Vec<ConstantPoolTag>, ClassLoadError> {
+    fn from_reader(reader: &mut dyn Read) -> Result<Vec<ConstantPoolTag>, ClassLoadError> {
         let byte = reader.read_u8()?;
-        return Ok(match byte {
+        Ok(match byte {
             1 => {
                 let length = reader.read_u16::<BigEndian>()?;
                 let mut bytes = vec![0u8; length as usize];
                 reader.read_exact(&mut bytes)?;
-                let string = String::from_utf8(bytes.clone()).unwrap_or("<nil>".to_string());
+                let string = strings::modified_utf8_to_string(&bytes).ok_or(ClassLoadError::IllegalUtf8)?;
                 vec![ConstantPoolTag::Utf8(length, bytes, string)]
             },
             3 => vec![ConstantPoolTag::Integer(reader.read_u32::<BigEndian>()?)],
@@ -73,18 +117,45 @@ impl ConstantPoolTag {
             12 => vec![ConstantPoolTag::NameAndType(reader.read_u16::<BigEndian>()?, reader.read_u16::<BigEndian>()?)],
             15 => vec![ConstantPoolTag::MethodHandle(reader.read_u8()?, reader.read_u16::<BigEndian>()?)],
             16 => vec![ConstantPoolTag::MethodType(reader.read_u16::<BigEndian>()?)],
+            17 => vec![ConstantPoolTag::Dynamic(reader.read_u16::<BigEndian>()?, reader.read_u16::<BigEndian>()?)],
             18 => vec![ConstantPoolTag::InvokeDynamic(reader.read_u16::<BigEndian>()?, reader.read_u16::<BigEndian>()?)],
-            _ => panic!("Unexpected constant pool tag: {} at 0x{:x}", byte, reader.seek(SeekFrom::Current(0))?)
-        });
+            19 => vec![ConstantPoolTag::Module(reader.read_u16::<BigEndian>()?)],
+            20 => vec![ConstantPoolTag::Package(reader.read_u16::<BigEndian>()?)],
+            _ => return Err(ClassLoadError::UnknownConstantPoolTag(byte))
+        })
+    }
+
+    /// Name of the tag as spelled by `javap`.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            ConstantPoolTag::Class(_) => "Class",
+            ConstantPoolTag::FieldRef(..) => "Fieldref",
+            ConstantPoolTag::MethodRef(..) => "Methodref",
+            ConstantPoolTag::InterfaceMethodRef(..) => "InterfaceMethodref",
+            ConstantPoolTag::String(_) => "String",
+            ConstantPoolTag::Integer(_) => "Integer",
+            ConstantPoolTag::Float(_) => "Float",
+            ConstantPoolTag::Long(..) => "Long",
+            ConstantPoolTag::Double(..) => "Double",
+            ConstantPoolTag::NameAndType(..) => "NameAndType",
+            ConstantPoolTag::Utf8(..) => "Utf8",
+            ConstantPoolTag::MethodHandle(..) => "MethodHandle",
+            ConstantPoolTag::MethodType(_) => "MethodType",
+            ConstantPoolTag::Dynamic(..) => "Dynamic",
+            ConstantPoolTag::InvokeDynamic(..) => "InvokeDynamic",
+            ConstantPoolTag::Module(_) => "Module",
+            ConstantPoolTag::Package(_) => "Package",
+            ConstantPoolTag::Dummy => "Dummy"
+        }
     }
 }
 
 #[derive(Debug)]
-struct ExceptionEntry {
-    pc_start: u16,
-    pc_end: u16,
-    handler_pc: u16,
-    catch_type: u16
+pub struct ExceptionEntry {
+    pub pc_start: u16,
+    pub pc_end: u16,
+    pub handler_pc: u16,
+    pub catch_type: u16
 }
 
 impl ExceptionEntry {
@@ -103,9 +174,9 @@ impl ExceptionEntry {
 }
 
 #[derive(Debug)]
-struct ElementValuePair {
-    name_index: u16,
-    value: ElementValue
+pub struct ElementValuePair {
+    pub name_index: u16,
+    pub value: ElementValue
 }
 
 impl ElementValuePair {
@@ -120,10 +191,10 @@ impl ElementValuePair {
 }
 
 #[derive(Debug)]
-struct Annotation {
-    type_index: u16,
-    num_element_value_pairs: u16,
-    element_value_pairs: Vec<ElementValuePair>
+pub struct Annotation {
+    pub type_index: u16,
+    pub num_element_value_pairs: u16,
+    pub element_value_pairs: Vec<ElementValuePair>
 }
 
 impl Annotation {
@@ -143,8 +214,8 @@ impl Annotation {
 }
 
 #[derive(Debug)]
-enum ElementValue {
-    ConstValueIndex(u16),
+pub enum ElementValue {
+    ConstValueIndex(u8, u16),
     EnumConstValue(u16, u16),
     ClassInfoIndex(u16),
     AnnotationValue(Annotation),
@@ -163,7 +234,7 @@ impl ElementValue {
             | 'I'
             | 'J'
             | 'S'
-            | 'Z' => Ok(ConstValueIndex(cursor.read_u16::<BigEndian>()?)),
+            | 'Z' => Ok(ConstValueIndex(tag, cursor.read_u16::<BigEndian>()?)),
             'e' => Ok(EnumConstValue(cursor.read_u16::<BigEndian>()?, cursor.read_u16::<BigEndian>()?)),
             'c' => Ok(ClassInfoIndex(cursor.read_u16::<BigEndian>()?)),
             '@' => Ok(AnnotationValue(Annotation::from_cursor(cursor)?)),
@@ -181,9 +252,9 @@ impl ElementValue {
 }
 
 #[derive(Debug)]
-struct ParameterAnnotation {
-    num_annotations: u16,
-    annotations: Vec<Annotation>
+pub struct ParameterAnnotation {
+    pub num_annotations: u16,
+    pub annotations: Vec<Annotation>
 }
 
 impl ParameterAnnotation {
@@ -201,11 +272,184 @@ impl ParameterAnnotation {
 }
 
 #[derive(Debug)]
-enum AttributeValue {
+pub struct LineNumberEntry {
+    pub start_pc: u16,
+    pub line_number: u16
+}
+
+#[derive(Debug)]
+pub struct LocalVariableEntry {
+    pub start_pc: u16,
+    pub length: u16,
+    pub name_index: u16,
+    pub descriptor_index: u16,
+    pub index: u16
+}
+
+#[derive(Debug)]
+pub enum VerificationType {
+    Top,
+    Integer,
+    Float,
+    Double,
+    Long,
+    Null,
+    UninitializedThis,
+    Object(u16),
+    Uninitialized(u16)
+}
+
+impl VerificationType {
+    fn from_cursor(cursor: &mut Cursor<&&Vec<u8>>) -> Result<Self, ClassLoadError> {
+        let tag = cursor.read_u8()?;
+        Ok(match tag {
+            0 => VerificationType::Top,
+            1 => VerificationType::Integer,
+            2 => VerificationType::Float,
+            3 => VerificationType::Double,
+            4 => VerificationType::Long,
+            5 => VerificationType::Null,
+            6 => VerificationType::UninitializedThis,
+            7 => VerificationType::Object(cursor.read_u16::<BigEndian>()?),
+            8 => VerificationType::Uninitialized(cursor.read_u16::<BigEndian>()?),
+            _ => return Err(ClassLoadError::UnknownVerificationType(tag))
+        })
+    }
+
+    fn list_from_cursor(cursor: &mut Cursor<&&Vec<u8>>, count: u16) -> Result<Vec<Self>, ClassLoadError> {
+        let mut ret: Vec<VerificationType> = Vec::new();
+        for _ in 0 .. count {
+            ret.push(VerificationType::from_cursor(cursor)?);
+        }
+        Ok(ret)
+    }
+}
+
+/// A `StackMapTable` entry; the frame kind is implied by `frame_type` as in JVMS 4.7.4.
+#[derive(Debug)]
+pub struct StackMapFrame {
+    pub frame_type: u8,
+    pub offset_delta: u16,
+    pub locals: Vec<VerificationType>,
+    pub stack: Vec<VerificationType>
+}
+
+impl StackMapFrame {
+    fn from_cursor(cursor: &mut Cursor<&&Vec<u8>>) -> Result<Self, ClassLoadError> {
+        let frame_type = cursor.read_u8()?;
+        let (offset_delta, locals, stack) = match frame_type {
+            0 ..= 63 => (frame_type as u16, vec![], vec![]),
+            64 ..= 127 => (frame_type as u16 - 64, vec![], vec![VerificationType::from_cursor(cursor)?]),
+            247 => {
+                let offset_delta = cursor.read_u16::<BigEndian>()?;
+                (offset_delta, vec![], vec![VerificationType::from_cursor(cursor)?])
+            }
+            248 ..= 251 => (cursor.read_u16::<BigEndian>()?, vec![], vec![]),
+            252 ..= 254 => {
+                let offset_delta = cursor.read_u16::<BigEndian>()?;
+                (offset_delta, VerificationType::list_from_cursor(cursor, frame_type as u16 - 251)?, vec![])
+            }
+            255 => {
+                let offset_delta = cursor.read_u16::<BigEndian>()?;
+                let locals_count = cursor.read_u16::<BigEndian>()?;
+                let locals = VerificationType::list_from_cursor(cursor, locals_count)?;
+                let stack_count = cursor.read_u16::<BigEndian>()?;
+                (offset_delta, locals, VerificationType::list_from_cursor(cursor, stack_count)?)
+            }
+            _ => return Err(ClassLoadError::UnknownStackMapFrameType(frame_type))
+        };
+        Ok(StackMapFrame {
+            frame_type,
+            offset_delta,
+            locals,
+            stack
+        })
+    }
+
+    /// Frame kind as named in JVMS 4.7.4.
+    pub fn kind(&self) -> &'static str {
+        match self.frame_type {
+            0 ..= 63 => "same",
+            64 ..= 127 => "same_locals_1_stack_item",
+            247 => "same_locals_1_stack_item_frame_extended",
+            248 ..= 250 => "chop",
+            251 => "same_frame_extended",
+            252 ..= 254 => "append",
+            _ => "full_frame"
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct InnerClassEntry {
+    pub inner_class_info_index: u16,
+    pub outer_class_info_index: u16,
+    pub inner_name_index: u16,
+    pub inner_class_access_flags: Vec<AccessFlags>
+}
+
+#[derive(Debug)]
+pub struct BootstrapMethod {
+    pub bootstrap_method_ref: u16,
+    pub bootstrap_arguments: Vec<u16>
+}
+
+#[derive(Debug)]
+pub struct MethodParameter {
+    pub name_index: u16,
+    pub access_flags: Vec<AccessFlags>
+}
+
+fn read_u16_list(cursor: &mut Cursor<&&Vec<u8>>) -> Result<Vec<u16>, ClassLoadError> {
+    let count = cursor.read_u16::<BigEndian>()?;
+    let mut ret: Vec<u16> = Vec::new();
+    for _ in 0 .. count {
+        ret.push(cursor.read_u16::<BigEndian>()?);
+    }
+    Ok(ret)
+}
+
+fn read_annotations(cursor: &mut Cursor<&&Vec<u8>>) -> Result<(u16, Vec<Annotation>), ClassLoadError> {
+    let num_annotations = cursor.read_u16::<BigEndian>()?;
+    let mut annotations: Vec<Annotation> = Vec::new();
+    for _ in 0 .. num_annotations {
+        annotations.push(Annotation::from_cursor(cursor)?);
+    }
+    Ok((num_annotations, annotations))
+}
+
+fn read_parameter_annotations(cursor: &mut Cursor<&&Vec<u8>>) -> Result<(u8, Vec<ParameterAnnotation>), ClassLoadError> {
+    let num_parameters = cursor.read_u8()?;
+    let mut parameters: Vec<ParameterAnnotation> = Vec::new();
+    for _ in 0 .. num_parameters {
+        parameters.push(ParameterAnnotation::from_cursor(cursor)?);
+    }
+    Ok((num_parameters, parameters))
+}
+
+#[derive(Debug)]
+pub enum AttributeValue {
     ConstantValue(u16),
     SourceFile(u16),
     Code(u16, u16, u32, Vec<u8>, u16, Vec<ExceptionEntry>, u16, Vec<AttributeInfo>),
+    LineNumberTable(Vec<LineNumberEntry>),
+    LocalVariableTable(Vec<LocalVariableEntry>),
+    LocalVariableTypeTable(Vec<LocalVariableEntry>),
+    StackMapTable(Vec<StackMapFrame>),
+    Exceptions(Vec<u16>),
+    Signature(u16),
+    InnerClasses(Vec<InnerClassEntry>),
+    EnclosingMethod(u16, u16),
+    BootstrapMethods(Vec<BootstrapMethod>),
+    NestHost(u16),
+    NestMembers(Vec<u16>),
+    MethodParameters(Vec<MethodParameter>),
+    AnnotationDefault(ElementValue),
+    Deprecated,
+    Synthetic,
+    RuntimeVisibleParameterAnnotations(u8, Vec<ParameterAnnotation>),
     RuntimeInvisibleParameterAnnotations(u8, Vec<ParameterAnnotation>),
+    RuntimeVisibleAnnotations(u16, Vec<Annotation>),
     RuntimeInvisibleAnnotations(u16, Vec<Annotation>),
     Unidentified(Vec<u8>),
 }
@@ -243,20 +487,102 @@ impl AttributeValue {
                     attr_table
                 )
             }
-            "RuntimeInvisibleParameterAnnotations" => {
-                let num_parameters = cursor.read_u8()?;
-                let mut parameters: Vec<ParameterAnnotation> = Vec::new();
-                for _ in 0 .. num_parameters {
-                    parameters.push(ParameterAnnotation::from_cursor(&mut cursor)?);
+            "LineNumberTable" => {
+                let length = cursor.read_u16::<BigEndian>()?;
+                let mut entries: Vec<LineNumberEntry> = Vec::new();
+                for _ in 0 .. length {
+                    entries.push(LineNumberEntry {
+                        start_pc: cursor.read_u16::<BigEndian>()?,
+                        line_number: cursor.read_u16::<BigEndian>()?
+                    });
+                }
+                AttributeValue::LineNumberTable(entries)
+            }
+            "LocalVariableTable" | "LocalVariableTypeTable" => {
+                let length = cursor.read_u16::<BigEndian>()?;
+                let mut entries: Vec<LocalVariableEntry> = Vec::new();
+                for _ in 0 .. length {
+                    entries.push(LocalVariableEntry {
+                        start_pc: cursor.read_u16::<BigEndian>()?,
+                        length: cursor.read_u16::<BigEndian>()?,
+                        name_index: cursor.read_u16::<BigEndian>()?,
+                        descriptor_index: cursor.read_u16::<BigEndian>()?,
+                        index: cursor.read_u16::<BigEndian>()?
+                    });
+                }
+                if name == "LocalVariableTable" {
+                    AttributeValue::LocalVariableTable(entries)
+                } else {
+                    AttributeValue::LocalVariableTypeTable(entries)
+                }
+            }
+            "StackMapTable" => {
+                let number_of_entries = cursor.read_u16::<BigEndian>()?;
+                let mut frames: Vec<StackMapFrame> = Vec::new();
+                for _ in 0 .. number_of_entries {
+                    frames.push(StackMapFrame::from_cursor(&mut cursor)?);
                 }
+                AttributeValue::StackMapTable(frames)
+            }
+            "Exceptions" => AttributeValue::Exceptions(read_u16_list(&mut cursor)?),
+            "Signature" => AttributeValue::Signature(cursor.read_u16::<BigEndian>()?),
+            "InnerClasses" => {
+                let number_of_classes = cursor.read_u16::<BigEndian>()?;
+                let mut classes: Vec<InnerClassEntry> = Vec::new();
+                for _ in 0 .. number_of_classes {
+                    classes.push(InnerClassEntry {
+                        inner_class_info_index: cursor.read_u16::<BigEndian>()?,
+                        outer_class_info_index: cursor.read_u16::<BigEndian>()?,
+                        inner_name_index: cursor.read_u16::<BigEndian>()?,
+                        inner_class_access_flags: AccessFlags::from_value(cursor.read_u16::<BigEndian>()?, false)
+                    });
+                }
+                AttributeValue::InnerClasses(classes)
+            }
+            "EnclosingMethod" => AttributeValue::EnclosingMethod(cursor.read_u16::<BigEndian>()?, cursor.read_u16::<BigEndian>()?),
+            "BootstrapMethods" => {
+                let num_bootstrap_methods = cursor.read_u16::<BigEndian>()?;
+                let mut methods: Vec<BootstrapMethod> = Vec::new();
+                for _ in 0 .. num_bootstrap_methods {
+                    let bootstrap_method_ref = cursor.read_u16::<BigEndian>()?;
+                    let bootstrap_arguments = read_u16_list(&mut cursor)?;
+                    methods.push(BootstrapMethod {
+                        bootstrap_method_ref,
+                        bootstrap_arguments
+                    });
+                }
+                AttributeValue::BootstrapMethods(methods)
+            }
+            "NestHost" => AttributeValue::NestHost(cursor.read_u16::<BigEndian>()?),
+            "NestMembers" => AttributeValue::NestMembers(read_u16_list(&mut cursor)?),
+            "MethodParameters" => {
+                let parameters_count = cursor.read_u8()?;
+                let mut parameters: Vec<MethodParameter> = Vec::new();
+                for _ in 0 .. parameters_count {
+                    parameters.push(MethodParameter {
+                        name_index: cursor.read_u16::<BigEndian>()?,
+                        access_flags: AccessFlags::from_value(cursor.read_u16::<BigEndian>()?, false)
+                    });
+                }
+                AttributeValue::MethodParameters(parameters)
+            }
+            "AnnotationDefault" => AttributeValue::AnnotationDefault(ElementValue::from_cursor(&mut cursor)?),
+            "Deprecated" => AttributeValue::Deprecated,
+            "Synthetic" => AttributeValue::Synthetic,
+            "RuntimeVisibleParameterAnnotations" => {
+                let (num_parameters, parameters) = read_parameter_annotations(&mut cursor)?;
+                AttributeValue::RuntimeVisibleParameterAnnotations(num_parameters, parameters)
+            }
+            "RuntimeInvisibleParameterAnnotations" => {
+                let (num_parameters, parameters) = read_parameter_annotations(&mut cursor)?;
                 AttributeValue::RuntimeInvisibleParameterAnnotations(num_parameters, parameters)
             }
+            "RuntimeVisibleAnnotations" => {
+                let (num_annotations, annotations) = read_annotations(&mut cursor)?;
+                AttributeValue::RuntimeVisibleAnnotations(num_annotations, annotations)
+            }
             "RuntimeInvisibleAnnotations" => {
-                let num_annotations = cursor.read_u16::<BigEndian>()?;
-                let mut annotations: Vec<Annotation> = Vec::new();
-                for _ in 0 .. num_annotations {
-                    annotations.push(Annotation::from_cursor(&mut cursor)?);
-                }
+                let (num_annotations, annotations) = read_annotations(&mut cursor)?;
                 AttributeValue::RuntimeInvisibleAnnotations(num_annotations, annotations)
             }
             _ => AttributeValue::Unidentified(info.clone())
@@ -265,24 +591,21 @@ impl AttributeValue {
     }
 }
 
-struct ClassFileConstantPool {
-    constant_pool_count: u16,
-    constant_pool: Vec<ConstantPoolTag>
+#[derive(Debug)]
+pub struct ClassFileConstantPool {
+    pub constant_pool_count: u16,
+    pub constant_pool: Vec<ConstantPoolTag>
 }
 
 impl ClassFileConstantPool {
-    fn from_reader(reader: &mut File) -> Result<ClassFileConstantPool, ClassLoadError> {
+    fn from_reader(reader: &mut dyn Read) -> Result<ClassFileConstantPool, ClassLoadError> {
         let constant_pool_count = reader.read_u16::<BigEndian>()?;
         let mut constant_pool: Vec<ConstantPoolTag> = vec![];
-        let mut entry_index: usize = 1;
-        println!("from_reader constant_pool size {}", constant_pool_count);
         loop {
-            if constant_pool.len() >= (constant_pool_count - 1) as usize {
+            if constant_pool.len() >= constant_pool_count.saturating_sub(1) as usize {
                 break
             }
             let tags = ConstantPoolTag::from_reader(&mut *reader)?;
-            println!("from_reader constant_pool {} = {:?}", entry_index, tags);
-            entry_index += tags.len();
             constant_pool.extend(tags);
         }
         Ok(ClassFileConstantPool {
@@ -290,10 +613,82 @@ impl ClassFileConstantPool {
             constant_pool
         })
     }
+
+    pub fn get(&self, index: u16) -> Option<&ConstantPoolTag> {
+        if index == 0 {
+            return None
+        }
+        self.constant_pool.get(index as usize - 1)
+    }
+
+    /// Iterates over the usable entries with their pool indexes, skipping the
+    /// unusable slots that follow `Long` and `Double` constants.
+    pub fn entries(&self) -> impl Iterator<Item = (u16, &ConstantPoolTag)> {
+        self.constant_pool.iter().enumerate()
+            .filter(|(_, tag)| !matches!(tag, ConstantPoolTag::Dummy))
+            .map(|(index, tag)| (index as u16 + 1, tag))
+    }
+
+    pub fn utf8(&self, index: u16) -> Result<&String, ClassLoadError> {
+        match self.get(index).ok_or(ClassLoadError::ConstantPoolMissing(index))? {
+            ConstantPoolTag::Utf8(_, _, value) => Ok(value),
+            x => Err(ClassLoadError::ConstantPoolTypeMismatch("Utf8".to_string(), format!("{:?}", x)))
+        }
+    }
+
+    /// A `Utf8` entry as the UTF-16 code units of a Java string, which unlike [`Self::utf8`]
+    /// keeps unpaired surrogates.
+    pub fn utf16(&self, index: u16) -> Result<Vec<u16>, ClassLoadError> {
+        match self.get(index).ok_or(ClassLoadError::ConstantPoolMissing(index))? {
+            ConstantPoolTag::Utf8(_, bytes, _) => strings::decode_modified_utf8(bytes).ok_or(ClassLoadError::IllegalUtf8),
+            x => Err(ClassLoadError::ConstantPoolTypeMismatch("Utf8".to_string(), format!("{:?}", x)))
+        }
+    }
+
+    pub fn class_name(&self, index: u16) -> Result<&String, ClassLoadError> {
+        match self.get(index).ok_or(ClassLoadError::ConstantPoolMissing(index))? {
+            ConstantPoolTag::Class(name_index) => self.utf8(*name_index),
+            x => Err(ClassLoadError::ConstantPoolTypeMismatch("Class".to_string(), format!("{:?}", x)))
+        }
+    }
+
+    pub fn name_and_type(&self, index: u16) -> Result<(&String, &String), ClassLoadError> {
+        match self.get(index).ok_or(ClassLoadError::ConstantPoolMissing(index))? {
+            ConstantPoolTag::NameAndType(name_index, descriptor_index) => Ok((self.utf8(*name_index)?, self.utf8(*descriptor_index)?)),
+            x => Err(ClassLoadError::ConstantPoolTypeMismatch("NameAndType".to_string(), format!("{:?}", x)))
+        }
+    }
+
+    /// Resolves a `Fieldref`, `Methodref` or `InterfaceMethodref` to (class, name, descriptor).
+    pub fn member_ref(&self, index: u16) -> Result<(&String, &String, &String), ClassLoadError> {
+        match self.get(index).ok_or(ClassLoadError::ConstantPoolMissing(index))? {
+            ConstantPoolTag::FieldRef(class_index, name_and_type_index)
+            | ConstantPoolTag::MethodRef(class_index, name_and_type_index)
+            | ConstantPoolTag::InterfaceMethodRef(class_index, name_and_type_index) => {
+                let (name, descriptor) = self.name_and_type(*name_and_type_index)?;
+                Ok((self.class_name(*class_index)?, name, descriptor))
+            }
+            x => Err(ClassLoadError::ConstantPoolTypeMismatch("Ref".to_string(), format!("{:?}", x)))
+        }
+    }
+
+    pub fn long(&self, index: u16) -> Result<i64, ClassLoadError> {
+        match self.get(index).ok_or(ClassLoadError::ConstantPoolMissing(index))? {
+            ConstantPoolTag::Long(high, low) => Ok((((*high as u64) << 32) | *low as u64) as i64),
+            x => Err(ClassLoadError::ConstantPoolTypeMismatch("Long".to_string(), format!("{:?}", x)))
+        }
+    }
+
+    pub fn double(&self, index: u16) -> Result<f64, ClassLoadError> {
+        match self.get(index).ok_or(ClassLoadError::ConstantPoolMissing(index))? {
+            ConstantPoolTag::Double(high, low) => Ok(f64::from_bits(((*high as u64) << 32) | *low as u64)),
+            x => Err(ClassLoadError::ConstantPoolTypeMismatch("Double".to_string(), format!("{:?}", x)))
+        }
+    }
 }
 
-#[derive(Debug)]
-enum AccessFlags {
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AccessFlags {
     Public,
     Final,
     Super,
@@ -315,8 +710,12 @@ enum AccessFlags {
 }
 
 impl AccessFlags {
-    fn from_reader(reader: &mut File, is_method: bool) -> Result<Vec<AccessFlags>, ClassLoadError> {
+    fn from_reader(reader: &mut dyn Read, is_method: bool) -> Result<Vec<AccessFlags>, ClassLoadError> {
         let value = reader.read_u16::<BigEndian>()?;
+        Ok(AccessFlags::from_value(value, is_method))
+    }
+
+    pub fn from_value(value: u16, is_method: bool) -> Vec<AccessFlags> {
         let mut ret: Vec<AccessFlags> = vec![];
         if value & 0x0001 == 0x0001 {
             ret.push(AccessFlags::Public)
@@ -375,40 +774,102 @@ impl AccessFlags {
         if value & 0x4000 == 0x4000 {
             ret.push(AccessFlags::Enum)
         }
-        Ok(ret)
+        ret
+    }
+
+    pub fn mask(&self) -> u16 {
+        match self {
+            AccessFlags::Public => 0x0001,
+            AccessFlags::Private => 0x0002,
+            AccessFlags::Protected => 0x0004,
+            AccessFlags::Static => 0x0008,
+            AccessFlags::Final => 0x0010,
+            AccessFlags::Super | AccessFlags::Synchronized => 0x0020,
+            AccessFlags::Volatile | AccessFlags::Bridge => 0x0040,
+            AccessFlags::Transient | AccessFlags::Varargs => 0x0080,
+            AccessFlags::Native => 0x0100,
+            AccessFlags::Interface => 0x0200,
+            AccessFlags::Abstract => 0x0400,
+            AccessFlags::Strict => 0x0800,
+            AccessFlags::Synthetic => 0x1000,
+            AccessFlags::Annotation => 0x2000,
+            AccessFlags::Enum => 0x4000
+        }
+    }
+
+    pub fn to_value(flags: &[AccessFlags]) -> u16 {
+        flags.iter().fold(0, |acc, flag| acc | flag.mask())
+    }
+
+    /// Constant name as spelled in the JVM specification, e.g. `ACC_PUBLIC`.
+    pub fn spec_name(&self) -> &'static str {
+        match self {
+            AccessFlags::Public => "ACC_PUBLIC",
+            AccessFlags::Final => "ACC_FINAL",
+            AccessFlags::Super => "ACC_SUPER",
+            AccessFlags::Interface => "ACC_INTERFACE",
+            AccessFlags::Abstract => "ACC_ABSTRACT",
+            AccessFlags::Synthetic => "ACC_SYNTHETIC",
+            AccessFlags::Annotation => "ACC_ANNOTATION",
+            AccessFlags::Enum => "ACC_ENUM",
+            AccessFlags::Private => "ACC_PRIVATE",
+            AccessFlags::Protected => "ACC_PROTECTED",
+            AccessFlags::Static => "ACC_STATIC",
+            AccessFlags::Volatile => "ACC_VOLATILE",
+            AccessFlags::Transient => "ACC_TRANSIENT",
+            AccessFlags::Synchronized => "ACC_SYNCHRONIZED",
+            AccessFlags::Bridge => "ACC_BRIDGE",
+            AccessFlags::Varargs => "ACC_VARARGS",
+            AccessFlags::Native => "ACC_NATIVE",
+            AccessFlags::Strict => "ACC_STRICT"
+        }
+    }
+
+    /// Java source modifier, if the flag has one.
+    pub fn modifier(&self) -> Option<&'static str> {
+        match self {
+            AccessFlags::Public => Some("public"),
+            AccessFlags::Private => Some("private"),
+            AccessFlags::Protected => Some("protected"),
+            AccessFlags::Static => Some("static"),
+            AccessFlags::Final => Some("final"),
+            AccessFlags::Abstract => Some("abstract"),
+            AccessFlags::Synchronized => Some("synchronized"),
+            AccessFlags::Volatile => Some("volatile"),
+            AccessFlags::Transient => Some("transient"),
+            AccessFlags::Native => Some("native"),
+            AccessFlags::Strict => Some("strictfp"),
+            _ => None
+        }
     }
 }
 
 struct ClassFileInterfaces {
-    interfaces_count: u16,
     interfaces: Vec<u16>
 }
 
 impl ClassFileInterfaces {
-    fn from_reader(reader: &mut File) -> Result<ClassFileInterfaces, ClassLoadError> {
+    fn from_reader(reader: &mut dyn Read) -> Result<ClassFileInterfaces, ClassLoadError> {
         let interfaces_count = reader.read_u16::<BigEndian>()?;
         let mut interfaces: Vec<u16> = vec![];
-        for interface_index in 0 .. interfaces_count {
-            let pool_index = reader.read_u16::<BigEndian>()?;
-            println!("from_reader interface {} index {}", interface_index, pool_index);
-            interfaces.push(pool_index);
+        for _ in 0 .. interfaces_count {
+            interfaces.push(reader.read_u16::<BigEndian>()?);
         }
         Ok(ClassFileInterfaces {
-            interfaces_count,
             interfaces
         })
     }
 }
 
 #[derive(Debug)]
-struct AttributeInfo {
-    attribute_name_index: u16,
-    attribute_length: u32,
-    info: Vec<u8>
+pub struct AttributeInfo {
+    pub attribute_name_index: u16,
+    pub attribute_length: u32,
+    pub info: Vec<u8>
 }
 
 impl AttributeInfo {
-    fn from_reader(reader: &mut File) -> Result<AttributeInfo, ClassLoadError> {
+    fn from_reader(reader: &mut dyn Read) -> Result<AttributeInfo, ClassLoadError> {
         let attribute_name_index = reader.read_u16::<BigEndian>()?;
         let attribute_length = reader.read_u32::<BigEndian>()?;
         let mut info = vec![0u8; attribute_length as usize];
@@ -435,22 +896,17 @@ impl AttributeInfo {
 
 #[derive(Debug)]
 struct ClassFileAttributes {
-    attributes_count: u16,
     attributes: Vec<AttributeInfo>
 }
 
 impl ClassFileAttributes {
-    fn from_reader(reader: &mut File) -> Result<ClassFileAttributes, ClassLoadError> {
+    fn from_reader(reader: &mut dyn Read) -> Result<ClassFileAttributes, ClassLoadError> {
         let attributes_count = reader.read_u16::<BigEndian>()?;
         let mut attributes: Vec<AttributeInfo> = vec![];
-        for attribute_index in 0 .. attributes_count {
-            let info = AttributeInfo::from_reader(reader)?;
-            println!("from_reader attribute {} info {:?}", attribute_index, info);
-            attributes.push(info);
+        for _ in 0 .. attributes_count {
+            attributes.push(AttributeInfo::from_reader(reader)?);
         }
-        println!("from_reader attributes_count {} attributes {}", attributes_count, attributes.len());
         Ok(ClassFileAttributes {
-            attributes_count,
             attributes
         })
     }
@@ -465,12 +921,11 @@ struct FieldInfo {
 }
 
 impl FieldInfo {
-    fn from_reader(reader: &mut File) -> Result<FieldInfo, ClassLoadError> {
+    fn from_reader(reader: &mut dyn Read) -> Result<FieldInfo, ClassLoadError> {
         let access_flags = AccessFlags::from_reader(reader, false)?;
         let name_index = reader.read_u16::<BigEndian>()?;
         let description_index = reader.read_u16::<BigEndian>()?;
         let attributes = ClassFileAttributes::from_reader(reader)?;
-        println!("from_reader field name {} description {} attributes {} access {:?}", name_index, description_index, attributes.attributes_count, access_flags);
         Ok(FieldInfo {
             access_flags,
             name_index,
@@ -481,21 +936,17 @@ impl FieldInfo {
 }
 
 struct ClassFileFields {
-    fields_count: u16,
     fields: Vec<FieldInfo>
 }
 
 impl ClassFileFields {
-    fn from_reader(reader: &mut File) -> Result<ClassFileFields, ClassLoadError> {
+    fn from_reader(reader: &mut dyn Read) -> Result<ClassFileFields, ClassLoadError> {
         let fields_count = reader.read_u16::<BigEndian>()?;
         let mut fields: Vec<FieldInfo> = vec![];
-        for field_index in 0 .. fields_count {
-            let field_info = FieldInfo::from_reader(reader)?;
-            println!("from_reader field {} field_info {:?}", field_index, field_info);
-            fields.push(field_info);
+        for _ in 0 .. fields_count {
+            fields.push(FieldInfo::from_reader(reader)?);
         }
         Ok(ClassFileFields {
-            fields_count,
             fields
         })
     }
@@ -510,12 +961,11 @@ struct MethodInfo {
 }
 
 impl MethodInfo {
-    fn from_reader(reader: &mut File) -> Result<MethodInfo, ClassLoadError> {
-        let access_flags = AccessFlags::from_reader(reader, false)?;
+    fn from_reader(reader: &mut dyn Read) -> Result<MethodInfo, ClassLoadError> {
+        let access_flags = AccessFlags::from_reader(reader, true)?;
         let name_index = reader.read_u16::<BigEndian>()?;
         let description_index = reader.read_u16::<BigEndian>()?;
         let attributes = ClassFileAttributes::from_reader(reader)?;
-        println!("from_reader method name {} description {} attributes {} access {:?}", name_index, description_index, attributes.attributes_count, access_flags);
         Ok(MethodInfo {
             access_flags,
             name_index,
@@ -527,21 +977,17 @@ impl MethodInfo {
 
 #[derive(Debug)]
 struct ClassFileMethods {
-    methods_count: u16,
     methods: Vec<MethodInfo>
 }
 
 impl ClassFileMethods {
-    fn from_reader(reader: &mut File) -> Result<ClassFileMethods, ClassLoadError> {
+    fn from_reader(reader: &mut dyn Read) -> Result<ClassFileMethods, ClassLoadError> {
         let methods_count = reader.read_u16::<BigEndian>()?;
         let mut methods: Vec<MethodInfo> = vec![];
-        for method_index in 0 ..methods_count {
-            let method_info = MethodInfo::from_reader(reader)?;
-            println!("from_reader method {} method_info {:?}", method_index, method_info);
-            methods.push(method_info);
+        for _ in 0 ..methods_count {
+            methods.push(MethodInfo::from_reader(reader)?);
         }
         Ok(ClassFileMethods {
-            methods_count,
             methods
         })
     }
@@ -562,7 +1008,7 @@ struct ClassFileHeader {
 }
 
 impl ClassFileHeader {
-    fn from_reader(reader: &mut File) -> Result<ClassFileHeader, ClassLoadError> {
+    fn from_reader(reader: &mut dyn Read) -> Result<ClassFileHeader, ClassLoadError> {
         let header = ClassFileHeader {
             magic: reader.read_u32::<BigEndian>()?,
             version_minor: reader.read_u16::<BigEndian>()?,
@@ -579,12 +1025,10 @@ impl ClassFileHeader {
         if header.magic != 0xCAFEBABE {
             return Err(ClassLoadError::MagicMismatch(header.magic))
         }
-        if header.version_major < 51 || header.version_major > 60 {
-            return Err(ClassLoadError::VersionUnsupported(header.version_major, header.version_minor))
+        if header.version_major < MIN_CLASS_FILE_VERSION || header.version_major > MAX_CLASS_FILE_VERSION {
+            let name = header.constant_pool.class_name(header.this_class).cloned().unwrap_or_default();
+            return Err(ClassLoadError::VersionUnsupported(name, header.version_major, header.version_minor))
         }
-        println!("from_reader access_flags {:?}", header.access_flags);
-        println!("from_reader this_class {}", header.this_class);
-        println!("from_reader super_class {}", header.super_class);
         Ok(header)
     }
 }
@@ -599,7 +1043,7 @@ impl ClassReader {
     }
 
     fn get_constant_value(&self, key: usize) -> Option<&ConstantPoolTag> {
-        self.header.constant_pool.constant_pool.get(key - 1)
+        self.header.constant_pool.get(key as u16)
     }
 
     fn get_constant_utf8(&self, index: usize) -> Option<&String> {
@@ -616,43 +1060,39 @@ impl ClassReader {
         Err(ClassLoadError::ConstantPoolMissing(attribute.attribute_name_index))
     }
 
-    fn get_attributes_map(&self) -> HashMap<&String, AttributeValue> {
-        let mut map: HashMap<_, _> = HashMap::new();
-        for attribute in &self.header.attributes.attributes {
-            let (name, value) = self.get_attribute_value(attribute).unwrap();
-            map.insert(name, value);
+    fn get_attributes(&self, attributes: &[AttributeInfo]) -> Result<Vec<Attribute>, ClassLoadError> {
+        let mut ret: Vec<Attribute> = Vec::new();
+        for attribute in attributes {
+            let (name, value) = self.get_attribute_value(attribute)?;
+            ret.push(Attribute {
+                name: name.clone(),
+                value
+            });
         }
-        map
+        Ok(ret)
     }
 
-    fn get_class_attribute(&self, name: &str) -> Result<AttributeValue, ClassLoadError> {
-        for attribute in &self.header.attributes.attributes {
-            let (attr_name, value) = self.get_attribute_value(attribute)?;
-            if name == attr_name {
-                return Ok(value)
+    fn get_source_file(&self, attributes: &[Attribute]) -> Result<Option<String>, ClassLoadError> {
+        for attribute in attributes {
+            if let AttributeValue::SourceFile(name_index) = attribute.value {
+                let name = self.get_constant_utf8(name_index as usize)
+                    .ok_or(ClassLoadError::ConstantPoolMissing(name_index))?;
+                return Ok(Some(name.clone()))
             }
         }
-        Err(ClassLoadError::AttributeMissing(name.to_string()))
-    }
-
-    fn get_source_file(&self) -> Result<&String, ClassLoadError> {
-        let key = "SourceFile";
-        match self.get_class_attribute(key)? {
-            AttributeValue::SourceFile(name_index) => self.get_constant_utf8(name_index as usize).ok_or(ClassLoadError::AttributeMissing(key.to_string())),
-            x => Err(ClassLoadError::AttributeTypeMismatch(key.to_string(), format!("{:?}", x).to_string()))
-        }
+        Ok(None)
     }
 
     fn get_class_name(&self) -> Result<&String, ClassLoadError> {
         let entry = self.get_constant_value(self.header.this_class as usize)
             .ok_or(ClassLoadError::ConstantPoolMissing(self.header.this_class))?;
         if let ConstantPoolTag::Class(class_index) = entry {
-            match self.get_constant_value(class_index.clone() as usize)
-                .ok_or(ClassLoadError::ConstantPoolMissing(class_index.clone()))? {
+            match self.get_constant_value(*class_index as usize)
+                .ok_or(ClassLoadError::ConstantPoolMissing(*class_index))? {
                 ConstantPoolTag::Utf8(_, _, name) => Ok(name),
                 ConstantPoolTag::NameAndType(name_index, _) => {
-                    Ok(self.get_constant_utf8(name_index.clone() as usize)
-                        .ok_or(ClassLoadError::ConstantPoolMissing(name_index.clone()))?)
+                    Ok(self.get_constant_utf8(*name_index as usize)
+                        .ok_or(ClassLoadError::ConstantPoolMissing(*name_index))?)
                 },
                 x => Err(ClassLoadError::ConstantPoolTypeMismatch("NameAndType".to_string(), format!("{:?}", x)))
             }
@@ -661,8 +1101,40 @@ impl ClassReader {
         }
     }
 
-    fn get_methods(&self) -> Result<HashMap<String, Method>, ClassLoadError> {
-        let mut map: HashMap<String, Method> = HashMap::new();
+    fn get_super_class_name(&self) -> Result<Option<&String>, ClassLoadError> {
+        if self.header.super_class == 0 {
+            return Ok(None)
+        }
+        Ok(Some(self.header.constant_pool.class_name(self.header.super_class)?))
+    }
+
+    fn get_interface_names(&self) -> Result<Vec<String>, ClassLoadError> {
+        let mut ret: Vec<String> = Vec::new();
+        for index in &self.header.interfaces.interfaces {
+            ret.push(self.header.constant_pool.class_name(*index)?.clone());
+        }
+        Ok(ret)
+    }
+
+    fn get_fields(&self) -> Result<Vec<Field>, ClassLoadError> {
+        let mut ret: Vec<Field> = Vec::new();
+        for field in &self.header.fields.fields {
+            let field_name = self.get_constant_utf8(field.name_index as usize)
+                .ok_or(ClassLoadError::ConstantPoolMissing(field.name_index))?;
+            let descriptor = self.get_constant_utf8(field.description_index as usize)
+                .ok_or(ClassLoadError::ConstantPoolMissing(field.description_index))?;
+            ret.push(Field {
+                field_name: field_name.clone(),
+                descriptor: descriptor.clone(),
+                access_flags: field.access_flags.clone(),
+                attributes: self.get_attributes(&field.attributes.attributes)?
+            });
+        }
+        Ok(ret)
+    }
+
+    fn get_methods(&self) -> Result<Vec<Method>, ClassLoadError> {
+        let mut ret: Vec<Method> = Vec::new();
         for method in &self.header.methods.methods {
             let name_index = method.name_index;
             let description_index = method.description_index;
@@ -670,55 +1142,186 @@ impl ClassReader {
                 .ok_or(ClassLoadError::ConstantPoolMissing(name_index))?;
             let description = self.get_constant_utf8(description_index as usize)
                 .ok_or(ClassLoadError::ConstantPoolMissing(description_index))?;
-            let mut method_code: Vec<u8> = Vec::new();
-            for attribute in &method.attributes.attributes {
-                let attribute_name = self.get_constant_utf8(attribute.attribute_name_index as usize)
-                    .ok_or(ClassLoadError::ConstantPoolMissing(name_index))?;
-                let attribute_value = AttributeValue::from_name_and_info(attribute_name, &attribute.info)?;
-                println!("method {} : {} attribute {} length {} value {:?}", method_name, description, attribute_name, attribute.attribute_length, attribute_value);
-                match attribute_value {
-                    AttributeValue::Code(_, _, _, code, ..) => method_code = code,
-                    _ => {}
+            let mut result = Method {
+                method_name: method_name.clone(),
+                descriptor: description.clone(),
+                access_flags: method.access_flags.clone(),
+                max_stack: 0,
+                max_locals: 0,
+                code: Vec::new(),
+                exception_table: Vec::new(),
+                code_attributes: Vec::new(),
+                attributes: Vec::new()
+            };
+            for attribute in self.get_attributes(&method.attributes.attributes)? {
+                match attribute.value {
+                    AttributeValue::Code(max_stack, max_locals, _, code, _, exception_table, _, code_attributes) => {
+                        result.max_stack = max_stack;
+                        result.max_locals = max_locals;
+                        result.code = code;
+                        result.exception_table = exception_table;
+                        result.code_attributes = self.get_attributes(&code_attributes)?;
+                    }
+                    _ => result.attributes.push(attribute)
                 }
             }
-            map.insert(method_name.clone(), Method {
-                method_name: method_name.clone(),
-                code: method_code
-            });
+            ret.push(result);
         }
-        Ok(map)
+        Ok(ret)
     }
 }
 
+/// A parsed attribute together with its resolved name.
+#[derive(Debug)]
+pub struct Attribute {
+    pub name: String,
+    pub value: AttributeValue
+}
+
+#[derive(Debug)]
+pub struct Field {
+    pub field_name: String,
+    pub descriptor: String,
+    pub access_flags: Vec<AccessFlags>,
+    pub attributes: Vec<Attribute>
+}
+
+impl Field {
+    pub fn is_static(&self) -> bool {
+        self.access_flags.contains(&AccessFlags::Static)
+    }
+
+    pub fn constant_value(&self) -> Option<u16> {
+        self.attributes.iter().find_map(|attribute| match attribute.value {
+            AttributeValue::ConstantValue(index) => Some(index),
+            _ => None
+        })
+    }
+}
+
+/// A method with its `Code` attribute unpacked; `attributes` holds every other method attribute
+/// and `code_attributes` the attributes nested in `Code`.
 #[derive(Debug)]
 pub struct Method {
     pub method_name: String,
-    pub code: Vec<u8>
+    pub descriptor: String,
+    pub access_flags: Vec<AccessFlags>,
+    pub max_stack: u16,
+    pub max_locals: u16,
+    pub code: Vec<u8>,
+    pub exception_table: Vec<ExceptionEntry>,
+    pub code_attributes: Vec<Attribute>,
+    pub attributes: Vec<Attribute>
+}
+
+impl Method {
+    pub fn is_static(&self) -> bool {
+        self.access_flags.contains(&AccessFlags::Static)
+    }
+
+    pub fn line_numbers(&self) -> &[LineNumberEntry] {
+        self.code_attributes.iter().find_map(|attribute| match &attribute.value {
+            AttributeValue::LineNumberTable(entries) => Some(entries.as_slice()),
+            _ => None
+        }).unwrap_or(&[])
+    }
+
+    /// The source line of the instruction at `pc`: the entry with the greatest `start_pc` not after it.
+    pub fn line_number(&self, pc: usize) -> Option<u16> {
+        self.line_numbers().iter()
+            .filter(|entry| entry.start_pc as usize <= pc)
+            .max_by_key(|entry| entry.start_pc)
+            .map(|entry| entry.line_number)
+    }
 }
 
 #[derive(Debug)]
 pub struct Class {
-    class_name: String,
-    source_file_name: String,
-    methods: HashMap<String, Method>
+    pub class_name: String,
+    pub super_class_name: Option<String>,
+    pub interface_names: Vec<String>,
+    pub source_file_name: Option<String>,
+    pub version_minor: u16,
+    pub version_major: u16,
+    pub access_flags: Vec<AccessFlags>,
+    pub this_class: u16,
+    pub super_class: u16,
+    pub interfaces: Vec<u16>,
+    pub constant_pool: ClassFileConstantPool,
+    pub fields: Vec<Field>,
+    pub methods: Vec<Method>,
+    pub attributes: Vec<Attribute>
 }
 
 impl Class {
     fn from_header(header: ClassFileHeader) -> Result<Self, ClassLoadError> {
         let reader = ClassReader::new(header);
-        let source_file_name = reader.get_source_file()?;
-        let class_name = reader.get_class_name()?;
-
+        let attributes = reader.get_attributes(&reader.header.attributes.attributes)?;
+        let source_file_name = reader.get_source_file(&attributes)?;
+        let class_name = reader.get_class_name()?.clone();
+        let super_class_name = reader.get_super_class_name()?.cloned();
+        let interface_names = reader.get_interface_names()?;
+        let fields = reader.get_fields()?;
         let methods = reader.get_methods()?;
+        let header = reader.header;
         Ok(Class {
-            class_name: class_name.clone(),
-            source_file_name: source_file_name.clone(),
-            methods: methods
+            class_name,
+            super_class_name,
+            interface_names,
+            source_file_name,
+            version_minor: header.version_minor,
+            version_major: header.version_major,
+            access_flags: header.access_flags,
+            this_class: header.this_class,
+            super_class: header.super_class,
+            interfaces: header.interfaces.interfaces,
+            constant_pool: header.constant_pool,
+            fields,
+            methods,
+            attributes
         })
     }
 
+    pub fn get_method(&self, name: &str, descriptor: &str) -> Option<&Method> {
+        self.methods.iter().find(|method| method.method_name == name && method.descriptor == descriptor)
+    }
+
     pub fn get_main(&self) -> Option<&Method> {
-        return self.methods.get("main");
+        self.get_method("main", "([Ljava/lang/String;)V")
+    }
+
+    /// The entries of the `BootstrapMethods` attribute, empty if there is none.
+    pub fn bootstrap_methods(&self) -> &[BootstrapMethod] {
+        self.attributes.iter().find_map(|attribute| match &attribute.value {
+            AttributeValue::BootstrapMethods(methods) => Some(methods.as_slice()),
+            _ => None
+        }).unwrap_or(&[])
+    }
+
+    /// The class named by the `NestHost` attribute, for members of a nest other than its host.
+    pub fn nest_host(&self) -> Option<String> {
+        self.attributes.iter().find_map(|attribute| match &attribute.value {
+            AttributeValue::NestHost(index) => self.constant_pool.class_name(*index).ok().cloned(),
+            _ => None
+        })
+    }
+
+    /// The entry of the `InnerClasses` attribute that describes this class itself, for nested classes.
+    pub fn inner_class_entry(&self) -> Option<&InnerClassEntry> {
+        self.attributes.iter().find_map(|attribute| match &attribute.value {
+            AttributeValue::InnerClasses(entries) => entries.iter()
+                .find(|entry| self.constant_pool.class_name(entry.inner_class_info_index).is_ok_and(|name| *name == self.class_name)),
+            _ => None
+        })
+    }
+
+    /// The `EnclosingMethod` attribute of a local or anonymous class: the enclosing class and,
+    /// unless it is a field initializer, the name and descriptor of the method.
+    pub fn enclosing_method(&self) -> Option<(u16, u16)> {
+        self.attributes.iter().find_map(|attribute| match &attribute.value {
+            AttributeValue::EnclosingMethod(class, method) => Some((*class, *method)),
+            _ => None
+        })
     }
  }
 
@@ -732,9 +1335,12 @@ impl Loader {
         self.load_from_reader(&mut file)
     }
 
-    pub fn load_from_reader(&self, reader: &mut File) -> Result<Class, ClassLoadError> {
+    pub fn load_from_reader(&self, reader: &mut dyn Read) -> Result<Class, ClassLoadError> {
         let header = ClassFileHeader::from_reader(reader)?;
         Class::from_header(header)
     }
-}
 
+    pub fn load_from_bytes(&self, bytes: &[u8]) -> Result<Class, ClassLoadError> {
+        self.load_from_reader(&mut Cursor::new(bytes))
+    }
+}
@@ -1,13 +1,24 @@
 extern crate core;
 
-use crate::env::VMEnv;
-use crate::interpret::Interpreter;
-use crate::vm::VM;
+use std::io::Write;
+use std::process::exit;
+#[cfg(test)]
+use rusty_jvm::{env, interpret, vm};
+use rusty_jvm::env::VMEnv;
+use rusty_jvm::interpret::{Interpreter, InterpreterError};
+use rusty_jvm::{disasm, json};
+use rusty_jvm::classpath::{ClassPath, ClassPathEntry, DEFAULT_RELEASE, SEPARATOR};
+#[cfg(test)]
+use rusty_jvm::loader::ClassLoadError;
+use rusty_jvm::loader::Loader;
+use rusty_jvm::registry::{ClassRegistry, LoaderId};
+use rusty_jvm::gc::{parse_heap_size, CollectorKind};
+use rusty_jvm::vm::{VMOptions, VM};
 
-pub mod vm;
-pub mod env;
-pub mod interpret;
-pub mod loader;
+#[cfg(test)]
+fn fixture(name: &str) -> String {
+    format!("{}/fixtures/classes/{}", env!("CARGO_MANIFEST_DIR"), name)
+}
 
 #[test]
 fn test_basic_math() {
@@ -25,42 +36,1569 @@ fn test_basic_math() {
 
 #[test]
 fn test_basic_class_load() {
-    use loader::Loader;
     let loader = Loader{};
-    println!("{:?}", loader.load_from_file("/tmp/x/Test.class").unwrap());
+    let class = loader.load_from_file(&fixture("Test.class")).unwrap();
+    let listing = disasm::disassemble_to_string(&class);
+    assert!(listing.contains("  Compiled from \"Test.java\""));
+    assert!(listing.contains("public class Test\n"));
+    assert!(listing.contains("  major version: 52"));
+    assert!(listing.contains("  flags: (0x0021) ACC_PUBLIC, ACC_SUPER"));
+    assert!(listing.contains("  public static void main(java.lang.String[]);"));
+    assert!(listing.contains("invokevirtual #"));
+    assert!(listing.contains("// Method java/io/PrintStream.println:(I)V"));
+    assert!(listing.contains("// Field java/lang/System.out:Ljava/io/PrintStream;"));
 }
 
 #[test]
 fn test_hard_class_load() {
-    use loader::Loader;
     let loader = Loader{};
-    println!("{:?}", loader.load_from_file("/tmp/x/More.class").unwrap());
+    let class = loader.load_from_file(&fixture("More.class")).unwrap();
+    let listing = disasm::disassemble_to_string(&class);
+    assert!(listing.contains("public class More implements java.lang.Runnable"));
+    assert!(listing.contains("tableswitch   { // 0 to 2"));
+    assert!(listing.contains("lookupswitch  { // 2"));
+    assert!(listing.contains("                    1000: "));
+    assert!(listing.contains("Class java/lang/ArithmeticException"));
+    assert!(listing.contains("   any"));
+    assert!(listing.contains("// class \"[[I\""));
+    assert!(listing.contains("// class \"[Ljava/lang/String;\""));
+    assert!(listing.contains("frame_type = 252 /* append */"));
+    assert!(listing.contains("locals = [ int ]"));
+    assert!(listing.contains("LocalVariableTable:"));
+    assert!(listing.contains("  this   LMore;"));
+    assert!(listing.contains("// Field counter:I"));
 }
 
 #[test]
 fn test_very_hard_class_load() {
-    use loader::Loader;
     let loader = Loader{};
-    println!("{:?}", loader.load_from_file("/tmp/x/Fields.class").unwrap());
+    let class = loader.load_from_file(&fixture("Fields.class")).unwrap();
+    let listing = disasm::disassemble_to_string(&class);
+    assert!(listing.contains("  public static final int ANSWER;\n    descriptor: I\n    flags: (0x0019) ACC_PUBLIC, ACC_STATIC, ACC_FINAL\n    ConstantValue: int 42"));
+    assert!(listing.contains("ConstantValue: long 1234567890123l"));
+    assert!(listing.contains("ConstantValue: float 1.5f"));
+    assert!(listing.contains("ConstantValue: double 3.14159d"));
+    assert!(listing.contains("ConstantValue: String hello\\n\\\"world\\\""));
+    assert!(listing.contains("  protected volatile boolean flag;"));
+    assert!(listing.contains("  transient char letter;"));
+    assert!(listing.contains("Signature: #"));
+    assert!(listing.contains("Ljava/util/List<Ljava/lang/String;>;"));
+    assert!(listing.contains("  public synchronized long next() throws java.io.IOException;"));
+    assert!(listing.contains("flags: (0x0021) ACC_PUBLIC, ACC_SYNCHRONIZED"));
+    assert!(listing.contains("RuntimeVisibleAnnotations:\n      0: #"));
+    assert!(listing.contains("        java.lang.Deprecated"));
+}
+
+#[test]
+fn test_class_json_export() {
+    use json::{class_to_json, class_to_json_value, JsonOptions, JsonValue};
+    let loader = Loader{};
+    let class = loader.load_from_file(&fixture("Fields.class")).unwrap();
+    let plain = class_to_json_value(&class, JsonOptions::default());
+    assert_eq!(plain.get("schema"), Some(&JsonValue::String(json::SCHEMA.to_string())));
+    assert_eq!(plain.get("super_class"), Some(&JsonValue::String("java/lang/Object".to_string())));
+    let text = class_to_json(&class, JsonOptions::default());
+    assert!(text.starts_with("{\"schema\":\"rusty-jvm/class/1\",\"name\":\"Fields\","));
+    assert!(!text.contains("\"resolved\""));
+
+    let resolved = class_to_json(&class, JsonOptions { resolve_constants: true, instructions: true, pretty: false });
+    assert!(resolved.contains("\"name\":\"ANSWER\",\"descriptor\":\"I\",\"access_flags\":{\"value\":25,\"flags\":[\"ACC_PUBLIC\",\"ACC_STATIC\",\"ACC_FINAL\"]}"));
+    assert!(resolved.contains("{\"name\":\"ConstantValue\",\"value\":{\"index\":"));
+    assert!(resolved.contains(",\"value\":42}"));
+    assert!(resolved.contains("\"value\":\"hello\\n\\\"world\\\"\""));
+    assert!(resolved.contains("\"resolved\":\"java/lang/Object.<init>:()V\""));
+    assert!(resolved.contains("{\"pc\":1,\"opcode\":\"invokespecial\",\"constant\":{\"index\":1,\"value\":\"java/lang/Object.<init>:()V\"}}"));
+    assert!(resolved.contains("\"type\":{\"index\":"));
+    assert!(resolved.contains("\"value\":\"Ljava/lang/Deprecated;\"},\"elements\":[]"));
+}
+
+#[test]
+fn test_unsupported_class_version() {
+    let loader = Loader{};
+    let mut data = std::fs::read(fixture("demo/Greeter.class")).unwrap();
+    data[6..8].copy_from_slice(&62u16.to_be_bytes());
+    let error = loader.load_from_bytes(&data).unwrap_err();
+    assert!(matches!(&error, ClassLoadError::VersionUnsupported(name, 62, 0) if name == "demo/Greeter"));
+    assert_eq!(error.java_exception(), ("java/lang/UnsupportedClassVersionError",
+        "demo.Greeter has been compiled by a more recent version of the Java Runtime (class file version 62.0), \
+        this version of the Java Runtime only recognizes class file versions up to 61.0".to_string()));
+    data[6..8].copy_from_slice(&50u16.to_be_bytes());
+    assert_eq!(loader.load_from_bytes(&data).unwrap_err().java_exception().1, "demo.Greeter (class file version 50.0) is not supported");
+}
+
+#[test]
+fn test_class_path_lookup() {
+    use rusty_jvm::classpath::{Archive, ClassPath};
+    let loader = Loader{};
+    let classes = format!("{}/fixtures/classes", env!("CARGO_MANIFEST_DIR"));
+    for jar in ["classes.jar", "classes-stored.jar"] {
+        let path = format!("{}/fixtures/jars/{}", env!("CARGO_MANIFEST_DIR"), jar);
+        let archive = Archive::open(&path).unwrap();
+        let expected_method = if jar == "classes.jar" { 8 } else { 0 };
+        assert_eq!(archive.entry("demo/Greeter.class").unwrap().method, expected_method);
+        assert_eq!(archive.read("demo/Greeter.class").unwrap().unwrap(), std::fs::read(format!("{}/demo/Greeter.class", classes)).unwrap());
+
+        let class_path = ClassPath::parse(&format!("{}{}{}", path, rusty_jvm::classpath::SEPARATOR, classes)).unwrap();
+        let class = class_path.load_class(&loader, "demo/Greeter").unwrap();
+        assert_eq!(class.class_name, "demo/Greeter");
+        assert!(class.get_method("greet", "(I)I").is_some());
+        assert!(matches!(class_path.load_class(&loader, "demo/Missing"), Err(ClassLoadError::ClassNotFound(name)) if name == "demo/Missing"));
+        assert!(class_path.find_resource("META-INF/MANIFEST.MF").unwrap().unwrap().starts_with(b"Manifest-Version: 1.0"));
+    }
+    let class_path = ClassPath::parse(&classes).unwrap();
+    assert_eq!(class_path.load_class(&loader, "More").unwrap().class_name, "More");
+    assert!(matches!(Archive::open(fixture("Test.class")), Err(ClassLoadError::InvalidArchive(..))));
+}
+
+#[test]
+fn test_manifest_parsing() {
+    use rusty_jvm::manifest::Manifest;
+    let manifest = Manifest::parse(b"Manifest-Version: 1.0\r\nmain-class: com.foo.M\r\n ain\r\nClass-Path: lib/a.jar \r\n lib/my%20b.jar\r\n  classes/\r\n\r\nName: com/foo/\r\nSealed: true\r\n\r\n").unwrap();
+    assert_eq!(manifest.main_class(), Some("com/foo/Main".to_string()));
+    assert_eq!(manifest.class_path(), vec!["lib/a.jar", "lib/my b.jar", "classes/"]);
+    assert!(!manifest.is_multi_release());
+    assert_eq!(manifest.entries.len(), 1);
+    assert_eq!(manifest.entries[0].0, "com/foo/");
+    assert_eq!(manifest.entries[0].1.get("sealed"), Some("true"));
+    assert!(matches!(Manifest::parse(b"Main-Class app.Main\n"), Err(ClassLoadError::InvalidManifest(_))));
+}
+
+#[test]
+fn test_executable_jar_class_path() {
+    let loader = Loader{};
+    let jar = format!("{}/fixtures/jars/app.jar", env!("CARGO_MANIFEST_DIR"));
+    let class_path = ClassPath::for_jar(&jar, 11).unwrap();
+    let manifest = class_path.manifest().unwrap();
+    assert_eq!(manifest.main_class(), Some("app/Main".to_string()));
+    assert!(manifest.is_multi_release());
+    // missing.jar from the manifest Class-Path is skipped, lib.jar is resolved next to app.jar.
+    assert_eq!(class_path.entries().len(), 2);
+    assert!(class_path.entries()[1].path().ends_with("lib.jar"));
+    assert!(class_path.load_class(&loader, "demo/Greeter").is_ok());
+    assert_eq!(class_path.load_class(&loader, "app/Version").unwrap().version_major, 55);
+    assert_eq!(ClassPath::for_jar(&jar, 10).unwrap().load_class(&loader, "app/Version").unwrap().version_major, 52);
+    assert_eq!(ClassPath::for_jar(&jar, 17).unwrap().load_class(&loader, "app/Version").unwrap().version_major, 55);
+    assert!(class_path.load_class(&loader, "app/Main").unwrap().get_main().is_some());
+}
+
+#[test]
+fn test_jimage_class_path() {
+    use rusty_jvm::jimage::Image;
+    use vm::VMValue;
+    // fixtures/gen_jimage.py stores the bundled runtime as java.base, compressed in every way jlink does.
+    let java_home = format!("{}/fixtures/jdk", env!("CARGO_MANIFEST_DIR"));
+    let image = Image::open(format!("{}/lib/modules", java_home)).unwrap();
+    let names = image.names().unwrap();
+    assert_eq!(names.len(), image.resource_count() as usize);
+    for (binary_name, data) in rusty_jvm::rt::CLASSES {
+        let name = format!("/java.base/{}.class", binary_name);
+        assert_eq!(image.read(&name).unwrap().as_deref(), Some(*data), "{}", name);
+        assert_eq!(image.read_resource(&format!("{}.class", binary_name)).unwrap().as_deref(), Some(*data));
+    }
+    let object = image.location("/java.base/java/lang/Object.class").unwrap().unwrap();
+    assert_eq!((object.module.as_str(), object.parent.as_str(), object.base.as_str(), object.extension.as_str()),
+               ("java.base", "java/lang", "Object", "class"));
+    let compressed = names.iter().filter(|name| image.location(name).unwrap().unwrap().compressed_size != 0).count();
+    assert!(compressed > 0 && compressed < names.len());
+    assert_eq!(image.package_module("java/lang").unwrap(), Some("java.base".to_string()));
+    assert_eq!(image.package_module("java/sql").unwrap(), None);
+    assert_eq!(image.read("/java.base/java/lang/Missing.class").unwrap(), None);
+    assert_eq!(image.read_resource("Test.class").unwrap(), None);
+    assert!(matches!(Image::open(fixture("Test.class")), Err(ClassLoadError::InvalidImage(..))));
+
+    // The image as the bootstrap class path in place of the bundled runtime.
+    let class_path = ClassPath::parse(&format!("{}/fixtures/classes", env!("CARGO_MANIFEST_DIR"))).unwrap();
+    let registry = ClassRegistry::new(ClassPath::java_home(&java_home).unwrap(), ClassPath::new(), class_path);
+    let mut env = VMEnv::of(VM::with_classes(1024, registry), Interpreter::new());
+    let exceptions = env.class_for_name(LoaderId::APPLICATION, "Exceptions", true).unwrap();
+    assert_eq!(env.invoke_static(exceptions, "divide", "(II)I", vec![VMValue::Int(6), VMValue::Int(0)]).unwrap(), Some(VMValue::Int(-1)));
+    let message = env.invoke_static(exceptions, "message", "()Ljava/lang/String;", vec![]).unwrap().unwrap().as_reference().unwrap().unwrap();
+    assert_eq!(env.vm().string_value(message).unwrap(), "Index 2 out of bounds for length 2");
+    assert!(matches!(ClassPath::java_home(fixture("")), Err(ClassLoadError::ClassFileReadFailure(_))));
+}
+
+#[test]
+#[ignore = "needs a JDK 17 in JAVA_HOME"]
+fn test_jdk_image() {
+    use rusty_jvm::jimage::Image;
+    let java_home = std::env::var("JAVA_HOME").expect("JAVA_HOME");
+    let image = Image::open(format!("{}/lib/modules", java_home)).unwrap();
+    assert_eq!(image.package_module("java/lang").unwrap(), Some("java.base".to_string()));
+    assert_eq!(image.package_module("java/sql").unwrap(), Some("java.sql".to_string()));
+
+    // fixtures/src17/Modern.java runs against the JDK's own java.base, booted through System.initPhase1,
+    // whose initialization nests deeper than the stack of a test thread allows.
+    let output = std::thread::Builder::new().stack_size(64 << 20).spawn(move || {
+        let class_path = ClassPath::parse(&format!("{}/fixtures/classes", env!("CARGO_MANIFEST_DIR"))).unwrap();
+        let registry = ClassRegistry::new(ClassPath::java_home(&java_home).unwrap(), ClassPath::new(), class_path);
+        let mut env = VMEnv::of(VM::with_classes(1024, registry), Interpreter::new());
+        let stdout = Capture::default();
+        env.stdout = Box::new(stdout.clone());
+        let class = env.class_for_name(LoaderId::APPLICATION, "Modern", false).unwrap();
+        env.run_main(class, &[]).unwrap();
+        stdout.text()
+    }).unwrap().join().unwrap();
+    assert_eq!(output.lines().collect::<Vec<_>>(), [
+        "2+4+6+8+10",
+        "[c, b, a]",
+        "Point[x=1, y=2.5, label=p] true false -1065614287",
+        "1 Square 4.0",
+        "{other=[1, 2, 4, 5], three=[0, 3, 6]}",
+        "built 5 40",
+        "   42|ab  |3.142|ff|1,234,567",
+        "42",
+        "wrapped java.lang.UnsupportedOperationException: reflected",
+        "java.lang.IllegalStateException: boom",
+        "\tat Modern.main(Modern.java:51)",
+        "true main"
+    ]);
+}
+
+#[test]
+fn test_class_loader_delegation() {
+    let classes = format!("{}/fixtures/classes", env!("CARGO_MANIFEST_DIR"));
+    let lib = format!("{}/fixtures/jars/lib.jar", env!("CARGO_MANIFEST_DIR"));
+    let mut boot = ClassPath::runtime();
+    boot.push(&lib).unwrap();
+    let mut registry = ClassRegistry::new(boot, ClassPath::new(), ClassPath::parse(&classes).unwrap());
+
+    // demo/Greeter is on both the boot and the application class path; the bootstrap loader wins.
+    let greeter = registry.load_class(LoaderId::APPLICATION, "demo/Greeter").unwrap();
+    assert_eq!(registry.class(greeter).defining_loader, LoaderId::BOOTSTRAP);
+    assert_eq!(registry.load_class(LoaderId::APPLICATION, "demo/Greeter").unwrap(), greeter);
+    assert_eq!(registry.find_loaded_class(LoaderId::PLATFORM, "demo/Greeter"), Some(greeter));
+
+    let more = registry.load_class(LoaderId::APPLICATION, "More").unwrap();
+    let more_class = registry.class(more);
+    assert_eq!(more_class.defining_loader, LoaderId::APPLICATION);
+    let object = more_class.super_class.unwrap();
+    assert_eq!(registry.class(object).name, "java/lang/Object");
+    assert_eq!(registry.class(object).defining_loader, LoaderId::BOOTSTRAP);
+    assert_eq!(registry.class(more_class.interfaces[0]).name, "java/lang/Runnable");
+    assert!(matches!(registry.load_class(LoaderId::PLATFORM, "More"), Err(ClassLoadError::ClassNotFound(_))));
+
+    // A loader defined in Java: defineClass, duplicate definitions and wrong names.
+    let custom = registry.add_loader("custom", LoaderId::APPLICATION).unwrap();
+    let data = std::fs::read(fixture("Test.class")).unwrap();
+    let test = registry.define_class(custom, Some("Test"), &data).unwrap();
+    assert_eq!(registry.class(test).defining_loader, custom);
+    assert_eq!(registry.load_class(custom, "Test").unwrap(), test);
+    let duplicate = registry.define_class(custom, None, &data).unwrap_err();
+    assert_eq!(duplicate.java_exception(), ("java/lang/LinkageError", "loader 'custom' attempted duplicate class definition for Test.".to_string()));
+    // The application loader can still define its own, distinct Test class.
+    let app_test = registry.load_class(LoaderId::APPLICATION, "Test").unwrap();
+    assert_ne!(app_test, test);
+    assert_eq!(registry.load_class(custom, "More").unwrap(), more);
+    let other = registry.add_loader("other", custom).unwrap();
+    assert!(matches!(registry.define_class(other, Some("Other"), &data), Err(ClassLoadError::WrongName(..))));
+    let object_data = rusty_jvm::rt::find_class("java/lang/Object").unwrap();
+    assert!(matches!(registry.define_class(other, None, object_data), Err(ClassLoadError::ProhibitedPackage(_))));
+}
+
+/// Collects what a program prints.
+#[cfg(test)]
+#[derive(Clone, Default)]
+struct Capture(std::rc::Rc<std::cell::RefCell<Vec<u8>>>);
+
+#[cfg(test)]
+impl Write for Capture {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0.borrow_mut().extend_from_slice(buf);
+        Ok(buf.len())
+    }
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+impl Capture {
+    fn text(&self) -> String {
+        String::from_utf8(self.0.borrow().clone()).unwrap()
+    }
+}
+
+#[cfg(test)]
+fn fixture_env() -> VMEnv {
+    let class_path = ClassPath::parse(&format!("{}/fixtures/classes", env!("CARGO_MANIFEST_DIR"))).unwrap();
+    let registry = ClassRegistry::new(ClassPath::runtime(), ClassPath::new(), class_path);
+    VMEnv::of(VM::with_classes(1024, registry), Interpreter::new())
+}
+
+#[test]
+fn test_class_initialization() {
+    use rusty_jvm::registry::ClassState;
+    use vm::VMValue;
+    let mut env = fixture_env();
+    let init = env.class_for_name(LoaderId::APPLICATION, "Init", false).unwrap();
+    let derived = env.class_for_name(LoaderId::APPLICATION, "Init$Derived", false).unwrap();
+    assert_eq!(env.vm().classes.class(derived).state, ClassState::Loaded);
+
+    // invokestatic initializes the superclass first, then the class itself.
+    assert_eq!(env.invoke_static(derived, "get", "()I", vec![]).unwrap(), Some(VMValue::Int(5)));
+    let base = env.vm().classes.class(derived).super_class.unwrap();
+    let classes = &env.vm().classes;
+    assert_eq!(classes.class(base).static_value("order"), Some(VMValue::Int(1)));
+    assert_eq!(classes.class(derived).static_value("order"), Some(VMValue::Int(2)));
+    assert_eq!(classes.class(derived).state, ClassState::Initialized);
+    assert_eq!(classes.class(init).state, ClassState::Initialized);
+
+    // Reflection with initialize = false only loads; ConstantValue fields are set on initialization.
+    let constants = env.class_for_name(LoaderId::APPLICATION, "Init$Constants", false).unwrap();
+    assert_eq!(env.vm().classes.class(constants).state, ClassState::Loaded);
+    env.class_for_name(LoaderId::APPLICATION, "Init$Constants", true).unwrap();
+    assert_eq!(env.vm().classes.class(constants).static_value("BIG"), Some(VMValue::Long(1234567890123)));
+    assert_eq!(env.vm().classes.class(constants).static_value("order"), Some(VMValue::Int(3)));
+
+    // Only superinterfaces declaring default methods are initialized along with a class.
+    let implementation = env.class_for_name(LoaderId::APPLICATION, "Init$Impl", true).unwrap();
+    let interfaces = env.vm().classes.class(implementation).interfaces.clone();
+    assert_eq!(env.vm().classes.class(interfaces[0]).static_value("ORDER"), Some(VMValue::Int(4)));
+    assert_eq!(env.vm().classes.class(interfaces[1]).state, ClassState::Prepared);
+    assert_eq!(env.vm().classes.class(implementation).static_value("order"), Some(VMValue::Int(5)));
+
+    // An Error from <clinit> propagates as is, later accesses see NoClassDefFoundError.
+    let failing = env.class_for_name(LoaderId::APPLICATION, "Init$Failing", false).unwrap();
+    match env.invoke_static(failing, "get", "()I", vec![]) {
+        Err(InterpreterError::Exception(e)) => assert_eq!(e.to_string(), "java.lang.UnsatisfiedLinkError: 'int Init$Failing.broken()'"),
+        other => panic!("unexpected {:?}", other)
+    }
+    assert_eq!(env.vm().classes.class(failing).state, ClassState::Erroneous);
+    match env.invoke_static(failing, "get", "()I", vec![]) {
+        Err(InterpreterError::Exception(e)) => assert_eq!(e.to_string(), "java.lang.NoClassDefFoundError: Could not initialize class Init$Failing"),
+        other => panic!("unexpected {:?}", other)
+    }
 }
 
 #[test]
+#[ignore = "needs the Kotlin build output of the cleaner project"]
 fn test_impossibly_hard_class_load() {
-    use loader::Loader;
     let loader = Loader{};
-    println!("{:?}", loader.load_from_file("/Users/user/IdeaProjects/cleaner/build/classes/kotlin/main/Options.class").unwrap());
+    let class = loader.load_from_file("/Users/user/IdeaProjects/cleaner/build/classes/kotlin/main/Options.class").unwrap();
+    println!("{}", disasm::disassemble_to_string(&class));
 }
 
 #[test]
+#[ignore = "needs the Kotlin build output of the cleaner project"]
 fn test_impossibly_hard_class_load_with_interpret() {
-    use loader::Loader;
+    let class_path = ClassPath::parse("/Users/user/IdeaProjects/cleaner/build/classes/kotlin/main").unwrap();
+    let registry = ClassRegistry::new(ClassPath::runtime(), ClassPath::new(), class_path);
+    let mut env = VMEnv::of(VM::with_classes(1024, registry), Interpreter::new());
+    let class = env.class_for_name(LoaderId::APPLICATION, "Options", false).unwrap();
+    env.run_main(class, &[]).expect("executed");
+}
+
+#[test]
+fn test_objects_and_fields() {
+    use rusty_jvm::descriptor::FieldType;
+    use vm::{Heap, VMValue};
+    let mut env = fixture_env();
+    let objects = env.class_for_name(LoaderId::APPLICATION, "Objects", true).unwrap();
+    let mut call = |name: &str, descriptor: &str, args: Vec<VMValue>| env.invoke_static(objects, name, descriptor, args);
+
+    // Virtual dispatch, super calls, hidden fields and default methods.
+    assert_eq!(call("dispatch", "()I", vec![]).unwrap(), Some(VMValue::Int(6)));
+    assert_eq!(call("shadowed", "()I", vec![]).unwrap(), Some(VMValue::Int(6)));
+    assert_eq!(call("interfaces", "()I", vec![]).unwrap(), Some(VMValue::Int(8)));
+    assert_eq!(call("wide", "(JD)J", vec![VMValue::Long(1 << 40), VMValue::Double(0.5)]).unwrap(), Some(VMValue::Long(1 << 40)));
+    assert_eq!(call("same", "()I", vec![]).unwrap(), Some(VMValue::Int(1)));
+    match call("nullField", "()I", vec![]) {
+        Err(InterpreterError::Exception(e)) => assert_eq!(e.class_name, "java/lang/NullPointerException"),
+        other => panic!("unexpected {:?}", other)
+    }
+
+
+    // Inherited fields keep their offsets, wide fields are aligned and come first.
+    let point3 = env.class_for_name(LoaderId::APPLICATION, "Objects$Point3", true).unwrap();
+    let point = env.vm().classes.class(point3).super_class.unwrap();
+    let point_layout = env.vm().classes.class(point).layout.clone();
+    let layout = env.vm().classes.class(point3).layout.clone();
+    assert_eq!(point_layout.field_offsets, vec![Some(0), Some(4)]);
+    assert_eq!(layout.field_offsets, vec![Some(24), Some(8), Some(16), Some(36), Some(32), Some(34), Some(37), Some(28)]);
+    assert_eq!(layout.size, 38);
+    assert_eq!(layout.reference_offsets, vec![28]);
+
+    let obj = env.vm_mut().new_object(point3).unwrap();
+    let heap = &mut env.vm_mut().heap;
+    assert_eq!(heap.size_of(obj), 64);
+    let hash = heap.identity_hash(obj);
+    assert_ne!(hash, 0);
+    assert_eq!(heap.identity_hash(obj), hash);
+    let locked = env.invoke_static(objects, "locked", "(LObjects$Point;)I", vec![VMValue::Reference(obj)]).unwrap();
+    assert_eq!(locked, Some(VMValue::Int(0)));
+    assert_eq!(env.vm().heap.monitor_count(obj), 0);
+
+    // Sub-int fields are narrowed on store and widened with their signedness on load.
+    let heap = &mut env.vm_mut().heap;
+    let (byte, char, short, boolean) = (36, 32, 34, 37);
+    assert!(heap.write_field(obj, byte, &FieldType::Byte, VMValue::Int(200)));
+    assert!(heap.write_field(obj, char, &FieldType::Char, VMValue::Int(-1)));
+    assert!(heap.write_field(obj, short, &FieldType::Short, VMValue::Int(40000)));
+    assert!(heap.write_field(obj, boolean, &FieldType::Boolean, VMValue::Int(2)));
+    assert_eq!(heap.read_field(obj, byte, &FieldType::Byte), VMValue::Int(-56));
+    assert_eq!(heap.read_field(obj, char, &FieldType::Char), VMValue::Int(65535));
+    assert_eq!(heap.read_field(obj, short, &FieldType::Short), VMValue::Int(-25536));
+    assert_eq!(heap.read_field(obj, boolean, &FieldType::Boolean), VMValue::Int(0));
+    assert!(!heap.write_field(obj, byte, &FieldType::Long, VMValue::Int(1)));
+    assert_eq!(Heap::HEADER_SIZE, 24);
+}
+
+#[test]
+fn test_arrays() {
+    use vm::VMValue;
+    let mut env = fixture_env();
+    let arrays = env.class_for_name(LoaderId::APPLICATION, "Arrays", true).unwrap();
+    let mut call = |name: &str, descriptor: &str, args: Vec<VMValue>| env.invoke_static(arrays, name, descriptor, args);
+    let exception = |result: Result<Option<VMValue>, InterpreterError>| match result {
+        Err(InterpreterError::Exception(e)) => e.to_string(),
+        other => panic!("unexpected {:?}", other)
+    };
+
+    assert_eq!(call("ints", "()I", vec![]).unwrap(), Some(VMValue::Int(10)));
+    assert_eq!(call("longs", "(J)J", vec![VMValue::Long(-1 << 40)]).unwrap(), Some(VMValue::Long(-1 << 40)));
+    assert_eq!(call("doubles", "(D)D", vec![VMValue::Double(2.5)]).unwrap(), Some(VMValue::Double(2.5)));
+    assert_eq!(call("bytes", "(B)I", vec![VMValue::Int(-3)]).unwrap(), Some(VMValue::Int(-3)));
+    assert_eq!(call("chars", "(C)I", vec![VMValue::Int(0xffff)]).unwrap(), Some(VMValue::Int(0xffff)));
+    assert_eq!(call("shorts", "(S)I", vec![VMValue::Int(-2)]).unwrap(), Some(VMValue::Int(-2)));
+    assert_eq!(call("booleans", "()Z", vec![]).unwrap(), Some(VMValue::Int(1)));
+    assert_eq!(call("matrix", "()I", vec![]).unwrap(), Some(VMValue::Int(10)));
+    assert_eq!(call("jagged", "()I", vec![]).unwrap(), Some(VMValue::Int(1)));
+    assert_eq!(exception(call("outOfBounds", "(I)I", vec![VMValue::Int(3)])), "java.lang.ArrayIndexOutOfBoundsException: Index 3 out of bounds for length 3");
+    assert_eq!(exception(call("outOfBounds", "(I)I", vec![VMValue::Int(-1)])), "java.lang.ArrayIndexOutOfBoundsException: Index -1 out of bounds for length 3");
+    assert_eq!(exception(call("negative", "(I)I", vec![VMValue::Int(-2)])), "java.lang.NegativeArraySizeException: -2");
+    assert_eq!(exception(call("negativeMatrix", "(I)I", vec![VMValue::Int(-1)])), "java.lang.NegativeArraySizeException: -1");
+
+    // Arrays are covariant, so stores into them are checked against the runtime element type.
+    let runnables = call("runnables", "()[Ljava/lang/Object;", vec![]).unwrap().unwrap();
+    let object_class = env.class_for_name(LoaderId::BOOTSTRAP, "java/lang/Object", true).unwrap();
+    let object = VMValue::Reference(env.vm_mut().new_object(object_class).unwrap());
+    let store = env.invoke_static(arrays, "store", "([Ljava/lang/Object;Ljava/lang/Object;)I", vec![runnables, object]);
+    assert_eq!(exception(store), "java.lang.ArrayStoreException: java.lang.Object");
+    let store = env.invoke_static(arrays, "store", "([Ljava/lang/Object;Ljava/lang/Object;)I", vec![runnables, VMValue::Null]);
+    assert_eq!(store.unwrap(), Some(VMValue::Int(1)));
+
+    // Array classes are created on demand, defined by the loader of their element class.
+    let classes = &mut env.vm_mut().classes;
+    let matrix = classes.load_class(LoaderId::APPLICATION, "[[Ljava/lang/Object;").unwrap();
+    assert_eq!(classes.class(matrix).defining_loader, LoaderId::BOOTSTRAP);
+    assert_eq!(classes.class(matrix).super_class, Some(object_class));
+    let points = classes.load_class(LoaderId::APPLICATION, "[LObjects$Point;").unwrap();
+    let points3 = classes.load_class(LoaderId::APPLICATION, "[LObjects$Point3;").unwrap();
+    assert_eq!(classes.class(points).defining_loader, LoaderId::APPLICATION);
+    let ints = classes.load_class(LoaderId::APPLICATION, "[I").unwrap();
+    let longs = classes.load_class(LoaderId::APPLICATION, "[J").unwrap();
+    let objects = classes.load_class(LoaderId::APPLICATION, "[Ljava/lang/Object;").unwrap();
+    let cloneable = classes.load_class(LoaderId::BOOTSTRAP, "java/lang/Cloneable").unwrap();
+    assert!(classes.is_assignable(points3, points));
+    assert!(classes.is_assignable(points3, objects));
+    assert!(classes.is_assignable(matrix, objects));
+    assert!(!classes.is_assignable(points, points3));
+    assert!(classes.is_assignable(ints, cloneable));
+    assert!(!classes.is_assignable(ints, longs));
+    assert!(!classes.is_assignable(ints, objects));
+}
+
+#[test]
+fn test_value_conversions() {
+    use rusty_jvm::descriptor::FieldType;
+    use vm::{ObjRef, VMValue};
+    assert_eq!(VMValue::from(-56i8), VMValue::Byte(-56));
+    assert_eq!(VMValue::Byte(-56).computational(), VMValue::Int(-56));
+    assert_eq!(VMValue::Char(0xffff).computational(), VMValue::Int(65535));
+    assert_eq!(VMValue::Short(-2).as_int().unwrap(), -2);
+    assert_eq!(VMValue::from(true).as_int().unwrap(), 1);
+    assert_eq!(VMValue::Int(200).narrow_to(&FieldType::Byte), VMValue::Byte(-56));
+    assert_eq!(VMValue::Int(-1).narrow_to(&FieldType::Char), VMValue::Char(0xffff));
+    assert_eq!(VMValue::Int(2).narrow_to(&FieldType::Boolean), VMValue::Boolean(false));
+    assert_eq!(VMValue::Int(7).narrow_to(&FieldType::Int), VMValue::Int(7));
+
+    assert_eq!(i64::try_from(VMValue::from(1i64 << 40)).unwrap(), 1 << 40);
+    assert_eq!(f64::try_from(VMValue::from(0.5f64)).unwrap(), 0.5);
+    assert_eq!(u16::try_from(VMValue::Int(65)).unwrap(), 65);
+    assert_eq!(Option::<ObjRef>::try_from(VMValue::from(Some(ObjRef(8)))).unwrap(), Some(ObjRef(8)));
+    assert_eq!(VMValue::from(None::<ObjRef>), VMValue::Null);
+    assert_eq!(VMValue::Null.as_reference().unwrap(), None);
+    match VMValue::Long(3).as_int() {
+        Err(InterpreterError::TypeMismatch(expected, actual)) => assert_eq!((expected.as_str(), actual.as_str()), ("int", "Long(3)")),
+        other => panic!("unexpected {:?}", other)
+    }
+    assert!(i32::try_from(VMValue::Null).is_err());
+    assert!(VMValue::Int(1).as_return_address().is_err());
+    assert_eq!(VMValue::ReturnAddress(12).type_name(), "returnAddress");
+    assert!(!VMValue::ReturnAddress(12).is_category2());
+}
+
+#[test]
+fn test_integer_arithmetic_conformance() {
+    use vm::VMValue::{self, Int, Long};
+    let mut env = fixture_env();
+    let math = env.class_for_name(LoaderId::APPLICATION, "IntegerMath", true).unwrap();
+    let (min, max) = (i32::MIN, i32::MAX);
+    let (lmin, lmax) = (i64::MIN, i64::MAX);
+    let table: Vec<(&str, &str, Vec<VMValue>, VMValue)> = vec![
+        ("iadd", "(II)I", vec![Int(max), Int(1)], Int(min)),
+        ("isub", "(II)I", vec![Int(min), Int(1)], Int(max)),
+        ("imul", "(II)I", vec![Int(0x10000), Int(0x10000)], Int(0)),
+        ("imul", "(II)I", vec![Int(max), Int(2)], Int(-2)),
+        ("idiv", "(II)I", vec![Int(7), Int(-2)], Int(-3)),
+        ("idiv", "(II)I", vec![Int(min), Int(-1)], Int(min)),
+        ("irem", "(II)I", vec![Int(-7), Int(2)], Int(-1)),
+        ("irem", "(II)I", vec![Int(7), Int(-2)], Int(1)),
+        ("irem", "(II)I", vec![Int(min), Int(-1)], Int(0)),
+        ("iand", "(II)I", vec![Int(0b1100), Int(0b1010)], Int(0b1000)),
+        ("ior", "(II)I", vec![Int(0b1100), Int(0b1010)], Int(0b1110)),
+        ("ixor", "(II)I", vec![Int(0b1100), Int(0b1010)], Int(0b0110)),
+        ("ishl", "(II)I", vec![Int(1), Int(31)], Int(min)),
+        ("ishl", "(II)I", vec![Int(1), Int(33)], Int(2)),
+        ("ishl", "(II)I", vec![Int(1), Int(-1)], Int(min)),
+        ("ishr", "(II)I", vec![Int(-16), Int(2)], Int(-4)),
+        ("ishr", "(II)I", vec![Int(-16), Int(34)], Int(-4)),
+        ("iushr", "(II)I", vec![Int(-16), Int(28)], Int(15)),
+        ("iushr", "(II)I", vec![Int(-1), Int(32)], Int(-1)),
+        ("ineg", "(I)I", vec![Int(min)], Int(min)),
+        ("ineg", "(I)I", vec![Int(5)], Int(-5)),
+        ("ladd", "(JJ)J", vec![Long(lmax), Long(1)], Long(lmin)),
+        ("lsub", "(JJ)J", vec![Long(lmin), Long(1)], Long(lmax)),
+        ("lmul", "(JJ)J", vec![Long(1 << 32), Long(1 << 32)], Long(0)),
+        ("ldiv", "(JJ)J", vec![Long(lmin), Long(-1)], Long(lmin)),
+        ("ldiv", "(JJ)J", vec![Long(-7), Long(2)], Long(-3)),
+        ("lrem", "(JJ)J", vec![Long(-7), Long(2)], Long(-1)),
+        ("lrem", "(JJ)J", vec![Long(lmin), Long(-1)], Long(0)),
+        ("land", "(JJ)J", vec![Long(-1), Long(1 << 40)], Long(1 << 40)),
+        ("lor", "(JJ)J", vec![Long(1 << 40), Long(1)], Long((1 << 40) | 1)),
+        ("lxor", "(JJ)J", vec![Long(-1), Long(0)], Long(-1)),
+        ("lshl", "(JI)J", vec![Long(1), Int(63)], Long(lmin)),
+        ("lshl", "(JI)J", vec![Long(1), Int(65)], Long(2)),
+        ("lshr", "(JI)J", vec![Long(-16), Int(66)], Long(-4)),
+        ("lushr", "(JI)J", vec![Long(-1), Int(60)], Long(15)),
+        ("lushr", "(JI)J", vec![Long(-1), Int(64)], Long(-1)),
+        ("lneg", "(J)J", vec![Long(lmin)], Long(lmin)),
+        ("lcmp", "(JJ)I", vec![Long(lmin), Long(lmax)], Int(-1)),
+        ("lcmp", "(JJ)I", vec![Long(lmax), Long(lmin)], Int(1)),
+        ("lcmp", "(JJ)I", vec![Long(3), Long(3)], Int(0)),
+        ("i2l", "(I)J", vec![Int(-1)], Long(-1)),
+        ("l2i", "(J)I", vec![Long(0x1_8000_0001)], Int(min + 1)),
+        ("i2b", "(I)I", vec![Int(0x1ff)], Int(-1)),
+        ("i2c", "(I)I", vec![Int(-1)], Int(0xffff)),
+        ("i2s", "(I)I", vec![Int(0x18000)], Int(-32768)),
+        ("compound", "(I)I", vec![Int(1)], Int(24)),
+    ];
+    for (name, descriptor, args, expected) in table {
+        let result = env.invoke_static(math, name, descriptor, args.clone()).unwrap();
+        assert_eq!(result, Some(expected), "{}{:?}", name, args);
+    }
+
+    for (name, descriptor, args) in [("idiv", "(II)I", vec![Int(1), Int(0)]), ("irem", "(II)I", vec![Int(1), Int(0)]),
+                                     ("ldiv", "(JJ)J", vec![Long(1), Long(0)]), ("lrem", "(JJ)J", vec![Long(1), Long(0)])] {
+        match env.invoke_static(math, name, descriptor, args) {
+            Err(InterpreterError::Exception(e)) => assert_eq!(e.to_string(), "java.lang.ArithmeticException: / by zero"),
+            other => panic!("unexpected {:?}", other)
+        }
+    }
+}
+
+#[test]
+fn test_floating_point_conformance() {
+    use vm::VMValue::{self, Int, Long, Float, Double};
+    let mut env = fixture_env();
+    let math = env.class_for_name(LoaderId::APPLICATION, "FloatMath", true).unwrap();
+    let (nan, inf) = (f32::NAN, f32::INFINITY);
+    let (dnan, dinf) = (f64::NAN, f64::INFINITY);
+    let table: Vec<(&str, &str, Vec<VMValue>, VMValue)> = vec![
+        ("fadd", "(FF)F", vec![Float(0.1), Float(0.2)], Float(0.1f32 + 0.2f32)),
+        ("fadd", "(FF)F", vec![Float(inf), Float(-inf)], Float(nan)),
+        ("fsub", "(FF)F", vec![Float(0.0), Float(0.0)], Float(0.0)),
+        ("fmul", "(FF)F", vec![Float(f32::MAX), Float(2.0)], Float(inf)),
+        ("fdiv", "(FF)F", vec![Float(1.0), Float(-0.0)], Float(-inf)),
+        ("fdiv", "(FF)F", vec![Float(0.0), Float(0.0)], Float(nan)),
+        ("frem", "(FF)F", vec![Float(5.5), Float(2.0)], Float(1.5)),
+        ("frem", "(FF)F", vec![Float(-5.5), Float(2.0)], Float(-1.5)),
+        ("frem", "(FF)F", vec![Float(5.0), Float(3.0)], Float(2.0)),
+        ("frem", "(FF)F", vec![Float(1.0), Float(0.0)], Float(nan)),
+        ("frem", "(FF)F", vec![Float(1.0), Float(inf)], Float(1.0)),
+        ("fneg", "(F)F", vec![Float(0.0)], Float(-0.0)),
+        ("dadd", "(DD)D", vec![Double(0.1), Double(0.2)], Double(0.30000000000000004)),
+        ("dsub", "(DD)D", vec![Double(dinf), Double(dinf)], Double(dnan)),
+        ("dmul", "(DD)D", vec![Double(-0.0), Double(5.0)], Double(-0.0)),
+        ("ddiv", "(DD)D", vec![Double(1.0), Double(3.0)], Double(1.0 / 3.0)),
+        ("drem", "(DD)D", vec![Double(5.0), Double(3.0)], Double(2.0)),
+        ("drem", "(DD)D", vec![Double(-7.5), Double(-2.0)], Double(-1.5)),
+        ("drem", "(DD)D", vec![Double(-0.0), Double(1.0)], Double(-0.0)),
+        ("dneg", "(D)D", vec![Double(dnan)], Double(dnan)),
+        ("fless", "(FF)Z", vec![Float(nan), Float(1.0)], Int(0)),
+        ("fgreater", "(FF)Z", vec![Float(nan), Float(1.0)], Int(0)),
+        ("fless", "(FF)Z", vec![Float(-0.0), Float(0.0)], Int(0)),
+        ("fequal", "(FF)Z", vec![Float(-0.0), Float(0.0)], Int(1)),
+        ("fequal", "(FF)Z", vec![Float(nan), Float(nan)], Int(0)),
+        ("dless", "(DD)Z", vec![Double(1.0), Double(dnan)], Int(0)),
+        ("dgreater", "(DD)Z", vec![Double(1.0), Double(dnan)], Int(0)),
+        ("dgreater", "(DD)Z", vec![Double(dinf), Double(f64::MAX)], Int(1)),
+        ("dequal", "(DD)Z", vec![Double(dnan), Double(dnan)], Int(0)),
+        ("i2f", "(I)F", vec![Int(16777217)], Float(16777216.0)),
+        ("i2d", "(I)D", vec![Int(i32::MIN)], Double(-2147483648.0)),
+        ("l2f", "(J)F", vec![Long(i64::MAX)], Float(9.223372e18)),
+        ("l2d", "(J)D", vec![Long((1 << 53) + 1)], Double(9007199254740992.0)),
+        ("f2i", "(F)I", vec![Float(nan)], Int(0)),
+        ("f2i", "(F)I", vec![Float(-1e20)], Int(i32::MIN)),
+        ("f2i", "(F)I", vec![Float(inf)], Int(i32::MAX)),
+        ("f2i", "(F)I", vec![Float(-2.9)], Int(-2)),
+        ("f2l", "(F)J", vec![Float(1e30)], Long(i64::MAX)),
+        ("f2l", "(F)J", vec![Float(nan)], Long(0)),
+        ("f2d", "(F)D", vec![Float(0.1)], Double(0.1f32 as f64)),
+        ("d2i", "(D)I", vec![Double(3e9)], Int(i32::MAX)),
+        ("d2i", "(D)I", vec![Double(-dinf)], Int(i32::MIN)),
+        ("d2l", "(D)J", vec![Double(dnan)], Long(0)),
+        ("d2l", "(D)J", vec![Double(-1e300)], Long(i64::MIN)),
+        ("d2l", "(D)J", vec![Double(-2.5)], Long(-2)),
+        ("d2f", "(D)F", vec![Double(1e300)], Float(inf)),
+        ("d2f", "(D)F", vec![Double(1e-50)], Float(0.0)),
+        ("d2f", "(D)F", vec![Double(dnan)], Float(nan)),
+    ];
+    // Compares bit patterns, so NaN matches NaN and 0.0 does not match -0.0.
+    let same = |a: VMValue, b: VMValue| match (a, b) {
+        (Float(a), Float(b)) => a.to_bits() == b.to_bits() || (a.is_nan() && b.is_nan()),
+        (Double(a), Double(b)) => a.to_bits() == b.to_bits() || (a.is_nan() && b.is_nan()),
+        (a, b) => a == b
+    };
+    for (name, descriptor, args, expected) in table {
+        let result = env.invoke_static(math, name, descriptor, args.clone()).unwrap().unwrap();
+        assert!(same(result, expected), "{}{:?} = {:?}, expected {:?}", name, args, result, expected);
+    }
+
+    assert_eq!(env::compare_floating(f64::NAN, 1.0, -1), -1);
+    assert_eq!(env::compare_floating(f64::NAN, 1.0, 1), 1);
+    assert_eq!(env::compare_floating(-0.0, 0.0, 1), 0);
+    assert_eq!(env::compare_floating(1.0, 2.0, 1), -1);
+}
+
+#[test]
+fn test_constant_loading() {
+    use vm::VMValue;
+    let mut env = fixture_env();
+    let literals = env.class_for_name(LoaderId::APPLICATION, "Literals", true).unwrap();
+    let call = |env: &mut VMEnv, class, name: &str, descriptor: &str| env.invoke_static(class, name, descriptor, vec![]).unwrap().unwrap();
+    assert_eq!(call(&mut env, literals, "small", "()I"), VMValue::Int(-100));
+    assert_eq!(call(&mut env, literals, "medium", "()I"), VMValue::Int(30000));
+    assert_eq!(call(&mut env, literals, "big", "()I"), VMValue::Int(1234567));
+    assert_eq!(call(&mut env, literals, "one", "()J"), VMValue::Long(1));
+    assert_eq!(call(&mut env, literals, "wide", "()J"), VMValue::Long(1234567890123));
+    assert_eq!(call(&mut env, literals, "two", "()F"), VMValue::Float(2.0));
+    assert_eq!(call(&mut env, literals, "half", "()F"), VMValue::Float(2.5));
+    assert_eq!(call(&mut env, literals, "unit", "()D"), VMValue::Double(1.0));
+    assert_eq!(call(&mut env, literals, "quarter", "()D"), VMValue::Double(3.25));
+
+    // String literals and String constant fields share one interned object.
+    let greeting = call(&mut env, literals, "greeting", "()Ljava/lang/String;").as_reference().unwrap().unwrap();
+    assert_eq!(env.vm().classes.class(literals).static_value("GREETING"), Some(VMValue::Reference(greeting)));
+    assert_eq!(env.vm_mut().intern_string("hello").unwrap(), greeting);
+    assert_eq!(env.vm().string_value(greeting).unwrap(), "hello");
+    let unicode = call(&mut env, literals, "unicode", "()Ljava/lang/String;").as_reference().unwrap().unwrap();
+    assert_eq!(env.vm().string_value(unicode).unwrap(), "h\u{e9}\u{20ac}");
+
+    // Class literals are the classes' mirrors.
+    let mirror = call(&mut env, literals, "self", "()Ljava/lang/Class;").as_reference().unwrap().unwrap();
+    assert_eq!(env.vm().mirrored_class(mirror).unwrap(), literals);
+    assert_eq!(call(&mut env, literals, "self", "()Ljava/lang/Class;"), VMValue::Reference(mirror));
+    let ints = call(&mut env, literals, "ints", "()Ljava/lang/Class;").as_reference().unwrap().unwrap();
+    let ints = env.vm().mirrored_class(ints).unwrap();
+    assert_eq!(env.vm().classes.class(ints).name, "[I");
+    let matrix = call(&mut env, literals, "matrix", "()Ljava/lang/Class;").as_reference().unwrap().unwrap();
+    let name = env.vm().get_field(matrix, "name").unwrap().as_reference().unwrap().unwrap();
+    assert_eq!(env.vm().string_value(name).unwrap(), "[[Ljava.lang.Object;");
+
+    // CONSTANT_Dynamic, MethodType and MethodHandle come from a generated class file.
+    let condy = env.class_for_name(LoaderId::APPLICATION, "Condy", true).unwrap();
+    assert_eq!(call(&mut env, condy, "answer", "()I"), VMValue::Int(42));
+    assert_eq!(call(&mut env, condy, "withArgs", "()I"), VMValue::Int(7));
+    let name = call(&mut env, condy, "name", "()Ljava/lang/Object;");
+    assert_eq!(env.vm().string_value(name.as_reference().unwrap().unwrap()).unwrap(), "constantName");
+    assert_eq!(call(&mut env, condy, "name", "()Ljava/lang/Object;"), name);
+
+    let type_name = |env: &VMEnv, mirror: VMValue| {
+        let name = env.vm().get_field(mirror.as_reference().unwrap().unwrap(), "name").unwrap();
+        env.vm().string_value(name.as_reference().unwrap().unwrap()).unwrap()
+    };
+    let method_type = call(&mut env, condy, "methodType", "()Ljava/lang/Object;").as_reference().unwrap().unwrap();
+    assert_eq!(type_name(&env, env.vm().get_field(method_type, "rtype").unwrap()), "java.lang.String");
+    let parameters = env.vm().get_field(method_type, "ptypes").unwrap().as_reference().unwrap().unwrap();
+    assert_eq!(env.vm().heap.array_length(parameters), 2);
+    let long = env.vm().heap.read_element(parameters, 1, &rusty_jvm::descriptor::FieldType::Object("java/lang/Class".to_string()));
+    assert_eq!(type_name(&env, long), "long");
+
+    let handle = call(&mut env, condy, "handle", "()Ljava/lang/Object;").as_reference().unwrap().unwrap();
+    assert_eq!(env.vm().get_field(handle, "referenceKind").unwrap(), VMValue::Int(6));
+    let handle_type = env.vm().get_field(handle, "type").unwrap().as_reference().unwrap().unwrap();
+    assert_eq!(type_name(&env, env.vm().get_field(handle_type, "rtype").unwrap()), "int");
+    let getter = call(&mut env, condy, "getter", "()Ljava/lang/Object;").as_reference().unwrap().unwrap();
+    let getter_type = env.vm().get_field(getter, "type").unwrap().as_reference().unwrap().unwrap();
+    let parameters = env.vm().get_field(getter_type, "ptypes").unwrap().as_reference().unwrap().unwrap();
+    let receiver = env.vm().heap.read_element(parameters, 0, &rusty_jvm::descriptor::FieldType::Object("java/lang/Class".to_string()));
+    assert_eq!(type_name(&env, receiver), "Condy");
+}
+
+#[test]
+fn test_exception_handling() {
+    use vm::VMValue;
+    let mut env = fixture_env();
+    let exceptions = env.class_for_name(LoaderId::APPLICATION, "Exceptions", true).unwrap();
+    let mut call = |name: &str, descriptor: &str, args: Vec<VMValue>| env.invoke_static(exceptions, name, descriptor, args);
+    assert_eq!(call("divide", "(II)I", vec![VMValue::Int(6), VMValue::Int(3)]).unwrap(), Some(VMValue::Int(2)));
+    assert_eq!(call("divide", "(II)I", vec![VMValue::Int(6), VMValue::Int(0)]).unwrap(), Some(VMValue::Int(-1)));
+    assert_eq!(call("finallyRuns", "(Z)I", vec![VMValue::Int(0)]).unwrap(), Some(VMValue::Int(11)));
+    assert_eq!(call("finallyRuns", "(Z)I", vec![VMValue::Int(1)]).unwrap(), Some(VMValue::Int(12)));
+    assert_eq!(call("unwind", "()I", vec![]).unwrap(), Some(VMValue::Int(7)));
+    assert_eq!(call("nullPointer", "()Z", vec![]).unwrap(), Some(VMValue::Int(1)));
+    let message = call("message", "()Ljava/lang/String;", vec![]).unwrap().unwrap().as_reference().unwrap().unwrap();
+    assert_eq!(env.vm().string_value(message).unwrap(), "Index 2 out of bounds for length 2");
+
+    // A finally block runs and rethrows when nothing catches the exception.
+    match env.invoke_static(exceptions, "uncaughtFinally", "()I", vec![]) {
+        Err(InterpreterError::Exception(e)) => {
+            assert_eq!(e.to_string(), "Exceptions$Failure: deep");
+            assert!(e.object.is_some());
+        }
+        other => panic!("expected Failure, got {:?}", other)
+    }
+    assert_eq!(env.vm().classes.class(exceptions).static_value("counter"), Some(VMValue::Int(99)));
+
+    let error = env.run_main(exceptions, &[]).unwrap_err();
+    assert_eq!(error.stack_trace().unwrap()[0].to_string(), "Exceptions.fail(Exceptions.java:85)");
+    match error {
+        InterpreterError::Exception(e) => {
+            assert_eq!(e.stack_trace_text(), "java.lang.IllegalStateException: wrapped\n\
+                \tat Exceptions.fail(Exceptions.java:85)\n\
+                \tat Exceptions.main(Exceptions.java:98)\n\
+                Caused by: Exceptions$Failure: deep\n\
+                \tat Exceptions.thrower(Exceptions.java:38)\n\
+                \tat Exceptions.thrower(Exceptions.java:40)\n\
+                \tat Exceptions.fail(Exceptions.java:83)\n\
+                \t... 1 more\n")
+        }
+        other => panic!("expected IllegalStateException, got {:?}", other)
+    }
+}
+
+#[test]
+fn test_stack_traces() {
+    use interpret::StackFrame;
+    let mut env = fixture_env();
+    let exceptions = env.class_for_name(LoaderId::APPLICATION, "Exceptions", true).unwrap();
+    // The trace starts where the throwable was constructed, without fillInStackTrace and <init> frames.
+    let captured = env.invoke_static(exceptions, "captured", "()Ljava/lang/Throwable;", vec![]).unwrap().unwrap();
+    let exception = env.java_exception(captured.as_reference().unwrap().unwrap()).unwrap();
+    assert_eq!(exception.stack_trace, vec![StackFrame {
+        class_name: "Exceptions".to_string(),
+        method_name: "captured".to_string(),
+        source_file: Some("Exceptions.java".to_string()),
+        line_number: Some(90)
+    }]);
+    let top = env.invoke_static(exceptions, "topMethod", "()Ljava/lang/String;", vec![]).unwrap().unwrap();
+    assert_eq!(env.vm().string_value(top.as_reference().unwrap().unwrap()).unwrap(), "captured");
+
+    // Frames of classes without debug information.
+    let frame = StackFrame { class_name: "a/B".to_string(), method_name: "c".to_string(), source_file: None, line_number: None };
+    assert_eq!(frame.to_string(), "a.B.c(Unknown Source)");
+    let frame = StackFrame { source_file: Some("B.java".to_string()), ..frame };
+    assert_eq!(frame.to_string(), "a.B.c(B.java)");
+}
+
+#[test]
+fn test_type_checks() {
+    use vm::VMValue;
+    let mut env = fixture_env();
+    let casts = env.class_for_name(LoaderId::APPLICATION, "Casts", true).unwrap();
+    assert_eq!(env.invoke_static(casts, "checks", "()I", vec![]).unwrap(), Some(VMValue::Int(511)));
+    assert_eq!(env.invoke_static(casts, "equalStrings", "()Z", vec![]).unwrap(), Some(VMValue::Int(1)));
+
+    let puppy = env.class_for_name(LoaderId::APPLICATION, "Casts$Puppy", true).unwrap();
+    let puppy = VMValue::Reference(env.vm_mut().new_object(puppy).unwrap());
+    assert_eq!(env.invoke_static(casts, "isAnimal", "(Ljava/lang/Object;)Z", vec![puppy]).unwrap(), Some(VMValue::Int(1)));
+    assert_eq!(env.invoke_static(casts, "cast", "(Ljava/lang/Object;)LCasts$Dog;", vec![puppy]).unwrap(), Some(puppy));
+    assert_eq!(env.invoke_static(casts, "cast", "(Ljava/lang/Object;)LCasts$Dog;", vec![VMValue::Null]).unwrap(), Some(VMValue::Null));
+    assert_eq!(env.invoke_static(casts, "isDog", "(Ljava/lang/Object;)Z", vec![VMValue::Null]).unwrap(), Some(VMValue::Int(0)));
+
+    let string = VMValue::Reference(env.vm_mut().new_string("dog").unwrap());
+    match env.invoke_static(casts, "cast", "(Ljava/lang/Object;)LCasts$Dog;", vec![string]) {
+        Err(InterpreterError::Exception(e)) => assert_eq!(e.to_string(), "java.lang.ClassCastException: class java.lang.String cannot be cast to class Casts$Dog \
+            (java.lang.String is in module java.base of loader 'bootstrap'; Casts$Dog is in unnamed module of loader 'app')"),
+        other => panic!("expected ClassCastException, got {:?}", other)
+    }
+    let ints = env.vm_mut().classes.load_class(LoaderId::BOOTSTRAP, "[I").unwrap();
+    let ints = VMValue::Reference(env.vm_mut().new_array(ints, 2).unwrap());
+    match env.invoke_static(casts, "castArray", "(Ljava/lang/Object;)[Ljava/lang/Object;", vec![ints]) {
+        Err(InterpreterError::Exception(e)) => assert_eq!(e.to_string(), "java.lang.ClassCastException: class [I cannot be cast to class [Ljava.lang.Object; \
+            ([I and [Ljava.lang.Object; are in module java.base of loader 'bootstrap')"),
+        other => panic!("expected ClassCastException, got {:?}", other)
+    }
+}
+
+#[test]
+fn test_garbage_collection() {
+    use vm::VMValue;
+    let class_path = ClassPath::parse(&format!("{}/fixtures/classes", env!("CARGO_MANIFEST_DIR"))).unwrap();
+    let registry = ClassRegistry::new(ClassPath::runtime(), ClassPath::new(), class_path);
+    let mut env = VMEnv::of(VM::with_options(1024, registry, VMOptions { max_heap: 512 << 10, ..Default::default() }), Interpreter::new());
+    let garbage = env.class_for_name(LoaderId::APPLICATION, "Garbage", true).unwrap();
+    let int = |value: i32| vec![VMValue::Int(value)];
+
+    // 4 MiB of short-lived arrays go through a 512 KiB heap.
+    assert_eq!(env.invoke_static(garbage, "churn", "(I)I", int(4096)).unwrap(), Some(VMValue::Int(4096 * 256)));
+    assert_eq!(env.invoke_static(garbage, "chain", "(I)I", int(1000)).unwrap(), Some(VMValue::Int(1000)));
+    assert!(env.vm().heap.size() <= 512 << 10);
+
+    // Statics, handles and interned strings are roots; nothing else is.
+    let node_class = env.class_for_name(LoaderId::APPLICATION, "Garbage$Node", false).unwrap();
+    let held = env.vm_mut().new_object(node_class).unwrap();
+    let handle = env.vm_mut().new_handle(held);
+    let loose = env.vm_mut().new_object(node_class).unwrap();
+    let interned = env.vm_mut().intern_string("kept").unwrap();
+    let stats = env.vm_mut().collect_garbage();
+    assert!(stats.freed_objects > 0);
+    let objects: Vec<_> = env.vm().heap.objects().collect();
+    assert!(objects.contains(&held) && objects.contains(&interned) && !objects.contains(&loose));
+    assert_eq!(env.vm().handle(handle), held);
+    let mut length = 0;
+    let mut node = env.vm().classes.class(garbage).static_value("kept").unwrap();
+    while let VMValue::Reference(obj) = node {
+        node = env.vm().get_field(obj, "next").unwrap();
+        length += 1;
+    }
+    assert_eq!(length, 1000);
+    env.vm_mut().release_handles(handle);
+    env.vm_mut().collect_garbage();
+    assert!(!env.vm().heap.objects().any(|obj| obj == held));
+
+    // Live data that does not fit is an OutOfMemoryError, which Java code can catch.
+    match env.invoke_static(garbage, "retain", "(I)I", int(4096)) {
+        Err(InterpreterError::Exception(e)) => assert_eq!(e.to_string(), "java.lang.OutOfMemoryError: Java heap space"),
+        other => panic!("expected OutOfMemoryError, got {:?}", other)
+    }
+    assert_eq!(env.invoke_static(garbage, "recovers", "(I)Z", int(4096)).unwrap(), Some(VMValue::Int(1)));
+
+    assert_eq!(parse_heap_size("64m"), Some(64 << 20));
+    assert_eq!(parse_heap_size("2G"), Some(2 << 30));
+    assert_eq!(parse_heap_size("4096"), Some(4096));
+    assert_eq!(parse_heap_size("8x"), None);
+    assert_eq!(parse_heap_size("8g"), None);
+}
+
+#[test]
+fn test_generational_collection() {
+    use vm::VMValue;
+    use rusty_jvm::gc::CollectionKind;
+    let class_path = ClassPath::parse(&format!("{}/fixtures/classes", env!("CARGO_MANIFEST_DIR"))).unwrap();
+    let registry = ClassRegistry::new(ClassPath::runtime(), ClassPath::new(), class_path);
+    let options = VMOptions { max_heap: 512 << 10, collector: CollectorKind::Generational, young_size: Some(64 << 10), ..Default::default() };
+    let mut env = VMEnv::of(VM::with_options(1024, registry, options), Interpreter::new());
+    assert_eq!(env.vm().collector_name(), "Generational");
+    let garbage = env.class_for_name(LoaderId::APPLICATION, "Garbage", true).unwrap();
+    let int = |value: i32| vec![VMValue::Int(value)];
+
+    assert_eq!(env.invoke_static(garbage, "churn", "(I)I", int(4096)).unwrap(), Some(VMValue::Int(4096 * 256)));
+    assert_eq!(env.invoke_static(garbage, "chain", "(I)I", int(1000)).unwrap(), Some(VMValue::Int(1000)));
+    assert!(env.vm().heap.size() <= 512 << 10);
+    let stats = env.vm().gc_stats();
+    assert!(stats.minor_collections > 0);
+    assert!(stats.max_pause <= stats.total_pause);
+
+    // Nursery objects stored into old objects, arrays and statics survive minor collections.
+    assert_eq!(env.invoke_static(garbage, "remembered", "(I)I", int(50)).unwrap(), Some(VMValue::Int(3 * 50 * 49 / 2)));
+
+    // Full collections move objects and update the roots that refer to them.
+    let node_class = env.class_for_name(LoaderId::APPLICATION, "Garbage$Node", false).unwrap();
+    let held = env.vm_mut().new_object(node_class).unwrap();
+    let handle = env.vm_mut().new_handle(held);
+    let next = env.vm_mut().new_object(node_class).unwrap();
+    let target = env.vm().handle(handle);
+    env.vm_mut().set_field(target, "next", VMValue::Reference(next)).unwrap();
+    let stats = env.vm_mut().collect_garbage();
+    assert_eq!(stats.kind, CollectionKind::Full);
+    let moved = env.vm().handle(handle);
+    assert_ne!(moved, held);
+    assert_eq!(env.vm().heap.class_of(moved), node_class);
+    assert_eq!(env.vm().references(moved).len(), 1);
+    let kept = env.vm().classes.class(garbage).static_value("kept").unwrap();
+    assert!(matches!(kept, VMValue::Reference(obj) if env.vm().heap.objects().any(|live| live == obj)));
+
+    match env.invoke_static(garbage, "retain", "(I)I", int(4096)) {
+        Err(InterpreterError::Exception(e)) => assert_eq!(e.to_string(), "java.lang.OutOfMemoryError: Java heap space"),
+        other => panic!("expected OutOfMemoryError, got {:?}", other)
+    }
+    assert_eq!(env.invoke_static(garbage, "recovers", "(I)Z", int(4096)).unwrap(), Some(VMValue::Int(1)));
+}
+
+#[test]
+fn test_references_and_finalization() {
+    use vm::VMValue;
+    for collector in [CollectorKind::MarkSweep, CollectorKind::Generational] {
+        let class_path = ClassPath::parse(&format!("{}/fixtures/classes", env!("CARGO_MANIFEST_DIR"))).unwrap();
+        let registry = ClassRegistry::new(ClassPath::runtime(), ClassPath::new(), class_path);
+        let options = VMOptions { max_heap: 512 << 10, collector, young_size: Some(64 << 10), ..Default::default() };
+        let mut env = VMEnv::of(VM::with_options(1024, registry, options), Interpreter::new());
+        let references = env.class_for_name(LoaderId::APPLICATION, "References", true).unwrap();
+        let call = |env: &mut VMEnv, name: &str| env.invoke_static(references, name, "()I", Vec::new()).unwrap();
+        let (weak, soft, phantom) = (VMValue::Int(1), VMValue::Int(2), VMValue::Int(4));
+
+        // Weak referents go with the first collection, soft ones stay while memory is left, and
+        // an object with a finalizer is finalized before its phantom reference is cleared.
+        env.invoke_static(references, "setUp", "()V", Vec::new()).unwrap();
+        env.vm_mut().collect_garbage();
+        assert_eq!(call(&mut env, "cleared"), Some(weak));
+        assert_eq!(call(&mut env, "queued"), Some(weak));
+        assert_eq!(call(&mut env, "finalized"), Some(VMValue::Int(1)));
+
+        // The finalizer resurrected the object; once it is unreachable again it is not finalized twice.
+        env.invoke_static(references, "release", "()V", Vec::new()).unwrap();
+        env.vm_mut().collect_garbage();
+        assert_eq!(call(&mut env, "queued"), Some(phantom));
+        assert_eq!(call(&mut env, "finalized"), Some(VMValue::Int(1)));
+
+        // Soft references are cleared before an OutOfMemoryError is thrown.
+        let exhausted = env.invoke_static(references, "exhaust", "(I)Z", vec![VMValue::Int(4096)]).unwrap();
+        assert_eq!(exhausted, Some(VMValue::Int(1)));
+        assert_eq!(call(&mut env, "queued"), Some(soft));
+    }
+}
+
+/// The records of an HPROF dump with 8 byte IDs as (tag, body), heap dump sub-records as
+/// (sub-tag, ID, body) and the strings and class names, for checking dumps.
+#[cfg(test)]
+struct Hprof {
+    records: Vec<(u8, Vec<u8>)>,
+    dumps: Vec<(u8, u64, Vec<u8>)>,
+    class_names: std::collections::HashMap<u64, String>
+}
+
+#[cfg(test)]
+impl Hprof {
+    fn parse(data: &[u8]) -> Hprof {
+        let u4 = |at: usize| u32::from_be_bytes(data[at..at + 4].try_into().unwrap()) as usize;
+        let id = |at: usize| u64::from_be_bytes(data[at..at + 8].try_into().unwrap());
+        assert!(data.starts_with(b"JAVA PROFILE 1.0.2\0"));
+        assert_eq!(u4(19), 8);
+        let (mut records, mut dumps) = (Vec::new(), Vec::new());
+        let mut strings = std::collections::HashMap::new();
+        let mut class_names = std::collections::HashMap::new();
+        let mut at = 31;
+        while at < data.len() {
+            let (tag, length) = (data[at], u4(at + 5));
+            let body = at + 9;
+            match tag {
+                0x01 => { strings.insert(id(body), String::from_utf8(data[body + 8..body + length].to_vec()).unwrap()); }
+                0x02 => { class_names.insert(id(body + 4), strings[&id(body + 16)].clone()); }
+                0x1c => {
+                    let mut sub = body;
+                    while sub < body + length {
+                        let value_size = |basic_type: u8| match basic_type { 2 => 8, 4 | 8 => 1, 5 | 9 => 2, 6 | 10 => 4, _ => 8 };
+                        let end = match data[sub] {
+                            0xff | 0x05 => sub + 9,
+                            0x01 => sub + 17,
+                            0x03 => sub + 17,
+                            0x20 => {
+                                let mut end = sub + 9 + 4 + 6 * 8 + 4;
+                                end += 2;
+                                let statics = u16::from_be_bytes([data[end], data[end + 1]]);
+                                end += 2;
+                                for _ in 0..statics {
+                                    end += 9 + value_size(data[end + 8]);
+                                }
+                                let fields = u16::from_be_bytes([data[end], data[end + 1]]) as usize;
+                                end + 2 + fields * 9
+                            }
+                            0x21 => sub + 25 + u4(sub + 21),
+                            0x22 => sub + 25 + u4(sub + 13) * 8,
+                            0x23 => sub + 18 + u4(sub + 13) * value_size(data[sub + 17]),
+                            other => panic!("unexpected heap dump record {:#x}", other)
+                        };
+                        dumps.push((data[sub], id(sub + 1), data[sub + 9..end].to_vec()));
+                        sub = end;
+                    }
+                }
+                _ => {}
+            }
+            records.push((tag, data[body..body + length].to_vec()));
+            at = body + length;
+        }
+        Hprof { records, dumps, class_names }
+    }
+
+    /// Instances of a class: instance dumps, or array dumps for an array class.
+    fn instances(&self, class_name: &str) -> usize {
+        self.dumps.iter().filter(|(tag, _, body)| match tag {
+            0x21 => self.class_names[&u64::from_be_bytes(body[4..12].try_into().unwrap())] == class_name,
+            0x22 => self.class_names[&u64::from_be_bytes(body[8..16].try_into().unwrap())] == class_name,
+            0x23 => class_name == format!("[{}", match body[8] { 4 => 'Z', 5 => 'C', 6 => 'F', 7 => 'D', 8 => 'B', 9 => 'S', 10 => 'I', _ => 'J' }),
+            _ => false
+        }).count()
+    }
+}
+
+#[test]
+fn test_heap_dump() {
+    use vm::VMValue;
+    let class_path = ClassPath::parse(&format!("{}/fixtures/classes", env!("CARGO_MANIFEST_DIR"))).unwrap();
+    let registry = ClassRegistry::new(ClassPath::runtime(), ClassPath::new(), class_path);
+    let dump_path = std::env::temp_dir().join(format!("rusty-jvm-test-{}.hprof", std::process::id()));
+    let _ = std::fs::remove_file(&dump_path);
+    let options = VMOptions { max_heap: 512 << 10, heap_dump_on_out_of_memory: true, heap_dump_path: Some(dump_path.clone()), ..Default::default() };
+    let mut env = VMEnv::of(VM::with_options(1024, registry, options), Interpreter::new());
+    let garbage = env.class_for_name(LoaderId::APPLICATION, "Garbage", true).unwrap();
+    env.invoke_static(garbage, "chain", "(I)I", vec![VMValue::Int(100)]).unwrap();
+    env.vm_mut().intern_string("dumped").unwrap();
+
+    let mut data = Vec::new();
+    env.write_heap_dump(&mut data).unwrap();
+    let dump = Hprof::parse(&data);
+    assert_eq!(dump.records.last().unwrap().0, 0x2c);
+    assert_eq!(dump.instances("Garbage$Node"), 100);
+    assert_eq!(dump.instances("[I"), 100);
+    assert!(dump.instances("java/lang/String") >= 1);
+    // Every class is dumped once, with the super class dumped too.
+    let classes: Vec<u64> = dump.dumps.iter().filter(|(tag, ..)| *tag == 0x20).map(|(_, id, _)| *id).collect();
+    assert_eq!(classes.len(), dump.class_names.len());
+    let node = dump.dumps.iter().find(|(tag, id, _)| *tag == 0x20 && dump.class_names[id] == "Garbage$Node").unwrap();
+    assert_eq!(dump.class_names[&u64::from_be_bytes(node.2[4..12].try_into().unwrap())], "java/lang/Object");
+    // Node instances hold an 8 byte `next` ID and an 8 byte `payload` ID.
+    let instance = dump.dumps.iter().find(|(tag, _, body)| *tag == 0x21 && dump.class_names[&u64::from_be_bytes(body[4..12].try_into().unwrap())] == "Garbage$Node").unwrap();
+    assert_eq!(&instance.2[12..16], &16u32.to_be_bytes());
+    assert!(dump.dumps.iter().any(|(tag, ..)| *tag == 0x05));
+
+    // The first OutOfMemoryError dumps the heap, with the stack of the code that ran out.
+    assert!(env.invoke_static(garbage, "retain", "(I)I", vec![VMValue::Int(4096)]).is_err());
+    let dump = Hprof::parse(&std::fs::read(&dump_path).unwrap());
+    assert!(dump.dumps.iter().any(|(tag, ..)| *tag == 0x03));
+    assert!(dump.records.iter().any(|(tag, body)| *tag == 0x05 && body[8..12] == 1u32.to_be_bytes()));
+    std::fs::remove_file(&dump_path).unwrap();
+    assert!(env.invoke_static(garbage, "retain", "(I)I", vec![VMValue::Int(4096)]).is_err());
+    assert!(!dump_path.exists());
+}
+
+#[test]
+fn test_heap_histogram_and_allocation_sites() {
+    use vm::VMValue;
+    let class_path = ClassPath::parse(&format!("{}/fixtures/classes", env!("CARGO_MANIFEST_DIR"))).unwrap();
+    let registry = ClassRegistry::new(ClassPath::runtime(), ClassPath::new(), class_path);
+    let options = VMOptions { allocation_sample_interval: Some(1), ..Default::default() };
+    let mut env = VMEnv::of(VM::with_options(1024, registry, options), Interpreter::new());
+    let garbage = env.class_for_name(LoaderId::APPLICATION, "Garbage", true).unwrap();
+    env.invoke_static(garbage, "chain", "(I)I", vec![VMValue::Int(100)]).unwrap();
+    env.invoke_static(garbage, "churn", "(I)I", vec![VMValue::Int(10)]).unwrap();
+    env.vm_mut().collect_garbage();
+
+    // The chain survives, the churned arrays do not.
+    let node = env.class_for_name(LoaderId::APPLICATION, "Garbage$Node", false).unwrap();
+    let histogram = env.vm().heap_histogram();
+    let nodes = histogram.iter().find(|entry| entry.class == node).unwrap();
+    assert_eq!((nodes.instances, nodes.bytes), (100, 100 * vm::Heap::object_size(env.vm().classes.class(node).layout.size) as usize));
+    assert!(histogram.windows(2).all(|pair| pair[0].bytes >= pair[1].bytes));
+    let mut report = Vec::new();
+    env.vm().print_heap_histogram(&mut report).unwrap();
+    let report = String::from_utf8(report).unwrap();
+    assert!(report.starts_with(" num     #instances         #bytes  class name\n"));
+    assert!(report.lines().any(|line| line.ends_with(&format!("{:>13} {:>14}  Garbage$Node", 100, nodes.bytes))));
+
+    // Every allocation by bytecode is sampled, live or not, with the instruction that made it.
+    let sites = env.vm().allocation_sites();
+    let churn = sites.iter().find(|(site, _)| env.vm().classes.class(site.class).class.methods[site.method].method_name == "churn").unwrap();
+    assert_eq!(churn.1.samples, 10);
+    assert_eq!(env.vm().classes.class(churn.1.allocated.unwrap()).name, "[I");
+    let method = &env.vm().classes.class(garbage).class.methods[churn.0.method];
+    assert!(matches!(interpret::Interpreter::new().decode(&method.code, churn.0.bci).unwrap().0, interpret::Opcode::NewArray(10)));
+    let chain: usize = sites.iter()
+        .filter(|(site, _)| env.vm().classes.class(site.class).class.methods[site.method].method_name == "chain")
+        .map(|(_, stats)| stats.samples).sum();
+    assert_eq!(chain, 100);
+    let mut report = Vec::new();
+    env.vm().print_allocation_sites(&mut report).unwrap();
+    assert!(String::from_utf8(report).unwrap().contains(&format!("Garbage.churn(I)I @ {} ([I)", churn.0.bci)));
+}
+
+#[test]
+fn test_strings() {
+    use vm::VMValue;
+    use rusty_jvm::strings;
+    let class_path = ClassPath::parse(&format!("{}/fixtures/classes", env!("CARGO_MANIFEST_DIR"))).unwrap();
+    let registry = ClassRegistry::new(ClassPath::runtime(), ClassPath::new(), class_path);
+    let mut env = VMEnv::of(VM::with_classes(1024, registry), Interpreter::new());
+    let class = env.class_for_name(LoaderId::APPLICATION, "Strings", true).unwrap();
+    let literal = |env: &VMEnv, name: &str| match env.vm().classes.class(class).static_value(name) {
+        Some(VMValue::Reference(string)) => string,
+        other => panic!("{} is {:?}", name, other)
+    };
+
+    // Latin-1 strings take a byte per character, the others two per UTF-16 code unit.
+    let cases: [(&str, Vec<u16>, i32); 4] = [
+        ("LATIN1", "caf\u{e9}".encode_utf16().collect(), 0),
+        ("UTF16", "\u{65e5}\u{672c}\u{8a9e}".encode_utf16().collect(), 1),
+        ("SUPPLEMENTARY", "a\u{1f600}\u{0}".encode_utf16().collect(), 1),
+        ("UNPAIRED", vec![0x78, 0xd800], 1)
+    ];
+    for (name, units, coder) in cases {
+        let string = literal(&env, name);
+        assert_eq!(env.vm().string_utf16(string).unwrap(), units);
+        assert_eq!(env.vm().get_field(string, "coder").unwrap(), VMValue::Int(coder));
+        let value = env.vm().get_field(string, "value").unwrap().as_reference().unwrap().unwrap();
+        assert_eq!(env.vm().heap.array_length(value) as usize, units.len() << coder);
+        let arg = |env: &VMEnv| vec![VMValue::Reference(literal(env, name))];
+        assert_eq!(env.invoke_static(class, "length", "(Ljava/lang/String;)I", arg(&env)).unwrap(), Some(VMValue::Int(units.len() as i32)));
+        let last = vec![VMValue::Reference(literal(&env, name)), VMValue::Int(units.len() as i32 - 1)];
+        assert_eq!(env.invoke_static(class, "charAt", "(Ljava/lang/String;I)I", last).unwrap(), Some(VMValue::Int(*units.last().unwrap() as i32)));
+        let hash = units.iter().fold(0i32, |h, unit| h.wrapping_mul(31).wrapping_add(*unit as i32));
+        assert_eq!(env.invoke_static(class, "hash", "(Ljava/lang/String;)I", arg(&env)).unwrap(), Some(VMValue::Int(hash)));
+        assert_eq!(env.invoke_static(class, "interned", "(Ljava/lang/String;)Z", arg(&env)).unwrap(), Some(VMValue::Int(1)));
+    }
+    assert_eq!(env.vm().string_value(literal(&env, "UNPAIRED")).unwrap(), "x\u{fffd}");
+    let interned = env.vm_mut().intern_utf16(&[0x78, 0xd800]).unwrap();
+    assert_eq!(interned, literal(&env, "UNPAIRED"));
+    assert_eq!(env.invoke_static(class, "internedFirst", "()Z", Vec::new()).unwrap(), Some(VMValue::Int(1)));
+
+    // Modified UTF-8 encodes U+0000 in two bytes and supplementary characters as surrogates.
+    let units: Vec<u16> = "a\u{1f600}\u{0}".encode_utf16().collect();
+    let encoded = strings::encode_modified_utf8(&units);
+    assert_eq!(encoded, [0x61, 0xed, 0xa0, 0xbd, 0xed, 0xb8, 0x80, 0xc0, 0x80]);
+    assert_eq!(strings::decode_modified_utf8(&encoded), Some(units));
+    assert_eq!(strings::modified_utf8_to_string(&encoded).as_deref(), Some("a\u{1f600}\u{0}"));
+    assert_eq!(strings::string_to_modified_utf8("\u{e9}"), [0xc3, 0xa9]);
+    for malformed in [&[0x00][..], &[0xf0, 0x9f, 0x98, 0x80], &[0xc3], &[0xe6, 0x97, 0x41]] {
+        assert_eq!(strings::decode_modified_utf8(malformed), None);
+    }
+}
+
+#[test]
+fn test_string_concat_and_format() {
+    use vm::VMValue;
+    let mut env = fixture_env();
+    let concat = env.class_for_name(LoaderId::APPLICATION, "Concat", true).unwrap();
+    let text = |env: &VMEnv, value: Option<VMValue>| env.vm().string_value(value.unwrap().as_reference().unwrap().unwrap()).unwrap();
+
+    // Concatenation compiled to invokedynamic calls of StringConcatFactory.makeConcatWithConstants.
+    let args = vec![VMValue::Int(1), VMValue::Int('c' as i32), VMValue::Int(-1), VMValue::Int(300), VMValue::Int(42),
+                    VMValue::Long(1 << 40), VMValue::Float(1.5), VMValue::Double(0.1)];
+    let result = env.invoke_static(concat, "primitives", "(ZCBSIJFD)Ljava/lang/String;", args).unwrap();
+    assert_eq!(text(&env, result), "z=true c=c b=-1 s=300 i=42 j=1099511627776 f=1.5 d=0.1");
+    let s = VMValue::Reference(env.vm_mut().new_string("s").unwrap());
+    let result = env.invoke_static(concat, "objects", "(Ljava/lang/String;Ljava/lang/Object;)Ljava/lang/String;", vec![s, VMValue::Null]).unwrap();
+    assert_eq!(text(&env, result), "snull(1, -2)!");
+    let result = env.invoke_static(concat, "tags", "(I)Ljava/lang/String;", vec![VMValue::Int(7)]).unwrap();
+    assert_eq!(text(&env, result), "\u{1}7\u{2}8");
+    let x = VMValue::Reference(env.vm_mut().new_string("x").unwrap());
+    let result = env.invoke_static(concat, "unicode", "(Ljava/lang/String;)Ljava/lang/String;", vec![x]).unwrap();
+    assert_eq!(text(&env, result), "x\u{65e5}\u{672c}x");
+
+    // String.format, with floating point values rounded half up from their shortest decimal.
+    let result = env.invoke_static(concat, "format", "()Ljava/lang/String;", vec![]).unwrap();
+    assert_eq!(text(&env, result), "str|   42|42   |-0042|ff|FF|10|1,234,567|+2.68|1.235e+04|z|false|%|42|42|0003.142|(7)|\n");
+    let result = env.invoke_static(concat, "formatFloats", "()Ljava/lang/String;", vec![]).unwrap();
+    assert_eq!(text(&env, result), "3 0.1 0.000000 10.00 0.000000e+00 1e+01 NaN -Infinity 1.100");
+    match env.invoke_static(concat, "missingArgument", "()Ljava/lang/String;", vec![]) {
+        Err(InterpreterError::Exception(e)) => assert_eq!(e.to_string(), "java.util.MissingFormatArgumentException: Format specifier '%s'"),
+        other => panic!("expected MissingFormatArgumentException, got {:?}", other)
+    }
+}
+
+#[test]
+fn test_native_methods() {
+    use vm::VMValue;
+    use rusty_jvm::natives;
+    use rusty_jvm::native;
+    let mut env = fixture_env();
+    natives!(env.natives,
+        "Natives" {
+            "add" "(II)I" => |_, args| Ok(Some(VMValue::Int(args[0].as_int()? + args[1].as_int()?))),
+            "offset" "(J)J" => |env, args| {
+                let base = env.vm().get_field(native::receiver(args)?, "base")?.as_int()?;
+                Ok(Some(VMValue::Long(base as i64 + args[1].as_long()?)))
+            },
+            "greeting" "(Ljava/lang/String;)Ljava/lang/String;" => |env, args| {
+                let name = match native::reference(args, 0)? {
+                    Some(name) => env.vm().string_value(name)?,
+                    None => "nobody".to_string()
+                };
+                Ok(Some(VMValue::Reference(env.vm_mut().new_string(&format!("Hello, {}!", name))?)))
+            },
+            "fail" "(Ljava/lang/String;)V" => |env, args| {
+                let message = native::reference(args, 0)?.map(|message| env.vm().string_value(message)).transpose()?;
+                Err(InterpreterError::Exception(interpret::JavaException::new("java/lang/IllegalStateException", message)))
+            }
+        }
+    );
+    let natives = env.class_for_name(LoaderId::APPLICATION, "Natives", true).unwrap();
+    assert_eq!(env.invoke_static(natives, "sum", "()I", vec![]).unwrap(), Some(VMValue::Int(84)));
+    let name = VMValue::Reference(env.vm_mut().new_string("Duke").unwrap());
+    let greeting = env.invoke_static(natives, "greeting", "(Ljava/lang/String;)Ljava/lang/String;", vec![name]).unwrap().unwrap();
+    assert_eq!(env.vm().string_value(greeting.as_reference().unwrap().unwrap()).unwrap(), "Hello, Duke!");
+    let greeting = env.invoke_static(natives, "greeting", "(Ljava/lang/String;)Ljava/lang/String;", vec![VMValue::Null]).unwrap().unwrap();
+    assert_eq!(env.vm().string_value(greeting.as_reference().unwrap().unwrap()).unwrap(), "Hello, nobody!");
+
+    // Natives throw by returning an exception, which Java code can catch.
+    let failure = env.invoke_static(natives, "failure", "()Ljava/lang/String;", vec![]).unwrap().unwrap();
+    assert_eq!(env.vm().string_value(failure.as_reference().unwrap().unwrap()).unwrap(), "from native code");
+    assert_eq!(env.invoke_static(natives, "objects", "()Z", vec![]).unwrap(), Some(VMValue::Int(1)));
+
+    // A native method without a binding, or whose binding was removed, cannot be linked.
+    match env.invoke_static(natives, "unbound", "(D)I", vec![VMValue::Double(1.0)]) {
+        Err(InterpreterError::Exception(e)) => assert_eq!(e.to_string(), "java.lang.UnsatisfiedLinkError: 'int Natives.unbound(double)'"),
+        other => panic!("expected UnsatisfiedLinkError, got {:?}", other)
+    }
+    assert!(env.natives.unregister("Natives", "add", "(II)I"));
+    match env.invoke_static(natives, "sum", "()I", vec![]) {
+        Err(InterpreterError::Exception(e)) => assert_eq!(e.to_string(), "java.lang.UnsatisfiedLinkError: 'int Natives.add(int, int)'"),
+        other => panic!("expected UnsatisfiedLinkError, got {:?}", other)
+    }
+}
+
+#[test]
+fn test_class_library() {
+    use rusty_jvm::strings;
+
+    let mut env = fixture_env();
+    let (stdout, stderr) = (Capture::default(), Capture::default());
+    env.stdout = Box::new(stdout.clone());
+    env.stderr = Box::new(stderr.clone());
+    let class = env.class_for_name(LoaderId::APPLICATION, "Library", false).unwrap();
+    // The program prints what it prints on a JDK, then calls System.exit with its second argument.
+    assert!(matches!(env.run_main(class, &["x".to_string(), "3".to_string()]), Err(InterpreterError::Exit(3))));
+    let output = stdout.text();
+    let lines: Vec<&str> = output.lines().collect();
+    assert_eq!(lines[0], "args: 2 x");
+    assert_eq!(lines[1], "12 World 4 8 -1");
+    assert_eq!(lines[5], "17 -x- null");
+    assert_eq!(lines[6], "]5.13lluneurt5.2d1cba[ 22 5");
+    assert_eq!(lines[7], "d83d de00 a");
+    assert_eq!(lines[12], "1125 true12-3 true 1000 5 1237");
+    assert_eq!(lines[13], "0.0 -0.0 1.0 0.1 100.0 1.0E7 0.001 9.999E-4 123456.789 1.0E21 3.141592653589793 \
+        1.7976931348623157E308 4.9E-324 Infinity -Infinity NaN 0.6666666666666666 ");
+    assert_eq!(lines[14], "0.1 0.33333334 1.0E10 3.4028235E38 1.4E-45 100.0 ");
+    assert_eq!(lines[17], "true true true 201 15 b true");
+    assert_eq!(lines[18], "java.lang.NumberFormatException: For input string: \"12a\"");
+    assert_eq!(lines[26], "3 -2 0 2 0 9223372036854775807");
+    assert_eq!(lines[30], "Point(3) Point(4) true");
+    assert_eq!(lines[33], "arraycopy: type mismatch: can not copy int[] into long[]");
+    assert_eq!(lines[34], "java.lang.CloneNotSupportedException: Library$1");
+    assert_eq!(lines.last(), Some(&"no newline, c1"));
+    assert_eq!(stderr.text(), "to stderr\n");
+
+    assert_eq!(strings::double_to_string(1e-5), "1.0E-5");
+    assert_eq!(strings::double_to_string(-1234.5), "-1234.5");
+    assert_eq!(strings::float_to_string(1.1f32), "1.1");
+    assert_eq!(strings::parse_floating::<f64>("\t-2.5e-1d "), Some(-0.25));
+    assert_eq!(strings::parse_floating::<f32>("Infinity"), Some(f32::INFINITY));
+    for malformed in ["", "inf", "1.0x", "0x1p3", "e5"] {
+        assert_eq!(strings::parse_floating::<f64>(malformed), None);
+    }
+}
+
+#[test]
+#[cfg(all(target_os = "linux", target_arch = "x86_64"))]
+fn test_jni_libraries() {
+    use vm::VMValue;
+    let fixtures = env!("CARGO_MANIFEST_DIR").to_string() + "/fixtures";
+    // The library is built from fixtures/native/jninatives.c with cc, against the headers of
+    // the JDK in JAVA_HOME or of the one providing javac.
+    let library_dir = std::env::temp_dir().join(format!("rusty-jvm-jni-{}", std::process::id()));
+    std::fs::create_dir_all(&library_dir).unwrap();
+    let java_home = std::env::var_os("JAVA_HOME").map(std::path::PathBuf::from).or_else(|| {
+        let javac = std::env::split_paths(&std::env::var_os("PATH")?).map(|dir| dir.join("javac")).find(|javac| javac.exists())?;
+        Some(std::fs::canonicalize(javac).ok()?.parent()?.parent()?.to_path_buf())
+    }).expect("test_jni_libraries needs a JDK in JAVA_HOME or javac on PATH for jni.h");
+    let status = std::process::Command::new("cc")
+        .args(["-shared", "-fPIC", "-O2"])
+        .arg(format!("-I{}/include", java_home.display()))
+        .arg(format!("-I{}/include/linux", java_home.display()))
+        .arg("-o").arg(library_dir.join("libjninatives.so"))
+        .arg(format!("{}/native/jninatives.c", fixtures))
+        .status().expect("test_jni_libraries needs a C compiler on PATH");
+    assert!(status.success(), "compiling fixtures/native/jninatives.c failed");
+    let class_path = ClassPath::parse(&format!("{}/classes", fixtures)).unwrap();
+    let registry = ClassRegistry::new(ClassPath::runtime(), ClassPath::new(), class_path);
+    // A small generational heap, so that objects held by native code move.
+    let options = VMOptions { max_heap: 512 << 10, collector: CollectorKind::Generational, library_path: vec![library_dir.clone()], ..Default::default() };
+    let mut env = VMEnv::of(VM::with_options(1024, registry, options), Interpreter::new());
+    let natives = env.class_for_name(LoaderId::APPLICATION, "JniNatives", true).unwrap();
+    let string = |env: &mut VMEnv, value: &str| VMValue::Reference(env.vm_mut().new_string(value).unwrap());
+    let text = |env: &VMEnv, value: Option<VMValue>| env.vm().string_value(value.unwrap().as_reference().unwrap().unwrap()).unwrap();
+    let link_error = |result: Result<Option<VMValue>, InterpreterError>| match result {
+        Err(InterpreterError::Exception(e)) if e.class_name == "java/lang/UnsatisfiedLinkError" => e.message.unwrap(),
+        other => panic!("expected UnsatisfiedLinkError, got {:?}", other)
+    };
+    let ints = |a: i32, b: i32| vec![VMValue::Int(a), VMValue::Int(b)];
+    assert_eq!(link_error(env.invoke_static(natives, "add", "(II)I", ints(1, 2))), "'int JniNatives.add(int, int)'");
+
+    // System.loadLibrary finds the library in java.library.path and runs its JNI_OnLoad.
+    let name = string(&mut env, "jninatives");
+    env.invoke_static(natives, "loadLibrary", "(Ljava/lang/String;)V", vec![name]).unwrap();
+    let path = std::fs::canonicalize(library_dir.join("libjninatives.so")).unwrap();
+    assert_eq!(env.native_libraries().iter().map(|library| (library.path.clone(), library.loader)).collect::<Vec<_>>(), [(path.clone(), LoaderId::APPLICATION)]);
+    let filename = string(&mut env, path.to_str().unwrap());
+    env.invoke_static(natives, "load", "(Ljava/lang/String;)V", vec![filename]).unwrap();
+    assert_eq!(env.native_libraries().len(), 1);
+    let relative = string(&mut env, "libjninatives.so");
+    assert_eq!(link_error(env.invoke_static(natives, "load", "(Ljava/lang/String;)V", vec![relative])), "Expecting an absolute path of the library: libjninatives.so");
+    let missing = string(&mut env, "missing");
+    assert_eq!(link_error(env.invoke_static(natives, "loadLibrary", "(Ljava/lang/String;)V", vec![missing])), format!("no missing in java.library.path: {}", library_dir.display()));
+
+    // Short names, long names of overloads, and RegisterNatives.
+    assert_eq!(env.invoke_static(natives, "add", "(II)I", ints(40, 2)).unwrap(), Some(VMValue::Int(42)));
+    let result = env.invoke_static(natives, "describe", "(I)Ljava/lang/String;", vec![VMValue::Int(-7)]).unwrap();
+    assert_eq!(text(&env, result), "int -7");
+    let value = string(&mut env, "caf\u{e9}");
+    let result = env.invoke_static(natives, "describe", "(Ljava/lang/String;)Ljava/lang/String;", vec![value]).unwrap();
+    assert_eq!(text(&env, result), "string of 4");
+    assert_eq!(env.invoke_static(natives, "registered", "()Z", vec![]).unwrap(), Some(VMValue::Int(1)));
+    assert_eq!(env.invoke_static(natives, "version", "()I", vec![]).unwrap(), Some(VMValue::Int(0x000a0000)));
+    assert_eq!(link_error(env.invoke_static(natives, "missing", "()I", vec![])), "'int JniNatives.missing()'");
+
+    // Arguments in registers and on the stack.
+    let args = vec![VMValue::Int(1), VMValue::Double(2.5), VMValue::Long(3), VMValue::Float(4.5), VMValue::Int(-5), VMValue::Int(6),
+                    VMValue::Int(-7), VMValue::Int(1), VMValue::Double(9.5), VMValue::Int(10), VMValue::Double(11.5), VMValue::Long(1 << 40),
+                    VMValue::Double(13.5), VMValue::Double(14.5), VMValue::Double(15.5), VMValue::Double(16.5), VMValue::Double(17.5),
+                    VMValue::Int(18), VMValue::Long(-19), VMValue::Double(20.5)];
+    let sum = 1.0 + 2.5 + 3.0 + 4.5 - 5.0 + 6.0 - 7.0 + 1.0 + 9.5 + 10.0 + 11.5 + (1u64 << 40) as f64 + 13.5 + 14.5 + 15.5 + 16.5 + 17.5 + 18.0 - 19.0 + 20.5;
+    assert_eq!(env.invoke_static(natives, "sum", "(IDJFBCSZDIDJDDDDDIJD)D", args).unwrap(), Some(VMValue::Double(sum)));
+
+    // Native code creates objects and calls Java methods, with variadic and array arguments.
+    let name = string(&mut env, "Duke");
+    let created = env.invoke_static(natives, "create", "(Ljava/lang/String;)LJniNatives;", vec![name]).unwrap().unwrap();
+    let (class_file, receiver) = (env.vm().classes.class(natives).class.clone(), created.as_reference().unwrap().unwrap());
+    let greet = class_file.methods.iter().position(|m| m.method_name == "greet").unwrap();
+    let greeting = string(&mut env, "Hello");
+    let result = env.invoke(natives, greet, vec![VMValue::Reference(receiver), greeting]).unwrap();
+    assert_eq!(text(&env, result), "Hello, Duke!");
+    let weigh_twice = class_file.methods.iter().position(|m| m.method_name == "weighTwice").unwrap();
+    assert_eq!(env.invoke(natives, weigh_twice, vec![created]).unwrap(), Some(VMValue::Long(4321 + 87650000)));
+
+    // Exceptions are pending until cleared or the native method returns.
+    let message = string(&mut env, "checked");
+    let result = env.invoke_static(natives, "rethrow", "(Ljava/lang/String;)Ljava/lang/String;", vec![message]).unwrap();
+    assert_eq!(text(&env, result), "checked");
+    let message = string(&mut env, "propagated");
+    let result = env.invoke_static(natives, "propagated", "(Ljava/lang/String;)Ljava/lang/String;", vec![message]).unwrap();
+    assert_eq!(text(&env, result), "propagated");
+    let message = string(&mut env, "thrown");
+    let result = env.invoke_static(natives, "thrown", "(Ljava/lang/String;)Ljava/lang/String;", vec![message]).unwrap();
+    assert_eq!(text(&env, result), "thrown");
+    let message = string(&mut env, "uncaught");
+    match env.invoke_static(natives, "propagate", "(Ljava/lang/String;)V", vec![message]) {
+        Err(InterpreterError::Exception(e)) => assert_eq!(e.to_string(), "java.lang.IllegalArgumentException: uncaught"),
+        other => panic!("expected IllegalArgumentException, got {:?}", other)
+    }
+
+    // Local and global references follow objects the collector moves.
+    let collections = env.vm().gc_stats().minor_collections;
+    let value = string(&mut env, "kept");
+    let result = env.invoke_static(natives, "keep", "(Ljava/lang/String;I)Ljava/lang/String;", vec![value, VMValue::Int(4096)]).unwrap();
+    assert!(env.vm().gc_stats().minor_collections > collections);
+    assert_eq!(text(&env, result), "kept");
+    std::fs::remove_dir_all(&library_dir).unwrap();
+}
+
+#[test]
+fn test_decode_wide_and_switch() {
+    use interpret::Opcode;
+    let interpreter = Interpreter::new();
+    let code = [0xc4, 0x84, 0x01, 0x00, 0x03, 0xe8, 0xaa, 0x00, 0x00, 0x00, 0x00, 0x10,
+        0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x0c];
+    assert_eq!(interpreter.decode(&code, 0).unwrap(), (Opcode::IInc(256, 1000), 6));
+    assert_eq!(interpreter.decode(&code, 6).unwrap(), (Opcode::TableSwitch(16, 1, 1, vec![12]), 24));
+}
+
+fn usage() -> ! {
+    eprintln!("usage: rusty-jvm [--release <n>] [<gc options>] -jar <app.jar> [args...]");
+    eprintln!("       rusty-jvm [--release <n>] [<gc options>] -cp <class path> <main class> [args...]");
+    eprintln!("       rusty-jvm disasm [--json [--resolve] [--instructions]] <file.class>...");
+    eprintln!("       rusty-jvm disasm [--json [--resolve] [--instructions]] -cp <class path> <class name>...");
+    eprintln!("gc options: -Xmx<size> -Xmn<size> -verbose:gc");
+    eprintln!("            -XX:+UseMarkSweepGC (the default) -XX:+UseGenerationalGC (copying nursery, mark-compact old generation)");
+    eprintln!("            -XX:+HeapDumpOnOutOfMemoryError -XX:HeapDumpPath=<path>");
+    eprintln!("profiling options: -XX:+PrintClassHistogram -XX:+PrintAllocationSites -XX:AllocationSampleInterval=<n>");
+    eprintln!("native options: -Djava.library.path=<directories>");
+    eprintln!("class library options: --java-home <dir> (the classes of <dir>/lib/modules, such as a JDK 17's, instead of the bundled runtime)");
+    exit(2)
+}
+
+fn disasm_command(args: &[String]) {
+    let mut options = json::JsonOptions { pretty: true, ..Default::default() };
+    let mut as_json = false;
+    let mut class_path: Option<ClassPath> = None;
+    let mut names: Vec<&String> = Vec::new();
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--json" => as_json = true,
+            "--resolve" => options.resolve_constants = true,
+            "--instructions" => options.instructions = true,
+            "-cp" | "-classpath" | "--class-path" => {
+                let value = args.next().unwrap_or_else(|| usage());
+                class_path = Some(ClassPath::parse(value).unwrap_or_else(|e| {
+                    eprintln!("rusty-jvm: invalid class path {}: {:?}", value, e);
+                    exit(1)
+                }));
+            }
+            flag if flag.starts_with('-') => usage(),
+            _ => names.push(arg)
+        }
+    }
+    if names.is_empty() || (!as_json && (options.resolve_constants || options.instructions)) {
+        usage()
+    }
     let loader = Loader{};
-    let class = loader.load_from_file("/Users/user/IdeaProjects/cleaner/build/classes/kotlin/main/Options.class").unwrap();
-    let main = class.get_main().unwrap();
-    let mut env = VMEnv::of(VM::new(1024), Interpreter::new());
-    env.execute(&main.code).expect("executed");
+    let stdout = std::io::stdout();
+    let mut documents: Vec<json::JsonValue> = Vec::new();
+    for name in names {
+        let loaded = match &class_path {
+            Some(class_path) => {
+                let binary_name = name.replace('.', "/");
+                class_path.load_class(&loader, &binary_name).map(|class| (class, class_file_location(class_path, &binary_name)))
+            }
+            None => loader.load_from_file(name).map(|class| {
+                let full_path = std::fs::canonicalize(name).map(|p| p.display().to_string()).unwrap_or_else(|_| name.clone());
+                (class, full_path)
+            })
+        };
+        match loaded {
+            Ok((class, _)) if as_json => documents.push(json::class_to_json_value(&class, options)),
+            Ok((class, location)) => {
+                println!("Classfile {}", location);
+                disasm::disassemble(&class, &mut stdout.lock()).expect("write to stdout");
+            }
+            Err(e) => {
+                eprintln!("rusty-jvm: failed to load {}: {:?}", name, e);
+                exit(1)
+            }
+        }
+    }
+    if as_json {
+        let document = if documents.len() == 1 { documents.remove(0) } else { json::JsonValue::Array(documents) };
+        println!("{}", document.to_pretty_string());
+    }
 }
 
+/// Where a class was found, spelled like javap does (`jar:file:/a.jar!/com/foo/Bar.class`).
+fn class_file_location(class_path: &ClassPath, binary_name: &str) -> String {
+    let resource = format!("{}.class", binary_name);
+    for entry in class_path.entries() {
+        let full_path = std::fs::canonicalize(entry.path()).unwrap_or_else(|_| entry.path().to_path_buf());
+        match entry {
+            ClassPathEntry::Archive(archive) if archive.entry(&resource).is_some() => {
+                return format!("jar:file:{}!/{}", full_path.display(), resource)
+            }
+            ClassPathEntry::Directory(_) if full_path.join(&resource).is_file() => {
+                return full_path.join(&resource).display().to_string()
+            }
+            _ => {}
+        }
+    }
+    resource
+}
+
+fn fail(message: &str) -> ! {
+    eprintln!("{}", message);
+    exit(1)
+}
+
+fn run_command(args: &[String]) {
+    let mut release = DEFAULT_RELEASE;
+    let mut options = VMOptions::default();
+    let mut print_histogram = false;
+    let mut boot_class_path: Option<ClassPath> = None;
+    let mut args = args.iter();
+    let (class_path, main_class) = loop {
+        match args.next().map(String::as_str) {
+            Some("--release") => {
+                release = args.next().and_then(|v| v.parse().ok()).unwrap_or_else(|| usage());
+            }
+            Some("--java-home") => {
+                let java_home = args.next().unwrap_or_else(|| usage());
+                boot_class_path = Some(ClassPath::java_home(java_home)
+                    .unwrap_or_else(|e| fail(&format!("rusty-jvm: invalid java home {}: {:?}", java_home, e))));
+            }
+            Some(flag) if flag.starts_with("-Xmx") => {
+                options.max_heap = parse_heap_size(&flag[4..])
+                    .unwrap_or_else(|| fail(&format!("Invalid maximum heap size: {}\nError: Could not create the Java Virtual Machine.", flag)));
+            }
+            Some(flag) if flag.starts_with("-Xmn") => {
+                options.young_size = Some(parse_heap_size(&flag[4..])
+                    .unwrap_or_else(|| fail(&format!("Invalid initial young generation size: {}\nError: Could not create the Java Virtual Machine.", flag))));
+            }
+            Some("-XX:+UseMarkSweepGC") => options.collector = CollectorKind::MarkSweep,
+            Some("-XX:+UseGenerationalGC") => options.collector = CollectorKind::Generational,
+            Some("-verbose:gc") | Some("-Xlog:gc") => options.log_gc = true,
+            Some("-XX:+HeapDumpOnOutOfMemoryError") => options.heap_dump_on_out_of_memory = true,
+            Some(flag) if flag.starts_with("-XX:HeapDumpPath=") => options.heap_dump_path = Some(flag["-XX:HeapDumpPath=".len()..].into()),
+            Some("-XX:+PrintClassHistogram") => print_histogram = true,
+            Some("-XX:+PrintAllocationSites") => options.allocation_sample_interval = options.allocation_sample_interval.or(Some(1)),
+            Some(flag) if flag.starts_with("-XX:AllocationSampleInterval=") => {
+                options.allocation_sample_interval = Some(flag["-XX:AllocationSampleInterval=".len()..].parse().ok().filter(|n| *n > 0)
+                    .unwrap_or_else(|| fail(&format!("Invalid allocation sample interval: {}\nError: Could not create the Java Virtual Machine.", flag))));
+            }
+            Some(flag) if flag.starts_with("-Djava.library.path=") => {
+                options.library_path = flag["-Djava.library.path=".len()..].split(SEPARATOR).filter(|path| !path.is_empty()).map(Into::into).collect();
+            }
+            Some("-jar") => {
+                let jar = args.next().unwrap_or_else(|| usage());
+                let class_path = ClassPath::for_jar(jar, release)
+                    .unwrap_or_else(|_| fail(&format!("Error: Unable to access jarfile {}", jar)));
+                let main_class = class_path.manifest().and_then(|manifest| manifest.main_class())
+                    .unwrap_or_else(|| fail(&format!("no main manifest attribute, in {}", jar)));
+                break (class_path, main_class)
+            }
+            Some("-cp") | Some("-classpath") | Some("--class-path") => {
+                let value = args.next().unwrap_or_else(|| usage());
+                let mut class_path = ClassPath::for_release(release);
+                for path in value.split(SEPARATOR).filter(|path| !path.is_empty()) {
+                    // Like java, class path entries that cannot be opened are ignored.
+                    let _ = class_path.push(path);
+                }
+                let main_class = args.next().unwrap_or_else(|| usage()).replace('.', "/");
+                break (class_path, main_class)
+            }
+            _ => usage()
+        }
+    };
+    let java_name = main_class.replace('/', ".");
+    let boot_class_path = boot_class_path.unwrap_or_else(ClassPath::runtime);
+    let mut registry = ClassRegistry::new(boot_class_path, ClassPath::for_release(release), class_path);
+    let class = registry.load_class(LoaderId::APPLICATION, &main_class).unwrap_or_else(|e| {
+        let (exception, message) = e.java_exception();
+        fail(&format!("Error: Could not find or load main class {}\nCaused by: {}: {}", java_name, exception.replace('/', "."), message))
+    });
+    if registry.class(class).class.get_main().filter(|main| main.is_static()).is_none() {
+        fail(&format!("Error: Main method not found in class {}, please define the main method as:\n   public static void main(String[] args)", java_name))
+    }
+    let print_allocation_sites = options.allocation_sample_interval.is_some();
+    let program_args: Vec<String> = args.cloned().collect();
+    let mut env = VMEnv::of(VM::with_options(1024, registry, options), Interpreter::new());
+    let status = match env.run_main(class, &program_args) {
+        Ok(()) => 0,
+        Err(InterpreterError::Exit(status)) => status,
+        Err(InterpreterError::Exception(exception)) => {
+            let _ = env.stdout.flush();
+            eprint!("Exception in thread \"main\" {}", exception.stack_trace_text());
+            1
+        }
+        Err(e) => {
+            eprintln!("Exception in thread \"main\" java.lang.InternalError: {:?}", e);
+            1
+        }
+    };
+    let _ = env.stdout.flush();
+    // The reports describe the heap as the program left it.
+    if print_histogram {
+        env.vm().print_heap_histogram(&mut std::io::stdout().lock()).expect("write to stdout");
+    }
+    if print_allocation_sites {
+        env.vm().print_allocation_sites(&mut std::io::stdout().lock()).expect("write to stdout");
+    }
+    if status != 0 {
+        exit(status)
+    }
+}
 
 fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    match args.first().map(String::as_str) {
+        Some("disasm") => disasm_command(&args[1..]),
+        Some(_) => run_command(&args),
+        None => usage()
+    }
 }
@@ -0,0 +1,109 @@
+use crate::loader::ClassLoadError;
+
+pub const MANIFEST_NAME: &str = "META-INF/MANIFEST.MF";
+
+/// A named group of attributes. Names are matched case-insensitively, as the jar specification requires.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ManifestSection {
+    pub attributes: Vec<(String, String)>
+}
+
+impl ManifestSection {
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.attributes.iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+/// A parsed `META-INF/MANIFEST.MF`: the main section followed by per-entry sections keyed by `Name`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Manifest {
+    pub main_section: ManifestSection,
+    pub entries: Vec<(String, ManifestSection)>
+}
+
+impl Manifest {
+    pub fn parse(data: &[u8]) -> Result<Manifest, ClassLoadError> {
+        let text = String::from_utf8_lossy(data);
+        let invalid = |line: &str| ClassLoadError::InvalidManifest(line.to_string());
+
+        // Join continuation lines (starting with a single space) onto the header they continue.
+        let mut lines: Vec<String> = Vec::new();
+        for line in text.split("\r\n").flat_map(|l| l.split('\n')).flat_map(|l| l.split('\r')) {
+            match line.strip_prefix(' ') {
+                Some(continuation) => lines.last_mut().ok_or_else(|| invalid(line))?.push_str(continuation),
+                None => lines.push(line.to_string())
+            }
+        }
+
+        let mut sections: Vec<ManifestSection> = vec![ManifestSection::default()];
+        let mut section_ended = false;
+        for line in lines {
+            if line.is_empty() {
+                section_ended = true;
+                continue
+            }
+            if section_ended {
+                sections.push(ManifestSection::default());
+                section_ended = false;
+            }
+            let (name, value) = line.split_once(": ").ok_or_else(|| invalid(&line))?;
+            sections.last_mut().unwrap().attributes.push((name.to_string(), value.to_string()));
+        }
+
+        let mut sections = sections.into_iter();
+        let main_section = sections.next().unwrap_or_default();
+        let mut entries: Vec<(String, ManifestSection)> = Vec::new();
+        for section in sections {
+            let name = section.get("Name").ok_or_else(|| invalid("section without Name"))?.to_string();
+            entries.push((name, section));
+        }
+        Ok(Manifest { main_section, entries })
+    }
+
+    pub fn main_attribute(&self, name: &str) -> Option<&str> {
+        self.main_section.get(name)
+    }
+
+    /// The `Main-Class` attribute as a binary name (`com/foo/Main`).
+    pub fn main_class(&self) -> Option<String> {
+        self.main_attribute("Main-Class").map(|name| name.trim().replace('.', "/"))
+    }
+
+    /// The space separated relative URLs of the `Class-Path` attribute.
+    pub fn class_path(&self) -> Vec<String> {
+        self.main_attribute("Class-Path")
+            .map(|value| value.split(' ').filter(|url| !url.is_empty()).map(decode_url_path).collect())
+            .unwrap_or_default()
+    }
+
+    pub fn is_multi_release(&self) -> bool {
+        self.main_attribute("Multi-Release").map(|value| value.trim().eq_ignore_ascii_case("true")).unwrap_or(false)
+    }
+}
+
+/// Undoes `%XX` escapes in a relative URL path.
+fn decode_url_path(url: &str) -> String {
+    let bytes = url.as_bytes();
+    let mut ret: Vec<u8> = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let escaped = if bytes[i] == b'%' && i + 2 < bytes.len() {
+            std::str::from_utf8(&bytes[i + 1..i + 3]).ok().and_then(|hex| u8::from_str_radix(hex, 16).ok())
+        } else {
+            None
+        };
+        match escaped {
+            Some(byte) => {
+                ret.push(byte);
+                i += 3;
+            }
+            None => {
+                ret.push(bytes[i]);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&ret).into_owned()
+}
@@ -0,0 +1,265 @@
+//! Native methods implemented in Rust. A native is bound by its declaring class, name and
+//! descriptor; it gets the [`VMEnv`] that called it, like the `JNIEnv *` of JNI, and the arguments
+//! with the receiver first, and returns the method's value or throws by returning an exception.
+//! Calling a native method without a binding throws `UnsatisfiedLinkError`.
+
+use std::collections::HashMap;
+use std::io::Write;
+use std::time::{SystemTime, UNIX_EPOCH};
+use crate::descriptor::FieldType;
+use crate::env::VMEnv;
+use crate::jdk;
+use crate::jni;
+use crate::interpret::{InterpreterError, JavaException};
+use crate::strings;
+use crate::vm::{ObjRef, VMValue};
+
+/// What a native returns: the method's value, `None` for `void`, or the exception it throws.
+pub type NativeResult = Result<Option<VMValue>, InterpreterError>;
+
+/// A native method. The reference arguments are kept alive while it runs, but not updated if the
+/// collector moves them: natives that allocate have to keep track of moved objects themselves.
+pub type NativeFn = fn(&mut VMEnv, &[VMValue]) -> NativeResult;
+
+/// Binds a list of natives, grouped by class, in a [`NativeRegistry`]:
+///
+/// ```
+/// use rusty_jvm::natives;
+/// use rusty_jvm::native::NativeRegistry;
+/// use rusty_jvm::vm::VMValue;
+///
+/// let mut registry = NativeRegistry::new();
+/// natives!(registry,
+///     "Math" {
+///         "add" "(II)I" => |_, args| Ok(Some(VMValue::Int(args[0].as_int()? + args[1].as_int()?))),
+///         "nothing" "()V" => |_, _| Ok(None)
+///     }
+/// );
+/// assert!(registry.lookup("Math", "add", "(II)I").is_some());
+/// ```
+#[macro_export]
+macro_rules! natives {
+    ($registry:expr, $($class:literal { $($name:literal $descriptor:literal => $function:expr),* $(,)? })*) => {
+        $($($registry.register($class, $name, $descriptor, $function);)*)*
+    };
+}
+
+/// The natives the VM knows, by (class, name, descriptor).
+#[derive(Default)]
+pub struct NativeRegistry {
+    methods: HashMap<(String, String, String), NativeFn>
+}
+
+impl NativeRegistry {
+    pub fn new() -> Self {
+        NativeRegistry::default()
+    }
+
+    /// A registry with the natives of the runtime classes bound.
+    pub fn with_runtime_natives() -> Self {
+        let mut registry = NativeRegistry::new();
+        natives!(registry,
+            "java/lang/Object" {
+                "getClass" "()Ljava/lang/Class;" => |env, args| {
+                    let class = env.vm().heap.class_of(receiver(args)?);
+                    Ok(Some(VMValue::Reference(env.vm_mut().class_mirror(class)?)))
+                },
+                "hashCode" "()I" => |env, args| Ok(Some(VMValue::Int(env.vm_mut().heap.identity_hash(receiver(args)?)))),
+                "clone" "()Ljava/lang/Object;" => object_clone
+            }
+            "java/lang/Throwable" {
+                "fillInStackTrace" "(I)Ljava/lang/Throwable;" => |env, args| Ok(Some(VMValue::Reference(env.fill_in_stack_trace(receiver(args)?)?)))
+            }
+            "java/lang/String" {
+                "intern" "()Ljava/lang/String;" => |env, args| Ok(Some(VMValue::Reference(env.vm_mut().intern(receiver(args)?)?)))
+            }
+            "java/lang/System" {
+                "load" "(Ljava/lang/String;)V" => jni::system_load,
+                "loadLibrary" "(Ljava/lang/String;)V" => jni::system_load_library,
+                "mapLibraryName" "(Ljava/lang/String;)Ljava/lang/String;" => jni::system_map_library_name,
+                "currentTimeMillis" "()J" => |_, _| {
+                    let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default();
+                    Ok(Some(VMValue::Long(now.as_millis() as i64)))
+                },
+                "nanoTime" "()J" => |env, _| Ok(Some(VMValue::Long(env.vm().started.elapsed().as_nanos() as i64))),
+                "arraycopy" "(Ljava/lang/Object;ILjava/lang/Object;II)V" => |env, args| {
+                    let (src, dest) = (non_null(reference(args, 0)?)?, non_null(reference(args, 2)?)?);
+                    env.vm_mut().copy_array(src, args[1].as_int()?, dest, args[3].as_int()?, args[4].as_int()?)?;
+                    Ok(None)
+                },
+                "identityHashCode" "(Ljava/lang/Object;)I" => |env, args| Ok(Some(VMValue::Int(match reference(args, 0)? {
+                    Some(obj) => env.vm_mut().heap.identity_hash(obj),
+                    None => 0
+                }))),
+                "gc" "()V" => |env, _| {
+                    env.vm_mut().collect_garbage();
+                    Ok(None)
+                },
+                "exit" "(I)V" => |_, args| Err(InterpreterError::Exit(args[0].as_int()?))
+            }
+            "java/io/FileOutputStream" {
+                "writeBytes" "(I[BII)V" => file_output_stream_write_bytes
+            }
+            "java/lang/Character" {
+                "isAlphabetic" "(I)Z" => |_, args| character_test(args, char::is_alphabetic),
+                "isNumeric" "(I)Z" => |_, args| character_test(args, char::is_numeric),
+                "isUpperCase" "(I)Z" => |_, args| character_test(args, char::is_uppercase),
+                "isLowerCase" "(I)Z" => |_, args| character_test(args, char::is_lowercase),
+                "toUpperCase" "(I)I" => |_, args| character_mapping(args, char::to_uppercase),
+                "toLowerCase" "(I)I" => |_, args| character_mapping(args, char::to_lowercase)
+            }
+            "java/lang/Double" {
+                "parseDouble" "(Ljava/lang/String;)D" => |env, args| Ok(Some(VMValue::Double(parse_number(env, args)?))),
+                "toString" "(D)Ljava/lang/String;" => |env, args| {
+                    let text = strings::double_to_string(args[0].as_double()?);
+                    Ok(Some(VMValue::Reference(env.vm_mut().new_string(&text)?)))
+                },
+                "doubleToRawLongBits" "(D)J" => |_, args| Ok(Some(VMValue::Long(args[0].as_double()?.to_bits() as i64))),
+                "longBitsToDouble" "(J)D" => |_, args| Ok(Some(VMValue::Double(f64::from_bits(args[0].as_long()? as u64))))
+            }
+            "java/lang/Float" {
+                "parseFloat" "(Ljava/lang/String;)F" => |env, args| Ok(Some(VMValue::Float(parse_number(env, args)?))),
+                "toString" "(F)Ljava/lang/String;" => |env, args| {
+                    let text = strings::float_to_string(args[0].as_float()?);
+                    Ok(Some(VMValue::Reference(env.vm_mut().new_string(&text)?)))
+                },
+                "floatToRawIntBits" "(F)I" => |_, args| Ok(Some(VMValue::Int(args[0].as_float()?.to_bits() as i32))),
+                "intBitsToFloat" "(I)F" => |_, args| Ok(Some(VMValue::Float(f32::from_bits(args[0].as_int()? as u32))))
+            }
+            "java/util/Formatter" {
+                "formatFloating" "(DZI)Ljava/lang/String;" => |env, args| format_floating(env, format!("{:e}", args[0].as_double()?), args),
+                "formatFloating" "(FZI)Ljava/lang/String;" => |env, args| format_floating(env, format!("{:e}", args[0].as_float()?), args)
+            }
+            "java/lang/Math" {
+                "sin" "(D)D" => |_, args| math(args, f64::sin),
+                "cos" "(D)D" => |_, args| math(args, f64::cos),
+                "tan" "(D)D" => |_, args| math(args, f64::tan),
+                "asin" "(D)D" => |_, args| math(args, f64::asin),
+                "acos" "(D)D" => |_, args| math(args, f64::acos),
+                "atan" "(D)D" => |_, args| math(args, f64::atan),
+                "sinh" "(D)D" => |_, args| math(args, f64::sinh),
+                "cosh" "(D)D" => |_, args| math(args, f64::cosh),
+                "tanh" "(D)D" => |_, args| math(args, f64::tanh),
+                "exp" "(D)D" => |_, args| math(args, f64::exp),
+                "log" "(D)D" => |_, args| math(args, f64::ln),
+                "log10" "(D)D" => |_, args| math(args, f64::log10),
+                "sqrt" "(D)D" => |_, args| math(args, f64::sqrt),
+                "cbrt" "(D)D" => |_, args| math(args, f64::cbrt),
+                "floor" "(D)D" => |_, args| math(args, f64::floor),
+                "ceil" "(D)D" => |_, args| math(args, f64::ceil),
+                "rint" "(D)D" => |_, args| math(args, f64::round_ties_even),
+                "atan2" "(DD)D" => |_, args| Ok(Some(VMValue::Double(args[0].as_double()?.atan2(args[1].as_double()?)))),
+                "hypot" "(DD)D" => |_, args| Ok(Some(VMValue::Double(args[0].as_double()?.hypot(args[1].as_double()?)))),
+                "pow" "(DD)D" => |_, args| Ok(Some(VMValue::Double(pow(args[0].as_double()?, args[1].as_double()?))))
+            }
+        );
+        jdk::register_natives(&mut registry);
+        registry
+    }
+
+    /// Binds a native method, replacing any earlier binding.
+    pub fn register(&mut self, class: &str, name: &str, descriptor: &str, function: NativeFn) {
+        self.methods.insert((class.to_string(), name.to_string(), descriptor.to_string()), function);
+    }
+
+    /// Removes a binding, returning whether there was one.
+    pub fn unregister(&mut self, class: &str, name: &str, descriptor: &str) -> bool {
+        self.methods.remove(&(class.to_string(), name.to_string(), descriptor.to_string())).is_some()
+    }
+
+    pub fn lookup(&self, class: &str, name: &str, descriptor: &str) -> Option<NativeFn> {
+        self.methods.get(&(class.to_string(), name.to_string(), descriptor.to_string())).copied()
+    }
+}
+
+/// The receiver of an instance native, `this`, which is never null.
+pub fn receiver(args: &[VMValue]) -> Result<ObjRef, InterpreterError> {
+    reference(args, 0)?.ok_or_else(|| InterpreterError::Exception(JavaException::new("java/lang/NullPointerException", None)))
+}
+
+/// A reference argument, `None` for null.
+pub fn reference(args: &[VMValue], index: usize) -> Result<Option<ObjRef>, InterpreterError> {
+    args.get(index).copied().unwrap_or(VMValue::Null).as_reference()
+}
+
+pub(crate) fn non_null(obj: Option<ObjRef>) -> Result<ObjRef, InterpreterError> {
+    obj.ok_or_else(|| InterpreterError::Exception(JavaException::new("java/lang/NullPointerException", None)))
+}
+
+/// `Object.clone`, for arrays and instances of classes implementing `Cloneable`.
+fn object_clone(env: &mut VMEnv, args: &[VMValue]) -> NativeResult {
+    let obj = receiver(args)?;
+    let class = env.vm().heap.class_of(obj);
+    let cloneable = env.vm_mut().bootstrap_class("java/lang/Cloneable")?;
+    let classes = &env.vm().classes;
+    if !classes.class(class).is_array() && !classes.is_assignable(class, cloneable) {
+        return Err(InterpreterError::exception("java/lang/CloneNotSupportedException", classes.class(class).java_name()))
+    }
+    Ok(Some(VMValue::Reference(env.vm_mut().clone_object(obj)?)))
+}
+
+/// `FileOutputStream.writeBytes` of the bundled runtime, which passes the file descriptor.
+fn file_output_stream_write_bytes(env: &mut VMEnv, args: &[VMValue]) -> NativeResult {
+    write_bytes(env, args[0].as_int()?, non_null(reference(args, 1)?)?, args[2].as_int()?, args[3].as_int()?)?;
+    Ok(None)
+}
+
+/// Writes bytes of an array to the standard output or error of [`VMEnv`]; the caller checked the range.
+pub(crate) fn write_bytes(env: &mut VMEnv, fd: i32, array: ObjRef, offset: i32, length: i32) -> Result<(), InterpreterError> {
+    let (offset, length) = (offset as u32, length as u32);
+    let heap = &env.vm().heap;
+    let bytes: Vec<u8> = (offset..offset + length)
+        .map(|index| heap.read_element(array, index, &FieldType::Byte).as_int().map(|byte| byte as u8))
+        .collect::<Result<_, _>>()?;
+    let written = match fd {
+        1 => env.stdout.write_all(&bytes),
+        2 => env.stderr.write_all(&bytes),
+        _ => return Err(InterpreterError::exception("java/io/IOException", "Bad file descriptor"))
+    };
+    written.map_err(|e| InterpreterError::exception("java/io/IOException", e.to_string()))
+}
+
+fn character_test(args: &[VMValue], test: fn(char) -> bool) -> NativeResult {
+    let code_point = char::from_u32(args[0].as_int()? as u32);
+    Ok(Some(VMValue::Int(code_point.is_some_and(test) as i32)))
+}
+
+/// A case mapping of a code point, which stays as it is unless it maps to exactly one character.
+fn character_mapping<I: Iterator<Item = char>>(args: &[VMValue], mapping: fn(char) -> I) -> NativeResult {
+    let code_point = args[0].as_int()?;
+    let mapped = char::from_u32(code_point as u32).and_then(|c| {
+        let mut mapped = mapping(c);
+        mapped.next().filter(|_| mapped.next().is_none())
+    });
+    Ok(Some(VMValue::Int(mapped.map(|c| c as i32).unwrap_or(code_point))))
+}
+
+/// `Double.parseDouble` and `Float.parseFloat`, see [`strings::parse_floating`].
+fn parse_number<T: std::str::FromStr>(env: &mut VMEnv, args: &[VMValue]) -> Result<T, InterpreterError> {
+    let text = env.vm().string_value(non_null(reference(args, 0)?)?)?;
+    match strings::parse_floating(&text) {
+        Some(value) => Ok(value),
+        None if text.trim_matches(|c: char| c <= ' ').is_empty() => Err(InterpreterError::exception("java/lang/NumberFormatException", "empty String")),
+        None if text.matches('.').count() > 1 => Err(InterpreterError::exception("java/lang/NumberFormatException", "multiple points")),
+        None => Err(InterpreterError::exception("java/lang/NumberFormatException", format!("For input string: \"{}\"", text)))
+    }
+}
+
+/// `Formatter.formatFloating` of a finite, non-negative value, see [`strings::format_floating`].
+fn format_floating(env: &mut VMEnv, shortest: String, args: &[VMValue]) -> NativeResult {
+    let text = strings::format_floating(&shortest, args[1].as_int()? != 0, args[2].as_int()?.max(0) as usize);
+    Ok(Some(VMValue::Reference(env.vm_mut().new_string(&text)?)))
+}
+
+fn math(args: &[VMValue], function: fn(f64) -> f64) -> NativeResult {
+    Ok(Some(VMValue::Double(function(args[0].as_double()?))))
+}
+
+/// `Math.pow`, which unlike C's `pow` is NaN for a NaN exponent and for ±1 to an infinite power.
+fn pow(a: f64, b: f64) -> f64 {
+    if b.is_nan() || (a.abs() == 1.0 && b.is_infinite()) {
+        f64::NAN
+    } else {
+        a.powf(b)
+    }
+}
@@ -0,0 +1,114 @@
+//! Lightweight heap profiling: a class histogram of the heap like `jmap -histo`, and sampling
+//! of the bytecode instructions that allocate, with `-XX:AllocationSampleInterval`.
+
+use std::collections::HashMap;
+use std::io::{self, Write};
+use crate::env::VMEnv;
+use crate::registry::ClassId;
+use crate::vm::{ObjRef, VM};
+
+/// The objects of one class in the heap.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct HistogramEntry {
+    pub class: ClassId,
+    pub instances: usize,
+    pub bytes: usize
+}
+
+/// A bytecode instruction that allocates: `new`, `newarray`, `anewarray` or `multianewarray`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct AllocationSite {
+    /// The class declaring the method and the method's index in it.
+    pub class: ClassId,
+    pub method: usize,
+    pub bci: usize
+}
+
+/// What the samples taken at an allocation site add up to.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub struct AllocationStats {
+    pub samples: usize,
+    pub bytes: usize,
+    /// The class of the objects allocated, the outermost array for `multianewarray`.
+    pub allocated: Option<ClassId>
+}
+
+impl VM {
+    /// Counts the objects in the heap by class, most bytes first. Unreachable objects the
+    /// collector has not freed yet are counted too, see [`VM::collect_garbage`].
+    pub fn heap_histogram(&self) -> Vec<HistogramEntry> {
+        let mut counts: HashMap<ClassId, HistogramEntry> = HashMap::new();
+        for obj in self.heap.objects() {
+            let class = self.heap.class_of(obj);
+            let entry = counts.entry(class).or_insert(HistogramEntry { class, instances: 0, bytes: 0 });
+            entry.instances += 1;
+            entry.bytes += self.heap.size_of(obj) as usize;
+        }
+        let mut histogram: Vec<HistogramEntry> = counts.into_values().collect();
+        histogram.sort_by(|a, b| b.bytes.cmp(&a.bytes).then_with(|| self.classes.class(a.class).name.cmp(&self.classes.class(b.class).name)));
+        histogram
+    }
+
+    /// Prints the histogram in the format of `jmap -histo`.
+    pub fn print_heap_histogram(&self, out: &mut impl Write) -> io::Result<()> {
+        let histogram = self.heap_histogram();
+        writeln!(out, " num     #instances         #bytes  class name")?;
+        writeln!(out, "----------------------------------------------")?;
+        for (index, entry) in histogram.iter().enumerate() {
+            writeln!(out, "{:>4}: {:>13} {:>14}  {}", index + 1, entry.instances, entry.bytes, self.classes.class(entry.class).java_name())?;
+        }
+        let instances: usize = histogram.iter().map(|entry| entry.instances).sum();
+        let bytes: usize = histogram.iter().map(|entry| entry.bytes).sum();
+        writeln!(out, "Total {:>13} {:>14}", instances, bytes)
+    }
+
+    /// Counts an allocation at `site` and takes one sample in every `allocation_sample_interval`.
+    pub(crate) fn record_allocation(&mut self, site: AllocationSite, obj: ObjRef) {
+        let interval = match self.options.allocation_sample_interval {
+            Some(interval) => interval.max(1),
+            None => return
+        };
+        self.allocations += 1;
+        if !self.allocations.is_multiple_of(interval as u64) {
+            return
+        }
+        let stats = self.allocation_sites.entry(site).or_default();
+        stats.samples += 1;
+        stats.bytes += self.heap.size_of(obj) as usize;
+        stats.allocated = Some(self.heap.class_of(obj));
+    }
+
+    /// The sampled allocation sites, most bytes first.
+    pub fn allocation_sites(&self) -> Vec<(AllocationSite, AllocationStats)> {
+        let mut sites: Vec<(AllocationSite, AllocationStats)> = self.allocation_sites.iter().map(|(site, stats)| (*site, *stats)).collect();
+        sites.sort_by(|(a_site, a), (b_site, b)| b.bytes.cmp(&a.bytes).then_with(|| (a_site.class, a_site.method, a_site.bci).cmp(&(b_site.class, b_site.method, b_site.bci))));
+        sites
+    }
+
+    /// Prints the sampled allocation sites, one line per site with the method, the bytecode index
+    /// and the class allocated.
+    pub fn print_allocation_sites(&self, out: &mut impl Write) -> io::Result<()> {
+        let sites = self.allocation_sites();
+        writeln!(out, " num       #samples         #bytes  allocation site")?;
+        writeln!(out, "----------------------------------------------")?;
+        for (index, (site, stats)) in sites.iter().enumerate() {
+            let class = self.classes.class(site.class);
+            let method = &class.class.methods[site.method];
+            let allocated = stats.allocated.map(|allocated| self.classes.class(allocated).java_name()).unwrap_or_default();
+            writeln!(out, "{:>4}: {:>13} {:>14}  {}.{}{} @ {} ({})", index + 1, stats.samples, stats.bytes, class.java_name(),
+                     method.method_name, method.descriptor, site.bci, allocated)?;
+        }
+        let samples: usize = sites.iter().map(|(_, stats)| stats.samples).sum();
+        let bytes: usize = sites.iter().map(|(_, stats)| stats.bytes).sum();
+        writeln!(out, "Total {:>13} {:>14}", samples, bytes)
+    }
+}
+
+impl VMEnv {
+    /// Records an object the current instruction allocated, see [`VM::record_allocation`].
+    pub(crate) fn record_allocation(&mut self, obj: ObjRef) {
+        let frame = self.frames().last().unwrap();
+        let site = AllocationSite { class: frame.class, method: frame.method, bci: frame.pc };
+        self.vm_mut().record_allocation(site, obj);
+    }
+}
@@ -0,0 +1,724 @@
+use std::collections::{HashMap, HashSet};
+use std::rc::Rc;
+use crate::classpath::ClassPath;
+use crate::descriptor::{FieldType, MethodDescriptor};
+use crate::gc::ReferenceKind;
+use crate::interpret::Interpreter;
+use crate::loader::{AccessFlags, Class, ClassFileConstantPool, ClassLoadError, Field, Loader, Method};
+use crate::vm::{ObjRef, ObjectLayout, VMValue};
+
+/// Identifies a class loader. The three built-in loaders have fixed ids, loaders created by
+/// Java code (subclasses of `java.lang.ClassLoader`) are numbered after them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct LoaderId(pub u32);
+
+impl LoaderId {
+    pub const BOOTSTRAP: LoaderId = LoaderId(0);
+    pub const PLATFORM: LoaderId = LoaderId(1);
+    pub const APPLICATION: LoaderId = LoaderId(2);
+}
+
+/// Index of a class in the [`ClassRegistry`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct ClassId(pub u32);
+
+pub struct ClassLoaderInfo {
+    pub id: LoaderId,
+    pub name: String,
+    /// `None` for the bootstrap loader, which has no parent to delegate to.
+    pub parent: Option<LoaderId>,
+    /// Where the loader finds classes itself. Loaders defined in Java have an empty class path
+    /// and only know the classes handed to [`ClassRegistry::define_class`].
+    pub class_path: ClassPath
+}
+
+/// Where a class is in its lifecycle (JVMS 5.3 - 5.5). States only ever advance, except that
+/// a failed initialization moves the class to `Erroneous` for good.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum ClassState {
+    Loaded,
+    Verified,
+    /// Static fields exist and hold their default values; the class is linked.
+    Prepared,
+    BeingInitialized,
+    Initialized,
+    Erroneous
+}
+
+/// A class as known to the running VM, i.e. a parsed class file plus its defining loader
+/// and resolved direct supertypes.
+pub struct RuntimeClass {
+    pub id: ClassId,
+    pub name: String,
+    pub defining_loader: LoaderId,
+    pub class: Rc<Class>,
+    pub super_class: Option<ClassId>,
+    pub interfaces: Vec<ClassId>,
+    pub state: ClassState,
+    /// Values of the static fields, indexed like `class.fields`; entries of instance fields are unused.
+    pub statics: Vec<VMValue>,
+    pub layout: Rc<ObjectLayout>,
+    /// For array classes, the type of the elements and, unless they are primitive, their class.
+    pub component_type: Option<FieldType>,
+    pub component_class: Option<ClassId>,
+    /// The `java.lang.Class` object of this class, created on first use.
+    pub mirror: Option<ObjRef>,
+    /// Loadable constants (JVMS 5.1) already resolved by `ldc`, by constant pool index.
+    pub resolved_constants: HashMap<u16, VMValue>,
+    /// For subclasses of `java.lang.ref.Reference`, how the garbage collector treats the referent.
+    pub reference_kind: Option<ReferenceKind>,
+    /// Whether instances have to be finalized: the class or a superclass overrides `finalize()`
+    /// with a method that does more than return.
+    pub finalizable: bool
+}
+
+impl RuntimeClass {
+    pub fn is_interface(&self) -> bool {
+        self.class.access_flags.contains(&AccessFlags::Interface)
+    }
+
+    /// The value of a static field by name, once the class has been prepared.
+    pub fn static_value(&self, name: &str) -> Option<VMValue> {
+        self.class.fields.iter().position(|f| f.field_name == name && f.is_static())
+            .and_then(|index| self.statics.get(index).copied())
+    }
+
+    pub fn is_array(&self) -> bool {
+        self.component_type.is_some()
+    }
+
+    pub fn is_linked(&self) -> bool {
+        self.state >= ClassState::Prepared
+    }
+
+    /// Name as printed in Java messages, e.g. `java.lang.String`.
+    pub fn java_name(&self) -> String {
+        self.name.replace('/', ".")
+    }
+}
+
+/// All classes loaded by the VM, keyed by (defining loader, binary name) as in JVMS 5.3.
+pub struct ClassRegistry {
+    loader: Loader,
+    loaders: Vec<ClassLoaderInfo>,
+    classes: Vec<RuntimeClass>,
+    defined: HashMap<(LoaderId, String), ClassId>,
+    /// Classes by initiating loader: every loader a class was requested from records the result,
+    /// so later requests do not delegate again.
+    initiated: HashMap<(LoaderId, String), ClassId>,
+    /// Classes whose supertypes are being resolved, used to detect circular hierarchies.
+    loading: HashSet<(LoaderId, String)>,
+    /// Pseudo classes of the primitive types and `void`, by Java name.
+    primitives: HashMap<String, ClassId>
+}
+
+impl ClassRegistry {
+    pub fn new(boot_class_path: ClassPath, platform_class_path: ClassPath, application_class_path: ClassPath) -> Self {
+        let loaders = vec![
+            ClassLoaderInfo { id: LoaderId::BOOTSTRAP, name: "'bootstrap'".to_string(), parent: None, class_path: boot_class_path },
+            ClassLoaderInfo { id: LoaderId::PLATFORM, name: "'platform'".to_string(), parent: Some(LoaderId::BOOTSTRAP), class_path: platform_class_path },
+            ClassLoaderInfo { id: LoaderId::APPLICATION, name: "'app'".to_string(), parent: Some(LoaderId::PLATFORM), class_path: application_class_path }
+        ];
+        ClassRegistry {
+            loader: Loader{},
+            loaders,
+            classes: Vec::new(),
+            defined: HashMap::new(),
+            initiated: HashMap::new(),
+            loading: HashSet::new(),
+            primitives: HashMap::new()
+        }
+    }
+
+    /// Registers a loader created by Java code; `parent` is where it delegates to first.
+    pub fn add_loader(&mut self, name: &str, parent: LoaderId) -> Result<LoaderId, ClassLoadError> {
+        self.loader_info(parent)?;
+        let id = LoaderId(self.loaders.len() as u32);
+        self.loaders.push(ClassLoaderInfo { id, name: format!("'{}'", name), parent: Some(parent), class_path: ClassPath::new() });
+        Ok(id)
+    }
+
+    pub fn loader_info(&self, loader: LoaderId) -> Result<&ClassLoaderInfo, ClassLoadError> {
+        self.loaders.get(loader.0 as usize).ok_or(ClassLoadError::UnknownLoader(loader.0))
+    }
+
+    pub fn class(&self, id: ClassId) -> &RuntimeClass {
+        &self.classes[id.0 as usize]
+    }
+
+    pub fn class_mut(&mut self, id: ClassId) -> &mut RuntimeClass {
+        &mut self.classes[id.0 as usize]
+    }
+
+    pub fn classes(&self) -> &[RuntimeClass] {
+        &self.classes
+    }
+
+    pub fn classes_mut(&mut self) -> &mut [RuntimeClass] {
+        &mut self.classes
+    }
+
+    /// The class `loader` has already been recorded as initiating loader for, like `findLoadedClass`.
+    pub fn find_loaded_class(&self, loader: LoaderId, name: &str) -> Option<ClassId> {
+        self.initiated.get(&(loader, name.to_string())).copied()
+    }
+
+    /// Loads a class by binary name, delegating to the parent loader first.
+    pub fn load_class(&mut self, loader: LoaderId, name: &str) -> Result<ClassId, ClassLoadError> {
+        if let Some(id) = self.find_loaded_class(loader, name) {
+            return Ok(id)
+        }
+        if name.starts_with('[') {
+            let id = self.load_array_class(loader, name)?;
+            self.initiated.insert((loader, name.to_string()), id);
+            return Ok(id)
+        }
+        let parent = self.loader_info(loader)?.parent;
+        let delegated = match parent {
+            Some(parent) => match self.load_class(parent, name) {
+                Ok(id) => Some(id),
+                Err(ClassLoadError::ClassNotFound(_)) => None,
+                Err(e) => return Err(e)
+            },
+            None => None
+        };
+        let id = match delegated {
+            Some(id) => id,
+            None => {
+                if self.loading.contains(&(loader, name.to_string())) {
+                    return Err(ClassLoadError::ClassCircularity(name.to_string()))
+                }
+                let data = self.loader_info(loader)?.class_path.find_class_bytes(name)?
+                    .ok_or_else(|| ClassLoadError::ClassNotFound(name.to_string()))?;
+                self.define_class(loader, Some(name), &data)?
+            }
+        };
+        self.initiated.insert((loader, name.to_string()), id);
+        Ok(id)
+    }
+
+    /// Defines a class from class file bytes with `loader` as its defining loader, as
+    /// `ClassLoader.defineClass` does. `name`, when given, must match the name in the class file.
+    pub fn define_class(&mut self, loader: LoaderId, name: Option<&str>, data: &[u8]) -> Result<ClassId, ClassLoadError> {
+        let loader_name = self.loader_info(loader)?.name.clone();
+        let mut class = self.loader.load_from_bytes(data)?;
+        let class_name = class.class_name.clone();
+        if let Some(name) = name {
+            if name != class_name {
+                return Err(ClassLoadError::WrongName(name.to_string(), class_name))
+            }
+        }
+        if loader != LoaderId::BOOTSTRAP && class_name.starts_with("java/") {
+            let package = class_name.rsplit_once('/').map(|(package, _)| package).unwrap_or("");
+            return Err(ClassLoadError::ProhibitedPackage(package.to_string()))
+        }
+        if loader == LoaderId::BOOTSTRAP && class_name == "java/lang/Class" && !class.fields.iter().any(|field| field.field_name == "classId") {
+            // The mirrors of a JDK's java.base lack the field that ties them to their class, so it
+            // is added to their layout like HotSpot injects its own fields into java.lang.Class.
+            class.fields.push(Field { field_name: "classId".to_string(), descriptor: "I".to_string(),
+                                      access_flags: vec![AccessFlags::Private, AccessFlags::Transient, AccessFlags::Synthetic], attributes: Vec::new() });
+        }
+        let key = (loader, class_name.clone());
+        if self.defined.contains_key(&key) || self.loading.contains(&key) {
+            return Err(ClassLoadError::DuplicateClassDefinition(loader_name, class_name))
+        }
+
+        self.loading.insert(key.clone());
+        let supertypes = self.resolve_supertypes(loader, &class);
+        self.loading.remove(&key);
+        let (super_class, interfaces) = supertypes?;
+        let mut layout = ObjectLayout::compute(super_class.map(|id| self.class(id).layout.as_ref()), &class.fields)?;
+        if class_name == "java/lang/ref/Reference" {
+            layout.referent_offset = class.fields.iter().position(|field| field.field_name == "referent")
+                .and_then(|index| layout.field_offsets[index]);
+        }
+        let reference_kind = match class_name.as_str() {
+            "java/lang/ref/SoftReference" => Some(ReferenceKind::Soft),
+            "java/lang/ref/WeakReference" => Some(ReferenceKind::Weak),
+            "java/lang/ref/FinalReference" => Some(ReferenceKind::Final),
+            "java/lang/ref/PhantomReference" => Some(ReferenceKind::Phantom),
+            _ => super_class.and_then(|id| self.class(id).reference_kind)
+        };
+        // A finalize that only returns is not worth finalizing for, as in HotSpot.
+        let finalizable = class.methods.iter()
+            .any(|m| m.method_name == "finalize" && m.descriptor == "()V" && !m.is_static() && !m.code.is_empty() && m.code != [0xb1])
+            || super_class.is_some_and(|id| self.class(id).finalizable);
+
+        let id = ClassId(self.classes.len() as u32);
+        self.classes.push(RuntimeClass {
+            id,
+            name: class_name,
+            defining_loader: loader,
+            class: Rc::new(class),
+            super_class,
+            interfaces,
+            state: ClassState::Loaded,
+            statics: Vec::new(),
+            layout: Rc::new(layout),
+            component_type: None,
+            component_class: None,
+            mirror: None,
+            resolved_constants: HashMap::new(),
+            reference_kind,
+            finalizable
+        });
+        self.defined.insert(key.clone(), id);
+        self.initiated.insert(key, id);
+        Ok(id)
+    }
+
+    /// Creates an array class such as `[I` or `[[Ljava/lang/String;` (JVMS 5.3.3). Its defining loader
+    /// is the one of the element class, the bootstrap loader for primitive elements.
+    fn load_array_class(&mut self, loader: LoaderId, name: &str) -> Result<ClassId, ClassLoadError> {
+        let component_type = match FieldType::parse(name)? {
+            FieldType::Array(component) => *component,
+            _ => return Err(ClassLoadError::ClassNotFound(name.to_string()))
+        };
+        let component_class = match &component_type {
+            FieldType::Object(component_name) => Some(self.load_class(loader, component_name)?),
+            FieldType::Array(_) => Some(self.load_class(loader, &name[1..])?),
+            _ => None
+        };
+        let defining_loader = component_class.map(|id| self.class(id).defining_loader).unwrap_or(LoaderId::BOOTSTRAP);
+        if let Some(id) = self.defined.get(&(defining_loader, name.to_string())) {
+            return Ok(*id)
+        }
+
+        let object = self.load_class(LoaderId::BOOTSTRAP, "java/lang/Object")?;
+        let interface_names = vec!["java/lang/Cloneable".to_string(), "java/io/Serializable".to_string()];
+        let mut interfaces: Vec<ClassId> = Vec::with_capacity(interface_names.len());
+        for interface_name in &interface_names {
+            interfaces.push(self.load_class(LoaderId::BOOTSTRAP, interface_name)?);
+        }
+        let public = component_class.map(|id| self.class(id).class.access_flags.contains(&AccessFlags::Public)).unwrap_or(true);
+        let mut access_flags = vec![AccessFlags::Final, AccessFlags::Abstract];
+        if public {
+            access_flags.insert(0, AccessFlags::Public);
+        }
+        let class = Class {
+            class_name: name.to_string(),
+            super_class_name: Some("java/lang/Object".to_string()),
+            interface_names,
+            source_file_name: None,
+            version_minor: 0,
+            version_major: 0,
+            access_flags,
+            this_class: 0,
+            super_class: 0,
+            interfaces: Vec::new(),
+            constant_pool: ClassFileConstantPool { constant_pool_count: 0, constant_pool: Vec::new() },
+            fields: Vec::new(),
+            methods: Vec::new(),
+            attributes: Vec::new()
+        };
+
+        let id = ClassId(self.classes.len() as u32);
+        self.classes.push(RuntimeClass {
+            id,
+            name: name.to_string(),
+            defining_loader,
+            class: Rc::new(class),
+            super_class: Some(object),
+            interfaces,
+            // Array classes have nothing to verify or initialize.
+            state: ClassState::Initialized,
+            statics: Vec::new(),
+            layout: self.class(object).layout.clone(),
+            component_type: Some(component_type),
+            component_class,
+            mirror: None,
+            resolved_constants: HashMap::new(),
+            reference_kind: None,
+            finalizable: false
+        });
+        self.defined.insert((defining_loader, name.to_string()), id);
+        self.initiated.insert((defining_loader, name.to_string()), id);
+        Ok(id)
+    }
+
+    /// Spins the class of a lambda created in `host`, which implements the named interfaces with
+    /// `methods`. Like the hidden classes of HotSpot it shares the loader of its host but is not
+    /// in that loader's namespace, so no name resolves to it.
+    pub fn define_lambda_class(&mut self, host: ClassId, name: String, interface_names: Vec<String>,
+                               fields: Vec<Field>, methods: Vec<Method>) -> Result<ClassId, ClassLoadError> {
+        let object = self.load_class(LoaderId::BOOTSTRAP, "java/lang/Object")?;
+        let mut interfaces: Vec<ClassId> = Vec::with_capacity(interface_names.len());
+        for interface_name in &interface_names {
+            interfaces.push(self.resolve_class(host, interface_name)?);
+        }
+        let layout = ObjectLayout::compute(Some(self.class(object).layout.as_ref()), &fields)?;
+        let class = Class {
+            class_name: name.clone(),
+            super_class_name: Some("java/lang/Object".to_string()),
+            interface_names,
+            source_file_name: None,
+            version_minor: 0,
+            version_major: 0,
+            access_flags: vec![AccessFlags::Final, AccessFlags::Synthetic],
+            this_class: 0,
+            super_class: 0,
+            interfaces: Vec::new(),
+            constant_pool: ClassFileConstantPool { constant_pool_count: 0, constant_pool: Vec::new() },
+            fields,
+            methods,
+            attributes: Vec::new()
+        };
+
+        let id = ClassId(self.classes.len() as u32);
+        let defining_loader = self.class(host).defining_loader;
+        self.classes.push(RuntimeClass {
+            id,
+            name,
+            defining_loader,
+            class: Rc::new(class),
+            super_class: Some(object),
+            interfaces,
+            // There are no static fields and no initializer.
+            state: ClassState::Initialized,
+            statics: Vec::new(),
+            layout: Rc::new(layout),
+            component_type: None,
+            component_class: None,
+            mirror: None,
+            resolved_constants: HashMap::new(),
+            reference_kind: None,
+            finalizable: false
+        });
+        Ok(id)
+    }
+
+    pub fn is_primitive(&self, id: ClassId) -> bool {
+        self.primitives.values().any(|primitive| *primitive == id)
+    }
+
+    /// The pseudo class of a primitive type or `void` (`None`), which only exists to have a mirror
+    /// such as `int.class`.
+    pub fn primitive_class(&mut self, field_type: Option<&FieldType>) -> ClassId {
+        let name = field_type.map(FieldType::java_name).unwrap_or_else(|| "void".to_string());
+        if let Some(id) = self.primitives.get(&name) {
+            return *id
+        }
+        let class = Class {
+            class_name: name.clone(),
+            super_class_name: None,
+            interface_names: Vec::new(),
+            source_file_name: None,
+            version_minor: 0,
+            version_major: 0,
+            access_flags: vec![AccessFlags::Public, AccessFlags::Final, AccessFlags::Abstract],
+            this_class: 0,
+            super_class: 0,
+            interfaces: Vec::new(),
+            constant_pool: ClassFileConstantPool { constant_pool_count: 0, constant_pool: Vec::new() },
+            fields: Vec::new(),
+            methods: Vec::new(),
+            attributes: Vec::new()
+        };
+        let id = ClassId(self.classes.len() as u32);
+        self.classes.push(RuntimeClass {
+            id,
+            name: name.clone(),
+            defining_loader: LoaderId::BOOTSTRAP,
+            class: Rc::new(class),
+            super_class: None,
+            interfaces: Vec::new(),
+            state: ClassState::Initialized,
+            statics: Vec::new(),
+            layout: Rc::new(ObjectLayout::default()),
+            component_type: None,
+            component_class: None,
+            mirror: None,
+            resolved_constants: HashMap::new(),
+            reference_kind: None,
+            finalizable: false
+        });
+        self.primitives.insert(name, id);
+        id
+    }
+
+    /// Resolves the class a field type found in `from` refers to, like [`ClassRegistry::resolve_class`];
+    /// primitive types map to their pseudo classes.
+    pub fn class_for_type(&mut self, from: ClassId, field_type: &FieldType) -> Result<ClassId, ClassLoadError> {
+        match field_type {
+            FieldType::Object(name) => self.resolve_class(from, name),
+            FieldType::Array(_) => self.resolve_class(from, &field_type.descriptor()),
+            _ => Ok(self.primitive_class(Some(field_type)))
+        }
+    }
+
+    /// Finds an instance field by name in a class or its superclasses, returning its offset and type.
+    pub fn instance_field(&self, id: ClassId, name: &str) -> Option<(u32, FieldType)> {
+        let mut current = Some(id);
+        while let Some(class_id) = current {
+            let class = self.class(class_id);
+            let found = class.class.fields.iter().enumerate()
+                .find(|(index, field)| field.field_name == name && class.layout.field_offsets[*index].is_some());
+            if let Some((index, _)) = found {
+                return Some((class.layout.field_offsets[index].unwrap(), class.layout.field_types[index].clone().unwrap()))
+            }
+            current = class.super_class;
+        }
+        None
+    }
+
+    /// Loads the direct superclass and superinterfaces through the defining loader (JVMS 5.3.5).
+    fn resolve_supertypes(&mut self, loader: LoaderId, class: &Class) -> Result<(Option<ClassId>, Vec<ClassId>), ClassLoadError> {
+        let resolve = |registry: &mut ClassRegistry, name: &str| match registry.load_class(loader, name) {
+            Err(ClassLoadError::ClassNotFound(name)) => Err(ClassLoadError::NoClassDefFound(name)),
+            other => other
+        };
+        let java_name = class.class_name.replace('/', ".");
+        let super_class = match &class.super_class_name {
+            Some(super_name) => {
+                let id = resolve(self, super_name)?;
+                if self.class(id).is_interface() {
+                    return Err(ClassLoadError::IncompatibleClassChange(format!(
+                        "class {} has interface {} as super class", java_name, super_name.replace('/', "."))))
+                }
+                Some(id)
+            }
+            None => None
+        };
+        let mut interfaces: Vec<ClassId> = Vec::with_capacity(class.interface_names.len());
+        for interface_name in &class.interface_names {
+            let id = resolve(self, interface_name)?;
+            if !self.class(id).is_interface() {
+                return Err(ClassLoadError::IncompatibleClassChange(format!(
+                    "class {} can not implement {}, because it is not an interface", java_name, interface_name.replace('/', "."))))
+            }
+            interfaces.push(id);
+        }
+        Ok((super_class, interfaces))
+    }
+
+    /// Resolves a class name found in the constant pool of `from`, using its defining loader.
+    pub fn resolve_class(&mut self, from: ClassId, name: &str) -> Result<ClassId, ClassLoadError> {
+        let loader = self.class(from).defining_loader;
+        match self.load_class(loader, name) {
+            Err(ClassLoadError::ClassNotFound(name)) => Err(ClassLoadError::NoClassDefFound(name)),
+            other => other
+        }
+    }
+
+    /// Verifies and prepares a class and its supertypes (JVMS 5.4).
+    pub fn link(&mut self, id: ClassId) -> Result<(), ClassLoadError> {
+        if self.class(id).is_linked() {
+            return Ok(())
+        }
+        let supertypes: Vec<ClassId> = self.class(id).super_class.iter().chain(self.class(id).interfaces.iter()).copied().collect();
+        for supertype in supertypes {
+            self.link(supertype)?;
+        }
+        if self.class(id).state == ClassState::Loaded {
+            self.verify(id)?;
+            self.class_mut(id).state = ClassState::Verified;
+        }
+        self.prepare(id)?;
+        self.class_mut(id).state = ClassState::Prepared;
+        Ok(())
+    }
+
+    /// Structural checks on every method: code is present exactly when the method is neither
+    /// abstract nor native, every instruction decodes, and branches and handlers land on instructions.
+    fn verify(&self, id: ClassId) -> Result<(), ClassLoadError> {
+        let class = &self.class(id).class;
+        let interpreter = Interpreter::new();
+        for method in &class.methods {
+            let fail = |reason: &str| Err(ClassLoadError::Verify(class.class_name.clone(),
+                format!("{} in method {}{}", reason, method.method_name, method.descriptor)));
+            let descriptor = MethodDescriptor::parse(&method.descriptor)?;
+            let is_abstract = method.access_flags.contains(&AccessFlags::Abstract) || method.access_flags.contains(&AccessFlags::Native);
+            if is_abstract {
+                if !method.code.is_empty() {
+                    return fail("Code attribute in native or abstract method")
+                }
+                continue
+            }
+            if method.code.is_empty() {
+                return fail("Missing Code attribute")
+            }
+            let parameter_slots = descriptor.parameter_slots() + if method.is_static() { 0 } else { 1 };
+            if (method.max_locals as usize) < parameter_slots {
+                return fail("Arguments can't fit into locals")
+            }
+            let mut starts = vec![false; method.code.len() + 1];
+            let mut targets: Vec<i64> = Vec::new();
+            let mut pc = 0;
+            while pc < method.code.len() {
+                let (opcode, next_pc) = match interpreter.decode(&method.code, pc) {
+                    Ok(decoded) => decoded,
+                    Err(_) => return fail(&format!("Bad instruction at {}", pc))
+                };
+                starts[pc] = true;
+                targets.extend(opcode.branch_offsets().iter().map(|offset| pc as i64 + *offset as i64));
+                pc = next_pc;
+            }
+            starts[method.code.len()] = true;
+            for target in targets {
+                if target < 0 || target as usize >= method.code.len() || !starts[target as usize] {
+                    return fail(&format!("Illegal target of jump or branch {}", target))
+                }
+            }
+            for entry in &method.exception_table {
+                let (start, end, handler) = (entry.pc_start as usize, entry.pc_end as usize, entry.handler_pc as usize);
+                if start >= end || end > method.code.len() || !starts[start] || !starts[end] || handler >= method.code.len() || !starts[handler] {
+                    return fail("Illegal exception table range")
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Creates the static fields with their default values (JVMS 5.4.2).
+    fn prepare(&mut self, id: ClassId) -> Result<(), ClassLoadError> {
+        let class = self.class(id).class.clone();
+        let mut statics: Vec<VMValue> = Vec::with_capacity(class.fields.len());
+        for field in &class.fields {
+            statics.push(VMValue::default_for(&FieldType::parse(&field.descriptor)?));
+        }
+        self.class_mut(id).statics = statics;
+        Ok(())
+    }
+
+    /// Looks up a field in a class, its superinterfaces and then its superclasses (JVMS 5.4.3.2).
+    /// Returns the declaring class and the index of the field in it.
+    pub fn find_field(&self, id: ClassId, name: &str, descriptor: &str) -> Option<(ClassId, usize)> {
+        let class = self.class(id);
+        if let Some(index) = class.class.fields.iter().position(|f: &Field| f.field_name == name && f.descriptor == descriptor) {
+            return Some((id, index))
+        }
+        for interface in &class.interfaces {
+            if let Some(found) = self.find_field(*interface, name, descriptor) {
+                return Some(found)
+            }
+        }
+        class.super_class.and_then(|super_class| self.find_field(super_class, name, descriptor))
+    }
+
+    /// Looks up a method in a class and its superclasses, then in its superinterfaces (JVMS 5.4.3.3).
+    /// Returns the declaring class and the index of the method in it.
+    pub fn find_method(&self, id: ClassId, name: &str, descriptor: &str) -> Option<(ClassId, usize)> {
+        let mut current = Some(id);
+        while let Some(class_id) = current {
+            let class = self.class(class_id);
+            if let Some(index) = class.class.methods.iter().position(|m: &Method| m.method_name == name && m.descriptor == descriptor) {
+                return Some((class_id, index))
+            }
+            current = class.super_class;
+        }
+        self.find_interface_method(id, name, descriptor)
+    }
+
+    fn find_interface_method(&self, id: ClassId, name: &str, descriptor: &str) -> Option<(ClassId, usize)> {
+        let mut current = Some(id);
+        while let Some(class_id) = current {
+            let class = self.class(class_id);
+            for interface in &class.interfaces {
+                let interface_class = self.class(*interface);
+                let found = interface_class.class.methods.iter()
+                    .position(|m: &Method| m.method_name == name && m.descriptor == descriptor && !m.is_static());
+                if let Some(index) = found {
+                    return Some((*interface, index))
+                }
+                if let Some(found) = self.find_interface_method(*interface, name, descriptor) {
+                    return Some(found)
+                }
+            }
+            current = class.super_class;
+        }
+        None
+    }
+
+    /// Selects the method `invokevirtual` and `invokeinterface` run for a receiver of class `id`
+    /// (JVMS 5.4.6): the closest overriding instance method of the class chain, otherwise the
+    /// most specific non-abstract superinterface method, otherwise any matching abstract one.
+    pub fn select_method(&self, id: ClassId, name: &str, descriptor: &str) -> Option<(ClassId, usize)> {
+        let mut current = Some(id);
+        while let Some(class_id) = current {
+            let class = self.class(class_id);
+            let found = class.class.methods.iter()
+                .position(|m: &Method| m.method_name == name && m.descriptor == descriptor
+                    && !m.is_static() && !m.access_flags.contains(&AccessFlags::Private));
+            if let Some(index) = found {
+                return Some((class_id, index))
+            }
+            current = class.super_class;
+        }
+        let mut candidates: Vec<(ClassId, usize)> = Vec::new();
+        self.collect_interface_methods(id, name, descriptor, &mut candidates);
+        let concrete: Vec<(ClassId, usize)> = candidates.iter().copied()
+            .filter(|(class, index)| !self.class(*class).class.methods[*index].access_flags.contains(&AccessFlags::Abstract))
+            .collect();
+        let most_specific = concrete.iter().copied()
+            .find(|(class, _)| concrete.iter().all(|(other, _)| other == class || !self.is_subclass_of(*other, *class)));
+        most_specific.or_else(|| candidates.first().copied())
+    }
+
+    fn collect_interface_methods(&self, id: ClassId, name: &str, descriptor: &str, found: &mut Vec<(ClassId, usize)>) {
+        let class = self.class(id);
+        for interface in &class.interfaces {
+            let index = self.class(*interface).class.methods.iter()
+                .position(|m: &Method| m.method_name == name && m.descriptor == descriptor
+                    && !m.is_static() && !m.access_flags.contains(&AccessFlags::Private));
+            match index {
+                Some(index) if !found.contains(&(*interface, index)) => found.push((*interface, index)),
+                _ => {}
+            }
+            self.collect_interface_methods(*interface, name, descriptor, found);
+        }
+        if let Some(super_class) = class.super_class {
+            self.collect_interface_methods(super_class, name, descriptor, found);
+        }
+    }
+
+    /// Whether a reference to an instance of `from` may be stored where `to` is expected, following
+    /// the rules of `checkcast` (JVMS 6.5): arrays are covariant in their reference element type.
+    pub fn is_assignable(&self, from: ClassId, to: ClassId) -> bool {
+        if from == to {
+            return true
+        }
+        let (from_class, to_class) = (self.class(from), self.class(to));
+        match (from_class.is_array(), to_class.is_array()) {
+            (true, true) => match (from_class.component_class, to_class.component_class) {
+                (Some(from), Some(to)) => self.is_assignable(from, to),
+                _ => false
+            },
+            (false, true) => false,
+            _ => self.is_subclass_of(from, to)
+        }
+    }
+
+    /// Where a class lives, as HotSpot describes it in `ClassCastException` messages: classes of the
+    /// bootstrap loader are in `java.base`, all others in the unnamed module of their loader.
+    pub fn module_description(&self, id: ClassId) -> String {
+        let loader = self.class(id).defining_loader;
+        let loader_name = self.loader_info(loader).map(|info| info.name.as_str()).unwrap_or("'unknown'");
+        if loader == LoaderId::BOOTSTRAP {
+            format!("module java.base of loader {}", loader_name)
+        } else {
+            format!("unnamed module of loader {}", loader_name)
+        }
+    }
+
+    /// The message of the `ClassCastException` for casting an instance of `from` to `to`, e.g.
+    /// `class Foo cannot be cast to class Bar (Foo and Bar are in unnamed module of loader 'app')`.
+    pub fn class_cast_message(&self, from: ClassId, to: ClassId) -> String {
+        let (from_name, to_name) = (self.class(from).java_name(), self.class(to).java_name());
+        let (from_module, to_module) = (self.module_description(from), self.module_description(to));
+        if from_module == to_module {
+            format!("class {} cannot be cast to class {} ({} and {} are in {})", from_name, to_name, from_name, to_name, from_module)
+        } else {
+            format!("class {} cannot be cast to class {} ({} is in {}; {} is in {})", from_name, to_name, from_name, from_module, to_name, to_module)
+        }
+    }
+
+    /// Whether `id` is `ancestor` or one of its subclasses or implementations.
+    pub fn is_subclass_of(&self, id: ClassId, ancestor: ClassId) -> bool {
+        if id == ancestor {
+            return true
+        }
+        let class = self.class(id);
+        class.super_class.map(|s| self.is_subclass_of(s, ancestor)).unwrap_or(false)
+            || class.interfaces.iter().any(|i| self.is_subclass_of(*i, ancestor))
+    }
+}
@@ -0,0 +1,102 @@
+//! The bundled runtime: class files compiled from `rt/src` by `rt/build.sh`, served to the
+//! bootstrap class loader without touching the file system.
+
+macro_rules! runtime_classes {
+    ($($name:literal),* $(,)?) => {
+        &[$(($name, include_bytes!(concat!(env!("CARGO_MANIFEST_DIR"), "/rt/classes/", $name, ".class")))),*]
+    };
+}
+
+/// Binary names and class file bytes of every bundled class.
+pub const CLASSES: &[(&str, &[u8])] = runtime_classes!(
+    "java/io/FileDescriptor",
+    "java/io/FileOutputStream",
+    "java/io/IOException",
+    "java/io/OutputStream",
+    "java/io/PrintStream",
+    "java/io/Serializable",
+    "java/lang/AbstractMethodError",
+    "java/lang/ArithmeticException",
+    "java/lang/ArrayIndexOutOfBoundsException",
+    "java/lang/ArrayStoreException",
+    "java/lang/Boolean",
+    "java/lang/BootstrapMethodError",
+    "java/lang/Byte",
+    "java/lang/CharSequence",
+    "java/lang/Character",
+    "java/lang/Class",
+    "java/lang/ClassCastException",
+    "java/lang/ClassCircularityError",
+    "java/lang/ClassFormatError",
+    "java/lang/ClassLoader",
+    "java/lang/ClassNotFoundException",
+    "java/lang/CloneNotSupportedException",
+    "java/lang/Cloneable",
+    "java/lang/Comparable",
+    "java/lang/Double",
+    "java/lang/Error",
+    "java/lang/Exception",
+    "java/lang/ExceptionInInitializerError",
+    "java/lang/Float",
+    "java/lang/IllegalAccessError",
+    "java/lang/IllegalArgumentException",
+    "java/lang/IllegalMonitorStateException",
+    "java/lang/IllegalStateException",
+    "java/lang/IncompatibleClassChangeError",
+    "java/lang/IndexOutOfBoundsException",
+    "java/lang/InstantiationError",
+    "java/lang/Integer",
+    "java/lang/InternalError",
+    "java/lang/InterruptedException",
+    "java/lang/LinkageError",
+    "java/lang/Long",
+    "java/lang/Math",
+    "java/lang/NegativeArraySizeException",
+    "java/lang/NoClassDefFoundError",
+    "java/lang/NoSuchFieldError",
+    "java/lang/NoSuchMethodError",
+    "java/lang/NullPointerException",
+    "java/lang/Number",
+    "java/lang/NumberFormatException",
+    "java/lang/Object",
+    "java/lang/OutOfMemoryError",
+    "java/lang/ReflectiveOperationException",
+    "java/lang/Runnable",
+    "java/lang/RuntimeException",
+    "java/lang/SecurityException",
+    "java/lang/Short",
+    "java/lang/StackOverflowError",
+    "java/lang/StackTraceElement",
+    "java/lang/String",
+    "java/lang/StringBuilder",
+    "java/lang/StringIndexOutOfBoundsException",
+    "java/lang/System",
+    "java/lang/Throwable",
+    "java/lang/UnsatisfiedLinkError",
+    "java/lang/UnsupportedClassVersionError",
+    "java/lang/UnsupportedOperationException",
+    "java/lang/VerifyError",
+    "java/lang/VirtualMachineError",
+    "java/lang/invoke/MethodHandle",
+    "java/lang/invoke/MethodHandles",
+    "java/lang/invoke/MethodHandles$Lookup",
+    "java/lang/invoke/MethodType",
+    "java/lang/ref/FinalReference",
+    "java/lang/ref/Finalizer",
+    "java/lang/ref/PhantomReference",
+    "java/lang/ref/Reference",
+    "java/lang/ref/ReferenceQueue",
+    "java/lang/ref/ReferenceQueue$Null",
+    "java/lang/ref/SoftReference",
+    "java/lang/ref/WeakReference",
+    "java/util/Formatter",
+    "java/util/IllegalFormatConversionException",
+    "java/util/IllegalFormatException",
+    "java/util/MissingFormatArgumentException",
+    "java/util/Objects",
+    "java/util/UnknownFormatConversionException",
+);
+
+pub fn find_class(binary_name: &str) -> Option<&'static [u8]> {
+    CLASSES.iter().find(|(name, _)| *name == binary_name).map(|(_, data)| *data)
+}
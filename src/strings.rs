@@ -0,0 +1,210 @@
+//! The encodings of Java strings: Modified UTF-8 (JVMS 4.4.7), which class files and JNI use, and
+//! the compact layout of `java.lang.String` since JDK 9, where `value` holds one byte per character
+//! when all characters are Latin-1 and two bytes per UTF-16 code unit otherwise.
+//!
+//! Java strings are sequences of UTF-16 code units that need not be well formed, so conversions to
+//! Rust strings replace unpaired surrogates with U+FFFD.
+//!
+//! Floating point numbers are converted to and from text the way `Double.toString` and
+//! `Double.parseDouble` do it.
+
+use std::str::FromStr;
+
+/// `String.coder` of strings stored one byte per character.
+pub const LATIN1: u8 = 0;
+/// `String.coder` of strings stored as UTF-16 code units, little-endian like HotSpot on x86.
+pub const UTF16: u8 = 1;
+
+/// Decodes Modified UTF-8 into UTF-16 code units, or `None` if the bytes are malformed: a zero
+/// byte, a byte that starts a four byte form, or a missing continuation byte.
+pub fn decode_modified_utf8(bytes: &[u8]) -> Option<Vec<u16>> {
+    let mut units = Vec::with_capacity(bytes.len());
+    let mut index = 0;
+    let continuation = |index: usize| bytes.get(index).filter(|byte| *byte & 0xc0 == 0x80).map(|byte| (*byte & 0x3f) as u16);
+    while index < bytes.len() {
+        let byte = bytes[index];
+        match byte {
+            0x01..=0x7f => {
+                units.push(byte as u16);
+                index += 1;
+            }
+            0xc0..=0xdf => {
+                units.push(((byte & 0x1f) as u16) << 6 | continuation(index + 1)?);
+                index += 2;
+            }
+            0xe0..=0xef => {
+                units.push(((byte & 0x0f) as u16) << 12 | continuation(index + 1)? << 6 | continuation(index + 2)?);
+                index += 3;
+            }
+            _ => return None
+        }
+    }
+    Some(units)
+}
+
+/// Encodes UTF-16 code units as Modified UTF-8: `U+0000` takes two bytes and supplementary
+/// characters are encoded as their two surrogates.
+pub fn encode_modified_utf8(units: &[u16]) -> Vec<u8> {
+    let mut bytes = Vec::with_capacity(units.len());
+    for &unit in units {
+        match unit {
+            0x0001..=0x007f => bytes.push(unit as u8),
+            0x0000 | 0x0080..=0x07ff => {
+                bytes.push(0xc0 | (unit >> 6) as u8);
+                bytes.push(0x80 | (unit & 0x3f) as u8);
+            }
+            _ => {
+                bytes.push(0xe0 | (unit >> 12) as u8);
+                bytes.push(0x80 | ((unit >> 6) & 0x3f) as u8);
+                bytes.push(0x80 | (unit & 0x3f) as u8);
+            }
+        }
+    }
+    bytes
+}
+
+/// Decodes Modified UTF-8 into a Rust string, see [`decode_modified_utf8`].
+pub fn modified_utf8_to_string(bytes: &[u8]) -> Option<String> {
+    decode_modified_utf8(bytes).map(|units| String::from_utf16_lossy(&units))
+}
+
+pub fn string_to_modified_utf8(value: &str) -> Vec<u8> {
+    encode_modified_utf8(&value.encode_utf16().collect::<Vec<u16>>())
+}
+
+/// The `value` and `coder` of a string of the code units: Latin-1 if every unit fits a byte.
+pub fn compress(units: &[u16]) -> (Vec<u8>, u8) {
+    if units.iter().all(|unit| *unit <= 0xff) {
+        (units.iter().map(|unit| *unit as u8).collect(), LATIN1)
+    } else {
+        (units.iter().flat_map(|unit| unit.to_le_bytes()).collect(), UTF16)
+    }
+}
+
+/// The code units of a string's `value` and `coder`.
+pub fn inflate(value: &[u8], coder: u8) -> Vec<u16> {
+    match coder {
+        LATIN1 => value.iter().map(|byte| *byte as u16).collect(),
+        _ => value.chunks_exact(2).map(|pair| u16::from_le_bytes([pair[0], pair[1]])).collect()
+    }
+}
+
+/// `Double.toString` as specified since JDK 19: the shortest decimal that rounds back to `value`,
+/// or the closest one of two digits when a single digit would do (`4.9E-324`, not `5.0E-324`).
+pub fn double_to_string(value: f64) -> String {
+    let mut scientific = format!("{:e}", value.abs());
+    if !scientific.contains('.') {
+        scientific = format!("{:.1e}", value.abs());
+    }
+    floating_to_string(value.is_nan(), value.is_infinite(), value.is_sign_negative(), &scientific)
+}
+
+/// `Float.toString`, with decimals that round back to the same `float`.
+pub fn float_to_string(value: f32) -> String {
+    let mut scientific = format!("{:e}", value.abs());
+    if !scientific.contains('.') {
+        scientific = format!("{:.1e}", value.abs());
+    }
+    floating_to_string(value.is_nan(), value.is_infinite(), value.is_sign_negative(), &scientific)
+}
+
+/// Lays out the digits of Rust's scientific form (`1.25e-5`) like Java: plain decimals from
+/// 10^-3 up to 10^7, `1.25E-5` outside, always with a digit after the point.
+fn floating_to_string(nan: bool, infinite: bool, negative: bool, scientific: &str) -> String {
+    if nan {
+        return "NaN".to_string()
+    }
+    let sign = if negative { "-" } else { "" };
+    if infinite {
+        return format!("{}Infinity", sign)
+    }
+    let (mantissa, exponent) = scientific.split_once('e').expect("scientific notation");
+    let exponent: i32 = exponent.parse().expect("exponent");
+    let digits = mantissa.replace('.', "");
+    let digits = match digits.trim_end_matches('0') {
+        "" => "0",
+        significant => significant
+    };
+    if !(-3..7).contains(&exponent) {
+        let fraction = if digits.len() > 1 { &digits[1..] } else { "0" };
+        return format!("{}{}.{}E{}", sign, &digits[..1], fraction, exponent)
+    }
+    if exponent < 0 {
+        return format!("{}0.{}{}", sign, "0".repeat((-exponent - 1) as usize), digits)
+    }
+    let point = exponent as usize + 1;
+    if digits.len() <= point {
+        format!("{}{}{}.0", sign, digits, "0".repeat(point - digits.len()))
+    } else {
+        format!("{}{}.{}", sign, &digits[..point], &digits[point..])
+    }
+}
+
+/// `Formatter`'s `%f`, or `%e` when `scientific`, of a finite and non-negative value in Rust's
+/// shortest scientific form (`format!("{:e}", value)`), with `precision` digits after the point.
+/// Like Java, the shortest decimal is rounded half up, so `2.675` gives `2.68` even though the
+/// closest `double` is slightly below it.
+pub fn format_floating(shortest: &str, scientific: bool, precision: usize) -> String {
+    let (mantissa, exponent) = shortest.split_once('e').expect("scientific notation");
+    // A leading zero takes the carry when rounding up adds a digit, as in 9.99 to 10.0.
+    let mut digits: Vec<u8> = std::iter::once(0).chain(mantissa.bytes().filter(u8::is_ascii_digit).map(|digit| digit - b'0')).collect();
+    let exponent = exponent.parse::<i32>().expect("exponent") + 1;
+    let kept = if scientific { 2 + precision as i32 } else { exponent + 1 + precision as i32 };
+    if kept <= 0 {
+        digits.clear();
+    } else if (kept as usize) < digits.len() {
+        let round_up = digits[kept as usize] >= 5;
+        digits.truncate(kept as usize);
+        if round_up {
+            let mut position = digits.len() - 1;
+            while digits[position] == 9 {
+                digits[position] = 0;
+                position -= 1;
+            }
+            digits[position] += 1;
+        }
+    }
+    digits.resize(kept.max(0) as usize, 0);
+    let text = |digits: &[u8]| digits.iter().map(|digit| (b'0' + digit) as char).collect::<String>();
+    let fraction = |digits: &[u8]| if precision == 0 { String::new() } else { format!(".{}", text(digits)) };
+    if scientific {
+        let (digits, exponent) = if digits[0] == 0 { (&digits[1..], exponent - 1) } else { (&digits[..digits.len() - 1], exponent) };
+        // Zero has an exponent of 0 rather than the one of its shortest form.
+        let exponent = if digits.iter().all(|digit| *digit == 0) { 0 } else { exponent };
+        let sign = if exponent < 0 { '-' } else { '+' };
+        return format!("{}{}e{}{:02}", digits[0], fraction(&digits[1..]), sign, exponent.abs())
+    }
+    let point = (exponent + 1).max(0) as usize;
+    let mut padded: Vec<u8> = match exponent + 1 {
+        shift if shift < 0 => std::iter::repeat_n(0, -shift as usize).chain(digits).collect(),
+        _ => digits
+    };
+    padded.resize(point + precision, 0);
+    let integer = text(&padded[..point]);
+    let integer = match integer.trim_start_matches('0') {
+        "" => "0",
+        integer => integer
+    };
+    format!("{}{}", integer, fraction(&padded[point..]))
+}
+
+/// `Double.parseDouble` and `Float.parseFloat`: a signed decimal with an optional exponent and
+/// `d` or `f` suffix, `NaN` or `Infinity`, surrounded by any control characters or spaces. The
+/// value is rounded once, to `T`. Hexadecimal floating point literals are not supported.
+pub fn parse_floating<T: FromStr>(text: &str) -> Option<T> {
+    let trimmed = text.trim_matches(|c: char| c <= ' ');
+    let unsigned = trimmed.strip_prefix(['+', '-']).unwrap_or(trimmed);
+    let sign = &trimmed[..trimmed.len() - unsigned.len()];
+    let number = match unsigned {
+        "NaN" => "NaN",
+        "Infinity" => "inf",
+        _ => {
+            let number = unsigned.strip_suffix(['d', 'D', 'f', 'F']).unwrap_or(unsigned);
+            if !number.bytes().all(|b| b.is_ascii_digit() || matches!(b, b'.' | b'e' | b'E' | b'+' | b'-')) {
+                return None
+            }
+            number
+        }
+    };
+    format!("{}{}", sign, number).parse().ok()
+}
@@ -1,12 +1,37 @@
+use std::collections::HashMap;
 use std::fmt::{Display, Formatter};
+use std::path::PathBuf;
+use std::time::Instant;
+use crate::classpath::ClassPath;
+use crate::descriptor::FieldType;
+use crate::gc::{reference_slots, CollectionKind, CollectionRequest, CollectorKind, GarbageCollector, GcStats, Generational, MarkSweep};
+use crate::interpret::InterpreterError;
+use crate::loader::Field;
+use crate::profile::{AllocationSite, AllocationStats};
+use crate::registry::{ClassId, ClassRegistry, LoaderId};
+use crate::strings;
 
-#[derive(Copy, Clone)]
+/// Address of an object in the [`Heap`]. Address 0 is never allocated and stands for `null`
+/// wherever references are stored in raw memory.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct ObjRef(pub u32);
+
+/// A Java value. Values on the operand stack and in local variables always have a computational
+/// type (`int`, `long`, `float`, `double`, reference or return address); `byte`, `short`, `char`
+/// and `boolean` only exist in storage and at the embedding boundary and widen to `Int`.
+#[derive(Debug, Copy, Clone, PartialEq)]
 pub enum VMValue {
     Int(i32),
     Long(i64),
-    Byte(u8),
+    Byte(i8),
+    Short(i16),
+    Char(u16),
+    Boolean(bool),
     Float(f32),
     Double(f64),
+    Reference(ObjRef),
+    /// The pc following a `jsr`, as stored by the subroutine and consumed by `ret`.
+    ReturnAddress(u32),
     Null
 }
 
@@ -16,35 +41,802 @@ impl Display for VMValue {
             VMValue::Int(v) => f.write_str(format!("Int({})", v).as_str()),
             VMValue::Long(v) => f.write_str(format!("Long({})", v).as_str()),
             VMValue::Byte(v) => f.write_str(format!("Byte({})", v).as_str()),
+            VMValue::Short(v) => f.write_str(format!("Short({})", v).as_str()),
+            VMValue::Char(v) => f.write_str(format!("Char({:?})", char::from_u32(*v as u32).unwrap_or(char::REPLACEMENT_CHARACTER)).as_str()),
+            VMValue::Boolean(v) => f.write_str(format!("Boolean({})", v).as_str()),
             VMValue::Float(v) => f.write_str(format!("Float({})", v).as_str()),
             VMValue::Double(v) => f.write_str(format!("Double({})", v).as_str()),
+            VMValue::Reference(v) => f.write_str(format!("Reference(@{:x})", v.0).as_str()),
+            VMValue::ReturnAddress(v) => f.write_str(format!("ReturnAddress({})", v).as_str()),
             VMValue::Null => f.write_str("Null")
         }
     }
 }
 
 impl VMValue {
-    pub fn int(self) -> i32 {
+    /// The initial value of a field or array element of the given type.
+    pub fn default_for(field_type: &FieldType) -> VMValue {
+        match field_type {
+            FieldType::Long => VMValue::Long(0),
+            FieldType::Float => VMValue::Float(0.0),
+            FieldType::Double => VMValue::Double(0.0),
+            FieldType::Object(_) | FieldType::Array(_) => VMValue::Null,
+            _ => VMValue::Int(0)
+        }
+    }
+
+    /// Whether the value takes two local variable slots.
+    pub fn is_category2(&self) -> bool {
+        matches!(self, VMValue::Long(_) | VMValue::Double(_))
+    }
+
+    /// The name of the value's type, as used in `TypeMismatch` errors.
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            VMValue::Int(_) => "int",
+            VMValue::Long(_) => "long",
+            VMValue::Byte(_) => "byte",
+            VMValue::Short(_) => "short",
+            VMValue::Char(_) => "char",
+            VMValue::Boolean(_) => "boolean",
+            VMValue::Float(_) => "float",
+            VMValue::Double(_) => "double",
+            VMValue::Reference(_) | VMValue::Null => "reference",
+            VMValue::ReturnAddress(_) => "returnAddress"
+        }
+    }
+
+    /// Widens `byte`, `short`, `char` and `boolean` to `Int` (JVMS 2.11.1); other values are unchanged.
+    pub fn computational(self) -> VMValue {
+        match self {
+            VMValue::Byte(v) => VMValue::Int(v as i32),
+            VMValue::Short(v) => VMValue::Int(v as i32),
+            VMValue::Char(v) => VMValue::Int(v as i32),
+            VMValue::Boolean(v) => VMValue::Int(v as i32),
+            other => other
+        }
+    }
+
+    /// Converts an `int` to the storage type of a field or parameter, narrowing as `putfield` does.
+    pub fn narrow_to(self, field_type: &FieldType) -> VMValue {
+        match (field_type, self.computational()) {
+            (FieldType::Byte, VMValue::Int(v)) => VMValue::Byte(v as i8),
+            (FieldType::Short, VMValue::Int(v)) => VMValue::Short(v as i16),
+            (FieldType::Char, VMValue::Int(v)) => VMValue::Char(v as u16),
+            (FieldType::Boolean, VMValue::Int(v)) => VMValue::Boolean(v & 1 != 0),
+            _ => self
+        }
+    }
+
+    fn mismatch(&self, expected: &str) -> InterpreterError {
+        InterpreterError::TypeMismatch(expected.to_string(), self.to_string())
+    }
+
+    /// The value as an `int`, widening the sub-int types.
+    pub fn as_int(self) -> Result<i32, InterpreterError> {
+        match self.computational() {
+            VMValue::Int(v) => Ok(v),
+            _ => Err(self.mismatch("int"))
+        }
+    }
+
+    pub fn as_long(self) -> Result<i64, InterpreterError> {
+        match self {
+            VMValue::Long(v) => Ok(v),
+            _ => Err(self.mismatch("long"))
+        }
+    }
+
+    pub fn as_float(self) -> Result<f32, InterpreterError> {
+        match self {
+            VMValue::Float(v) => Ok(v),
+            _ => Err(self.mismatch("float"))
+        }
+    }
+
+    pub fn as_double(self) -> Result<f64, InterpreterError> {
+        match self {
+            VMValue::Double(v) => Ok(v),
+            _ => Err(self.mismatch("double"))
+        }
+    }
+
+    /// `int` values other than 0 are `true`, like the JVM treats `boolean` results.
+    pub fn as_boolean(self) -> Result<bool, InterpreterError> {
+        self.as_int().map(|v| v != 0).map_err(|_| self.mismatch("boolean"))
+    }
+
+    /// `None` for `null`.
+    pub fn as_reference(self) -> Result<Option<ObjRef>, InterpreterError> {
+        match self {
+            VMValue::Reference(v) => Ok(Some(v)),
+            VMValue::Null => Ok(None),
+            _ => Err(self.mismatch("reference"))
+        }
+    }
+
+    pub fn as_return_address(self) -> Result<u32, InterpreterError> {
         match self {
-            Self::Int(v) => v,
-            _ => panic!("Expected int, got {}", self)
+            VMValue::ReturnAddress(v) => Ok(v),
+            _ => Err(self.mismatch("returnAddress"))
+        }
+    }
+}
+
+macro_rules! value_conversions {
+    ($($rust:ty => $variant:ident, $accessor:ident);* $(;)?) => {
+        $(
+            impl From<$rust> for VMValue {
+                fn from(value: $rust) -> Self {
+                    VMValue::$variant(value)
+                }
+            }
+
+            impl TryFrom<VMValue> for $rust {
+                type Error = InterpreterError;
+
+                fn try_from(value: VMValue) -> Result<Self, Self::Error> {
+                    value.$accessor()
+                }
+            }
+        )*
+    };
+}
+
+value_conversions!(
+    i32 => Int, as_int;
+    i64 => Long, as_long;
+    f32 => Float, as_float;
+    f64 => Double, as_double;
+    bool => Boolean, as_boolean;
+);
+
+impl From<i8> for VMValue {
+    fn from(value: i8) -> Self {
+        VMValue::Byte(value)
+    }
+}
+
+impl From<i16> for VMValue {
+    fn from(value: i16) -> Self {
+        VMValue::Short(value)
+    }
+}
+
+/// Java `char` is a UTF-16 code unit, so `u16` maps to `Char`.
+impl From<u16> for VMValue {
+    fn from(value: u16) -> Self {
+        VMValue::Char(value)
+    }
+}
+
+impl From<ObjRef> for VMValue {
+    fn from(value: ObjRef) -> Self {
+        VMValue::Reference(value)
+    }
+}
+
+impl From<Option<ObjRef>> for VMValue {
+    fn from(value: Option<ObjRef>) -> Self {
+        value.map(VMValue::Reference).unwrap_or(VMValue::Null)
+    }
+}
+
+impl TryFrom<VMValue> for Option<ObjRef> {
+    type Error = InterpreterError;
+
+    fn try_from(value: VMValue) -> Result<Self, Self::Error> {
+        value.as_reference()
+    }
+}
+
+impl TryFrom<VMValue> for i8 {
+    type Error = InterpreterError;
+
+    fn try_from(value: VMValue) -> Result<Self, Self::Error> {
+        value.as_int().map(|v| v as i8)
+    }
+}
+
+impl TryFrom<VMValue> for i16 {
+    type Error = InterpreterError;
+
+    fn try_from(value: VMValue) -> Result<Self, Self::Error> {
+        value.as_int().map(|v| v as i16)
+    }
+}
+
+impl TryFrom<VMValue> for u16 {
+    type Error = InterpreterError;
+
+    fn try_from(value: VMValue) -> Result<Self, Self::Error> {
+        value.as_int().map(|v| v as u16)
+    }
+}
+
+/// Where the instance fields of a class live inside its objects. Fields of the superclass come
+/// first at the same offsets, so an offset is valid for every subclass.
+#[derive(Debug, Clone, Default)]
+pub struct ObjectLayout {
+    /// Bytes taken by the fields, not counting the header.
+    pub size: u32,
+    /// Offsets of the fields declared by the class itself, indexed like `class.fields`;
+    /// `None` for static fields.
+    pub field_offsets: Vec<Option<u32>>,
+    pub field_types: Vec<Option<FieldType>>,
+    /// Offsets of every reference field, inherited ones included.
+    pub reference_offsets: Vec<u32>,
+    /// Offset of `java.lang.ref.Reference.referent` in reference objects, which is among the
+    /// reference offsets too.
+    pub referent_offset: Option<u32>
+}
+
+impl ObjectLayout {
+    /// Lays out the instance fields declared by a class after those of its superclass, largest
+    /// first so every field is naturally aligned.
+    pub fn compute(super_layout: Option<&ObjectLayout>, fields: &[Field]) -> Result<ObjectLayout, crate::loader::ClassLoadError> {
+        let mut layout = ObjectLayout {
+            size: super_layout.map(|l| l.size).unwrap_or(0),
+            field_offsets: vec![None; fields.len()],
+            field_types: vec![None; fields.len()],
+            reference_offsets: super_layout.map(|l| l.reference_offsets.clone()).unwrap_or_default(),
+            referent_offset: super_layout.and_then(|l| l.referent_offset)
+        };
+        let mut instance_fields: Vec<(usize, FieldType)> = Vec::new();
+        for (index, field) in fields.iter().enumerate() {
+            if !field.is_static() {
+                instance_fields.push((index, FieldType::parse(&field.descriptor)?));
+            }
         }
+        instance_fields.sort_by_key(|(index, field_type)| (std::cmp::Reverse(Heap::field_size(field_type)), *index));
+        for (index, field_type) in instance_fields {
+            let size = Heap::field_size(&field_type);
+            let offset = layout.size.div_ceil(size) * size;
+            if field_type.is_reference() {
+                layout.reference_offsets.push(offset);
+            }
+            layout.field_offsets[index] = Some(offset);
+            layout.field_types[index] = Some(field_type);
+            layout.size = offset + size;
+        }
+        Ok(layout)
     }
 }
 
+/// Objects live in one growable byte arena. Every object starts with a header:
+///
+/// | offset | contents                                   |
+/// |--------|--------------------------------------------|
+/// | 0      | class id                                   |
+/// | 4      | size of the object in bytes, header included |
+/// | 8      | identity hash, 0 until first requested     |
+/// | 12     | monitor entry count                        |
+/// | 16     | word reserved for the garbage collector    |
+/// | 20     | array length, 0 for plain objects          |
+///
+/// followed by the fields at the offsets given by the class's [`ObjectLayout`]. Which chunks of
+/// the arena hold objects is up to the [`GarbageCollector`]; space without an object is formatted
+/// as free chunks, which keep the class id [`Heap::FREE`] and the size so that the arena can
+/// always be walked object by object.
+pub struct Heap {
+    memory: Vec<u8>,
+    hash_state: u32,
+    /// Bytes the arena may grow to.
+    max_size: usize
+}
+
+impl Heap {
+    pub const HEADER_SIZE: u32 = 24;
+    pub const ALIGNMENT: u32 = 8;
+    /// Class id of a free chunk.
+    pub const FREE: u32 = u32::MAX;
+
+    pub fn new(initial_capacity: usize, max_size: usize) -> Self {
+        let mut memory = Vec::with_capacity(initial_capacity.min(max_size).max(Heap::ALIGNMENT as usize));
+        // Keeps address 0 free for null.
+        memory.resize(Heap::ALIGNMENT as usize, 0);
+        Heap { memory, hash_state: 0x9e3779b9, max_size }
+    }
+
+    /// Bytes a field of the given type takes inside an object.
+    pub fn field_size(field_type: &FieldType) -> u32 {
+        match field_type {
+            FieldType::Long | FieldType::Double => 8,
+            FieldType::Int | FieldType::Float | FieldType::Object(_) | FieldType::Array(_) => 4,
+            FieldType::Char | FieldType::Short => 2,
+            FieldType::Byte | FieldType::Boolean => 1
+        }
+    }
+
+    /// Bytes an object with `body_size` bytes of fields takes, header and padding included.
+    pub fn object_size(body_size: u32) -> u32 {
+        (Heap::HEADER_SIZE + body_size).div_ceil(Heap::ALIGNMENT) * Heap::ALIGNMENT
+    }
+
+    /// Bytes the arena spans, free chunks and the reserved null address included.
+    pub fn size(&self) -> usize {
+        self.memory.len()
+    }
+
+    pub fn max_size(&self) -> usize {
+        self.max_size
+    }
+
+    /// Bytes taken by objects, including the reserved null address.
+    pub fn used(&self) -> usize {
+        Heap::ALIGNMENT as usize + self.objects().map(|obj| self.size_of(obj) as usize).sum::<usize>()
+    }
+
+    /// Grows the arena with zeroed bytes or cuts it short.
+    pub(crate) fn resize(&mut self, size: usize) {
+        self.memory.resize(size, 0);
+    }
+
+    /// Marks `size` bytes at `address` as a free chunk; an empty chunk leaves the arena untouched.
+    pub(crate) fn format_free(&mut self, address: u32, size: u32) {
+        if size > 0 {
+            self.write_u32(ObjRef(address), 0, Heap::FREE);
+            self.write_u32(ObjRef(address), 4, size);
+        }
+    }
+
+    /// Creates a zeroed object of `class` in the `size` bytes at `address`.
+    pub(crate) fn init_object(&mut self, address: u32, class: ClassId, size: u32, array_length: u32) -> ObjRef {
+        self.memory[address as usize..(address + size) as usize].fill(0);
+        let obj = ObjRef(address);
+        self.write_u32(obj, 0, class.0);
+        self.write_u32(obj, 4, size);
+        self.write_u32(obj, 20, array_length);
+        obj
+    }
+
+    /// Copies an object, header included, to `address`. The ranges may overlap.
+    pub(crate) fn copy_object(&mut self, obj: ObjRef, address: u32) -> ObjRef {
+        let size = self.size_of(obj) as usize;
+        self.memory.copy_within(obj.0 as usize..obj.0 as usize + size, address as usize);
+        ObjRef(address)
+    }
+
+    /// Copies the fields or elements of `obj` to `copy`, an object of the same class and size.
+    pub(crate) fn copy_fields(&mut self, obj: ObjRef, copy: ObjRef) {
+        let size = self.size_of(obj);
+        self.memory.copy_within((obj.0 + Heap::HEADER_SIZE) as usize..(obj.0 + size) as usize, (copy.0 + Heap::HEADER_SIZE) as usize);
+    }
+
+    /// Every object in the heap in address order.
+    pub fn objects(&self) -> impl Iterator<Item = ObjRef> + '_ {
+        self.objects_in(Heap::ALIGNMENT, self.memory.len() as u32)
+    }
+
+    /// The objects between two addresses, which must both be chunk boundaries.
+    pub(crate) fn objects_in(&self, start: u32, end: u32) -> impl Iterator<Item = ObjRef> + '_ {
+        let mut address = start;
+        std::iter::from_fn(move || {
+            while address < end {
+                let obj = ObjRef(address);
+                address += self.size_of(obj);
+                if self.read_u32(obj, 0) != Heap::FREE {
+                    return Some(obj)
+                }
+            }
+            None
+        })
+    }
+
+    /// The header word owned by the garbage collector, for marks and forwarding addresses.
+    pub(crate) fn gc_word(&self, obj: ObjRef) -> u32 {
+        self.read_u32(obj, 16)
+    }
+
+    pub(crate) fn set_gc_word(&mut self, obj: ObjRef, word: u32) {
+        self.write_u32(obj, 16, word)
+    }
+
+    /// Reads the reference stored in the four bytes at an absolute address inside an object.
+    pub(crate) fn reference_at(&self, slot: u32) -> Option<ObjRef> {
+        match self.read_u32(ObjRef(slot), 0) {
+            0 => None,
+            address => Some(ObjRef(address))
+        }
+    }
+
+    pub(crate) fn set_reference_at(&mut self, slot: u32, obj: ObjRef) {
+        self.write_u32(ObjRef(slot), 0, obj.0)
+    }
+
+    pub(crate) fn clear_reference_at(&mut self, slot: u32) {
+        self.write_u32(ObjRef(slot), 0, 0)
+    }
+
+    fn read_u32(&self, obj: ObjRef, offset: u32) -> u32 {
+        let start = (obj.0 + offset) as usize;
+        u32::from_le_bytes(self.memory[start..start + 4].try_into().unwrap())
+    }
+
+    fn write_u32(&mut self, obj: ObjRef, offset: u32, value: u32) {
+        let start = (obj.0 + offset) as usize;
+        self.memory[start..start + 4].copy_from_slice(&value.to_le_bytes());
+    }
+
+    pub fn class_of(&self, obj: ObjRef) -> ClassId {
+        ClassId(self.read_u32(obj, 0))
+    }
+
+    pub fn size_of(&self, obj: ObjRef) -> u32 {
+        self.read_u32(obj, 4)
+    }
+
+    pub fn array_length(&self, obj: ObjRef) -> u32 {
+        self.read_u32(obj, 20)
+    }
+
+    /// The identity hash code, generated with a xor-shift sequence on first request like HotSpot does.
+    pub fn identity_hash(&mut self, obj: ObjRef) -> i32 {
+        let hash = self.read_u32(obj, 8);
+        if hash != 0 {
+            return hash as i32
+        }
+        let mut hash = 0;
+        while hash == 0 {
+            let mut x = self.hash_state;
+            x ^= x << 13;
+            x ^= x >> 17;
+            x ^= x << 5;
+            self.hash_state = x;
+            hash = x & 0x7fffffff;
+        }
+        self.write_u32(obj, 8, hash);
+        hash as i32
+    }
+
+    pub fn monitor_count(&self, obj: ObjRef) -> u32 {
+        self.read_u32(obj, 12)
+    }
+
+    pub fn set_monitor_count(&mut self, obj: ObjRef, count: u32) {
+        self.write_u32(obj, 12, count)
+    }
+
+    /// Reads element `index` of an array whose elements have type `element_type`.
+    pub fn read_element(&self, array: ObjRef, index: u32, element_type: &FieldType) -> VMValue {
+        self.read_field(array, index * Heap::field_size(element_type), element_type)
+    }
+
+    /// Stores element `index` of an array, narrowing like [`Heap::write_field`].
+    pub fn write_element(&mut self, array: ObjRef, index: u32, element_type: &FieldType, value: VMValue) -> bool {
+        self.write_field(array, index * Heap::field_size(element_type), element_type, value)
+    }
+
+    /// Reads a field at `offset` from the start of the field area, widened to its computational type.
+    pub fn read_field(&self, obj: ObjRef, offset: u32, field_type: &FieldType) -> VMValue {
+        let start = (obj.0 + Heap::HEADER_SIZE + offset) as usize;
+        let bytes = &self.memory[start..start + Heap::field_size(field_type) as usize];
+        match field_type {
+            FieldType::Boolean => VMValue::Int(bytes[0] as i32),
+            FieldType::Byte => VMValue::Int(bytes[0] as i8 as i32),
+            FieldType::Char => VMValue::Int(u16::from_le_bytes(bytes.try_into().unwrap()) as i32),
+            FieldType::Short => VMValue::Int(i16::from_le_bytes(bytes.try_into().unwrap()) as i32),
+            FieldType::Int => VMValue::Int(i32::from_le_bytes(bytes.try_into().unwrap())),
+            FieldType::Float => VMValue::Float(f32::from_le_bytes(bytes.try_into().unwrap())),
+            FieldType::Long => VMValue::Long(i64::from_le_bytes(bytes.try_into().unwrap())),
+            FieldType::Double => VMValue::Double(f64::from_le_bytes(bytes.try_into().unwrap())),
+            FieldType::Object(_) | FieldType::Array(_) => match u32::from_le_bytes(bytes.try_into().unwrap()) {
+                0 => VMValue::Null,
+                address => VMValue::Reference(ObjRef(address))
+            }
+        }
+    }
+
+    /// Stores a value into a field, narrowing ints to the field's type (`boolean` keeps only bit 0).
+    /// Returns false when the value does not fit the field type.
+    pub fn write_field(&mut self, obj: ObjRef, offset: u32, field_type: &FieldType, value: VMValue) -> bool {
+        let start = (obj.0 + Heap::HEADER_SIZE + offset) as usize;
+        let bytes: Vec<u8> = match (field_type, value.computational()) {
+            (FieldType::Boolean, VMValue::Int(v)) => vec![(v & 1) as u8],
+            (FieldType::Byte, VMValue::Int(v)) => vec![v as u8],
+            (FieldType::Char, VMValue::Int(v)) | (FieldType::Short, VMValue::Int(v)) => (v as u16).to_le_bytes().to_vec(),
+            (FieldType::Int, VMValue::Int(v)) => v.to_le_bytes().to_vec(),
+            (FieldType::Float, VMValue::Float(v)) => v.to_le_bytes().to_vec(),
+            (FieldType::Long, VMValue::Long(v)) => v.to_le_bytes().to_vec(),
+            (FieldType::Double, VMValue::Double(v)) => v.to_le_bytes().to_vec(),
+            (FieldType::Object(_), VMValue::Reference(r)) | (FieldType::Array(_), VMValue::Reference(r)) => r.0.to_le_bytes().to_vec(),
+            (FieldType::Object(_), VMValue::Null) | (FieldType::Array(_), VMValue::Null) => vec![0; 4],
+            _ => return false
+        };
+        self.memory[start..start + bytes.len()].copy_from_slice(&bytes);
+        true
+    }
+}
+
+/// Default limit on the size of the heap.
+pub const DEFAULT_MAX_HEAP: usize = 256 << 20;
+
+/// Settings fixed when a VM is created, the counterparts of `java` launcher options like `-Xmx`.
+#[derive(Debug, Clone)]
+pub struct VMOptions {
+    /// Bytes the heap may grow to before allocation fails with `OutOfMemoryError`.
+    pub max_heap: usize,
+    pub collector: CollectorKind,
+    /// Bytes of the generational collector's nursery, a quarter of the maximum heap by default
+    /// and at most half of it.
+    pub young_size: Option<usize>,
+    /// Prints a line for every collection, like `-verbose:gc`.
+    pub log_gc: bool,
+    /// Writes an HPROF heap dump the first time the heap is exhausted, like
+    /// `-XX:+HeapDumpOnOutOfMemoryError`, to `heap_dump_path` (see [`heap_dump_file`](crate::hprof::heap_dump_file)).
+    pub heap_dump_on_out_of_memory: bool,
+    pub heap_dump_path: Option<PathBuf>,
+    /// Samples one in this many allocations by bytecode, see [`VM::allocation_sites`].
+    pub allocation_sample_interval: Option<u32>,
+    /// The directories `System.loadLibrary` searches, like `-Djava.library.path`.
+    pub library_path: Vec<PathBuf>
+}
+
+impl Default for VMOptions {
+    fn default() -> Self {
+        VMOptions {
+            max_heap: DEFAULT_MAX_HEAP,
+            collector: CollectorKind::MarkSweep,
+            young_size: None,
+            log_gc: false,
+            heap_dump_on_out_of_memory: false,
+            heap_dump_path: None,
+            allocation_sample_interval: None,
+            library_path: Vec::new()
+        }
+    }
+}
+
+/// An object held by Rust code, the embedding counterpart of a JNI local reference. The object is
+/// a garbage collection root until the handle is released with [`VM::release_handles`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Handle(pub(crate) usize);
+
+/// An object held by Rust code until it is deleted with [`VM::delete_global_handle`], the
+/// embedding counterpart of a JNI global reference.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct GlobalHandle(pub(crate) usize);
+
+/// Where a garbage collection root was found.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum RootKind {
+    /// A local variable or operand of a frame.
+    Stack,
+    /// A static field of a class.
+    Static(ClassId),
+    /// The mirror or a resolved constant of a class.
+    Class(ClassId),
+    InternedString,
+    Handle,
+    GlobalHandle,
+    /// A reference the collector cleared or found ready to finalize, not handed to Java code yet.
+    PendingReference
+}
+
 pub struct VM {
     interop_stack: Vec<VMValue>,
     interop_stack_ptr: usize,
-    interop_stack_size: usize
+    interop_stack_size: usize,
+    pub classes: ClassRegistry,
+    pub heap: Heap,
+    /// The string table: the canonical `java.lang.String` object of every interned value, by its
+    /// UTF-16 code units.
+    strings: HashMap<Vec<u16>, ObjRef>,
+    handles: Vec<ObjRef>,
+    /// Global handles by index, null where deleted.
+    global_handles: Vec<ObjRef>,
+    pub(crate) pending_references: Vec<ObjRef>,
+    pub(crate) collector: Box<dyn GarbageCollector>,
+    pub(crate) gc_stats: GcStats,
+    pub(crate) options: VMOptions,
+    pub(crate) started: Instant,
+    /// Allocations by bytecode so far, while sampling them.
+    pub(crate) allocations: u64,
+    pub(crate) allocation_sites: HashMap<AllocationSite, AllocationStats>,
+    /// The unnamed module of the bootstrap loader, which a JDK's `java.base` creates in
+    /// `BootLoader.<clinit>`. With the module system not started, every class is in it.
+    pub(crate) unnamed_module: Option<GlobalHandle>
 }
 
 impl VM {
+    /// A VM whose only classes are the bundled runtime.
     pub fn new(initial_iterop_capacity: usize) -> Self {
+        VM::with_classes(initial_iterop_capacity, ClassRegistry::new(ClassPath::runtime(), ClassPath::new(), ClassPath::new()))
+    }
+
+    pub fn with_classes(initial_iterop_capacity: usize, classes: ClassRegistry) -> Self {
+        VM::with_options(initial_iterop_capacity, classes, VMOptions::default())
+    }
+
+    pub fn with_options(initial_iterop_capacity: usize, classes: ClassRegistry, options: VMOptions) -> Self {
+        let mut heap = Heap::new(1 << 20, options.max_heap);
+        let collector: Box<dyn GarbageCollector> = match options.collector {
+            CollectorKind::MarkSweep => Box::new(MarkSweep::default()),
+            CollectorKind::Generational => {
+                let young_size = options.young_size.unwrap_or(options.max_heap / 4).min(options.max_heap / 2);
+                Box::new(Generational::new(&mut heap, young_size))
+            }
+        };
         VM {
             interop_stack_ptr: 0,
             interop_stack_size: 0,
-            interop_stack: Vec::with_capacity(initial_iterop_capacity)
+            interop_stack: Vec::with_capacity(initial_iterop_capacity),
+            classes,
+            heap,
+            strings: HashMap::new(),
+            handles: Vec::new(),
+            global_handles: Vec::new(),
+            pending_references: Vec::new(),
+            collector,
+            gc_stats: GcStats::default(),
+            options,
+            started: Instant::now(),
+            allocations: 0,
+            allocation_sites: HashMap::new(),
+            unnamed_module: None
+        }
+    }
+
+    /// Keeps `obj` alive until the handle is released.
+    pub fn new_handle(&mut self, obj: ObjRef) -> Handle {
+        self.handles.push(obj);
+        Handle(self.handles.len() - 1)
+    }
+
+    pub fn handle(&self, handle: Handle) -> ObjRef {
+        self.handles[handle.0]
+    }
+
+    /// The handle the next [`VM::new_handle`] returns; releasing it releases everything created since.
+    pub fn handle_scope(&self) -> Handle {
+        Handle(self.handles.len())
+    }
+
+    /// Releases `handle` and every handle created after it.
+    pub fn release_handles(&mut self, handle: Handle) {
+        self.handles.truncate(handle.0);
+    }
+
+    /// Stops a handle from keeping its object alive before its scope is released.
+    pub fn clear_handle(&mut self, handle: Handle) {
+        self.handles[handle.0] = ObjRef(0);
+    }
+
+    /// Keeps `obj` alive until the handle is deleted, reusing the slot of a deleted handle.
+    pub fn new_global_handle(&mut self, obj: ObjRef) -> GlobalHandle {
+        match self.global_handles.iter().position(|slot| slot.0 == 0) {
+            Some(index) => {
+                self.global_handles[index] = obj;
+                GlobalHandle(index)
+            }
+            None => {
+                self.global_handles.push(obj);
+                GlobalHandle(self.global_handles.len() - 1)
+            }
+        }
+    }
+
+    /// The object of a global handle, `None` once it was deleted.
+    pub fn global_handle(&self, handle: GlobalHandle) -> Option<ObjRef> {
+        self.global_handles.get(handle.0).copied().filter(|obj| obj.0 != 0)
+    }
+
+    pub fn delete_global_handle(&mut self, handle: GlobalHandle) {
+        if let Some(slot) = self.global_handles.get_mut(handle.0) {
+            *slot = ObjRef(0);
+        }
+    }
+
+    /// Every garbage collection root: the values on the interop stack, which hold the locals and
+    /// operands of all frames, the statics, mirrors and resolved constants of classes, the
+    /// interned strings and the local and global handles.
+    pub fn roots(&self) -> Vec<(RootKind, ObjRef)> {
+        let mut roots: Vec<(RootKind, ObjRef)> = Vec::new();
+        for value in &self.interop_stack[..self.interop_stack_ptr] {
+            if let VMValue::Reference(obj) = value {
+                roots.push((RootKind::Stack, *obj));
+            }
+        }
+        for class in self.classes.classes() {
+            for value in &class.statics {
+                if let VMValue::Reference(obj) = value {
+                    roots.push((RootKind::Static(class.id), *obj));
+                }
+            }
+            for value in class.resolved_constants.values() {
+                if let VMValue::Reference(obj) = value {
+                    roots.push((RootKind::Class(class.id), *obj));
+                }
+            }
+            if let Some(mirror) = class.mirror {
+                roots.push((RootKind::Class(class.id), mirror));
+            }
+        }
+        roots.extend(self.strings.values().map(|string| (RootKind::InternedString, *string)));
+        roots.extend(self.handles.iter().filter(|obj| obj.0 != 0).map(|obj| (RootKind::Handle, *obj)));
+        roots.extend(self.global_handles.iter().filter(|obj| obj.0 != 0).map(|obj| (RootKind::GlobalHandle, *obj)));
+        roots.extend(self.pending_references.iter().map(|obj| (RootKind::PendingReference, *obj)));
+        roots
+    }
+
+    /// Calls `visit` with every root [`VM::roots`] lists, in an order that stays the same while
+    /// the VM is not changed, so that a moving collector can update them.
+    pub(crate) fn visit_roots_mut(&mut self, mut visit: impl FnMut(RootKind, &mut ObjRef)) {
+        for value in &mut self.interop_stack[..self.interop_stack_ptr] {
+            if let VMValue::Reference(obj) = value {
+                visit(RootKind::Stack, obj);
+            }
+        }
+        for class in self.classes.classes_mut() {
+            for value in &mut class.statics {
+                if let VMValue::Reference(obj) = value {
+                    visit(RootKind::Static(class.id), obj);
+                }
+            }
+            for value in class.resolved_constants.values_mut() {
+                if let VMValue::Reference(obj) = value {
+                    visit(RootKind::Class(class.id), obj);
+                }
+            }
+            if let Some(mirror) = &mut class.mirror {
+                visit(RootKind::Class(class.id), mirror);
+            }
+        }
+        for string in self.strings.values_mut() {
+            visit(RootKind::InternedString, string);
+        }
+        for obj in self.handles.iter_mut().filter(|obj| obj.0 != 0) {
+            visit(RootKind::Handle, obj);
+        }
+        for obj in self.global_handles.iter_mut().filter(|obj| obj.0 != 0) {
+            visit(RootKind::GlobalHandle, obj);
+        }
+        for obj in &mut self.pending_references {
+            visit(RootKind::PendingReference, obj);
+        }
+    }
+
+    /// Takes one of the references the collector cleared or found ready to finalize; the VM passes
+    /// them to `java.lang.ref.Reference` at the next safepoint.
+    pub fn take_pending_reference(&mut self) -> Option<ObjRef> {
+        self.pending_references.pop()
+    }
+
+    pub fn has_pending_references(&self) -> bool {
+        !self.pending_references.is_empty()
+    }
+
+    /// Number of values on the interop stack. Frames live on the interop stack as a window of
+    /// local variables followed by the operand stack.
+    pub fn stack_pointer(&self) -> usize {
+        self.interop_stack_ptr
+    }
+
+    /// Drops every value above `ptr`.
+    pub(crate) fn truncate(&mut self, ptr: usize) {
+        if ptr > self.interop_stack_ptr {
+            panic!("interop stack ptr violation: truncate to {} at {}", ptr, self.interop_stack_ptr)
+        }
+        self.interop_stack_ptr = ptr;
+    }
+
+    pub fn get(&self, index: usize) -> VMValue {
+        if index >= self.interop_stack_ptr {
+            panic!("interop stack ptr violation: get {} at {}", index, self.interop_stack_ptr)
         }
+        self.interop_stack[index]
+    }
+
+    pub(crate) fn set(&mut self, index: usize, val: VMValue) {
+        if index >= self.interop_stack_ptr {
+            panic!("interop stack ptr violation: set {} at {}", index, self.interop_stack_ptr)
+        }
+        self.interop_stack[index] = val;
+    }
+
+    /// The value `depth` entries below the top of the stack, 0 being the top.
+    pub fn peek(&self, depth: usize) -> VMValue {
+        self.get(self.interop_stack_ptr - 1 - depth)
     }
 
     pub(crate) fn push(&mut self, val: VMValue) {
@@ -59,12 +851,275 @@ impl VM {
             panic!("interop stack ptr violation: size:{} ptr:{}", self.interop_stack_size, self.interop_stack_ptr)
         }
     }
+    /// Allocates from the heap, collecting garbage when it is full: first whatever the collector
+    /// chooses to, then the whole heap and, as a last resort, the whole heap without sparing the
+    /// referents of soft references.
+    fn allocate(&mut self, class: ClassId, body_size: u32, array_length: u32) -> Result<ObjRef, InterpreterError> {
+        let size = Heap::object_size(body_size);
+        let mut address = self.collector.allocate(&mut self.heap, size);
+        if address.is_none() && self.collect(CollectionRequest::default(), "Allocation Failure").kind == CollectionKind::Minor {
+            address = self.collector.allocate(&mut self.heap, size);
+            if address.is_none() {
+                self.collect(CollectionRequest { full: true, clear_soft_references: false }, "Allocation Failure");
+            }
+        }
+        if address.is_none() {
+            address = self.collector.allocate(&mut self.heap, size);
+        }
+        if address.is_none() {
+            self.collect(CollectionRequest { full: true, clear_soft_references: true }, "Last ditch collection");
+            address = self.collector.allocate(&mut self.heap, size);
+        }
+        let address = address.ok_or_else(|| InterpreterError::exception("java/lang/OutOfMemoryError", "Java heap space"))?;
+        Ok(self.heap.init_object(address, class, size, array_length))
+    }
+
+    /// Allocates an instance of a class with all fields zeroed; the class must be linked.
+    pub fn new_object(&mut self, class: ClassId) -> Result<ObjRef, InterpreterError> {
+        let size = self.classes.class(class).layout.size;
+        self.allocate(class, size, 0)
+    }
+
+    /// Allocates an array of an array class with all elements zeroed.
+    pub fn new_array(&mut self, class: ClassId, length: i32) -> Result<ObjRef, InterpreterError> {
+        if length < 0 {
+            return Err(InterpreterError::exception("java/lang/NegativeArraySizeException", length.to_string()))
+        }
+        let element_type = self.classes.class(class).component_type.as_ref()
+            .ok_or_else(|| InterpreterError::TypeMismatch("array class".to_string(), self.classes.class(class).name.clone()))?;
+        let size = (length as u64) * Heap::field_size(element_type) as u64;
+        if size + Heap::HEADER_SIZE as u64 + self.heap.size() as u64 >= u32::MAX as u64 {
+            return Err(InterpreterError::exception("java/lang/OutOfMemoryError", "Requested array size exceeds VM limit"))
+        }
+        self.allocate(class, size as u32, length as u32)
+    }
+
+    /// `Object.clone`: a new object of the same class, or array of the same length, holding the
+    /// same field values. The copy gets its own identity hash and monitor.
+    pub fn clone_object(&mut self, obj: ObjRef) -> Result<ObjRef, InterpreterError> {
+        let class = self.heap.class_of(obj);
+        let (size, length) = (self.heap.size_of(obj), self.heap.array_length(obj));
+        let scope = self.new_handle(obj);
+        let copy = self.allocate(class, size - Heap::HEADER_SIZE, length);
+        let obj = self.handle(scope);
+        self.release_handles(scope);
+        let copy = copy?;
+        self.heap.copy_fields(obj, copy);
+        for slot in reference_slots(&self.heap, &self.classes, copy) {
+            if let Some(target) = self.heap.reference_at(slot) {
+                self.collector.write_barrier(&mut self.heap, copy, target);
+            }
+        }
+        Ok(copy)
+    }
+
+    /// `System.arraycopy`, with HotSpot's exception messages. The elements are read before any
+    /// is written, so the ranges may overlap; reference elements are checked against the
+    /// destination's component type as they are stored.
+    pub fn copy_array(&mut self, src: ObjRef, src_pos: i32, dest: ObjRef, dest_pos: i32, length: i32) -> Result<(), InterpreterError> {
+        let array_store = |message: String| InterpreterError::exception("java/lang/ArrayStoreException", message);
+        let out_of_bounds = |message: String| InterpreterError::exception("java/lang/ArrayIndexOutOfBoundsException", message);
+        let (src_class, dest_class) = (self.heap.class_of(src), self.heap.class_of(dest));
+        let src_type = self.classes.class(src_class).component_type.clone()
+            .ok_or_else(|| array_store(format!("arraycopy: source type {} is not an array", self.classes.class(src_class).java_name())))?;
+        let dest_type = self.classes.class(dest_class).component_type.clone()
+            .ok_or_else(|| array_store(format!("arraycopy: destination type {} is not an array", self.classes.class(dest_class).java_name())))?;
+        let describe = |element_type: &FieldType| match element_type.is_reference() {
+            true => "object array".to_string(),
+            false => element_type.java_name()
+        };
+        if src_type.is_reference() != dest_type.is_reference() || (!src_type.is_reference() && src_type != dest_type) {
+            return Err(array_store(format!("arraycopy: type mismatch: can not copy {}[] into {}[]", describe(&src_type), describe(&dest_type))))
+        }
+        let (src_length, dest_length) = (self.heap.array_length(src) as i64, self.heap.array_length(dest) as i64);
+        if src_pos < 0 {
+            return Err(out_of_bounds(format!("arraycopy: source index {} out of bounds for {}[{}]", src_pos, describe(&src_type), src_length)))
+        }
+        if dest_pos < 0 {
+            return Err(out_of_bounds(format!("arraycopy: destination index {} out of bounds for {}[{}]", dest_pos, describe(&dest_type), dest_length)))
+        }
+        if length < 0 {
+            return Err(out_of_bounds(format!("arraycopy: length {} is negative", length)))
+        }
+        if src_pos as i64 + length as i64 > src_length {
+            return Err(out_of_bounds(format!("arraycopy: last source index {} out of bounds for {}[{}]", src_pos as i64 + length as i64, describe(&src_type), src_length)))
+        }
+        if dest_pos as i64 + length as i64 > dest_length {
+            return Err(out_of_bounds(format!("arraycopy: last destination index {} out of bounds for {}[{}]", dest_pos as i64 + length as i64, describe(&dest_type), dest_length)))
+        }
+        let values: Vec<VMValue> = (0..length).map(|index| self.heap.read_element(src, (src_pos + index) as u32, &src_type)).collect();
+        let component = self.classes.class(dest_class).component_class;
+        for (index, value) in values.into_iter().enumerate() {
+            if let (VMValue::Reference(obj), Some(component)) = (value, component) {
+                if !self.classes.is_assignable(self.heap.class_of(obj), component) {
+                    return Err(array_store(format!("arraycopy: element type mismatch: can not cast one of the elements of {}[] to the type of the destination array, {}",
+                        src_type.java_name(), self.classes.class(component).java_name())))
+                }
+            }
+            self.store_element(dest, dest_pos as u32 + index as u32, &dest_type, value);
+        }
+        Ok(())
+    }
+
+    /// Loads and links a bootstrap class the VM creates instances of itself.
+    pub fn bootstrap_class(&mut self, name: &str) -> Result<ClassId, InterpreterError> {
+        let id = self.classes.load_class(LoaderId::BOOTSTRAP, name)?;
+        self.classes.link(id)?;
+        Ok(id)
+    }
+
+    /// Reads an instance field by name.
+    pub fn get_field(&self, obj: ObjRef, name: &str) -> Result<VMValue, InterpreterError> {
+        let (offset, field_type) = self.classes.instance_field(self.heap.class_of(obj), name)
+            .ok_or_else(|| InterpreterError::exception("java/lang/NoSuchFieldError", name))?;
+        Ok(self.heap.read_field(obj, offset, &field_type))
+    }
+
+    /// Stores an instance field by name.
+    pub fn set_field(&mut self, obj: ObjRef, name: &str, value: VMValue) -> Result<(), InterpreterError> {
+        let (offset, field_type) = self.classes.instance_field(self.heap.class_of(obj), name)
+            .ok_or_else(|| InterpreterError::exception("java/lang/NoSuchFieldError", name))?;
+        if !self.store_field(obj, offset, &field_type, value) {
+            return Err(InterpreterError::TypeMismatch(field_type.java_name(), value.to_string()))
+        }
+        Ok(())
+    }
+
+    /// Stores a field like [`Heap::write_field`], through the collector's write barrier.
+    pub fn store_field(&mut self, obj: ObjRef, offset: u32, field_type: &FieldType, value: VMValue) -> bool {
+        if !self.heap.write_field(obj, offset, field_type, value) {
+            return false
+        }
+        if let VMValue::Reference(target) = value {
+            self.collector.write_barrier(&mut self.heap, obj, target);
+        }
+        true
+    }
+
+    /// Stores an array element like [`Heap::write_element`], through the collector's write barrier.
+    pub fn store_element(&mut self, array: ObjRef, index: u32, element_type: &FieldType, value: VMValue) -> bool {
+        if !self.heap.write_element(array, index, element_type, value) {
+            return false
+        }
+        if let VMValue::Reference(target) = value {
+            self.collector.write_barrier(&mut self.heap, array, target);
+        }
+        true
+    }
+
+    /// Stores a static field, through the collector's write barrier.
+    pub fn set_static(&mut self, class: ClassId, index: usize, value: VMValue) {
+        self.classes.class_mut(class).statics[index] = value;
+        if let VMValue::Reference(target) = value {
+            self.collector.static_write_barrier(class, target);
+        }
+    }
+
+    /// Creates a new, not interned `java.lang.String`.
+    pub fn new_string(&mut self, value: &str) -> Result<ObjRef, InterpreterError> {
+        self.new_string_utf16(&value.encode_utf16().collect::<Vec<u16>>())
+    }
+
+    /// A `java.lang.String` of UTF-16 code units, stored one byte per character when they are all
+    /// Latin-1 (see [`strings::compress`]).
+    pub fn new_string_utf16(&mut self, units: &[u16]) -> Result<ObjRef, InterpreterError> {
+        let string_class = self.bootstrap_class("java/lang/String")?;
+        let bytes_class = self.classes.load_class(LoaderId::BOOTSTRAP, "[B")?;
+        let (bytes, coder) = strings::compress(units);
+        let value = self.new_array(bytes_class, bytes.len() as i32)?;
+        for (index, byte) in bytes.iter().enumerate() {
+            self.heap.write_element(value, index as u32, &FieldType::Byte, VMValue::Int(*byte as i8 as i32));
+        }
+        let scope = self.new_handle(value);
+        let string = self.new_object(string_class);
+        let value = self.handle(scope);
+        self.release_handles(scope);
+        let string = string?;
+        self.set_field(string, "value", VMValue::Reference(value))?;
+        self.set_field(string, "coder", VMValue::Int(coder as i32))?;
+        Ok(string)
+    }
+
+    /// The canonical string object for a value, as used for literals.
+    pub fn intern_string(&mut self, value: &str) -> Result<ObjRef, InterpreterError> {
+        self.intern_utf16(&value.encode_utf16().collect::<Vec<u16>>())
+    }
+
+    /// The canonical string object of UTF-16 code units, created if there is none yet.
+    pub fn intern_utf16(&mut self, units: &[u16]) -> Result<ObjRef, InterpreterError> {
+        if let Some(string) = self.strings.get(units) {
+            return Ok(*string)
+        }
+        let string = self.new_string_utf16(units)?;
+        self.strings.insert(units.to_vec(), string);
+        Ok(string)
+    }
+
+    /// `String.intern()`: the canonical string equal to `string`, which becomes canonical itself
+    /// if there is none yet.
+    pub fn intern(&mut self, string: ObjRef) -> Result<ObjRef, InterpreterError> {
+        let units = self.string_utf16(string)?;
+        Ok(*self.strings.entry(units).or_insert(string))
+    }
+
+    /// The UTF-16 code units of a `java.lang.String`.
+    pub fn string_utf16(&self, string: ObjRef) -> Result<Vec<u16>, InterpreterError> {
+        let value = match self.get_field(string, "value")?.as_reference()? {
+            Some(value) => value,
+            None => return Ok(Vec::new())
+        };
+        let coder = self.get_field(string, "coder")?.as_int()? as u8;
+        let bytes: Vec<u8> = (0..self.heap.array_length(value))
+            .map(|index| self.heap.read_element(value, index, &FieldType::Byte).as_int().unwrap_or(0) as u8)
+            .collect();
+        Ok(strings::inflate(&bytes, coder))
+    }
+
+    /// The contents of a `java.lang.String`; unpaired surrogates become U+FFFD.
+    pub fn string_value(&self, string: ObjRef) -> Result<String, InterpreterError> {
+        Ok(String::from_utf16_lossy(&self.string_utf16(string)?))
+    }
+
+    /// The `java.lang.Class` object of a class, created the first time it is asked for.
+    pub fn class_mirror(&mut self, id: ClassId) -> Result<ObjRef, InterpreterError> {
+        if let Some(mirror) = self.classes.class(id).mirror {
+            return Ok(mirror)
+        }
+        let class_class = self.bootstrap_class("java/lang/Class")?;
+        let mirror = self.new_object(class_class)?;
+        self.classes.class_mut(id).mirror = Some(mirror);
+        self.set_field(mirror, "classId", VMValue::Int(id.0 as i32))?;
+        let name = self.classes.class(id).java_name();
+        let name = self.intern_string(&name)?;
+        let mirror = self.classes.class(id).mirror.unwrap();
+        self.set_field(mirror, "name", VMValue::Reference(name))?;
+        if let Some(module) = self.unnamed_module.and_then(|module| self.global_handle(module)) {
+            self.set_field(mirror, "module", VMValue::Reference(module))?;
+        }
+        // The mirrors of a JDK's java.base keep the component type of arrays themselves.
+        if self.classes.instance_field(class_class, "componentType").is_some() && self.classes.class(id).is_array() {
+            let component = match self.classes.class(id).component_class {
+                Some(component) => component,
+                None => self.classes.primitive_class(self.classes.class(id).component_type.clone().as_ref())
+            };
+            let component = self.class_mirror(component)?;
+            let mirror = self.classes.class(id).mirror.unwrap();
+            self.set_field(mirror, "componentType", VMValue::Reference(component))?;
+        }
+        Ok(self.classes.class(id).mirror.unwrap())
+    }
+
+    /// The class a `java.lang.Class` object stands for.
+    pub fn mirrored_class(&self, mirror: ObjRef) -> Result<ClassId, InterpreterError> {
+        Ok(ClassId(self.get_field(mirror, "classId")?.as_int()? as u32))
+    }
+
     pub fn pop(&mut self) -> VMValue {
         if self.interop_stack_ptr == 0 {
             panic!("interop stack ptr violation: pop at {} size:{}", self.interop_stack_ptr, self.interop_stack_size)
         }
         self.interop_stack_ptr -= 1;
-        self.interop_stack[self.interop_stack_ptr].clone()
+        self.interop_stack[self.interop_stack_ptr]
     }
 }
 